@@ -2,10 +2,27 @@
 // SPDX-FileCopyrightText: 2025 Yuta Takahashi
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
+use std::path::PathBuf;
+
+/// Services of this server that are not part of the Sapphillon API, under `proto/`.
+const PROTOS: &[&str] = &[
+    "proto/sapphillon/server/v1/event_service.proto",
+    "proto/sapphillon/server/v1/permission_management_service.proto",
+    "proto/sapphillon/server/v1/plugin_management_service.proto",
+    "proto/sapphillon/server/v1/prompt_service.proto",
+    "proto/sapphillon/server/v1/schedule_service.proto",
+    "proto/sapphillon/server/v1/workflow_management_service.proto",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // TODO Re-enable Windows support
     #[cfg(target_os = "windows")]
     compile_error!("Currently, Windows support is suspended.");
 
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
+    tonic_prost_build::configure()
+        .file_descriptor_set_path(out_dir.join("sapphillon_server_descriptor.bin"))
+        .compile_protos(PROTOS, &["proto"])?;
+
     Ok(())
 }
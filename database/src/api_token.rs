@@ -0,0 +1,112 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for the API tokens accepted by `--auth token`.
+//!
+//! Tokens are generated and hashed by the server; this layer only stores the SHA-256
+//! hash and the token's scopes, as a space-separated list.
+
+use chrono::Utc;
+use entity::entity::api_token::{self, Entity as ApiToken, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, QueryOrder};
+
+/// Stores a new API token.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `name` - Unique name of the token, e.g. `ci`
+/// * `token_hash` - Hex-encoded SHA-256 hash of the token
+/// * `scopes` - Scopes granted to the token
+///
+/// # Returns
+///
+/// Returns the created `Model`, or a database error when the name is taken.
+pub async fn create_api_token(
+    db: &DatabaseConnection,
+    name: &str,
+    token_hash: &str,
+    scopes: &[String],
+) -> Result<Model, DbErr> {
+    let active_model = api_token::ActiveModel {
+        id: Set(name.to_string()),
+        token_hash: Set(token_hash.to_string()),
+        scopes: Set(scopes.join(" ")),
+        created_at: Set(Some(Utc::now())),
+    };
+    active_model.insert(db).await
+}
+
+/// Lists every API token, ordered by name.
+pub async fn list_api_tokens(db: &DatabaseConnection) -> Result<Vec<Model>, DbErr> {
+    ApiToken::find()
+        .order_by_asc(api_token::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Deletes an API token.
+///
+/// # Returns
+///
+/// Returns the number of deleted records (0 or 1).
+pub async fn delete_api_token(db: &DatabaseConnection, name: &str) -> Result<u64, DbErr> {
+    let result = ApiToken::delete_by_id(name.to_string()).exec(db).await?;
+    Ok(result.rows_affected)
+}
+
+/// Splits the stored scopes of a token.
+pub fn token_scopes(token: &Model) -> Vec<&str> {
+    token.scopes.split_whitespace().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE api_token (
+                id TEXT NOT NULL PRIMARY KEY,
+                token_hash TEXT NOT NULL UNIQUE,
+                scopes TEXT NOT NULL,
+                created_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_create_list_and_delete_tokens() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let scopes = ["read", "run"].map(str::to_string);
+        let created = create_api_token(&db, "ci", "hash-1", &scopes).await?;
+        assert_eq!(token_scopes(&created), vec!["read", "run"]);
+        create_api_token(&db, "backup", "hash-2", &scopes[..1]).await?;
+        assert!(
+            create_api_token(&db, "ci", "hash-3", &scopes)
+                .await
+                .is_err()
+        );
+
+        let names: Vec<String> = list_api_tokens(&db)
+            .await?
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(names, vec!["backup", "ci"]);
+
+        assert_eq!(delete_api_token(&db, "ci").await?, 1);
+        assert_eq!(delete_api_token(&db, "ci").await?, 0);
+        assert_eq!(list_api_tokens(&db).await?.len(), 1);
+        Ok(())
+    }
+}
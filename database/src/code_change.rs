@@ -0,0 +1,127 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for the changes between workflow code revisions.
+//!
+//! A change is recorded when a revision is made from an earlier one, e.g. by fixing or
+//! rolling back a workflow. It keeps the diff against that revision as JSON and a
+//! summary for people; the first revision of a workflow has no change.
+
+use chrono::Utc;
+use entity::entity::workflow_code_change::{
+    self, ActiveModel, Entity as WorkflowCodeChange, Model,
+};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+
+/// Records how a workflow code revision differs from the one it was made from.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_code_id` - The new revision
+/// * `workflow_id` - Workflow both revisions belong to
+/// * `previous_code_id` - The revision the new one was made from
+/// * `diff_json` - JSON description of the line changes
+/// * `summary` - Description of the change for people
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_code_change(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+    workflow_id: &str,
+    previous_code_id: Option<&str>,
+    diff_json: &str,
+    summary: &str,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        workflow_code_id: Set(workflow_code_id.to_string()),
+        workflow_id: Set(workflow_id.to_string()),
+        previous_code_id: Set(previous_code_id.map(str::to_string)),
+        diff_json: Set(diff_json.to_string()),
+        summary: Set(summary.to_string()),
+        created_at: Set(Some(Utc::now())),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Lists the recorded changes of a workflow's code revisions.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow whose changes are returned
+///
+/// # Returns
+///
+/// Returns the changes ordered by creation time.
+pub async fn list_code_changes(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+) -> Result<Vec<Model>, DbErr> {
+    WorkflowCodeChange::find()
+        .filter(workflow_code_change::Column::WorkflowId.eq(workflow_id))
+        .order_by_asc(workflow_code_change::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_code_change (
+                workflow_code_id TEXT NOT NULL PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                previous_code_id TEXT,
+                diff_json TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                created_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_listed_per_workflow() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        create_code_change(&db, "wc-2", "wf-1", Some("wc-1"), "[]", "Fix the URL").await?;
+        create_code_change(&db, "wc-3", "wf-1", Some("wc-2"), "[]", "Add logging").await?;
+        create_code_change(&db, "wc-9", "wf-2", Some("wc-8"), "[]", "Other").await?;
+
+        let changes = list_code_changes(&db, "wf-1").await?;
+        let summary: Vec<(&str, Option<&str>, &str)> = changes
+            .iter()
+            .map(|c| {
+                (
+                    c.workflow_code_id.as_str(),
+                    c.previous_code_id.as_deref(),
+                    c.summary.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("wc-2", Some("wc-1"), "Fix the URL"),
+                ("wc-3", Some("wc-2"), "Add logging"),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for denied permissions of a workflow code.
+//!
+//! A denied permission names a plugin function (or `*` for every function), a
+//! permission type and optional resources. It takes precedence over the allowed
+//! permissions of the same code: a call matching a denial fails even when an
+//! allowed permission also covers it. `resource_json` holds a JSON array of
+//! resource patterns; `NULL` denies every resource.
+
+use chrono::Utc;
+use entity::entity::workflow_code_denied_permission::{
+    self, ActiveModel, Entity as WorkflowCodeDeniedPermission, Model,
+};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+/// Denies a permission to a workflow code.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_code_id` - Workflow code the denial applies to
+/// * `plugin_function_id` - Function the denial applies to, or `*` for every function
+/// * `permission_type` - `PermissionType` value of the denied permission
+/// * `resource_json` - JSON array of denied resource patterns; `None` denies all resources
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_denied_permission(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+    plugin_function_id: &str,
+    permission_type: i32,
+    resource_json: Option<&str>,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        workflow_code_id: Set(workflow_code_id.to_string()),
+        plugin_function_id: Set(plugin_function_id.to_string()),
+        permission_type: Set(permission_type),
+        resource_json: Set(resource_json.map(str::to_string)),
+        created_at: Set(Some(Utc::now())),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Retrieves a denied permission by its ID.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `denied_permission_id` - The unique identifier of the denial
+///
+/// # Returns
+///
+/// Returns `Some(Model)` if found, `None` otherwise.
+pub async fn get_denied_permission(
+    db: &DatabaseConnection,
+    denied_permission_id: &str,
+) -> Result<Option<Model>, DbErr> {
+    WorkflowCodeDeniedPermission::find_by_id(denied_permission_id.to_string())
+        .one(db)
+        .await
+}
+
+/// Lists the denied permissions of a workflow code ordered by creation time.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_code_id` - Workflow code whose denials are returned
+///
+/// # Returns
+///
+/// Returns a vector of denied permission models.
+pub async fn list_denied_permissions(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+) -> Result<Vec<Model>, DbErr> {
+    WorkflowCodeDeniedPermission::find()
+        .filter(workflow_code_denied_permission::Column::WorkflowCodeId.eq(workflow_code_id))
+        .order_by_asc(workflow_code_denied_permission::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+/// Deletes a denied permission.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `denied_permission_id` - The unique identifier of the denial to delete
+///
+/// # Returns
+///
+/// Returns the number of deleted records (0 or 1).
+pub async fn delete_denied_permission(
+    db: &DatabaseConnection,
+    denied_permission_id: &str,
+) -> Result<u64, DbErr> {
+    let result = WorkflowCodeDeniedPermission::delete_by_id(denied_permission_id.to_string())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_code_denied_permission (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_code_id TEXT NOT NULL,
+                plugin_function_id TEXT NOT NULL,
+                permission_type INTEGER NOT NULL,
+                resource_json TEXT,
+                created_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_create_list_and_delete_denied_permissions() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let denied =
+            create_denied_permission(&db, "wc-1", "*", 3, Some(r#"["internal.corp"]"#)).await?;
+        create_denied_permission(&db, "wc-2", "app.exec", 5, None).await?;
+
+        let listed = list_denied_permissions(&db, "wc-1").await?;
+        assert_eq!(listed, vec![denied.clone()]);
+        assert_eq!(
+            listed[0].resource_json.as_deref(),
+            Some(r#"["internal.corp"]"#)
+        );
+
+        assert_eq!(
+            get_denied_permission(&db, &denied.id).await?,
+            Some(denied.clone())
+        );
+        assert_eq!(delete_denied_permission(&db, &denied.id).await?, 1);
+        assert_eq!(get_denied_permission(&db, &denied.id).await?, None);
+        assert_eq!(delete_denied_permission(&db, &denied.id).await?, 0);
+        assert!(list_denied_permissions(&db, "wc-1").await?.is_empty());
+        Ok(())
+    }
+}
@@ -31,6 +31,8 @@ pub async fn create_ext_plugin_package(
         plugin_package_id: Set(plugin_package_id),
         install_dir: Set(install_dir),
         missing: Set(false),
+        enabled: Set(true),
+        available_version: Set(None),
     };
 
     active_model.insert(db).await
@@ -100,6 +102,86 @@ pub async fn mark_ext_plugin_missing(
     }
 }
 
+/// Enables or disables an external plugin package.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `plugin_package_id` - The unique identifier of the plugin
+/// * `enabled` - Whether workflows may use the plugin
+///
+/// # Returns
+///
+/// Returns the updated model or an error if the plugin was not found.
+pub async fn set_ext_plugin_enabled(
+    db: &DatabaseConnection,
+    plugin_package_id: &str,
+    enabled: bool,
+) -> Result<Model, DbErr> {
+    let existing = ExtPluginPackage::find_by_id(plugin_package_id.to_string())
+        .one(db)
+        .await?;
+
+    match existing {
+        Some(model) => {
+            let mut active_model: ActiveModel = model.into();
+            active_model.enabled = Set(enabled);
+            active_model.update(db).await
+        }
+        None => Err(DbErr::RecordNotFound(format!(
+            "External plugin package not found: {plugin_package_id}"
+        ))),
+    }
+}
+
+/// Records the newer version of an external plugin package found in the plugin store.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `plugin_package_id` - The unique identifier of the plugin
+/// * `available_version` - Newer version, or `None` when the plugin is up to date
+///
+/// # Returns
+///
+/// Returns the updated model or an error if the plugin was not found.
+pub async fn set_ext_plugin_available_version(
+    db: &DatabaseConnection,
+    plugin_package_id: &str,
+    available_version: Option<String>,
+) -> Result<Model, DbErr> {
+    let existing = ExtPluginPackage::find_by_id(plugin_package_id.to_string())
+        .one(db)
+        .await?;
+
+    match existing {
+        Some(model) => {
+            let mut active_model: ActiveModel = model.into();
+            active_model.available_version = Set(available_version);
+            active_model.update(db).await
+        }
+        None => Err(DbErr::RecordNotFound(format!(
+            "External plugin package not found: {plugin_package_id}"
+        ))),
+    }
+}
+
+/// Lists the external plugin packages that have a newer version in the plugin store.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+///
+/// # Returns
+///
+/// Returns a vector of external plugin packages where `available_version` is set.
+pub async fn list_ext_plugin_updates(db: &DatabaseConnection) -> Result<Vec<Model>, DbErr> {
+    ExtPluginPackage::find()
+        .filter(ext_plugin_package::Column::AvailableVersion.is_not_null())
+        .all(db)
+        .await
+}
+
 /// Deletes an external plugin package record from the database.
 ///
 /// # Arguments
@@ -152,7 +234,9 @@ mod tests {
             CREATE TABLE ext_plugin_package (
                 plugin_package_id TEXT NOT NULL PRIMARY KEY,
                 install_dir TEXT NOT NULL,
-                missing INTEGER NOT NULL DEFAULT 0
+                missing INTEGER NOT NULL DEFAULT 0,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                available_version TEXT
             )
         "#;
         db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
@@ -238,6 +322,67 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_set_ext_plugin_enabled() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        let created = create_ext_plugin_package(
+            &db,
+            "test/pkg/1.0.0".to_string(),
+            "/tmp/test/pkg/1.0.0".to_string(),
+        )
+        .await?;
+        assert!(created.enabled);
+
+        let updated = set_ext_plugin_enabled(&db, "test/pkg/1.0.0", false).await?;
+        assert!(!updated.enabled);
+        let fetched = get_ext_plugin_package(&db, "test/pkg/1.0.0")
+            .await?
+            .unwrap();
+        assert!(!fetched.enabled);
+
+        let updated = set_ext_plugin_enabled(&db, "test/pkg/1.0.0", true).await?;
+        assert!(updated.enabled);
+
+        let result = set_ext_plugin_enabled(&db, "missing/pkg/1.0.0", false).await;
+        assert!(matches!(result, Err(DbErr::RecordNotFound(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_available_version() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        create_ext_plugin_package(
+            &db,
+            "test/pkg/1.0.0".to_string(),
+            "/tmp/test/pkg/1.0.0".to_string(),
+        )
+        .await?;
+        create_ext_plugin_package(
+            &db,
+            "test/other/1.0.0".to_string(),
+            "/tmp/test/other/1.0.0".to_string(),
+        )
+        .await?;
+        assert!(list_ext_plugin_updates(&db).await?.is_empty());
+
+        let updated =
+            set_ext_plugin_available_version(&db, "test/pkg/1.0.0", Some("1.1.0".to_string()))
+                .await?;
+        assert_eq!(updated.available_version.as_deref(), Some("1.1.0"));
+
+        let updates = list_ext_plugin_updates(&db).await?;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].plugin_package_id, "test/pkg/1.0.0");
+
+        set_ext_plugin_available_version(&db, "test/pkg/1.0.0", None).await?;
+        assert!(list_ext_plugin_updates(&db).await?.is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_delete_ext_plugin_package() -> Result<(), DbErr> {
         let db = setup_db().await?;
@@ -2,12 +2,26 @@
 // SPDX-FileCopyrightText: 2025 Yuta Takahashi
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
+pub mod api_token;
+pub mod code_change;
+pub mod denied_permission;
 pub mod ext_plugin;
 pub mod model;
+pub mod organization;
 pub mod permission;
+pub mod permission_grant;
+pub mod permission_profile;
 pub mod plugin;
+pub mod prompt;
 pub mod provider;
+pub mod recording;
+pub mod schedule;
+pub mod search;
+pub mod state;
+pub mod trigger;
+pub mod user;
 pub mod workflow;
+pub mod writer;
 
 #[cfg(test)]
 use sea_orm::{Database, DatabaseConnection, DbErr};
@@ -69,7 +69,9 @@ mod tests {
                 name TEXT PRIMARY KEY,
                 display_name TEXT NOT NULL,
                 api_key TEXT NOT NULL,
-                api_endpoint TEXT NOT NULL
+                api_endpoint TEXT NOT NULL,
+                kind TEXT,
+                options TEXT
             )
         "#;
         db.execute(Statement::from_string(
@@ -97,6 +99,8 @@ mod tests {
             display_name: "Base".to_string(),
             api_key: "key".to_string(),
             api_endpoint: "https://example.test".to_string(),
+            kind: None,
+            options: None,
         };
         let active: entity::entity::provider::ActiveModel = provider.into();
         active.insert(&db).await?;
@@ -115,7 +115,9 @@ mod tests {
                 name TEXT PRIMARY KEY,
                 display_name TEXT NOT NULL,
                 api_key TEXT NOT NULL,
-                api_endpoint TEXT NOT NULL
+                api_endpoint TEXT NOT NULL,
+                kind TEXT,
+                options TEXT
             )
         "#;
         db.execute(Statement::from_string(
@@ -144,6 +146,8 @@ mod tests {
             display_name: "Base".to_string(),
             api_key: "key".to_string(),
             api_endpoint: "https://example.test".to_string(),
+            kind: None,
+            options: None,
         }
         .into();
         provider_active.insert(&db).await?;
@@ -0,0 +1,194 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for the tags and folders that organize workflows.
+//!
+//! A workflow has any number of tags and is filed in at most one folder, a
+//! slash-separated path such as `work/reports`. Workflows without a folder are at
+//! the top level. Tags and folder paths are checked and normalized by the server
+//! before they are stored here.
+
+use chrono::Utc;
+use entity::entity::workflow_folder::{self, Entity as WorkflowFolder};
+use entity::entity::workflow_tag::{self, Entity as WorkflowTag};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+
+/// Replaces the tags of a workflow.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow to tag
+/// * `tags` - The new tags; an empty list removes every tag
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the tags are replaced, or a database error.
+pub async fn set_workflow_tags(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    tags: &[String],
+) -> Result<(), DbErr> {
+    let (db, workflow_id, tags) = (db.clone(), workflow_id.to_string(), tags.to_vec());
+    crate::writer::serialize_write(async move {
+        let txn = db.begin().await?;
+        WorkflowTag::delete_many()
+            .filter(workflow_tag::Column::WorkflowId.eq(workflow_id.clone()))
+            .exec(&txn)
+            .await?;
+        let now = Utc::now();
+        for tag in tags {
+            let active_model = workflow_tag::ActiveModel {
+                workflow_id: Set(workflow_id.clone()),
+                tag: Set(tag),
+                created_at: Set(Some(now)),
+            };
+            active_model.insert(&txn).await?;
+        }
+        txn.commit().await
+    })
+    .await
+}
+
+/// Lists the tags of a workflow in alphabetical order.
+pub async fn list_workflow_tags(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+) -> Result<Vec<String>, DbErr> {
+    let tags = WorkflowTag::find()
+        .filter(workflow_tag::Column::WorkflowId.eq(workflow_id))
+        .order_by_asc(workflow_tag::Column::Tag)
+        .all(db)
+        .await?;
+    Ok(tags.into_iter().map(|t| t.tag).collect())
+}
+
+/// Files a workflow in a folder, or moves it to the top level.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow to move
+/// * `folder` - Folder path, or `None` for the top level
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the workflow is moved, or a database error.
+pub async fn set_workflow_folder(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    folder: Option<&str>,
+) -> Result<(), DbErr> {
+    let existing = WorkflowFolder::find_by_id(workflow_id.to_string())
+        .one(db)
+        .await?;
+    match (existing, folder) {
+        (Some(existing), Some(folder)) => {
+            let mut active_model: workflow_folder::ActiveModel = existing.into();
+            active_model.folder = Set(folder.to_string());
+            active_model.updated_at = Set(Some(Utc::now()));
+            active_model.update(db).await?;
+        }
+        (None, Some(folder)) => {
+            let active_model = workflow_folder::ActiveModel {
+                workflow_id: Set(workflow_id.to_string()),
+                folder: Set(folder.to_string()),
+                updated_at: Set(Some(Utc::now())),
+            };
+            active_model.insert(db).await?;
+        }
+        (Some(_), None) => {
+            WorkflowFolder::delete_by_id(workflow_id.to_string())
+                .exec(db)
+                .await?;
+        }
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+/// Returns the folder a workflow is filed in, or `None` when it is at the top level.
+pub async fn get_workflow_folder(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+) -> Result<Option<String>, DbErr> {
+    let folder = WorkflowFolder::find_by_id(workflow_id.to_string())
+        .one(db)
+        .await?;
+    Ok(folder.map(|f| f.folder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        for sql in [
+            r#"
+            CREATE TABLE workflow_tag (
+                workflow_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                created_at TEXT,
+                PRIMARY KEY (workflow_id, tag)
+            )
+            "#,
+            r#"
+            CREATE TABLE workflow_folder (
+                workflow_id TEXT NOT NULL PRIMARY KEY,
+                folder TEXT NOT NULL,
+                updated_at TEXT
+            )
+            "#,
+        ] {
+            db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+                .await?;
+        }
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_tags_are_replaced() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let tags = ["reports", "daily"].map(str::to_string);
+        set_workflow_tags(&db, "wf-1", &tags).await?;
+        set_workflow_tags(&db, "wf-2", &tags[..1]).await?;
+        assert_eq!(
+            list_workflow_tags(&db, "wf-1").await?,
+            vec!["daily", "reports"]
+        );
+
+        set_workflow_tags(&db, "wf-1", &["mail".to_string()]).await?;
+        assert_eq!(list_workflow_tags(&db, "wf-1").await?, vec!["mail"]);
+
+        set_workflow_tags(&db, "wf-1", &[]).await?;
+        assert!(list_workflow_tags(&db, "wf-1").await?.is_empty());
+        assert_eq!(list_workflow_tags(&db, "wf-2").await?, vec!["reports"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_workflows_move_between_folders() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        assert_eq!(get_workflow_folder(&db, "wf-1").await?, None);
+
+        set_workflow_folder(&db, "wf-1", Some("work")).await?;
+        set_workflow_folder(&db, "wf-1", Some("work/reports")).await?;
+        assert_eq!(
+            get_workflow_folder(&db, "wf-1").await?.as_deref(),
+            Some("work/reports")
+        );
+
+        set_workflow_folder(&db, "wf-1", None).await?;
+        assert_eq!(get_workflow_folder(&db, "wf-1").await?, None);
+        Ok(())
+    }
+}
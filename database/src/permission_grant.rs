@@ -0,0 +1,275 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for limited permission grants of a workflow code.
+//!
+//! Unlike allowed permissions, a grant stops applying once `expires_at` has passed or
+//! it has been used by `max_uses` runs, so a permission can be given for a single run
+//! without leaving a standing capability on the code. `uses` counts the runs that
+//! claimed a use of the grant before relying on it.
+
+use chrono::{DateTime, Utc};
+use entity::entity::workflow_code_permission_grant::{
+    self, ActiveModel, Entity as WorkflowCodePermissionGrant, Model,
+};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use uuid::Uuid;
+
+/// A limited grant to create; see [`create_permission_grant`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewPermissionGrant<'a> {
+    /// Function the grant applies to, or `*` for every function
+    pub plugin_function_id: &'a str,
+    /// `PermissionType` value of the granted permission
+    pub permission_type: i32,
+    /// `PermissionLevel` value the grant covers up to; `0` covers every level
+    pub permission_level: i32,
+    /// JSON array of granted resource patterns; `None` grants every resource
+    pub resource_json: Option<&'a str>,
+    /// When the grant stops applying
+    pub expires_at: Option<DateTime<Utc>>,
+    /// How many runs may use the grant
+    pub max_uses: Option<i32>,
+}
+
+/// Grants a permission to a workflow code until it expires or is used up.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_code_id` - Workflow code receiving the grant
+/// * `grant` - The permission and its limits
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_permission_grant(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+    grant: NewPermissionGrant<'_>,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        workflow_code_id: Set(workflow_code_id.to_string()),
+        plugin_function_id: Set(grant.plugin_function_id.to_string()),
+        permission_type: Set(grant.permission_type),
+        permission_level: Set(grant.permission_level),
+        resource_json: Set(grant.resource_json.map(str::to_string)),
+        expires_at: Set(grant.expires_at),
+        max_uses: Set(grant.max_uses),
+        uses: Set(0),
+        created_at: Set(Some(Utc::now())),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Lists every grant of a workflow code, including expired and used up ones.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_code_id` - Workflow code whose grants are returned
+///
+/// # Returns
+///
+/// Returns the grants ordered by creation time.
+pub async fn list_permission_grants(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+) -> Result<Vec<Model>, DbErr> {
+    WorkflowCodePermissionGrant::find()
+        .filter(workflow_code_permission_grant::Column::WorkflowCodeId.eq(workflow_code_id))
+        .order_by_asc(workflow_code_permission_grant::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+/// Lists the grants of a workflow code that still apply at `now`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_code_id` - Workflow code whose grants are returned
+/// * `now` - Grants expiring at or before this time are skipped
+///
+/// # Returns
+///
+/// Returns the grants that have neither expired nor been used up.
+pub async fn list_active_permission_grants(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<Model>, DbErr> {
+    WorkflowCodePermissionGrant::find()
+        .filter(workflow_code_permission_grant::Column::WorkflowCodeId.eq(workflow_code_id))
+        .filter(
+            Condition::any()
+                .add(workflow_code_permission_grant::Column::ExpiresAt.is_null())
+                .add(workflow_code_permission_grant::Column::ExpiresAt.gt(now)),
+        )
+        .filter(
+            Condition::any()
+                .add(workflow_code_permission_grant::Column::MaxUses.is_null())
+                .add(
+                    Expr::col(workflow_code_permission_grant::Column::Uses)
+                        .lt(Expr::col(workflow_code_permission_grant::Column::MaxUses)),
+                ),
+        )
+        .order_by_asc(workflow_code_permission_grant::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+/// Claims one use of a grant for a run.
+///
+/// The use is only counted while the grant has uses left, in the same statement, so
+/// runs relying on a grant at the same time cannot take it past `max_uses`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `grant_id` - Grant the run is about to rely on
+///
+/// # Returns
+///
+/// Returns `true` when the use was counted, or `false` when the grant does not exist
+/// or is used up.
+pub async fn claim_permission_grant_use(
+    db: &DatabaseConnection,
+    grant_id: &str,
+) -> Result<bool, DbErr> {
+    let result = WorkflowCodePermissionGrant::update_many()
+        .col_expr(
+            workflow_code_permission_grant::Column::Uses,
+            Expr::col(workflow_code_permission_grant::Column::Uses).add(1),
+        )
+        .filter(workflow_code_permission_grant::Column::Id.eq(grant_id))
+        .filter(
+            Condition::any()
+                .add(workflow_code_permission_grant::Column::MaxUses.is_null())
+                .add(
+                    Expr::col(workflow_code_permission_grant::Column::Uses)
+                        .lt(Expr::col(workflow_code_permission_grant::Column::MaxUses)),
+                ),
+        )
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Revokes a grant.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `grant_id` - The unique identifier of the grant to delete
+///
+/// # Returns
+///
+/// Returns the number of deleted records (0 or 1).
+pub async fn delete_permission_grant(
+    db: &DatabaseConnection,
+    grant_id: &str,
+) -> Result<u64, DbErr> {
+    let result = WorkflowCodePermissionGrant::delete_by_id(grant_id.to_string())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_code_permission_grant (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_code_id TEXT NOT NULL,
+                plugin_function_id TEXT NOT NULL,
+                permission_type INTEGER NOT NULL,
+                permission_level INTEGER NOT NULL DEFAULT 0,
+                resource_json TEXT,
+                expires_at TEXT,
+                max_uses INTEGER,
+                uses INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    fn grant(
+        expires_at: Option<DateTime<Utc>>,
+        max_uses: Option<i32>,
+    ) -> NewPermissionGrant<'static> {
+        NewPermissionGrant {
+            plugin_function_id: "app.sapphillon.core.exec.exec",
+            permission_type: 5,
+            permission_level: 0,
+            resource_json: None,
+            expires_at,
+            max_uses,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grants_stop_applying_when_expired_or_used_up() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let now = Utc::now();
+        let one_shot = create_permission_grant(&db, "wc-1", grant(None, Some(1))).await?;
+        let expired =
+            create_permission_grant(&db, "wc-1", grant(Some(now - Duration::minutes(1)), None))
+                .await?;
+        let timed =
+            create_permission_grant(&db, "wc-1", grant(Some(now + Duration::hours(1)), None))
+                .await?;
+
+        let active: Vec<String> = list_active_permission_grants(&db, "wc-1", now)
+            .await?
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        assert_eq!(active, vec![one_shot.id.clone(), timed.id.clone()]);
+
+        assert!(claim_permission_grant_use(&db, &one_shot.id).await?);
+        let active: Vec<String> = list_active_permission_grants(&db, "wc-1", now)
+            .await?
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        assert_eq!(active, vec![timed.id.clone()]);
+        assert_eq!(list_permission_grants(&db, "wc-1").await?.len(), 3);
+
+        // A used up grant cannot be claimed again, while a grant without a limit can.
+        assert!(!claim_permission_grant_use(&db, &one_shot.id).await?);
+        assert!(claim_permission_grant_use(&db, &timed.id).await?);
+        assert!(!claim_permission_grant_use(&db, "missing").await?);
+        let uses: Vec<(String, i32)> = list_permission_grants(&db, "wc-1")
+            .await?
+            .into_iter()
+            .map(|g| (g.id, g.uses))
+            .collect();
+        assert!(uses.contains(&(one_shot.id.clone(), 1)));
+        assert!(uses.contains(&(timed.id.clone(), 1)));
+
+        assert_eq!(delete_permission_grant(&db, &expired.id).await?, 1);
+        assert_eq!(delete_permission_grant(&db, &expired.id).await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for permission profiles and their attachment to workflows.
+//!
+//! A profile is a named set of allowed permissions, such as `read-only`, that can
+//! be attached to a workflow instead of listing permissions per plugin function.
+//! `permissions` holds the profile's entries as JSON; the server parses and
+//! expands them at run time, so this layer stores them as plain text.
+
+use chrono::Utc;
+use entity::entity::permission_profile::{self, ActiveModel, Entity as PermissionProfile, Model};
+use entity::entity::workflow_permission_profile::{self, Entity as WorkflowPermissionProfile};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+/// Creates a profile or replaces the description and permissions of an existing one.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `name` - Unique name of the profile, e.g. `read-only`
+/// * `description` - Optional human-readable description
+/// * `permissions` - JSON array of the profile's permission entries
+///
+/// # Returns
+///
+/// Returns the stored `Model` on success, or a database error.
+pub async fn save_permission_profile(
+    db: &DatabaseConnection,
+    name: &str,
+    description: Option<&str>,
+    permissions: &str,
+) -> Result<Model, DbErr> {
+    match PermissionProfile::find_by_id(name.to_string())
+        .one(db)
+        .await?
+    {
+        Some(existing) => {
+            let mut active_model: ActiveModel = existing.into();
+            active_model.description = Set(description.map(str::to_string));
+            active_model.permissions = Set(permissions.to_string());
+            active_model.update(db).await
+        }
+        None => {
+            let active_model = ActiveModel {
+                name: Set(name.to_string()),
+                description: Set(description.map(str::to_string)),
+                permissions: Set(permissions.to_string()),
+                created_at: Set(Some(Utc::now())),
+            };
+            active_model.insert(db).await
+        }
+    }
+}
+
+/// Retrieves a profile by name.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `name` - Name of the profile
+///
+/// # Returns
+///
+/// Returns `Some(Model)` if found, `None` otherwise.
+pub async fn get_permission_profile(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<Option<Model>, DbErr> {
+    PermissionProfile::find_by_id(name.to_string())
+        .one(db)
+        .await
+}
+
+/// Lists all profiles ordered by name.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+///
+/// # Returns
+///
+/// Returns a vector of profile models.
+pub async fn list_permission_profiles(db: &DatabaseConnection) -> Result<Vec<Model>, DbErr> {
+    PermissionProfile::find()
+        .order_by_asc(permission_profile::Column::Name)
+        .all(db)
+        .await
+}
+
+/// Deletes a profile and detaches it from every workflow.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `name` - Name of the profile to delete
+///
+/// # Returns
+///
+/// Returns the number of deleted profiles (0 or 1).
+pub async fn delete_permission_profile(db: &DatabaseConnection, name: &str) -> Result<u64, DbErr> {
+    WorkflowPermissionProfile::delete_many()
+        .filter(workflow_permission_profile::Column::ProfileName.eq(name))
+        .exec(db)
+        .await?;
+    let result = PermissionProfile::delete_by_id(name.to_string())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Attaches a profile to a workflow. Attaching an attached profile again does nothing.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow receiving the profile's permissions
+/// * `name` - Name of the profile
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or a database error.
+pub async fn attach_permission_profile(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    name: &str,
+) -> Result<(), DbErr> {
+    let attached = WorkflowPermissionProfile::find()
+        .filter(workflow_permission_profile::Column::WorkflowId.eq(workflow_id))
+        .filter(workflow_permission_profile::Column::ProfileName.eq(name))
+        .one(db)
+        .await?;
+    if attached.is_none() {
+        let active_model = workflow_permission_profile::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            workflow_id: Set(workflow_id.to_string()),
+            profile_name: Set(name.to_string()),
+            created_at: Set(Some(Utc::now())),
+        };
+        active_model.insert(db).await?;
+    }
+    Ok(())
+}
+
+/// Detaches a profile from a workflow.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow to detach the profile from
+/// * `name` - Name of the profile
+///
+/// # Returns
+///
+/// Returns the number of detached profiles (0 or 1).
+pub async fn detach_permission_profile(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    name: &str,
+) -> Result<u64, DbErr> {
+    let result = WorkflowPermissionProfile::delete_many()
+        .filter(workflow_permission_profile::Column::WorkflowId.eq(workflow_id))
+        .filter(workflow_permission_profile::Column::ProfileName.eq(name))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Lists the profiles attached to a workflow, in the order they were attached.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow whose profiles are returned
+///
+/// # Returns
+///
+/// Returns the attached profile models.
+pub async fn list_workflow_permission_profiles(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+) -> Result<Vec<Model>, DbErr> {
+    let attached = WorkflowPermissionProfile::find()
+        .filter(workflow_permission_profile::Column::WorkflowId.eq(workflow_id))
+        .order_by_asc(workflow_permission_profile::Column::CreatedAt)
+        .find_also_related(PermissionProfile)
+        .all(db)
+        .await?;
+    Ok(attached
+        .into_iter()
+        .filter_map(|(_, profile)| profile)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        for sql in [
+            r#"
+            CREATE TABLE permission_profile (
+                name TEXT NOT NULL PRIMARY KEY,
+                description TEXT,
+                permissions TEXT NOT NULL,
+                created_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE workflow_permission_profile (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                profile_name TEXT NOT NULL,
+                created_at TEXT
+            )
+            "#,
+        ] {
+            db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+                .await?;
+        }
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_save_and_list_profiles() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        save_permission_profile(&db, "read-only", None, "[]").await?;
+        let updated =
+            save_permission_profile(&db, "read-only", Some("reads"), r#"[{"function":"*"}]"#)
+                .await?;
+        assert_eq!(updated.description.as_deref(), Some("reads"));
+
+        save_permission_profile(&db, "full-trust", None, "[]").await?;
+        let names: Vec<String> = list_permission_profiles(&db)
+            .await?
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["full-trust", "read-only"]);
+
+        let fetched = get_permission_profile(&db, "read-only")
+            .await?
+            .expect("profile");
+        assert_eq!(fetched.permissions, r#"[{"function":"*"}]"#);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_attach_and_detach_profiles() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        save_permission_profile(&db, "read-only", None, "[]").await?;
+        save_permission_profile(&db, "full-trust", None, "[]").await?;
+
+        attach_permission_profile(&db, "wf-1", "read-only").await?;
+        attach_permission_profile(&db, "wf-1", "read-only").await?;
+        attach_permission_profile(&db, "wf-2", "full-trust").await?;
+
+        let attached = list_workflow_permission_profiles(&db, "wf-1").await?;
+        assert_eq!(attached.len(), 1);
+        assert_eq!(attached[0].name, "read-only");
+
+        assert_eq!(
+            detach_permission_profile(&db, "wf-1", "read-only").await?,
+            1
+        );
+        assert!(
+            list_workflow_permission_profiles(&db, "wf-1")
+                .await?
+                .is_empty()
+        );
+
+        assert_eq!(delete_permission_profile(&db, "full-trust").await?, 1);
+        assert!(
+            list_workflow_permission_profiles(&db, "wf-2")
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+}
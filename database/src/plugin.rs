@@ -11,7 +11,11 @@ use sea_orm::{DatabaseConnection, DbErr};
 
 // entity models are converted via helpers in `entity::convert::plugin_code`.
 
-use sapphillon_core::proto::sapphillon::v1::PluginPackage as ProtoPluginPackage;
+use sapphillon_core::proto::sapphillon::v1::{
+    Permission as ProtoPermission, PluginFunction as ProtoPluginFunction,
+    PluginPackage as ProtoPluginPackage,
+};
+use std::collections::HashMap;
 
 /// Lists plugin packages and returns protobuf `PluginPackage` messages.
 ///
@@ -28,64 +32,121 @@ pub async fn list_plugins(
     let (items, token) =
         plugin_package_crud::list_plugin_packages(db, next_page_token, page_size).await?;
 
-    let mut out: Vec<ProtoPluginPackage> = Vec::with_capacity(items.len());
+    let function_ids: Vec<String> = items
+        .iter()
+        .flat_map(|(_, funcs)| funcs.iter().map(|func| func.function_id.clone()))
+        .collect();
+    let perms_by_function = load_function_permissions(db, &function_ids).await?;
 
-    // Collect all function ids for batch loading permissions.
-    let mut all_function_ids: Vec<String> = Vec::new();
-    for (_pkg_entity, func_entities) in items.iter() {
-        for func in func_entities.iter() {
-            all_function_ids.push(func.function_id.clone());
-        }
-    }
+    let out = items
+        .into_iter()
+        .map(|(pkg_entity, func_entities)| {
+            package_to_proto(&pkg_entity, func_entities, &perms_by_function)
+        })
+        .collect();
+
+    Ok((out, token))
+}
+
+/// Retrieves one plugin package with its functions and their permissions.
+///
+/// # Arguments
+///
+/// * `db` - The database connection to use.
+/// * `package_id` - The package identifier to look up.
+///
+/// # Returns
+///
+/// Returns `Ok(Some(package))` when found, `Ok(None)` when missing, or a [`DbErr`] on failure.
+pub async fn get_plugin(
+    db: &DatabaseConnection,
+    package_id: &str,
+) -> Result<Option<ProtoPluginPackage>, DbErr> {
+    let Some((pkg_entity, func_entities)) =
+        plugin_package_crud::get_plugin_package(db, package_id).await?
+    else {
+        return Ok(None);
+    };
+    let function_ids: Vec<String> = func_entities
+        .iter()
+        .map(|func| func.function_id.clone())
+        .collect();
+    let perms_by_function = load_function_permissions(db, &function_ids).await?;
+    Ok(Some(package_to_proto(
+        &pkg_entity,
+        func_entities,
+        &perms_by_function,
+    )))
+}
+
+/// Retrieves one plugin function with its permissions.
+///
+/// # Arguments
+///
+/// * `db` - The database connection to use.
+/// * `function_id` - The function identifier to look up.
+///
+/// # Returns
+///
+/// Returns `Ok(Some(function))` when found, `Ok(None)` when missing, or a [`DbErr`] on failure.
+pub async fn get_plugin_function(
+    db: &DatabaseConnection,
+    function_id: &str,
+) -> Result<Option<ProtoPluginFunction>, DbErr> {
+    let Some((func, _)) = plugin_function_crud::get_plugin_function(db, function_id).await? else {
+        return Ok(None);
+    };
+    let perms_by_function = load_function_permissions(db, &[func.function_id.clone()]).await?;
+    let perms = perms_by_function
+        .get(&func.function_id)
+        .cloned()
+        .unwrap_or_default();
+    Ok(Some(entity::convert::plugin::plugin_function_to_proto(
+        &func,
+        Some(&perms),
+    )))
+}
 
-    // Batch-load all function permission relations in one query.
+/// Batch-loads the permissions of the given functions, keyed by function ID.
+async fn load_function_permissions(
+    db: &DatabaseConnection,
+    function_ids: &[String],
+) -> Result<HashMap<String, Vec<ProtoPermission>>, DbErr> {
     let perm_relations =
         plugin_function_permission_crud::list_plugin_function_permissions_for_function_ids(
             db,
-            &all_function_ids,
+            function_ids,
         )
         .await?;
 
-    // Map function_id -> Vec<permission::Model>
-    use std::collections::HashMap;
-    let mut perms_by_function: HashMap<String, Vec<entity::entity::permission::Model>> =
-        HashMap::new();
+    let mut perms_by_function: HashMap<String, Vec<ProtoPermission>> = HashMap::new();
     for (_rel, perm_opt, _func_opt) in perm_relations.into_iter() {
         if let Some(perm) = perm_opt {
             perms_by_function
                 .entry(perm.plugin_function_id.clone())
                 .or_default()
-                .push(perm);
-        }
-    }
-
-    // Now convert packages & their functions to proto using the in-memory map.
-    for (pkg_entity, func_entities) in items.into_iter() {
-        let mut proto_funcs: Vec<sapphillon_core::proto::sapphillon::v1::PluginFunction> =
-            Vec::new();
-        for func in func_entities.into_iter() {
-            let proto_perms: Vec<sapphillon_core::proto::sapphillon::v1::Permission> =
-                match perms_by_function.get(&func.function_id) {
-                    Some(perm_entities) => perm_entities
-                        .iter()
-                        .map(entity::convert::plugin::permission_to_proto)
-                        .collect(),
-                    None => Vec::new(),
-                };
-
-            let proto_fn =
-                entity::convert::plugin::plugin_function_to_proto(&func, Some(&proto_perms));
-            proto_funcs.push(proto_fn);
+                .push(entity::convert::plugin::permission_to_proto(&perm));
         }
-
-        let proto_pkg = entity::convert::plugin::plugin_package_to_proto_with_functions(
-            &pkg_entity,
-            Some(&proto_funcs),
-        );
-        out.push(proto_pkg);
     }
+    Ok(perms_by_function)
+}
 
-    Ok((out, token))
+fn package_to_proto(
+    pkg_entity: &entity::entity::plugin_package::Model,
+    func_entities: Vec<entity::entity::plugin_function::Model>,
+    perms_by_function: &HashMap<String, Vec<ProtoPermission>>,
+) -> ProtoPluginPackage {
+    let proto_funcs: Vec<ProtoPluginFunction> = func_entities
+        .iter()
+        .map(|func| {
+            let proto_perms = perms_by_function
+                .get(&func.function_id)
+                .cloned()
+                .unwrap_or_default();
+            entity::convert::plugin::plugin_function_to_proto(func, Some(&proto_perms))
+        })
+        .collect();
+    entity::convert::plugin::plugin_package_to_proto_with_functions(pkg_entity, Some(&proto_funcs))
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -114,6 +175,14 @@ impl From<&entity::entity::permission::Model> for PermissionKey {
 pub async fn init_register_plugins(
     db: &DatabaseConnection,
     plugins: Vec<ProtoPluginPackage>,
+) -> Result<(), DbErr> {
+    let db = db.clone();
+    crate::writer::serialize_write(async move { register_plugins(&db, plugins).await }).await
+}
+
+async fn register_plugins(
+    db: &DatabaseConnection,
+    plugins: Vec<ProtoPluginPackage>,
 ) -> Result<(), DbErr> {
     use entity::convert::plugin_code::{
         proto_to_permission, proto_to_plugin_function, proto_to_plugin_package,
@@ -121,7 +190,7 @@ pub async fn init_register_plugins(
     use entity::entity::{permission, plugin_function, plugin_function_permission, plugin_package};
     use sea_orm::ActiveValue::{NotSet, Set};
     use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
-    use std::collections::{HashMap, HashSet};
+    use std::collections::HashSet;
 
     if plugins.is_empty() {
         return Ok(());
@@ -644,6 +713,34 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_plugin_and_function_include_permissions() -> Result<(), sea_orm::DbErr> {
+        let db = setup_db().await?;
+
+        insert_package(&db, "pkg1").await?;
+        insert_function(&db, "pkg1.fn1", "pkg1", "F1").await?;
+        insert_function(&db, "pkg1.fn2", "pkg1", "F2").await?;
+        insert_permission(&db, 101, "pkg1.fn1").await?;
+        link_permission(&db, "pkg1.fn1", "101").await?;
+
+        let pkg = get_plugin(&db, "pkg1").await?.expect("package");
+        assert_eq!(pkg.functions.len(), 2);
+        assert!(get_plugin(&db, "missing").await?.is_none());
+
+        let func = get_plugin_function(&db, "pkg1.fn1")
+            .await?
+            .expect("function");
+        assert_eq!(func.function_name, "F1");
+        assert_eq!(func.permissions.len(), 1);
+        let func = get_plugin_function(&db, "pkg1.fn2")
+            .await?
+            .expect("function");
+        assert!(func.permissions.is_empty());
+        assert!(get_plugin_function(&db, "pkg1.missing").await?.is_none());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_init_register_plugins_updates_on_diff() -> Result<(), sea_orm::DbErr> {
         use sapphillon_core::proto::sapphillon::v1::{
@@ -0,0 +1,209 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for workflow prompts.
+//!
+//! A prompt is a question a running workflow asks a person. It stays `pending`
+//! until it is answered or the workflow stops waiting and marks it `expired`;
+//! only pending prompts can change state, so a late answer cannot overwrite an
+//! expired prompt. Answers are stored as JSON text.
+
+use chrono::Utc;
+use entity::entity::workflow_prompt::{self, ActiveModel, Entity as WorkflowPrompt, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+/// Status of a prompt waiting for an answer.
+pub const PROMPT_STATUS_PENDING: &str = "pending";
+/// Status of an answered prompt.
+pub const PROMPT_STATUS_ANSWERED: &str = "answered";
+/// Status of a prompt the workflow stopped waiting for.
+pub const PROMPT_STATUS_EXPIRED: &str = "expired";
+
+/// Creates a pending prompt.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow asking the question
+/// * `kind` - Expected answer, e.g. `confirm` or `input`
+/// * `message` - Question shown to the person
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_prompt(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    kind: &str,
+    message: &str,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        workflow_id: Set(workflow_id.to_string()),
+        kind: Set(kind.to_string()),
+        message: Set(message.to_string()),
+        status: Set(PROMPT_STATUS_PENDING.to_string()),
+        answer: Set(None),
+        created_at: Set(Some(Utc::now())),
+        answered_at: Set(None),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Retrieves a prompt by its ID.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `prompt_id` - The unique identifier of the prompt
+///
+/// # Returns
+///
+/// Returns `Some(Model)` if found, `None` otherwise.
+pub async fn get_prompt(db: &DatabaseConnection, prompt_id: &str) -> Result<Option<Model>, DbErr> {
+    WorkflowPrompt::find_by_id(prompt_id.to_string())
+        .one(db)
+        .await
+}
+
+/// Lists prompts, oldest first.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `pending_only` - When set, only prompts still waiting for an answer are returned
+///
+/// # Returns
+///
+/// Returns a vector of prompt models.
+pub async fn list_prompts(
+    db: &DatabaseConnection,
+    pending_only: bool,
+) -> Result<Vec<Model>, DbErr> {
+    let mut query = WorkflowPrompt::find().order_by_asc(workflow_prompt::Column::CreatedAt);
+    if pending_only {
+        query = query.filter(workflow_prompt::Column::Status.eq(PROMPT_STATUS_PENDING));
+    }
+    query.all(db).await
+}
+
+/// Stores the answer of a pending prompt.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `prompt_id` - The unique identifier of the prompt
+/// * `answer` - JSON text of the answer
+///
+/// # Returns
+///
+/// Returns the number of updated prompts; 0 when the prompt does not exist or is no
+/// longer pending.
+pub async fn answer_prompt(
+    db: &DatabaseConnection,
+    prompt_id: &str,
+    answer: &str,
+) -> Result<u64, DbErr> {
+    let result = WorkflowPrompt::update_many()
+        .col_expr(
+            workflow_prompt::Column::Status,
+            Expr::value(PROMPT_STATUS_ANSWERED),
+        )
+        .col_expr(workflow_prompt::Column::Answer, Expr::value(answer))
+        .col_expr(workflow_prompt::Column::AnsweredAt, Expr::value(Utc::now()))
+        .filter(workflow_prompt::Column::Id.eq(prompt_id))
+        .filter(workflow_prompt::Column::Status.eq(PROMPT_STATUS_PENDING))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Marks a pending prompt as expired.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `prompt_id` - The unique identifier of the prompt
+///
+/// # Returns
+///
+/// Returns the number of updated prompts; 0 when the prompt was answered in the meantime.
+pub async fn expire_prompt(db: &DatabaseConnection, prompt_id: &str) -> Result<u64, DbErr> {
+    let result = WorkflowPrompt::update_many()
+        .col_expr(
+            workflow_prompt::Column::Status,
+            Expr::value(PROMPT_STATUS_EXPIRED),
+        )
+        .filter(workflow_prompt::Column::Id.eq(prompt_id))
+        .filter(workflow_prompt::Column::Status.eq(PROMPT_STATUS_PENDING))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_prompt (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                message TEXT NOT NULL,
+                status TEXT NOT NULL,
+                answer TEXT,
+                created_at TEXT,
+                answered_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_answer_prompt_only_once() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let prompt = create_prompt(&db, "wf-1", "confirm", "Delete 200 files?").await?;
+        assert_eq!(list_prompts(&db, true).await?.len(), 1);
+
+        assert_eq!(answer_prompt(&db, &prompt.id, "true").await?, 1);
+        assert_eq!(answer_prompt(&db, &prompt.id, "false").await?, 0);
+
+        let answered = get_prompt(&db, &prompt.id).await?.expect("prompt");
+        assert_eq!(answered.status, PROMPT_STATUS_ANSWERED);
+        assert_eq!(answered.answer.as_deref(), Some("true"));
+        assert!(answered.answered_at.is_some());
+        assert!(list_prompts(&db, true).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_prompt_rejects_answers() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let prompt = create_prompt(&db, "wf-1", "input", "Folder name?").await?;
+
+        assert_eq!(expire_prompt(&db, &prompt.id).await?, 1);
+        assert_eq!(answer_prompt(&db, &prompt.id, "\"tmp\"").await?, 0);
+        assert_eq!(
+            get_prompt(&db, &prompt.id).await?.expect("prompt").status,
+            PROMPT_STATUS_EXPIRED
+        );
+        assert_eq!(list_prompts(&db, false).await?.len(), 1);
+        Ok(())
+    }
+}
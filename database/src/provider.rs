@@ -7,7 +7,7 @@ pub mod provider_crud;
 pub use provider_crud::{
     create_provider as create_provider_entity, delete_provider as delete_provider_entity,
     get_provider as get_provider_entity, list_providers as list_providers_entity,
-    update_provider as update_provider_entity,
+    reencrypt_provider_api_keys, set_provider_options, update_provider as update_provider_entity,
 };
 
 use entity::entity::provider::Model as EntityProvider;
@@ -74,7 +74,9 @@ mod tests {
                 name TEXT PRIMARY KEY,
                 display_name TEXT NOT NULL,
                 api_key TEXT NOT NULL,
-                api_endpoint TEXT NOT NULL
+                api_endpoint TEXT NOT NULL,
+                kind TEXT,
+                options TEXT
             )
         "#;
         db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
@@ -4,6 +4,7 @@
 
 use base64::Engine as _;
 use base64::engine::general_purpose;
+use entity::encryption::FieldKeyring;
 use entity::entity::provider;
 use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, QuerySelect};
 
@@ -49,6 +50,28 @@ pub async fn update_provider(
     }
 }
 
+/// Sets the API kind and the provider-specific options (a JSON object) of a stored
+/// provider. These are not part of the proto message, so [`update_provider`] keeps them.
+///
+/// Returns `Ok(Some(model))` when the provider exists, `Ok(None)` otherwise.
+pub async fn set_provider_options(
+    db: &DatabaseConnection,
+    name: &str,
+    kind: Option<String>,
+    options: Option<String>,
+) -> Result<Option<provider::Model>, DbErr> {
+    if let Some(existing) = get_provider(db, name).await? {
+        let mut active_model: provider::ActiveModel = existing.into();
+        use sea_orm::ActiveValue::Set;
+        active_model.kind = Set(kind);
+        active_model.options = Set(options);
+        let updated = active_model.update(db).await?;
+        Ok(Some(updated))
+    } else {
+        Ok(None)
+    }
+}
+
 /// Returns providers using an opaque base64-encoded offset pagination scheme.
 pub async fn list_providers(
     db: &DatabaseConnection,
@@ -108,6 +131,30 @@ pub async fn delete_provider(db: &DatabaseConnection, name: &str) -> Result<bool
     }
 }
 
+/// Seals every stored API key with the current key of `keyring`, e.g. after a new key
+/// was put first or when encryption is turned on for an existing database.
+///
+/// Returns the number of providers whose key was re-encrypted, or a [`DbErr::Custom`]
+/// when a key cannot be decrypted with `keyring`.
+pub async fn reencrypt_provider_api_keys(
+    db: &DatabaseConnection,
+    keyring: &FieldKeyring,
+) -> Result<u64, DbErr> {
+    let mut updated = 0;
+    for existing in provider::Entity::find().all(db).await? {
+        let api_key = keyring
+            .reencrypt(&existing.api_key)
+            .map_err(|err| DbErr::Custom(format!("provider {}: {err:#}", existing.name)))?;
+        if let Some(api_key) = api_key {
+            let mut active_model: provider::ActiveModel = existing.into();
+            active_model.api_key = sea_orm::ActiveValue::Set(api_key);
+            active_model.update(db).await?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,7 +168,9 @@ mod tests {
                 name TEXT PRIMARY KEY,
                 display_name TEXT NOT NULL,
                 api_key TEXT NOT NULL,
-                api_endpoint TEXT NOT NULL
+                api_endpoint TEXT NOT NULL,
+                kind TEXT,
+                options TEXT
             )
         "#;
         db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
@@ -137,6 +186,8 @@ mod tests {
             display_name: "Demo".to_string(),
             api_key: "secret".to_string(),
             api_endpoint: "https://example.test".to_string(),
+            kind: None,
+            options: None,
         };
 
         let inserted = create_provider(&db, model.clone()).await?;
@@ -156,6 +207,8 @@ mod tests {
             display_name: "Source".to_string(),
             api_key: "key".to_string(),
             api_endpoint: "https://a.test".to_string(),
+            kind: None,
+            options: None,
         };
         create_provider(&db, model.clone()).await?;
 
@@ -164,6 +217,8 @@ mod tests {
             display_name: "Updated".to_string(),
             api_key: "changed".to_string(),
             api_endpoint: "https://b.test".to_string(),
+            kind: None,
+            options: None,
         };
         let updated = update_provider(&db, updated).await?;
         assert!(updated.is_some());
@@ -175,6 +230,47 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn provider_options_survive_proto_updates() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let model = provider::Model {
+            name: "providers/claude".to_string(),
+            display_name: "Claude".to_string(),
+            api_key: "key".to_string(),
+            api_endpoint: String::new(),
+            kind: None,
+            options: None,
+        };
+        create_provider(&db, model.clone()).await?;
+
+        let set = set_provider_options(
+            &db,
+            &model.name,
+            Some("anthropic".to_string()),
+            Some(r#"{"max_tokens":8192}"#.to_string()),
+        )
+        .await?;
+        assert_eq!(set.unwrap().kind.as_deref(), Some("anthropic"));
+        assert!(
+            set_provider_options(&db, "providers/missing", None, None)
+                .await?
+                .is_none()
+        );
+
+        update_provider(
+            &db,
+            provider::Model {
+                display_name: "Renamed".to_string(),
+                ..model.clone()
+            },
+        )
+        .await?;
+        let fetched = get_provider(&db, &model.name).await?.unwrap();
+        assert_eq!(fetched.kind.as_deref(), Some("anthropic"));
+        assert_eq!(fetched.options.as_deref(), Some(r#"{"max_tokens":8192}"#));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn list_providers_paginates() -> Result<(), DbErr> {
         let db = setup_db().await?;
@@ -184,6 +280,8 @@ mod tests {
                 display_name: format!("P{idx}"),
                 api_key: format!("key{idx}"),
                 api_endpoint: format!("https://{idx}.test"),
+                kind: None,
+                options: None,
             };
             create_provider(&db, model).await?;
         }
@@ -196,4 +294,44 @@ mod tests {
         assert_eq!(page_two.len(), 3);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn api_keys_are_reencrypted_with_the_current_key() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let model = provider::Model {
+            name: "providers/demo".to_string(),
+            display_name: "Demo".to_string(),
+            api_key: "secret".to_string(),
+            api_endpoint: "https://example.test".to_string(),
+            kind: None,
+            options: None,
+        };
+        create_provider(&db, model.clone()).await?;
+        create_provider(
+            &db,
+            provider::Model {
+                name: "providers/keyless".to_string(),
+                api_key: String::new(),
+                ..model.clone()
+            },
+        )
+        .await?;
+
+        let old_line = FieldKeyring::generate_key_line("old");
+        let old = FieldKeyring::parse(&old_line).unwrap();
+        assert_eq!(reencrypt_provider_api_keys(&db, &old).await?, 1);
+        let sealed = get_provider(&db, &model.name).await?.unwrap().api_key;
+        assert!(sealed.starts_with("enc:v1:old:"), "{sealed}");
+        assert_eq!(reencrypt_provider_api_keys(&db, &old).await?, 0);
+
+        let new_line = FieldKeyring::generate_key_line("new");
+        let rotated = FieldKeyring::parse(&format!("{new_line}\n{old_line}")).unwrap();
+        assert_eq!(reencrypt_provider_api_keys(&db, &rotated).await?, 1);
+        let resealed = get_provider(&db, &model.name).await?.unwrap().api_key;
+        assert_eq!(rotated.decrypt(&resealed).unwrap(), "secret");
+
+        let unrelated = FieldKeyring::parse(&FieldKeyring::generate_key_line("other")).unwrap();
+        assert!(reencrypt_provider_api_keys(&db, &unrelated).await.is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,157 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for workflow recordings.
+//!
+//! A recording stores the plugin calls made during one run, as a JSON array,
+//! together with the console output the run produced. The server replays the
+//! calls to re-execute the run deterministically; this layer does not interpret
+//! the JSON.
+
+use chrono::Utc;
+use entity::entity::workflow_recording::{self, ActiveModel, Entity as WorkflowRecording, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+/// Stores the recording of a run.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow that was run
+/// * `workflow_code_id` - Code revision that was run
+/// * `calls` - JSON array of the recorded plugin calls
+/// * `output` - Console output of the run
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_recording(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    workflow_code_id: &str,
+    calls: &str,
+    output: &str,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        workflow_id: Set(workflow_id.to_string()),
+        workflow_code_id: Set(workflow_code_id.to_string()),
+        calls: Set(calls.to_string()),
+        output: Set(output.to_string()),
+        created_at: Set(Some(Utc::now())),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Retrieves a recording by its ID.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `recording_id` - The unique identifier of the recording
+///
+/// # Returns
+///
+/// Returns `Some(Model)` if found, `None` otherwise.
+pub async fn get_recording(
+    db: &DatabaseConnection,
+    recording_id: &str,
+) -> Result<Option<Model>, DbErr> {
+    WorkflowRecording::find_by_id(recording_id.to_string())
+        .one(db)
+        .await
+}
+
+/// Lists recordings, newest first.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - When set, only recordings of this workflow are returned
+///
+/// # Returns
+///
+/// Returns a vector of recording models.
+pub async fn list_recordings(
+    db: &DatabaseConnection,
+    workflow_id: Option<&str>,
+) -> Result<Vec<Model>, DbErr> {
+    let mut query = WorkflowRecording::find();
+    if let Some(workflow_id) = workflow_id {
+        query = query.filter(workflow_recording::Column::WorkflowId.eq(workflow_id));
+    }
+    query
+        .order_by_desc(workflow_recording::Column::CreatedAt)
+        .order_by_asc(workflow_recording::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Deletes a recording.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `recording_id` - The unique identifier of the recording
+///
+/// # Returns
+///
+/// Returns the number of deleted recordings.
+pub async fn delete_recording(db: &DatabaseConnection, recording_id: &str) -> Result<u64, DbErr> {
+    let result = WorkflowRecording::delete_by_id(recording_id.to_string())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_recording (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                workflow_code_id TEXT NOT NULL,
+                calls TEXT NOT NULL,
+                output TEXT NOT NULL,
+                created_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_create_get_list_delete_recording() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        let created = create_recording(&db, "wf-1", "code-1", "[]", "hello\n").await?;
+        create_recording(&db, "wf-2", "code-2", "[]", "").await?;
+
+        let fetched = get_recording(&db, &created.id).await?.expect("recording");
+        assert_eq!(fetched.workflow_code_id, "code-1");
+        assert_eq!(fetched.output, "hello\n");
+
+        assert_eq!(list_recordings(&db, Some("wf-1")).await?.len(), 1);
+        assert_eq!(list_recordings(&db, None).await?.len(), 2);
+
+        assert_eq!(delete_recording(&db, &created.id).await?, 1);
+        assert_eq!(delete_recording(&db, &created.id).await?, 0);
+        assert!(get_recording(&db, &created.id).await?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for workflow schedules.
+//!
+//! A schedule attaches a cron expression to a workflow. The scheduler in the
+//! server computes `next_run_at` and uses these helpers to find and advance due
+//! schedules; cron parsing itself is not done at this layer.
+
+use chrono::{DateTime, Utc};
+use entity::entity::workflow_schedule::{self, ActiveModel, Entity as WorkflowSchedule, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+/// Creates a new, enabled schedule for a workflow.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow to run
+/// * `owner_id` - User that owns the schedule, normally the workflow's owner
+/// * `cron` - Cron expression describing when to run
+/// * `next_run_at` - First time the workflow should run
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_schedule(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    owner_id: Option<&str>,
+    cron: &str,
+    next_run_at: DateTime<Utc>,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        workflow_id: Set(workflow_id.to_string()),
+        cron: Set(cron.to_string()),
+        enabled: Set(true),
+        next_run_at: Set(Some(next_run_at)),
+        last_run_at: Set(None),
+        created_at: Set(Some(Utc::now())),
+        owner_id: Set(owner_id.map(str::to_string)),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Retrieves a schedule by its ID.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `schedule_id` - The unique identifier of the schedule
+///
+/// # Returns
+///
+/// Returns `Some(Model)` if found, `None` otherwise.
+pub async fn get_schedule(
+    db: &DatabaseConnection,
+    schedule_id: &str,
+) -> Result<Option<Model>, DbErr> {
+    WorkflowSchedule::find_by_id(schedule_id.to_string())
+        .one(db)
+        .await
+}
+
+/// Lists schedules ordered by creation time.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - When set, only schedules of this workflow are returned
+///
+/// # Returns
+///
+/// Returns a vector of schedule models.
+pub async fn list_schedules(
+    db: &DatabaseConnection,
+    workflow_id: Option<&str>,
+) -> Result<Vec<Model>, DbErr> {
+    let mut query = WorkflowSchedule::find().order_by_asc(workflow_schedule::Column::CreatedAt);
+    if let Some(workflow_id) = workflow_id {
+        query = query.filter(workflow_schedule::Column::WorkflowId.eq(workflow_id));
+    }
+    query.all(db).await
+}
+
+/// Lists the enabled schedules whose next run time is at or before `now`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `now` - Reference time
+///
+/// # Returns
+///
+/// Returns the due schedules, earliest first.
+pub async fn list_due_schedules(
+    db: &DatabaseConnection,
+    now: DateTime<Utc>,
+) -> Result<Vec<Model>, DbErr> {
+    WorkflowSchedule::find()
+        .filter(workflow_schedule::Column::Enabled.eq(true))
+        .filter(workflow_schedule::Column::NextRunAt.lte(now))
+        .order_by_asc(workflow_schedule::Column::NextRunAt)
+        .all(db)
+        .await
+}
+
+/// Records a run of a schedule and stores when it should run next.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `schedule_id` - The unique identifier of the schedule
+/// * `ran_at` - When the run started
+/// * `next_run_at` - Next run time, or `None` when the schedule will not fire again
+///
+/// # Returns
+///
+/// Returns the updated model or an error if the schedule was not found.
+pub async fn mark_schedule_run(
+    db: &DatabaseConnection,
+    schedule_id: &str,
+    ran_at: DateTime<Utc>,
+    next_run_at: Option<DateTime<Utc>>,
+) -> Result<Model, DbErr> {
+    let existing = WorkflowSchedule::find_by_id(schedule_id.to_string())
+        .one(db)
+        .await?;
+
+    match existing {
+        Some(model) => {
+            let mut active_model: ActiveModel = model.into();
+            active_model.last_run_at = Set(Some(ran_at));
+            active_model.next_run_at = Set(next_run_at);
+            active_model.update(db).await
+        }
+        None => Err(DbErr::RecordNotFound(format!(
+            "Workflow schedule not found: {schedule_id}"
+        ))),
+    }
+}
+
+/// Deletes a schedule.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `schedule_id` - The unique identifier of the schedule to delete
+///
+/// # Returns
+///
+/// Returns the number of deleted records (0 or 1).
+pub async fn delete_schedule(db: &DatabaseConnection, schedule_id: &str) -> Result<u64, DbErr> {
+    let result = WorkflowSchedule::delete_by_id(schedule_id.to_string())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_schedule (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                cron TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                next_run_at TEXT,
+                last_run_at TEXT,
+                created_at TEXT,
+                owner_id TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_create_get_and_list_schedules() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let next = Utc::now() + Duration::hours(1);
+
+        let created = create_schedule(&db, "wf-1", None, "0 * * * *", next).await?;
+        let owned = create_schedule(&db, "wf-2", Some("alice"), "*/5 * * * *", next).await?;
+        assert!(created.enabled);
+        assert_eq!(created.owner_id, None);
+        assert_eq!(owned.owner_id.as_deref(), Some("alice"));
+        assert_eq!(created.cron, "0 * * * *");
+
+        let fetched = get_schedule(&db, &created.id).await?.unwrap();
+        assert_eq!(fetched.workflow_id, "wf-1");
+
+        assert_eq!(list_schedules(&db, None).await?.len(), 2);
+        assert_eq!(list_schedules(&db, Some("wf-2")).await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_due_schedules_and_mark_run() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let now = Utc::now();
+
+        let due =
+            create_schedule(&db, "wf-due", None, "* * * * *", now - Duration::minutes(1)).await?;
+        create_schedule(&db, "wf-later", None, "* * * * *", now + Duration::hours(1)).await?;
+
+        let found = list_due_schedules(&db, now).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, due.id);
+
+        let updated =
+            mark_schedule_run(&db, &due.id, now, Some(now + Duration::minutes(1))).await?;
+        assert!(updated.last_run_at.is_some());
+        assert!(list_due_schedules(&db, now).await?.is_empty());
+
+        assert!(mark_schedule_run(&db, "missing", now, None).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_schedule() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let created = create_schedule(&db, "wf-1", None, "0 0 * * *", Utc::now()).await?;
+
+        assert_eq!(delete_schedule(&db, &created.id).await?, 1);
+        assert_eq!(delete_schedule(&db, &created.id).await?, 0);
+        assert!(get_schedule(&db, &created.id).await?.is_none());
+        Ok(())
+    }
+}
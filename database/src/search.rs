@@ -0,0 +1,346 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Full-text search over workflow names and descriptions, code and result output.
+//!
+//! SQLite searches the `workflow_search` FTS5 table, which triggers keep in sync with its
+//! source tables, and ranks hits with BM25. Postgres matches the sources against
+//! `to_tsvector('simple', ...)`, backed by GIN indexes, and ranks hits with `ts_rank`.
+//! MySQL has no index: it matches with `LIKE` and ranks hits by how often the terms occur.
+//!
+//! Every term of the query must occur in a hit. Snippets mark the matching terms with
+//! `<mark>` and `</mark>`.
+
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, DbErr, FromQueryResult, Statement, Value,
+};
+
+/// Opening marker around matched terms in snippets.
+pub const HIGHLIGHT_START: &str = "<mark>";
+/// Closing marker around matched terms in snippets.
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// Characters of context kept on each side of the first match in MySQL snippets.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// A row matching a search query.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct SearchHit {
+    /// `workflow`, `code` or `result`: the table the row comes from.
+    pub kind: String,
+    /// Id of the workflow, workflow code or workflow result that matched.
+    pub source_id: String,
+    /// Workflow the row belongs to.
+    pub workflow_id: String,
+    /// Relevance of the hit; higher is better. Scores are only comparable within one search.
+    pub score: f64,
+    /// Excerpt of the matching text with the terms highlighted.
+    pub snippet: String,
+}
+
+/// Splits a query into the terms that must all occur in a hit.
+fn search_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_string).collect()
+}
+
+/// Builds an FTS5 query matching every term literally, so that the user cannot write
+/// FTS5 syntax such as `NEAR` or column filters.
+fn fts5_match_expression(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Searches workflow content for `query`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `query` - Words to search for; every word must occur in a hit
+/// * `limit` - Maximum number of hits to return
+///
+/// # Returns
+///
+/// Returns the hits, best first. A query without words matches nothing.
+pub async fn search_workflow_content(
+    db: &DatabaseConnection,
+    query: &str,
+    limit: u64,
+) -> Result<Vec<SearchHit>, DbErr> {
+    let terms = search_terms(query);
+    if terms.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+    match db.get_database_backend() {
+        DbBackend::Sqlite => search_sqlite(db, &terms, limit).await,
+        DbBackend::Postgres => search_postgres(db, &terms, limit).await,
+        DbBackend::MySql => search_mysql(db, &terms, limit).await,
+    }
+}
+
+async fn search_sqlite(
+    db: &DatabaseConnection,
+    terms: &[String],
+    limit: u64,
+) -> Result<Vec<SearchHit>, DbErr> {
+    // bm25() is lower for better matches; negate it so that higher scores are better.
+    let sql = format!(
+        "SELECT kind, source_id, workflow_id, -bm25(workflow_search) AS score, \
+         snippet(workflow_search, 3, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}', '…', 16) AS snippet \
+         FROM workflow_search WHERE workflow_search MATCH ? \
+         ORDER BY bm25(workflow_search) LIMIT ?"
+    );
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        sql,
+        [fts5_match_expression(terms).into(), (limit as i64).into()],
+    );
+    SearchHit::find_by_statement(statement).all(db).await
+}
+
+async fn search_postgres(
+    db: &DatabaseConnection,
+    terms: &[String],
+    limit: u64,
+) -> Result<Vec<SearchHit>, DbErr> {
+    // The expressions match the GIN indexes created by the workflow_search migration.
+    let sql = format!(
+        "SELECT kind, source_id, workflow_id, \
+         CAST(ts_rank(to_tsvector('simple', content), query) AS DOUBLE PRECISION) AS score, \
+         ts_headline('simple', content, query, \
+         'StartSel={HIGHLIGHT_START}, StopSel={HIGHLIGHT_END}, MinWords=8, MaxWords=24') AS snippet \
+         FROM ( \
+             SELECT 'workflow' AS kind, id AS source_id, id AS workflow_id, \
+             display_name || ' ' || COALESCE(description, '') AS content FROM workflow \
+             UNION ALL SELECT 'code', id, workflow_id, code FROM workflow_code \
+             UNION ALL SELECT 'result', id, workflow_id, COALESCE(result, '') FROM workflow_result \
+         ) AS source, plainto_tsquery('simple', $1) AS query \
+         WHERE to_tsvector('simple', content) @@ query \
+         ORDER BY score DESC LIMIT $2"
+    );
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        sql,
+        [terms.join(" ").into(), (limit as i64).into()],
+    );
+    SearchHit::find_by_statement(statement).all(db).await
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ContentRow {
+    kind: String,
+    source_id: String,
+    workflow_id: String,
+    content: String,
+}
+
+async fn search_mysql(
+    db: &DatabaseConnection,
+    terms: &[String],
+    limit: u64,
+) -> Result<Vec<SearchHit>, DbErr> {
+    let condition = vec!["LOWER(content) LIKE ? ESCAPE '!'"; terms.len()].join(" AND ");
+    let sql = format!(
+        "SELECT kind, source_id, workflow_id, content FROM ( \
+             SELECT 'workflow' AS kind, id AS source_id, id AS workflow_id, \
+             CONCAT(display_name, ' ', COALESCE(description, '')) AS content FROM workflow \
+             UNION ALL SELECT 'code', id, workflow_id, code FROM workflow_code \
+             UNION ALL SELECT 'result', id, workflow_id, COALESCE(result, '') FROM workflow_result \
+         ) AS source WHERE {condition}"
+    );
+    let patterns: Vec<Value> = terms
+        .iter()
+        .map(|term| format!("%{}%", escape_like(&term.to_lowercase())).into())
+        .collect();
+    let statement = Statement::from_sql_and_values(DbBackend::MySql, sql, patterns);
+    let rows = ContentRow::find_by_statement(statement).all(db).await?;
+
+    let mut hits: Vec<SearchHit> = rows
+        .into_iter()
+        .map(|row| SearchHit {
+            score: term_frequency(&row.content, terms),
+            snippet: highlight(&row.content, terms),
+            kind: row.kind,
+            source_id: row.source_id,
+            workflow_id: row.workflow_id,
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit as usize);
+    Ok(hits)
+}
+
+/// Escapes `LIKE` wildcards with `!`.
+fn escape_like(term: &str) -> String {
+    term.replace('!', "!!")
+        .replace('%', "!%")
+        .replace('_', "!_")
+}
+
+/// Finds every case-insensitive occurrence of the terms, as non-overlapping byte ranges in
+/// order.
+fn term_matches(content: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    let lower = content.to_lowercase();
+    // Lowercasing can change byte lengths outside ASCII; fall back to exact matching then.
+    let (haystack, case_insensitive) = if lower.len() == content.len() {
+        (lower.as_str(), true)
+    } else {
+        (content, false)
+    };
+    let mut matches: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| {
+            let needle = if case_insensitive {
+                term.to_lowercase()
+            } else {
+                term.clone()
+            };
+            haystack
+                .match_indices(&needle)
+                .map(|(start, found)| (start, start + found.len()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    matches.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(matches.len());
+    for (start, end) in matches {
+        match merged.last_mut() {
+            Some(last) if start < last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn term_frequency(content: &str, terms: &[String]) -> f64 {
+    term_matches(content, terms).len() as f64
+}
+
+/// Cuts an excerpt around the first match and marks every match in it.
+fn highlight(content: &str, terms: &[String]) -> String {
+    let matches = term_matches(content, terms);
+    let first = matches.first().map_or(0, |(start, _)| *start);
+    let start = content[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(index, _)| index);
+    let end = content[first..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT * 2)
+        .map_or(content.len(), |(index, _)| first + index);
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut position = start;
+    for (match_start, match_end) in matches {
+        if match_start < start || match_end > end {
+            continue;
+        }
+        snippet.push_str(&content[position..match_start]);
+        snippet.push_str(HIGHLIGHT_START);
+        snippet.push_str(&content[match_start..match_end]);
+        snippet.push_str(HIGHLIGHT_END);
+        position = match_end;
+    }
+    snippet.push_str(&content[position..end]);
+    if end < content.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+        db.execute_unprepared(
+            "CREATE VIRTUAL TABLE workflow_search USING fts5(
+                kind UNINDEXED, source_id UNINDEXED, workflow_id UNINDEXED, content
+            )",
+        )
+        .await?;
+        for (kind, source_id, workflow_id, content) in [
+            (
+                "workflow",
+                "wf-1",
+                "wf-1",
+                "Daily report Mails the sales report",
+            ),
+            (
+                "code",
+                "code-1",
+                "wf-1",
+                "const report = fetchSales(); mail(report);",
+            ),
+            ("result", "res-1", "wf-2", "sent 3 mails"),
+        ] {
+            db.execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "INSERT INTO workflow_search (kind, source_id, workflow_id, content) \
+                 VALUES (?, ?, ?, ?)",
+                [
+                    kind.into(),
+                    source_id.into(),
+                    workflow_id.into(),
+                    content.into(),
+                ],
+            ))
+            .await?;
+        }
+        Ok(db)
+    }
+
+    #[test]
+    fn fts5_queries_match_terms_literally() {
+        assert_eq!(
+            fts5_match_expression(&terms(&["sales", "NEAR(a", "say\"hi\""])),
+            r#""sales" "NEAR(a" "say""hi""""#
+        );
+    }
+
+    #[test]
+    fn highlight_marks_terms_around_the_first_match() {
+        assert_eq!(
+            highlight("Mails the Sales report", &terms(&["sales", "report"])),
+            "Mails the <mark>Sales</mark> <mark>report</mark>"
+        );
+
+        let long = format!("{} needle {}", "a".repeat(200), "b".repeat(200));
+        let snippet = highlight(&long, &terms(&["needle"]));
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("<mark>needle</mark>"));
+        assert!(snippet.chars().count() < 3 * SNIPPET_CONTEXT + 20);
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_and_highlights_hits() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        let hits = search_workflow_content(&db, "report", 10).await?;
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit.workflow_id == "wf-1"));
+        assert!(hits[0].score >= hits[1].score);
+        assert!(hits[0].snippet.contains("<mark>report</mark>"));
+
+        let hits = search_workflow_content(&db, "sales report", 10).await?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].source_id, "wf-1");
+
+        assert_eq!(search_workflow_content(&db, "report", 1).await?.len(), 1);
+        assert!(search_workflow_content(&db, "  ", 10).await?.is_empty());
+        Ok(())
+    }
+}
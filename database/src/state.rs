@@ -0,0 +1,185 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for persistent workflow state.
+//!
+//! Each workflow owns a namespace of JSON values keyed by string, which the
+//! `state` plugin reads and writes so recurring runs can remember what they
+//! already processed.
+
+use chrono::Utc;
+use entity::entity::workflow_state::{self, ActiveModel, Entity as WorkflowState, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+/// Retrieves a single state value.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow owning the state
+/// * `key` - State key
+///
+/// # Returns
+///
+/// Returns the stored JSON text, or `None` when the key is not set.
+pub async fn get_state(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    key: &str,
+) -> Result<Option<String>, DbErr> {
+    let model = WorkflowState::find_by_id((workflow_id.to_string(), key.to_string()))
+        .one(db)
+        .await?;
+    Ok(model.map(|m| m.value))
+}
+
+/// Inserts or replaces a state value.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow owning the state
+/// * `key` - State key
+/// * `value` - JSON text to store
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the value is stored.
+pub async fn set_state(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    key: &str,
+    value: &str,
+) -> Result<(), DbErr> {
+    let active_model = ActiveModel {
+        workflow_id: Set(workflow_id.to_string()),
+        key: Set(key.to_string()),
+        value: Set(value.to_string()),
+        updated_at: Set(Some(Utc::now())),
+    };
+
+    WorkflowState::insert(active_model)
+        .on_conflict(
+            OnConflict::columns([
+                workflow_state::Column::WorkflowId,
+                workflow_state::Column::Key,
+            ])
+            .update_columns([
+                workflow_state::Column::Value,
+                workflow_state::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Lists every state entry of a workflow ordered by key.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow owning the state
+///
+/// # Returns
+///
+/// Returns the stored entries.
+pub async fn list_state(db: &DatabaseConnection, workflow_id: &str) -> Result<Vec<Model>, DbErr> {
+    WorkflowState::find()
+        .filter(workflow_state::Column::WorkflowId.eq(workflow_id))
+        .order_by_asc(workflow_state::Column::Key)
+        .all(db)
+        .await
+}
+
+/// Deletes one key, or every key of the workflow when `key` is `None`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow owning the state
+/// * `key` - Key to delete; `None` clears the whole namespace
+///
+/// # Returns
+///
+/// Returns the number of deleted entries.
+pub async fn delete_state(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    key: Option<&str>,
+) -> Result<u64, DbErr> {
+    let mut query =
+        WorkflowState::delete_many().filter(workflow_state::Column::WorkflowId.eq(workflow_id));
+    if let Some(key) = key {
+        query = query.filter(workflow_state::Column::Key.eq(key));
+    }
+    let result = query.exec(db).await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_state (
+                workflow_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT,
+                PRIMARY KEY (workflow_id, key)
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_set_overwrites_and_get_reads() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        assert_eq!(get_state(&db, "wf-1", "seen").await?, None);
+        set_state(&db, "wf-1", "seen", "[1]").await?;
+        set_state(&db, "wf-1", "seen", "[1,2]").await?;
+        set_state(&db, "wf-2", "seen", "[]").await?;
+
+        assert_eq!(
+            get_state(&db, "wf-1", "seen").await?.as_deref(),
+            Some("[1,2]")
+        );
+        assert_eq!(get_state(&db, "wf-2", "seen").await?.as_deref(), Some("[]"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete_state() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        set_state(&db, "wf-1", "b", "2").await?;
+        set_state(&db, "wf-1", "a", "1").await?;
+        set_state(&db, "wf-2", "a", "1").await?;
+
+        let keys: Vec<_> = list_state(&db, "wf-1")
+            .await?
+            .into_iter()
+            .map(|m| m.key)
+            .collect();
+        assert_eq!(keys, vec!["a", "b"]);
+
+        assert_eq!(delete_state(&db, "wf-1", Some("a")).await?, 1);
+        assert_eq!(delete_state(&db, "wf-1", None).await?, 1);
+        assert!(list_state(&db, "wf-1").await?.is_empty());
+        assert_eq!(list_state(&db, "wf-2").await?.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! CRUD operations for workflow event triggers.
+//!
+//! A trigger binds a workflow to an event source such as a file change, a
+//! webhook or an active-window change. `kind` names the source and `config`
+//! holds its JSON settings; the server validates both and runs the listeners,
+//! so this layer stores them as plain text.
+
+use chrono::Utc;
+use entity::entity::workflow_trigger::{self, ActiveModel, Entity as WorkflowTrigger, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+/// Creates a new, enabled trigger for a workflow.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - Workflow to run
+/// * `kind` - Event source, e.g. `file`
+/// * `config` - JSON settings of the event source
+///
+/// # Returns
+///
+/// Returns the created `Model` on success, or a database error.
+pub async fn create_trigger(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    kind: &str,
+    config: &str,
+) -> Result<Model, DbErr> {
+    let active_model = ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        workflow_id: Set(workflow_id.to_string()),
+        kind: Set(kind.to_string()),
+        config: Set(config.to_string()),
+        enabled: Set(true),
+        created_at: Set(Some(Utc::now())),
+    };
+
+    active_model.insert(db).await
+}
+
+/// Retrieves a trigger by its ID.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `trigger_id` - The unique identifier of the trigger
+///
+/// # Returns
+///
+/// Returns `Some(Model)` if found, `None` otherwise.
+pub async fn get_trigger(
+    db: &DatabaseConnection,
+    trigger_id: &str,
+) -> Result<Option<Model>, DbErr> {
+    WorkflowTrigger::find_by_id(trigger_id.to_string())
+        .one(db)
+        .await
+}
+
+/// Lists triggers ordered by creation time.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `workflow_id` - When set, only triggers of this workflow are returned
+///
+/// # Returns
+///
+/// Returns a vector of trigger models.
+pub async fn list_triggers(
+    db: &DatabaseConnection,
+    workflow_id: Option<&str>,
+) -> Result<Vec<Model>, DbErr> {
+    let mut query = WorkflowTrigger::find().order_by_asc(workflow_trigger::Column::CreatedAt);
+    if let Some(workflow_id) = workflow_id {
+        query = query.filter(workflow_trigger::Column::WorkflowId.eq(workflow_id));
+    }
+    query.all(db).await
+}
+
+/// Lists the enabled triggers, optionally of a single kind.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `kind` - When set, only triggers of this kind are returned
+///
+/// # Returns
+///
+/// Returns the enabled triggers ordered by creation time.
+pub async fn list_enabled_triggers(
+    db: &DatabaseConnection,
+    kind: Option<&str>,
+) -> Result<Vec<Model>, DbErr> {
+    let mut query = WorkflowTrigger::find()
+        .filter(workflow_trigger::Column::Enabled.eq(true))
+        .order_by_asc(workflow_trigger::Column::CreatedAt);
+    if let Some(kind) = kind {
+        query = query.filter(workflow_trigger::Column::Kind.eq(kind));
+    }
+    query.all(db).await
+}
+
+/// Enables or disables a trigger.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `trigger_id` - The unique identifier of the trigger
+/// * `enabled` - New state of the trigger
+///
+/// # Returns
+///
+/// Returns the updated model or an error if the trigger was not found.
+pub async fn set_trigger_enabled(
+    db: &DatabaseConnection,
+    trigger_id: &str,
+    enabled: bool,
+) -> Result<Model, DbErr> {
+    let existing = WorkflowTrigger::find_by_id(trigger_id.to_string())
+        .one(db)
+        .await?;
+
+    match existing {
+        Some(model) => {
+            let mut active_model: ActiveModel = model.into();
+            active_model.enabled = Set(enabled);
+            active_model.update(db).await
+        }
+        None => Err(DbErr::RecordNotFound(format!(
+            "Workflow trigger not found: {trigger_id}"
+        ))),
+    }
+}
+
+/// Deletes a trigger.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `trigger_id` - The unique identifier of the trigger to delete
+///
+/// # Returns
+///
+/// Returns the number of deleted records (0 or 1).
+pub async fn delete_trigger(db: &DatabaseConnection, trigger_id: &str) -> Result<u64, DbErr> {
+    let result = WorkflowTrigger::delete_by_id(trigger_id.to_string())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE workflow_trigger (
+                id TEXT NOT NULL PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                config TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                created_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_triggers() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let file = create_trigger(&db, "wf-1", "file", r#"{"path":"/tmp"}"#).await?;
+        create_trigger(&db, "wf-2", "window", "{}").await?;
+
+        let fetched = get_trigger(&db, &file.id).await?.expect("trigger");
+        assert_eq!(fetched.kind, "file");
+        assert!(fetched.enabled);
+
+        assert_eq!(list_triggers(&db, None).await?.len(), 2);
+        assert_eq!(list_triggers(&db, Some("wf-1")).await?.len(), 1);
+        assert_eq!(list_enabled_triggers(&db, Some("window")).await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_disable_and_delete_trigger() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let created = create_trigger(&db, "wf-1", "file", "{}").await?;
+
+        let disabled = set_trigger_enabled(&db, &created.id, false).await?;
+        assert!(!disabled.enabled);
+        assert!(list_enabled_triggers(&db, None).await?.is_empty());
+        assert!(set_trigger_enabled(&db, "missing", true).await.is_err());
+
+        assert_eq!(delete_trigger(&db, &created.id).await?, 1);
+        assert_eq!(delete_trigger(&db, &created.id).await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Users and the data they own.
+//!
+//! A user is identified by the subject its requests authenticate as. Workflows and
+//! schedules record their owner in `owner_id`. Rows without an owner were created before
+//! users existed or by anonymous callers; they are shared with every caller, but only
+//! admins may change them (see [`is_changeable_by`]).
+//!
+//! Queries made for a caller pass its owner: `Some(user_id)` sees the user's rows and the
+//! shared ones, `None` (anonymous callers and the command line) sees every row.
+
+use chrono::Utc;
+use entity::entity::user_account::{self, Entity as UserAccount, Model};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryOrder};
+
+/// Records that a user was seen, creating the user on first sight.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `user_id` - Subject the user authenticated as
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the user is stored, or a database error.
+pub async fn touch_user(db: &DatabaseConnection, user_id: &str) -> Result<(), DbErr> {
+    let now = Utc::now();
+    let active_model = user_account::ActiveModel {
+        id: Set(user_id.to_string()),
+        created_at: Set(Some(now)),
+        last_seen_at: Set(Some(now)),
+    };
+    UserAccount::insert(active_model)
+        .on_conflict(
+            OnConflict::column(user_account::Column::Id)
+                .update_column(user_account::Column::LastSeenAt)
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// Retrieves a user by the subject it authenticates as.
+pub async fn get_user(db: &DatabaseConnection, user_id: &str) -> Result<Option<Model>, DbErr> {
+    UserAccount::find_by_id(user_id.to_string()).one(db).await
+}
+
+/// Lists every user, ordered by ID.
+pub async fn list_users(db: &DatabaseConnection) -> Result<Vec<Model>, DbErr> {
+    UserAccount::find()
+        .order_by_asc(user_account::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Restricts a query to the rows `owner` may see.
+///
+/// # Arguments
+///
+/// * `owner_column` - The `owner_id` column of the queried table
+/// * `owner` - The caller's user ID, or `None` for a caller that sees every row
+pub fn visible_to<C: ColumnTrait>(owner_column: C, owner: Option<&str>) -> Condition {
+    match owner {
+        Some(owner) => Condition::any()
+            .add(owner_column.eq(owner))
+            .add(owner_column.is_null()),
+        None => Condition::all(),
+    }
+}
+
+/// Whether a row owned by `row_owner` is visible to `owner`; see [`visible_to`].
+pub fn is_visible_to(row_owner: Option<&str>, owner: Option<&str>) -> bool {
+    match (row_owner, owner) {
+        (Some(row_owner), Some(owner)) => row_owner == owner,
+        _ => true,
+    }
+}
+
+/// Whether `owner` may change, run or delete a row owned by `row_owner`.
+///
+/// Users change their own rows. Shared rows are read-only for them unless
+/// `may_change_shared` is set, which callers set for admins. A caller without an owner
+/// changes every row, like it sees every row.
+pub fn is_changeable_by(
+    row_owner: Option<&str>,
+    owner: Option<&str>,
+    may_change_shared: bool,
+) -> bool {
+    match (row_owner, owner) {
+        (Some(row_owner), Some(owner)) => row_owner == owner,
+        (None, Some(_)) => may_change_shared,
+        (_, None) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::entity::workflow;
+    use sea_orm::{ConnectionTrait, DbBackend, QueryFilter, QueryTrait, Statement};
+
+    async fn setup_db() -> Result<DatabaseConnection, DbErr> {
+        let state = crate::global_state_for_tests!();
+        let db = state.get_db_connection().await?;
+
+        let sql = r#"
+            CREATE TABLE user_account (
+                id TEXT NOT NULL PRIMARY KEY,
+                created_at TEXT,
+                last_seen_at TEXT
+            )
+        "#;
+        db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+            .await?;
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_touch_user_creates_then_updates() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        touch_user(&db, "alice").await?;
+        let created = get_user(&db, "alice").await?.unwrap();
+
+        touch_user(&db, "alice").await?;
+        touch_user(&db, "bob").await?;
+        let seen = get_user(&db, "alice").await?.unwrap();
+        assert_eq!(seen.created_at, created.created_at);
+        assert!(seen.last_seen_at >= created.last_seen_at);
+
+        let users: Vec<String> = list_users(&db).await?.into_iter().map(|u| u.id).collect();
+        assert_eq!(users, vec!["alice", "bob"]);
+        Ok(())
+    }
+
+    #[test]
+    fn owners_see_their_rows_and_shared_rows() {
+        let sql = workflow::Entity::find()
+            .filter(visible_to(workflow::Column::OwnerId, Some("alice")))
+            .build(DbBackend::Sqlite)
+            .to_string();
+        assert!(
+            sql.contains(r#""workflow"."owner_id" = 'alice' OR "workflow"."owner_id" IS NULL"#),
+            "{sql}"
+        );
+        let unrestricted = workflow::Entity::find()
+            .filter(visible_to(workflow::Column::OwnerId, None))
+            .build(DbBackend::Sqlite)
+            .to_string();
+        assert!(!unrestricted.contains("WHERE"), "{unrestricted}");
+
+        assert!(is_visible_to(None, Some("alice")));
+        assert!(is_visible_to(Some("alice"), Some("alice")));
+        assert!(!is_visible_to(Some("bob"), Some("alice")));
+        assert!(is_visible_to(Some("bob"), None));
+    }
+
+    #[test]
+    fn shared_rows_are_changed_only_by_admins() {
+        assert!(is_changeable_by(Some("alice"), Some("alice"), false));
+        assert!(!is_changeable_by(Some("bob"), Some("alice"), true));
+        assert!(!is_changeable_by(None, Some("alice"), false));
+        assert!(is_changeable_by(None, Some("alice"), true));
+        assert!(is_changeable_by(None, None, false));
+        assert!(is_changeable_by(Some("bob"), None, false));
+    }
+}
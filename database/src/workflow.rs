@@ -7,6 +7,9 @@ pub mod workflow_code_crud;
 pub mod workflow_crud;
 pub mod workflow_result_crud;
 
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
 use entity::convert::{
     proto_allowed_permissions_to_entities, proto_string_to_option, proto_timestamp_to_datetime,
     proto_to_plugin_function, proto_to_plugin_package, proto_to_workflow_code,
@@ -18,15 +21,20 @@ use entity::entity::{
     workflow_code_allowed_permission, workflow_code_plugin_function, workflow_code_plugin_package,
     workflow_result,
 };
-use sapphillon_core::proto::sapphillon::v1::{Workflow, WorkflowCode};
+use sapphillon_core::proto::sapphillon::v1::{Workflow, WorkflowCode, WorkflowResult};
+use sea_orm::sea_query::Expr;
 use sea_orm::{
     ActiveModelTrait,
     ActiveValue::{NotSet, Set},
-    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
 };
 
 use uuid::Uuid;
 
+/// Message prefix of the error returned when a workflow changed since the caller read it.
+const VERSION_CONFLICT: &str = "workflow version conflict";
+
 pub async fn create_workflow_code(
     db: &DatabaseConnection,
     code: String,
@@ -36,12 +44,19 @@ pub async fn create_workflow_code(
 ) -> Result<WorkflowCode, DbErr> {
     // Build an entity model and delegate insertion to the CRUD helper.
     // Note: workflow IDs are stored as strings in the entity model.
-    // Start code_revision at 1 and set a
+    // The code becomes the workflow's next revision, starting at 1, with a
     // default language of 0 (WORKFLOW_LANGUAGE_UNSPECIFIED).
+    let latest_revision = workflow_code::Entity::find()
+        .filter(workflow_code::Column::WorkflowId.eq(workflow_id.clone()))
+        .order_by_desc(workflow_code::Column::CodeRevision)
+        .one(db)
+        .await?
+        .map(|code| code.code_revision)
+        .unwrap_or(0);
     let wc = entity::entity::workflow_code::Model {
         id: Uuid::new_v4().to_string(),
         workflow_id,
-        code_revision: 1,
+        code_revision: latest_revision + 1,
         code,
         language: 0,
         created_at: None,
@@ -110,6 +125,8 @@ pub async fn create_workflow(
         workflow_language,
         created_at: Some(chrono::Utc::now()),
         updated_at: Some(chrono::Utc::now()),
+        version: 1,
+        owner_id: None,
     };
 
     workflow_crud::create_workflow(db, wm.clone()).await?;
@@ -139,6 +156,29 @@ pub async fn create_workflow(
     Ok(proto)
 }
 
+/// Finds the workflow whose code contains a revision identical to `code`.
+///
+/// # Arguments
+///
+/// * `db` - The database connection to query.
+/// * `code` - The workflow code to look for.
+///
+/// # Returns
+///
+/// Returns the ID of the workflow with the most recent matching revision, `None` when no
+/// revision matches, or a [`DbErr`] on failure.
+pub async fn find_workflow_id_by_code(
+    db: &DatabaseConnection,
+    code: &str,
+) -> Result<Option<String>, DbErr> {
+    let found = workflow_code::Entity::find()
+        .filter(workflow_code::Column::Code.eq(code))
+        .order_by_desc(workflow_code::Column::CreatedAt)
+        .one(db)
+        .await?;
+    Ok(found.map(|code| code.workflow_id))
+}
+
 pub async fn get_workflow_by_id(
     db: &DatabaseConnection,
     workflow_id: &str,
@@ -301,10 +341,145 @@ pub async fn get_workflow_by_id(
 /// The implementation performs simple delete-and-replace synchronization for relation tables.
 /// Callers should ensure any required rows (e.g. plugin packages/functions) referenced by the
 /// proto are present or included in the payload.
+///
+/// Workflow-level results (`Workflow.workflow_results`) are added or updated but never
+/// removed. As they do not name their code revision, a stored result keeps its revision and
+/// a new one is attached to the newest revision created before it ran.
+///
+/// The write runs in a transaction and increments the workflow's version.
 pub async fn update_workflow_from_proto(
     db: &DatabaseConnection,
     proto: &Workflow,
 ) -> Result<Workflow, DbErr> {
+    update_workflow_from_proto_at_version(db, proto, None).await
+}
+
+/// Like [`update_workflow_from_proto`], but only writes when the stored workflow is still at
+/// `expected_version`, so updates made from a stale copy are rejected.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `proto` - The workflow to store
+/// * `expected_version` - Version the caller read the workflow at; `None` skips the check
+///
+/// # Returns
+///
+/// Returns the stored workflow, or an error for which [`is_version_conflict`] is `true` when
+/// the workflow changed since `expected_version`.
+pub async fn update_workflow_from_proto_at_version(
+    db: &DatabaseConnection,
+    proto: &Workflow,
+    expected_version: Option<i32>,
+) -> Result<Workflow, DbErr> {
+    store_workflow_from_proto(db, proto, expected_version, None).await
+}
+
+/// Like [`update_workflow_from_proto`], but a workflow that does not exist yet is created
+/// as owned by `owner_id`. The owner of an existing workflow is left unchanged.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `proto` - The workflow to store
+/// * `owner_id` - User that owns a new workflow; `None` shares it with every user
+///
+/// # Returns
+///
+/// Returns the stored workflow, or a database error.
+pub async fn create_workflow_from_proto(
+    db: &DatabaseConnection,
+    proto: &Workflow,
+    owner_id: Option<&str>,
+) -> Result<Workflow, DbErr> {
+    store_workflow_from_proto(db, proto, None, owner_id).await
+}
+
+async fn store_workflow_from_proto(
+    db: &DatabaseConnection,
+    proto: &Workflow,
+    expected_version: Option<i32>,
+    owner_id: Option<&str>,
+) -> Result<Workflow, DbErr> {
+    let workflow_id = proto.id.clone();
+    let (conn, proto, owner_id) = (db.clone(), proto.clone(), owner_id.map(str::to_string));
+    crate::writer::serialize_write(async move {
+        let txn = conn.begin().await?;
+        write_workflow_from_proto(&txn, &proto, expected_version, owner_id).await?;
+        txn.commit().await
+    })
+    .await?;
+    get_workflow_by_id(db, &workflow_id).await
+}
+
+/// Returns the version of a stored workflow, which increases with every write.
+///
+/// # Returns
+///
+/// Returns the version, or [`DbErr::RecordNotFound`] if the workflow does not exist.
+pub async fn get_workflow_version(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+) -> Result<i32, DbErr> {
+    workflow::Entity::find_by_id(workflow_id.to_string())
+        .one(db)
+        .await?
+        .map(|workflow| workflow.version)
+        .ok_or_else(|| DbErr::RecordNotFound(format!("workflow not found: {workflow_id}")))
+}
+
+/// Returns the user that owns a stored workflow.
+///
+/// # Returns
+///
+/// Returns the owner, `None` for a workflow shared with every user, or
+/// [`DbErr::RecordNotFound`] if the workflow does not exist.
+pub async fn get_workflow_owner(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+) -> Result<Option<String>, DbErr> {
+    workflow::Entity::find_by_id(workflow_id.to_string())
+        .one(db)
+        .await?
+        .map(|workflow| workflow.owner_id)
+        .ok_or_else(|| DbErr::RecordNotFound(format!("workflow not found: {workflow_id}")))
+}
+
+/// Returns the workflow a stored workflow code belongs to.
+///
+/// # Returns
+///
+/// Returns the workflow ID, or [`DbErr::RecordNotFound`] if the code does not exist.
+pub async fn get_workflow_code_workflow_id(
+    db: &DatabaseConnection,
+    workflow_code_id: &str,
+) -> Result<String, DbErr> {
+    workflow_code::Entity::find_by_id(workflow_code_id.to_string())
+        .one(db)
+        .await?
+        .map(|code| code.workflow_id)
+        .ok_or_else(|| {
+            DbErr::RecordNotFound(format!("workflow code not found: {workflow_code_id}"))
+        })
+}
+
+/// Whether `err` reports a write rejected because the workflow changed since it was read.
+pub fn is_version_conflict(err: &DbErr) -> bool {
+    matches!(err, DbErr::Custom(msg) if msg.starts_with(VERSION_CONFLICT))
+}
+
+fn version_conflict(workflow_id: &str, expected_version: i32) -> DbErr {
+    DbErr::Custom(format!(
+        "{VERSION_CONFLICT}: workflow {workflow_id} is no longer at version {expected_version}"
+    ))
+}
+
+async fn write_workflow_from_proto<C: ConnectionTrait>(
+    db: &C,
+    proto: &Workflow,
+    expected_version: Option<i32>,
+    owner_id: Option<String>,
+) -> Result<(), DbErr> {
     let description = proto_string_to_option(&proto.description);
     let created_at = proto
         .created_at
@@ -322,6 +497,8 @@ pub async fn update_workflow_from_proto(
         workflow_language: proto.workflow_language,
         created_at,
         updated_at,
+        version: 1,
+        owner_id,
     };
 
     // Upsert the workflow itself.
@@ -329,18 +506,52 @@ pub async fn update_workflow_from_proto(
         .one(db)
         .await?
     {
-        let mut active: workflow::ActiveModel = existing.into();
-        active.display_name = Set(workflow_model.display_name.clone());
-        active.description = Set(workflow_model.description.clone());
-        active.workflow_language = Set(workflow_model.workflow_language);
-        active.created_at = Set(workflow_model.created_at);
-        active.updated_at = Set(workflow_model.updated_at);
-        active.update(db).await?;
+        if let Some(expected) = expected_version.filter(|expected| *expected != existing.version) {
+            return Err(version_conflict(&existing.id, expected));
+        }
+        // Matching the version read above detects writes committed in between.
+        let updated = workflow::Entity::update_many()
+            .col_expr(
+                workflow::Column::DisplayName,
+                Expr::value(workflow_model.display_name.clone()),
+            )
+            .col_expr(
+                workflow::Column::Description,
+                Expr::value(workflow_model.description.clone()),
+            )
+            .col_expr(
+                workflow::Column::WorkflowLanguage,
+                Expr::value(workflow_model.workflow_language),
+            )
+            .col_expr(
+                workflow::Column::CreatedAt,
+                Expr::value(workflow_model.created_at),
+            )
+            .col_expr(
+                workflow::Column::UpdatedAt,
+                Expr::value(workflow_model.updated_at),
+            )
+            .col_expr(
+                workflow::Column::Version,
+                Expr::col(workflow::Column::Version).add(1),
+            )
+            .filter(workflow::Column::Id.eq(existing.id.clone()))
+            .filter(workflow::Column::Version.eq(existing.version))
+            .exec(db)
+            .await?;
+        if updated.rows_affected == 0 {
+            return Err(version_conflict(&existing.id, existing.version));
+        }
     } else {
         let active: workflow::ActiveModel = workflow_model.clone().into();
         active.insert(db).await?;
     }
 
+    // Read the code revisions of stored workflow-level results before the per-code
+    // refresh below deletes them.
+    let stored_result_codes =
+        stored_result_code_ids(db, &workflow_model.id, &proto.workflow_results).await?;
+
     for code_proto in &proto.workflow_code {
         let code_entity = proto_to_workflow_code(code_proto, workflow_model.id.clone());
 
@@ -525,26 +736,207 @@ pub async fn update_workflow_from_proto(
                 code_entity.id.clone(),
             );
 
-            let active = workflow_result::ActiveModel {
-                id: Set(result_model.id),
-                workflow_id: Set(result_model.workflow_id),
-                workflow_code_id: Set(result_model.workflow_code_id),
-                display_name: Set(result_model.display_name),
-                description: Set(result_model.description),
-                result: Set(result_model.result),
-                ran_at: Set(result_model.ran_at),
-                result_type: Set(result_model.result_type),
-                exit_code: Set(result_model.exit_code),
-                workflow_result_revision: Set(result_model.workflow_result_revision),
-            };
+            result_active_model(result_model).insert(db).await?;
+        }
+    }
+
+    // Workflow-level results, such as imported or replayed runs, do not name the code
+    // revision they ran. Results also listed under a code revision were stored above; the
+    // others keep the revision they are stored with, or get the one current when they ran.
+    let listed_under_code: HashSet<&str> = proto
+        .workflow_code
+        .iter()
+        .flat_map(|code| code.result.iter().map(|result| result.id.as_str()))
+        .collect();
+    let unlisted: Vec<&WorkflowResult> = proto
+        .workflow_results
+        .iter()
+        .filter(|result| !listed_under_code.contains(result.id.as_str()))
+        .collect();
+    if unlisted.is_empty() {
+        return Ok(());
+    }
+
+    let codes = workflow_code::Entity::find()
+        .filter(workflow_code::Column::WorkflowId.eq(workflow_model.id.clone()))
+        .all(db)
+        .await?;
+    for result_proto in unlisted {
+        let code_id = match stored_result_codes.get(&result_proto.id) {
+            Some(code_id) => code_id.clone(),
+            None => {
+                let ran_at = result_proto
+                    .ran_at
+                    .as_ref()
+                    .and_then(proto_timestamp_to_datetime);
+                code_for_result(&codes, ran_at)
+                    .ok_or_else(|| {
+                        DbErr::Custom(format!(
+                            "workflow {} has no code for result {}",
+                            workflow_model.id, result_proto.id
+                        ))
+                    })?
+                    .to_string()
+            }
+        };
+        let result_model =
+            proto_to_workflow_result(result_proto, workflow_model.id.clone(), code_id);
+        let exists = workflow_result::Entity::find_by_id(result_model.id.clone())
+            .one(db)
+            .await?
+            .is_some();
+        let active = result_active_model(result_model);
+        if exists {
+            active.update(db).await?;
+        } else {
             active.insert(db).await?;
         }
     }
 
-    // Note: Top-level workflow results (Workflow.workflow_results) are not synchronized here,
-    // because the proto message omits the workflow_code_id required by the schema.
+    Ok(())
+}
+
+/// Maps the workflow-level results that are already stored to their code revision ids.
+///
+/// # Returns
+///
+/// Returns the code revision id of each stored result, or an error when a result is stored
+/// for a different workflow.
+async fn stored_result_code_ids<C: ConnectionTrait>(
+    db: &C,
+    workflow_id: &str,
+    results: &[WorkflowResult],
+) -> Result<HashMap<String, String>, DbErr> {
+    if results.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let ids: Vec<String> = results.iter().map(|result| result.id.clone()).collect();
+    let stored = workflow_result::Entity::find()
+        .filter(workflow_result::Column::Id.is_in(ids))
+        .all(db)
+        .await?;
+    let mut code_ids = HashMap::with_capacity(stored.len());
+    for result in stored {
+        if result.workflow_id != workflow_id {
+            return Err(DbErr::Custom(format!(
+                "workflow result {} belongs to workflow {}",
+                result.id, result.workflow_id
+            )));
+        }
+        code_ids.insert(result.id, result.workflow_code_id);
+    }
+    Ok(code_ids)
+}
+
+/// Picks the code revision a result ran: the newest revision created at or before `ran_at`,
+/// or the newest revision when that cannot be told.
+fn code_for_result(codes: &[workflow_code::Model], ran_at: Option<DateTime<Utc>>) -> Option<&str> {
+    let newest_before_run = ran_at.and_then(|ran_at| {
+        codes
+            .iter()
+            .filter(|code| {
+                code.created_at
+                    .is_some_and(|created_at| created_at <= ran_at)
+            })
+            .max_by_key(|code| code.code_revision)
+    });
+    newest_before_run
+        .or_else(|| codes.iter().max_by_key(|code| code.code_revision))
+        .map(|code| code.id.as_str())
+}
 
-    get_workflow_by_id(db, &workflow_model.id).await
+fn result_active_model(model: workflow_result::Model) -> workflow_result::ActiveModel {
+    workflow_result::ActiveModel {
+        id: Set(model.id),
+        workflow_id: Set(model.workflow_id),
+        workflow_code_id: Set(model.workflow_code_id),
+        display_name: Set(model.display_name),
+        description: Set(model.description),
+        result: Set(model.result),
+        ran_at: Set(model.ran_at),
+        result_type: Set(model.result_type),
+        exit_code: Set(model.exit_code),
+        workflow_result_revision: Set(model.workflow_result_revision),
+    }
+}
+
+/// Deletes a single workflow result.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `result_id` - The identifier of the workflow result to delete
+///
+/// # Returns
+///
+/// Returns `Ok(())` when the result was removed, or [`DbErr::RecordNotFound`] if no result
+/// with the given id exists.
+pub async fn delete_workflow_result_by_id(
+    db: &DatabaseConnection,
+    result_id: &str,
+) -> Result<(), DbErr> {
+    let deleted = workflow_result::Entity::delete_by_id(result_id.to_string())
+        .exec(db)
+        .await?;
+    if deleted.rows_affected == 0 {
+        return Err(DbErr::RecordNotFound(format!(
+            "workflow result not found: {result_id}"
+        )));
+    }
+    Ok(())
+}
+
+/// Deletes every workflow result that ran before the given instant.
+///
+/// Results without a `ran_at` timestamp are kept, since their age is unknown.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `before` - Results with `ran_at` strictly earlier than this instant are removed
+/// * `workflow_id` - When set, only results belonging to this workflow are removed
+///
+/// # Returns
+///
+/// Returns the number of deleted results.
+pub async fn delete_workflow_results_before(
+    db: &DatabaseConnection,
+    before: chrono::DateTime<chrono::Utc>,
+    workflow_id: Option<&str>,
+) -> Result<u64, DbErr> {
+    let mut query =
+        workflow_result::Entity::delete_many().filter(workflow_result::Column::RanAt.lt(before));
+    if let Some(workflow_id) = workflow_id {
+        query = query.filter(workflow_result::Column::WorkflowId.eq(workflow_id.to_string()));
+    }
+    let deleted = query.exec(db).await?;
+    Ok(deleted.rows_affected)
+}
+
+/// Deletes the workflow results with the given ids.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `result_ids` - Identifiers of the results to remove; unknown ids are ignored
+///
+/// # Returns
+///
+/// Returns the number of deleted results.
+pub async fn delete_workflow_results_by_ids(
+    db: &DatabaseConnection,
+    result_ids: &[String],
+) -> Result<u64, DbErr> {
+    let mut deleted = 0;
+    // Bounded batches keep each statement under the backends' bind parameter limits.
+    for batch in result_ids.chunks(500) {
+        deleted += workflow_result::Entity::delete_many()
+            .filter(workflow_result::Column::Id.is_in(batch.iter().cloned()))
+            .exec(db)
+            .await?
+            .rows_affected;
+    }
+    Ok(deleted)
 }
 
 #[cfg(test)]
@@ -566,7 +958,9 @@ mod tests {
                 description TEXT,
                 workflow_language INTEGER NOT NULL,
                 created_at TEXT,
-                updated_at TEXT
+                updated_at TEXT,
+                version INTEGER NOT NULL DEFAULT 1,
+                owner_id TEXT
             )
         "#;
         db.execute(Statement::from_string(
@@ -786,6 +1180,30 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_create_workflow_code_appends_revisions() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+        let wf_id = "wf_rev".to_string();
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!(
+                "INSERT INTO workflow (id, display_name, workflow_language) VALUES ('{wf_id}','WF', 0)"
+            ),
+        ))
+        .await?;
+
+        let first =
+            create_workflow_code(&db, "v1".to_string(), wf_id.clone(), vec![], vec![]).await?;
+        let second =
+            create_workflow_code(&db, "v2".to_string(), wf_id.clone(), vec![], vec![]).await?;
+        assert_eq!(first.code_revision, 1);
+        assert_eq!(second.code_revision, 2);
+
+        assert_eq!(find_workflow_id_by_code(&db, "v1").await?, Some(wf_id));
+        assert_eq!(find_workflow_id_by_code(&db, "v3").await?, None);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_create_workflow_inserts_row_and_returns_proto() -> Result<(), DbErr> {
         let db = setup_db().await?;
@@ -954,4 +1372,217 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_update_workflow_from_proto_checks_versions() -> Result<(), DbErr> {
+        let db = setup_full_db().await?;
+        let mut proto = create_workflow(&db, "Versioned".to_string(), None, 0).await?;
+        assert_eq!(get_workflow_version(&db, &proto.id).await?, 1);
+
+        proto.display_name = "First edit".to_string();
+        update_workflow_from_proto_at_version(&db, &proto, Some(1)).await?;
+        assert_eq!(get_workflow_version(&db, &proto.id).await?, 2);
+
+        proto.display_name = "Stale edit".to_string();
+        let err = update_workflow_from_proto_at_version(&db, &proto, Some(1))
+            .await
+            .expect_err("stale version must be rejected");
+        assert!(is_version_conflict(&err), "{err}");
+        let stored = get_workflow_by_id(&db, &proto.id).await?;
+        assert_eq!(stored.display_name, "First edit");
+
+        update_workflow_from_proto(&db, &proto).await?;
+        assert_eq!(get_workflow_version(&db, &proto.id).await?, 3);
+        assert!(matches!(
+            get_workflow_version(&db, "missing").await,
+            Err(DbErr::RecordNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_workflow_from_proto_records_the_owner() -> Result<(), DbErr> {
+        let db = setup_full_db().await?;
+        let shared = create_workflow(&db, "Shared".to_string(), None, 0).await?;
+        assert_eq!(get_workflow_owner(&db, &shared.id).await?, None);
+
+        let mut owned = Workflow {
+            id: "wf-owned".to_string(),
+            display_name: "Owned".to_string(),
+            ..Default::default()
+        };
+        create_workflow_from_proto(&db, &owned, Some("alice")).await?;
+        assert_eq!(
+            get_workflow_owner(&db, &owned.id).await?.as_deref(),
+            Some("alice")
+        );
+
+        // Updates, including ones made for another owner, keep the owner.
+        owned.display_name = "Renamed".to_string();
+        update_workflow_from_proto(&db, &owned).await?;
+        create_workflow_from_proto(&db, &owned, Some("bob")).await?;
+        assert_eq!(
+            get_workflow_owner(&db, &owned.id).await?.as_deref(),
+            Some("alice")
+        );
+        assert!(matches!(
+            get_workflow_owner(&db, "missing").await,
+            Err(DbErr::RecordNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_workflow_from_proto_stores_workflow_level_results() -> Result<(), DbErr> {
+        use sapphillon_core::proto::google::protobuf::Timestamp;
+        use sapphillon_core::proto::sapphillon::v1::WorkflowCode as ProtoWorkflowCode;
+
+        let db = setup_full_db().await?;
+        let at = |seconds: i64| Some(Timestamp { seconds, nanos: 0 });
+        let code = |id: &str, code_revision: i32, created_at: i64| ProtoWorkflowCode {
+            id: id.to_string(),
+            code_revision,
+            code: format!("// revision {code_revision}"),
+            language: 1,
+            created_at: at(created_at),
+            result: Vec::new(),
+            plugin_packages: Vec::new(),
+            plugin_function_ids: Vec::new(),
+            allowed_permissions: Vec::new(),
+        };
+        let result = |id: &str, ran_at: Option<Timestamp>| WorkflowResult {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            ran_at,
+            workflow_result_revision: 1,
+            ..WorkflowResult::default()
+        };
+
+        let mut workflow = Workflow {
+            id: "wf1".to_string(),
+            display_name: "Imported".to_string(),
+            workflow_language: 1,
+            workflow_code: vec![code("wc1", 1, 1_000), code("wc2", 2, 2_000)],
+            workflow_results: vec![result("imported", at(1_500)), result("undated", None)],
+            ..Workflow::default()
+        };
+        let stored = update_workflow_from_proto(&db, &workflow).await?;
+        assert_eq!(stored.workflow_results.len(), 2);
+
+        let code_of = |id: &'static str| {
+            let db = db.clone();
+            async move {
+                workflow_result::Entity::find_by_id(id.to_string())
+                    .one(&db)
+                    .await
+                    .map(|result| result.expect("result stored").workflow_code_id)
+            }
+        };
+        assert_eq!(code_of("imported").await?, "wc1");
+        assert_eq!(code_of("undated").await?, "wc2");
+
+        // A later write keeps the stored revision even though the per-code refresh
+        // removes the result first.
+        workflow.workflow_results[0].display_name = "Renamed".to_string();
+        workflow.workflow_code.push(code("wc3", 3, 500));
+        update_workflow_from_proto(&db, &workflow).await?;
+        assert_eq!(code_of("imported").await?, "wc1");
+        let renamed = workflow_result::Entity::find_by_id("imported".to_string())
+            .one(&db)
+            .await?
+            .expect("result stored");
+        assert_eq!(renamed.display_name.as_deref(), Some("Renamed"));
+        Ok(())
+    }
+
+    async fn insert_result(
+        db: &DatabaseConnection,
+        id: &str,
+        workflow_id: &str,
+        ran_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), DbErr> {
+        let active = workflow_result::ActiveModel {
+            id: Set(id.to_string()),
+            workflow_id: Set(workflow_id.to_string()),
+            workflow_code_id: Set(format!("{workflow_id}-code")),
+            display_name: Set(None),
+            description: Set(None),
+            result: Set(None),
+            ran_at: Set(ran_at),
+            result_type: Set(0),
+            exit_code: Set(None),
+            workflow_result_revision: Set(1),
+        };
+        active.insert(db).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_workflow_result_by_id() -> Result<(), DbErr> {
+        let db = setup_full_db().await?;
+        insert_result(&db, "res1", "wf1", None).await?;
+
+        delete_workflow_result_by_id(&db, "res1").await?;
+        let remaining = workflow_result::Entity::find_by_id("res1".to_string())
+            .one(&db)
+            .await?;
+        assert!(remaining.is_none());
+
+        let missing = delete_workflow_result_by_id(&db, "res1").await;
+        assert!(matches!(missing, Err(DbErr::RecordNotFound(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_workflow_results_before() -> Result<(), DbErr> {
+        let db = setup_full_db().await?;
+        let cutoff = chrono::Utc::now();
+        let old = cutoff - chrono::Duration::days(30);
+        let recent = cutoff + chrono::Duration::seconds(1);
+
+        insert_result(&db, "old-wf1", "wf1", Some(old)).await?;
+        insert_result(&db, "old-wf2", "wf2", Some(old)).await?;
+        insert_result(&db, "new-wf1", "wf1", Some(recent)).await?;
+        insert_result(&db, "undated", "wf1", None).await?;
+
+        let deleted = delete_workflow_results_before(&db, cutoff, Some("wf1")).await?;
+        assert_eq!(deleted, 1);
+
+        let deleted = delete_workflow_results_before(&db, cutoff, None).await?;
+        assert_eq!(deleted, 1);
+
+        let mut remaining: Vec<String> = workflow_result::Entity::find()
+            .all(&db)
+            .await?
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec!["new-wf1".to_string(), "undated".to_string()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_workflow_results_by_ids() -> Result<(), DbErr> {
+        let db = setup_full_db().await?;
+        insert_result(&db, "res1", "wf1", None).await?;
+        insert_result(&db, "res2", "wf1", None).await?;
+        insert_result(&db, "res3", "wf2", None).await?;
+
+        let ids = ["res1", "res3", "missing"].map(str::to_string);
+        let deleted = delete_workflow_results_by_ids(&db, &ids).await?;
+        assert_eq!(deleted, 2);
+
+        let remaining: Vec<String> = workflow_result::Entity::find()
+            .all(&db)
+            .await?
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(remaining, vec!["res2".to_string()]);
+        Ok(())
+    }
 }
@@ -186,7 +186,9 @@ mod tests {
                 description TEXT,
                 workflow_language INTEGER NOT NULL,
                 created_at TEXT,
-                updated_at TEXT
+                updated_at TEXT,
+                version INTEGER NOT NULL DEFAULT 1,
+                owner_id TEXT
             )
         "#;
         db.execute(Statement::from_string(
@@ -236,6 +238,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let active_wf: entity_wf::ActiveModel = wf.into();
         active_wf.insert(&db).await?;
@@ -274,6 +278,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let active_wf: entity_wf::ActiveModel = wf.into();
         active_wf.insert(&db).await?;
@@ -331,6 +337,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let active_wf: entity_wf::ActiveModel = wf.into();
         active_wf.insert(&db).await?;
@@ -374,6 +382,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let active_wf: entity_wf::ActiveModel = wf.into();
         active_wf.insert(&db).await?;
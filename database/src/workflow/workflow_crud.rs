@@ -64,11 +64,13 @@ pub(crate) async fn update_workflow(
 ) -> Result<(), DbErr> {
     let existing = get_workflow(db, &wf.id).await?;
     if let Some(existing) = existing {
+        let version = existing.version;
         let mut active_model: workflow::ActiveModel = existing.into();
         use sea_orm::ActiveValue::Set;
         active_model.display_name = Set(wf.display_name);
         active_model.description = Set(wf.description);
         active_model.workflow_language = Set(wf.workflow_language);
+        active_model.version = Set(version + 1);
         active_model.update(db).await?;
     }
     Ok(())
@@ -182,7 +184,9 @@ mod tests {
                 description TEXT,
                 workflow_language INTEGER NOT NULL,
                 created_at TEXT,
-                updated_at TEXT
+                updated_at TEXT,
+                version INTEGER NOT NULL DEFAULT 1,
+                owner_id TEXT
             )
         "#;
         db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
@@ -211,6 +215,8 @@ mod tests {
             workflow_language: 1,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
 
         // create should succeed
@@ -238,6 +244,8 @@ mod tests {
             workflow_language: 1,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
 
         create_workflow(&db, w).await?;
@@ -273,6 +281,8 @@ mod tests {
                 workflow_language: 0,
                 created_at: None,
                 updated_at: None,
+                version: 1,
+                owner_id: None,
             };
             create_workflow(&db, w).await?;
         }
@@ -314,6 +324,8 @@ mod tests {
             workflow_language: 2,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         create_workflow(&db, initial).await?;
 
@@ -324,6 +336,8 @@ mod tests {
             workflow_language: 3,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
 
         update_workflow(&db, updated).await?;
@@ -334,6 +348,7 @@ mod tests {
         assert_eq!(found.display_name, "After");
         assert!(found.description.is_none());
         assert_eq!(found.workflow_language, 3);
+        assert_eq!(found.version, 2);
 
         Ok(())
     }
@@ -358,6 +373,8 @@ mod tests {
             workflow_language: 2,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         create_workflow(&db, initial).await?;
 
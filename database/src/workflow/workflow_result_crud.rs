@@ -2,10 +2,32 @@
 // SPDX-FileCopyrightText: 2025 Yuta Takahashi
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
+use crate::user::visible_to;
 use base64::Engine as _;
 use base64::engine::general_purpose;
-use entity::entity::workflow_result;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, QuerySelect};
+use chrono::{DateTime, Utc};
+use entity::entity::workflow_result::Column;
+use entity::entity::{workflow, workflow_result};
+use sea_orm::sea_query::{Alias, Expr, Func};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+};
+
+/// Filters applied by [`list_workflow_results_filtered`]; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct WorkflowResultFilter {
+    /// Only results of this workflow.
+    pub workflow_id: Option<String>,
+    /// Only results that ran at or after this instant.
+    pub ran_after: Option<DateTime<Utc>>,
+    /// Only results that ran strictly before this instant.
+    pub ran_before: Option<DateTime<Utc>>,
+    /// Only results that finished with this exit code.
+    pub exit_code: Option<i32>,
+    /// Only results of the workflows this owner may see; see [`visible_to`].
+    pub owner_id: Option<String>,
+}
 
 /// Inserts a new workflow result into the database.
 ///
@@ -33,8 +55,7 @@ pub(crate) async fn create_workflow_result(
 ///
 /// # Returns
 /// The matching workflow result model if found, or `None` when absent.
-#[allow(dead_code)]
-pub(crate) async fn get_workflow_result(
+pub async fn get_workflow_result(
     db: &DatabaseConnection,
     id: &str,
 ) -> Result<Option<workflow_result::Model>, DbErr> {
@@ -90,7 +111,123 @@ pub(crate) async fn list_workflow_results(
     next_page_token: Option<String>,
     page_size: Option<u32>,
 ) -> Result<(Vec<workflow_result::Model>, String), DbErr> {
-    let offset: u64 = match next_page_token {
+    let offset = decode_page_token(next_page_token);
+    let limit = page_limit(page_size);
+
+    let query_limit = limit.saturating_add(1);
+    let items = workflow_result::Entity::find()
+        .offset(Some(offset))
+        .limit(Some(query_limit))
+        .all(db)
+        .await?;
+
+    Ok(paginate(items, offset, limit))
+}
+
+/// Lists workflow results matching a filter, newest first, using offset-based pagination.
+///
+/// # Arguments
+/// * `db` - The database connection used for the query.
+/// * `filter` - Workflow, time-range and exit-code constraints.
+/// * `next_page_token` - The token indicating where to resume the listing.
+/// * `page_size` - The maximum number of items to retrieve per page.
+///
+/// # Returns
+/// A tuple containing the fetched workflow results and the token for the next page.
+pub async fn list_workflow_results_filtered(
+    db: &DatabaseConnection,
+    filter: &WorkflowResultFilter,
+    next_page_token: Option<String>,
+    page_size: Option<u32>,
+) -> Result<(Vec<workflow_result::Model>, String), DbErr> {
+    let offset = decode_page_token(next_page_token);
+    let limit = page_limit(page_size);
+
+    let mut query = workflow_result::Entity::find();
+    if let Some(workflow_id) = &filter.workflow_id {
+        query = query.filter(Column::WorkflowId.eq(workflow_id.clone()));
+    }
+    if let Some(ran_after) = filter.ran_after {
+        query = query.filter(Column::RanAt.gte(ran_after));
+    }
+    if let Some(ran_before) = filter.ran_before {
+        query = query.filter(Column::RanAt.lt(ran_before));
+    }
+    if let Some(exit_code) = filter.exit_code {
+        query = query.filter(Column::ExitCode.eq(exit_code));
+    }
+    if let Some(owner_id) = &filter.owner_id {
+        let visible = workflow::Entity::find()
+            .select_only()
+            .column(workflow::Column::Id)
+            .filter(visible_to(workflow::Column::OwnerId, Some(owner_id)))
+            .into_query();
+        query = query.filter(Column::WorkflowId.in_subquery(visible));
+    }
+
+    let items = query
+        .order_by_desc(Column::RanAt)
+        .order_by_asc(Column::Id)
+        .offset(Some(offset))
+        .limit(Some(limit.saturating_add(1)))
+        .all(db)
+        .await?;
+
+    Ok(paginate(items, offset, limit))
+}
+
+/// The bookkeeping fields of a stored workflow result, without its output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkflowResultSize {
+    pub id: String,
+    pub workflow_id: String,
+    pub ran_at: Option<DateTime<Utc>>,
+    /// Length of the stored output; `0` when there is none.
+    pub size: u64,
+}
+
+/// Lists every workflow result with the length of its output, without loading the output.
+///
+/// # Arguments
+/// * `db` - The database connection used for the query.
+///
+/// # Returns
+/// The size of every stored workflow result, in no particular order.
+pub async fn list_workflow_result_sizes(
+    db: &DatabaseConnection,
+) -> Result<Vec<WorkflowResultSize>, DbErr> {
+    // LENGTH returns a 32-bit integer on Postgres; cast it so every backend decodes an i64.
+    let length = Expr::expr(Func::cust(Alias::new("LENGTH")).arg(Expr::col(Column::Result)));
+    let length = match db.get_database_backend() {
+        DbBackend::MySql => length.cast_as(Alias::new("SIGNED")),
+        DbBackend::Postgres | DbBackend::Sqlite => length.cast_as(Alias::new("BIGINT")),
+    };
+
+    let rows: Vec<(String, String, Option<DateTime<Utc>>, Option<i64>)> =
+        workflow_result::Entity::find()
+            .select_only()
+            .column(Column::Id)
+            .column(Column::WorkflowId)
+            .column(Column::RanAt)
+            .column_as(length, "size")
+            .into_tuple()
+            .all(db)
+            .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, workflow_id, ran_at, size)| WorkflowResultSize {
+            id,
+            workflow_id,
+            ran_at,
+            size: size.unwrap_or(0).max(0) as u64,
+        })
+        .collect())
+}
+
+/// Decodes a page token into a row offset; invalid or missing tokens start from the beginning.
+fn decode_page_token(next_page_token: Option<String>) -> u64 {
+    match next_page_token {
         Some(token) => match general_purpose::STANDARD.decode(token) {
             Ok(bytes) => {
                 if bytes.len() == 8 {
@@ -104,20 +241,22 @@ pub(crate) async fn list_workflow_results(
             Err(_) => 0u64,
         },
         None => 0u64,
-    };
+    }
+}
 
-    let limit = match page_size {
+fn page_limit(page_size: Option<u32>) -> u64 {
+    match page_size {
         Some(0) | None => 100u64,
         Some(sz) => sz as u64,
-    };
-
-    let query_limit = limit.saturating_add(1);
-    let mut items = workflow_result::Entity::find()
-        .offset(Some(offset))
-        .limit(Some(query_limit))
-        .all(db)
-        .await?;
+    }
+}
 
+/// Trims the one extra row fetched to detect a following page and builds its token.
+fn paginate(
+    mut items: Vec<workflow_result::Model>,
+    offset: u64,
+    limit: u64,
+) -> (Vec<workflow_result::Model>, String) {
     let has_next = (items.len() as u64) > limit;
     if has_next {
         items.truncate(limit as usize);
@@ -131,7 +270,7 @@ pub(crate) async fn list_workflow_results(
         String::new()
     };
 
-    Ok((items, next_token))
+    (items, next_token)
 }
 
 /// Removes a workflow result from the database if it exists.
@@ -173,7 +312,9 @@ mod tests {
                 description TEXT,
                 workflow_language INTEGER NOT NULL,
                 created_at TEXT,
-                updated_at TEXT
+                updated_at TEXT,
+                version INTEGER NOT NULL DEFAULT 1,
+                owner_id TEXT
             )
         "#;
         db.execute(Statement::from_string(
@@ -236,6 +377,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let wc = entity_wc::Model {
             id: "wc1".to_string(),
@@ -280,6 +423,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let wc = entity_wc::Model {
             id: "wc1".to_string(),
@@ -343,6 +488,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let wc = entity_wc::Model {
             id: "wc1".to_string(),
@@ -393,6 +540,8 @@ mod tests {
             workflow_language: 0,
             created_at: None,
             updated_at: None,
+            version: 1,
+            owner_id: None,
         };
         let active_wf: entity_wf::ActiveModel = wf.into();
         active_wf.insert(&db).await?;
@@ -440,4 +589,156 @@ mod tests {
         assert_eq!(collected.len(), 5);
         Ok(())
     }
+
+    #[tokio::test]
+    /// Checks that filters narrow the listing and results come back newest first.
+    async fn test_list_workflow_results_filtered() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        for (id, owner_id) in [("wf1", None), ("wf2", Some("bob"))] {
+            let wf = entity_wf::Model {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                description: None,
+                workflow_language: 0,
+                created_at: None,
+                updated_at: None,
+                version: 1,
+                owner_id: owner_id.map(str::to_string),
+            };
+            let active_wf: entity_wf::ActiveModel = wf.into();
+            active_wf.insert(&db).await?;
+            let wc = entity_wc::Model {
+                id: format!("{id}-code"),
+                workflow_id: id.to_string(),
+                code_revision: 1,
+                code: "c".to_string(),
+                language: 0,
+                created_at: None,
+            };
+            let active_wc: entity_wc::ActiveModel = wc.into();
+            active_wc.insert(&db).await?;
+        }
+
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2025, 1, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        for (id, workflow_id, ran_on, exit_code) in [
+            ("r1", "wf1", 1, 0),
+            ("r2", "wf1", 2, 1),
+            ("r3", "wf1", 3, 0),
+            ("r4", "wf2", 2, 0),
+        ] {
+            let r = workflow_result::Model {
+                id: id.to_string(),
+                workflow_id: workflow_id.to_string(),
+                workflow_code_id: format!("{workflow_id}-code"),
+                display_name: None,
+                description: None,
+                result: None,
+                ran_at: Some(day(ran_on)),
+                result_type: 0,
+                exit_code: Some(exit_code),
+                workflow_result_revision: 1,
+            };
+            create_workflow_result(&db, r).await?;
+        }
+
+        let ids = |items: Vec<workflow_result::Model>| {
+            items.into_iter().map(|r| r.id).collect::<Vec<_>>()
+        };
+
+        let filter = WorkflowResultFilter {
+            workflow_id: Some("wf1".to_string()),
+            ..Default::default()
+        };
+        let (items, next) = list_workflow_results_filtered(&db, &filter, None, None).await?;
+        assert_eq!(ids(items), vec!["r3", "r2", "r1"]);
+        assert!(next.is_empty());
+
+        let filter = WorkflowResultFilter {
+            ran_after: Some(day(2)),
+            ran_before: Some(day(3)),
+            ..Default::default()
+        };
+        let (items, _) = list_workflow_results_filtered(&db, &filter, None, None).await?;
+        assert_eq!(ids(items), vec!["r2", "r4"]);
+
+        let filter = WorkflowResultFilter {
+            exit_code: Some(0),
+            ..Default::default()
+        };
+        let (items, next) = list_workflow_results_filtered(&db, &filter, None, Some(2)).await?;
+        assert_eq!(ids(items), vec!["r3", "r4"]);
+        let (items, next) =
+            list_workflow_results_filtered(&db, &filter, Some(next), Some(2)).await?;
+        assert_eq!(ids(items), vec!["r1"]);
+        assert!(next.is_empty());
+
+        let filter = WorkflowResultFilter {
+            ran_after: Some(day(2)),
+            owner_id: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let (items, _) = list_workflow_results_filtered(&db, &filter, None, None).await?;
+        assert_eq!(ids(items), vec!["r3", "r2"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    /// Checks that result sizes are listed without loading the outputs.
+    async fn test_list_workflow_result_sizes() -> Result<(), DbErr> {
+        let db = setup_db().await?;
+
+        let wf = entity_wf::Model {
+            id: "wf1".to_string(),
+            display_name: "WF".to_string(),
+            description: None,
+            workflow_language: 0,
+            created_at: None,
+            updated_at: None,
+            version: 1,
+            owner_id: None,
+        };
+        let wc = entity_wc::Model {
+            id: "wc1".to_string(),
+            workflow_id: "wf1".to_string(),
+            code_revision: 1,
+            code: "code".to_string(),
+            language: 0,
+            created_at: None,
+        };
+        let active_wf: entity_wf::ActiveModel = wf.into();
+        active_wf.insert(&db).await?;
+        let active_wc: entity_wc::ActiveModel = wc.into();
+        active_wc.insert(&db).await?;
+
+        for (id, result) in [("r1", Some("12345")), ("r2", None)] {
+            let r = workflow_result::Model {
+                id: id.to_string(),
+                workflow_id: "wf1".to_string(),
+                workflow_code_id: "wc1".to_string(),
+                display_name: None,
+                description: None,
+                result: result.map(str::to_string),
+                ran_at: None,
+                result_type: 0,
+                exit_code: Some(0),
+                workflow_result_revision: 1,
+            };
+            create_workflow_result(&db, r).await?;
+        }
+
+        let mut sizes = list_workflow_result_sizes(&db).await?;
+        sizes.sort_by(|a, b| a.id.cmp(&b.id));
+        let sizes: Vec<(&str, u64)> = sizes.iter().map(|r| (r.id.as_str(), r.size)).collect();
+        assert_eq!(sizes, vec![("r1", 5), ("r2", 0)]);
+
+        Ok(())
+    }
 }
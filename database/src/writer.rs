@@ -0,0 +1,141 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Serializes transactional writes to SQLite.
+//!
+//! SQLite allows one writer at a time. A single statement waits for the write lock for up to
+//! the connection's busy timeout, but a transaction that has read before it writes cannot
+//! wait: SQLite fails it with "database is locked" at once. The functions of this crate that
+//! write in a transaction therefore run it through [`serialize_write`], which hands it to a
+//! [`Writer`] task running one write at a time. Reads never go through the writer and stay
+//! concurrent.
+//!
+//! Until a writer is installed with [`set_writer`], for example on Postgres and MySQL, which
+//! handle concurrent writers themselves, writes run directly.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+use sea_orm::DbErr;
+use tokio::sync::{mpsc, oneshot};
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+static WRITER: OnceLock<Writer> = OnceLock::new();
+
+/// Handle to a task that runs the writes sent to it one at a time, in order.
+#[derive(Debug, Clone)]
+pub struct Writer {
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl Writer {
+    /// Spawns the writer task on the current tokio runtime.
+    pub fn spawn() -> Self {
+        let (jobs, mut receiver) = mpsc::unbounded_channel::<Job>();
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                // A panicking write fails its caller but does not stop the writer.
+                let _ = tokio::spawn(job).await;
+            }
+        });
+        Self { jobs }
+    }
+
+    /// Runs `write` once every write sent before it has finished.
+    ///
+    /// # Returns
+    ///
+    /// Returns the result of `write`, or an error when the writer task has stopped or the
+    /// write panicked.
+    pub async fn run<T, F>(&self, write: F) -> Result<T, DbErr>
+    where
+        F: Future<Output = Result<T, DbErr>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        let job: Job = Box::pin(async move {
+            let _ = reply.send(write.await);
+        });
+        self.jobs
+            .send(job)
+            .map_err(|_| DbErr::Custom("database writer has stopped".to_string()))?;
+        result
+            .await
+            .map_err(|_| DbErr::Custom("database write was aborted".to_string()))?
+    }
+}
+
+/// Installs the writer used by [`serialize_write`]. Only the first call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the writer was installed, `false` if one was already installed.
+pub fn set_writer(writer: Writer) -> bool {
+    WRITER.set(writer).is_ok()
+}
+
+/// Runs `write` on the installed writer, or directly when none is installed.
+///
+/// `write` must not call `serialize_write` itself: it would wait for its own completion.
+pub async fn serialize_write<T, F>(write: F) -> Result<T, DbErr>
+where
+    F: Future<Output = Result<T, DbErr>> + Send + 'static,
+    T: Send + 'static,
+{
+    match WRITER.get() {
+        Some(writer) => writer.run(write).await,
+        None => write.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn writes_run_one_at_a_time() {
+        let writer = Writer::spawn();
+        let running = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..5)
+            .map(|_| {
+                let (writer, running, finished) =
+                    (writer.clone(), running.clone(), finished.clone());
+                tokio::spawn(async move {
+                    writer
+                        .run(async move {
+                            assert_eq!(running.fetch_add(1, Ordering::SeqCst), 0);
+                            tokio::time::sleep(Duration::from_millis(5)).await;
+                            running.fetch_sub(1, Ordering::SeqCst);
+                            Ok::<_, DbErr>(finished.fetch_add(1, Ordering::SeqCst))
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(finished.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn a_panicking_write_does_not_stop_the_writer() {
+        let writer = Writer::spawn();
+        let failed = writer
+            .run(async {
+                "not a number".parse::<u32>().expect("write failed");
+                Ok::<(), DbErr>(())
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(writer.run(async { Ok::<_, DbErr>(1) }).await.unwrap(), 1);
+    }
+}
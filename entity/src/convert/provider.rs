@@ -4,16 +4,22 @@
 
 //! This module provides functions for converting between the `provider` entity and its
 //! corresponding protobuf representation.
+//!
+//! The API key is sealed in the entity and open in the proto message; see
+//! [`crate::encryption`].
 
+use crate::encryption::{decrypt_field, encrypt_field};
 use crate::entity::provider::Model as EntityProvider;
 use sapphillon_core::proto::sapphillon::ai::v1::Provider as ProtoProvider;
 
 impl From<EntityProvider> for ProtoProvider {
     fn from(entity: EntityProvider) -> Self {
+        // A key that cannot be opened stays sealed, so saving the message back keeps it.
+        let api_key = decrypt_field(&entity.api_key).unwrap_or(entity.api_key);
         ProtoProvider {
             name: entity.name,
             display_name: entity.display_name,
-            api_key: entity.api_key,
+            api_key,
             api_endpoint: entity.api_endpoint,
         }
     }
@@ -24,8 +30,10 @@ impl From<ProtoProvider> for EntityProvider {
         EntityProvider {
             name: proto.name,
             display_name: proto.display_name,
-            api_key: proto.api_key,
+            api_key: encrypt_field(&proto.api_key),
             api_endpoint: proto.api_endpoint,
+            kind: None,
+            options: None,
         }
     }
 }
@@ -70,6 +78,8 @@ mod tests {
             display_name: "My Provider".to_string(),
             api_key: "secret-key".to_string(),
             api_endpoint: "https://api.example.com".to_string(),
+            kind: None,
+            options: None,
         };
 
         // Entity -> Proto
@@ -0,0 +1,267 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Encryption of sensitive column values, such as provider API keys.
+//!
+//! Values are sealed with AES-256-GCM and stored as
+//! `enc:v1:<key id>:<base64 of nonce and ciphertext>`. A [`FieldKeyring`] holds named
+//! keys: the first one encrypts new values and every one of them decrypts, so keys can be
+//! rotated by putting a new key first and re-encrypting the stored values.
+//!
+//! The convert layer seals designated fields when an entity is built from a proto message
+//! and opens them when a proto message is built from an entity. Until a keyring is
+//! installed with [`set_field_keyring`], values are stored as plaintext, and plaintext
+//! stored before encryption was enabled is always read as is.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose;
+
+const SEALED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+static FIELD_KEYRING: OnceLock<FieldKeyring> = OnceLock::new();
+
+/// Named AES-256-GCM keys used to seal column values.
+pub struct FieldKeyring {
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl fmt::Debug for FieldKeyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<&str> = self.keys.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("FieldKeyring").field("keys", &ids).finish()
+    }
+}
+
+impl FieldKeyring {
+    /// Parses keys written one per line as `<id> <base64 key>`.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. The first key encrypts new
+    /// values.
+    ///
+    /// # Returns
+    ///
+    /// Returns the keyring, or an error when a line is malformed, a key is not 32 bytes,
+    /// an id is repeated or no key is given.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut keys: Vec<(String, Aes256Gcm)> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_no = index + 1;
+            let (id, encoded) = line
+                .split_once(char::is_whitespace)
+                .with_context(|| format!("line {line_no}: expected `<id> <base64 key>`"))?;
+            if id.contains(':') {
+                bail!("line {line_no}: key id must not contain ':'");
+            }
+            if keys.iter().any(|(known, _)| known == id) {
+                bail!("line {line_no}: key id '{id}' is repeated");
+            }
+            let bytes = general_purpose::STANDARD
+                .decode(encoded.trim())
+                .with_context(|| format!("line {line_no}: key is not valid base64"))?;
+            if bytes.len() != KEY_LEN {
+                bail!("line {line_no}: key must be {KEY_LEN} bytes");
+            }
+            let key = Key::<Aes256Gcm>::from_slice(&bytes);
+            keys.push((id.to_string(), Aes256Gcm::new(key)));
+        }
+        if keys.is_empty() {
+            bail!("no encryption key given");
+        }
+        Ok(Self { keys })
+    }
+
+    /// Generates a random key as a line for [`FieldKeyring::parse`].
+    pub fn generate_key_line(id: &str) -> String {
+        let key = Aes256Gcm::generate_key(OsRng);
+        format!("{id} {}", general_purpose::STANDARD.encode(key))
+    }
+
+    /// Id of the key that encrypts new values.
+    pub fn current_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    /// Seals `plaintext` with the current key.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let (id, cipher) = &self.keys[0];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("AES-GCM encrypts inputs of any column size");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        format!(
+            "{SEALED_PREFIX}{id}:{}",
+            general_purpose::STANDARD.encode(sealed)
+        )
+    }
+
+    /// Opens a stored value; plaintext values are returned unchanged.
+    ///
+    /// # Returns
+    ///
+    /// Returns the plaintext, or an error when the key that sealed the value is not in
+    /// the keyring or the value was altered.
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let Some((id, encoded)) = split_sealed(stored) else {
+            return Ok(stored.to_string());
+        };
+        let (_, cipher) = self
+            .keys
+            .iter()
+            .find(|(known, _)| known == id)
+            .ok_or_else(|| anyhow!("encryption key '{id}' is not available"))?;
+        let sealed = general_purpose::STANDARD
+            .decode(encoded)
+            .context("sealed value is not valid base64")?;
+        if sealed.len() < NONCE_LEN {
+            bail!("sealed value is truncated");
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("sealed value cannot be decrypted with key '{id}'"))?;
+        String::from_utf8(plaintext).context("decrypted value is not UTF-8")
+    }
+
+    /// Seals a stored value with the current key unless it already is.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(value)` when the value was plaintext or sealed with an older key,
+    /// `None` when it needs no change, or an error when it cannot be decrypted.
+    pub fn reencrypt(&self, stored: &str) -> Result<Option<String>> {
+        if stored.is_empty() {
+            return Ok(None);
+        }
+        if split_sealed(stored).is_some_and(|(id, _)| id == self.current_key_id()) {
+            return Ok(None);
+        }
+        Ok(Some(self.encrypt(&self.decrypt(stored)?)))
+    }
+}
+
+/// Splits a sealed value into its key id and encoded payload.
+fn split_sealed(stored: &str) -> Option<(&str, &str)> {
+    stored.strip_prefix(SEALED_PREFIX)?.split_once(':')
+}
+
+/// Installs the keyring used by [`encrypt_field`] and [`decrypt_field`]. Only the first
+/// call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the keyring was installed, `false` if one was already installed.
+pub fn set_field_keyring(keyring: FieldKeyring) -> bool {
+    FIELD_KEYRING.set(keyring).is_ok()
+}
+
+/// The installed keyring, if any.
+pub fn field_keyring() -> Option<&'static FieldKeyring> {
+    FIELD_KEYRING.get()
+}
+
+/// Whether a stored value is sealed.
+pub fn is_encrypted(stored: &str) -> bool {
+    split_sealed(stored).is_some()
+}
+
+/// Seals a value for storage with the installed keyring.
+///
+/// Empty values, values that are already sealed and every value while no keyring is
+/// installed are returned unchanged.
+pub fn encrypt_field(value: &str) -> String {
+    match field_keyring() {
+        Some(keyring) if !value.is_empty() && !is_encrypted(value) => keyring.encrypt(value),
+        _ => value.to_string(),
+    }
+}
+
+/// Opens a stored value with the installed keyring; plaintext is returned unchanged.
+///
+/// # Returns
+///
+/// Returns the plaintext, or an error when the value is sealed and cannot be opened,
+/// e.g. because no keyring is installed.
+pub fn decrypt_field(stored: &str) -> Result<String> {
+    match field_keyring() {
+        Some(keyring) => keyring.decrypt(stored),
+        None if is_encrypted(stored) => bail!("value is encrypted but no encryption key is set"),
+        None => Ok(stored.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyring(lines: &[&str]) -> FieldKeyring {
+        FieldKeyring::parse(&lines.join("\n")).unwrap()
+    }
+
+    #[test]
+    fn sealed_values_round_trip() {
+        let line = FieldKeyring::generate_key_line("k1");
+        let keyring = keyring(&["# database keys", "", &line]);
+
+        let sealed = keyring.encrypt("sk-secret");
+        assert!(sealed.starts_with("enc:v1:k1:"), "{sealed}");
+        assert!(!sealed.contains("sk-secret"));
+        assert_ne!(sealed, keyring.encrypt("sk-secret"));
+        assert_eq!(keyring.decrypt(&sealed).unwrap(), "sk-secret");
+        assert_eq!(keyring.decrypt("plain-key").unwrap(), "plain-key");
+    }
+
+    #[test]
+    fn rotation_reseals_values_of_older_keys() {
+        let old_line = FieldKeyring::generate_key_line("old");
+        let new_line = FieldKeyring::generate_key_line("new");
+        let old = keyring(&[&old_line]);
+        let rotated = keyring(&[&new_line, &old_line]);
+
+        let sealed = old.encrypt("sk-secret");
+        let resealed = rotated.reencrypt(&sealed).unwrap().unwrap();
+        assert!(resealed.starts_with("enc:v1:new:"), "{resealed}");
+        assert_eq!(rotated.decrypt(&resealed).unwrap(), "sk-secret");
+        assert_eq!(rotated.reencrypt(&resealed).unwrap(), None);
+        assert!(rotated.reencrypt("plain").unwrap().is_some());
+        assert_eq!(rotated.reencrypt("").unwrap(), None);
+
+        assert!(old.decrypt(&resealed).is_err());
+    }
+
+    #[test]
+    fn altered_values_are_rejected() {
+        let keyring = keyring(&[&FieldKeyring::generate_key_line("k1")]);
+        let sealed = keyring.encrypt("sk-secret");
+        let mut payload = general_purpose::STANDARD
+            .decode(sealed.trim_start_matches("enc:v1:k1:"))
+            .unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let altered = format!("enc:v1:k1:{}", general_purpose::STANDARD.encode(payload));
+        assert!(keyring.decrypt(&altered).is_err());
+    }
+
+    #[test]
+    fn malformed_keyrings_are_rejected() {
+        let line = FieldKeyring::generate_key_line("k1");
+        assert!(FieldKeyring::parse("").is_err());
+        assert!(FieldKeyring::parse("k1").is_err());
+        assert!(FieldKeyring::parse("k1 c2hvcnQ=").is_err());
+        assert!(FieldKeyring::parse(&format!("{line}\n{line}")).is_err());
+        assert!(FieldKeyring::parse(&line.replacen("k1", "k:1", 1)).is_err());
+    }
+}
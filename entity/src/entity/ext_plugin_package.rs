@@ -9,6 +9,8 @@ pub struct Model {
     pub plugin_package_id: String,
     pub install_dir: String,
     pub missing: bool,
+    pub enabled: bool,
+    pub available_version: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
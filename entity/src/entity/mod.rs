@@ -2,16 +2,30 @@
 
 pub mod prelude;
 
+pub mod api_token;
 pub mod ext_plugin_package;
 pub mod model;
 pub mod permission;
+pub mod permission_profile;
 pub mod plugin_function;
 pub mod plugin_function_permission;
 pub mod plugin_package;
 pub mod provider;
+pub mod user_account;
 pub mod workflow;
 pub mod workflow_code;
 pub mod workflow_code_allowed_permission;
+pub mod workflow_code_change;
+pub mod workflow_code_denied_permission;
+pub mod workflow_code_permission_grant;
 pub mod workflow_code_plugin_function;
 pub mod workflow_code_plugin_package;
+pub mod workflow_folder;
+pub mod workflow_permission_profile;
+pub mod workflow_prompt;
+pub mod workflow_recording;
 pub mod workflow_result;
+pub mod workflow_schedule;
+pub mod workflow_state;
+pub mod workflow_tag;
+pub mod workflow_trigger;
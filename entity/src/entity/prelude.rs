@@ -1,15 +1,29 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.17
 
+pub use super::api_token::Entity as ApiToken;
 pub use super::ext_plugin_package::Entity as ExtPluginPackage;
 pub use super::model::Entity as Model;
 pub use super::permission::Entity as Permission;
+pub use super::permission_profile::Entity as PermissionProfile;
 pub use super::plugin_function::Entity as PluginFunction;
 pub use super::plugin_function_permission::Entity as PluginFunctionPermission;
 pub use super::plugin_package::Entity as PluginPackage;
 pub use super::provider::Entity as Provider;
+pub use super::user_account::Entity as UserAccount;
 pub use super::workflow::Entity as Workflow;
 pub use super::workflow_code::Entity as WorkflowCode;
 pub use super::workflow_code_allowed_permission::Entity as WorkflowCodeAllowedPermission;
+pub use super::workflow_code_change::Entity as WorkflowCodeChange;
+pub use super::workflow_code_denied_permission::Entity as WorkflowCodeDeniedPermission;
+pub use super::workflow_code_permission_grant::Entity as WorkflowCodePermissionGrant;
 pub use super::workflow_code_plugin_function::Entity as WorkflowCodePluginFunction;
 pub use super::workflow_code_plugin_package::Entity as WorkflowCodePluginPackage;
+pub use super::workflow_folder::Entity as WorkflowFolder;
+pub use super::workflow_permission_profile::Entity as WorkflowPermissionProfile;
+pub use super::workflow_prompt::Entity as WorkflowPrompt;
+pub use super::workflow_recording::Entity as WorkflowRecording;
 pub use super::workflow_result::Entity as WorkflowResult;
+pub use super::workflow_schedule::Entity as WorkflowSchedule;
+pub use super::workflow_state::Entity as WorkflowState;
+pub use super::workflow_tag::Entity as WorkflowTag;
+pub use super::workflow_trigger::Entity as WorkflowTrigger;
@@ -10,6 +10,8 @@ pub struct Model {
     pub display_name: String,
     pub api_key: String,
     pub api_endpoint: String,
+    pub kind: Option<String>,
+    pub options: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -12,14 +12,32 @@ pub struct Model {
     pub workflow_language: i32,
     pub created_at: Option<DateTimeUtc>,
     pub updated_at: Option<DateTimeUtc>,
+    pub version: i32,
+    pub owner_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::workflow_code::Entity")]
     WorkflowCode,
+    #[sea_orm(has_one = "super::workflow_folder::Entity")]
+    WorkflowFolder,
+    #[sea_orm(has_many = "super::workflow_permission_profile::Entity")]
+    WorkflowPermissionProfile,
+    #[sea_orm(has_many = "super::workflow_prompt::Entity")]
+    WorkflowPrompt,
+    #[sea_orm(has_many = "super::workflow_recording::Entity")]
+    WorkflowRecording,
     #[sea_orm(has_many = "super::workflow_result::Entity")]
     WorkflowResult,
+    #[sea_orm(has_many = "super::workflow_schedule::Entity")]
+    WorkflowSchedule,
+    #[sea_orm(has_many = "super::workflow_state::Entity")]
+    WorkflowState,
+    #[sea_orm(has_many = "super::workflow_tag::Entity")]
+    WorkflowTag,
+    #[sea_orm(has_many = "super::workflow_trigger::Entity")]
+    WorkflowTrigger,
 }
 
 impl Related<super::workflow_code::Entity> for Entity {
@@ -28,10 +46,58 @@ impl Related<super::workflow_code::Entity> for Entity {
     }
 }
 
+impl Related<super::workflow_folder::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowFolder.def()
+    }
+}
+
+impl Related<super::workflow_permission_profile::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowPermissionProfile.def()
+    }
+}
+
+impl Related<super::workflow_prompt::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowPrompt.def()
+    }
+}
+
+impl Related<super::workflow_recording::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowRecording.def()
+    }
+}
+
 impl Related<super::workflow_result::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::WorkflowResult.def()
     }
 }
 
+impl Related<super::workflow_schedule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowSchedule.def()
+    }
+}
+
+impl Related<super::workflow_state::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowState.def()
+    }
+}
+
+impl Related<super::workflow_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowTag.def()
+    }
+}
+
+impl Related<super::workflow_trigger::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowTrigger.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
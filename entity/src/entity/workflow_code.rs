@@ -27,6 +27,12 @@ pub enum Relation {
     Workflow,
     #[sea_orm(has_many = "super::workflow_code_allowed_permission::Entity")]
     WorkflowCodeAllowedPermission,
+    #[sea_orm(has_one = "super::workflow_code_change::Entity")]
+    WorkflowCodeChange,
+    #[sea_orm(has_many = "super::workflow_code_denied_permission::Entity")]
+    WorkflowCodeDeniedPermission,
+    #[sea_orm(has_many = "super::workflow_code_permission_grant::Entity")]
+    WorkflowCodePermissionGrant,
     #[sea_orm(has_many = "super::workflow_code_plugin_function::Entity")]
     WorkflowCodePluginFunction,
     #[sea_orm(has_many = "super::workflow_code_plugin_package::Entity")]
@@ -47,6 +53,24 @@ impl Related<super::workflow_code_allowed_permission::Entity> for Entity {
     }
 }
 
+impl Related<super::workflow_code_change::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowCodeChange.def()
+    }
+}
+
+impl Related<super::workflow_code_denied_permission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowCodeDeniedPermission.def()
+    }
+}
+
+impl Related<super::workflow_code_permission_grant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkflowCodePermissionGrant.def()
+    }
+}
+
 impl Related<super::workflow_code_plugin_function::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::WorkflowCodePluginFunction.def()
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
 pub mod convert;
+pub mod encryption;
 pub mod entity;
 
 pub use entity::provider;
@@ -0,0 +1,21 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Column definitions whose SQL type depends on the database backend
+
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+/// An instant the entities read as `DateTimeUtc`.
+///
+/// SQLite keeps the `timestamp` type the schema has always used. Postgres needs
+/// `timestamp with time zone` to decode the column as UTC.
+pub(crate) fn utc_timestamp<T: IntoIden>(manager: &SchemaManager, column: T) -> ColumnDef {
+    let mut def = ColumnDef::new(column);
+    match manager.get_database_backend() {
+        DatabaseBackend::Sqlite => def.timestamp(),
+        DatabaseBackend::Postgres | DatabaseBackend::MySql => def.timestamp_with_time_zone(),
+    };
+    def
+}
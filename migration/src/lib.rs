@@ -4,15 +4,50 @@
 
 pub use sea_orm_migration::prelude::*;
 
+mod columns;
 mod m20250908_000001_create_providers_and_models;
+mod m20261016_000001_create_workflow_schedule;
+mod m20261016_000002_create_workflow_state;
+mod m20261016_000003_create_workflow_recording;
+mod m20261016_000004_create_workflow_trigger;
+mod m20261016_000005_create_workflow_prompt;
+mod m20261016_000006_create_workflow_code_denied_permission;
+mod m20261016_000007_create_permission_profile;
+mod m20261016_000008_create_workflow_code_permission_grant;
+mod m20261016_000009_add_ext_plugin_package_enabled;
+mod m20261016_000010_add_ext_plugin_package_available_version;
+mod m20261016_000011_add_provider_kind_and_options;
+mod m20261016_000012_create_workflow_code_change;
+mod m20261016_000013_create_workflow_tag_and_folder;
+mod m20261016_000014_add_workflow_version;
+mod m20261016_000015_create_workflow_search;
+mod m20261016_000016_create_user_account_and_owner;
+mod m20261016_000017_create_api_token;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(
-            m20250908_000001_create_providers_and_models::Migration,
-        )]
+        vec![
+            Box::new(m20250908_000001_create_providers_and_models::Migration),
+            Box::new(m20261016_000001_create_workflow_schedule::Migration),
+            Box::new(m20261016_000002_create_workflow_state::Migration),
+            Box::new(m20261016_000003_create_workflow_recording::Migration),
+            Box::new(m20261016_000004_create_workflow_trigger::Migration),
+            Box::new(m20261016_000005_create_workflow_prompt::Migration),
+            Box::new(m20261016_000006_create_workflow_code_denied_permission::Migration),
+            Box::new(m20261016_000007_create_permission_profile::Migration),
+            Box::new(m20261016_000008_create_workflow_code_permission_grant::Migration),
+            Box::new(m20261016_000009_add_ext_plugin_package_enabled::Migration),
+            Box::new(m20261016_000010_add_ext_plugin_package_available_version::Migration),
+            Box::new(m20261016_000011_add_provider_kind_and_options::Migration),
+            Box::new(m20261016_000012_create_workflow_code_change::Migration),
+            Box::new(m20261016_000013_create_workflow_tag_and_folder::Migration),
+            Box::new(m20261016_000014_add_workflow_version::Migration),
+            Box::new(m20261016_000015_create_workflow_search::Migration),
+            Box::new(m20261016_000016_create_user_account_and_owner::Migration),
+            Box::new(m20261016_000017_create_api_token::Migration),
+        ]
     }
 }
@@ -143,8 +143,11 @@ CREATE TABLE ext_plugin_package (
     missing BOOLEAN NOT NULL DEFAULT FALSE
 );
 */
+use sea_orm_migration::sea_orm::DatabaseBackend;
 use sea_orm_migration::{prelude::*, schema::*};
 
+use crate::columns::utc_timestamp;
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -188,12 +191,8 @@ impl MigrationTrait for Migration {
                             .not_null()
                             .default(false),
                     )
-                    .col(
-                        ColumnDef::new(PluginPackage::InstalledAt)
-                            .timestamp()
-                            .null(),
-                    )
-                    .col(ColumnDef::new(PluginPackage::UpdatedAt).timestamp().null())
+                    .col(utc_timestamp(manager, PluginPackage::InstalledAt).null())
+                    .col(utc_timestamp(manager, PluginPackage::UpdatedAt).null())
                     .col(ColumnDef::new(PluginPackage::ProviderId).string().null())
                     .to_owned(),
             )
@@ -246,52 +245,6 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
-        manager
-            .create_table(
-                Table::create()
-                    .table(PluginFunctionPermission::Table)
-                    .if_not_exists()
-                    .col(
-                        ColumnDef::new(PluginFunctionPermission::Id)
-                            .integer()
-                            .not_null()
-                            .auto_increment()
-                            .primary_key(),
-                    )
-                    .col(
-                        ColumnDef::new(PluginFunctionPermission::PluginFunctionId)
-                            .string()
-                            .not_null(),
-                    )
-                    .foreign_key(
-                        ForeignKey::create()
-                            .name("fk_plugin_function_permission_function")
-                            .from(
-                                PluginFunctionPermission::Table,
-                                PluginFunctionPermission::PluginFunctionId,
-                            )
-                            .to(PluginFunction::Table, PluginFunction::FunctionId)
-                            .on_delete(ForeignKeyAction::Cascade),
-                    )
-                    .col(
-                        ColumnDef::new(PluginFunctionPermission::PermissionId)
-                            .string()
-                            .not_null(),
-                    )
-                    .foreign_key(
-                        ForeignKey::create()
-                            .name("fk_plugin_function_permission_permission")
-                            .from(
-                                PluginFunctionPermission::Table,
-                                PluginFunctionPermission::PermissionId,
-                            )
-                            .to(Permission::Table, Permission::Id)
-                            .on_delete(ForeignKeyAction::Cascade),
-                    )
-                    .to_owned(),
-            )
-            .await?;
-
         manager
             .create_table(
                 Table::create()
@@ -325,6 +278,53 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
+        let mut plugin_function_permission = Table::create()
+            .table(PluginFunctionPermission::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(PluginFunctionPermission::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(
+                ColumnDef::new(PluginFunctionPermission::PluginFunctionId)
+                    .string()
+                    .not_null(),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .name("fk_plugin_function_permission_function")
+                    .from(
+                        PluginFunctionPermission::Table,
+                        PluginFunctionPermission::PluginFunctionId,
+                    )
+                    .to(PluginFunction::Table, PluginFunction::FunctionId)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .col(
+                ColumnDef::new(PluginFunctionPermission::PermissionId)
+                    .string()
+                    .not_null(),
+            )
+            .to_owned();
+        // permission_id is text while permission.id is an integer; only SQLite accepts a
+        // foreign key between the two, so other backends leave the column unconstrained.
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            plugin_function_permission.foreign_key(
+                ForeignKey::create()
+                    .name("fk_plugin_function_permission_permission")
+                    .from(
+                        PluginFunctionPermission::Table,
+                        PluginFunctionPermission::PermissionId,
+                    )
+                    .to(Permission::Table, Permission::Id)
+                    .on_delete(ForeignKeyAction::Cascade),
+            );
+        }
+        manager.create_table(plugin_function_permission).await?;
+
         manager
             .create_table(
                 Table::create()
@@ -386,8 +386,8 @@ impl MigrationTrait for Migration {
                             .integer()
                             .not_null(),
                     )
-                    .col(ColumnDef::new(Workflow::CreatedAt).timestamp().null())
-                    .col(ColumnDef::new(Workflow::UpdatedAt).timestamp().null())
+                    .col(utc_timestamp(manager, Workflow::CreatedAt).null())
+                    .col(utc_timestamp(manager, Workflow::UpdatedAt).null())
                     .to_owned(),
             )
             .await?;
@@ -411,7 +411,7 @@ impl MigrationTrait for Migration {
                     )
                     .col(ColumnDef::new(WorkflowCode::Code).text().not_null())
                     .col(ColumnDef::new(WorkflowCode::Language).integer().not_null())
-                    .col(ColumnDef::new(WorkflowCode::CreatedAt).timestamp().null())
+                    .col(utc_timestamp(manager, WorkflowCode::CreatedAt).null())
                     .foreign_key(
                         ForeignKey::create()
                             .name("fk_workflow_code_workflow")
@@ -447,7 +447,7 @@ impl MigrationTrait for Migration {
                     .col(ColumnDef::new(WorkflowResult::DisplayName).string().null())
                     .col(ColumnDef::new(WorkflowResult::Description).string().null())
                     .col(ColumnDef::new(WorkflowResult::Result).text().null())
-                    .col(ColumnDef::new(WorkflowResult::RanAt).timestamp().null())
+                    .col(utc_timestamp(manager, WorkflowResult::RanAt).null())
                     .col(
                         ColumnDef::new(WorkflowResult::ResultType)
                             .integer()
@@ -684,10 +684,6 @@ impl MigrationTrait for Migration {
             .drop_table(Table::drop().table(Workflow::Table).to_owned())
             .await?;
 
-        manager
-            .drop_table(Table::drop().table(Permission::Table).to_owned())
-            .await?;
-
         manager
             .drop_table(Table::drop().table(Model::Table).to_owned())
             .await?;
@@ -700,6 +696,10 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
+        manager
+            .drop_table(Table::drop().table(Permission::Table).to_owned())
+            .await?;
+
         manager
             .drop_table(Table::drop().table(PluginFunction::Table).to_owned())
             .await?;
@@ -0,0 +1,102 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_schedule
+-- Cron-style triggers that run a workflow's latest code automatically.
+CREATE TABLE workflow_schedule (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    cron TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMP,
+    last_run_at TIMESTAMP,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_schedule_next_run_at ON workflow_schedule(next_run_at);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowSchedule::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowSchedule::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowSchedule::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WorkflowSchedule::Cron).string().not_null())
+                    .col(
+                        ColumnDef::new(WorkflowSchedule::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(utc_timestamp(manager, WorkflowSchedule::NextRunAt).null())
+                    .col(utc_timestamp(manager, WorkflowSchedule::LastRunAt).null())
+                    .col(utc_timestamp(manager, WorkflowSchedule::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_schedule_workflow")
+                            .from(WorkflowSchedule::Table, WorkflowSchedule::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_schedule_next_run_at")
+                    .table(WorkflowSchedule::Table)
+                    .col(WorkflowSchedule::NextRunAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowSchedule::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowSchedule {
+    Table,
+    Id,
+    WorkflowId,
+    Cron,
+    Enabled,
+    NextRunAt,
+    LastRunAt,
+    CreatedAt,
+}
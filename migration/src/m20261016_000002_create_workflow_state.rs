@@ -0,0 +1,77 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_state
+-- Key/value state that a workflow keeps between runs; values are JSON.
+CREATE TABLE workflow_state (
+    workflow_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMP,
+    PRIMARY KEY (workflow_id, key),
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowState::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowState::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WorkflowState::Key).string().not_null())
+                    .col(ColumnDef::new(WorkflowState::Value).text().not_null())
+                    .col(utc_timestamp(manager, WorkflowState::UpdatedAt).null())
+                    .primary_key(
+                        Index::create()
+                            .col(WorkflowState::WorkflowId)
+                            .col(WorkflowState::Key),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_state_workflow")
+                            .from(WorkflowState::Table, WorkflowState::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowState::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowState {
+    Table,
+    WorkflowId,
+    Key,
+    Value,
+    UpdatedAt,
+}
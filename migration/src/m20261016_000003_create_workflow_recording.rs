@@ -0,0 +1,86 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_recording
+-- Plugin calls captured during a recorded run, used to replay the run deterministically.
+CREATE TABLE workflow_recording (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    workflow_code_id TEXT NOT NULL,
+    calls TEXT NOT NULL,
+    output TEXT NOT NULL,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowRecording::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowRecording::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowRecording::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowRecording::WorkflowCodeId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WorkflowRecording::Calls).text().not_null())
+                    .col(ColumnDef::new(WorkflowRecording::Output).text().not_null())
+                    .col(utc_timestamp(manager, WorkflowRecording::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_recording_workflow")
+                            .from(WorkflowRecording::Table, WorkflowRecording::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowRecording::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowRecording {
+    Table,
+    Id,
+    WorkflowId,
+    WorkflowCodeId,
+    Calls,
+    Output,
+    CreatedAt,
+}
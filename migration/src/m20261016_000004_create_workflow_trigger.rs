@@ -0,0 +1,98 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_trigger
+-- Event triggers (file changes, webhooks, window changes) that run a workflow's latest code.
+CREATE TABLE workflow_trigger (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    config TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_trigger_kind ON workflow_trigger(kind);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowTrigger::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowTrigger::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowTrigger::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WorkflowTrigger::Kind).string().not_null())
+                    .col(ColumnDef::new(WorkflowTrigger::Config).text().not_null())
+                    .col(
+                        ColumnDef::new(WorkflowTrigger::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(utc_timestamp(manager, WorkflowTrigger::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_trigger_workflow")
+                            .from(WorkflowTrigger::Table, WorkflowTrigger::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_trigger_kind")
+                    .table(WorkflowTrigger::Table)
+                    .col(WorkflowTrigger::Kind)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowTrigger::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowTrigger {
+    Table,
+    Id,
+    WorkflowId,
+    Kind,
+    Config,
+    Enabled,
+    CreatedAt,
+}
@@ -0,0 +1,99 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_prompt
+-- Questions a running workflow asks a person, kept until they are answered or expire.
+CREATE TABLE workflow_prompt (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    message TEXT NOT NULL,
+    status TEXT NOT NULL,
+    answer TEXT,
+    created_at TIMESTAMP,
+    answered_at TIMESTAMP,
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_prompt_status ON workflow_prompt(status);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowPrompt::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowPrompt::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowPrompt::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WorkflowPrompt::Kind).string().not_null())
+                    .col(ColumnDef::new(WorkflowPrompt::Message).text().not_null())
+                    .col(ColumnDef::new(WorkflowPrompt::Status).string().not_null())
+                    .col(ColumnDef::new(WorkflowPrompt::Answer).text().null())
+                    .col(utc_timestamp(manager, WorkflowPrompt::CreatedAt).null())
+                    .col(utc_timestamp(manager, WorkflowPrompt::AnsweredAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_prompt_workflow")
+                            .from(WorkflowPrompt::Table, WorkflowPrompt::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_prompt_status")
+                    .table(WorkflowPrompt::Table)
+                    .col(WorkflowPrompt::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowPrompt::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowPrompt {
+    Table,
+    Id,
+    WorkflowId,
+    Kind,
+    Message,
+    Status,
+    Answer,
+    CreatedAt,
+    AnsweredAt,
+}
@@ -0,0 +1,112 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_code_denied_permission
+-- Permissions a workflow code must never use, even when they are also allowed.
+CREATE TABLE workflow_code_denied_permission (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_code_id TEXT NOT NULL,
+    plugin_function_id TEXT NOT NULL,
+    permission_type INTEGER NOT NULL,
+    resource_json TEXT,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_code_id) REFERENCES workflow_code(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_code_denied_permission_code ON workflow_code_denied_permission(workflow_code_id);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowCodeDeniedPermission::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowCodeDeniedPermission::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeDeniedPermission::WorkflowCodeId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeDeniedPermission::PluginFunctionId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeDeniedPermission::PermissionType)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeDeniedPermission::ResourceJson)
+                            .text()
+                            .null(),
+                    )
+                    .col(utc_timestamp(manager, WorkflowCodeDeniedPermission::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_code_denied_permission_workflow_code")
+                            .from(
+                                WorkflowCodeDeniedPermission::Table,
+                                WorkflowCodeDeniedPermission::WorkflowCodeId,
+                            )
+                            .to(WorkflowCode::Table, WorkflowCode::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_code_denied_permission_code")
+                    .table(WorkflowCodeDeniedPermission::Table)
+                    .col(WorkflowCodeDeniedPermission::WorkflowCodeId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WorkflowCodeDeniedPermission::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WorkflowCode {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowCodeDeniedPermission {
+    Table,
+    Id,
+    WorkflowCodeId,
+    PluginFunctionId,
+    PermissionType,
+    ResourceJson,
+    CreatedAt,
+}
@@ -0,0 +1,188 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- permission_profile
+-- Named sets of allowed permissions that can be attached to a workflow as a whole.
+-- `permissions` is a JSON array of {"function", "type", "level", "resource"} entries.
+CREATE TABLE permission_profile (
+    name TEXT NOT NULL PRIMARY KEY,
+    description TEXT,
+    permissions TEXT NOT NULL,
+    created_at TIMESTAMP
+);
+
+-- workflow_permission_profile
+-- Profiles attached to a workflow; their permissions are added to every run.
+CREATE TABLE workflow_permission_profile (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    profile_name TEXT NOT NULL,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE,
+    FOREIGN KEY (profile_name) REFERENCES permission_profile(name) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX idx_workflow_permission_profile_unique
+    ON workflow_permission_profile(workflow_id, profile_name);
+
+-- Built-in profiles: read-only, local-files-only and full-trust.
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+/// Profiles every installation starts with.
+const BUILTIN_PROFILES: [(&str, &str, &str); 3] = [
+    (
+        "read-only",
+        "Read files, window information and the web without changing anything",
+        r#"[{"function":"*","type":"filesystem-read","level":"medium"},{"function":"*","type":"execute","level":"medium"},{"function":"*","type":"net-access","level":"medium"}]"#,
+    ),
+    (
+        "local-files-only",
+        "Read and write local files without network access or running programs",
+        r#"[{"function":"*","type":"filesystem-read"},{"function":"*","type":"filesystem-write"}]"#,
+    ),
+    (
+        "full-trust",
+        "Every permission of every plugin function",
+        r#"[{"function":"*","type":"filesystem-read"},{"function":"*","type":"filesystem-write"},{"function":"*","type":"execute"},{"function":"*","type":"net-access"}]"#,
+    ),
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PermissionProfile::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PermissionProfile::Name)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PermissionProfile::Description).text().null())
+                    .col(
+                        ColumnDef::new(PermissionProfile::Permissions)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(utc_timestamp(manager, PermissionProfile::CreatedAt).null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowPermissionProfile::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowPermissionProfile::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowPermissionProfile::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowPermissionProfile::ProfileName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(utc_timestamp(manager, WorkflowPermissionProfile::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_permission_profile_workflow")
+                            .from(
+                                WorkflowPermissionProfile::Table,
+                                WorkflowPermissionProfile::WorkflowId,
+                            )
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_permission_profile_profile")
+                            .from(
+                                WorkflowPermissionProfile::Table,
+                                WorkflowPermissionProfile::ProfileName,
+                            )
+                            .to(PermissionProfile::Table, PermissionProfile::Name)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_permission_profile_unique")
+                    .table(WorkflowPermissionProfile::Table)
+                    .col(WorkflowPermissionProfile::WorkflowId)
+                    .col(WorkflowPermissionProfile::ProfileName)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut insert = Query::insert();
+        insert.into_table(PermissionProfile::Table).columns([
+            PermissionProfile::Name,
+            PermissionProfile::Description,
+            PermissionProfile::Permissions,
+        ]);
+        for (name, description, permissions) in BUILTIN_PROFILES {
+            insert.values_panic([name.into(), description.into(), permissions.into()]);
+        }
+        manager.exec_stmt(insert).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WorkflowPermissionProfile::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(PermissionProfile::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum PermissionProfile {
+    Table,
+    Name,
+    Description,
+    Permissions,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowPermissionProfile {
+    Table,
+    Id,
+    WorkflowId,
+    ProfileName,
+    CreatedAt,
+}
@@ -0,0 +1,138 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_code_permission_grant
+-- Permissions granted to a workflow code until they expire or have been used up.
+CREATE TABLE workflow_code_permission_grant (
+    id TEXT NOT NULL PRIMARY KEY,
+    workflow_code_id TEXT NOT NULL,
+    plugin_function_id TEXT NOT NULL,
+    permission_type INTEGER NOT NULL,
+    permission_level INTEGER NOT NULL DEFAULT 0,
+    resource_json TEXT,
+    expires_at TIMESTAMP,
+    max_uses INTEGER,
+    uses INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_code_id) REFERENCES workflow_code(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_code_permission_grant_code ON workflow_code_permission_grant(workflow_code_id);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowCodePermissionGrant::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::WorkflowCodeId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::PluginFunctionId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::PermissionType)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::PermissionLevel)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::ResourceJson)
+                            .text()
+                            .null(),
+                    )
+                    .col(utc_timestamp(manager, WorkflowCodePermissionGrant::ExpiresAt).null())
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::MaxUses)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodePermissionGrant::Uses)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(utc_timestamp(manager, WorkflowCodePermissionGrant::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_code_permission_grant_workflow_code")
+                            .from(
+                                WorkflowCodePermissionGrant::Table,
+                                WorkflowCodePermissionGrant::WorkflowCodeId,
+                            )
+                            .to(WorkflowCode::Table, WorkflowCode::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_code_permission_grant_code")
+                    .table(WorkflowCodePermissionGrant::Table)
+                    .col(WorkflowCodePermissionGrant::WorkflowCodeId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WorkflowCodePermissionGrant::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WorkflowCode {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowCodePermissionGrant {
+    Table,
+    Id,
+    WorkflowCodeId,
+    PluginFunctionId,
+    PermissionType,
+    PermissionLevel,
+    ResourceJson,
+    ExpiresAt,
+    MaxUses,
+    Uses,
+    CreatedAt,
+}
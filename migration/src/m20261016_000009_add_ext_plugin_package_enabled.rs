@@ -0,0 +1,53 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- ext_plugin_package.enabled
+-- Disabled external plugins stay installed but are not offered to workflows.
+ALTER TABLE ext_plugin_package ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1;
+*/
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Databases whose tables were created outside the migrator may have the column.
+        if manager.has_column("ext_plugin_package", "enabled").await? {
+            return Ok(());
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExtPluginPackage::Table)
+                    .add_column(
+                        ColumnDef::new(ExtPluginPackage::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExtPluginPackage::Table)
+                    .drop_column(ExtPluginPackage::Enabled)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExtPluginPackage {
+    Table,
+    Enabled,
+}
@@ -0,0 +1,55 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- ext_plugin_package.available_version
+-- Newer version of the plugin found in the plugin store, if any.
+ALTER TABLE ext_plugin_package ADD COLUMN available_version TEXT;
+*/
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Databases whose tables were created outside the migrator may have the column.
+        if manager
+            .has_column("ext_plugin_package", "available_version")
+            .await?
+        {
+            return Ok(());
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExtPluginPackage::Table)
+                    .add_column(
+                        ColumnDef::new(ExtPluginPackage::AvailableVersion)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExtPluginPackage::Table)
+                    .drop_column(ExtPluginPackage::AvailableVersion)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExtPluginPackage {
+    Table,
+    AvailableVersion,
+}
@@ -0,0 +1,59 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- provider.kind
+-- API the provider speaks: openai, anthropic, ollama or gemini; NULL is openai.
+ALTER TABLE provider ADD COLUMN kind TEXT;
+-- provider.options
+-- JSON object of provider-specific request options, e.g. {"temperature": 0.2}.
+ALTER TABLE provider ADD COLUMN options TEXT;
+*/
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite adds one column per ALTER TABLE statement. Columns that already exist,
+        // e.g. in databases whose tables were created outside the migrator, are kept.
+        for column in [Provider::Kind, Provider::Options] {
+            if manager.has_column("provider", &column.to_string()).await? {
+                continue;
+            }
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Provider::Table)
+                        .add_column(ColumnDef::new(column).string().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Provider::Kind, Provider::Options] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Provider::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Provider {
+    Table,
+    Kind,
+    Options,
+}
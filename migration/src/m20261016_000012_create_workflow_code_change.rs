@@ -0,0 +1,108 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_code_change
+-- How a workflow code revision differs from the revision it was made from.
+CREATE TABLE workflow_code_change (
+    workflow_code_id TEXT NOT NULL PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    previous_code_id TEXT,
+    diff_json TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    created_at TIMESTAMP,
+    FOREIGN KEY (workflow_code_id) REFERENCES workflow_code(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_code_change_workflow ON workflow_code_change(workflow_id);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowCodeChange::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowCodeChange::WorkflowCodeId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeChange::WorkflowId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeChange::PreviousCodeId)
+                            .string()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeChange::DiffJson)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkflowCodeChange::Summary)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(utc_timestamp(manager, WorkflowCodeChange::CreatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_code_change_workflow_code")
+                            .from(
+                                WorkflowCodeChange::Table,
+                                WorkflowCodeChange::WorkflowCodeId,
+                            )
+                            .to(WorkflowCode::Table, WorkflowCode::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_code_change_workflow")
+                    .table(WorkflowCodeChange::Table)
+                    .col(WorkflowCodeChange::WorkflowId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowCodeChange::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WorkflowCode {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowCodeChange {
+    Table,
+    WorkflowCodeId,
+    WorkflowId,
+    PreviousCodeId,
+    DiffJson,
+    Summary,
+    CreatedAt,
+}
@@ -0,0 +1,139 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- workflow_tag
+-- Free-form labels of a workflow, e.g. "reports".
+CREATE TABLE workflow_tag (
+    workflow_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMP,
+    PRIMARY KEY (workflow_id, tag),
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_tag_tag ON workflow_tag(tag);
+
+-- workflow_folder
+-- The folder a workflow is filed in, as a slash-separated path such as "work/reports".
+-- Workflows without a row are at the top level.
+CREATE TABLE workflow_folder (
+    workflow_id TEXT NOT NULL PRIMARY KEY,
+    folder TEXT NOT NULL,
+    updated_at TIMESTAMP,
+    FOREIGN KEY (workflow_id) REFERENCES workflow(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_workflow_folder_folder ON workflow_folder(folder);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowTag::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(WorkflowTag::WorkflowId).string().not_null())
+                    .col(ColumnDef::new(WorkflowTag::Tag).string().not_null())
+                    .col(utc_timestamp(manager, WorkflowTag::CreatedAt).null())
+                    .primary_key(
+                        Index::create()
+                            .col(WorkflowTag::WorkflowId)
+                            .col(WorkflowTag::Tag),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_tag_workflow")
+                            .from(WorkflowTag::Table, WorkflowTag::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_tag_tag")
+                    .table(WorkflowTag::Table)
+                    .col(WorkflowTag::Tag)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkflowFolder::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkflowFolder::WorkflowId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WorkflowFolder::Folder).string().not_null())
+                    .col(utc_timestamp(manager, WorkflowFolder::UpdatedAt).null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workflow_folder_workflow")
+                            .from(WorkflowFolder::Table, WorkflowFolder::WorkflowId)
+                            .to(Workflow::Table, Workflow::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workflow_folder_folder")
+                    .table(WorkflowFolder::Table)
+                    .col(WorkflowFolder::Folder)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WorkflowFolder::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WorkflowTag::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workflow {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowTag {
+    Table,
+    WorkflowId,
+    Tag,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WorkflowFolder {
+    Table,
+    WorkflowId,
+    Folder,
+    UpdatedAt,
+}
@@ -0,0 +1,185 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- Full-text index over workflow names and descriptions, code and result output.
+
+-- SQLite: an FTS5 table kept in sync with its sources by triggers.
+-- kind is 'workflow', 'code' or 'result'; source_id is the id of the row in its table.
+CREATE VIRTUAL TABLE workflow_search USING fts5(
+    kind UNINDEXED,
+    source_id UNINDEXED,
+    workflow_id UNINDEXED,
+    content
+);
+CREATE TRIGGER workflow_search_code_insert AFTER INSERT ON workflow_code BEGIN
+    INSERT INTO workflow_search (kind, source_id, workflow_id, content)
+    VALUES ('code', NEW.id, NEW.workflow_id, NEW.code);
+END;
+-- ... and likewise for updates and deletes of workflow, workflow_code and workflow_result.
+
+-- Postgres: expression indexes matched by the search query.
+CREATE INDEX idx_workflow_search ON workflow
+    USING GIN (to_tsvector('simple', display_name || ' ' || COALESCE(description, '')));
+CREATE INDEX idx_workflow_code_search ON workflow_code USING GIN (to_tsvector('simple', code));
+CREATE INDEX idx_workflow_result_search ON workflow_result
+    USING GIN (to_tsvector('simple', COALESCE(result, '')));
+
+-- MySQL has no index; it is searched with LIKE.
+*/
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// A table whose rows are indexed. `workflow_id` and `content` are SQL expressions in which
+/// `{row}` stands for the row prefix, e.g. `NEW.` in a trigger.
+struct Source {
+    table: &'static str,
+    kind: &'static str,
+    workflow_id: &'static str,
+    content: &'static str,
+    /// Columns whose update changes `workflow_id` or `content`.
+    columns: &'static str,
+}
+
+const SOURCES: [Source; 3] = [
+    Source {
+        table: "workflow",
+        kind: "workflow",
+        workflow_id: "{row}id",
+        content: "{row}display_name || ' ' || COALESCE({row}description, '')",
+        columns: "display_name, description",
+    },
+    Source {
+        table: "workflow_code",
+        kind: "code",
+        workflow_id: "{row}workflow_id",
+        content: "{row}code",
+        columns: "workflow_id, code",
+    },
+    Source {
+        table: "workflow_result",
+        kind: "result",
+        workflow_id: "{row}workflow_id",
+        content: "COALESCE({row}result, '')",
+        columns: "workflow_id, result",
+    },
+];
+
+impl Source {
+    fn workflow_id(&self, row: &str) -> String {
+        self.workflow_id.replace("{row}", row)
+    }
+
+    fn content(&self, row: &str) -> String {
+        self.content.replace("{row}", row)
+    }
+}
+
+fn sqlite_up() -> Vec<String> {
+    let mut statements = vec![
+        "CREATE VIRTUAL TABLE IF NOT EXISTS workflow_search USING fts5(\
+         kind UNINDEXED, source_id UNINDEXED, workflow_id UNINDEXED, content)"
+            .to_string(),
+    ];
+    for source in &SOURCES {
+        let Source { table, kind, .. } = source;
+        let insert = format!(
+            "INSERT INTO workflow_search (kind, source_id, workflow_id, content) \
+             VALUES ('{kind}', NEW.id, {}, {});",
+            source.workflow_id("NEW."),
+            source.content("NEW."),
+        );
+        let delete =
+            format!("DELETE FROM workflow_search WHERE kind = '{kind}' AND source_id = OLD.id;");
+        // Index the rows stored before this migration.
+        statements.push(format!(
+            "INSERT INTO workflow_search (kind, source_id, workflow_id, content) \
+             SELECT '{kind}', id, {}, {} FROM {table}",
+            source.workflow_id(""),
+            source.content("")
+        ));
+        statements.push(format!(
+            "CREATE TRIGGER IF NOT EXISTS workflow_search_{kind}_insert \
+             AFTER INSERT ON {table} BEGIN {insert} END"
+        ));
+        statements.push(format!(
+            "CREATE TRIGGER IF NOT EXISTS workflow_search_{kind}_update \
+             AFTER UPDATE OF {} ON {table} BEGIN {delete} {insert} END",
+            source.columns
+        ));
+        statements.push(format!(
+            "CREATE TRIGGER IF NOT EXISTS workflow_search_{kind}_delete \
+             AFTER DELETE ON {table} BEGIN {delete} END"
+        ));
+    }
+    statements
+}
+
+fn sqlite_down() -> Vec<String> {
+    let mut statements: Vec<String> = SOURCES
+        .iter()
+        .flat_map(|source| {
+            ["insert", "update", "delete"].map(|event| {
+                format!(
+                    "DROP TRIGGER IF EXISTS workflow_search_{}_{event}",
+                    source.kind
+                )
+            })
+        })
+        .collect();
+    statements.push("DROP TABLE IF EXISTS workflow_search".to_string());
+    statements
+}
+
+fn postgres_up() -> Vec<String> {
+    SOURCES
+        .iter()
+        .map(|source| {
+            format!(
+                "CREATE INDEX IF NOT EXISTS idx_{}_search ON {} \
+                 USING GIN (to_tsvector('simple', {}))",
+                source.table,
+                source.table,
+                source.content("")
+            )
+        })
+        .collect()
+}
+
+fn postgres_down() -> Vec<String> {
+    SOURCES
+        .iter()
+        .map(|source| format!("DROP INDEX IF EXISTS idx_{}_search", source.table))
+        .collect()
+}
+
+async fn execute_all(manager: &SchemaManager<'_>, statements: Vec<String>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    for statement in statements {
+        db.execute_unprepared(&statement).await?;
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Sqlite => execute_all(manager, sqlite_up()).await,
+            DatabaseBackend::Postgres => execute_all(manager, postgres_up()).await,
+            DatabaseBackend::MySql => Ok(()),
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Sqlite => execute_all(manager, sqlite_down()).await,
+            DatabaseBackend::Postgres => execute_all(manager, postgres_down()).await,
+            DatabaseBackend::MySql => Ok(()),
+        }
+    }
+}
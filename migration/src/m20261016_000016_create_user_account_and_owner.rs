@@ -0,0 +1,118 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+/*
+-- user_account
+-- Users that own data, keyed by the subject their requests authenticate as
+-- (a token name, "uid:1000" or an OIDC "sub" claim).
+CREATE TABLE user_account (
+    id TEXT NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP,
+    last_seen_at TIMESTAMP
+);
+
+-- owner_id
+-- The user_account a row belongs to; NULL for rows created before users existed or
+-- by anonymous callers, which every caller can see. SQLite cannot add a foreign key
+-- to an existing table, so the column is not constrained.
+ALTER TABLE workflow ADD COLUMN owner_id TEXT;
+CREATE INDEX idx_workflow_owner_id ON workflow(owner_id);
+ALTER TABLE workflow_schedule ADD COLUMN owner_id TEXT;
+CREATE INDEX idx_workflow_schedule_owner_id ON workflow_schedule(owner_id);
+*/
+use sea_orm_migration::prelude::*;
+
+use crate::columns::utc_timestamp;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+async fn add_owner_column(manager: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
+    // Databases whose tables were created outside the migrator may have the column.
+    if !manager.has_column(table, "owner_id").await? {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new(table))
+                    .add_column(ColumnDef::new(Owned::OwnerId).string().null())
+                    .to_owned(),
+            )
+            .await?;
+    }
+    manager
+        .create_index(
+            Index::create()
+                .name(format!("idx_{table}_owner_id"))
+                .table(Alias::new(table))
+                .col(Owned::OwnerId)
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await
+}
+
+async fn drop_owner_column(manager: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
+    manager
+        .drop_index(
+            Index::drop()
+                .name(format!("idx_{table}_owner_id"))
+                .table(Alias::new(table))
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .alter_table(
+            Table::alter()
+                .table(Alias::new(table))
+                .drop_column(Owned::OwnerId)
+                .to_owned(),
+        )
+        .await
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserAccount::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserAccount::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(utc_timestamp(manager, UserAccount::CreatedAt).null())
+                    .col(utc_timestamp(manager, UserAccount::LastSeenAt).null())
+                    .to_owned(),
+            )
+            .await?;
+
+        add_owner_column(manager, "workflow").await?;
+        add_owner_column(manager, "workflow_schedule").await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_owner_column(manager, "workflow_schedule").await?;
+        drop_owner_column(manager, "workflow").await?;
+        manager
+            .drop_table(Table::drop().table(UserAccount::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserAccount {
+    Table,
+    Id,
+    CreatedAt,
+    LastSeenAt,
+}
+
+#[derive(DeriveIden)]
+enum Owned {
+    OwnerId,
+}
@@ -0,0 +1,233 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Matching of denied resources against what a plugin call actually accesses
+//
+// A denial must hold however the resource is spelled, so both sides are normalized first:
+// - paths are made absolute, `.`, `..` and repeated separators are removed, and symlinks
+//   are resolved in the part of the path that exists,
+// - URLs and hosts are parsed as URLs, so case, IDNA, IP address spellings, brackets
+//   and a trailing dot do not matter.
+
+use sapphillon_core::proto::sapphillon::v1::PermissionType;
+use std::ffi::OsString;
+use std::net::Ipv6Addr;
+use std::path::{Component, Path, PathBuf};
+use url::{Host, Url};
+
+/// Returns whether the denied resource `pattern` covers `resource`, accessed with a
+/// permission of `permission_type`.
+pub(crate) fn denied_resource_matches(permission_type: i32, pattern: &str, resource: &str) -> bool {
+    if pattern == resource {
+        return true;
+    }
+    if permission_type == PermissionType::FilesystemRead as i32
+        || permission_type == PermissionType::FilesystemWrite as i32
+    {
+        path_matches(pattern, resource)
+    } else if permission_type == PermissionType::NetAccess as i32 {
+        url_matches(pattern, resource)
+    } else {
+        literal_matches(pattern, resource)
+    }
+}
+
+/// Matches resources that are neither paths nor URLs, such as commands, by prefix.
+fn literal_matches(pattern: &str, resource: &str) -> bool {
+    resource.strip_prefix(pattern).is_some_and(|rest| {
+        pattern.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?', '#'])
+    })
+}
+
+fn path_matches(pattern: &str, resource: &str) -> bool {
+    match (resolve_path(pattern), resolve_path(resource)) {
+        (Some(pattern), Some(resource)) => resource.starts_with(pattern),
+        _ => false,
+    }
+}
+
+/// Returns the path the operating system would access for `path`.
+///
+/// The longest existing part of the path is canonicalized, which resolves symlinks and
+/// the `..` components inside it; the rest is normalized lexically.
+fn resolve_path(path: &str) -> Option<PathBuf> {
+    if path.is_empty() {
+        return None;
+    }
+    let absolute = std::path::absolute(path).ok()?;
+    let mut existing: &Path = &absolute;
+    let mut missing: Vec<OsString> = Vec::new();
+    let mut resolved = loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            break canonical;
+        }
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(last)) => {
+                missing.push(last.as_os_str().to_os_string());
+                existing = parent;
+            }
+            _ => break PathBuf::new(),
+        }
+    };
+    for part in missing.iter().rev() {
+        match Path::new(part).components().next() {
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            }
+            Some(Component::CurDir) | None => {}
+            Some(component) => resolved.push(component),
+        }
+    }
+    Some(resolved)
+}
+
+fn url_matches(pattern: &str, resource: &str) -> bool {
+    let Some(url) = parse_url(resource) else {
+        return false;
+    };
+    if !pattern.contains("://") {
+        // A bare host covers that host and its subdomains on every scheme and port.
+        return match (parse_url(pattern).as_ref().and_then(host_of), host_of(&url)) {
+            (Some(pattern), Some(host)) => host_covers(&pattern, &host),
+            _ => false,
+        };
+    }
+    let Some(pattern) = parse_url(pattern) else {
+        return false;
+    };
+    pattern.scheme() == url.scheme()
+        && host_of(&pattern).is_some()
+        && host_of(&pattern) == host_of(&url)
+        && pattern.port_or_known_default() == url.port_or_known_default()
+        && path_covers(pattern.path(), url.path())
+}
+
+/// Parses a URL, or a bare `host[:port][/path]` as an `http` URL.
+fn parse_url(resource: &str) -> Option<Url> {
+    if resource.contains("://") {
+        return Url::parse(resource).ok();
+    }
+    // A bare IPv6 address needs brackets to be read as a host.
+    let resource = match resource.parse::<Ipv6Addr>() {
+        Ok(address) => format!("[{address}]"),
+        Err(_) => resource.to_string(),
+    };
+    Url::parse(&format!("http://{resource}")).ok()
+}
+
+/// The host of `url`, with a trailing dot removed from domain names.
+fn host_of(url: &Url) -> Option<Host<String>> {
+    match url.host()? {
+        Host::Domain(domain) => Some(Host::Domain(
+            domain.trim_end_matches('.').to_ascii_lowercase(),
+        )),
+        Host::Ipv4(address) => Some(Host::Ipv4(address)),
+        Host::Ipv6(address) => Some(Host::Ipv6(address)),
+    }
+}
+
+fn host_covers(pattern: &Host<String>, host: &Host<String>) -> bool {
+    match (pattern, host) {
+        (Host::Domain(pattern), Host::Domain(host)) => {
+            !pattern.is_empty() && (host == pattern || host.ends_with(&format!(".{pattern}")))
+        }
+        _ => pattern == host,
+    }
+}
+
+fn path_covers(pattern: &str, path: &str) -> bool {
+    if pattern.is_empty() || pattern == "/" {
+        return true;
+    }
+    path.strip_prefix(pattern)
+        .is_some_and(|rest| rest.is_empty() || pattern.ends_with('/') || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(pattern: &str, resource: &str) -> bool {
+        denied_resource_matches(PermissionType::FilesystemRead as i32, pattern, resource)
+    }
+
+    fn net(pattern: &str, resource: &str) -> bool {
+        denied_resource_matches(PermissionType::NetAccess as i32, pattern, resource)
+    }
+
+    #[test]
+    fn test_paths_are_normalized_before_matching() {
+        assert!(read("/etc", "/etc/passwd"));
+        assert!(read("/etc", "/tmp/../etc/passwd"));
+        assert!(read("/etc", "/etc//passwd"));
+        assert!(read("/etc", "/etc/./passwd"));
+        assert!(read("/etc/", "/tmp/../etc/does-not-exist/../passwd"));
+        assert!(!read("/etc", "/etcetera"));
+        assert!(!read("/etc", "/etc/../tmp/file"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_resolved_before_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("secret");
+        std::fs::create_dir(&secret).unwrap();
+        std::fs::write(secret.join("key"), "k").unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&secret, &link).unwrap();
+
+        let pattern = secret.to_str().unwrap();
+        assert!(read(pattern, link.join("key").to_str().unwrap()));
+        assert!(read(pattern, link.join("new-file").to_str().unwrap()));
+        assert!(!read(pattern, dir.path().join("other").to_str().unwrap()));
+    }
+
+    #[test]
+    fn test_hosts_are_normalized_before_matching() {
+        assert!(net("evil.com", "https://evil.com/"));
+        assert!(net("evil.com", "https://evil.com./"));
+        assert!(net("evil.com", "https://EVIL.com:8443/x"));
+        assert!(net("evil.com", "https://api.evil.com./x"));
+        assert!(net("evil.com.", "https://evil.com/"));
+        assert!(net("evil.com", "https://user@evil.com/"));
+        assert!(!net("evil.com", "https://notevil.com/"));
+        assert!(!net("evil.com", "https://evil.com.example/"));
+
+        assert!(net("::1", "http://[::1]:8080/"));
+        assert!(net("[::1]", "http://[0:0:0:0:0:0:0:1]/"));
+        assert!(net("127.0.0.1", "http://127.1/"));
+        assert!(!net("::1", "http://[::2]/"));
+    }
+
+    #[test]
+    fn test_url_patterns_match_scheme_host_port_and_path() {
+        assert!(net(
+            "https://example.com/admin",
+            "https://example.com./admin/users"
+        ));
+        assert!(net(
+            "https://example.com/admin",
+            "https://example.com:443/public/../admin"
+        ));
+        assert!(!net(
+            "https://example.com/admin",
+            "https://example.com/administrator"
+        ));
+        assert!(!net(
+            "https://example.com/admin",
+            "http://example.com/admin"
+        ));
+        assert!(!net(
+            "https://example.com/admin",
+            "https://example.com:8443/admin"
+        ));
+    }
+
+    #[test]
+    fn test_other_resources_match_literally() {
+        let exec = PermissionType::Execute as i32;
+        assert!(denied_resource_matches(exec, "rm", "rm"));
+        assert!(!denied_resource_matches(exec, "rm", "rmdir"));
+    }
+}
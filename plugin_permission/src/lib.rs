@@ -0,0 +1,995 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Shared permission helpers for the built-in plugins
+//
+// Precedence when a plugin function checks a permission:
+// 1. A matching denied permission fails the call, even when it is also allowed.
+// 2. Otherwise an allowed permission at a sufficient level lets the call through.
+// 3. Otherwise a limited grant that has not expired lets the call through.
+// 4. Otherwise a person is asked, if a `PermissionRequester` is installed.
+//
+// Permission levels, in increasing order:
+// - Unspecified: read-only metadata, e.g. window titles or directory listings.
+// - Medium: reads user data or talks to the network.
+// - High: changes or sends data, runs programs or exposes secrets.
+// A function declares the level it needs; an allowed permission with a level grants
+// up to that level, and one without a level grants every level.
+//
+// Everything not allowed is denied: a function without an allowed entry of its own or
+// for `*` can only use permissions a person grants it.
+//
+// In a dry run every function that needs a permission fails before any of these checks
+// with an error starting with `DRY_RUN_STUB`, so it never does its work.
+use deno_core::OpState;
+use deno_error::JsErrorBox;
+use sapphillon_core::permission::{
+    CheckPermissionResult, Permissions, PluginFunctionPermissions, check_permission,
+};
+use sapphillon_core::proto::sapphillon::v1::{
+    AllowedPermission, Permission, PermissionLevel, PermissionType,
+};
+use sapphillon_core::runtime::OpStateWorkflowData;
+use std::cell::{Cell, RefCell};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+mod denied_resource;
+mod net_access;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// A permission a running workflow is missing, sent to a [`PermissionRequester`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionRequest {
+    pub workflow_id: String,
+    pub workflow_code_id: String,
+    pub plugin_function_id: String,
+    /// Permissions the function needs, with the accessed resource filled in.
+    pub permissions: Vec<Permission>,
+}
+
+/// Asks a person whether a running workflow may use a permission it was not given.
+pub trait PermissionRequester: Send + Sync {
+    /// Blocks until the request is answered; returns `false` when it is denied or
+    /// nobody answers in time.
+    fn request(&self, request: &PermissionRequest) -> anyhow::Result<bool>;
+}
+
+/// A permission a workflow code must never use, even when it is also allowed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeniedPermission {
+    /// Function the denial applies to, or `*` for every function.
+    pub plugin_function_id: String,
+    /// `PermissionType` value of the denied permission.
+    pub permission_type: i32,
+    /// Denied resource patterns; empty denies every resource.
+    ///
+    /// A pattern matches a resource equal to it or below it (`/home/me` covers
+    /// `/home/me/notes.txt`, `https://example.com/admin` covers `https://example.com/admin/users`).
+    /// A bare host matches URLs on that host and its subdomains, so `internal.corp`
+    /// covers `https://wiki.internal.corp/page`. Paths and URLs are normalized before
+    /// they are compared, so `/tmp/../etc/passwd` and symlinks into a denied directory
+    /// are denied too.
+    pub resource: Vec<String>,
+}
+
+/// A permission granted to a workflow code until it expires or has been used by a
+/// number of runs; the run only receives grants that were still valid when it started,
+/// and claims a use of a grant the first time a call relies on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitedGrant {
+    /// Reported back in [`PermissionUsage::used_grants`] when a call relied on the grant.
+    pub id: String,
+    /// Function the grant applies to, or `*` for every function.
+    pub plugin_function_id: String,
+    pub permission: Permission,
+    /// When the grant stops applying, also in the middle of a run.
+    pub expires_at: Option<SystemTime>,
+}
+
+/// What a run did with its permissions, returned by [`run_with_permission_context`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PermissionUsage {
+    /// Permissions a person granted while the run was waiting on a request.
+    pub granted: Vec<AllowedPermission>,
+    /// IDs of the limited grants the run claimed a use of, each listed once.
+    pub used_grants: Vec<String>,
+}
+
+impl DeniedPermission {
+    fn denies(&self, plugin_function_id: &str, permission: &Permission) -> bool {
+        (self.plugin_function_id == "*" || self.plugin_function_id == plugin_function_id)
+            && self.permission_type == permission.permission_type
+            && (self.resource.is_empty()
+                || self.resource.iter().any(|pattern| {
+                    permission.resource.iter().any(|resource| {
+                        denied_resource::denied_resource_matches(
+                            self.permission_type,
+                            pattern,
+                            resource,
+                        )
+                    })
+                }))
+    }
+}
+
+/// Describes a permission for people, e.g. `FilesystemRead on /tmp/report.txt`.
+pub fn describe_permission(permission: &Permission) -> String {
+    let mut kind = PermissionType::try_from(permission.permission_type)
+        .map(|kind| format!("{kind:?}"))
+        .unwrap_or_else(|_| format!("permission type {}", permission.permission_type));
+    if permission.permission_level != PermissionLevel::Unspecified as i32 {
+        let level = PermissionLevel::try_from(permission.permission_level)
+            .map(|level| format!("{level:?}"))
+            .unwrap_or_else(|_| permission.permission_level.to_string());
+        kind = format!("{kind} ({level})");
+    }
+    if permission.resource.is_empty() {
+        kind
+    } else {
+        format!("{kind} on {}", permission.resource.join(", "))
+    }
+}
+
+/// Returns the first of `required` whose level is above every allowed permission of
+/// the same type.
+///
+/// Allowed permissions without a level grant every level, so grants made before levels
+/// were enforced keep working. Types with no allowed permission are left to the
+/// regular permission check.
+pub fn find_insufficient_level(
+    allowed: &[Permission],
+    required: &[Permission],
+) -> Option<Permission> {
+    required
+        .iter()
+        .find(|required| {
+            let mut same_type = allowed
+                .iter()
+                .filter(|allowed| allowed.permission_type == required.permission_type)
+                .peekable();
+            same_type.peek().is_some()
+                && !same_type.any(|allowed| {
+                    allowed.permission_level == PermissionLevel::Unspecified as i32
+                        || allowed.permission_level >= required.permission_level
+                })
+        })
+        .cloned()
+}
+
+/// Parses a permission type written as `net-access`, `net_access` or
+/// `PERMISSION_TYPE_NET_ACCESS` into its `PermissionType` value.
+pub fn parse_permission_type(name: &str) -> Option<i32> {
+    let name = name.trim().to_uppercase().replace('-', "_");
+    let name = if name.starts_with("PERMISSION_TYPE_") {
+        name
+    } else {
+        format!("PERMISSION_TYPE_{name}")
+    };
+    PermissionType::from_str_name(&name).map(|kind| kind as i32)
+}
+
+/// Parses a permission level written as `medium` or `PERMISSION_LEVEL_MEDIUM` into its
+/// `PermissionLevel` value.
+pub fn parse_permission_level(name: &str) -> Option<i32> {
+    let name = name.trim().to_uppercase();
+    let name = if name.starts_with("PERMISSION_LEVEL_") {
+        name
+    } else {
+        format!("PERMISSION_LEVEL_{name}")
+    };
+    PermissionLevel::from_str_name(&name).map(|level| level as i32)
+}
+
+static PERMISSION_REQUESTER: OnceLock<Arc<dyn PermissionRequester>> = OnceLock::new();
+
+/// Enables permission prompting. Only the first call takes effect.
+///
+/// Until a requester is installed, missing permissions fail the plugin call.
+pub fn set_permission_requester(requester: Arc<dyn PermissionRequester>) -> bool {
+    PERMISSION_REQUESTER.set(requester).is_ok()
+}
+
+struct RunContext {
+    workflow_id: String,
+    workflow_code_id: String,
+    denied: Vec<DeniedPermission>,
+    grants: Vec<LimitedGrant>,
+    claim_grant: Box<dyn FnMut(&str) -> bool>,
+    usage: PermissionUsage,
+}
+
+thread_local! {
+    static RUN_CONTEXT: RefCell<Option<RunContext>> = const { RefCell::new(None) };
+    static DRY_RUN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Prefix of the error message a plugin call fails with in a dry run, followed by the
+/// plugin function ID. The dry-run prelude turns these failures into recorded calls.
+pub const DRY_RUN_STUB: &str = "::sapphillon-dry-run-stub::";
+
+/// Runs `run` as a dry run: plugin functions called on this thread that need a
+/// permission, or call [`stub_in_dry_run`] themselves, fail without doing anything.
+pub fn run_as_dry_run<R>(run: impl FnOnce() -> R) -> R {
+    let previous = DRY_RUN.with(|dry_run| dry_run.replace(true));
+    let value = run();
+    DRY_RUN.with(|dry_run| dry_run.set(previous));
+    value
+}
+
+/// Fails the call of `plugin_function_id` when it is part of a dry run.
+///
+/// [`check_allowed_permissions`] does this for every function that needs a permission;
+/// functions that change something without needing one call it before doing so.
+pub fn stub_in_dry_run(plugin_function_id: &str) -> Result<(), JsErrorBox> {
+    if DRY_RUN.with(Cell::get) {
+        return Err(JsErrorBox::generic(format!(
+            "{DRY_RUN_STUB}{plugin_function_id}"
+        )));
+    }
+    Ok(())
+}
+
+/// Runs `run` as the given workflow code, applying `denied` and `grants` and letting
+/// plugins called on this thread ask for missing permissions.
+///
+/// `claim_grant` is called with the ID of a grant before the run first relies on it
+/// and returns whether a use could still be counted; a grant used up by other runs in
+/// the meantime no longer applies.
+///
+/// The JS runtime runs its ops on the thread that runs the workflow, so `run` must
+/// execute the workflow itself rather than hand it to another thread.
+///
+/// # Returns
+///
+/// Returns the value of `run` and how the run used its permissions.
+pub fn run_with_permission_context<R>(
+    workflow_id: &str,
+    workflow_code_id: &str,
+    denied: Vec<DeniedPermission>,
+    grants: Vec<LimitedGrant>,
+    claim_grant: impl FnMut(&str) -> bool + 'static,
+    run: impl FnOnce() -> R,
+) -> (R, PermissionUsage) {
+    let previous = RUN_CONTEXT.with(|context| {
+        context.borrow_mut().replace(RunContext {
+            workflow_id: workflow_id.to_string(),
+            workflow_code_id: workflow_code_id.to_string(),
+            denied,
+            grants,
+            claim_grant: Box::new(claim_grant),
+            usage: PermissionUsage::default(),
+        })
+    });
+    let value = run();
+    let context =
+        RUN_CONTEXT.with(|context| std::mem::replace(&mut *context.borrow_mut(), previous));
+    (value, context.map(|c| c.usage).unwrap_or_default())
+}
+
+/// Returns the limited grants of the current run that apply to `plugin_function_id` now.
+fn active_grants(plugin_function_id: &str) -> Vec<LimitedGrant> {
+    let now = SystemTime::now();
+    RUN_CONTEXT.with(|context| {
+        context.borrow().as_ref().map_or_else(Vec::new, |context| {
+            context
+                .grants
+                .iter()
+                .filter(|grant| {
+                    (grant.plugin_function_id == "*"
+                        || grant.plugin_function_id == plugin_function_id)
+                        && grant.expires_at.is_none_or(|expires_at| now < expires_at)
+                })
+                .cloned()
+                .collect()
+        })
+    })
+}
+
+/// Claims a use of a grant for the current run, once per run.
+///
+/// Returns `false` and drops the grant from the run when it is used up.
+fn claim_grant(grant_id: &str) -> bool {
+    RUN_CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let Some(context) = context.as_mut() else {
+            return false;
+        };
+        if context.usage.used_grants.iter().any(|id| id == grant_id) {
+            return true;
+        }
+        if (context.claim_grant)(grant_id) {
+            context.usage.used_grants.push(grant_id.to_string());
+            true
+        } else {
+            context.grants.retain(|grant| grant.id != grant_id);
+            false
+        }
+    })
+}
+
+/// Returns the first of `permissions` denied to `plugin_function_id` in the current run.
+///
+/// Denials take precedence over allowed permissions and are never asked about.
+pub fn find_denied_permission(
+    plugin_function_id: &str,
+    permissions: &[Permission],
+) -> Option<Permission> {
+    RUN_CONTEXT.with(|context| {
+        let context = context.borrow();
+        let context = context.as_ref()?;
+        permissions
+            .iter()
+            .find(|permission| {
+                context
+                    .denied
+                    .iter()
+                    .any(|denied| denied.denies(plugin_function_id, permission))
+            })
+            .cloned()
+    })
+}
+
+/// Asks for `permissions` on behalf of `plugin_function_id`.
+///
+/// Permissions already granted earlier in the same run are not asked for again.
+/// Returns `false` without asking when no requester is installed or the call is not
+/// part of a run started with [`run_with_permission_context`].
+pub fn request_permission(plugin_function_id: &str, permissions: &[Permission]) -> bool {
+    let Some(requester) = PERMISSION_REQUESTER.get() else {
+        return false;
+    };
+    request_permission_from(requester.as_ref(), plugin_function_id, permissions)
+}
+
+/// Asks `requester` for `permissions` like [`request_permission`] asks the installed one.
+fn request_permission_from(
+    requester: &dyn PermissionRequester,
+    plugin_function_id: &str,
+    permissions: &[Permission],
+) -> bool {
+    let request = RUN_CONTEXT.with(|context| {
+        let context = context.borrow();
+        let context = context.as_ref()?;
+        Some(PermissionRequest {
+            workflow_id: context.workflow_id.clone(),
+            workflow_code_id: context.workflow_code_id.clone(),
+            plugin_function_id: plugin_function_id.to_string(),
+            permissions: permissions.to_vec(),
+        })
+    });
+    let Some(request) = request else {
+        return false;
+    };
+    if already_granted(&request) {
+        return true;
+    }
+
+    let granted = match requester.request(&request) {
+        Ok(granted) => granted,
+        Err(err) => {
+            warn!("Permission request for {plugin_function_id} failed: {err:#}");
+            false
+        }
+    };
+    if granted {
+        RUN_CONTEXT.with(|context| {
+            if let Some(context) = context.borrow_mut().as_mut() {
+                context.usage.granted.push(AllowedPermission {
+                    plugin_function_id: request.plugin_function_id,
+                    permissions: request.permissions,
+                });
+            }
+        });
+    }
+    granted
+}
+
+/// Checks that the running workflow may call `plugin_function_id` with
+/// `required_permissions`, asking a person for missing ones when possible.
+///
+/// `resource` (a path, URL, ...) is filled into required permissions that do not name
+/// one, so allowed and denied permissions can be scoped to it. Every built-in plugin
+/// calls this before doing any work.
+pub fn ensure_permission(
+    state: &mut OpState,
+    plugin_function_id: &str,
+    required_permissions: Vec<Permission>,
+    resource: &str,
+) -> Result<(), JsErrorBox> {
+    // Copied out so the workflow data is not locked while a person answers a request.
+    let allowed = state
+        .borrow::<Arc<Mutex<OpStateWorkflowData>>>()
+        .lock()
+        .unwrap()
+        .get_allowed_permissions()
+        .clone()
+        .unwrap_or_default();
+    check_allowed_permissions(&allowed, plugin_function_id, required_permissions, resource)
+}
+
+/// The checks of [`ensure_permission`] against an explicit list of allowed permissions.
+pub fn check_allowed_permissions(
+    allowed: &[PluginFunctionPermissions],
+    plugin_function_id: &str,
+    required_permissions: Vec<Permission>,
+    resource: &str,
+) -> Result<(), JsErrorBox> {
+    if !required_permissions.is_empty() {
+        stub_in_dry_run(plugin_function_id)?;
+    }
+
+    let required_permissions: Vec<Permission> = required_permissions
+        .into_iter()
+        .map(|mut p| {
+            if !resource.is_empty() && p.resource.is_empty() {
+                p.resource = vec![resource.to_string()];
+            }
+            p
+        })
+        .collect();
+
+    // Denied permissions win over allowed ones and are never asked for.
+    if let Some(denied) = find_denied_permission(plugin_function_id, &required_permissions) {
+        return Err(JsErrorBox::new(
+            "PermissionDenied. Denied Permission:",
+            describe_permission(&denied),
+        ));
+    }
+
+    let allowed_permissions = allowed
+        .iter()
+        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
+        .map(|p| p.permissions.clone())
+        .unwrap_or_else(|| Permissions::new(vec![]));
+
+    let mut shortfall = find_shortfall(&allowed_permissions, &required_permissions);
+    // Each failed claim drops a grant, so this ends once the grants are claimed or
+    // no longer cover the call.
+    while shortfall.is_some() {
+        let grants = active_grants(plugin_function_id);
+        let Some(needed) =
+            grants_closing_shortfall(&allowed_permissions, &required_permissions, grants)
+        else {
+            break;
+        };
+        if needed.iter().all(|grant| claim_grant(&grant.id)) {
+            shortfall = None;
+        }
+    }
+
+    match shortfall {
+        None => Ok(()),
+        // Give a person the chance to grant the permission before failing the call.
+        Some(_) if request_permission(plugin_function_id, &required_permissions) => Ok(()),
+        Some(Shortfall::Missing(missing)) => Err(JsErrorBox::new(
+            "PermissionDenied. Missing Permissions:",
+            missing,
+        )),
+        Some(Shortfall::Level(permission)) => Err(JsErrorBox::new(
+            "PermissionDenied. Insufficient Permission Level:",
+            describe_permission(&permission),
+        )),
+    }
+}
+
+/// Returns whether the allowed permissions cover `required` for a function, without
+/// asking anyone. Denied permissions and limited grants are not considered.
+pub fn allowed_permissions_cover(
+    allowed: &[AllowedPermission],
+    plugin_function_id: &str,
+    required: &[Permission],
+) -> bool {
+    let allowed_permissions = allowed
+        .iter()
+        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
+        .map(|p| Permissions::new(p.permissions.clone()))
+        .unwrap_or_else(|| Permissions::new(vec![]));
+    find_shortfall(&allowed_permissions, required).is_none()
+}
+
+/// Returns the grants that close the shortfall of `allowed` for `required`, or `None`
+/// when all of them together do not.
+///
+/// Grants the call can do without are left out, so a run only uses up the grants it
+/// actually relied on.
+fn grants_closing_shortfall(
+    allowed: &Permissions,
+    required: &[Permission],
+    grants: Vec<LimitedGrant>,
+) -> Option<Vec<LimitedGrant>> {
+    let covers = |grants: &[LimitedGrant]| {
+        let mut with_grants = allowed.clone();
+        with_grants
+            .permissions
+            .extend(grants.iter().map(|grant| grant.permission.clone()));
+        find_shortfall(&with_grants, required).is_none()
+    };
+    if grants.is_empty() || !covers(&grants) {
+        return None;
+    }
+    let mut needed = grants;
+    let mut index = 0;
+    while index < needed.len() {
+        let mut without = needed.clone();
+        without.remove(index);
+        if covers(&without) {
+            needed = without;
+        } else {
+            index += 1;
+        }
+    }
+    Some(needed)
+}
+
+/// Why allowed permissions do not cover a call.
+enum Shortfall {
+    /// A required permission is not allowed at all.
+    Missing(String),
+    /// A required permission is only allowed at a lower level.
+    Level(Permission),
+}
+
+fn find_shortfall(allowed: &Permissions, required: &[Permission]) -> Option<Shortfall> {
+    // URLs are matched here; the core check compares resources as plain strings.
+    let (net_required, other_required): (Vec<Permission>, Vec<Permission>) = required
+        .iter()
+        .cloned()
+        .partition(|p| p.permission_type == PermissionType::NetAccess as i32);
+    if let Some(missing) = net_required
+        .iter()
+        .find(|required| !net_access_allowed(&allowed.permissions, required))
+    {
+        return Some(Shortfall::Missing(describe_permission(missing)));
+    }
+    if let CheckPermissionResult::MissingPermission(perm) =
+        check_permission(allowed, &Permissions::new(other_required))
+    {
+        return Some(Shortfall::Missing(perm.to_string()));
+    }
+    find_insufficient_level(&allowed.permissions, required).map(Shortfall::Level)
+}
+
+/// Returns whether an allowed NetAccess permission covers every URL of `required`.
+fn net_access_allowed(allowed: &[Permission], required: &Permission) -> bool {
+    allowed
+        .iter()
+        .filter(|allowed| allowed.permission_type == required.permission_type)
+        .any(|allowed| {
+            allowed.resource.is_empty()
+                || required.resource.iter().all(|url| {
+                    allowed
+                        .resource
+                        .iter()
+                        .any(|pattern| net_access::url_pattern_matches(pattern, url))
+                })
+        })
+}
+
+fn already_granted(request: &PermissionRequest) -> bool {
+    RUN_CONTEXT.with(|context| {
+        context.borrow().as_ref().is_some_and(|context| {
+            request.permissions.iter().all(|permission| {
+                context.usage.granted.iter().any(|grant| {
+                    grant.plugin_function_id == request.plugin_function_id
+                        && grant.permissions.contains(permission)
+                })
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Grants every request except those for `app.denied`, remembering what was asked.
+    struct RecordingRequester {
+        asked: Mutex<Vec<PermissionRequest>>,
+    }
+
+    impl PermissionRequester for RecordingRequester {
+        fn request(&self, request: &PermissionRequest) -> anyhow::Result<bool> {
+            self.asked.lock().unwrap().push(request.clone());
+            Ok(request.plugin_function_id != "app.denied")
+        }
+    }
+
+    fn read_permission(resource: &str) -> Permission {
+        Permission {
+            display_name: "Read".to_string(),
+            description: "".to_string(),
+            permission_type: PermissionType::FilesystemRead as i32,
+            permission_level: PermissionLevel::Unspecified as i32,
+            resource: vec![resource.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_request_permission_asks_once_per_run() {
+        let requester = Arc::new(RecordingRequester {
+            asked: Mutex::new(Vec::new()),
+        });
+        let permissions = vec![read_permission("/tmp/a")];
+        // Not installed: the installed requester is global and would answer the
+        // requests of tests running in parallel.
+        let request = |plugin_function_id: &str| {
+            request_permission_from(requester.as_ref(), plugin_function_id, &permissions)
+        };
+
+        assert!(!request("app.read"));
+        assert!(requester.asked.lock().unwrap().is_empty());
+
+        let (results, usage) = run_with_permission_context(
+            "wf",
+            "wc",
+            vec![],
+            vec![],
+            |_| true,
+            || {
+                [
+                    request("app.read"),
+                    request("app.read"),
+                    request("app.denied"),
+                ]
+            },
+        );
+        assert_eq!(results, [true, true, false]);
+        assert_eq!(
+            usage.granted,
+            vec![AllowedPermission {
+                plugin_function_id: "app.read".to_string(),
+                permissions: permissions.clone(),
+            }]
+        );
+
+        let asked = requester.asked.lock().unwrap();
+        assert_eq!(asked.len(), 2);
+        assert_eq!(asked[0].workflow_id, "wf");
+        assert_eq!(asked[0].workflow_code_id, "wc");
+    }
+
+    #[test]
+    fn test_find_insufficient_level_compares_levels_per_type() {
+        let with_level = |permission_type: PermissionType, level: PermissionLevel| Permission {
+            permission_type: permission_type as i32,
+            permission_level: level as i32,
+            ..read_permission("/tmp")
+        };
+        let write_high = with_level(PermissionType::FilesystemWrite, PermissionLevel::High);
+        let read_medium = with_level(PermissionType::FilesystemRead, PermissionLevel::Medium);
+
+        let capped = [with_level(
+            PermissionType::FilesystemWrite,
+            PermissionLevel::Medium,
+        )];
+        assert_eq!(
+            find_insufficient_level(&capped, &[write_high.clone()]),
+            Some(write_high.clone())
+        );
+        assert_eq!(
+            find_insufficient_level(&capped, &[read_medium.clone()]),
+            None
+        );
+
+        let uncapped = [with_level(
+            PermissionType::FilesystemWrite,
+            PermissionLevel::Unspecified,
+        )];
+        assert_eq!(
+            find_insufficient_level(&uncapped, &[write_high.clone()]),
+            None
+        );
+
+        let high = [with_level(
+            PermissionType::FilesystemWrite,
+            PermissionLevel::High,
+        )];
+        assert_eq!(find_insufficient_level(&high, &[write_high.clone()]), None);
+        assert_eq!(
+            describe_permission(&write_high),
+            "FilesystemWrite (High) on /tmp"
+        );
+    }
+
+    #[test]
+    fn test_parse_permission_type_accepts_cli_spellings() {
+        let net = Some(PermissionType::NetAccess as i32);
+        assert_eq!(parse_permission_type("net-access"), net);
+        assert_eq!(parse_permission_type("NET_ACCESS"), net);
+        assert_eq!(parse_permission_type("PERMISSION_TYPE_NET_ACCESS"), net);
+        assert_eq!(parse_permission_type("teleport"), None);
+    }
+
+    #[test]
+    fn test_parse_permission_level_accepts_cli_spellings() {
+        let high = Some(PermissionLevel::High as i32);
+        assert_eq!(parse_permission_level("high"), high);
+        assert_eq!(parse_permission_level("PERMISSION_LEVEL_HIGH"), high);
+        assert_eq!(parse_permission_level("max"), None);
+    }
+
+    #[test]
+    fn test_denied_permissions_match_resources() {
+        let denied = vec![
+            DeniedPermission {
+                plugin_function_id: "*".to_string(),
+                permission_type: PermissionType::NetAccess as i32,
+                resource: vec!["internal.corp".to_string()],
+            },
+            DeniedPermission {
+                plugin_function_id: "app.read".to_string(),
+                permission_type: PermissionType::FilesystemRead as i32,
+                resource: vec!["/etc".to_string()],
+            },
+        ];
+        let net = |url: &str| Permission {
+            permission_type: PermissionType::NetAccess as i32,
+            resource: vec![url.to_string()],
+            ..read_permission("")
+        };
+
+        let ((), _) = run_with_permission_context(
+            "wf",
+            "wc",
+            denied,
+            vec![],
+            |_| true,
+            || {
+                assert!(
+                    find_denied_permission("app.fetch", &[net("https://internal.corp/a")])
+                        .is_some()
+                );
+                assert!(
+                    find_denied_permission("app.fetch", &[net("https://wiki.internal.corp:8443")])
+                        .is_some()
+                );
+                assert!(
+                    find_denied_permission("app.fetch", &[net("https://notinternal.corp")])
+                        .is_none()
+                );
+                assert!(
+                    find_denied_permission("app.read", &[read_permission("/etc/passwd")]).is_some()
+                );
+                assert!(
+                    find_denied_permission("app.read", &[read_permission("/etcetera")]).is_none()
+                );
+                assert!(
+                    find_denied_permission("app.other", &[read_permission("/etc/passwd")])
+                        .is_none()
+                );
+            },
+        );
+        assert!(find_denied_permission("app.read", &[read_permission("/etc/passwd")]).is_none());
+    }
+
+    #[test]
+    fn test_check_allowed_permissions_denies_by_default() {
+        let read = Permission {
+            resource: vec![],
+            ..read_permission("")
+        };
+        let required = vec![read.clone()];
+        let allowed = |function: &str| {
+            vec![PluginFunctionPermissions {
+                plugin_function_id: function.to_string(),
+                permissions: Permissions::new(vec![read.clone()]),
+            }]
+        };
+
+        assert!(check_allowed_permissions(&[], "app.read", required.clone(), "/tmp/a").is_err());
+        assert!(
+            check_allowed_permissions(
+                &allowed("app.other"),
+                "app.read",
+                required.clone(),
+                "/tmp/a"
+            )
+            .is_err()
+        );
+        assert!(
+            check_allowed_permissions(&allowed("app.read"), "app.read", required.clone(), "/tmp/a")
+                .is_ok()
+        );
+        assert!(check_allowed_permissions(&allowed("*"), "app.read", required, "/tmp/a").is_ok());
+    }
+
+    #[test]
+    fn test_dry_run_stubs_calls_that_need_a_permission() {
+        let read = read_permission("/tmp/a");
+        let allowed = vec![PluginFunctionPermissions {
+            plugin_function_id: "*".to_string(),
+            permissions: Permissions::new(vec![read.clone()]),
+        }];
+
+        let (needs_permission, needs_none) = run_as_dry_run(|| {
+            (
+                check_allowed_permissions(&allowed, "app.read", vec![read.clone()], "/tmp/a"),
+                check_allowed_permissions(&allowed, "app.pure", vec![], ""),
+            )
+        });
+        let error = needs_permission.unwrap_err().to_string();
+        assert!(
+            error.contains(&format!("{DRY_RUN_STUB}app.read")),
+            "{error}"
+        );
+        assert!(needs_none.is_ok());
+        assert!(check_allowed_permissions(&allowed, "app.read", vec![read], "/tmp/a").is_ok());
+    }
+
+    #[test]
+    fn test_allowed_permissions_cover_matches_urls_without_asking() {
+        let net = |url: &str| Permission {
+            permission_type: PermissionType::NetAccess as i32,
+            ..read_permission(url)
+        };
+        let allowed = vec![AllowedPermission {
+            plugin_function_id: "app.fetch".to_string(),
+            permissions: vec![net("https://example.com")],
+        }];
+
+        assert!(allowed_permissions_cover(
+            &allowed,
+            "app.fetch",
+            &[net("https://example.com/items")]
+        ));
+        assert!(!allowed_permissions_cover(
+            &allowed,
+            "app.fetch",
+            &[net("https://other.test/")]
+        ));
+        assert!(!allowed_permissions_cover(
+            &allowed,
+            "app.read",
+            &[read_permission("/tmp/a")]
+        ));
+    }
+
+    #[test]
+    fn test_check_allowed_permissions_matches_net_access_urls() {
+        let net = |resource: Vec<String>| Permission {
+            permission_type: PermissionType::NetAccess as i32,
+            resource,
+            ..read_permission("")
+        };
+        let allowed = vec![PluginFunctionPermissions {
+            plugin_function_id: "app.fetch".to_string(),
+            permissions: Permissions::new(vec![net(vec!["https://api.example.com".to_string()])]),
+        }];
+
+        let check = |url: &str| {
+            check_allowed_permissions(&allowed, "app.fetch", vec![net(vec![])], url).is_ok()
+        };
+        assert!(check("https://api.example.com/v1/items?id=2"));
+        assert!(!check("https://other.example.com/"));
+    }
+
+    #[test]
+    fn test_limited_grants_apply_until_they_expire() {
+        // The recording requester refuses "app.denied", so only the grants can allow it.
+        let read = Permission {
+            resource: vec![],
+            ..read_permission("")
+        };
+        let execute = Permission {
+            permission_type: PermissionType::Execute as i32,
+            ..read.clone()
+        };
+        let grants = vec![
+            LimitedGrant {
+                id: "g1".to_string(),
+                plugin_function_id: "app.denied".to_string(),
+                permission: read.clone(),
+                expires_at: None,
+            },
+            LimitedGrant {
+                id: "g2".to_string(),
+                plugin_function_id: "*".to_string(),
+                permission: execute.clone(),
+                expires_at: Some(SystemTime::now() - std::time::Duration::from_secs(1)),
+            },
+        ];
+
+        let (results, usage) = run_with_permission_context(
+            "wf",
+            "wc",
+            vec![],
+            grants,
+            |_| true,
+            || {
+                [
+                    check_allowed_permissions(&[], "app.denied", vec![read.clone()], "/tmp/a")
+                        .is_ok(),
+                    check_allowed_permissions(&[], "app.denied", vec![read.clone()], "/tmp/b")
+                        .is_ok(),
+                    check_allowed_permissions(&[], "app.denied", vec![execute], "ls").is_ok(),
+                ]
+            },
+        );
+        assert_eq!(results, [true, true, false]);
+        assert_eq!(usage.used_grants, vec!["g1".to_string()]);
+        assert!(usage.granted.is_empty());
+        assert!(check_allowed_permissions(&[], "app.denied", vec![read], "/tmp/a").is_err());
+    }
+
+    #[test]
+    fn test_only_grants_a_call_needs_are_used() {
+        let grant = |id: &str, resource: &str| LimitedGrant {
+            id: id.to_string(),
+            plugin_function_id: "app.denied".to_string(),
+            permission: read_permission(resource),
+            expires_at: None,
+        };
+        let grants = vec![grant("g-a", "/tmp/a"), grant("g-b", "/tmp/b")];
+        let read = Permission {
+            resource: vec![],
+            ..read_permission("")
+        };
+
+        let (allowed, usage) = run_with_permission_context(
+            "wf",
+            "wc",
+            vec![],
+            grants,
+            |_| true,
+            || check_allowed_permissions(&[], "app.denied", vec![read], "/tmp/a").is_ok(),
+        );
+        assert!(allowed);
+        assert_eq!(usage.used_grants, vec!["g-a".to_string()]);
+    }
+
+    #[test]
+    fn test_used_up_grants_are_not_relied_on() {
+        let grant = |id: &str| LimitedGrant {
+            id: id.to_string(),
+            plugin_function_id: "app.denied".to_string(),
+            permission: read_permission("/tmp"),
+            expires_at: None,
+        };
+        // Either grant covers the call; the last one is tried first.
+        let grants = vec![grant("g-left"), grant("g-used-up")];
+        let read = Permission {
+            resource: vec![],
+            ..read_permission("")
+        };
+
+        let claims = Arc::new(Mutex::new(Vec::new()));
+        let claimed = claims.clone();
+        let claim = move |id: &str| {
+            claimed.lock().unwrap().push(id.to_string());
+            id != "g-used-up"
+        };
+        let (results, usage) =
+            run_with_permission_context("wf", "wc", vec![], grants, claim, || {
+                [
+                    check_allowed_permissions(&[], "app.denied", vec![read.clone()], "/tmp/a")
+                        .is_ok(),
+                    check_allowed_permissions(&[], "app.denied", vec![read.clone()], "/tmp/b")
+                        .is_ok(),
+                ]
+            });
+        assert_eq!(results, [true, true]);
+        assert_eq!(usage.used_grants, vec!["g-left".to_string()]);
+        // The used up grant is dropped after its failed claim, and the other one is
+        // only claimed once per run.
+        assert_eq!(
+            *claims.lock().unwrap(),
+            vec!["g-used-up".to_string(), "g-left".to_string()]
+        );
+
+        let (allowed, usage) = run_with_permission_context(
+            "wf",
+            "wc",
+            vec![],
+            vec![grant("g-used-up")],
+            |_| false,
+            || check_allowed_permissions(&[], "app.denied", vec![read], "/tmp/a").is_ok(),
+        );
+        assert!(!allowed);
+        assert!(usage.used_grants.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// URL-aware matching of allowed NetAccess resources
+//
+// An allowed resource is a URL pattern `[scheme://]host[:port][/path]`:
+// - the scheme, when given, must match; without one any scheme matches,
+// - `*.example.com` matches every subdomain of example.com, but not example.com itself,
+// - without a port only the scheme's default port matches,
+// - the path matches itself and everything below it; query and fragment are ignored.
+// So `https://api.example.com` covers `https://api.example.com/v1/items?id=2`.
+
+struct UrlParts<'a> {
+    scheme: Option<&'a str>,
+    host: &'a str,
+    port: Option<&'a str>,
+    path: &'a str,
+}
+
+fn split_url(url: &str) -> Option<UrlParts<'_>> {
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme), rest),
+        None => (None, url),
+    };
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, tail) = rest.split_at(authority_end);
+    let host_port = authority.rsplit('@').next()?;
+    let (host, port) = match host_port.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+            (host, Some(port))
+        }
+        _ => (host_port, None),
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let path_end = tail.find(['?', '#']).unwrap_or(tail.len());
+    Some(UrlParts {
+        scheme,
+        host,
+        port,
+        path: &tail[..path_end],
+    })
+}
+
+fn default_port(scheme: Option<&str>) -> Option<&'static str> {
+    match scheme?.to_ascii_lowercase().as_str() {
+        "http" | "ws" => Some("80"),
+        "https" | "wss" => Some("443"),
+        _ => None,
+    }
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .to_ascii_lowercase()
+            .ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+        None => host.eq_ignore_ascii_case(pattern),
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if pattern.is_empty() || pattern == "/" {
+        return true;
+    }
+    match path.strip_prefix(pattern) {
+        Some(rest) => rest.is_empty() || pattern.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Returns whether the allowed resource `pattern` covers the accessed `url`.
+///
+/// Resources that are not URLs only match themselves.
+pub(crate) fn url_pattern_matches(pattern: &str, url: &str) -> bool {
+    if pattern == url {
+        return true;
+    }
+    let (Some(pattern), Some(url)) = (split_url(pattern), split_url(url)) else {
+        return false;
+    };
+    let Some(scheme) = url.scheme else {
+        return false;
+    };
+    if pattern
+        .scheme
+        .is_some_and(|expected| !expected.eq_ignore_ascii_case(scheme))
+    {
+        return false;
+    }
+    let url_port = url.port.or_else(|| default_port(url.scheme));
+    let pattern_port = pattern.port.or_else(|| default_port(Some(scheme)));
+    host_matches(pattern.host, url.host)
+        && url_port == pattern_port
+        && path_matches(pattern.path, url.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_grant_covers_paths_and_queries() {
+        assert!(url_pattern_matches(
+            "https://api.example.com",
+            "https://api.example.com/v1/items?id=2"
+        ));
+        assert!(url_pattern_matches(
+            "api.example.com",
+            "http://api.example.com/"
+        ));
+        assert!(!url_pattern_matches(
+            "https://api.example.com",
+            "http://api.example.com/v1"
+        ));
+        assert!(!url_pattern_matches(
+            "https://api.example.com",
+            "https://api.example.com.evil.test/"
+        ));
+    }
+
+    #[test]
+    fn test_path_prefix_stops_at_segments() {
+        assert!(url_pattern_matches(
+            "https://example.com/v1",
+            "https://example.com/v1/items#top"
+        ));
+        assert!(!url_pattern_matches(
+            "https://example.com/v1",
+            "https://example.com/v10"
+        ));
+        assert!(!url_pattern_matches(
+            "https://example.com/v1",
+            "https://example.com/"
+        ));
+    }
+
+    #[test]
+    fn test_wildcard_subdomains_and_ports() {
+        assert!(url_pattern_matches(
+            "*.example.com",
+            "https://a.b.example.com/x"
+        ));
+        assert!(!url_pattern_matches(
+            "*.example.com",
+            "https://example.com/x"
+        ));
+        assert!(url_pattern_matches(
+            "https://example.com",
+            "https://example.com:443/x"
+        ));
+        assert!(!url_pattern_matches(
+            "https://example.com",
+            "https://example.com:8443/x"
+        ));
+        assert!(url_pattern_matches(
+            "https://example.com:8443",
+            "https://example.com:8443/x"
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+function zip(paths, dest) {
+    return Deno.core.ops.op2_archive_zip(paths, dest);
+}
+
+function unzip(src, destDir) {
+    return Deno.core.ops.op2_archive_unzip(src, destDir);
+}
+
+function tarGz(paths, dest) {
+    return Deno.core.ops.op2_archive_tar_gz(paths, dest);
+}
+
+function untarGz(src, destDir) {
+    return Deno.core.ops.op2_archive_untar_gz(src, destDir);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.archive = globalThis.app.sapphillon.core.archive || {};
+
+globalThis.app.sapphillon.core.archive.zip = zip;
+globalThis.app.sapphillon.core.archive.unzip = unzip;
+globalThis.app.sapphillon.core.archive.tarGz = tarGz;
+globalThis.app.sapphillon.core.archive.untarGz = untarGz;
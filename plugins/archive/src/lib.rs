@@ -0,0 +1,713 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Archive plugin - creates and extracts zip and tar.gz archives with permission checks
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use std::fs::File;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+pub fn archive_zip_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.archive.zip".to_string(),
+        function_name: "archive.zip".to_string(),
+        version: "".to_string(),
+        description: "Creates a zip archive containing the given files and directories."
+            .to_string(),
+        permissions: archive_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "paths".to_string(),
+                    r#type: "string[]".to_string(),
+                    description: "Files or directories to add to the archive".to_string(),
+                },
+                FunctionParameter {
+                    name: "dest".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path of the zip file to create".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "entries".to_string(),
+                r#type: "number".to_string(),
+                description: "Number of entries written to the archive".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn archive_unzip_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.archive.unzip".to_string(),
+        function_name: "archive.unzip".to_string(),
+        version: "".to_string(),
+        description: "Extracts a zip archive into the given directory.".to_string(),
+        permissions: archive_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "src".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path of the zip file to extract".to_string(),
+                },
+                FunctionParameter {
+                    name: "destDir".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Directory to extract into".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "files".to_string(),
+                r#type: "string[]".to_string(),
+                description: "Paths of the extracted entries".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn archive_tar_gz_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.archive.tarGz".to_string(),
+        function_name: "archive.tarGz".to_string(),
+        version: "".to_string(),
+        description:
+            "Creates a gzip-compressed tar archive containing the given files and directories."
+                .to_string(),
+        permissions: archive_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "paths".to_string(),
+                    r#type: "string[]".to_string(),
+                    description: "Files or directories to add to the archive".to_string(),
+                },
+                FunctionParameter {
+                    name: "dest".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path of the .tar.gz file to create".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "entries".to_string(),
+                r#type: "number".to_string(),
+                description: "Number of top-level paths written to the archive".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn archive_untar_gz_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.archive.untarGz".to_string(),
+        function_name: "archive.untarGz".to_string(),
+        version: "".to_string(),
+        description: "Extracts a gzip-compressed tar archive into the given directory.".to_string(),
+        permissions: archive_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "src".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path of the .tar.gz file to extract".to_string(),
+                },
+                FunctionParameter {
+                    name: "destDir".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Directory to extract into".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "files".to_string(),
+                r#type: "string[]".to_string(),
+                description: "Paths of the extracted entries".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn archive_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.archive".to_string(),
+        package_name: "Archive".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to create and extract zip and tar.gz archives.".to_string(),
+        functions: vec![
+            archive_zip_plugin_function(),
+            archive_unzip_plugin_function(),
+            archive_tar_gz_plugin_function(),
+            archive_untar_gz_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_archive_zip_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        archive_zip_plugin_function().function_id,
+        "Zip".to_string(),
+        archive_zip_plugin_function().description,
+        op2_archive_zip(),
+        Some(include_str!("00_archive.js").to_string()),
+    )
+}
+
+pub fn core_archive_unzip_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        archive_unzip_plugin_function().function_id,
+        "Unzip".to_string(),
+        archive_unzip_plugin_function().description,
+        op2_archive_unzip(),
+        Some(include_str!("00_archive.js").to_string()),
+    )
+}
+
+pub fn core_archive_tar_gz_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        archive_tar_gz_plugin_function().function_id,
+        "TarGz".to_string(),
+        archive_tar_gz_plugin_function().description,
+        op2_archive_tar_gz(),
+        Some(include_str!("00_archive.js").to_string()),
+    )
+}
+
+pub fn core_archive_untar_gz_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        archive_untar_gz_plugin_function().function_id,
+        "UntarGz".to_string(),
+        archive_untar_gz_plugin_function().description,
+        op2_archive_untar_gz(),
+        Some(include_str!("00_archive.js").to_string()),
+    )
+}
+
+pub fn core_archive_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        archive_plugin_package().package_id,
+        "Archive".to_string(),
+        vec![
+            core_archive_zip_plugin(),
+            core_archive_unzip_plugin(),
+            core_archive_tar_gz_plugin(),
+            core_archive_untar_gz_plugin(),
+        ],
+    )
+}
+
+#[op2]
+fn op2_archive_zip(
+    state: &mut OpState,
+    #[serde] paths: Vec<String>,
+    #[string] dest: String,
+) -> std::result::Result<u32, JsErrorBox> {
+    let function_id = archive_zip_plugin_function().function_id;
+    ensure_sources_readable(state, &function_id, &paths)?;
+    ensure_permission(
+        state,
+        &function_id,
+        archive_write_plugin_permissions(),
+        &dest,
+    )?;
+
+    match create_zip(&paths, Path::new(&dest)) {
+        Ok(entries) => Ok(entries as u32),
+        Err(e) => Err(JsErrorBox::new("Error", e.to_string())),
+    }
+}
+
+#[op2]
+#[serde]
+fn op2_archive_unzip(
+    state: &mut OpState,
+    #[string] src: String,
+    #[string] dest_dir: String,
+) -> std::result::Result<Vec<String>, JsErrorBox> {
+    let function_id = archive_unzip_plugin_function().function_id;
+    ensure_permission(state, &function_id, archive_read_plugin_permissions(), &src)?;
+    ensure_permission(
+        state,
+        &function_id,
+        archive_write_plugin_permissions(),
+        &dest_dir,
+    )?;
+
+    extract_zip(Path::new(&src), Path::new(&dest_dir))
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+fn op2_archive_tar_gz(
+    state: &mut OpState,
+    #[serde] paths: Vec<String>,
+    #[string] dest: String,
+) -> std::result::Result<u32, JsErrorBox> {
+    let function_id = archive_tar_gz_plugin_function().function_id;
+    ensure_sources_readable(state, &function_id, &paths)?;
+    ensure_permission(
+        state,
+        &function_id,
+        archive_write_plugin_permissions(),
+        &dest,
+    )?;
+
+    match create_tar_gz(&paths, Path::new(&dest)) {
+        Ok(entries) => Ok(entries as u32),
+        Err(e) => Err(JsErrorBox::new("Error", e.to_string())),
+    }
+}
+
+#[op2]
+#[serde]
+fn op2_archive_untar_gz(
+    state: &mut OpState,
+    #[string] src: String,
+    #[string] dest_dir: String,
+) -> std::result::Result<Vec<String>, JsErrorBox> {
+    let function_id = archive_untar_gz_plugin_function().function_id;
+    ensure_permission(state, &function_id, archive_read_plugin_permissions(), &src)?;
+    ensure_permission(
+        state,
+        &function_id,
+        archive_write_plugin_permissions(),
+        &dest_dir,
+    )?;
+
+    extract_tar_gz(Path::new(&src), Path::new(&dest_dir))
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn ensure_sources_readable(
+    state: &mut OpState,
+    plugin_function_id: &str,
+    paths: &[String],
+) -> Result<(), JsErrorBox> {
+    for path in paths {
+        ensure_permission(
+            state,
+            plugin_function_id,
+            archive_read_plugin_permissions(),
+            path,
+        )?;
+    }
+    Ok(())
+}
+
+/// Name a source path is stored under: its final component, so `/home/me/docs` becomes `docs/...`.
+fn entry_name(path: &Path) -> anyhow::Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "cannot archive path without a file name: {}",
+                path.display()
+            )
+        })
+}
+
+/// Walks `path` and returns every file and directory below it with its archive name and
+/// whether it is a directory.
+///
+/// Only the source paths are permission-checked, so symbolic links below them are skipped
+/// instead of followed; a link could otherwise pull in files from anywhere on the system.
+/// A source path that is itself a link is refused.
+fn collect_entries(
+    path: &Path,
+    name: &str,
+    out: &mut Vec<(PathBuf, String, bool)>,
+) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.is_symlink() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("refusing to archive symbolic link: {}", path.display()),
+        ));
+    }
+    collect_below(path, name, metadata.is_dir(), out)
+}
+
+fn collect_below(
+    path: &Path,
+    name: &str,
+    is_dir: bool,
+    out: &mut Vec<(PathBuf, String, bool)>,
+) -> io::Result<()> {
+    out.push((path.to_path_buf(), name.to_string(), is_dir));
+    if is_dir {
+        let mut children: Vec<_> = std::fs::read_dir(path)?.collect::<io::Result<_>>()?;
+        children.sort_by_key(|entry| entry.file_name());
+        for child in children {
+            let child_path = child.path();
+            let file_type = child.file_type()?;
+            if file_type.is_symlink() {
+                log::warn!(
+                    "Skipping symbolic link while archiving: {}",
+                    child_path.display()
+                );
+                continue;
+            }
+            let child_name = format!("{name}/{}", child.file_name().to_string_lossy());
+            collect_below(&child_path, &child_name, file_type.is_dir(), out)?;
+        }
+    }
+    Ok(())
+}
+
+fn create_zip(paths: &[String], dest: &Path) -> anyhow::Result<usize> {
+    let mut entries = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        collect_entries(path, &entry_name(path)?, &mut entries)?;
+    }
+
+    let mut writer = zip::ZipWriter::new(File::create(dest)?);
+    let options = SimpleFileOptions::default();
+    for (path, name, is_dir) in &entries {
+        if *is_dir {
+            writer.add_directory(format!("{name}/"), options)?;
+        } else {
+            writer.start_file(name.as_str(), options)?;
+            io::copy(&mut File::open(path)?, &mut writer)?;
+        }
+    }
+    writer.finish()?;
+    Ok(entries.len())
+}
+
+fn extract_zip(src: &Path, dest_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(File::open(src)?)?;
+    let mut extracted = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let out_path = safe_join(dest_dir, Path::new(file.name()))?;
+        if file.is_dir() {
+            std::fs::create_dir_all(&out_path)?;
+        } else {
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut file, &mut File::create(&out_path)?)?;
+        }
+        extracted.push(out_path.to_string_lossy().to_string());
+    }
+    Ok(extracted)
+}
+
+fn create_tar_gz(paths: &[String], dest: &Path) -> anyhow::Result<usize> {
+    let mut entries = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        collect_entries(path, &entry_name(path)?, &mut entries)?;
+    }
+
+    let encoder = GzEncoder::new(File::create(dest)?, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    for (path, name, is_dir) in &entries {
+        if *is_dir {
+            builder.append_dir(name, path)?;
+        } else {
+            builder.append_path_with_name(path, name)?;
+        }
+    }
+    builder.into_inner()?.finish()?;
+    Ok(paths.len())
+}
+
+fn extract_tar_gz(src: &Path, dest_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(src)?));
+    let mut extracted = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        // Links could point outside the destination directory after extraction.
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            anyhow::bail!(
+                "refusing to extract link entry: {}",
+                entry.path()?.display()
+            );
+        }
+        let out_path = safe_join(dest_dir, &entry.path()?)?;
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&out_path)?;
+        extracted.push(out_path.to_string_lossy().to_string());
+    }
+    Ok(extracted)
+}
+
+/// Joins an archive entry name onto `dest_dir`, rejecting names that would escape it
+/// (absolute paths, drive prefixes, or `..` components) to prevent zip-slip attacks.
+fn safe_join(dest_dir: &Path, entry: &Path) -> anyhow::Result<PathBuf> {
+    let mut out = dest_dir.to_path_buf();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                anyhow::bail!("archive entry escapes destination: {}", entry.display())
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn archive_plugin_permissions() -> Vec<Permission> {
+    let mut permissions = archive_read_plugin_permissions();
+    permissions.extend(archive_write_plugin_permissions());
+    permissions
+}
+
+fn archive_read_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Read".to_string(),
+        description: "Allows the plugin to read archive sources from the local filesystem."
+            .to_string(),
+        permission_type: PermissionType::FilesystemRead as i32,
+        permission_level: PermissionLevel::Medium as i32,
+        resource: vec![],
+    }]
+}
+
+fn archive_write_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Write".to_string(),
+        description:
+            "Allows the plugin to write archives and extracted files to the local filesystem."
+                .to_string(),
+        permission_type: PermissionType::FilesystemWrite as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    fn write_fixture(root: &Path) -> PathBuf {
+        let src = root.join("docs");
+        std::fs::create_dir_all(src.join("nested")).unwrap();
+        std::fs::write(src.join("a.txt"), "alpha").unwrap();
+        std::fs::write(src.join("nested").join("b.txt"), "beta").unwrap();
+        src
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = write_fixture(dir.path());
+        let archive = dir.path().join("docs.zip");
+        let out = dir.path().join("out");
+
+        let entries = create_zip(&[src.to_string_lossy().to_string()], &archive).unwrap();
+        assert_eq!(entries, 4);
+
+        extract_zip(&archive, &out).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(out.join("docs").join("a.txt")).unwrap(),
+            "alpha"
+        );
+        assert_eq!(
+            std::fs::read_to_string(out.join("docs").join("nested").join("b.txt")).unwrap(),
+            "beta"
+        );
+    }
+
+    #[test]
+    fn test_tar_gz_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = write_fixture(dir.path());
+        let archive = dir.path().join("docs.tar.gz");
+        let out = dir.path().join("out");
+
+        create_tar_gz(&[src.to_string_lossy().to_string()], &archive).unwrap();
+        extract_tar_gz(&archive, &out).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(out.join("docs").join("nested").join("b.txt")).unwrap(),
+            "beta"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_archives_skip_symbolic_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = write_fixture(dir.path());
+        std::fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret.txt"), src.join("link.txt")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), src.join("nested").join("up")).unwrap();
+        let sources = [src.to_string_lossy().to_string()];
+
+        let zip_path = dir.path().join("docs.zip");
+        assert_eq!(create_zip(&sources, &zip_path).unwrap(), 4);
+        let zip_out = dir.path().join("zip_out");
+        let extracted = extract_zip(&zip_path, &zip_out).unwrap();
+        assert!(extracted.iter().all(|path| !path.contains("link.txt")));
+        assert!(!zip_out.join("docs").join("nested").join("up").exists());
+
+        let tar_path = dir.path().join("docs.tar.gz");
+        create_tar_gz(&sources, &tar_path).unwrap();
+        let tar_out = dir.path().join("tar_out");
+        extract_tar_gz(&tar_path, &tar_out).unwrap();
+        assert!(!tar_out.join("docs").join("link.txt").exists());
+        assert!(!tar_out.join("docs").join("nested").join("up").exists());
+
+        let link = [src.join("link.txt").to_string_lossy().to_string()];
+        assert!(create_zip(&link, &dir.path().join("link.zip")).is_err());
+        assert!(create_tar_gz(&link, &dir.path().join("link.tar.gz")).is_err());
+    }
+
+    #[test]
+    fn test_unzip_rejects_path_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("evil.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        writer
+            .start_file("../evil.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"pwned").unwrap();
+        writer.finish().unwrap();
+
+        let out = dir.path().join("out");
+        assert!(extract_zip(&archive, &out).is_err());
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_safe_join() {
+        let base = Path::new("/tmp/out");
+        assert_eq!(
+            safe_join(base, Path::new("./a/b.txt")).unwrap(),
+            base.join("a").join("b.txt")
+        );
+        assert!(safe_join(base, Path::new("a/../../b.txt")).is_err());
+        assert!(safe_join(base, Path::new("/etc/passwd")).is_err());
+    }
+
+    fn permission_for(
+        plugin_function_id: String,
+        grants: &[(PermissionType, &Path)],
+    ) -> PluginFunctionPermissions {
+        PluginFunctionPermissions {
+            plugin_function_id,
+            permissions: Permissions {
+                permissions: grants
+                    .iter()
+                    .map(|(permission_type, path)| Permission {
+                        display_name: "Archive".to_string(),
+                        description: "Allows archive tests".to_string(),
+                        permission_type: *permission_type as i32,
+                        permission_level: PermissionLevel::Unspecified as i32,
+                        resource: vec![path.to_string_lossy().to_string()],
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_archive_in_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = write_fixture(dir.path());
+        let archive = dir.path().join("docs.zip");
+        let out = dir.path().join("out");
+
+        let code = format!(
+            r#"
+            app.sapphillon.core.archive.zip([{src:?}], {archive:?});
+            const files = app.sapphillon.core.archive.unzip({archive:?}, {out:?});
+            console.log(files.length);
+            "#,
+            src = src.to_string_lossy(),
+            archive = archive.to_string_lossy(),
+            out = out.to_string_lossy(),
+        );
+
+        let zip_permission = permission_for(
+            archive_zip_plugin_function().function_id,
+            &[
+                (PermissionType::FilesystemRead, &src),
+                (PermissionType::FilesystemWrite, &archive),
+            ],
+        );
+        let unzip_permission = permission_for(
+            archive_unzip_plugin_function().function_id,
+            &[
+                (PermissionType::FilesystemRead, &archive),
+                (PermissionType::FilesystemWrite, &out),
+            ],
+        );
+
+        let workflow_permissions = vec![zip_permission, unzip_permission];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_archive_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "4\n");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_permission_denied_in_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = write_fixture(dir.path());
+        let archive = dir.path().join("denied.zip");
+
+        let code = format!(
+            "app.sapphillon.core.archive.zip([{:?}], {:?});",
+            src.to_string_lossy(),
+            archive.to_string_lossy()
+        );
+
+        // Read access to the sources alone is not enough to create the archive.
+        let workflow_permissions = vec![permission_for(
+            archive_zip_plugin_function().function_id,
+            &[(PermissionType::FilesystemRead, &src)],
+        )];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_archive_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+        assert!(!archive.exists());
+    }
+}
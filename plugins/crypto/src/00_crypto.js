@@ -0,0 +1,30 @@
+function sha256(data, encoding) {
+    return Deno.core.ops.op2_crypto_sha256(String(data), encoding || "hex");
+}
+
+function hmac(key, message, encoding) {
+    return Deno.core.ops.op2_crypto_hmac(String(key), String(message), encoding || "hex");
+}
+
+function randomUuid() {
+    return Deno.core.ops.op2_crypto_random_uuid();
+}
+
+function base64Encode(text) {
+    return Deno.core.ops.op2_crypto_base64_encode(String(text));
+}
+
+function base64Decode(data) {
+    return Deno.core.ops.op2_crypto_base64_decode(String(data));
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.crypto = globalThis.app.sapphillon.core.crypto || {};
+
+globalThis.app.sapphillon.core.crypto.sha256 = sha256;
+globalThis.app.sapphillon.core.crypto.hmac = hmac;
+globalThis.app.sapphillon.core.crypto.randomUuid = randomUuid;
+globalThis.app.sapphillon.core.crypto.base64Encode = base64Encode;
+globalThis.app.sapphillon.core.crypto.base64Decode = base64Decode;
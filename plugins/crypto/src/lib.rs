@@ -0,0 +1,342 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Crypto plugin - hashing, HMAC signing, UUIDs and base64 helpers
+use base64::{Engine as _, engine::general_purpose};
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use hmac::{Hmac, Mac};
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use sha2::{Digest, Sha256};
+
+pub fn crypto_sha256_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.crypto.sha256".to_string(),
+        function_name: "crypto.sha256".to_string(),
+        version: "".to_string(),
+        description: "Computes the SHA-256 digest of a UTF-8 string.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "data".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Text to hash".to_string(),
+                },
+                FunctionParameter {
+                    name: "encoding".to_string(),
+                    r#type: "\"hex\" | \"base64\" | undefined".to_string(),
+                    description: "Output encoding (defaults to hex)".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "digest".to_string(),
+                r#type: "string".to_string(),
+                description: "Encoded digest".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn crypto_hmac_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.crypto.hmac".to_string(),
+        function_name: "crypto.hmac".to_string(),
+        version: "".to_string(),
+        description: "Signs a message with HMAC-SHA256.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "key".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Secret key".to_string(),
+                },
+                FunctionParameter {
+                    name: "message".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Message to sign".to_string(),
+                },
+                FunctionParameter {
+                    name: "encoding".to_string(),
+                    r#type: "\"hex\" | \"base64\" | undefined".to_string(),
+                    description: "Output encoding (defaults to hex)".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "signature".to_string(),
+                r#type: "string".to_string(),
+                description: "Encoded MAC".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn crypto_random_uuid_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.crypto.randomUuid".to_string(),
+        function_name: "crypto.randomUuid".to_string(),
+        version: "".to_string(),
+        description: "Generates a random (version 4) UUID.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "uuid".to_string(),
+                r#type: "string".to_string(),
+                description: "Hyphenated lowercase UUID".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn crypto_base64_encode_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.crypto.base64Encode".to_string(),
+        function_name: "crypto.base64Encode".to_string(),
+        version: "".to_string(),
+        description: "Encodes a UTF-8 string as standard base64.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "text".to_string(),
+                r#type: "string".to_string(),
+                description: "Text to encode".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "data".to_string(),
+                r#type: "string".to_string(),
+                description: "Base64 string".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn crypto_base64_decode_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.crypto.base64Decode".to_string(),
+        function_name: "crypto.base64Decode".to_string(),
+        version: "".to_string(),
+        description: "Decodes standard base64 into a UTF-8 string.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "data".to_string(),
+                r#type: "string".to_string(),
+                description: "Base64 string".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "text".to_string(),
+                r#type: "string".to_string(),
+                description: "Decoded text; fails if the bytes are not valid UTF-8".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn crypto_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.crypto".to_string(),
+        package_name: "Crypto".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin for hashing, message signing, UUIDs and base64.".to_string(),
+        functions: vec![
+            crypto_sha256_plugin_function(),
+            crypto_hmac_plugin_function(),
+            crypto_random_uuid_plugin_function(),
+            crypto_base64_encode_plugin_function(),
+            crypto_base64_decode_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_crypto_sha256_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        crypto_sha256_plugin_function().function_id,
+        "Sha256".to_string(),
+        crypto_sha256_plugin_function().description,
+        op2_crypto_sha256(),
+        Some(include_str!("00_crypto.js").to_string()),
+    )
+}
+
+pub fn core_crypto_hmac_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        crypto_hmac_plugin_function().function_id,
+        "Hmac".to_string(),
+        crypto_hmac_plugin_function().description,
+        op2_crypto_hmac(),
+        Some(include_str!("00_crypto.js").to_string()),
+    )
+}
+
+pub fn core_crypto_random_uuid_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        crypto_random_uuid_plugin_function().function_id,
+        "RandomUuid".to_string(),
+        crypto_random_uuid_plugin_function().description,
+        op2_crypto_random_uuid(),
+        Some(include_str!("00_crypto.js").to_string()),
+    )
+}
+
+pub fn core_crypto_base64_encode_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        crypto_base64_encode_plugin_function().function_id,
+        "Base64Encode".to_string(),
+        crypto_base64_encode_plugin_function().description,
+        op2_crypto_base64_encode(),
+        Some(include_str!("00_crypto.js").to_string()),
+    )
+}
+
+pub fn core_crypto_base64_decode_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        crypto_base64_decode_plugin_function().function_id,
+        "Base64Decode".to_string(),
+        crypto_base64_decode_plugin_function().description,
+        op2_crypto_base64_decode(),
+        Some(include_str!("00_crypto.js").to_string()),
+    )
+}
+
+pub fn core_crypto_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        crypto_plugin_package().package_id,
+        "Crypto".to_string(),
+        vec![
+            core_crypto_sha256_plugin(),
+            core_crypto_hmac_plugin(),
+            core_crypto_random_uuid_plugin(),
+            core_crypto_base64_encode_plugin(),
+            core_crypto_base64_decode_plugin(),
+        ],
+    )
+}
+
+#[op2]
+#[string]
+fn op2_crypto_sha256(
+    #[string] data: String,
+    #[string] encoding: String,
+) -> std::result::Result<String, JsErrorBox> {
+    encode_digest(&Sha256::digest(data.as_bytes()), &encoding)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[string]
+fn op2_crypto_hmac(
+    #[string] key: String,
+    #[string] message: String,
+    #[string] encoding: String,
+) -> std::result::Result<String, JsErrorBox> {
+    encode_digest(&hmac_sha256(key.as_bytes(), message.as_bytes()), &encoding)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[string]
+fn op2_crypto_random_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[op2]
+#[string]
+fn op2_crypto_base64_encode(#[string] text: String) -> String {
+    general_purpose::STANDARD.encode(text.as_bytes())
+}
+
+#[op2]
+#[string]
+fn op2_crypto_base64_decode(#[string] data: String) -> std::result::Result<String, JsErrorBox> {
+    base64_decode(&data).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length, so new_from_slice cannot fail here.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn encode_digest(bytes: &[u8], encoding: &str) -> anyhow::Result<String> {
+    match encoding.to_ascii_lowercase().as_str() {
+        "" | "hex" => Ok(hex::encode(bytes)),
+        "base64" => Ok(general_purpose::STANDARD.encode(bytes)),
+        _ => anyhow::bail!("unsupported encoding: {encoding:?}"),
+    }
+}
+
+fn base64_decode(data: &str) -> anyhow::Result<String> {
+    let bytes = general_purpose::STANDARD.decode(data.trim())?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            encode_digest(&Sha256::digest(b"abc"), "hex").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(encode_digest(b"abc", "base32").is_err());
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(mac),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        let encoded = general_purpose::STANDARD.encode("こんにちは".as_bytes());
+        assert_eq!(base64_decode(&encoded).unwrap(), "こんにちは");
+        assert!(base64_decode("not base64!").is_err());
+        assert!(base64_decode("/w==").is_err());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_crypto_in_workflow() {
+        let code = r#"
+            const crypto = app.sapphillon.core.crypto;
+            const decoded = crypto.base64Decode(crypto.base64Encode("hello"));
+            console.log(decoded + " " + crypto.randomUuid().length);
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_crypto_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "hello 36\n");
+    }
+}
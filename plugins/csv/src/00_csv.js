@@ -0,0 +1,15 @@
+function parse(text, options) {
+    return Deno.core.ops.op2_csv_parse(text, options || {});
+}
+
+function stringify(rows, options) {
+    return Deno.core.ops.op2_csv_stringify(rows, options || {});
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.csv = globalThis.app.sapphillon.core.csv || {};
+
+globalThis.app.sapphillon.core.csv.parse = parse;
+globalThis.app.sapphillon.core.csv.stringify = stringify;
@@ -0,0 +1,359 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// CSV plugin - parses and serializes CSV text with correct quoting
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+pub fn csv_parse_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.csv.parse".to_string(),
+        function_name: "csv.parse".to_string(),
+        version: "".to_string(),
+        description: "Parses CSV text into an array of objects keyed by the header row."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "text".to_string(),
+                    r#type: "string".to_string(),
+                    description: "CSV text to parse".to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: "{ headers?: boolean, delimiter?: string }".to_string(),
+                    description: "When headers is false, rows are returned as arrays of strings"
+                        .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "rows".to_string(),
+                r#type: "object[]".to_string(),
+                description: "Parsed rows".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn csv_stringify_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.csv.stringify".to_string(),
+        function_name: "csv.stringify".to_string(),
+        version: "".to_string(),
+        description: "Serializes an array of objects or arrays into CSV text.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "rows".to_string(),
+                    r#type: "object[] | any[][]".to_string(),
+                    description: "Rows to serialize".to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: "{ columns?: string[], delimiter?: string }".to_string(),
+                    description: "Column order for object rows, sorted by key when not given, and the field delimiter"
+                        .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "text".to_string(),
+                r#type: "string".to_string(),
+                description: "CSV text".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn csv_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.csv".to_string(),
+        package_name: "CSV".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to parse and serialize CSV text.".to_string(),
+        functions: vec![csv_parse_plugin_function(), csv_stringify_plugin_function()],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_csv_parse_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        csv_parse_plugin_function().function_id,
+        "Parse".to_string(),
+        csv_parse_plugin_function().description,
+        op2_csv_parse(),
+        Some(include_str!("00_csv.js").to_string()),
+    )
+}
+
+pub fn core_csv_stringify_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        csv_stringify_plugin_function().function_id,
+        "Stringify".to_string(),
+        csv_stringify_plugin_function().description,
+        op2_csv_stringify(),
+        Some(include_str!("00_csv.js").to_string()),
+    )
+}
+
+pub fn core_csv_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        csv_plugin_package().package_id,
+        "CSV".to_string(),
+        vec![core_csv_parse_plugin(), core_csv_stringify_plugin()],
+    )
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct ParseOptions {
+    headers: bool,
+    delimiter: String,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            headers: true,
+            delimiter: ",".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct StringifyOptions {
+    columns: Option<Vec<String>>,
+    delimiter: String,
+}
+
+impl Default for StringifyOptions {
+    fn default() -> Self {
+        Self {
+            columns: None,
+            delimiter: ",".to_string(),
+        }
+    }
+}
+
+#[op2]
+#[serde]
+fn op2_csv_parse(
+    #[string] text: String,
+    #[serde] options: ParseOptions,
+) -> std::result::Result<Vec<Value>, JsErrorBox> {
+    parse_csv(&text, &options).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[string]
+fn op2_csv_stringify(
+    #[serde] rows: Vec<Value>,
+    #[serde] options: StringifyOptions,
+) -> std::result::Result<String, JsErrorBox> {
+    stringify_csv(&rows, &options).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn delimiter_byte(delimiter: &str) -> anyhow::Result<u8> {
+    match delimiter.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => anyhow::bail!("delimiter must be a single ASCII character, got {delimiter:?}"),
+    }
+}
+
+fn parse_csv(text: &str, options: &ParseOptions) -> anyhow::Result<Vec<Value>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(options.headers)
+        .delimiter(delimiter_byte(&options.delimiter)?)
+        .from_reader(text.as_bytes());
+
+    let headers = if options.headers {
+        Some(reader.headers()?.clone())
+    } else {
+        None
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row = match &headers {
+            Some(headers) => {
+                let mut object = Map::new();
+                for (name, field) in headers.iter().zip(record.iter()) {
+                    object.insert(name.to_string(), Value::String(field.to_string()));
+                }
+                Value::Object(object)
+            }
+            None => Value::Array(
+                record
+                    .iter()
+                    .map(|field| Value::String(field.to_string()))
+                    .collect(),
+            ),
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn stringify_csv(rows: &[Value], options: &StringifyOptions) -> anyhow::Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter_byte(&options.delimiter)?)
+        .flexible(true)
+        .from_writer(Vec::new());
+
+    // Object rows get a header row; use the explicit column order when given,
+    // otherwise every key of every row, sorted. serde_json keeps object keys
+    // sorted, so the property order of the JavaScript objects is not known here.
+    let columns = match &options.columns {
+        Some(columns) => Some(columns.clone()),
+        None if rows.iter().any(Value::is_object) => {
+            let columns: BTreeSet<&String> = rows
+                .iter()
+                .filter_map(Value::as_object)
+                .flat_map(Map::keys)
+                .collect();
+            Some(columns.into_iter().cloned().collect())
+        }
+        None => None,
+    };
+
+    if let Some(columns) = &columns {
+        writer.write_record(columns)?;
+    }
+
+    for row in rows {
+        let fields: Vec<String> = match (row, &columns) {
+            (Value::Object(object), Some(columns)) => columns
+                .iter()
+                .map(|column| object.get(column).map(field_to_string).unwrap_or_default())
+                .collect(),
+            (Value::Array(values), _) => values.iter().map(field_to_string).collect(),
+            (other, _) => anyhow::bail!("each row must be an object or an array, got {other}"),
+        };
+        writer.write_record(&fields)?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn field_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_with_headers() {
+        let text = "name,comment\nalice,\"hello, world\"\nbob,\"say \"\"hi\"\"\"\n";
+        let rows = parse_csv(text, &ParseOptions::default()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], json!({"name": "alice", "comment": "hello, world"}));
+        assert_eq!(rows[1], json!({"name": "bob", "comment": "say \"hi\""}));
+    }
+
+    #[test]
+    fn test_parse_without_headers_and_custom_delimiter() {
+        let options = ParseOptions {
+            headers: false,
+            delimiter: ";".to_string(),
+        };
+        let rows = parse_csv("a;b\nc;d\n", &options).unwrap();
+        assert_eq!(rows, vec![json!(["a", "b"]), json!(["c", "d"])]);
+    }
+
+    #[test]
+    fn test_parse_rejects_multi_character_delimiter() {
+        let options = ParseOptions {
+            headers: true,
+            delimiter: "::".to_string(),
+        };
+        assert!(parse_csv("a::b\n", &options).is_err());
+    }
+
+    #[test]
+    fn test_stringify_objects_quotes_fields() {
+        let rows = vec![
+            json!({"name": "alice", "comment": "hello, world"}),
+            json!({"name": "bob", "age": 3}),
+        ];
+        let options = StringifyOptions {
+            columns: Some(vec![
+                "name".to_string(),
+                "comment".to_string(),
+                "age".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let text = stringify_csv(&rows, &options).unwrap();
+        assert_eq!(text, "name,comment,age\nalice,\"hello, world\",\nbob,,3\n");
+    }
+
+    #[test]
+    fn test_stringify_sorts_columns_without_explicit_order() {
+        let rows = vec![
+            json!({"name": "alice", "comment": "hi"}),
+            json!({"name": "bob", "age": 3}),
+        ];
+        let text = stringify_csv(&rows, &StringifyOptions::default()).unwrap();
+        assert_eq!(text, "age,comment,name\n,hi,alice\n3,,bob\n");
+    }
+
+    #[test]
+    fn test_stringify_round_trip() {
+        let rows = vec![json!(["x", "line\nbreak"]), json!(["y", "z"])];
+        let text = stringify_csv(&rows, &StringifyOptions::default()).unwrap();
+        let options = ParseOptions {
+            headers: false,
+            ..Default::default()
+        };
+        assert_eq!(parse_csv(&text, &options).unwrap(), rows);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_csv_in_workflow() {
+        let code = r#"
+            const rows = app.sapphillon.core.csv.parse("a,b\n1,2\n");
+            console.log(app.sapphillon.core.csv.stringify(rows, { columns: ["b", "a"] }));
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_csv_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "b,a\n2,1\n\n");
+    }
+}
@@ -4,15 +4,13 @@
 
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
-use sapphillon_core::permission::{CheckPermissionResult, Permissions, check_permission};
+use plugin_permission::ensure_permission;
 use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
 use sapphillon_core::proto::sapphillon::v1::{
     FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
     PluginPackage,
 };
-use sapphillon_core::runtime::OpStateWorkflowData;
 use std::process::Command;
-use std::sync::{Arc, Mutex};
 
 pub fn exec_plugin_function() -> PluginFunction {
     PluginFunction {
@@ -109,50 +107,12 @@ fn exec(command: &str) -> anyhow::Result<String> {
     }
 }
 
-fn ensure_permission(
-    state: &mut OpState,
-    plugin_function_id: &str,
-    required_permissions: Vec<Permission>,
-    resource: &str,
-) -> Result<(), JsErrorBox> {
-    let data = state
-        .borrow::<Arc<Mutex<OpStateWorkflowData>>>()
-        .lock()
-        .unwrap();
-    let allowed = data.get_allowed_permissions().clone().unwrap_or_default();
-
-    let required_permissions = Permissions::new(
-        required_permissions
-            .into_iter()
-            .map(|mut p| {
-                if !resource.is_empty() && p.resource.is_empty() {
-                    p.resource = vec![resource.to_string()];
-                }
-                p
-            })
-            .collect(),
-    );
-
-    let allowed_permissions = allowed
-        .into_iter()
-        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
-        .map(|p| p.permissions)
-        .unwrap_or_else(|| Permissions::new(vec![]));
-
-    match check_permission(&allowed_permissions, &required_permissions) {
-        CheckPermissionResult::Ok => Ok(()),
-        CheckPermissionResult::MissingPermission(perm) => Err(JsErrorBox::new(
-            "PermissionDenied. Missing Permissions:",
-            perm.to_string(),
-        )),
-    }
-}
 fn exec_plugin_permissions() -> Vec<Permission> {
     vec![Permission {
         display_name: "Command Access".to_string(),
         description: "Allows the plugin to execute shell commands.".to_string(),
         permission_type: PermissionType::Execute as i32,
-        permission_level: PermissionLevel::Unspecified as i32,
+        permission_level: PermissionLevel::High as i32,
         resource: vec![],
     }]
 }
@@ -162,6 +122,7 @@ mod tests {
     use super::*;
     use sapphillon_core::permission::PluginFunctionPermissions;
     use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
 
     #[test]
     fn test_exec_success() {
@@ -4,15 +4,17 @@
 
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
-use sapphillon_core::permission::{CheckPermissionResult, Permissions, check_permission};
+use plugin_permission::ensure_permission;
 use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
 use sapphillon_core::proto::sapphillon::v1::{
     FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
     PluginPackage,
 };
-use sapphillon_core::runtime::OpStateWorkflowData;
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use url::Url;
+
+/// Redirects followed before a request gives up, the same as ureq's default.
+const MAX_REDIRECTS: usize = 10;
 
 pub fn post_plugin_function() -> PluginFunction {
     PluginFunction {
@@ -21,7 +23,7 @@ pub fn post_plugin_function() -> PluginFunction {
         version: "".to_string(),
         description: "Posts the content of a URL using reqwest and returns it as a string."
             .to_string(),
-        permissions: fetch_plugin_permissions(),
+        permissions: post_plugin_permissions(),
         function_define: Some(FunctionDefine {
             parameters: vec![
                 FunctionParameter {
@@ -118,18 +120,15 @@ fn op2_fetch(
     state: &mut OpState,
     #[string] url: String,
 ) -> std::result::Result<String, JsErrorBox> {
-    // Permission Check
-    ensure_permission(
-        state,
-        &fetch_plugin_function().function_id,
-        fetch_plugin_permissions(),
-        &url,
-    )?;
-
-    match fetch(&url) {
-        Ok(body) => Ok(body),
-        Err(e) => Err(JsErrorBox::new("Error", e.to_string())),
-    }
+    // Permission Check, for the URL and every redirect target
+    fetch(&url, &mut |target| {
+        ensure_permission(
+            state,
+            &fetch_plugin_function().function_id,
+            fetch_plugin_permissions(),
+            target,
+        )
+    })
 }
 
 #[op2]
@@ -139,36 +138,72 @@ fn op2_post(
     #[string] url: String,
     #[string] body: String,
 ) -> std::result::Result<String, JsErrorBox> {
-    // Permission Check
-    ensure_permission(
-        state,
-        &post_plugin_function().function_id,
-        fetch_plugin_permissions(),
-        &url,
-    )?;
-
-    match post(&url, &body) {
-        Ok(body) => Ok(body),
-        Err(e) => Err(JsErrorBox::new("Error", e.to_string())),
-    }
+    // Permission Check, for the URL and every redirect target
+    post(&url, &body, &mut |target| {
+        ensure_permission(
+            state,
+            &post_plugin_function().function_id,
+            post_plugin_permissions(),
+            target,
+        )
+    })
 }
 
-fn fetch(url: &str) -> anyhow::Result<String> {
-    let agent: ureq::Agent = ureq::Agent::config_builder()
-        .timeout_global(Some(Duration::from_secs(30)))
-        .build()
-        .into();
-    let body = agent.get(url).call()?.body_mut().read_to_string()?;
-    Ok(body)
+/// Checks a URL before a request is sent to it.
+type UrlCheck<'a> = dyn FnMut(&str) -> Result<(), JsErrorBox> + 'a;
+
+fn fetch(url: &str, check: &mut UrlCheck) -> Result<String, JsErrorBox> {
+    send(url, None, check)
 }
 
-fn post(url: &str, body: &str) -> anyhow::Result<String> {
+fn post(url: &str, body: &str, check: &mut UrlCheck) -> Result<String, JsErrorBox> {
+    send(url, Some(body), check)
+}
+
+/// Sends a request and follows its redirects one at a time, so `check` sees every
+/// URL the request reaches and an allowed host cannot redirect to a denied one.
+fn send(url: &str, mut body: Option<&str>, check: &mut UrlCheck) -> Result<String, JsErrorBox> {
     let agent: ureq::Agent = ureq::Agent::config_builder()
         .timeout_global(Some(Duration::from_secs(30)))
+        .max_redirects(0)
         .build()
         .into();
-    let response_body = agent.post(url).send(body)?.body_mut().read_to_string()?;
-    Ok(response_body)
+    let mut target = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        check(&target)?;
+        let response = match body {
+            Some(body) => agent.post(&target).send(body),
+            None => agent.get(&target).call(),
+        };
+        let mut response = response.map_err(request_error)?;
+        let status = response.status();
+        if !status.is_redirection() {
+            return response.body_mut().read_to_string().map_err(request_error);
+        }
+        let location = response
+            .headers()
+            .get("location")
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| {
+                JsErrorBox::new("Error", format!("{target} redirected without a Location"))
+            })?;
+        let next = Url::parse(&target)
+            .and_then(|base| base.join(location))
+            .map_err(request_error)?;
+        // Only 307 and 308 repeat the request as it was; the others continue with a GET.
+        if !matches!(status.as_u16(), 307 | 308) {
+            body = None;
+        }
+        target = next.to_string();
+    }
+    Err(JsErrorBox::new(
+        "Error",
+        format!("{url} redirected more than {MAX_REDIRECTS} times"),
+    ))
+}
+
+fn request_error(e: impl std::fmt::Display) -> JsErrorBox {
+    JsErrorBox::new("Error", e.to_string())
 }
 
 fn fetch_plugin_permissions() -> Vec<Permission> {
@@ -176,48 +211,20 @@ fn fetch_plugin_permissions() -> Vec<Permission> {
         display_name: "Network Access".to_string(),
         description: "Allows the plugin to make network requests.".to_string(),
         permission_type: PermissionType::NetAccess as i32,
-        permission_level: PermissionLevel::Unspecified as i32,
+        permission_level: PermissionLevel::Medium as i32,
         resource: vec![],
     }]
 }
 
-fn ensure_permission(
-    state: &mut OpState,
-    plugin_function_id: &str,
-    required_permissions: Vec<Permission>,
-    resource: &str,
-) -> Result<(), JsErrorBox> {
-    let data = state
-        .borrow::<Arc<Mutex<OpStateWorkflowData>>>()
-        .lock()
-        .unwrap();
-    let allowed = data.get_allowed_permissions().clone().unwrap_or_default();
-
-    let required_permissions = Permissions::new(
-        required_permissions
-            .into_iter()
-            .map(|mut p| {
-                if !resource.is_empty() && p.resource.is_empty() {
-                    p.resource = vec![resource.to_string()];
-                }
-                p
-            })
-            .collect(),
-    );
-
-    let allowed_permissions = allowed
-        .into_iter()
-        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
-        .map(|p| p.permissions)
-        .unwrap_or_else(|| Permissions::new(vec![]));
-
-    match check_permission(&allowed_permissions, &required_permissions) {
-        CheckPermissionResult::Ok => Ok(()),
-        CheckPermissionResult::MissingPermission(perm) => Err(JsErrorBox::new(
-            "PermissionDenied. Missing Permissions:",
-            perm.to_string(),
-        )),
-    }
+/// Sending data is High: unlike a fetch it can leak what the workflow has read.
+fn post_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Network Send".to_string(),
+        description: "Allows the plugin to send data over the network.".to_string(),
+        permission_type: PermissionType::NetAccess as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
 }
 
 #[cfg(test)]
@@ -226,11 +233,12 @@ mod tests {
     use sapphillon_core::permission::PluginFunctionPermissions;
     use sapphillon_core::proto::sapphillon::v1::PermissionType;
     use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
 
     #[test]
     fn test_fetch() {
         let url = "https://dummyjson.com/test";
-        let result = fetch(url);
+        let result = fetch(url, &mut |_| Ok(()));
         assert!(result.is_ok());
         let body = result.unwrap();
         assert!(body.contains("ok"));
@@ -240,7 +248,7 @@ mod tests {
     #[test]
     fn test_post() {
         let url = "https://dummyjson.com/products/add";
-        let result = post(url, r#"{"title":"test"}"#);
+        let result = post(url, r#"{"title":"test"}"#, &mut |_| Ok(()));
         assert!(result.is_ok());
         let body = result.unwrap();
         assert!(body.contains("id"));
@@ -256,9 +264,7 @@ mod tests {
             console.log(response);
         "#;
 
-        // Provide allowed permissions so permission_check inside the plugin passes.
-        let _url = "https://dummyjson.com/test".to_string();
-
+        // Only another host is allowed, so the fetch must be denied.
         let perm: PluginFunctionPermissions = PluginFunctionPermissions {
             plugin_function_id: fetch_plugin_function().function_id,
             permissions: sapphillon_core::permission::Permissions {
@@ -267,7 +273,7 @@ mod tests {
                     description: "Allows the plugin to make network requests.".to_string(),
                     permission_type: PermissionType::NetAccess as i32,
                     permission_level: PermissionLevel::Unspecified as i32,
-                    resource: vec!["dummyjson.com/test".to_string()],
+                    resource: vec!["https://example.com".to_string()],
                 }],
             },
         };
@@ -285,12 +291,93 @@ mod tests {
         assert_eq!(workflow.result.len(), 1);
 
         let actual = &workflow.result[0].result;
-        // Accept either a successful fetch result or a permission-denied message depending on test environment.
         assert!(
-            actual.to_lowercase().contains("permission denied") || actual.contains("Uncaught"),
+            actual.contains("Uncaught"),
             "Unexpected workflow result: {actual}"
         );
     }
+    /// Serves one request with a redirect to `location` and returns the server's URL.
+    fn serve_redirect(location: impl Fn(u16) -> String + Send + 'static) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                location(port)
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://127.0.0.1:{port}/start")
+    }
+
+    #[test]
+    fn test_redirect_targets_are_checked() {
+        let url = serve_redirect(|port| format!("http://localhost:{port}/denied"));
+        let mut checked = Vec::new();
+        let result = fetch(&url, &mut |target| {
+            checked.push(target.to_string());
+            if target.contains("/denied") {
+                Err(JsErrorBox::new("PermissionDenied", target.to_string()))
+            } else {
+                Ok(())
+            }
+        });
+
+        assert!(result.is_err(), "The redirect target must be denied");
+        assert_eq!(checked.len(), 2);
+        assert_eq!(checked[0], url);
+        assert!(checked[1].starts_with("http://localhost:"));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_redirect_to_a_host_that_is_not_allowed_is_denied() {
+        let url = serve_redirect(|_| "https://dummyjson.com/test".to_string());
+        let code = format!(
+            r#"
+            const response = app.sapphillon.core.fetch.fetch("{url}");
+            console.log(response);
+        "#
+        );
+
+        // Only the redirecting server is allowed, not where it redirects to.
+        let perm: PluginFunctionPermissions = PluginFunctionPermissions {
+            plugin_function_id: fetch_plugin_function().function_id,
+            permissions: sapphillon_core::permission::Permissions {
+                permissions: vec![Permission {
+                    display_name: "Network Access".to_string(),
+                    description: "Allows the plugin to make network requests.".to_string(),
+                    permission_type: PermissionType::NetAccess as i32,
+                    permission_level: PermissionLevel::Unspecified as i32,
+                    resource: vec![url.trim_end_matches("/start").to_string()],
+                }],
+            },
+        };
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_fetch_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+
+        let actual = &workflow.result[0].result;
+        assert!(
+            actual.contains("Uncaught"),
+            "Unexpected workflow result: {actual}"
+        );
+    }
+
     #[tokio::test]
     #[allow(clippy::arc_with_non_send_sync)]
     async fn test_fetch_in_workflow() {
@@ -311,7 +398,8 @@ mod tests {
                     description: "Allows the plugin to make network requests.".to_string(),
                     permission_type: PermissionType::NetAccess as i32,
                     permission_level: PermissionLevel::Unspecified as i32,
-                    resource: vec!["https://dummyjson.com/test".to_string()],
+                    // A host grant covers every path on it.
+                    resource: vec!["https://dummyjson.com".to_string()],
                 }],
             },
         };
@@ -328,7 +416,7 @@ mod tests {
         workflow.run(tokio::runtime::Handle::current(), None, None);
         assert_eq!(workflow.result.len(), 1);
 
-        let expected = fetch(&url).unwrap() + "\n";
+        let expected = fetch(&url, &mut |_| Ok(())).unwrap() + "\n";
 
         let actual = &workflow.result[0].result;
         // Accept either a successful fetch result or a permission-denied message depending on test environment.
@@ -355,7 +443,7 @@ mod tests {
                     description: "Allows the plugin to make network requests.".to_string(),
                     permission_type: PermissionType::NetAccess as i32,
                     permission_level: PermissionLevel::Unspecified as i32,
-                    resource: vec!["https://dummyjson.com/products/add".to_string()],
+                    resource: vec!["https://dummyjson.com/products".to_string()],
                 }],
             },
         };
@@ -372,7 +460,7 @@ mod tests {
         workflow.run(tokio::runtime::Handle::current(), None, None);
         assert_eq!(workflow.result.len(), 1);
 
-        let expected = post(&url, r#"{"title":"test"}"#).unwrap() + "\n";
+        let expected = post(&url, r#"{"title":"test"}"#, &mut |_| Ok(())).unwrap() + "\n";
 
         let actual = &workflow.result[0].result;
         // Accept either a successful fetch result or a permission-denied message depending on test environment.
@@ -390,6 +478,14 @@ mod tests {
         assert!(p.resource.is_empty());
     }
 
+    #[test]
+    fn test_post_requires_higher_level_than_fetch() {
+        let fetch = &fetch_plugin_permissions()[0];
+        let post = &post_plugin_permissions()[0];
+        assert_eq!(post.permission_type, fetch.permission_type);
+        assert!(post.permission_level > fetch.permission_level);
+    }
+
     #[test]
     fn test_fetch_plugin_package() {
         let pkg = fetch_plugin_package();
@@ -5,15 +5,13 @@
 // Filesystem plugin - provides simple text file IO (read) with permission checks
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
-use sapphillon_core::permission::{CheckPermissionResult, Permissions, check_permission};
+use plugin_permission::ensure_permission;
 use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
 use sapphillon_core::proto::sapphillon::v1::{
     FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
     PluginPackage,
 };
-use sapphillon_core::runtime::OpStateWorkflowData;
 use std::fs;
-use std::sync::{Arc, Mutex};
 
 pub fn filesystem_read_plugin_function() -> PluginFunction {
     PluginFunction {
@@ -213,7 +211,7 @@ fn filesystem_write_plugin_permissions() -> Vec<Permission> {
         display_name: "Filesystem Write".to_string(),
         description: "Allows the plugin to write files to the local filesystem.".to_string(),
         permission_type: PermissionType::FilesystemWrite as i32,
-        permission_level: PermissionLevel::Unspecified as i32,
+        permission_level: PermissionLevel::High as i32,
         resource: vec![],
     }]
 }
@@ -258,50 +256,11 @@ fn filesystem_read_plugin_permissions() -> Vec<Permission> {
         display_name: "Filesystem Read".to_string(),
         description: "Allows the plugin to read files from the local filesystem.".to_string(),
         permission_type: PermissionType::FilesystemRead as i32,
-        permission_level: PermissionLevel::Unspecified as i32,
+        permission_level: PermissionLevel::Medium as i32,
         resource: vec![],
     }]
 }
 
-fn ensure_permission(
-    state: &mut OpState,
-    plugin_function_id: &str,
-    required_permissions: Vec<Permission>,
-    resource: &str,
-) -> Result<(), JsErrorBox> {
-    let data = state
-        .borrow::<Arc<Mutex<OpStateWorkflowData>>>()
-        .lock()
-        .unwrap();
-    let allowed = data.get_allowed_permissions().clone().unwrap_or_default();
-
-    let required_permissions = Permissions::new(
-        required_permissions
-            .into_iter()
-            .map(|mut p| {
-                if !resource.is_empty() && p.resource.is_empty() {
-                    p.resource = vec![resource.to_string()];
-                }
-                p
-            })
-            .collect(),
-    );
-
-    let allowed_permissions = allowed
-        .into_iter()
-        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
-        .map(|p| p.permissions)
-        .unwrap_or_else(|| Permissions::new(vec![]));
-
-    match check_permission(&allowed_permissions, &required_permissions) {
-        CheckPermissionResult::Ok => Ok(()),
-        CheckPermissionResult::MissingPermission(perm) => Err(JsErrorBox::new(
-            "PermissionDenied. Missing Permissions:",
-            perm.to_string(),
-        )),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,6 +269,7 @@ mod tests {
     use sapphillon_core::workflow::CoreWorkflowCode;
     use serial_test::serial;
     use std::io::Write;
+    use std::sync::Arc;
 
     // Tests below use std::env::temp_dir() to construct temporary file paths so
     // they work both on Unix-like systems and Windows (avoids hard-coded paths
@@ -0,0 +1,20 @@
+function toMarkdown(html) {
+    return Deno.core.ops.op2_html_to_markdown(String(html));
+}
+
+function select(html, selector) {
+    return Deno.core.ops.op2_html_select(String(html), String(selector));
+}
+
+function extractLinks(html, baseUrl) {
+    return Deno.core.ops.op2_html_extract_links(String(html), baseUrl ? String(baseUrl) : "");
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.html = globalThis.app.sapphillon.core.html || {};
+
+globalThis.app.sapphillon.core.html.toMarkdown = toMarkdown;
+globalThis.app.sapphillon.core.html.select = select;
+globalThis.app.sapphillon.core.html.extractLinks = extractLinks;
@@ -0,0 +1,331 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// HTML plugin - converts HTML to Markdown and extracts elements and links
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use url::Url;
+
+pub fn html_to_markdown_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.html.toMarkdown".to_string(),
+        function_name: "html.toMarkdown".to_string(),
+        version: "".to_string(),
+        description: "Converts an HTML document or fragment to Markdown.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "html".to_string(),
+                r#type: "string".to_string(),
+                description: "HTML source".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "markdown".to_string(),
+                r#type: "string".to_string(),
+                description: "Markdown text".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn html_select_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.html.select".to_string(),
+        function_name: "html.select".to_string(),
+        version: "".to_string(),
+        description: "Returns the elements matching a CSS selector.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "html".to_string(),
+                    r#type: "string".to_string(),
+                    description: "HTML source".to_string(),
+                },
+                FunctionParameter {
+                    name: "selector".to_string(),
+                    r#type: "string".to_string(),
+                    description: "CSS selector, e.g. \"article h2 > a\"".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "elements".to_string(),
+                r#type: "{ tag: string, text: string, html: string, attributes: Record<string, string> }[]".to_string(),
+                description: "Matches in document order; text is whitespace-normalized".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn html_extract_links_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.html.extractLinks".to_string(),
+        function_name: "html.extractLinks".to_string(),
+        version: "".to_string(),
+        description: "Returns every hyperlink in an HTML document.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "html".to_string(),
+                    r#type: "string".to_string(),
+                    description: "HTML source".to_string(),
+                },
+                FunctionParameter {
+                    name: "baseUrl".to_string(),
+                    r#type: "string | undefined".to_string(),
+                    description: "URL used to resolve relative links".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "links".to_string(),
+                r#type: "{ href: string, text: string }[]".to_string(),
+                description: "Links from <a href> elements in document order".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn html_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.html".to_string(),
+        package_name: "HTML".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to convert HTML to Markdown and extract elements and links."
+            .to_string(),
+        functions: vec![
+            html_to_markdown_plugin_function(),
+            html_select_plugin_function(),
+            html_extract_links_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_html_to_markdown_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        html_to_markdown_plugin_function().function_id,
+        "ToMarkdown".to_string(),
+        html_to_markdown_plugin_function().description,
+        op2_html_to_markdown(),
+        Some(include_str!("00_html.js").to_string()),
+    )
+}
+
+pub fn core_html_select_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        html_select_plugin_function().function_id,
+        "Select".to_string(),
+        html_select_plugin_function().description,
+        op2_html_select(),
+        Some(include_str!("00_html.js").to_string()),
+    )
+}
+
+pub fn core_html_extract_links_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        html_extract_links_plugin_function().function_id,
+        "ExtractLinks".to_string(),
+        html_extract_links_plugin_function().description,
+        op2_html_extract_links(),
+        Some(include_str!("00_html.js").to_string()),
+    )
+}
+
+pub fn core_html_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        html_plugin_package().package_id,
+        "HTML".to_string(),
+        vec![
+            core_html_to_markdown_plugin(),
+            core_html_select_plugin(),
+            core_html_extract_links_plugin(),
+        ],
+    )
+}
+
+#[derive(Debug, Serialize)]
+struct Element {
+    tag: String,
+    text: String,
+    html: String,
+    attributes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Link {
+    href: String,
+    text: String,
+}
+
+#[op2]
+#[string]
+fn op2_html_to_markdown(#[string] html: String) -> String {
+    html2md::parse_html(&html)
+}
+
+#[op2]
+#[serde]
+fn op2_html_select(
+    #[string] html: String,
+    #[string] selector: String,
+) -> std::result::Result<Vec<Element>, JsErrorBox> {
+    select(&html, &selector).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_html_extract_links(
+    #[string] html: String,
+    #[string] base_url: String,
+) -> std::result::Result<Vec<Link>, JsErrorBox> {
+    extract_links(&html, &base_url).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn select(html: &str, selector: &str) -> anyhow::Result<Vec<Element>> {
+    let parsed = Selector::parse(selector)
+        .map_err(|e| anyhow::anyhow!("invalid selector {selector:?}: {e}"))?;
+    let document = Html::parse_document(html);
+    Ok(document
+        .select(&parsed)
+        .map(|element| Element {
+            tag: element.value().name().to_string(),
+            text: normalize_whitespace(element.text()),
+            html: element.html(),
+            attributes: element
+                .value()
+                .attrs()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        })
+        .collect())
+}
+
+/// Collects `<a href>` links; with a base URL, relative links are resolved and
+/// links that cannot be resolved are dropped.
+fn extract_links(html: &str, base_url: &str) -> anyhow::Result<Vec<Link>> {
+    let base = if base_url.is_empty() {
+        None
+    } else {
+        Some(Url::parse(base_url)?)
+    };
+    let anchors = Selector::parse("a[href]").expect("static selector is valid");
+    let document = Html::parse_document(html);
+
+    let mut links = Vec::new();
+    for anchor in document.select(&anchors) {
+        let Some(href) = anchor.value().attr("href") else {
+            continue;
+        };
+        let href = match &base {
+            Some(base) => match base.join(href.trim()) {
+                Ok(url) => url.to_string(),
+                Err(_) => continue,
+            },
+            None => href.trim().to_string(),
+        };
+        links.push(Link {
+            href,
+            text: normalize_whitespace(anchor.text()),
+        });
+    }
+    Ok(links)
+}
+
+fn normalize_whitespace<'a>(parts: impl Iterator<Item = &'a str>) -> String {
+    parts
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    const PAGE: &str = r#"
+        <html><body>
+            <h1>Title</h1>
+            <ul class="items">
+                <li data-id="1"><a href="/one">First
+                    item</a></li>
+                <li data-id="2"><a href="https://example.org/two">Second</a></li>
+            </ul>
+        </body></html>
+    "#;
+
+    #[test]
+    fn test_select() {
+        let items = select(PAGE, "ul.items > li").unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].tag, "li");
+        assert_eq!(items[0].text, "First item");
+        assert_eq!(items[1].attributes.get("data-id").unwrap(), "2");
+        assert!(items[1].html.starts_with("<li"));
+
+        assert!(select(PAGE, "ul >>> li").is_err());
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let links = extract_links(PAGE, "").unwrap();
+        assert_eq!(
+            links,
+            vec![
+                Link {
+                    href: "/one".to_string(),
+                    text: "First item".to_string(),
+                },
+                Link {
+                    href: "https://example.org/two".to_string(),
+                    text: "Second".to_string(),
+                },
+            ]
+        );
+
+        let resolved = extract_links(PAGE, "https://example.com/list/").unwrap();
+        assert_eq!(resolved[0].href, "https://example.com/one");
+        assert_eq!(resolved[1].href, "https://example.org/two");
+
+        assert!(extract_links(PAGE, "not a url").is_err());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_to_markdown_in_workflow() {
+        let code = r#"
+            const md = app.sapphillon.core.html.toMarkdown("<p>Hello <b>world</b></p>");
+            console.log(md.includes("**world**") && !md.includes("<p>"));
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_html_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "true\n");
+    }
+}
@@ -0,0 +1,25 @@
+function resize(src, dest, width, height) {
+    return Deno.core.ops.op2_image_resize(src, dest, width, height);
+}
+
+function convert(src, dest, format) {
+    return Deno.core.ops.op2_image_convert(src, dest, format);
+}
+
+function info(path) {
+    return Deno.core.ops.op2_image_info(path);
+}
+
+function thumbnailFromBase64(data, maxSize, format) {
+    return Deno.core.ops.op2_image_thumbnail_from_base64(data, maxSize, format || "png");
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.image = globalThis.app.sapphillon.core.image || {};
+
+globalThis.app.sapphillon.core.image.resize = resize;
+globalThis.app.sapphillon.core.image.convert = convert;
+globalThis.app.sapphillon.core.image.info = info;
+globalThis.app.sapphillon.core.image.thumbnailFromBase64 = thumbnailFromBase64;
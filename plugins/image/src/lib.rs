@@ -0,0 +1,531 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Image plugin - resizes, converts and inspects images with permission checks
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::Path;
+
+pub fn image_resize_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.image.resize".to_string(),
+        function_name: "image.resize".to_string(),
+        version: "".to_string(),
+        description: "Resizes an image to fit within the given bounds, keeping its aspect ratio."
+            .to_string(),
+        permissions: image_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "src".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path of the source image".to_string(),
+                },
+                FunctionParameter {
+                    name: "dest".to_string(),
+                    r#type: "string".to_string(),
+                    description:
+                        "Path to write the resized image; the format follows its extension"
+                            .to_string(),
+                },
+                FunctionParameter {
+                    name: "width".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Maximum width in pixels".to_string(),
+                },
+                FunctionParameter {
+                    name: "height".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Maximum height in pixels".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "info".to_string(),
+                r#type: "{ width: number, height: number, format: string }".to_string(),
+                description: "Dimensions and format of the written image".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn image_convert_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.image.convert".to_string(),
+        function_name: "image.convert".to_string(),
+        version: "".to_string(),
+        description: "Converts an image to another format.".to_string(),
+        permissions: image_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "src".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path of the source image".to_string(),
+                },
+                FunctionParameter {
+                    name: "dest".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Path to write the converted image".to_string(),
+                },
+                FunctionParameter {
+                    name: "format".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Target format: png, jpeg, gif, webp or bmp".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "info".to_string(),
+                r#type: "{ width: number, height: number, format: string }".to_string(),
+                description: "Dimensions and format of the written image".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn image_info_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.image.info".to_string(),
+        function_name: "image.info".to_string(),
+        version: "".to_string(),
+        description: "Returns the dimensions and format of an image file.".to_string(),
+        permissions: image_read_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "path".to_string(),
+                r#type: "string".to_string(),
+                description: "Path of the image".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "info".to_string(),
+                r#type: "{ width: number, height: number, format: string }".to_string(),
+                description: "Dimensions and format of the image".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn image_thumbnail_from_base64_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.image.thumbnailFromBase64".to_string(),
+        function_name: "image.thumbnailFromBase64".to_string(),
+        version: "".to_string(),
+        description:
+            "Creates a thumbnail from base64-encoded image data, such as a captured screenshot."
+                .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "data".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Base64-encoded image".to_string(),
+                },
+                FunctionParameter {
+                    name: "maxSize".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Maximum width and height of the thumbnail in pixels".to_string(),
+                },
+                FunctionParameter {
+                    name: "format".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Output format (defaults to png)".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "data".to_string(),
+                r#type: "string".to_string(),
+                description: "Base64-encoded thumbnail".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn image_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.image".to_string(),
+        package_name: "Image".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to resize, convert and inspect images.".to_string(),
+        functions: vec![
+            image_resize_plugin_function(),
+            image_convert_plugin_function(),
+            image_info_plugin_function(),
+            image_thumbnail_from_base64_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_image_resize_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        image_resize_plugin_function().function_id,
+        "Resize".to_string(),
+        image_resize_plugin_function().description,
+        op2_image_resize(),
+        Some(include_str!("00_image.js").to_string()),
+    )
+}
+
+pub fn core_image_convert_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        image_convert_plugin_function().function_id,
+        "Convert".to_string(),
+        image_convert_plugin_function().description,
+        op2_image_convert(),
+        Some(include_str!("00_image.js").to_string()),
+    )
+}
+
+pub fn core_image_info_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        image_info_plugin_function().function_id,
+        "Info".to_string(),
+        image_info_plugin_function().description,
+        op2_image_info(),
+        Some(include_str!("00_image.js").to_string()),
+    )
+}
+
+pub fn core_image_thumbnail_from_base64_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        image_thumbnail_from_base64_plugin_function().function_id,
+        "ThumbnailFromBase64".to_string(),
+        image_thumbnail_from_base64_plugin_function().description,
+        op2_image_thumbnail_from_base64(),
+        Some(include_str!("00_image.js").to_string()),
+    )
+}
+
+pub fn core_image_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        image_plugin_package().package_id,
+        "Image".to_string(),
+        vec![
+            core_image_resize_plugin(),
+            core_image_convert_plugin(),
+            core_image_info_plugin(),
+            core_image_thumbnail_from_base64_plugin(),
+        ],
+    )
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ImageInfo {
+    width: u32,
+    height: u32,
+    format: String,
+}
+
+#[op2]
+#[serde]
+fn op2_image_resize(
+    state: &mut OpState,
+    #[string] src: String,
+    #[string] dest: String,
+    #[smi] width: u32,
+    #[smi] height: u32,
+) -> std::result::Result<ImageInfo, JsErrorBox> {
+    let function_id = image_resize_plugin_function().function_id;
+    ensure_permission(state, &function_id, image_read_plugin_permissions(), &src)?;
+    ensure_permission(state, &function_id, image_write_plugin_permissions(), &dest)?;
+
+    resize_image(Path::new(&src), Path::new(&dest), width, height)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_image_convert(
+    state: &mut OpState,
+    #[string] src: String,
+    #[string] dest: String,
+    #[string] format: String,
+) -> std::result::Result<ImageInfo, JsErrorBox> {
+    let function_id = image_convert_plugin_function().function_id;
+    ensure_permission(state, &function_id, image_read_plugin_permissions(), &src)?;
+    ensure_permission(state, &function_id, image_write_plugin_permissions(), &dest)?;
+
+    convert_image(Path::new(&src), Path::new(&dest), &format)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_image_info(
+    state: &mut OpState,
+    #[string] path: String,
+) -> std::result::Result<ImageInfo, JsErrorBox> {
+    ensure_permission(
+        state,
+        &image_info_plugin_function().function_id,
+        image_read_plugin_permissions(),
+        &path,
+    )?;
+
+    image_info(Path::new(&path)).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[string]
+fn op2_image_thumbnail_from_base64(
+    #[string] data: String,
+    #[smi] max_size: u32,
+    #[string] format: String,
+) -> std::result::Result<String, JsErrorBox> {
+    thumbnail_from_base64(&data, max_size, &format)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn parse_format(format: &str) -> anyhow::Result<ImageFormat> {
+    match ImageFormat::from_extension(format.trim_start_matches('.')) {
+        Some(format) if format.writing_enabled() => Ok(format),
+        _ => anyhow::bail!("unsupported image format: {format}"),
+    }
+}
+
+fn format_name(format: ImageFormat) -> String {
+    format
+        .extensions_str()
+        .first()
+        .copied()
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Encodes `image` in `format`, dropping the alpha channel for formats that cannot store it.
+fn encode(image: &DynamicImage, format: ImageFormat) -> anyhow::Result<Vec<u8>> {
+    let image = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+        _ => image.clone(),
+    };
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), format)?;
+    Ok(bytes)
+}
+
+fn save(image: &DynamicImage, dest: &Path, format: ImageFormat) -> anyhow::Result<ImageInfo> {
+    std::fs::write(dest, encode(image, format)?)?;
+    Ok(ImageInfo {
+        width: image.width(),
+        height: image.height(),
+        format: format_name(format),
+    })
+}
+
+fn resize_image(src: &Path, dest: &Path, width: u32, height: u32) -> anyhow::Result<ImageInfo> {
+    if width == 0 || height == 0 {
+        anyhow::bail!("width and height must be greater than zero");
+    }
+    let format = ImageFormat::from_path(dest)?;
+    let image = image::open(src)?;
+    let resized = image.resize(width, height, image::imageops::FilterType::Lanczos3);
+    save(&resized, dest, format)
+}
+
+fn convert_image(src: &Path, dest: &Path, format: &str) -> anyhow::Result<ImageInfo> {
+    let format = parse_format(format)?;
+    let image = image::open(src)?;
+    save(&image, dest, format)
+}
+
+fn image_info(path: &Path) -> anyhow::Result<ImageInfo> {
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader
+        .format()
+        .ok_or_else(|| anyhow::anyhow!("unrecognized image format: {}", path.display()))?;
+    let (width, height) = reader.into_dimensions()?;
+    Ok(ImageInfo {
+        width,
+        height,
+        format: format_name(format),
+    })
+}
+
+fn thumbnail_from_base64(data: &str, max_size: u32, format: &str) -> anyhow::Result<String> {
+    if max_size == 0 {
+        anyhow::bail!("maxSize must be greater than zero");
+    }
+    let format = parse_format(format)?;
+    // Accept data URLs as produced by browsers, e.g. "data:image/png;base64,...".
+    let data = data.split_once(',').map_or(data, |(_, payload)| payload);
+    let bytes = STANDARD.decode(data.trim())?;
+    let image = image::load_from_memory(&bytes)?;
+    let thumbnail = image.thumbnail(max_size, max_size);
+    Ok(STANDARD.encode(encode(&thumbnail, format)?))
+}
+
+fn image_plugin_permissions() -> Vec<Permission> {
+    let mut permissions = image_read_plugin_permissions();
+    permissions.extend(image_write_plugin_permissions());
+    permissions
+}
+
+fn image_read_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Read".to_string(),
+        description: "Allows the plugin to read images from the local filesystem.".to_string(),
+        permission_type: PermissionType::FilesystemRead as i32,
+        permission_level: PermissionLevel::Medium as i32,
+        resource: vec![],
+    }]
+}
+
+fn image_write_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Write".to_string(),
+        description: "Allows the plugin to write images to the local filesystem.".to_string(),
+        permission_type: PermissionType::FilesystemWrite as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    fn write_sample(path: &Path, width: u32, height: u32) {
+        let image = RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 128]));
+        image.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_resize_keeps_aspect_ratio() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.png");
+        let dest = dir.path().join("dest.png");
+        write_sample(&src, 200, 100);
+
+        let info = resize_image(&src, &dest, 50, 50).unwrap();
+        assert_eq!((info.width, info.height), (50, 25));
+        assert_eq!(image_info(&dest).unwrap(), info);
+    }
+
+    #[test]
+    fn test_convert_to_jpeg_drops_alpha() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.png");
+        let dest = dir.path().join("dest.jpg");
+        write_sample(&src, 8, 8);
+
+        let info = convert_image(&src, &dest, "jpeg").unwrap();
+        assert_eq!(info.format, "jpg");
+        assert_eq!(image_info(&dest).unwrap().format, "jpg");
+    }
+
+    #[test]
+    fn test_convert_rejects_unknown_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.png");
+        write_sample(&src, 8, 8);
+        assert!(convert_image(&src, &dir.path().join("dest.xyz"), "xyz").is_err());
+    }
+
+    #[test]
+    fn test_thumbnail_from_base64_accepts_data_url() {
+        let sample =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 32, Rgba([0, 0, 255, 255])));
+        let encoded = STANDARD.encode(encode(&sample, ImageFormat::Png).unwrap());
+
+        let thumbnail =
+            thumbnail_from_base64(&format!("data:image/png;base64,{encoded}"), 16, "png").unwrap();
+        let decoded = image::load_from_memory(&STANDARD.decode(thumbnail).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (16, 8));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_permission_denied_in_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.png");
+        write_sample(&src, 8, 8);
+
+        let code = format!(
+            "app.sapphillon.core.image.info({:?});",
+            src.to_string_lossy()
+        );
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: image_info_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: vec![],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_image_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_image_info_in_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.png");
+        write_sample(&src, 12, 34);
+        let path = src.to_string_lossy().to_string();
+
+        let code = format!(
+            "const info = app.sapphillon.core.image.info({path:?}); console.log(info.width + 'x' + info.height);"
+        );
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: image_info_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: vec![Permission {
+                    display_name: "Image".to_string(),
+                    description: "Allows image tests".to_string(),
+                    permission_type: PermissionType::FilesystemRead as i32,
+                    permission_level: PermissionLevel::Unspecified as i32,
+                    resource: vec![path.clone()],
+                }],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_image_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "12x34\n");
+    }
+}
@@ -0,0 +1,25 @@
+function typeText(text) {
+    return Deno.core.ops.op2_input_type_text(text);
+}
+
+function keyPress(combo) {
+    return Deno.core.ops.op2_input_key_press(combo);
+}
+
+function moveMouse(x, y) {
+    return Deno.core.ops.op2_input_move_mouse(x, y);
+}
+
+function click(button) {
+    return Deno.core.ops.op2_input_click(button || "left");
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.input = globalThis.app.sapphillon.core.input || {};
+
+globalThis.app.sapphillon.core.input.typeText = typeText;
+globalThis.app.sapphillon.core.input.keyPress = keyPress;
+globalThis.app.sapphillon.core.input.moveMouse = moveMouse;
+globalThis.app.sapphillon.core.input.click = click;
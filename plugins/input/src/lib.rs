@@ -0,0 +1,390 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Input plugin - simulates keyboard and mouse input for GUI automation
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use enigo::{Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+
+/// Permission resource for keyboard input.
+pub const KEYBOARD_RESOURCE: &str = "input://keyboard";
+/// Permission resource for mouse input.
+pub const MOUSE_RESOURCE: &str = "input://mouse";
+
+pub fn input_type_text_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.input.typeText".to_string(),
+        function_name: "input.typeText".to_string(),
+        version: "".to_string(),
+        description: "Types text into the focused window as if entered on the keyboard."
+            .to_string(),
+        permissions: input_plugin_permissions(KEYBOARD_RESOURCE),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "text".to_string(),
+                r#type: "string".to_string(),
+                description: "Text to type".to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn input_key_press_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.input.keyPress".to_string(),
+        function_name: "input.keyPress".to_string(),
+        version: "".to_string(),
+        description: "Presses a key combination such as \"ctrl+shift+t\" or \"enter\".".to_string(),
+        permissions: input_plugin_permissions(KEYBOARD_RESOURCE),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "combo".to_string(),
+                r#type: "string".to_string(),
+                description: "Keys joined with '+'; modifiers are ctrl, shift, alt and meta"
+                    .to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn input_move_mouse_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.input.moveMouse".to_string(),
+        function_name: "input.moveMouse".to_string(),
+        version: "".to_string(),
+        description: "Moves the mouse pointer to absolute screen coordinates.".to_string(),
+        permissions: input_plugin_permissions(MOUSE_RESOURCE),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "x".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Horizontal position in pixels".to_string(),
+                },
+                FunctionParameter {
+                    name: "y".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Vertical position in pixels".to_string(),
+                },
+            ],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn input_click_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.input.click".to_string(),
+        function_name: "input.click".to_string(),
+        version: "".to_string(),
+        description: "Clicks a mouse button at the current pointer position.".to_string(),
+        permissions: input_plugin_permissions(MOUSE_RESOURCE),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "button".to_string(),
+                r#type: "string".to_string(),
+                description: "left, right or middle (defaults to left)".to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn input_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.input".to_string(),
+        package_name: "Input".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to simulate keyboard and mouse input.".to_string(),
+        functions: vec![
+            input_type_text_plugin_function(),
+            input_key_press_plugin_function(),
+            input_move_mouse_plugin_function(),
+            input_click_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_input_type_text_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        input_type_text_plugin_function().function_id,
+        "TypeText".to_string(),
+        input_type_text_plugin_function().description,
+        op2_input_type_text(),
+        Some(include_str!("00_input.js").to_string()),
+    )
+}
+
+pub fn core_input_key_press_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        input_key_press_plugin_function().function_id,
+        "KeyPress".to_string(),
+        input_key_press_plugin_function().description,
+        op2_input_key_press(),
+        Some(include_str!("00_input.js").to_string()),
+    )
+}
+
+pub fn core_input_move_mouse_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        input_move_mouse_plugin_function().function_id,
+        "MoveMouse".to_string(),
+        input_move_mouse_plugin_function().description,
+        op2_input_move_mouse(),
+        Some(include_str!("00_input.js").to_string()),
+    )
+}
+
+pub fn core_input_click_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        input_click_plugin_function().function_id,
+        "Click".to_string(),
+        input_click_plugin_function().description,
+        op2_input_click(),
+        Some(include_str!("00_input.js").to_string()),
+    )
+}
+
+pub fn core_input_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        input_plugin_package().package_id,
+        "Input".to_string(),
+        vec![
+            core_input_type_text_plugin(),
+            core_input_key_press_plugin(),
+            core_input_move_mouse_plugin(),
+            core_input_click_plugin(),
+        ],
+    )
+}
+
+#[op2]
+fn op2_input_type_text(
+    state: &mut OpState,
+    #[string] text: String,
+) -> std::result::Result<(), JsErrorBox> {
+    ensure_permission(
+        state,
+        &input_type_text_plugin_function().function_id,
+        input_plugin_permissions(KEYBOARD_RESOURCE),
+        "",
+    )?;
+
+    with_enigo(|enigo| Ok(enigo.text(&text)?))
+}
+
+#[op2]
+fn op2_input_key_press(
+    state: &mut OpState,
+    #[string] combo: String,
+) -> std::result::Result<(), JsErrorBox> {
+    ensure_permission(
+        state,
+        &input_key_press_plugin_function().function_id,
+        input_plugin_permissions(KEYBOARD_RESOURCE),
+        "",
+    )?;
+
+    let keys = parse_combo(&combo).map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+    with_enigo(|enigo| press_combo(enigo, &keys))
+}
+
+#[op2]
+fn op2_input_move_mouse(
+    state: &mut OpState,
+    #[smi] x: i32,
+    #[smi] y: i32,
+) -> std::result::Result<(), JsErrorBox> {
+    ensure_permission(
+        state,
+        &input_move_mouse_plugin_function().function_id,
+        input_plugin_permissions(MOUSE_RESOURCE),
+        "",
+    )?;
+
+    with_enigo(|enigo| Ok(enigo.move_mouse(x, y, Coordinate::Abs)?))
+}
+
+#[op2]
+fn op2_input_click(
+    state: &mut OpState,
+    #[string] button: String,
+) -> std::result::Result<(), JsErrorBox> {
+    ensure_permission(
+        state,
+        &input_click_plugin_function().function_id,
+        input_plugin_permissions(MOUSE_RESOURCE),
+        "",
+    )?;
+
+    let button = parse_button(&button).map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+    with_enigo(|enigo| Ok(enigo.button(button, Direction::Click)?))
+}
+
+fn with_enigo(f: impl FnOnce(&mut Enigo) -> anyhow::Result<()>) -> Result<(), JsErrorBox> {
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+    f(&mut enigo).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+/// Holds every key down in order, then releases them in reverse so modifiers wrap the last key.
+fn press_combo(enigo: &mut Enigo, keys: &[Key]) -> anyhow::Result<()> {
+    let Some((last, modifiers)) = keys.split_last() else {
+        anyhow::bail!("empty key combination");
+    };
+    for key in modifiers {
+        enigo.key(*key, Direction::Press)?;
+    }
+    let result = enigo.key(*last, Direction::Click);
+    for key in modifiers.iter().rev() {
+        enigo.key(*key, Direction::Release)?;
+    }
+    Ok(result?)
+}
+
+/// Parses a combination like `ctrl+shift+t` into keys; the last entry is the main key.
+fn parse_combo(combo: &str) -> anyhow::Result<Vec<Key>> {
+    let keys = combo
+        .split('+')
+        .map(|part| parse_key(part.trim()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if keys.is_empty() {
+        anyhow::bail!("empty key combination");
+    }
+    Ok(keys)
+}
+
+fn parse_key(name: &str) -> anyhow::Result<Key> {
+    let lower = name.to_ascii_lowercase();
+    let key = match lower.as_str() {
+        "ctrl" | "control" => Key::Control,
+        "shift" => Key::Shift,
+        "alt" | "option" => Key::Alt,
+        "meta" | "cmd" | "command" | "win" | "super" => Key::Meta,
+        "enter" | "return" => Key::Return,
+        "tab" => Key::Tab,
+        "esc" | "escape" => Key::Escape,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => {
+            let mut chars = lower.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Unicode(c),
+                _ => anyhow::bail!("unknown key: {name:?}"),
+            }
+        }
+    };
+    Ok(key)
+}
+
+fn parse_button(name: &str) -> anyhow::Result<Button> {
+    match name.to_ascii_lowercase().as_str() {
+        "" | "left" => Ok(Button::Left),
+        "right" => Ok(Button::Right),
+        "middle" => Ok(Button::Middle),
+        _ => anyhow::bail!("unknown mouse button: {name:?}"),
+    }
+}
+
+// The core permission model has no dedicated input-control type yet; input is
+// treated as a high-level Execute permission on an input:// resource.
+fn input_plugin_permissions(resource: &str) -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Input Control".to_string(),
+        description: "Allows the plugin to send keyboard and mouse input to other applications."
+            .to_string(),
+        permission_type: PermissionType::Execute as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![resource.to_string()],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_combo() {
+        assert_eq!(
+            parse_combo("Ctrl+Shift+T").unwrap(),
+            vec![Key::Control, Key::Shift, Key::Unicode('t')]
+        );
+        assert_eq!(parse_combo("enter").unwrap(), vec![Key::Return]);
+        assert!(parse_combo("ctrl+bogus").is_err());
+        assert!(parse_combo("").is_err());
+    }
+
+    #[test]
+    fn test_parse_button() {
+        assert_eq!(parse_button("").unwrap(), Button::Left);
+        assert_eq!(parse_button("Right").unwrap(), Button::Right);
+        assert!(parse_button("fourth").is_err());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_permission_denied_in_workflow() {
+        let code = r#"app.sapphillon.core.input.typeText("hello");"#;
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: input_type_text_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: vec![],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_input_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+    }
+}
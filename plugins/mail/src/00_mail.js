@@ -0,0 +1,16 @@
+function send(message) {
+    const to = Array.isArray(message.to) ? message.to : [message.to];
+    return Deno.core.ops.op2_mail_send({
+        to: to,
+        subject: message.subject || "",
+        body: message.body || "",
+        attachments: message.attachments || [],
+    });
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.mail = globalThis.app.sapphillon.core.mail || {};
+
+globalThis.app.sapphillon.core.mail.send = send;
@@ -0,0 +1,344 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Mail plugin - sends email over SMTP using credentials from the secrets store
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+pub fn mail_send_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.mail.send".to_string(),
+        function_name: "mail.send".to_string(),
+        version: "".to_string(),
+        description: "Sends an email through the SMTP server configured in the secrets store."
+            .to_string(),
+        permissions: mail_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "message".to_string(),
+                r#type: "{ to: string | string[], subject: string, body: string, attachments?: string[] }"
+                    .to_string(),
+                description: "Recipients, subject, plain-text body and paths of files to attach"
+                    .to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn mail_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.mail".to_string(),
+        package_name: "Mail".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to send email over SMTP.".to_string(),
+        functions: vec![mail_send_plugin_function()],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_mail_send_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        mail_send_plugin_function().function_id,
+        "Send".to_string(),
+        mail_send_plugin_function().description,
+        op2_mail_send(),
+        Some(include_str!("00_mail.js").to_string()),
+    )
+}
+
+pub fn core_mail_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        mail_plugin_package().package_id,
+        "Mail".to_string(),
+        vec![core_mail_send_plugin()],
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct MailMessage {
+    to: Vec<String>,
+    subject: String,
+    body: String,
+    attachments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SmtpTls {
+    /// TLS from the first byte (usually port 465).
+    Implicit,
+    /// Plain connection upgraded with STARTTLS (usually port 587).
+    StartTls,
+    /// No encryption; only for local relays.
+    None,
+}
+
+/// SMTP settings read from the secrets store (`smtp.host`, `smtp.port`, `smtp.username`,
+/// `smtp.password`, `smtp.from`, `smtp.tls`).
+#[derive(Debug)]
+struct SmtpConfig {
+    host: String,
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+    tls: SmtpTls,
+}
+
+impl SmtpConfig {
+    fn from_secrets() -> anyhow::Result<Self> {
+        Self::load(secrets::get_secret)
+    }
+
+    fn load(lookup: impl Fn(&str) -> anyhow::Result<Option<String>>) -> anyhow::Result<Self> {
+        let required = |name: &str| -> anyhow::Result<String> {
+            lookup(name)?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "SMTP is not configured: secret '{name}' is missing (set it with `secrets set {name}` or {})",
+                    secrets::secret_env_var(name)
+                )
+            })
+        };
+
+        let host = required("smtp.host")?;
+        let port = match lookup("smtp.port")? {
+            Some(port) => port
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid smtp.port: {port}"))?,
+            None => 587,
+        };
+        let tls = match lookup("smtp.tls")?.as_deref() {
+            Some("tls") => SmtpTls::Implicit,
+            Some("starttls") => SmtpTls::StartTls,
+            Some("none") => SmtpTls::None,
+            Some(other) => {
+                anyhow::bail!("invalid smtp.tls: {other} (expected tls, starttls or none)")
+            }
+            None if port == 465 => SmtpTls::Implicit,
+            None => SmtpTls::StartTls,
+        };
+
+        Ok(Self {
+            host,
+            port,
+            username: lookup("smtp.username")?,
+            password: lookup("smtp.password")?,
+            from: required("smtp.from")?,
+            tls,
+        })
+    }
+
+    /// NetAccess resource that must be granted to send mail through this server.
+    fn resource(&self) -> String {
+        format!("smtp://{}:{}", self.host, self.port)
+    }
+
+    fn transport(&self) -> anyhow::Result<SmtpTransport> {
+        let builder = match self.tls {
+            SmtpTls::Implicit => SmtpTransport::relay(&self.host)?,
+            SmtpTls::StartTls => SmtpTransport::starttls_relay(&self.host)?,
+            SmtpTls::None => SmtpTransport::builder_dangerous(&self.host),
+        };
+        let builder = builder.port(self.port);
+        let builder = match (&self.username, &self.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+        Ok(builder.build())
+    }
+}
+
+#[op2]
+fn op2_mail_send(
+    state: &mut OpState,
+    #[serde] message: MailMessage,
+) -> std::result::Result<(), JsErrorBox> {
+    let config = SmtpConfig::from_secrets().map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+
+    let function_id = mail_send_plugin_function().function_id;
+    ensure_permission(
+        state,
+        &function_id,
+        mail_net_plugin_permissions(),
+        &config.resource(),
+    )?;
+    for attachment in &message.attachments {
+        ensure_permission(
+            state,
+            &function_id,
+            mail_attachment_plugin_permissions(),
+            attachment,
+        )?;
+    }
+
+    send_mail(&config, &message).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn build_message(from: &str, message: &MailMessage) -> anyhow::Result<Message> {
+    if message.to.is_empty() {
+        anyhow::bail!("at least one recipient is required");
+    }
+
+    let mut builder = Message::builder()
+        .from(from.parse::<Mailbox>()?)
+        .subject(message.subject.clone());
+    for to in &message.to {
+        builder = builder.to(to.parse::<Mailbox>()?);
+    }
+
+    if message.attachments.is_empty() {
+        return Ok(builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())?);
+    }
+
+    let mut multipart = MultiPart::mixed().singlepart(SinglePart::plain(message.body.clone()));
+    for path in &message.attachments {
+        let path = Path::new(path);
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow::anyhow!("invalid attachment path: {}", path.display()))?;
+        let content = std::fs::read(path)?;
+        multipart = multipart.singlepart(
+            Attachment::new(filename)
+                .body(content, ContentType::parse("application/octet-stream")?),
+        );
+    }
+    Ok(builder.multipart(multipart)?)
+}
+
+fn send_mail(config: &SmtpConfig, message: &MailMessage) -> anyhow::Result<()> {
+    let email = build_message(&config.from, message)?;
+    config.transport()?.send(&email)?;
+    log::info!(
+        "Sent mail via {} to {} recipient(s)",
+        config.resource(),
+        message.to.len()
+    );
+    Ok(())
+}
+
+fn mail_plugin_permissions() -> Vec<Permission> {
+    let mut permissions = mail_net_plugin_permissions();
+    permissions.extend(mail_attachment_plugin_permissions());
+    permissions
+}
+
+fn mail_net_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Network Access".to_string(),
+        description: "Allows the plugin to connect to the configured SMTP server.".to_string(),
+        permission_type: PermissionType::NetAccess as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+fn mail_attachment_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Read".to_string(),
+        description: "Allows the plugin to read files attached to outgoing mail.".to_string(),
+        permission_type: PermissionType::FilesystemRead as i32,
+        permission_level: PermissionLevel::Medium as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup_from(
+        values: &[(&str, &str)],
+    ) -> impl Fn(&str) -> anyhow::Result<Option<String>> + use<> {
+        let values: HashMap<String, String> = values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| Ok(values.get(name).cloned())
+    }
+
+    fn message(attachments: Vec<String>) -> MailMessage {
+        MailMessage {
+            to: vec!["Alice <alice@example.com>".to_string()],
+            subject: "Weekly report".to_string(),
+            body: "See attached.".to_string(),
+            attachments,
+        }
+    }
+
+    #[test]
+    fn test_config_defaults_to_starttls_on_587() {
+        let config = SmtpConfig::load(lookup_from(&[
+            ("smtp.host", "smtp.example.com"),
+            ("smtp.from", "bot@example.com"),
+        ]))
+        .unwrap();
+        assert_eq!(config.port, 587);
+        assert_eq!(config.tls, SmtpTls::StartTls);
+        assert_eq!(config.resource(), "smtp://smtp.example.com:587");
+    }
+
+    #[test]
+    fn test_config_uses_implicit_tls_on_465() {
+        let config = SmtpConfig::load(lookup_from(&[
+            ("smtp.host", "smtp.example.com"),
+            ("smtp.port", "465"),
+            ("smtp.from", "bot@example.com"),
+        ]))
+        .unwrap();
+        assert_eq!(config.tls, SmtpTls::Implicit);
+    }
+
+    #[test]
+    fn test_config_requires_host() {
+        let err = SmtpConfig::load(lookup_from(&[("smtp.from", "bot@example.com")])).unwrap_err();
+        assert!(err.to_string().contains("smtp.host"));
+    }
+
+    #[test]
+    fn test_build_message_with_attachment() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.txt");
+        std::fs::write(&path, "numbers").unwrap();
+
+        let email = build_message(
+            "bot@example.com",
+            &message(vec![path.to_string_lossy().to_string()]),
+        )
+        .unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Subject: Weekly report"));
+        assert!(formatted.contains("report.txt"));
+    }
+
+    #[test]
+    fn test_build_message_rejects_invalid_recipient() {
+        let mut msg = message(vec![]);
+        msg.to = vec!["not an address".to_string()];
+        assert!(build_message("bot@example.com", &msg).is_err());
+    }
+}
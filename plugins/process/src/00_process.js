@@ -0,0 +1,20 @@
+function list() {
+    return Deno.core.ops.op2_process_list();
+}
+
+function info(pid) {
+    return Deno.core.ops.op2_process_info(pid);
+}
+
+function kill(pid) {
+    return Deno.core.ops.op2_process_kill(pid);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.process = globalThis.app.sapphillon.core.process || {};
+
+globalThis.app.sapphillon.core.process.list = list;
+globalThis.app.sapphillon.core.process.info = info;
+globalThis.app.sapphillon.core.process.kill = kill;
@@ -0,0 +1,345 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Process plugin - lists, inspects and terminates local processes with permission checks
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use serde::Serialize;
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System};
+
+const PROCESS_INFO_TYPE: &str = "{ pid: number, parentPid: number | null, name: string, exe: string | null, cmd: string[], cpuUsage: number, memory: number, status: string, startTime: number }";
+
+pub fn process_list_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.process.list".to_string(),
+        function_name: "process.list".to_string(),
+        version: "".to_string(),
+        description: "Lists running processes with their CPU and memory usage.".to_string(),
+        permissions: process_read_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "processes".to_string(),
+                r#type: format!("{PROCESS_INFO_TYPE}[]"),
+                description:
+                    "Running processes; cpuUsage is a percentage of one core and memory is in bytes"
+                        .to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn process_info_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.process.info".to_string(),
+        function_name: "process.info".to_string(),
+        version: "".to_string(),
+        description: "Returns details about a single process.".to_string(),
+        permissions: process_read_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "pid".to_string(),
+                r#type: "number".to_string(),
+                description: "Process ID".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "process".to_string(),
+                r#type: format!("{PROCESS_INFO_TYPE} | null"),
+                description: "The process, or null if no process has this ID".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn process_kill_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.process.kill".to_string(),
+        function_name: "process.kill".to_string(),
+        version: "".to_string(),
+        description: "Terminates a process.".to_string(),
+        permissions: process_kill_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "pid".to_string(),
+                r#type: "number".to_string(),
+                description: "Process ID".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "killed".to_string(),
+                r#type: "boolean".to_string(),
+                description: "Whether the termination signal was delivered".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn process_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.process".to_string(),
+        package_name: "Process".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to list, inspect and terminate local processes.".to_string(),
+        functions: vec![
+            process_list_plugin_function(),
+            process_info_plugin_function(),
+            process_kill_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_process_list_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        process_list_plugin_function().function_id,
+        "List".to_string(),
+        process_list_plugin_function().description,
+        op2_process_list(),
+        Some(include_str!("00_process.js").to_string()),
+    )
+}
+
+pub fn core_process_info_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        process_info_plugin_function().function_id,
+        "Info".to_string(),
+        process_info_plugin_function().description,
+        op2_process_info(),
+        Some(include_str!("00_process.js").to_string()),
+    )
+}
+
+pub fn core_process_kill_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        process_kill_plugin_function().function_id,
+        "Kill".to_string(),
+        process_kill_plugin_function().description,
+        op2_process_kill(),
+        Some(include_str!("00_process.js").to_string()),
+    )
+}
+
+pub fn core_process_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        process_plugin_package().package_id,
+        "Process".to_string(),
+        vec![
+            core_process_list_plugin(),
+            core_process_info_plugin(),
+            core_process_kill_plugin(),
+        ],
+    )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProcessInfo {
+    pid: u32,
+    parent_pid: Option<u32>,
+    name: String,
+    exe: Option<String>,
+    cmd: Vec<String>,
+    cpu_usage: f32,
+    memory: u64,
+    status: String,
+    start_time: u64,
+}
+
+impl From<&Process> for ProcessInfo {
+    fn from(process: &Process) -> Self {
+        Self {
+            pid: process.pid().as_u32(),
+            parent_pid: process.parent().map(|pid| pid.as_u32()),
+            name: process.name().to_string_lossy().to_string(),
+            exe: process.exe().map(|path| path.to_string_lossy().to_string()),
+            cmd: process
+                .cmd()
+                .iter()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            cpu_usage: process.cpu_usage(),
+            memory: process.memory(),
+            status: process.status().to_string(),
+            start_time: process.start_time(),
+        }
+    }
+}
+
+#[op2]
+#[serde]
+fn op2_process_list(state: &mut OpState) -> std::result::Result<Vec<ProcessInfo>, JsErrorBox> {
+    ensure_permission(
+        state,
+        &process_list_plugin_function().function_id,
+        process_read_plugin_permissions(),
+        "",
+    )?;
+
+    let system = snapshot(None);
+    let mut processes: Vec<ProcessInfo> = system.processes().values().map(Into::into).collect();
+    processes.sort_by_key(|process| process.pid);
+    Ok(processes)
+}
+
+#[op2]
+#[serde]
+fn op2_process_info(
+    state: &mut OpState,
+    #[smi] pid: u32,
+) -> std::result::Result<Option<ProcessInfo>, JsErrorBox> {
+    ensure_permission(
+        state,
+        &process_info_plugin_function().function_id,
+        process_read_plugin_permissions(),
+        "",
+    )?;
+
+    let pid = Pid::from_u32(pid);
+    let system = snapshot(Some(&[pid]));
+    Ok(system.process(pid).map(Into::into))
+}
+
+#[op2]
+fn op2_process_kill(state: &mut OpState, #[smi] pid: u32) -> std::result::Result<bool, JsErrorBox> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    let process = system
+        .process(pid)
+        .ok_or_else(|| JsErrorBox::new("Error", format!("No process with PID {pid}")))?;
+
+    // Grants name the executable rather than the PID, which changes between runs.
+    ensure_permission(
+        state,
+        &process_kill_plugin_function().function_id,
+        process_kill_plugin_permissions(),
+        &process.name().to_string_lossy(),
+    )?;
+
+    let killed = process.kill();
+    log::info!("Kill PID {pid}: {killed}");
+    Ok(killed)
+}
+
+/// Refreshes the selected processes twice so CPU usage reflects a real sampling interval.
+fn snapshot(pids: Option<&[Pid]>) -> System {
+    let to_update = || match pids {
+        Some(pids) => ProcessesToUpdate::Some(pids),
+        None => ProcessesToUpdate::All,
+    };
+    let refresh = ProcessRefreshKind::everything();
+    let mut system = System::new();
+    system.refresh_processes_specifics(to_update(), true, refresh);
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_processes_specifics(to_update(), true, refresh);
+    system
+}
+
+fn process_read_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Process Access".to_string(),
+        description: "Allows the plugin to inspect running processes.".to_string(),
+        permission_type: PermissionType::Execute as i32,
+        permission_level: PermissionLevel::Unspecified as i32,
+        resource: vec![],
+    }]
+}
+
+fn process_kill_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Process Control".to_string(),
+        description: "Allows the plugin to terminate processes.".to_string(),
+        permission_type: PermissionType::Execute as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_snapshot_contains_current_process() {
+        let pid = Pid::from_u32(std::process::id());
+        let system = snapshot(Some(&[pid]));
+        let info = ProcessInfo::from(system.process(pid).unwrap());
+        assert_eq!(info.pid, std::process::id());
+        assert!(!info.name.is_empty());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_process_info_in_workflow() {
+        let code = format!(
+            "const p = app.sapphillon.core.process.info({}); console.log(p.pid);",
+            std::process::id()
+        );
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: process_info_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: process_read_plugin_permissions(),
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_process_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(
+            workflow.result[0].result,
+            format!("{}\n", std::process::id())
+        );
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_kill_permission_denied_in_workflow() {
+        let code = format!("app.sapphillon.core.process.kill({});", std::process::id());
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: process_kill_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: vec![],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_process_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+    }
+}
@@ -0,0 +1,33 @@
+const DEFAULT_TIMEOUT_MS = 60 * 60 * 1000;
+
+function ask(kind, message, options) {
+    const opts = options || {};
+    const workflowId = globalThis.__sapphillonStateNamespace;
+    const timeoutMs = Math.max(0, Math.floor(Number(opts.timeoutMs ?? DEFAULT_TIMEOUT_MS) || 0));
+    const answer = Deno.core.ops.op2_prompt_ask(
+        typeof workflowId === "string" ? workflowId : "",
+        kind,
+        String(message),
+        timeoutMs,
+    );
+    if (answer === null || answer === undefined) {
+        throw new Error("prompt was not answered in time: " + message);
+    }
+    return answer;
+}
+
+function confirm(message, options) {
+    return ask("confirm", message, options) === true;
+}
+
+function input(message, options) {
+    return String(ask("input", message, options));
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.prompt = globalThis.app.sapphillon.core.prompt || {};
+
+globalThis.app.sapphillon.core.prompt.confirm = confirm;
+globalThis.app.sapphillon.core.prompt.input = input;
@@ -0,0 +1,265 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Prompt plugin - pauses a workflow until a person answers a question
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// What kind of answer a prompt expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    /// A yes/no question, answered with a JSON boolean.
+    Confirm,
+    /// A free-text question, answered with a JSON string.
+    Input,
+}
+
+impl PromptKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptKind::Confirm => "confirm",
+            PromptKind::Input => "input",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "confirm" => Some(PromptKind::Confirm),
+            "input" => Some(PromptKind::Input),
+            _ => None,
+        }
+    }
+}
+
+/// Surfaces prompts to a person and waits for the answer.
+pub trait PromptHandler: Send + Sync {
+    /// Blocks until the prompt is answered, returning `None` when `timeout` elapses first.
+    fn ask(
+        &self,
+        workflow_id: &str,
+        kind: PromptKind,
+        message: &str,
+        timeout: Duration,
+    ) -> anyhow::Result<Option<Value>>;
+}
+
+/// Handler used until one is installed: nobody can answer, so every prompt fails.
+struct UnattendedPromptHandler;
+
+impl PromptHandler for UnattendedPromptHandler {
+    fn ask(&self, _: &str, _: PromptKind, _: &str, _: Duration) -> anyhow::Result<Option<Value>> {
+        anyhow::bail!("prompts can only be answered while the Sapphillon server is running")
+    }
+}
+
+static PROMPT_HANDLER: OnceLock<Arc<dyn PromptHandler>> = OnceLock::new();
+
+/// Installs the handler backing `prompt.confirm`/`prompt.input`. Only the first call takes effect.
+pub fn set_prompt_handler(handler: Arc<dyn PromptHandler>) -> bool {
+    PROMPT_HANDLER.set(handler).is_ok()
+}
+
+fn prompt_handler() -> &'static Arc<dyn PromptHandler> {
+    PROMPT_HANDLER.get_or_init(|| Arc::new(UnattendedPromptHandler))
+}
+
+pub fn prompt_confirm_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.prompt.confirm".to_string(),
+        function_name: "prompt.confirm".to_string(),
+        version: "".to_string(),
+        description: "Pauses the workflow until a person approves or rejects the question."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "message".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Question shown to the person".to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: "object".to_string(),
+                    description: "Optional { timeoutMs = 3600000 }; an unanswered prompt throws"
+                        .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "approved".to_string(),
+                r#type: "boolean".to_string(),
+                description: "True when the question was approved".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn prompt_input_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.prompt.input".to_string(),
+        function_name: "prompt.input".to_string(),
+        version: "".to_string(),
+        description: "Pauses the workflow until a person types an answer.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "message".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Question shown to the person".to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: "object".to_string(),
+                    description: "Optional { timeoutMs = 3600000 }; an unanswered prompt throws"
+                        .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "answer".to_string(),
+                r#type: "string".to_string(),
+                description: "Text the person entered".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn prompt_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.prompt".to_string(),
+        package_name: "Prompt".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to ask a person for approval or input during a workflow."
+            .to_string(),
+        functions: vec![
+            prompt_confirm_plugin_function(),
+            prompt_input_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_prompt_confirm_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        prompt_confirm_plugin_function().function_id,
+        "Confirm".to_string(),
+        prompt_confirm_plugin_function().description,
+        op2_prompt_ask(),
+        Some(include_str!("00_prompt.js").to_string()),
+    )
+}
+
+pub fn core_prompt_input_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        prompt_input_plugin_function().function_id,
+        "Input".to_string(),
+        prompt_input_plugin_function().description,
+        op2_prompt_ask(),
+        Some(include_str!("00_prompt.js").to_string()),
+    )
+}
+
+pub fn core_prompt_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        prompt_plugin_package().package_id,
+        "Prompt".to_string(),
+        vec![core_prompt_confirm_plugin(), core_prompt_input_plugin()],
+    )
+}
+
+#[op2]
+#[serde]
+fn op2_prompt_ask(
+    #[string] workflow_id: String,
+    #[string] kind: String,
+    #[string] message: String,
+    timeout_ms: f64,
+) -> std::result::Result<Option<Value>, JsErrorBox> {
+    // Asking a person is an effect of its own, so dry runs never do it.
+    plugin_permission::stub_in_dry_run(&format!("app.sapphillon.core.prompt.{kind}"))?;
+    let kind = PromptKind::parse(&kind)
+        .ok_or_else(|| JsErrorBox::new("Error", format!("unknown prompt kind: {kind}")))?;
+    // Capped so the deadline can never overflow; about 49 days is effectively forever.
+    let timeout = Duration::from_millis(timeout_ms.clamp(0.0, f64::from(u32::MAX)) as u64);
+    prompt_handler()
+        .ask(&workflow_id, kind, &message, timeout)
+        .map_err(|e| JsErrorBox::new("Error", format!("{e:#}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use serde_json::json;
+
+    /// Approves every confirmation and answers inputs with an upper-cased message;
+    /// messages containing "ignore" time out.
+    struct ScriptedPromptHandler;
+
+    impl PromptHandler for ScriptedPromptHandler {
+        fn ask(
+            &self,
+            _: &str,
+            kind: PromptKind,
+            message: &str,
+            _: Duration,
+        ) -> anyhow::Result<Option<Value>> {
+            if message.contains("ignore") {
+                return Ok(None);
+            }
+            Ok(Some(match kind {
+                PromptKind::Confirm => json!(true),
+                PromptKind::Input => json!(message.to_uppercase()),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_prompt_kind_round_trips() {
+        for kind in [PromptKind::Confirm, PromptKind::Input] {
+            assert_eq!(PromptKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(PromptKind::parse("choice"), None);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_prompts_in_workflow() {
+        set_prompt_handler(Arc::new(ScriptedPromptHandler));
+        let code = r#"
+            const prompt = app.sapphillon.core.prompt;
+            let timedOut = false;
+            try {
+                prompt.input("ignore me", { timeoutMs: 1 });
+            } catch (e) {
+                timedOut = true;
+            }
+            console.log(`${prompt.confirm("delete 200 files?")} ${prompt.input("name")} ${timedOut}`);
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_prompt_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result[0].result, "true NAME true\n");
+    }
+}
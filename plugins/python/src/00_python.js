@@ -0,0 +1,9 @@
+function registerPythonFunction(packageId, functionName) {
+    let target = globalThis;
+    for (const segment of packageId.split(".")) {
+        target[segment] = target[segment] || {};
+        target = target[segment];
+    }
+    target[functionName] = (...args) =>
+        JSON.parse(Deno.core.ops.op2_python_call(packageId, functionName, JSON.stringify(args)));
+}
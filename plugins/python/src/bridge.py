@@ -0,0 +1,48 @@
+# Sapphillon
+# SPDX-FileCopyrightText: 2025 Yuta Takahashi
+# SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+# Runs one call of a Python plugin: reads a JSON-RPC request from stdin, calls the
+# named function of the entrypoint with the request's arguments and writes the
+# JSON-RPC response to stdout. Anything the plugin prints goes to stderr.
+
+import importlib.util
+import json
+import sys
+import traceback
+
+
+def respond(message):
+    sys.__stdout__.write(json.dumps(message) + "\n")
+    sys.__stdout__.flush()
+
+
+def main():
+    entrypoint = sys.argv[1]
+    request = json.loads(sys.stdin.readline())
+    request_id = request.get("id")
+    sys.stdout = sys.stderr
+    try:
+        spec = importlib.util.spec_from_file_location("sapphillon_plugin", entrypoint)
+        module = importlib.util.module_from_spec(spec)
+        spec.loader.exec_module(module)
+        params = request["params"]
+        function = getattr(module, params["func_name"])
+        result = function(**params["args"])
+        respond({"jsonrpc": "2.0", "id": request_id, "result": {"args": {"result": result}}})
+    except Exception as error:
+        respond(
+            {
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "error": {
+                    "code": -32000,
+                    "message": f"{type(error).__name__}: {error}",
+                    "data": traceback.format_exc(),
+                },
+            }
+        )
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,599 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Python plugin - external plugins written in Python and run in a subprocess
+//
+// A Python plugin is a directory with a `plugin.json` manifest and the script it names:
+//
+// {
+//   "runtime": "python",
+//   "meta": { "name": "slugify", "version": "1.0.0", "description": "...",
+//             "author_id": "com.example", "package_id": "com.example.slugify" },
+//   "entrypoint": "main.py",
+//   "functions": {
+//     "slugify": {
+//       "description": "...",
+//       "permissions": [],
+//       "parameters": [{ "name": "text", "type": "string", "description": "..." }],
+//       "returns": [{ "name": "slug", "type": "string", "description": "..." }]
+//     }
+//   }
+// }
+//
+// Each call starts the interpreter with a small bridge that imports the entrypoint and
+// exchanges one JSON-RPC request and response over stdio. The request carries the
+// same `func_name` and named `args` as `RsJsBridgeArgs`, and the result is returned
+// under `args.result` like `RsJsBridgeReturns`.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PluginFunction, PluginPackage,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// Name of the manifest file in a Python plugin directory.
+pub const MANIFEST_FILE: &str = "plugin.json";
+
+/// Value of the manifest's `runtime` field for Python plugins.
+pub const RUNTIME: &str = "python";
+
+/// Package that carries the shared op for every registered Python plugin.
+const PYTHON_HOST_PACKAGE_ID: &str = "app.sapphillon.python";
+
+/// Script that runs one call inside the interpreter.
+const BRIDGE: &str = include_str!("bridge.py");
+
+/// Error types for Python plugins.
+#[derive(Debug, thiserror::Error)]
+pub enum PythonPluginError {
+    #[error("invalid Python plugin manifest: {0}")]
+    Manifest(String),
+
+    #[error("invalid arguments: {0}")]
+    Arguments(String),
+
+    #[error("failed to start the Python interpreter: {0}")]
+    Spawn(String),
+
+    #[error("Python plugin did not answer within {0:?}")]
+    Timeout(Duration),
+
+    #[error("Python plugin broke the bridge protocol: {0}")]
+    Protocol(String),
+
+    #[error("Python plugin raised {0}")]
+    Plugin(String),
+}
+
+/// How calls are run.
+#[derive(Debug, Clone)]
+pub struct PythonLimits {
+    /// Interpreter to start, looked up on `PATH` unless absolute.
+    pub interpreter: String,
+    /// Time a single call may take before the interpreter is killed.
+    pub timeout: Duration,
+}
+
+impl Default for PythonLimits {
+    fn default() -> Self {
+        Self {
+            interpreter: "python3".to_string(),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PythonPluginMeta {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub author_id: String,
+    pub package_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestParameter {
+    name: String,
+    #[serde(rename = "type")]
+    parameter_type: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestPermission {
+    #[serde(rename = "type")]
+    permission_type: String,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    resource: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestFunction {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    permissions: Vec<ManifestPermission>,
+    #[serde(default)]
+    parameters: Vec<ManifestParameter>,
+    #[serde(default)]
+    returns: Vec<ManifestParameter>,
+}
+
+/// Contents of a Python plugin's `plugin.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PythonPluginManifest {
+    pub runtime: String,
+    pub meta: PythonPluginMeta,
+    /// Path of the script, relative to the manifest.
+    pub entrypoint: String,
+    functions: BTreeMap<String, ManifestFunction>,
+}
+
+impl PythonPluginManifest {
+    pub fn parse(json: &str) -> Result<Self, PythonPluginError> {
+        let manifest: Self =
+            serde_json::from_str(json).map_err(|e| PythonPluginError::Manifest(e.to_string()))?;
+        if manifest.runtime != RUNTIME {
+            return Err(PythonPluginError::Manifest(format!(
+                "runtime must be '{RUNTIME}', not '{}'",
+                manifest.runtime
+            )));
+        }
+        for (field, value) in [
+            ("name", &manifest.meta.name),
+            ("version", &manifest.meta.version),
+            ("author_id", &manifest.meta.author_id),
+            ("package_id", &manifest.meta.package_id),
+            ("entrypoint", &manifest.entrypoint),
+        ] {
+            if value.trim().is_empty() {
+                return Err(PythonPluginError::Manifest(format!(
+                    "{field} must not be empty"
+                )));
+            }
+        }
+        if manifest.functions.is_empty() {
+            return Err(PythonPluginError::Manifest(
+                "functions declares no functions".to_string(),
+            ));
+        }
+        Ok(manifest)
+    }
+}
+
+/// A loaded Python plugin.
+pub struct PythonPlugin {
+    meta: PythonPluginMeta,
+    entrypoint: PathBuf,
+    functions: BTreeMap<String, PluginFunction>,
+    limits: PythonLimits,
+}
+
+impl PythonPlugin {
+    /// Loads the plugin in `dir` from its `plugin.json`.
+    pub fn load(dir: &Path, limits: PythonLimits) -> Result<Self, PythonPluginError> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let json = std::fs::read_to_string(&manifest_path).map_err(|e| {
+            PythonPluginError::Manifest(format!("{}: {e}", manifest_path.display()))
+        })?;
+        Self::new(PythonPluginManifest::parse(&json)?, dir, limits)
+    }
+
+    /// Builds a plugin from a parsed manifest whose entrypoint is relative to `dir`.
+    pub fn new(
+        manifest: PythonPluginManifest,
+        dir: &Path,
+        limits: PythonLimits,
+    ) -> Result<Self, PythonPluginError> {
+        let entrypoint = dir.join(&manifest.entrypoint);
+        if !entrypoint.is_file() {
+            return Err(PythonPluginError::Manifest(format!(
+                "entrypoint {} does not exist",
+                entrypoint.display()
+            )));
+        }
+
+        let mut functions = BTreeMap::new();
+        for (name, function) in manifest.functions {
+            let permissions = function
+                .permissions
+                .iter()
+                .map(to_permission)
+                .collect::<Result<Vec<_>, _>>()?;
+            let to_parameters = |parameters: Vec<ManifestParameter>| -> Vec<FunctionParameter> {
+                parameters
+                    .into_iter()
+                    .map(|p| FunctionParameter {
+                        name: p.name,
+                        r#type: p.parameter_type,
+                        description: p.description,
+                    })
+                    .collect()
+            };
+            functions.insert(
+                name.clone(),
+                PluginFunction {
+                    function_id: format!("{}.{name}", manifest.meta.package_id),
+                    function_name: name,
+                    version: manifest.meta.version.clone(),
+                    description: function.description,
+                    permissions,
+                    function_define: Some(FunctionDefine {
+                        parameters: to_parameters(function.parameters),
+                        returns: to_parameters(function.returns),
+                    }),
+                },
+            );
+        }
+        Ok(Self {
+            meta: manifest.meta,
+            entrypoint,
+            functions,
+            limits,
+        })
+    }
+
+    pub fn meta(&self) -> &PythonPluginMeta {
+        &self.meta
+    }
+
+    /// Describes the plugin the way built-in plugins are registered.
+    pub fn plugin_package(&self) -> PluginPackage {
+        PluginPackage {
+            package_id: self.meta.package_id.clone(),
+            package_name: self.meta.name.clone(),
+            provider_id: "".to_string(),
+            package_version: self.meta.version.clone(),
+            description: self.meta.description.clone(),
+            functions: self.functions.values().cloned().collect(),
+            plugin_store_url: "".to_string(),
+            internal_plugin: Some(false),
+            verified: Some(false),
+            deprecated: Some(false),
+            installed_at: None,
+            updated_at: None,
+        }
+    }
+
+    /// Calls a function with positional arguments, which are passed to Python by the
+    /// parameter names the manifest declares.
+    pub fn call(&self, function: &str, args: &[Value]) -> Result<Value, PythonPluginError> {
+        let plugin_function = self.functions.get(function).ok_or_else(|| {
+            PythonPluginError::Arguments(format!(
+                "{} has no function '{function}'",
+                self.meta.package_id
+            ))
+        })?;
+        let parameters = plugin_function
+            .function_define
+            .as_ref()
+            .map(|define| define.parameters.as_slice())
+            .unwrap_or_default();
+        if args.len() > parameters.len() {
+            return Err(PythonPluginError::Arguments(format!(
+                "{function} takes {} arguments but {} were given",
+                parameters.len(),
+                args.len()
+            )));
+        }
+        let named: serde_json::Map<String, Value> = parameters
+            .iter()
+            .zip(args)
+            .map(|(parameter, value)| (parameter.name.clone(), value.clone()))
+            .collect();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "call",
+            "params": { "func_name": function, "args": named },
+        });
+        let response = self.exchange(&request)?;
+        if let Some(error) = response.get("error") {
+            let message = error["message"].as_str().unwrap_or("an error");
+            return Err(PythonPluginError::Plugin(message.to_string()));
+        }
+        response
+            .pointer("/result/args/result")
+            .cloned()
+            .ok_or_else(|| PythonPluginError::Protocol("response has no result".to_string()))
+    }
+
+    /// Runs the bridge for one request and reads its response.
+    fn exchange(&self, request: &Value) -> Result<Value, PythonPluginError> {
+        let dir = self.entrypoint.parent().unwrap_or(Path::new("."));
+        let mut child = Command::new(&self.limits.interpreter)
+            .arg("-c")
+            .arg(BRIDGE)
+            .arg(&self.entrypoint)
+            .current_dir(dir)
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("PYTHONIOENCODING", "utf-8")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| PythonPluginError::Spawn(format!("{}: {e}", self.limits.interpreter)))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let written = writeln!(stdin, "{request}");
+        drop(stdin);
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = std::thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+
+        let deadline = Instant::now() + self.limits.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(PythonPluginError::Timeout(self.limits.timeout));
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                Err(e) => return Err(PythonPluginError::Spawn(e.to_string())),
+            }
+        }
+        written.map_err(|e| PythonPluginError::Protocol(format!("cannot send request: {e}")))?;
+
+        let output = reader
+            .join()
+            .map_err(|_| PythonPluginError::Protocol("cannot read response".to_string()))?
+            .map_err(|e| PythonPluginError::Protocol(e.to_string()))?;
+        let line = output
+            .lines()
+            .last()
+            .ok_or_else(|| PythonPluginError::Protocol("no response".to_string()))?;
+        serde_json::from_str(line)
+            .map_err(|e| PythonPluginError::Protocol(format!("response is not JSON: {e}")))
+    }
+}
+
+fn to_permission(permission: &ManifestPermission) -> Result<Permission, PythonPluginError> {
+    let permission_type = plugin_permission::parse_permission_type(&permission.permission_type)
+        .ok_or_else(|| {
+            PythonPluginError::Manifest(format!(
+                "unknown permission type '{}'",
+                permission.permission_type
+            ))
+        })?;
+    let permission_level = match permission.level.as_deref() {
+        None => PermissionLevel::Unspecified as i32,
+        Some(level) => plugin_permission::parse_permission_level(level).ok_or_else(|| {
+            PythonPluginError::Manifest(format!("unknown permission level '{level}'"))
+        })?,
+    };
+    Ok(Permission {
+        display_name: permission.permission_type.clone(),
+        description: "Declared by a Python plugin".to_string(),
+        permission_type,
+        permission_level,
+        resource: permission.resource.clone(),
+    })
+}
+
+static PYTHON_PLUGINS: RwLock<BTreeMap<String, Arc<PythonPlugin>>> = RwLock::new(BTreeMap::new());
+
+/// Replaces every registered plugin, e.g. when the installed plugins are reloaded.
+pub fn replace_python_plugins(plugins: Vec<PythonPlugin>) {
+    *PYTHON_PLUGINS.write().unwrap() = plugins
+        .into_iter()
+        .map(|plugin| (plugin.meta.package_id.clone(), Arc::new(plugin)))
+        .collect();
+}
+
+fn registered_plugin(package_id: &str) -> Option<Arc<PythonPlugin>> {
+    PYTHON_PLUGINS.read().unwrap().get(package_id).cloned()
+}
+
+fn registered_plugins() -> Vec<Arc<PythonPlugin>> {
+    PYTHON_PLUGINS.read().unwrap().values().cloned().collect()
+}
+
+/// The runtime package that exposes every registered Python plugin to workflows as
+/// `<package_id>.<function>(...)`.
+pub fn core_python_plugin_package() -> CorePluginPackage {
+    let mut js = include_str!("00_python.js").to_string();
+    for plugin in registered_plugins() {
+        for name in plugin.functions.keys() {
+            js.push_str(&format!(
+                "registerPythonFunction({}, {});\n",
+                Value::String(plugin.meta.package_id.clone()),
+                Value::String(name.clone())
+            ));
+        }
+    }
+    CorePluginPackage::new(
+        PYTHON_HOST_PACKAGE_ID.to_string(),
+        "Python".to_string(),
+        vec![CorePluginFunction::new(
+            format!("{PYTHON_HOST_PACKAGE_ID}.call"),
+            "Call".to_string(),
+            "Calls a function of a registered Python plugin.".to_string(),
+            op2_python_call(),
+            Some(js),
+        )],
+    )
+}
+
+#[op2]
+#[string]
+fn op2_python_call(
+    state: &mut OpState,
+    #[string] package_id: String,
+    #[string] function: String,
+    #[string] args_json: String,
+) -> std::result::Result<String, JsErrorBox> {
+    let plugin = registered_plugin(&package_id).ok_or_else(|| {
+        JsErrorBox::new(
+            "Error",
+            format!("Python plugin '{package_id}' is not loaded"),
+        )
+    })?;
+    let plugin_function = plugin.functions.get(&function).ok_or_else(|| {
+        JsErrorBox::new(
+            "Error",
+            format!("Python plugin '{package_id}' has no function '{function}'"),
+        )
+    })?;
+
+    // The interpreter is not sandboxed, so the declared permissions are checked before
+    // it starts, each resource on its own like the built-in plugins do.
+    for permission in &plugin_function.permissions {
+        let unscoped = Permission {
+            resource: vec![],
+            ..permission.clone()
+        };
+        if permission.resource.is_empty() {
+            ensure_permission(
+                state,
+                &plugin_function.function_id,
+                vec![unscoped.clone()],
+                "",
+            )?;
+        }
+        for resource in &permission.resource {
+            ensure_permission(
+                state,
+                &plugin_function.function_id,
+                vec![unscoped.clone()],
+                resource,
+            )?;
+        }
+    }
+
+    let args: Vec<Value> = serde_json::from_str(&args_json)
+        .map_err(|e| JsErrorBox::new("TypeError", e.to_string()))?;
+    let result = plugin
+        .call(&function, &args)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN_PY: &str = r#"
+import time
+
+def greet(name, punctuation="!"):
+    print("logged, not returned")
+    return {"greeting": f"Hello, {name}{punctuation}"}
+
+def fail():
+    raise ValueError("bad input")
+
+def sleep():
+    time.sleep(10)
+"#;
+
+    const MANIFEST: &str = r#"{
+        "runtime": "python",
+        "meta": { "name": "greeter", "version": "1.0.0", "author_id": "com.test",
+                  "package_id": "com.test.greeter" },
+        "entrypoint": "main.py",
+        "functions": {
+            "greet": { "parameters": [
+                { "name": "name", "type": "string" },
+                { "name": "punctuation", "type": "string" }
+            ] },
+            "fail": {},
+            "sleep": {}
+        }
+    }"#;
+
+    fn python_available() -> bool {
+        Command::new("python3").arg("--version").output().is_ok()
+    }
+
+    fn load(dir: &Path, limits: PythonLimits) -> PythonPlugin {
+        std::fs::write(dir.join(MANIFEST_FILE), MANIFEST).unwrap();
+        std::fs::write(dir.join("main.py"), MAIN_PY).unwrap();
+        PythonPlugin::load(dir, limits).unwrap()
+    }
+
+    #[test]
+    fn call_passes_named_arguments_and_returns_the_result() {
+        if !python_available() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = load(dir.path(), PythonLimits::default());
+
+        assert_eq!(
+            plugin.call("greet", &[json!("Ada")]).unwrap(),
+            json!({ "greeting": "Hello, Ada!" })
+        );
+        assert_eq!(
+            plugin.call("greet", &[json!("Ada"), json!("?")]).unwrap(),
+            json!({ "greeting": "Hello, Ada?" })
+        );
+        assert!(matches!(
+            plugin.call("greet", &[json!(1), json!(2), json!(3)]),
+            Err(PythonPluginError::Arguments(_))
+        ));
+        assert_eq!(
+            plugin.plugin_package().functions[1].function_id,
+            "com.test.greeter.greet"
+        );
+    }
+
+    #[test]
+    fn call_reports_exceptions_and_timeouts() {
+        if !python_available() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = load(
+            dir.path(),
+            PythonLimits {
+                timeout: Duration::from_millis(500),
+                ..PythonLimits::default()
+            },
+        );
+
+        let err = plugin.call("fail", &[]).unwrap_err();
+        assert!(
+            matches!(&err, PythonPluginError::Plugin(message) if message == "ValueError: bad input")
+        );
+        assert!(matches!(
+            plugin.call("sleep", &[]),
+            Err(PythonPluginError::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn manifest_requires_python_runtime_and_entrypoint() {
+        assert!(PythonPluginManifest::parse(MANIFEST).is_ok());
+        assert!(PythonPluginManifest::parse(&MANIFEST.replace("\"python\"", "\"wasm\"")).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = PythonPluginManifest::parse(MANIFEST).unwrap();
+        assert!(matches!(
+            PythonPlugin::new(manifest, dir.path(), PythonLimits::default()),
+            Err(PythonPluginError::Manifest(_))
+        ));
+    }
+}
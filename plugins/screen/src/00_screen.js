@@ -0,0 +1,38 @@
+function monitors() {
+    return Deno.core.ops.op2_screen_monitors();
+}
+
+// Ops return PNG bytes as a Uint8Array; workflows get a standalone ArrayBuffer.
+function toCaptureResult(result) {
+    if (result instanceof Uint8Array) {
+        return result.buffer.slice(result.byteOffset, result.byteOffset + result.byteLength);
+    }
+    return result;
+}
+
+function capture(monitorId, options) {
+    return toCaptureResult(
+        Deno.core.ops.op2_screen_capture(
+            monitorId === undefined ? null : monitorId,
+            options === undefined ? null : options,
+        ),
+    );
+}
+
+function captureWindow(titleOrId, options) {
+    return toCaptureResult(
+        Deno.core.ops.op2_screen_capture_window(
+            String(titleOrId),
+            options === undefined ? null : options,
+        ),
+    );
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.screen = globalThis.app.sapphillon.core.screen || {};
+
+globalThis.app.sapphillon.core.screen.monitors = monitors;
+globalThis.app.sapphillon.core.screen.capture = capture;
+globalThis.app.sapphillon.core.screen.captureWindow = captureWindow;
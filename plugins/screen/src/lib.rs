@@ -0,0 +1,495 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Screen plugin - captures monitors and native windows as PNG images
+use base64::{Engine as _, engine::general_purpose};
+use deno_core::{OpState, ToJsBuffer, op2};
+use deno_error::JsErrorBox;
+use image::{ImageFormat, RgbaImage};
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use xcap::{Monitor, Window};
+
+/// Permission resource for capturing screen contents.
+pub const SCREEN_RESOURCE: &str = "screen://capture";
+
+pub fn screen_monitors_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.screen.monitors".to_string(),
+        function_name: "screen.monitors".to_string(),
+        version: "".to_string(),
+        description: "Lists the connected monitors and their geometry.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "monitors".to_string(),
+                r#type: "{ id: number, name: string, x: number, y: number, width: number, height: number, scaleFactor: number, isPrimary: boolean }[]".to_string(),
+                description: "Connected monitors; positions and sizes in pixels".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn screen_capture_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.screen.capture".to_string(),
+        function_name: "screen.capture".to_string(),
+        version: "".to_string(),
+        description:
+            "Captures a monitor as a PNG and returns it as base64, as bytes, or saves it to a file."
+                .to_string(),
+        permissions: screen_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "monitorId".to_string(),
+                    r#type: "number | undefined".to_string(),
+                    description:
+                        "Monitor id from screen.monitors(); defaults to the primary monitor"
+                            .to_string(),
+                },
+                capture_options_parameter(),
+            ],
+            returns: vec![capture_return_parameter()],
+        }),
+    }
+}
+
+pub fn screen_capture_window_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.screen.captureWindow".to_string(),
+        function_name: "screen.captureWindow".to_string(),
+        version: "".to_string(),
+        description:
+            "Captures a native window as a PNG and returns it as base64, as bytes, or saves it to a file."
+                .to_string(),
+        permissions: screen_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "titleOrId".to_string(),
+                    r#type: "string | number".to_string(),
+                    description:
+                        "Window id, or a case-insensitive substring of the window title or app name"
+                            .to_string(),
+                },
+                capture_options_parameter(),
+            ],
+            returns: vec![capture_return_parameter()],
+        }),
+    }
+}
+
+fn capture_options_parameter() -> FunctionParameter {
+    FunctionParameter {
+        name: "options".to_string(),
+        r#type: "{ format?: 'base64' | 'bytes', saveTo?: string } | undefined".to_string(),
+        description: "format 'bytes' returns an ArrayBuffer; saveTo writes the PNG to that path instead of returning it"
+            .to_string(),
+    }
+}
+
+fn capture_return_parameter() -> FunctionParameter {
+    FunctionParameter {
+        name: "png".to_string(),
+        r#type: "string | ArrayBuffer".to_string(),
+        description: "Base64-encoded PNG, the PNG bytes, or the path it was saved to".to_string(),
+    }
+}
+
+pub fn screen_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.screen".to_string(),
+        package_name: "Screen".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to capture screenshots of monitors and windows.".to_string(),
+        functions: vec![
+            screen_monitors_plugin_function(),
+            screen_capture_plugin_function(),
+            screen_capture_window_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_screen_monitors_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        screen_monitors_plugin_function().function_id,
+        "Monitors".to_string(),
+        screen_monitors_plugin_function().description,
+        op2_screen_monitors(),
+        Some(include_str!("00_screen.js").to_string()),
+    )
+}
+
+pub fn core_screen_capture_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        screen_capture_plugin_function().function_id,
+        "Capture".to_string(),
+        screen_capture_plugin_function().description,
+        op2_screen_capture(),
+        Some(include_str!("00_screen.js").to_string()),
+    )
+}
+
+pub fn core_screen_capture_window_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        screen_capture_window_plugin_function().function_id,
+        "CaptureWindow".to_string(),
+        screen_capture_window_plugin_function().description,
+        op2_screen_capture_window(),
+        Some(include_str!("00_screen.js").to_string()),
+    )
+}
+
+pub fn core_screen_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        screen_plugin_package().package_id,
+        "Screen".to_string(),
+        vec![
+            core_screen_monitors_plugin(),
+            core_screen_capture_plugin(),
+            core_screen_capture_window_plugin(),
+        ],
+    )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MonitorInfo {
+    id: u32,
+    name: String,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+    is_primary: bool,
+}
+
+#[op2]
+#[serde]
+fn op2_screen_monitors() -> std::result::Result<Vec<MonitorInfo>, JsErrorBox> {
+    list_monitors().map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_screen_capture(
+    state: &mut OpState,
+    #[serde] monitor_id: Option<u32>,
+    #[serde] options: Option<CaptureOptions>,
+) -> std::result::Result<CaptureOutput, JsErrorBox> {
+    let function_id = screen_capture_plugin_function().function_id;
+    let options = options.unwrap_or_default();
+    ensure_capture_permissions(state, &function_id, &options)?;
+
+    capture_monitor(monitor_id)
+        .and_then(|image| capture_output(&image, &options))
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_screen_capture_window(
+    state: &mut OpState,
+    #[string] title_or_id: String,
+    #[serde] options: Option<CaptureOptions>,
+) -> std::result::Result<CaptureOutput, JsErrorBox> {
+    let function_id = screen_capture_window_plugin_function().function_id;
+    let options = options.unwrap_or_default();
+    ensure_capture_permissions(state, &function_id, &options)?;
+
+    capture_window(&title_or_id)
+        .and_then(|image| capture_output(&image, &options))
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptureOptions {
+    #[serde(default)]
+    format: CaptureFormat,
+    save_to: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CaptureFormat {
+    #[default]
+    Base64,
+    Bytes,
+}
+
+/// What a capture returns: the PNG, or the path it was saved to.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum CaptureOutput {
+    Text(String),
+    Bytes(ToJsBuffer),
+}
+
+fn ensure_capture_permissions(
+    state: &mut OpState,
+    function_id: &str,
+    options: &CaptureOptions,
+) -> std::result::Result<(), JsErrorBox> {
+    ensure_permission(state, function_id, screen_capture_permissions(), "")?;
+    if let Some(path) = &options.save_to {
+        ensure_permission(state, function_id, screen_write_plugin_permissions(), path)?;
+    }
+    Ok(())
+}
+
+fn capture_output(image: &RgbaImage, options: &CaptureOptions) -> anyhow::Result<CaptureOutput> {
+    let png = encode_png(image)?;
+    if let Some(path) = &options.save_to {
+        std::fs::write(path, png)?;
+        return Ok(CaptureOutput::Text(path.clone()));
+    }
+    Ok(match options.format {
+        CaptureFormat::Base64 => CaptureOutput::Text(general_purpose::STANDARD.encode(png)),
+        CaptureFormat::Bytes => CaptureOutput::Bytes(png.into()),
+    })
+}
+
+fn list_monitors() -> anyhow::Result<Vec<MonitorInfo>> {
+    Monitor::all()?
+        .iter()
+        .map(|monitor| {
+            Ok(MonitorInfo {
+                id: monitor.id()?,
+                name: monitor.name()?,
+                x: monitor.x()?,
+                y: monitor.y()?,
+                width: monitor.width()?,
+                height: monitor.height()?,
+                scale_factor: monitor.scale_factor()?,
+                is_primary: monitor.is_primary()?,
+            })
+        })
+        .collect()
+}
+
+fn capture_monitor(monitor_id: Option<u32>) -> anyhow::Result<RgbaImage> {
+    let monitors = Monitor::all()?;
+    let mut selected = None;
+    for monitor in monitors {
+        let matches = match monitor_id {
+            Some(id) => monitor.id()? == id,
+            None => monitor.is_primary()?,
+        };
+        if matches {
+            selected = Some(monitor);
+            break;
+        }
+    }
+    let Some(monitor) = selected else {
+        match monitor_id {
+            Some(id) => anyhow::bail!("no monitor with id {id}"),
+            None => anyhow::bail!("no primary monitor found"),
+        }
+    };
+    Ok(monitor.capture_image()?)
+}
+
+fn capture_window(title_or_id: &str) -> anyhow::Result<RgbaImage> {
+    let mut windows = Vec::new();
+    for window in Window::all()? {
+        if window.is_minimized().unwrap_or(false) {
+            continue;
+        }
+        let candidate = WindowCandidate {
+            id: window.id()?,
+            title: window.title().unwrap_or_default(),
+            app_name: window.app_name().unwrap_or_default(),
+        };
+        windows.push((candidate, window));
+    }
+
+    let candidates: Vec<_> = windows.iter().map(|(c, _)| c.clone()).collect();
+    let Some(index) = find_window(&candidates, title_or_id) else {
+        anyhow::bail!("no window matches {title_or_id:?}");
+    };
+    Ok(windows[index].1.capture_image()?)
+}
+
+#[derive(Debug, Clone)]
+struct WindowCandidate {
+    id: u32,
+    title: String,
+    app_name: String,
+}
+
+/// Picks a window by exact id first, then by case-insensitive title or app name substring.
+fn find_window(candidates: &[WindowCandidate], title_or_id: &str) -> Option<usize> {
+    if let Ok(id) = title_or_id.trim().parse::<u32>() {
+        if let Some(index) = candidates.iter().position(|c| c.id == id) {
+            return Some(index);
+        }
+    }
+
+    let needle = title_or_id.to_lowercase();
+    if needle.is_empty() {
+        return None;
+    }
+    candidates
+        .iter()
+        .position(|c| c.title.to_lowercase().contains(&needle))
+        .or_else(|| {
+            candidates
+                .iter()
+                .position(|c| c.app_name.to_lowercase().contains(&needle))
+        })
+}
+
+fn encode_png(image: &RgbaImage) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    image.write_to(&mut buffer, ImageFormat::Png)?;
+    Ok(buffer.into_inner())
+}
+
+fn screen_plugin_permissions() -> Vec<Permission> {
+    let mut permissions = screen_capture_permissions();
+    permissions.extend(screen_write_plugin_permissions());
+    permissions
+}
+
+// The core permission model has no dedicated screen-recording type yet; reading
+// screen contents is treated as a high-level FilesystemRead permission on a
+// screen:// resource.
+fn screen_capture_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Screen Capture".to_string(),
+        description: "Allows the plugin to capture the contents of the screen and other windows."
+            .to_string(),
+        permission_type: PermissionType::FilesystemRead as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![SCREEN_RESOURCE.to_string()],
+    }]
+}
+
+/// Only checked when a capture is saved with `saveTo`.
+fn screen_write_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Write".to_string(),
+        description: "Allows the plugin to save screenshots to the local filesystem.".to_string(),
+        permission_type: PermissionType::FilesystemWrite as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    fn candidate(id: u32, title: &str, app_name: &str) -> WindowCandidate {
+        WindowCandidate {
+            id,
+            title: title.to_string(),
+            app_name: app_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_find_window() {
+        let candidates = vec![
+            candidate(10, "Inbox - Mail", "Mail"),
+            candidate(42, "README.md - Editor", "Code"),
+        ];
+
+        assert_eq!(find_window(&candidates, "42"), Some(1));
+        assert_eq!(find_window(&candidates, "readme"), Some(1));
+        assert_eq!(find_window(&candidates, "code"), Some(1));
+        assert_eq!(find_window(&candidates, "INBOX"), Some(0));
+        assert_eq!(find_window(&candidates, "terminal"), None);
+        assert_eq!(find_window(&candidates, ""), None);
+    }
+
+    #[test]
+    fn test_capture_output_base64() {
+        let image = RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]));
+        let CaptureOutput::Text(encoded) =
+            capture_output(&image, &CaptureOptions::default()).unwrap()
+        else {
+            panic!("expected base64 text");
+        };
+        let bytes = general_purpose::STANDARD.decode(encoded).unwrap();
+        assert!(bytes.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn test_capture_output_save_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.png").to_string_lossy().into_owned();
+        let image = RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]));
+        let options = CaptureOptions {
+            format: CaptureFormat::Bytes,
+            save_to: Some(path.clone()),
+        };
+
+        let CaptureOutput::Text(saved) = capture_output(&image, &options).unwrap() else {
+            panic!("expected the saved path");
+        };
+        assert_eq!(saved, path);
+        assert!(
+            std::fs::read(&path)
+                .unwrap()
+                .starts_with(b"\x89PNG\r\n\x1a\n")
+        );
+    }
+
+    #[test]
+    fn test_capture_options_parse() {
+        let options: CaptureOptions =
+            serde_json::from_str(r#"{ "format": "bytes", "saveTo": "/tmp/a.png" }"#).unwrap();
+        assert_eq!(options.format, CaptureFormat::Bytes);
+        assert_eq!(options.save_to.as_deref(), Some("/tmp/a.png"));
+        assert!(serde_json::from_str::<CaptureOptions>(r#"{ "format": "jpeg" }"#).is_err());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_permission_denied_in_workflow() {
+        let code = r#"app.sapphillon.core.screen.capture();"#;
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: screen_capture_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: vec![],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_screen_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+    }
+}
@@ -0,0 +1,10 @@
+function appsLaunch(id) {
+    return Deno.core.ops.op2_apps_launch(id);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.apps = globalThis.app.sapphillon.core.apps || {};
+
+globalThis.app.sapphillon.core.apps.launch = appsLaunch;
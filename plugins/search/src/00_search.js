@@ -1,5 +1,51 @@
-function searchFile(root_path, query) {
-    return Deno.core.ops.op2_search_file(root_path, query);
+// Dates may be passed as Date objects, ISO strings or milliseconds since the epoch.
+function toEpochMillis(value) {
+    if (value === undefined || value === null) {
+        return undefined;
+    }
+    if (value instanceof Date) {
+        return value.getTime();
+    }
+    if (typeof value === "string") {
+        return Date.parse(value);
+    }
+    return value;
+}
+
+// Splits the options into the result filter and the page, which the ops take separately.
+function toSearchArgs(options) {
+    if (options === undefined || options === null) {
+        return [null, null];
+    }
+    const { offset, maxResults, ...filter } = options;
+    return [
+        {
+            ...filter,
+            modifiedAfter: toEpochMillis(filter.modifiedAfter),
+            modifiedBefore: toEpochMillis(filter.modifiedBefore),
+        },
+        { offset, maxResults },
+    ];
+}
+
+function searchFile(root_path, query, options) {
+    return Deno.core.ops.op2_search_file(root_path, query, ...toSearchArgs(options));
+}
+
+function searchStart(root_path, query, options) {
+    return Deno.core.ops.op2_search_start(root_path, query, ...toSearchArgs(options));
+}
+
+function searchNext(search, timeoutMs) {
+    return Deno.core.ops.op2_search_next(search, timeoutMs === undefined ? null : timeoutMs);
+}
+
+function searchCancel(search) {
+    return Deno.core.ops.op2_search_cancel(search);
+}
+
+function searchApps(query) {
+    return Deno.core.ops.op2_search_apps(query === undefined || query === null ? "" : query);
 }
 
 globalThis.app = globalThis.app || {};
@@ -8,3 +54,7 @@ globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
 globalThis.app.sapphillon.core.search = globalThis.app.sapphillon.core.search || {};
 
 globalThis.app.sapphillon.core.search.file = searchFile;
+globalThis.app.sapphillon.core.search.start = searchStart;
+globalThis.app.sapphillon.core.search.next = searchNext;
+globalThis.app.sapphillon.core.search.cancel = searchCancel;
+globalThis.app.sapphillon.core.search.apps = searchApps;
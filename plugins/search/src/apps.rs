@@ -0,0 +1,362 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Installed application discovery and launching.
+//!
+//! Applications are found where the desktop lists them:
+//! - **Windows**: Start Menu shortcuts
+//! - **macOS**: `.app` bundles in the Applications folders
+//! - **Linux**: `.desktop` files in the XDG data directories
+
+use deno_error::JsErrorBox;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+/// An installed application a workflow can launch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppEntry {
+    /// Stable ID passed to `apps.launch`: the desktop file ID on Linux, the bundle or
+    /// shortcut path elsewhere.
+    pub id: String,
+    pub name: String,
+    /// Program and arguments that start the application.
+    pub command: Vec<String>,
+    /// File the application was found in.
+    pub path: String,
+}
+
+/// Returns the installed applications whose name or ID contains `query`, ignoring
+/// case, sorted by name. An empty query returns every application.
+pub fn find_apps(query: &str) -> Vec<AppEntry> {
+    let query = query.trim().to_lowercase();
+    let mut apps: Vec<AppEntry> = installed_apps()
+        .into_iter()
+        .filter(|app| {
+            query.is_empty()
+                || app.name.to_lowercase().contains(&query)
+                || app.id.to_lowercase().contains(&query)
+        })
+        .collect();
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    apps
+}
+
+/// Starts the installed application with the given ID without waiting for it.
+///
+/// Only IDs returned by [`find_apps`] are accepted, so a workflow cannot pass an
+/// arbitrary command through this function.
+pub fn launch_app(id: &str) -> Result<AppEntry, JsErrorBox> {
+    let app = installed_apps()
+        .into_iter()
+        .find(|app| app.id == id)
+        .ok_or_else(|| JsErrorBox::new("Error", format!("unknown application {id}")))?;
+    let (program, args) = app
+        .command
+        .split_first()
+        .ok_or_else(|| JsErrorBox::generic(format!("{id} has no launch command")))?;
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| JsErrorBox::generic(format!("Failed to launch {id}: {e}")))?;
+    Ok(app)
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+#[cfg(target_os = "windows")]
+fn installed_apps() -> Vec<AppEntry> {
+    let dirs: Vec<PathBuf> = ["APPDATA", "ProgramData"]
+        .iter()
+        .filter_map(|var| std::env::var_os(var))
+        .map(|base| PathBuf::from(base).join(r"Microsoft\Windows\Start Menu\Programs"))
+        .collect();
+    files_with_extension(&dirs, "lnk", usize::MAX)
+        .into_iter()
+        .map(|path| {
+            let path_str = path.to_string_lossy().into_owned();
+            AppEntry {
+                id: path_str.clone(),
+                name: file_stem(&path),
+                command: vec![
+                    "cmd".to_string(),
+                    "/C".to_string(),
+                    "start".to_string(),
+                    String::new(),
+                    path_str.clone(),
+                ],
+                path: path_str,
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn installed_apps() -> Vec<AppEntry> {
+    let mut dirs = vec![
+        PathBuf::from("/Applications"),
+        PathBuf::from("/System/Applications"),
+    ];
+    if let Some(home) = home_dir() {
+        dirs.push(home.join("Applications"));
+    }
+    // Depth 2 covers folders such as Utilities without walking into the bundles.
+    files_with_extension(&dirs, "app", 2)
+        .into_iter()
+        .map(|path| {
+            let path_str = path.to_string_lossy().into_owned();
+            AppEntry {
+                id: path_str.clone(),
+                name: file_stem(&path),
+                command: vec!["open".to_string(), path_str.clone()],
+                path: path_str,
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn installed_apps() -> Vec<AppEntry> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".local/share")));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let dirs: Vec<PathBuf> = data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .map(|dir| dir.join("applications"))
+        .collect();
+    desktop_apps(&dirs)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn installed_apps() -> Vec<AppEntry> {
+    Vec::new()
+}
+
+/// Paths with the given extension below `dirs`, in directory order.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn files_with_extension(dirs: &[PathBuf], extension: &str, max_depth: usize) -> Vec<PathBuf> {
+    dirs.iter()
+        .flat_map(|dir| {
+            WalkDir::new(dir)
+                .max_depth(max_depth)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+        })
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(extension))
+        .collect()
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Applications described by the `.desktop` files in `dirs`. A desktop file ID found
+/// in an earlier directory hides the same ID in later ones, as the XDG spec asks.
+#[cfg(target_os = "linux")]
+fn desktop_apps(dirs: &[PathBuf]) -> Vec<AppEntry> {
+    let mut seen = std::collections::HashSet::new();
+    let mut apps = Vec::new();
+    for dir in dirs {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+                continue;
+            }
+            let Some(id) = desktop_file_id(dir, path) else {
+                continue;
+            };
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            if let Some((name, command)) = parse_desktop_entry(&content) {
+                apps.push(AppEntry {
+                    id,
+                    name,
+                    command,
+                    path: path.to_string_lossy().into_owned(),
+                });
+            }
+        }
+    }
+    apps
+}
+
+/// The desktop file ID: the path below the applications directory with `/` replaced
+/// by `-`, e.g. `kde4/konsole.desktop` is `kde4-konsole.desktop`.
+#[cfg(target_os = "linux")]
+fn desktop_file_id(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    Some(relative.to_str()?.replace('/', "-"))
+}
+
+/// Reads the name and launch command of a visible application entry.
+#[cfg(target_os = "linux")]
+fn parse_desktop_entry(content: &str) -> Option<(String, Vec<String>)> {
+    let mut in_entry = false;
+    let (mut name, mut exec, mut is_app, mut hidden) = (None, None, false, false);
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match (key.trim(), value.trim()) {
+            ("Name", value) => name = Some(value.to_string()),
+            ("Exec", value) => exec = Some(value.to_string()),
+            ("Type", value) => is_app = value == "Application",
+            ("NoDisplay" | "Hidden", "true") => hidden = true,
+            _ => {}
+        }
+    }
+    if !is_app || hidden {
+        return None;
+    }
+    let command = exec_arguments(&exec?);
+    (!command.is_empty()).then(|| (name.unwrap_or_default(), command))
+}
+
+/// Splits an `Exec` value into arguments and drops the field codes (`%f`, `%U`, ...)
+/// that stand for files, URLs or icons a launcher would pass.
+#[cfg(target_os = "linux")]
+fn exec_arguments(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut in_arg) = (false, false);
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => current.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args.into_iter()
+        .filter_map(|arg| {
+            let mut expanded = String::new();
+            let mut had_field_code = false;
+            let mut chars = arg.chars();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    expanded.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('%') => expanded.push('%'),
+                    _ => had_field_code = true,
+                }
+            }
+            (!had_field_code || !expanded.is_empty()).then_some(expanded)
+        })
+        .collect()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_exec_arguments_drop_field_codes() {
+        assert_eq!(
+            exec_arguments(r#"env "MY VAR=a \"b\"" /opt/app --open %U --progress=100%%"#),
+            vec![
+                "env",
+                r#"MY VAR=a "b""#,
+                "/opt/app",
+                "--open",
+                "--progress=100%"
+            ]
+        );
+        assert_eq!(exec_arguments("gimp-2.10 %f"), vec!["gimp-2.10"]);
+    }
+
+    #[test]
+    fn test_desktop_apps_skip_hidden_entries_and_shadowed_ids() {
+        let user = tempdir().unwrap();
+        let system = tempdir().unwrap();
+        fs::create_dir(system.path().join("kde4")).unwrap();
+        fs::write(
+            user.path().join("editor.desktop"),
+            "[Desktop Entry]\nType=Application\nName=My Editor\nExec=myeditor %F\n",
+        )
+        .unwrap();
+        fs::write(
+            system.path().join("editor.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor\n",
+        )
+        .unwrap();
+        fs::write(
+            system.path().join("kde4/konsole.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Konsole\nName[de]=Terminal\nExec=konsole\n\n[Desktop Action new]\nName=New Window\nExec=konsole --new\n",
+        )
+        .unwrap();
+        fs::write(
+            system.path().join("helper.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Helper\nExec=helper\nNoDisplay=true\n",
+        )
+        .unwrap();
+
+        let mut apps = desktop_apps(&[user.path().to_path_buf(), system.path().to_path_buf()]);
+        apps.sort_by(|a, b| a.id.cmp(&b.id));
+        let summary: Vec<(&str, &str, Vec<String>)> = apps
+            .iter()
+            .map(|app| (app.id.as_str(), app.name.as_str(), app.command.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("editor.desktop", "My Editor", vec!["myeditor".to_string()]),
+                (
+                    "kde4-konsole.desktop",
+                    "Konsole",
+                    vec!["konsole".to_string()]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,363 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! File name index used by the walkdir searcher.
+//!
+//! Without a native search service every query walks the filesystem. When roots
+//! are configured, a background thread records the entries below them in a
+//! sidecar SQLite file and keeps it current with filesystem change events, so
+//! searches below those roots are answered from the index instead.
+
+use crate::searcher::SearchFilter;
+use deno_error::JsErrorBox;
+use notify::{RecursiveMode, Watcher};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Entries written per transaction, so searches are not blocked by a long scan.
+const BATCH_SIZE: usize = 5000;
+/// Time change events are collected for before they are applied together.
+const EVENT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+static FILE_INDEX: OnceLock<Arc<FileIndex>> = OnceLock::new();
+
+/// Starts indexing `roots` into the SQLite file at `db_path` and lets the walkdir
+/// searcher use the index for searches below them.
+///
+/// Roots scanned by an earlier run are used right away while they are scanned
+/// again in the background; new roots are used once their first scan finishes.
+///
+/// # Returns
+///
+/// Returns an error when the index file cannot be opened, a root does not exist,
+/// or an index was already started.
+pub fn start_file_index(db_path: &Path, roots: &[PathBuf]) -> anyhow::Result<()> {
+    let index = FileIndex::open(db_path, roots)?;
+    let index = Arc::new(index);
+    if FILE_INDEX.set(index.clone()).is_err() {
+        anyhow::bail!("the file index was already started");
+    }
+    index.spawn_indexer()?;
+    Ok(())
+}
+
+/// The started index, when it can answer searches below `root`.
+pub(crate) fn index_covering(root: &Path) -> Option<&'static FileIndex> {
+    FILE_INDEX
+        .get()
+        .map(|index| index.as_ref())
+        .filter(|index| index.covers(root))
+}
+
+/// Entries below the configured roots, stored in a SQLite file.
+pub(crate) struct FileIndex {
+    conn: Mutex<Connection>,
+    roots: Vec<PathBuf>,
+    /// Roots whose entries have been scanned at least once.
+    scanned: Mutex<HashSet<PathBuf>>,
+}
+
+impl FileIndex {
+    pub(crate) fn open(db_path: &Path, roots: &[PathBuf]) -> anyhow::Result<Self> {
+        let roots = roots
+            .iter()
+            .map(|root| {
+                root.canonicalize()
+                    .map_err(|e| anyhow::anyhow!("cannot index {}: {e}", root.display()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS files (
+                 path TEXT PRIMARY KEY NOT NULL,
+                 name_lower TEXT NOT NULL,
+                 scan INTEGER NOT NULL
+             ) WITHOUT ROWID;
+             CREATE TABLE IF NOT EXISTS roots (
+                 path TEXT PRIMARY KEY NOT NULL,
+                 scanned_at INTEGER NOT NULL
+             );",
+        )?;
+        let scanned = {
+            let mut stmt = conn.prepare("SELECT path FROM roots")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            rows.filter_map(Result::ok)
+                .map(PathBuf::from)
+                .filter(|path| roots.contains(path))
+                .collect()
+        };
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            roots,
+            scanned: Mutex::new(scanned),
+        })
+    }
+
+    /// Watches the roots, then scans them and applies changes on a background thread.
+    fn spawn_indexer(self: &Arc<Self>) -> anyhow::Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for root in &self.roots {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+        }
+
+        let index = self.clone();
+        thread::spawn(move || {
+            // Owned by the thread so events keep arriving as long as it runs.
+            let _watcher = watcher;
+            for root in &index.roots {
+                match index.scan(root) {
+                    Ok(count) => info!("Indexed {count} entries below {}", root.display()),
+                    Err(e) => error!("Cannot index {}: {e}", root.display()),
+                }
+            }
+            while let Ok(first) = rx.recv() {
+                thread::sleep(EVENT_DEBOUNCE);
+                let paths: BTreeSet<PathBuf> = std::iter::once(first)
+                    .chain(rx.try_iter())
+                    .filter_map(|event: notify::Result<notify::Event>| event.ok())
+                    .flat_map(|event| event.paths)
+                    .collect();
+                if let Err(e) = index.apply_changes(&paths) {
+                    warn!("Cannot update the file index: {e}");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Records every entry below `root` and forgets entries that are gone.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of entries found.
+    pub(crate) fn scan(&self, root: &Path) -> rusqlite::Result<usize> {
+        let scan = now_millis();
+        let count = self.insert_entries(WalkDir::new(root), scan)?;
+        {
+            let conn = self.conn.lock().unwrap();
+            let (root_str, lower, upper) = subtree_bounds(root);
+            conn.execute(
+                "DELETE FROM files WHERE (path = ?1 OR (path > ?2 AND path < ?3)) AND scan <> ?4",
+                params![root_str, lower, upper, scan],
+            )?;
+            conn.execute(
+                "INSERT INTO roots (path, scanned_at) VALUES (?1, ?2)
+                 ON CONFLICT(path) DO UPDATE SET scanned_at = excluded.scanned_at",
+                params![root_str, scan],
+            )?;
+        }
+        self.scanned.lock().unwrap().insert(root.to_path_buf());
+        Ok(count)
+    }
+
+    /// Brings the entries at and below `paths` in line with the filesystem.
+    pub(crate) fn apply_changes(&self, paths: &BTreeSet<PathBuf>) -> rusqlite::Result<()> {
+        let scan = now_millis();
+        for path in paths {
+            if path.symlink_metadata().is_ok() {
+                self.insert_entries(WalkDir::new(path), scan)?;
+            } else {
+                let (path_str, lower, upper) = subtree_bounds(path);
+                self.conn.lock().unwrap().execute(
+                    "DELETE FROM files WHERE path = ?1 OR (path > ?2 AND path < ?3)",
+                    params![path_str, lower, upper],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_entries(&self, walk: WalkDir, scan: i64) -> rusqlite::Result<usize> {
+        let mut count = 0;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        for entry in walk.into_iter().filter_map(Result::ok) {
+            let Some(path) = entry.path().to_str() else {
+                continue;
+            };
+            batch.push((
+                path.to_string(),
+                entry.file_name().to_string_lossy().to_lowercase(),
+            ));
+            if batch.len() == BATCH_SIZE {
+                count += self.write_batch(&batch, scan)?;
+                batch.clear();
+            }
+        }
+        count += self.write_batch(&batch, scan)?;
+        Ok(count)
+    }
+
+    fn write_batch(&self, batch: &[(String, String)], scan: i64) -> rusqlite::Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO files (path, name_lower, scan) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(path) DO UPDATE SET scan = excluded.scan",
+            )?;
+            for (path, name_lower) in batch {
+                stmt.execute(params![path, name_lower, scan])?;
+            }
+        }
+        tx.commit()?;
+        Ok(batch.len())
+    }
+
+    /// Returns whether `root` is below a root that has been scanned.
+    pub(crate) fn covers(&self, root: &Path) -> bool {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let scanned = self.scanned.lock().unwrap();
+        self.roots
+            .iter()
+            .any(|indexed| root.starts_with(indexed) && scanned.contains(indexed))
+    }
+
+    /// Searches the index like the walkdir searcher searches the filesystem. Entries
+    /// removed since the last change event are skipped.
+    pub(crate) fn search_each(
+        &self,
+        root: &Path,
+        query: &str,
+        filter: &SearchFilter,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), JsErrorBox> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let (root_str, lower, upper) = subtree_bounds(&root);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT path FROM files
+                 WHERE (path = ?1 OR (path > ?2 AND path < ?3)) AND instr(name_lower, ?4) > 0
+                 ORDER BY path",
+            )
+            .map_err(index_error)?;
+        let mut rows = stmt
+            .query(params![root_str, lower, upper, query.to_lowercase()])
+            .map_err(index_error)?;
+        while let Some(row) = rows.next().map_err(index_error)? {
+            let path: String = row.get(0).map_err(index_error)?;
+            let entry = Path::new(&path);
+            let name = entry
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            if !filter.name_matches(&name, query)
+                || entry.symlink_metadata().is_err()
+                || !filter.path_matches(entry)
+            {
+                continue;
+            }
+            if !emit(path) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The path and the exclusive bounds of the paths below it, for a range query on
+/// the primary key.
+fn subtree_bounds(path: &Path) -> (String, String, String) {
+    let path = path.to_string_lossy().into_owned();
+    let mut lower = path.clone();
+    if !lower.ends_with(MAIN_SEPARATOR) {
+        lower.push(MAIN_SEPARATOR);
+    }
+    // The separator is ASCII, so the next character sorts right after every child.
+    let mut upper = lower[..lower.len() - 1].to_string();
+    upper.push((MAIN_SEPARATOR as u8 + 1) as char);
+    (path, lower, upper)
+}
+
+fn index_error(e: rusqlite::Error) -> JsErrorBox {
+    JsErrorBox::generic(format!("File index error: {e}"))
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn search(index: &FileIndex, root: &Path, query: &str) -> Vec<String> {
+        let mut paths = Vec::new();
+        index
+            .search_each(root, query, &SearchFilter::default(), &mut |path| {
+                paths.push(path);
+                true
+            })
+            .unwrap();
+        paths
+    }
+
+    #[test]
+    fn test_index_answers_searches_after_scan_and_changes() {
+        let db = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let root = files.path().canonicalize().unwrap();
+        fs::create_dir(root.join("Reports")).unwrap();
+        fs::write(root.join("Reports/Q1-report.pdf"), "x").unwrap();
+        fs::write(root.join("notes.txt"), "x").unwrap();
+
+        let index = FileIndex::open(&db.path().join("index.sqlite"), &[root.clone()]).unwrap();
+        assert!(!index.covers(&root));
+        assert_eq!(index.scan(&root).unwrap(), 4);
+        assert!(index.covers(&root.join("Reports")));
+
+        assert_eq!(search(&index, &root, "REPORT").len(), 2);
+        assert_eq!(
+            search(&index, &root.join("Reports"), "notes"),
+            Vec::<String>::new()
+        );
+
+        fs::remove_dir_all(root.join("Reports")).unwrap();
+        fs::write(root.join("report-2.txt"), "x").unwrap();
+        index
+            .apply_changes(&BTreeSet::from([
+                root.join("Reports"),
+                root.join("report-2.txt"),
+            ]))
+            .unwrap();
+        assert_eq!(
+            search(&index, &root, "report"),
+            vec![root.join("report-2.txt").to_string_lossy().into_owned()]
+        );
+    }
+
+    #[test]
+    fn test_scanned_roots_are_remembered() {
+        let db = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let root = files.path().canonicalize().unwrap();
+        let db_path = db.path().join("index.sqlite");
+
+        FileIndex::open(&db_path, &[root.clone()])
+            .unwrap()
+            .scan(&root)
+            .unwrap();
+        assert!(FileIndex::open(&db_path, &[root.clone()])
+            .unwrap()
+            .covers(&root));
+    }
+}
@@ -8,19 +8,22 @@
 //! - **Windows**: Windows Search API (Windows Index Search)
 //! - **macOS**: Spotlight (MDQuery)
 //! - **Linux**: GNOME Tracker, KDE Baloo, or locate
-//! - **Fallback**: walkdir-based filesystem traversal
+//! - **Fallback**: walkdir-based filesystem traversal, answered from a file index
+//!   below the roots passed to [`start_file_index`]
+//!
+//! It also finds installed applications (`search.apps`) and launches them
+//! (`apps.launch`, in the separate `app.sapphillon.core.apps` package).
 
 use deno_core::{op2, OpState};
 use deno_error::JsErrorBox;
-use sapphillon_core::permission::{check_permission, CheckPermissionResult, Permissions};
+use plugin_permission::ensure_permission;
 use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
 use sapphillon_core::proto::sapphillon::v1::{
     FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
     PluginPackage,
 };
-use sapphillon_core::runtime::OpStateWorkflowData;
 use std::sync::OnceLock;
-use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 // Platform-specific modules
 #[cfg(target_os = "windows")]
@@ -32,10 +35,16 @@ mod macos_search;
 #[cfg(target_os = "linux")]
 mod linux_search;
 
+mod apps;
+mod file_index;
 mod searcher;
+mod session;
 mod walkdir_search;
 
-use searcher::FileSearcher;
+use apps::AppEntry;
+pub use file_index::start_file_index;
+use searcher::{FileSearcher, SearchFilter};
+use session::{SearchChunk, SearchPage, SearchSession, SearchSessions};
 use walkdir_search::WalkdirSearcher;
 
 /// Get the best available file searcher for the current platform.
@@ -74,6 +83,15 @@ pub fn get_active_searcher_name() -> &'static str {
     get_searcher().name()
 }
 
+/// Resource of the permission to list installed applications.
+const INSTALLED_APPS_RESOURCE: &str = "apps://installed";
+/// Type of an application returned by `search.apps` and `apps.launch`.
+const APP_ENTRY_TYPE: &str = "{ id: string, name: string, command: string[], path: string }";
+
+/// Type of the options accepted by `search.file` and `search.start`.
+const SEARCH_OPTIONS_TYPE: &str = "{ extensions?: string[], minSize?: number, maxSize?: number, modifiedAfter?: Date | number | string, modifiedBefore?: Date | number | string, directoriesOnly?: boolean, caseSensitive?: boolean, offset?: number, maxResults?: number } | undefined";
+const SEARCH_OPTIONS_DESCRIPTION: &str = "Extra conditions on the results; sizes in bytes, file names match case-insensitively unless caseSensitive is set. offset skips matches and maxResults (default 1000) limits them";
+
 pub fn search_plugin_function() -> PluginFunction {
     PluginFunction {
         function_id: "app.sapphillon.core.search.file".to_string(),
@@ -94,6 +112,11 @@ pub fn search_plugin_function() -> PluginFunction {
                     r#type: "string".to_string(),
                     description: "Search query".to_string(),
                 },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: SEARCH_OPTIONS_TYPE.to_string(),
+                    description: SEARCH_OPTIONS_DESCRIPTION.to_string(),
+                },
             ],
             returns: vec![FunctionParameter {
                 name: "results".to_string(),
@@ -104,13 +127,167 @@ pub fn search_plugin_function() -> PluginFunction {
     }
 }
 
+pub fn search_start_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.search.start".to_string(),
+        function_name: "search.start".to_string(),
+        version: "".to_string(),
+        description: "Starts a file search in the background and returns a handle to read its results in chunks with search.next or stop it with search.cancel."
+            .to_string(),
+        permissions: search_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "root_path".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Root directory to search".to_string(),
+                },
+                FunctionParameter {
+                    name: "query".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Search query".to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: SEARCH_OPTIONS_TYPE.to_string(),
+                    description: SEARCH_OPTIONS_DESCRIPTION.to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "search".to_string(),
+                r#type: "number".to_string(),
+                description: "Search handle".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn search_next_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.search.next".to_string(),
+        function_name: "search.next".to_string(),
+        version: "".to_string(),
+        description: "Returns the paths a background search found since the last call, waiting up to timeoutMs for one while it runs."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "search".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Handle returned by search.start".to_string(),
+                },
+                FunctionParameter {
+                    name: "timeoutMs".to_string(),
+                    r#type: "number | undefined".to_string(),
+                    description: "Longest time to wait for results; defaults to 1000".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "chunk".to_string(),
+                r#type: "{ paths: string[], done: boolean, error: string | null }".to_string(),
+                description: "New paths; done is set once every path has been returned".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn search_cancel_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.search.cancel".to_string(),
+        function_name: "search.cancel".to_string(),
+        version: "".to_string(),
+        description: "Stops a background search and discards its remaining results.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "search".to_string(),
+                r#type: "number".to_string(),
+                description: "Handle returned by search.start".to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn search_apps_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.search.apps".to_string(),
+        function_name: "search.apps".to_string(),
+        version: "".to_string(),
+        description: "Finds installed applications (Start Menu, /Applications or .desktop files) whose name or ID contains the query; pass an ID to apps.launch to start one."
+            .to_string(),
+        permissions: search_apps_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "query".to_string(),
+                r#type: "string".to_string(),
+                description: "Part of the application name, ignoring case; empty lists every application".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "apps".to_string(),
+                r#type: format!("{APP_ENTRY_TYPE}[]"),
+                description: "Matching applications sorted by name".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn apps_launch_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.apps.launch".to_string(),
+        function_name: "apps.launch".to_string(),
+        version: "".to_string(),
+        description:
+            "Starts an installed application found by search.apps without waiting for it to exit."
+                .to_string(),
+        permissions: apps_launch_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "id".to_string(),
+                r#type: "string".to_string(),
+                description: "ID of an application returned by search.apps".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "app".to_string(),
+                r#type: APP_ENTRY_TYPE.to_string(),
+                description: "The launched application".to_string(),
+            }],
+        }),
+    }
+}
+
 pub fn search_plugin_package() -> PluginPackage {
     PluginPackage {
         package_id: "app.sapphillon.core.search".to_string(),
         package_name: "Search".to_string(),
         provider_id: "".to_string(),
         description: "A plugin to search for files on the local filesystem using native OS search APIs (Windows Search/Everything, macOS Spotlight, Linux Tracker/Baloo).".to_string(),
-        functions: vec![search_plugin_function()],
+        functions: vec![
+            search_plugin_function(),
+            search_start_plugin_function(),
+            search_next_plugin_function(),
+            search_cancel_plugin_function(),
+            search_apps_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn apps_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.apps".to_string(),
+        package_name: "Apps".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to launch the installed applications found by search.apps."
+            .to_string(),
+        functions: vec![apps_launch_plugin_function()],
         package_version: env!("CARGO_PKG_VERSION").to_string(),
         deprecated: None,
         plugin_store_url: "BUILTIN".to_string(),
@@ -131,11 +308,75 @@ pub fn core_search_plugin() -> CorePluginFunction {
     )
 }
 
+pub fn core_search_start_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        search_start_plugin_function().function_id,
+        "SearchStart".to_string(),
+        search_start_plugin_function().description,
+        op2_search_start(),
+        Some(include_str!("00_search.js").to_string()),
+    )
+}
+
+pub fn core_search_next_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        search_next_plugin_function().function_id,
+        "SearchNext".to_string(),
+        search_next_plugin_function().description,
+        op2_search_next(),
+        Some(include_str!("00_search.js").to_string()),
+    )
+}
+
+pub fn core_search_cancel_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        search_cancel_plugin_function().function_id,
+        "SearchCancel".to_string(),
+        search_cancel_plugin_function().description,
+        op2_search_cancel(),
+        Some(include_str!("00_search.js").to_string()),
+    )
+}
+
+pub fn core_search_apps_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        search_apps_plugin_function().function_id,
+        "SearchApps".to_string(),
+        search_apps_plugin_function().description,
+        op2_search_apps(),
+        Some(include_str!("00_search.js").to_string()),
+    )
+}
+
+pub fn core_apps_launch_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        apps_launch_plugin_function().function_id,
+        "AppsLaunch".to_string(),
+        apps_launch_plugin_function().description,
+        op2_apps_launch(),
+        Some(include_str!("00_apps.js").to_string()),
+    )
+}
+
 pub fn core_search_plugin_package() -> CorePluginPackage {
     CorePluginPackage::new(
         search_plugin_package().package_id,
         "Search".to_string(),
-        vec![core_search_plugin()],
+        vec![
+            core_search_plugin(),
+            core_search_start_plugin(),
+            core_search_next_plugin(),
+            core_search_cancel_plugin(),
+            core_search_apps_plugin(),
+        ],
+    )
+}
+
+pub fn core_apps_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        apps_plugin_package().package_id,
+        "Apps".to_string(),
+        vec![core_apps_launch_plugin()],
     )
 }
 
@@ -144,15 +385,44 @@ fn search_plugin_permissions() -> Vec<Permission> {
         display_name: "Execute".to_string(),
         description: "Allows the plugin to execute commands.".to_string(),
         permission_type: PermissionType::Execute as i32,
+        permission_level: PermissionLevel::Medium as i32,
+        resource: vec![],
+    }]
+}
+
+fn search_apps_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Installed Applications".to_string(),
+        description: "Allows the plugin to list the installed applications.".to_string(),
+        permission_type: PermissionType::Execute as i32,
         permission_level: PermissionLevel::Unspecified as i32,
+        resource: vec![INSTALLED_APPS_RESOURCE.to_string()],
+    }]
+}
+
+fn apps_launch_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Launch Applications".to_string(),
+        description: "Allows the plugin to start installed applications.".to_string(),
+        permission_type: PermissionType::Execute as i32,
+        permission_level: PermissionLevel::High as i32,
         resource: vec![],
     }]
 }
 
 /// Core search logic using the best available searcher.
-fn search_file_logic(root_path: String, query: String) -> Result<String, JsErrorBox> {
+fn search_file_logic(
+    root_path: String,
+    query: String,
+    filter: SearchFilter,
+    page: SearchPage,
+) -> Result<String, JsErrorBox> {
     let searcher = get_searcher();
-    let results = searcher.search(&root_path, &query)?;
+    let mut results = Vec::new();
+    page.run(searcher, &root_path, &query, &filter, &mut |path| {
+        results.push(path);
+        true
+    })?;
     Ok(serde_json::to_string(&results).unwrap())
 }
 
@@ -162,6 +432,8 @@ fn op2_search_file(
     state: &mut OpState,
     #[string] root_path: String,
     #[string] query: String,
+    #[serde] filter: Option<SearchFilter>,
+    #[serde] page: Option<SearchPage>,
 ) -> std::result::Result<String, JsErrorBox> {
     ensure_permission(
         state,
@@ -169,48 +441,102 @@ fn op2_search_file(
         search_plugin_permissions(),
         &root_path,
     )?;
-    search_file_logic(root_path, query)
+    search_file_logic(
+        root_path,
+        query,
+        filter.unwrap_or_default(),
+        page.unwrap_or_default(),
+    )
 }
 
-fn ensure_permission(
+#[op2]
+#[smi]
+fn op2_search_start(
     state: &mut OpState,
-    plugin_function_id: &str,
-    required_permissions: Vec<Permission>,
-    resource: &str,
-) -> Result<(), JsErrorBox> {
-    let data = state
-        .borrow::<Arc<Mutex<OpStateWorkflowData>>>()
-        .lock()
-        .unwrap();
-    let allowed = data.get_allowed_permissions().clone().unwrap_or_default();
-
-    let required_permissions = Permissions::new(
-        required_permissions
-            .into_iter()
-            .map(|mut p| {
-                if !resource.is_empty() && p.resource.is_empty() {
-                    p.resource = vec![resource.to_string()];
-                }
-                p
-            })
-            .collect(),
+    #[string] root_path: String,
+    #[string] query: String,
+    #[serde] filter: Option<SearchFilter>,
+    #[serde] page: Option<SearchPage>,
+) -> std::result::Result<u32, JsErrorBox> {
+    ensure_permission(
+        state,
+        &search_start_plugin_function().function_id,
+        search_plugin_permissions(),
+        &root_path,
+    )?;
+    let session = SearchSession::start(
+        get_searcher(),
+        root_path,
+        query,
+        filter.unwrap_or_default(),
+        page.unwrap_or_default(),
     );
 
-    let allowed_permissions = allowed
-        .into_iter()
-        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
-        .map(|p| p.permissions)
-        .unwrap_or_else(|| Permissions::new(vec![]));
-
-    match check_permission(&allowed_permissions, &required_permissions) {
-        CheckPermissionResult::Ok => Ok(()),
-        CheckPermissionResult::MissingPermission(perm) => Err(JsErrorBox::new(
-            "PermissionDenied. Missing Permissions:",
-            perm.to_string(),
-        )),
+    if !state.has::<SearchSessions>() {
+        state.put(SearchSessions::default());
+    }
+    Ok(state.borrow_mut::<SearchSessions>().insert(session))
+}
+
+#[op2]
+#[serde]
+fn op2_search_next(
+    state: &mut OpState,
+    #[smi] handle: u32,
+    #[serde] timeout_ms: Option<u64>,
+) -> std::result::Result<SearchChunk, JsErrorBox> {
+    let session = state
+        .try_borrow::<SearchSessions>()
+        .and_then(|sessions| sessions.sessions.get(&handle))
+        .ok_or_else(|| unknown_search_error(handle))?;
+    Ok(session.next_chunk(Duration::from_millis(timeout_ms.unwrap_or(1000))))
+}
+
+#[op2]
+fn op2_search_cancel(state: &mut OpState, #[smi] handle: u32) -> Result<(), JsErrorBox> {
+    let removed = state
+        .try_borrow_mut::<SearchSessions>()
+        .and_then(|sessions| sessions.sessions.remove(&handle));
+    match removed {
+        Some(_) => Ok(()),
+        None => Err(unknown_search_error(handle)),
     }
 }
 
+#[op2]
+#[serde]
+fn op2_search_apps(
+    state: &mut OpState,
+    #[string] query: String,
+) -> std::result::Result<Vec<AppEntry>, JsErrorBox> {
+    ensure_permission(
+        state,
+        &search_apps_plugin_function().function_id,
+        search_apps_plugin_permissions(),
+        "",
+    )?;
+    Ok(apps::find_apps(&query))
+}
+
+#[op2]
+#[serde]
+fn op2_apps_launch(
+    state: &mut OpState,
+    #[string] id: String,
+) -> std::result::Result<AppEntry, JsErrorBox> {
+    ensure_permission(
+        state,
+        &apps_launch_plugin_function().function_id,
+        apps_launch_plugin_permissions(),
+        &id,
+    )?;
+    apps::launch_app(&id)
+}
+
+fn unknown_search_error(handle: u32) -> JsErrorBox {
+    JsErrorBox::new("Error", format!("unknown search {handle}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,12 +561,16 @@ mod tests {
         let searcher = WalkdirSearcher::new();
 
         // Search for a file that exists.
-        let results = searcher.search(&dir_path, "file1").unwrap();
+        let results = searcher
+            .search(&dir_path, "file1", &SearchFilter::default())
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert!(results[0].contains("file1.txt"));
 
         // Search for a file that doesn't exist.
-        let results = searcher.search(&dir_path, "nonexistent").unwrap();
+        let results = searcher
+            .search(&dir_path, "nonexistent", &SearchFilter::default())
+            .unwrap();
         assert_eq!(results.len(), 0);
     }
 
@@ -9,7 +9,7 @@
 //! 2. `BalooSearcher` - KDE Baloo (KDE Plasma environments)
 //! 3. `LocateSearcher` - mlocate/plocate (command-line, available everywhere)
 
-use crate::searcher::FileSearcher;
+use crate::searcher::{FileSearcher, SearchFilter};
 use deno_error::JsErrorBox;
 use std::sync::OnceLock;
 
@@ -49,7 +49,12 @@ impl Default for TrackerSearcher {
 }
 
 impl FileSearcher for TrackerSearcher {
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox> {
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox> {
         use zbus::blocking::Connection;
 
         let conn = Connection::session()
@@ -87,8 +92,11 @@ impl FileSearcher for TrackerSearcher {
             )
         };
 
-        // Try Tracker 3.x first, then fall back to Tracker 2.x
-        Self::query_tracker3(&conn, &sparql).or_else(|_| Self::query_tracker2(&conn, &sparql))
+        // Try Tracker 3.x first, then fall back to Tracker 2.x. The SPARQL query matches
+        // names case-insensitively; the remaining conditions are checked on the results.
+        let results = Self::query_tracker3(&conn, &sparql)
+            .or_else(|_| Self::query_tracker2(&conn, &sparql))?;
+        Ok(filter.retain(results, query))
     }
 
     fn is_available(&self) -> bool {
@@ -204,7 +212,12 @@ impl Default for BalooSearcher {
 }
 
 impl FileSearcher for BalooSearcher {
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox> {
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox> {
         // Baloo uses the baloosearch command or baloo6/baloo5 D-Bus interface
         // The D-Bus interface varies between KDE versions, so we'll use the CLI tool
         // which provides a stable interface
@@ -246,7 +259,7 @@ impl FileSearcher for BalooSearcher {
             .map(|s| s.to_string())
             .collect();
 
-        Ok(results)
+        Ok(filter.retain(results, query))
     }
 
     fn is_available(&self) -> bool {
@@ -297,12 +310,19 @@ impl Default for LocateSearcher {
 }
 
 impl FileSearcher for LocateSearcher {
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox> {
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox> {
         let locate_cmd = Self::find_locate_command()
             .ok_or_else(|| JsErrorBox::new("SearchError", "No locate command found"))?;
 
         let mut cmd = std::process::Command::new(locate_cmd);
-        cmd.arg("-i"); // Case insensitive
+        if !filter.case_sensitive {
+            cmd.arg("-i");
+        }
         cmd.arg("-l").arg("1000"); // Limit results
         cmd.arg(query);
 
@@ -325,7 +345,7 @@ impl FileSearcher for LocateSearcher {
             .map(|s| s.to_string())
             .collect();
 
-        Ok(results)
+        Ok(filter.retain(results, query))
     }
 
     fn is_available(&self) -> bool {
@@ -4,7 +4,7 @@
 
 //! macOS native file search implementation using Spotlight (MDQuery).
 
-use crate::searcher::FileSearcher;
+use crate::searcher::{FileSearcher, SearchFilter};
 use crate::walkdir_search::WalkdirSearcher;
 use deno_error::JsErrorBox;
 use std::sync::OnceLock;
@@ -111,19 +111,25 @@ impl Default for SpotlightSearcher {
 }
 
 impl FileSearcher for SpotlightSearcher {
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox> {
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox> {
         // If the path is not indexed by Spotlight, use walkdir fallback directly
         if !root_path.is_empty() && root_path != "/" && !Self::is_path_indexed(root_path) {
-            return self.walkdir_fallback.search(root_path, query);
+            return self.walkdir_fallback.search(root_path, query, filter);
         }
 
-        // Try Spotlight search first
-        let spotlight_results = self.spotlight_search(root_path, query)?;
+        // Try Spotlight search first; its name matching is case-insensitive and
+        // cannot express the other conditions, so they are checked afterwards.
+        let spotlight_results = filter.retain(self.spotlight_search(root_path, query)?, query);
 
         // If Spotlight returns no results and we have a specific path,
         // fallback to walkdir (the path might not be indexed)
         if spotlight_results.is_empty() && !root_path.is_empty() && root_path != "/" {
-            return self.walkdir_fallback.search(root_path, query);
+            return self.walkdir_fallback.search(root_path, query, filter);
         }
 
         Ok(spotlight_results)
@@ -5,6 +5,9 @@
 //! Common trait and types for file searchers across platforms.
 
 use deno_error::JsErrorBox;
+use serde::Deserialize;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// A trait for file search implementations across different platforms.
 pub trait FileSearcher: Send + Sync {
@@ -13,10 +16,37 @@ pub trait FileSearcher: Send + Sync {
     /// # Arguments
     /// * `root_path` - The root directory to search in (may be ignored by indexed searchers)
     /// * `query` - The search query (file name pattern)
+    /// * `filter` - Additional conditions every result must meet
     ///
     /// # Returns
     /// A vector of file paths matching the query
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox>;
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox>;
+
+    /// Search like [`FileSearcher::search`], handing each path to `emit` as soon as it
+    /// is found. The search stops early once `emit` returns `false`, which is how
+    /// result limits and cancellation reach a long filesystem walk.
+    ///
+    /// Indexed searchers answer in one query, so the default implementation emits
+    /// the paths returned by `search`.
+    fn search_each(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), JsErrorBox> {
+        for path in self.search(root_path, query, filter)? {
+            if !emit(path) {
+                break;
+            }
+        }
+        Ok(())
+    }
 
     /// Check if this searcher is available on the current system.
     fn is_available(&self) -> bool;
@@ -24,3 +54,200 @@ pub trait FileSearcher: Send + Sync {
     /// Get the name of this searcher for debugging/logging purposes.
     fn name(&self) -> &'static str;
 }
+
+/// Conditions on search results beyond the file name query, passed from the
+/// workflow as the options of `search.file`.
+///
+/// Indexed searchers cannot express most of these in their query language, so
+/// they apply them to the paths the index returns with [`SearchFilter::retain`].
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilter {
+    /// File extensions without the dot, e.g. `["pdf", "docx"]`, compared case-insensitively.
+    pub extensions: Vec<String>,
+    /// Smallest file size in bytes.
+    pub min_size: Option<u64>,
+    /// Largest file size in bytes.
+    pub max_size: Option<u64>,
+    /// Only entries modified at or after this time, in milliseconds since the Unix epoch.
+    pub modified_after: Option<u64>,
+    /// Only entries modified at or before this time, in milliseconds since the Unix epoch.
+    pub modified_before: Option<u64>,
+    /// Only return directories.
+    pub directories_only: bool,
+    /// Match the query against file names case-sensitively.
+    pub case_sensitive: bool,
+}
+
+impl SearchFilter {
+    /// Returns whether `name` contains `query`, honouring `case_sensitive`.
+    pub fn name_matches(&self, name: &str, query: &str) -> bool {
+        if self.case_sensitive {
+            name.contains(query)
+        } else {
+            name.to_lowercase().contains(&query.to_lowercase())
+        }
+    }
+
+    /// Returns whether the entry at `path` meets the type, extension, size and
+    /// modification time conditions. Entries whose metadata cannot be read only
+    /// match a filter without such conditions.
+    pub fn path_matches(&self, path: &Path) -> bool {
+        if !self.needs_metadata() {
+            return true;
+        }
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+
+        if self.directories_only && !metadata.is_dir() {
+            return false;
+        }
+        if !self.extensions.is_empty() {
+            let extension = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase());
+            let listed = extension.is_some_and(|ext| {
+                self.extensions
+                    .iter()
+                    .any(|wanted| wanted.trim_start_matches('.').to_lowercase() == ext)
+            });
+            if metadata.is_dir() || !listed {
+                return false;
+            }
+        }
+        if self.min_size.is_some() || self.max_size.is_some() {
+            let size = metadata.len();
+            if metadata.is_dir()
+                || self.min_size.is_some_and(|min| size < min)
+                || self.max_size.is_some_and(|max| size > max)
+            {
+                return false;
+            }
+        }
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            let Ok(modified) = metadata.modified() else {
+                return false;
+            };
+            if self
+                .modified_after
+                .is_some_and(|after| modified < epoch_millis(after))
+                || self
+                    .modified_before
+                    .is_some_and(|before| modified > epoch_millis(before))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Keeps the paths returned by an index that meet the conditions. Indexes match
+    /// case-insensitively, so with `case_sensitive` the file name is checked again.
+    pub fn retain(&self, paths: Vec<String>, query: &str) -> Vec<String> {
+        paths
+            .into_iter()
+            .filter(|path| {
+                let path = Path::new(path);
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default();
+                (!self.case_sensitive || self.name_matches(&name, query)) && self.path_matches(path)
+            })
+            .collect()
+    }
+
+    fn needs_metadata(&self) -> bool {
+        self.directories_only
+            || !self.extensions.is_empty()
+            || self.min_size.is_some()
+            || self.max_size.is_some()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some()
+    }
+}
+
+fn epoch_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_filter_parses_options() {
+        let filter: SearchFilter = serde_json::from_str(
+            r#"{ "extensions": ["pdf"], "minSize": 10, "directoriesOnly": false, "caseSensitive": true }"#,
+        )
+        .unwrap();
+        assert_eq!(filter.extensions, vec!["pdf"]);
+        assert_eq!(filter.min_size, Some(10));
+        assert!(filter.case_sensitive);
+        assert_eq!(filter.max_size, None);
+    }
+
+    #[test]
+    fn test_filter_matches_entries() {
+        let dir = tempdir().unwrap();
+        let small = dir.path().join("small.TXT");
+        let large = dir.path().join("large.log");
+        fs::write(&small, "a").unwrap();
+        fs::write(&large, "a".repeat(100)).unwrap();
+
+        let by_extension = SearchFilter {
+            extensions: vec![".txt".to_string()],
+            ..SearchFilter::default()
+        };
+        assert!(by_extension.path_matches(&small));
+        assert!(!by_extension.path_matches(&large));
+        assert!(!by_extension.path_matches(dir.path()));
+
+        let by_size = SearchFilter {
+            min_size: Some(10),
+            max_size: Some(1000),
+            ..SearchFilter::default()
+        };
+        assert!(!by_size.path_matches(&small));
+        assert!(by_size.path_matches(&large));
+
+        let directories = SearchFilter {
+            directories_only: true,
+            ..SearchFilter::default()
+        };
+        assert!(directories.path_matches(dir.path()));
+        assert!(!directories.path_matches(&small));
+
+        let future = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+            + 60_000;
+        let modified_later = SearchFilter {
+            modified_after: Some(future),
+            ..SearchFilter::default()
+        };
+        assert!(!modified_later.path_matches(&small));
+        let modified_earlier = SearchFilter {
+            modified_before: Some(future),
+            ..SearchFilter::default()
+        };
+        assert!(modified_earlier.path_matches(&small));
+    }
+
+    #[test]
+    fn test_filter_case_sensitivity() {
+        let insensitive = SearchFilter::default();
+        assert!(insensitive.name_matches("Report.pdf", "report"));
+
+        let sensitive = SearchFilter {
+            case_sensitive: true,
+            ..SearchFilter::default()
+        };
+        assert!(!sensitive.name_matches("Report.pdf", "report"));
+        assert!(sensitive.name_matches("Report.pdf", "Report"));
+    }
+}
@@ -0,0 +1,251 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Searches running in the background so workflows can read results in chunks
+//! and cancel them.
+
+use crate::searcher::{FileSearcher, SearchFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Results returned when a workflow does not pass `maxResults`.
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// The `offset` and `maxResults` options of `search.file` and `search.start`. The
+/// JavaScript glue passes them apart from the [`SearchFilter`] options.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchPage {
+    /// Number of matches to skip.
+    pub offset: usize,
+    /// Largest number of matches to return after `offset`.
+    pub max_results: Option<usize>,
+}
+
+impl SearchPage {
+    /// Runs the search, skipping `offset` matches and stopping after `max_results`
+    /// or when `emit` returns `false`.
+    pub fn run(
+        &self,
+        searcher: &dyn FileSearcher,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), deno_error::JsErrorBox> {
+        let max_results = self.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+        if max_results == 0 {
+            return Ok(());
+        }
+        let (mut skipped, mut returned) = (0, 0);
+        searcher.search_each(root_path, query, filter, &mut |path| {
+            if skipped < self.offset {
+                skipped += 1;
+                return true;
+            }
+            returned += 1;
+            emit(path) && returned < max_results
+        })
+    }
+}
+
+/// The next part of a background search's results.
+#[derive(Debug, Default, Serialize)]
+pub struct SearchChunk {
+    pub paths: Vec<String>,
+    /// Set once the search has finished and every path has been returned.
+    pub done: bool,
+    /// Why the search stopped, when it failed.
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct Progress {
+    paths: VecDeque<String>,
+    finished: bool,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct Shared {
+    progress: Mutex<Progress>,
+    ready: Condvar,
+}
+
+/// A search running on a background thread. Dropping it cancels the search.
+pub struct SearchSession {
+    shared: Arc<Shared>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchSession {
+    pub fn start(
+        searcher: &'static dyn FileSearcher,
+        root_path: String,
+        query: String,
+        filter: SearchFilter,
+        page: SearchPage,
+    ) -> Self {
+        let shared = Arc::new(Shared::default());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (thread_shared, thread_cancelled) = (shared.clone(), cancelled.clone());
+        thread::spawn(move || {
+            let result = page.run(searcher, &root_path, &query, &filter, &mut |path| {
+                if thread_cancelled.load(Ordering::Relaxed) {
+                    return false;
+                }
+                thread_shared.progress.lock().unwrap().paths.push_back(path);
+                thread_shared.ready.notify_all();
+                true
+            });
+            let mut progress = thread_shared.progress.lock().unwrap();
+            progress.finished = true;
+            progress.error = result.err().map(|e| e.to_string());
+            thread_shared.ready.notify_all();
+        });
+        Self { shared, cancelled }
+    }
+
+    /// Takes the paths found so far, waiting up to `timeout` for one when none are
+    /// queued and the search is still running.
+    pub fn next_chunk(&self, timeout: Duration) -> SearchChunk {
+        let progress = self.shared.progress.lock().unwrap();
+        let (mut progress, _) = self
+            .shared
+            .ready
+            .wait_timeout_while(progress, timeout, |progress| {
+                progress.paths.is_empty() && !progress.finished
+            })
+            .unwrap();
+        SearchChunk {
+            paths: progress.paths.drain(..).collect(),
+            done: progress.finished,
+            error: progress.error.take(),
+        }
+    }
+
+    /// Asks the background search to stop at the next match it finds.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SearchSession {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Searches started by a single workflow run, keyed by the handle returned to
+/// JavaScript. They are cancelled when the run's `OpState` is dropped.
+#[derive(Default)]
+pub struct SearchSessions {
+    next_handle: u32,
+    pub sessions: HashMap<u32, SearchSession>,
+}
+
+impl SearchSessions {
+    pub fn insert(&mut self, session: SearchSession) -> u32 {
+        self.next_handle += 1;
+        self.sessions.insert(self.next_handle, session);
+        self.next_handle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walkdir_search::WalkdirSearcher;
+    use std::fs;
+    use tempfile::tempdir;
+
+    static WALKDIR: WalkdirSearcher = WalkdirSearcher;
+
+    fn write_files(dir: &std::path::Path, count: usize) {
+        for i in 0..count {
+            fs::write(dir.join(format!("item-{i:02}.txt")), "x").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_page_applies_offset_and_max_results() {
+        let dir = tempdir().unwrap();
+        write_files(dir.path(), 10);
+        let root = dir.path().to_str().unwrap();
+
+        let page: SearchPage = serde_json::from_str(r#"{ "offset": 3, "maxResults": 4 }"#).unwrap();
+        let filter = SearchFilter {
+            extensions: vec!["txt".to_string()],
+            ..SearchFilter::default()
+        };
+
+        let mut paths = Vec::new();
+        page.run(&WALKDIR, root, "item", &filter, &mut |path| {
+            paths.push(path);
+            true
+        })
+        .unwrap();
+        assert_eq!(paths.len(), 4);
+
+        let mut all = Vec::new();
+        SearchPage::default()
+            .run(&WALKDIR, root, "item", &filter, &mut |path| {
+                all.push(path);
+                true
+            })
+            .unwrap();
+        assert_eq!(all.len(), 10);
+        assert_eq!(paths, all[3..7]);
+    }
+
+    #[test]
+    fn test_session_returns_chunks_until_done() {
+        let dir = tempdir().unwrap();
+        write_files(dir.path(), 5);
+
+        let session = SearchSession::start(
+            &WALKDIR,
+            dir.path().to_str().unwrap().to_string(),
+            "item".to_string(),
+            SearchFilter::default(),
+            SearchPage::default(),
+        );
+
+        let mut paths = Vec::new();
+        loop {
+            let chunk = session.next_chunk(Duration::from_secs(5));
+            assert_eq!(chunk.error, None);
+            paths.extend(chunk.paths);
+            if chunk.done {
+                break;
+            }
+        }
+        assert_eq!(paths.len(), 5);
+    }
+
+    #[test]
+    fn test_cancelled_session_finishes() {
+        let dir = tempdir().unwrap();
+        write_files(dir.path(), 5);
+
+        let session = SearchSession::start(
+            &WALKDIR,
+            dir.path().to_str().unwrap().to_string(),
+            "item".to_string(),
+            SearchFilter::default(),
+            SearchPage::default(),
+        );
+        session.cancel();
+
+        let mut chunk = session.next_chunk(Duration::from_secs(5));
+        while !chunk.done {
+            chunk = session.next_chunk(Duration::from_secs(5));
+        }
+        assert_eq!(chunk.error, None);
+    }
+}
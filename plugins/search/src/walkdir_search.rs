@@ -5,10 +5,13 @@
 //! Walkdir-based file search implementation.
 //!
 //! This is a cross-platform fallback searcher that works on all operating systems
-//! by traversing the filesystem directly.
+//! by traversing the filesystem directly, or by querying the file index when
+//! one covers the searched directory.
 
-use crate::searcher::FileSearcher;
+use crate::file_index;
+use crate::searcher::{FileSearcher, SearchFilter};
 use deno_error::JsErrorBox;
+use std::path::Path;
 use walkdir::WalkDir;
 
 /// Searcher using walkdir for filesystem traversal.
@@ -30,18 +33,43 @@ impl Default for WalkdirSearcher {
 }
 
 impl FileSearcher for WalkdirSearcher {
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox> {
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox> {
+        let mut results = Vec::new();
+        self.search_each(root_path, query, filter, &mut |path| {
+            results.push(path);
+            results.len() < 1000 // Limit results to prevent memory issues
+        })?;
+        Ok(results)
+    }
+
+    fn search_each(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), JsErrorBox> {
         let root = if root_path.is_empty() { "/" } else { root_path };
+        if let Some(index) = file_index::index_covering(Path::new(root)) {
+            return index.search_each(Path::new(root), query, filter, emit);
+        }
 
-        let results: Vec<String> = WalkDir::new(root)
+        let matches = WalkDir::new(root)
             .into_iter()
             .filter_map(Result::ok)
-            .filter(|e| e.file_name().to_string_lossy().contains(query))
-            .take(1000) // Limit results to prevent memory issues
-            .map(|e| e.path().to_string_lossy().into_owned())
-            .collect();
-
-        Ok(results)
+            .filter(|e| filter.name_matches(&e.file_name().to_string_lossy(), query))
+            .filter(|e| filter.path_matches(e.path()));
+        for entry in matches {
+            if !emit(entry.path().to_string_lossy().into_owned()) {
+                break;
+            }
+        }
+        Ok(())
     }
 
     fn is_available(&self) -> bool {
@@ -75,19 +103,60 @@ mod tests {
         let searcher = WalkdirSearcher::new();
 
         // Test searching for existing file
-        let results = searcher.search(&dir_path, "doc1").unwrap();
+        let results = searcher
+            .search(&dir_path, "doc1", &SearchFilter::default())
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert!(results[0].contains("doc1.txt"));
 
         // Test searching for non-existing file
-        let results = searcher.search(&dir_path, "nonexistent").unwrap();
+        let results = searcher
+            .search(&dir_path, "nonexistent", &SearchFilter::default())
+            .unwrap();
         assert_eq!(results.len(), 0);
 
         // Test searching for multiple files
-        let results = searcher.search(&dir_path, "doc").unwrap();
+        let results = searcher
+            .search(&dir_path, "doc", &SearchFilter::default())
+            .unwrap();
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_walkdir_search_with_filter() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap().to_string();
+
+        fs::create_dir(dir.path().join("Reports")).unwrap();
+        fs::write(dir.path().join("Reports/report-q1.pdf"), "pdf").unwrap();
+        fs::write(dir.path().join("report-notes.txt"), "notes").unwrap();
+
+        let searcher = WalkdirSearcher::new();
+
+        // Matching is case-insensitive by default, so the directory matches too.
+        let results = searcher
+            .search(&dir_path, "report", &SearchFilter::default())
+            .unwrap();
+        assert_eq!(results.len(), 3);
+
+        let pdfs = SearchFilter {
+            extensions: vec!["pdf".to_string()],
+            ..SearchFilter::default()
+        };
+        let results = searcher.search(&dir_path, "report", &pdfs).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].ends_with("report-q1.pdf"));
+
+        let directories = SearchFilter {
+            directories_only: true,
+            case_sensitive: true,
+            ..SearchFilter::default()
+        };
+        let results = searcher.search(&dir_path, "Report", &directories).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].ends_with("Reports"));
+    }
+
     #[test]
     fn test_walkdir_is_always_available() {
         let searcher = WalkdirSearcher::new();
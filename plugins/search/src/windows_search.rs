@@ -7,7 +7,7 @@
 //! This module provides `WindowsSearchApiSearcher` which uses the built-in
 //! Windows Search indexer (Windows Index Search) available on all modern Windows versions.
 
-use crate::searcher::FileSearcher;
+use crate::searcher::{FileSearcher, SearchFilter};
 use deno_error::JsErrorBox;
 use std::sync::OnceLock;
 
@@ -55,7 +55,12 @@ impl Default for WindowsSearchApiSearcher {
 }
 
 impl FileSearcher for WindowsSearchApiSearcher {
-    fn search(&self, root_path: &str, query: &str) -> Result<Vec<String>, JsErrorBox> {
+    fn search(
+        &self,
+        root_path: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<String>, JsErrorBox> {
         use windows::core::BSTR;
         use windows::Win32::System::Com::{
             CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED,
@@ -155,7 +160,9 @@ impl FileSearcher for WindowsSearchApiSearcher {
                     .collect();
             }
 
-            Ok(results)
+            // The index matches names case-insensitively; the remaining conditions
+            // are checked on the returned paths.
+            Ok(filter.retain(results, query))
         }
     }
 
@@ -0,0 +1,15 @@
+function get(name) {
+    return Deno.core.ops.op2_secrets_get(name);
+}
+
+function set(name, value) {
+    return Deno.core.ops.op2_secrets_set(name, value);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.secrets = globalThis.app.sapphillon.core.secrets || {};
+
+globalThis.app.sapphillon.core.secrets.get = get;
+globalThis.app.sapphillon.core.secrets.set = set;
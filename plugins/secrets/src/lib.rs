@@ -0,0 +1,422 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Secrets plugin - keeps credentials used by plugins out of workflow code and the database
+//
+// Secrets live in the OS keyring under the `sapphillon` service. For headless
+// environments without a keyring, a secret can also be supplied through the
+// `SAPPHILLON_SECRET_<NAME>` environment variable, which takes precedence.
+//
+// Every value read or stored is remembered for the rest of the process, so the
+// server can mask it in workflow results and logs (see `revealed_secrets`).
+//
+// Workflows of a user only reach that user's secrets, which are stored as
+// `<owner>/<name>` and cannot be overridden from the environment (see
+// `run_as_owner`). Shared workflows and the server itself use the unprefixed names.
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use std::cell::RefCell;
+use std::sync::Mutex;
+
+const KEYRING_SERVICE: &str = "sapphillon";
+const ENV_PREFIX: &str = "SAPPHILLON_SECRET_";
+
+/// Values shorter than this are not remembered; masking them would mangle ordinary output.
+const MIN_REVEALED_LEN: usize = 4;
+
+static REVEALED_SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static SECRET_OWNER: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Prefix of the permission resource that names a secret, e.g. `secret://smtp.password`.
+pub const SECRET_RESOURCE_PREFIX: &str = "secret://";
+
+pub fn secrets_get_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.secrets.get".to_string(),
+        function_name: "secrets.get".to_string(),
+        version: "".to_string(),
+        description: "Reads a secret from the OS keyring.".to_string(),
+        permissions: secrets_read_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "name".to_string(),
+                r#type: "string".to_string(),
+                description: "Name of the secret".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "value".to_string(),
+                r#type: "string | null".to_string(),
+                description: "The secret value, or null if it is not set".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn secrets_set_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.secrets.set".to_string(),
+        function_name: "secrets.set".to_string(),
+        version: "".to_string(),
+        description: "Stores a secret in the OS keyring, replacing any existing value.".to_string(),
+        permissions: secrets_write_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "name".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Name of the secret".to_string(),
+                },
+                FunctionParameter {
+                    name: "value".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Value to store".to_string(),
+                },
+            ],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn secrets_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.secrets".to_string(),
+        package_name: "Secrets".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to read and store secrets in the OS keyring.".to_string(),
+        functions: vec![secrets_get_plugin_function(), secrets_set_plugin_function()],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_secrets_get_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        secrets_get_plugin_function().function_id,
+        "Get".to_string(),
+        secrets_get_plugin_function().description,
+        op2_secrets_get(),
+        Some(include_str!("00_secrets.js").to_string()),
+    )
+}
+
+pub fn core_secrets_set_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        secrets_set_plugin_function().function_id,
+        "Set".to_string(),
+        secrets_set_plugin_function().description,
+        op2_secrets_set(),
+        Some(include_str!("00_secrets.js").to_string()),
+    )
+}
+
+pub fn core_secrets_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        secrets_plugin_package().package_id,
+        "Secrets".to_string(),
+        vec![core_secrets_get_plugin(), core_secrets_set_plugin()],
+    )
+}
+
+/// Runs `run` with the secrets of `owner`: `secrets.get` and `secrets.set` called on
+/// this thread use the secret names of [`owned_secret_name`]. Workflows without an
+/// owner use the shared secrets.
+///
+/// Like the permission context, the owner is bound to the thread, so `run` must execute
+/// the workflow itself.
+pub fn run_as_owner<R>(owner: Option<&str>, run: impl FnOnce() -> R) -> R {
+    let previous = SECRET_OWNER.with(|current| current.replace(owner.map(str::to_string)));
+    let value = run();
+    SECRET_OWNER.with(|current| *current.borrow_mut() = previous);
+    value
+}
+
+/// Name under which the secret `name` of `owner` is stored, e.g. `alice/smtp.password`.
+/// Secrets without an owner keep their name.
+pub fn owned_secret_name(owner: Option<&str>, name: &str) -> String {
+    match owner {
+        Some(owner) => format!("{owner}/{name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Permission resource naming the secret `name`.
+pub fn secret_resource(name: &str) -> String {
+    format!("{SECRET_RESOURCE_PREFIX}{name}")
+}
+
+#[op2]
+#[serde]
+fn op2_secrets_get(
+    state: &mut OpState,
+    #[string] name: String,
+) -> std::result::Result<Option<String>, JsErrorBox> {
+    ensure_permission(
+        state,
+        &secrets_get_plugin_function().function_id,
+        secrets_read_plugin_permissions(),
+        &secret_resource(&name),
+    )?;
+
+    let value = match SECRET_OWNER.with(|owner| owner.borrow().clone()) {
+        Some(owner) => get_owned_secret(&owner, &name),
+        None => get_secret(&name),
+    };
+    value.map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+fn op2_secrets_set(
+    state: &mut OpState,
+    #[string] name: String,
+    #[string] value: String,
+) -> std::result::Result<(), JsErrorBox> {
+    ensure_permission(
+        state,
+        &secrets_set_plugin_function().function_id,
+        secrets_write_plugin_permissions(),
+        &secret_resource(&name),
+    )?;
+
+    let stored = match SECRET_OWNER.with(|owner| owner.borrow().clone()) {
+        Some(owner) => set_owned_secret(&owner, &name, &value),
+        None => set_secret(&name, &value),
+    };
+    stored.map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+/// Returns the environment variable that overrides the secret `name`,
+/// e.g. `smtp.host` becomes `SAPPHILLON_SECRET_SMTP_HOST`.
+pub fn secret_env_var(name: &str) -> String {
+    let suffix: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{ENV_PREFIX}{suffix}")
+}
+
+fn entry(name: &str) -> anyhow::Result<keyring::Entry> {
+    if name.is_empty() {
+        anyhow::bail!("secret name must not be empty");
+    }
+    Ok(keyring::Entry::new(KEYRING_SERVICE, name)?)
+}
+
+/// Looks up a secret.
+///
+/// # Arguments
+///
+/// * `name` - Name of the secret, e.g. `smtp.password`.
+///
+/// # Returns
+///
+/// Returns `Ok(Some(value))` when the secret exists, `Ok(None)` when it does not,
+/// or an error when the keyring cannot be accessed.
+pub fn get_secret(name: &str) -> anyhow::Result<Option<String>> {
+    match std::env::var(secret_env_var(name)) {
+        Ok(value) => {
+            remember_secret(&value);
+            Ok(Some(value))
+        }
+        Err(_) => get_keyring_secret(name),
+    }
+}
+
+/// Looks up a secret of `owner` in the OS keyring; see [`owned_secret_name`].
+///
+/// Unlike [`get_secret`], the environment is not consulted, since its variables are
+/// shared by every user of the server.
+pub fn get_owned_secret(owner: &str, name: &str) -> anyhow::Result<Option<String>> {
+    if name.is_empty() {
+        anyhow::bail!("secret name must not be empty");
+    }
+    get_keyring_secret(&owned_secret_name(Some(owner), name))
+}
+
+fn get_keyring_secret(name: &str) -> anyhow::Result<Option<String>> {
+    let value = match entry(name)?.get_password() {
+        Ok(value) => value,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    remember_secret(&value);
+    Ok(Some(value))
+}
+
+/// Stores a secret in the OS keyring, replacing any existing value.
+pub fn set_secret(name: &str, value: &str) -> anyhow::Result<()> {
+    entry(name)?.set_password(value)?;
+    remember_secret(value);
+    log::debug!("Stored secret: {name}");
+    Ok(())
+}
+
+/// Stores a secret of `owner` in the OS keyring; see [`owned_secret_name`].
+pub fn set_owned_secret(owner: &str, name: &str, value: &str) -> anyhow::Result<()> {
+    if name.is_empty() {
+        anyhow::bail!("secret name must not be empty");
+    }
+    set_secret(&owned_secret_name(Some(owner), name), value)
+}
+
+/// Returns the secret values read or stored by this process, longest first, so a
+/// value that contains another one is masked as a whole.
+pub fn revealed_secrets() -> Vec<String> {
+    REVEALED_SECRETS
+        .lock()
+        .map(|secrets| secrets.clone())
+        .unwrap_or_default()
+}
+
+fn remember_secret(value: &str) {
+    if value.chars().count() < MIN_REVEALED_LEN {
+        return;
+    }
+    let Ok(mut secrets) = REVEALED_SECRETS.lock() else {
+        return;
+    };
+    if !secrets.iter().any(|known| known == value) {
+        secrets.push(value.to_string());
+        secrets.sort_by_key(|known| std::cmp::Reverse(known.len()));
+    }
+}
+
+/// Removes a secret from the OS keyring. Removing a missing secret is not an error.
+pub fn delete_secret(name: &str) -> anyhow::Result<()> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// The core permission model has no dedicated SecretsAccess type yet, so secret
+// access is expressed as a read or write on a `secret://<name>` resource. Those
+// resources never match filesystem paths, so filesystem grants do not leak secrets.
+fn secrets_read_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Secrets Access".to_string(),
+        description: "Allows the plugin to read secrets from the OS keyring.".to_string(),
+        permission_type: PermissionType::FilesystemRead as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+fn secrets_write_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Secrets Access".to_string(),
+        description: "Allows the plugin to store secrets in the OS keyring.".to_string(),
+        permission_type: PermissionType::FilesystemWrite as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_secret_env_var() {
+        assert_eq!(secret_env_var("smtp.host"), "SAPPHILLON_SECRET_SMTP_HOST");
+        assert_eq!(secret_env_var("api-key"), "SAPPHILLON_SECRET_API_KEY");
+    }
+
+    #[test]
+    fn test_empty_name_is_rejected() {
+        assert!(set_secret("", "value").is_err());
+        assert!(set_owned_secret("alice", "", "value").is_err());
+    }
+
+    #[test]
+    fn test_owned_secret_name() {
+        assert_eq!(
+            owned_secret_name(Some("alice"), "smtp.password"),
+            "alice/smtp.password"
+        );
+        assert_eq!(owned_secret_name(None, "smtp.password"), "smtp.password");
+    }
+
+    #[test]
+    fn test_revealed_secrets_skip_short_values() {
+        remember_secret("abc");
+        remember_secret("revealed-token");
+        remember_secret("revealed-token-with-suffix");
+        remember_secret("revealed-token");
+
+        let revealed = revealed_secrets();
+        assert!(!revealed.contains(&"abc".to_string()));
+        let position = |value: &str| revealed.iter().position(|known| known == value);
+        assert!(position("revealed-token-with-suffix") < position("revealed-token"));
+        assert_eq!(
+            revealed
+                .iter()
+                .filter(|known| *known == "revealed-token")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_secret_resource() {
+        assert_eq!(secret_resource("api.token"), "secret://api.token");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_permission_denied_in_workflow() {
+        use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+        use sapphillon_core::workflow::CoreWorkflowCode;
+
+        let code = "app.sapphillon.core.secrets.get(\"api.token\");";
+
+        // A grant for a different secret must not give access to this one.
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: secrets_get_plugin_function().function_id,
+            permissions: Permissions {
+                permissions: vec![Permission {
+                    display_name: "Secrets Access".to_string(),
+                    description: "Allows secrets tests".to_string(),
+                    permission_type: PermissionType::FilesystemRead as i32,
+                    permission_level: PermissionLevel::High as i32,
+                    resource: vec![secret_resource("other.token")],
+                }],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_secrets_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+    }
+}
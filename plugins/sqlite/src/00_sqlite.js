@@ -0,0 +1,25 @@
+function open(path) {
+    return Deno.core.ops.op2_sqlite_open(path);
+}
+
+function query(handle, sql, params) {
+    return Deno.core.ops.op2_sqlite_query(handle, sql, params || []);
+}
+
+function execute(handle, sql, params) {
+    return Deno.core.ops.op2_sqlite_execute(handle, sql, params || []);
+}
+
+function close(handle) {
+    return Deno.core.ops.op2_sqlite_close(handle);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.sqlite = globalThis.app.sapphillon.core.sqlite || {};
+
+globalThis.app.sapphillon.core.sqlite.open = open;
+globalThis.app.sapphillon.core.sqlite.query = query;
+globalThis.app.sapphillon.core.sqlite.execute = execute;
+globalThis.app.sapphillon.core.sqlite.close = close;
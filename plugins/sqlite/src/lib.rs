@@ -0,0 +1,645 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// SQLite plugin - provides parameterized access to local SQLite databases with permission checks
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use plugin_permission::ensure_permission;
+use rusqlite::Connection;
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::limits::Limit;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
+    PluginPackage,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+pub fn sqlite_open_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sqlite.open".to_string(),
+        function_name: "sqlite.open".to_string(),
+        version: "".to_string(),
+        description: "Opens (or creates) a SQLite database file and returns a handle to it."
+            .to_string(),
+        permissions: sqlite_read_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "path".to_string(),
+                r#type: "string".to_string(),
+                description: "Path to the SQLite database file".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "handle".to_string(),
+                r#type: "number".to_string(),
+                description: "Handle used by query, execute and close".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sqlite_query_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sqlite.query".to_string(),
+        function_name: "sqlite.query".to_string(),
+        version: "".to_string(),
+        description:
+            "Runs a parameterized SELECT statement and returns the rows as an array of objects."
+                .to_string(),
+        permissions: sqlite_read_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "handle".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Handle returned by sqlite.open".to_string(),
+                },
+                FunctionParameter {
+                    name: "sql".to_string(),
+                    r#type: "string".to_string(),
+                    description: "A single SQL statement using ? placeholders".to_string(),
+                },
+                FunctionParameter {
+                    name: "params".to_string(),
+                    r#type: "any[]".to_string(),
+                    description: "Values bound to the statement placeholders".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "rows".to_string(),
+                r#type: "object[]".to_string(),
+                description: "Rows keyed by column name".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sqlite_execute_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sqlite.execute".to_string(),
+        function_name: "sqlite.execute".to_string(),
+        version: "".to_string(),
+        description:
+            "Runs a parameterized statement that modifies the database and returns the number of affected rows."
+                .to_string(),
+        permissions: sqlite_write_plugin_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "handle".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Handle returned by sqlite.open".to_string(),
+                },
+                FunctionParameter {
+                    name: "sql".to_string(),
+                    r#type: "string".to_string(),
+                    description: "A single SQL statement using ? placeholders".to_string(),
+                },
+                FunctionParameter {
+                    name: "params".to_string(),
+                    r#type: "any[]".to_string(),
+                    description: "Values bound to the statement placeholders".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "changes".to_string(),
+                r#type: "number".to_string(),
+                description: "Number of rows changed by the statement".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sqlite_close_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sqlite.close".to_string(),
+        function_name: "sqlite.close".to_string(),
+        version: "".to_string(),
+        description: "Closes a SQLite database handle.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "handle".to_string(),
+                r#type: "number".to_string(),
+                description: "Handle returned by sqlite.open".to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn sqlite_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.sqlite".to_string(),
+        package_name: "SQLite".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to query and update local SQLite databases.".to_string(),
+        functions: vec![
+            sqlite_open_plugin_function(),
+            sqlite_query_plugin_function(),
+            sqlite_execute_plugin_function(),
+            sqlite_close_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_sqlite_open_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sqlite_open_plugin_function().function_id,
+        "Open".to_string(),
+        sqlite_open_plugin_function().description,
+        op2_sqlite_open(),
+        Some(include_str!("00_sqlite.js").to_string()),
+    )
+}
+
+pub fn core_sqlite_query_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sqlite_query_plugin_function().function_id,
+        "Query".to_string(),
+        sqlite_query_plugin_function().description,
+        op2_sqlite_query(),
+        Some(include_str!("00_sqlite.js").to_string()),
+    )
+}
+
+pub fn core_sqlite_execute_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sqlite_execute_plugin_function().function_id,
+        "Execute".to_string(),
+        sqlite_execute_plugin_function().description,
+        op2_sqlite_execute(),
+        Some(include_str!("00_sqlite.js").to_string()),
+    )
+}
+
+pub fn core_sqlite_close_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sqlite_close_plugin_function().function_id,
+        "Close".to_string(),
+        sqlite_close_plugin_function().description,
+        op2_sqlite_close(),
+        Some(include_str!("00_sqlite.js").to_string()),
+    )
+}
+
+pub fn core_sqlite_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        sqlite_plugin_package().package_id,
+        "SQLite".to_string(),
+        vec![
+            core_sqlite_open_plugin(),
+            core_sqlite_query_plugin(),
+            core_sqlite_execute_plugin(),
+            core_sqlite_close_plugin(),
+        ],
+    )
+}
+
+/// Connections opened by a single workflow run, keyed by the handle returned to JavaScript.
+#[derive(Default)]
+struct SqliteConnections {
+    next_handle: u32,
+    connections: HashMap<u32, SqliteConnection>,
+}
+
+struct SqliteConnection {
+    path: String,
+    conn: Connection,
+}
+
+#[op2]
+#[smi]
+fn op2_sqlite_open(
+    state: &mut OpState,
+    #[string] path: String,
+) -> std::result::Result<u32, JsErrorBox> {
+    // Opening a database that does not exist yet creates it, which is a write.
+    let mut required = sqlite_read_plugin_permissions();
+    if !std::path::Path::new(&path).exists() {
+        required.extend(sqlite_write_plugin_permissions());
+    }
+    ensure_permission(
+        state,
+        &sqlite_open_plugin_function().function_id,
+        required,
+        &path,
+    )?;
+
+    let conn = open_connection(&path).map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+
+    if !state.has::<SqliteConnections>() {
+        state.put(SqliteConnections::default());
+    }
+    let connections = state.borrow_mut::<SqliteConnections>();
+    connections.next_handle += 1;
+    let handle = connections.next_handle;
+    connections
+        .connections
+        .insert(handle, SqliteConnection { path, conn });
+    Ok(handle)
+}
+
+#[op2]
+#[serde]
+fn op2_sqlite_query(
+    state: &mut OpState,
+    #[smi] handle: u32,
+    #[string] sql: String,
+    #[serde] params: Vec<Value>,
+) -> std::result::Result<Vec<Map<String, Value>>, JsErrorBox> {
+    let path = connection_path(state, handle)?;
+    ensure_permission(
+        state,
+        &sqlite_query_plugin_function().function_id,
+        sqlite_read_plugin_permissions(),
+        &path,
+    )?;
+
+    let connections = state.borrow::<SqliteConnections>();
+    let entry = connections
+        .connections
+        .get(&handle)
+        .ok_or_else(|| unknown_handle_error(handle))?;
+    query_rows(&entry.conn, &sql, &params).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[smi]
+fn op2_sqlite_execute(
+    state: &mut OpState,
+    #[smi] handle: u32,
+    #[string] sql: String,
+    #[serde] params: Vec<Value>,
+) -> std::result::Result<u32, JsErrorBox> {
+    let path = connection_path(state, handle)?;
+    ensure_permission(
+        state,
+        &sqlite_execute_plugin_function().function_id,
+        sqlite_write_plugin_permissions(),
+        &path,
+    )?;
+
+    let connections = state.borrow::<SqliteConnections>();
+    let entry = connections
+        .connections
+        .get(&handle)
+        .ok_or_else(|| unknown_handle_error(handle))?;
+    match execute_statement(&entry.conn, &sql, &params) {
+        Ok(changes) => Ok(changes as u32),
+        Err(e) => Err(JsErrorBox::new("Error", e.to_string())),
+    }
+}
+
+#[op2]
+fn op2_sqlite_close(state: &mut OpState, #[smi] handle: u32) -> Result<(), JsErrorBox> {
+    let removed = state
+        .try_borrow_mut::<SqliteConnections>()
+        .and_then(|connections| connections.connections.remove(&handle));
+    match removed {
+        Some(_) => Ok(()),
+        None => Err(unknown_handle_error(handle)),
+    }
+}
+
+/// Opens a database so statements can only reach the file the permission check was for.
+///
+/// Attaching another database (which `VACUUM INTO` does as well) would read or write a
+/// file that was never checked, and an extension could do anything, so both are refused.
+fn open_connection(path: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0)?;
+    conn.authorizer(Some(authorize));
+    Ok(conn)
+}
+
+fn authorize(context: AuthContext<'_>) -> Authorization {
+    match context.action {
+        AuthAction::Attach { .. } => Authorization::Deny,
+        AuthAction::Function { function_name }
+            if function_name.eq_ignore_ascii_case("load_extension") =>
+        {
+            Authorization::Deny
+        }
+        _ => Authorization::Allow,
+    }
+}
+
+fn connection_path(state: &OpState, handle: u32) -> Result<String, JsErrorBox> {
+    state
+        .try_borrow::<SqliteConnections>()
+        .and_then(|connections| connections.connections.get(&handle))
+        .map(|entry| entry.path.clone())
+        .ok_or_else(|| unknown_handle_error(handle))
+}
+
+fn unknown_handle_error(handle: u32) -> JsErrorBox {
+    JsErrorBox::new("Error", format!("Unknown SQLite handle: {handle}"))
+}
+
+/// Runs a single statement and collects every row into a JSON object keyed by column name.
+///
+/// `Connection::prepare` rejects trailing statements, so only one statement can run per call
+/// and values must be supplied through `params` rather than interpolated into `sql`.
+/// Queries only need read access, so statements that would change the database are refused.
+fn query_rows(
+    conn: &Connection,
+    sql: &str,
+    params: &[Value],
+) -> anyhow::Result<Vec<Map<String, Value>>> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        anyhow::bail!(
+            "sqlite.query only runs statements that read; use sqlite.execute to change the database"
+        );
+    }
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect();
+    let values: Vec<SqlValue> = params.iter().map(json_to_sql).collect();
+
+    let mut rows = stmt.query(rusqlite::params_from_iter(values.iter()))?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut object = Map::new();
+        for (index, name) in columns.iter().enumerate() {
+            object.insert(name.clone(), sql_to_json(row.get_ref(index)?));
+        }
+        out.push(object);
+    }
+    Ok(out)
+}
+
+/// Runs a single data-modifying statement and returns the number of changed rows.
+fn execute_statement(conn: &Connection, sql: &str, params: &[Value]) -> anyhow::Result<usize> {
+    let mut stmt = conn.prepare(sql)?;
+    let values: Vec<SqlValue> = params.iter().map(json_to_sql).collect();
+    let changes = stmt.execute(rusqlite::params_from_iter(values.iter()))?;
+    Ok(changes)
+}
+
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        // Arrays and objects are stored as their JSON text.
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn sql_to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(b) => Value::Array(b.iter().map(|byte| Value::from(*byte)).collect()),
+    }
+}
+
+fn sqlite_read_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Read".to_string(),
+        description: "Allows the plugin to read SQLite databases from the local filesystem."
+            .to_string(),
+        permission_type: PermissionType::FilesystemRead as i32,
+        permission_level: PermissionLevel::Medium as i32,
+        resource: vec![],
+    }]
+}
+
+fn sqlite_write_plugin_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Filesystem Write".to_string(),
+        description: "Allows the plugin to modify SQLite databases on the local filesystem."
+            .to_string(),
+        permission_type: PermissionType::FilesystemWrite as i32,
+        permission_level: PermissionLevel::High as i32,
+        resource: vec![],
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::permission::PluginFunctionPermissions;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn permission_for(
+        plugin_function_id: String,
+        permission_type: PermissionType,
+        path: &str,
+    ) -> PluginFunctionPermissions {
+        PluginFunctionPermissions {
+            plugin_function_id,
+            permissions: sapphillon_core::permission::Permissions {
+                permissions: vec![Permission {
+                    display_name: "SQLite".to_string(),
+                    description: "Allows SQLite tests".to_string(),
+                    permission_type: permission_type as i32,
+                    permission_level: PermissionLevel::Unspecified as i32,
+                    resource: vec![path.to_string()],
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_execute_and_query_with_params() {
+        let conn = Connection::open_in_memory().unwrap();
+        execute_statement(
+            &conn,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price REAL)",
+            &[],
+        )
+        .unwrap();
+        let changes = execute_statement(
+            &conn,
+            "INSERT INTO items (name, price) VALUES (?, ?)",
+            &[json!("apple"), json!(1.5)],
+        )
+        .unwrap();
+        assert_eq!(changes, 1);
+
+        let rows = query_rows(
+            &conn,
+            "SELECT id, name, price FROM items WHERE name = ?",
+            &[json!("apple")],
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["id"], json!(1));
+        assert_eq!(rows[0]["name"], json!("apple"));
+        assert_eq!(rows[0]["price"], json!(1.5));
+    }
+
+    #[test]
+    fn test_multiple_statements_are_rejected() {
+        let conn = Connection::open_in_memory().unwrap();
+        let res = execute_statement(&conn, "CREATE TABLE a (id INTEGER); DROP TABLE a;", &[]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_query_rejects_statements_that_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = open_connection(dir.path().join("q.db").to_str().unwrap()).unwrap();
+        execute_statement(&conn, "CREATE TABLE notes (body TEXT)", &[]).unwrap();
+        execute_statement(&conn, "INSERT INTO notes (body) VALUES ('a')", &[]).unwrap();
+
+        assert!(query_rows(&conn, "INSERT INTO notes (body) VALUES ('b')", &[]).is_err());
+        assert!(query_rows(&conn, "DELETE FROM notes", &[]).is_err());
+        assert!(query_rows(&conn, "DROP TABLE notes", &[]).is_err());
+        assert!(query_rows(&conn, "INSERT INTO notes VALUES ('c') RETURNING body", &[]).is_err());
+        let rows = query_rows(&conn, "SELECT body FROM notes", &[]).unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn test_other_databases_cannot_be_attached() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = open_connection(dir.path().join("main.db").to_str().unwrap()).unwrap();
+        let other = dir.path().join("other.db");
+        let other_path = json!(other.to_str().unwrap());
+
+        let attach = "ATTACH DATABASE ? AS other";
+        assert!(query_rows(&conn, attach, std::slice::from_ref(&other_path)).is_err());
+        assert!(execute_statement(&conn, attach, std::slice::from_ref(&other_path)).is_err());
+        assert!(execute_statement(&conn, "VACUUM INTO ?", &[other_path]).is_err());
+        assert!(!other.exists());
+    }
+
+    #[test]
+    fn test_extensions_cannot_be_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = open_connection(dir.path().join("ext.db").to_str().unwrap()).unwrap();
+        assert!(query_rows(&conn, "SELECT load_extension('libevil')", &[]).is_err());
+        assert!(execute_statement(&conn, "SELECT load_extension('libevil')", &[]).is_err());
+    }
+
+    #[test]
+    fn test_json_to_sql_conversion() {
+        assert_eq!(json_to_sql(&json!(null)), SqlValue::Null);
+        assert_eq!(json_to_sql(&json!(true)), SqlValue::Integer(1));
+        assert_eq!(json_to_sql(&json!(42)), SqlValue::Integer(42));
+        assert_eq!(json_to_sql(&json!(0.5)), SqlValue::Real(0.5));
+        assert_eq!(
+            json_to_sql(&json!({"a": 1})),
+            SqlValue::Text("{\"a\":1}".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_sqlite_in_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("workflow.db");
+        let db_path = db_path.to_str().unwrap().to_string();
+        let escaped_path = db_path.replace(r"\", r"\\");
+
+        let code = format!(
+            r#"
+            const db = app.sapphillon.core.sqlite.open({escaped_path:?});
+            app.sapphillon.core.sqlite.execute(db, "CREATE TABLE notes (body TEXT)");
+            app.sapphillon.core.sqlite.execute(db, "INSERT INTO notes (body) VALUES (?)", ["hello"]);
+            const rows = app.sapphillon.core.sqlite.query(db, "SELECT body FROM notes");
+            app.sapphillon.core.sqlite.close(db);
+            console.log(JSON.stringify(rows));
+            "#
+        );
+
+        let mut open_permission = permission_for(
+            sqlite_open_plugin_function().function_id,
+            PermissionType::FilesystemRead,
+            &db_path,
+        );
+        // Creating the database file also requires write access on open.
+        open_permission.permissions.permissions.push(Permission {
+            display_name: "SQLite".to_string(),
+            description: "Allows SQLite tests".to_string(),
+            permission_type: PermissionType::FilesystemWrite as i32,
+            permission_level: PermissionLevel::Unspecified as i32,
+            resource: vec![db_path.clone()],
+        });
+
+        let workflow_permissions = vec![
+            open_permission,
+            permission_for(
+                sqlite_execute_plugin_function().function_id,
+                PermissionType::FilesystemWrite,
+                &db_path,
+            ),
+            permission_for(
+                sqlite_query_plugin_function().function_id,
+                PermissionType::FilesystemRead,
+                &db_path,
+            ),
+        ];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_sqlite_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        let actual = &workflow.result[0].result;
+        assert_eq!(actual, "[{\"body\":\"hello\"}]\n");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_permission_denied_in_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("denied.db");
+        let escaped_path = db_path.to_str().unwrap().replace(r"\", r"\\");
+
+        let code = format!("app.sapphillon.core.sqlite.open({escaped_path:?});");
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: sqlite_open_plugin_function().function_id,
+            permissions: sapphillon_core::permission::Permissions {
+                permissions: vec![],
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_sqlite_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+        assert!(!db_path.exists());
+    }
+}
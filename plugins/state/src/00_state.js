@@ -0,0 +1,24 @@
+function namespace() {
+    const ns = globalThis.__sapphillonStateNamespace;
+    if (typeof ns !== "string") {
+        throw new Error("state is only available inside a stored workflow run");
+    }
+    return ns;
+}
+
+function get(key) {
+    const value = Deno.core.ops.op2_state_get(namespace(), String(key));
+    return value === undefined ? null : value;
+}
+
+function set(key, value) {
+    Deno.core.ops.op2_state_set(namespace(), String(key), value === undefined ? null : value);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.state = globalThis.app.sapphillon.core.state || {};
+
+globalThis.app.sapphillon.core.state.get = get;
+globalThis.app.sapphillon.core.state.set = set;
@@ -0,0 +1,253 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// State plugin - persistent key/value storage that survives between workflow runs
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Backend holding workflow state. Values are namespaced by workflow id.
+pub trait StateStore: Send + Sync {
+    fn get(&self, namespace: &str, key: &str) -> anyhow::Result<Option<Value>>;
+    /// Stores `value`, or removes the key when `value` is `None`.
+    fn set(&self, namespace: &str, key: &str, value: Option<Value>) -> anyhow::Result<()>;
+}
+
+/// Process-local store used until a persistent one is installed (and in tests).
+#[derive(Default)]
+pub struct MemoryStateStore {
+    values: Mutex<HashMap<(String, String), Value>>,
+}
+
+impl StateStore for MemoryStateStore {
+    fn get(&self, namespace: &str, key: &str) -> anyhow::Result<Option<Value>> {
+        let values = self.values.lock().map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(values
+            .get(&(namespace.to_string(), key.to_string()))
+            .cloned())
+    }
+
+    fn set(&self, namespace: &str, key: &str, value: Option<Value>) -> anyhow::Result<()> {
+        let mut values = self.values.lock().map_err(|e| anyhow::anyhow!("{e}"))?;
+        let entry = (namespace.to_string(), key.to_string());
+        match value {
+            Some(value) => values.insert(entry, value),
+            None => values.remove(&entry),
+        };
+        Ok(())
+    }
+}
+
+static STATE_STORE: OnceLock<Arc<dyn StateStore>> = OnceLock::new();
+
+/// Installs the store backing `state.get`/`state.set`. Only the first call takes effect.
+pub fn set_state_store(store: Arc<dyn StateStore>) -> bool {
+    STATE_STORE.set(store).is_ok()
+}
+
+fn state_store() -> &'static Arc<dyn StateStore> {
+    STATE_STORE.get_or_init(|| Arc::new(MemoryStateStore::default()))
+}
+
+/// JS prepended to workflow code so the plugin knows which workflow's namespace to use.
+pub fn namespace_prelude(workflow_id: &str) -> String {
+    let id = Value::String(workflow_id.to_string());
+    format!(
+        "Object.defineProperty(globalThis, \"__sapphillonStateNamespace\", {{ value: {id}, writable: false }});\n"
+    )
+}
+
+pub fn state_get_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.state.get".to_string(),
+        function_name: "state.get".to_string(),
+        version: "".to_string(),
+        description: "Reads a value this workflow stored in an earlier run.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "key".to_string(),
+                r#type: "string".to_string(),
+                description: "State key".to_string(),
+            }],
+            returns: vec![FunctionParameter {
+                name: "value".to_string(),
+                r#type: "any".to_string(),
+                description: "Stored JSON value, or null when the key is not set".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn state_set_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.state.set".to_string(),
+        function_name: "state.set".to_string(),
+        version: "".to_string(),
+        description: "Stores a JSON value for later runs of this workflow; null deletes the key."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "key".to_string(),
+                    r#type: "string".to_string(),
+                    description: "State key".to_string(),
+                },
+                FunctionParameter {
+                    name: "value".to_string(),
+                    r#type: "any".to_string(),
+                    description: "JSON-serializable value; null or undefined deletes the key"
+                        .to_string(),
+                },
+            ],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn state_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.state".to_string(),
+        package_name: "State".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to keep key/value state between workflow runs.".to_string(),
+        functions: vec![state_get_plugin_function(), state_set_plugin_function()],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_state_get_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        state_get_plugin_function().function_id,
+        "Get".to_string(),
+        state_get_plugin_function().description,
+        op2_state_get(),
+        Some(include_str!("00_state.js").to_string()),
+    )
+}
+
+pub fn core_state_set_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        state_set_plugin_function().function_id,
+        "Set".to_string(),
+        state_set_plugin_function().description,
+        op2_state_set(),
+        Some(include_str!("00_state.js").to_string()),
+    )
+}
+
+pub fn core_state_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        state_plugin_package().package_id,
+        "State".to_string(),
+        vec![core_state_get_plugin(), core_state_set_plugin()],
+    )
+}
+
+#[op2]
+#[serde]
+fn op2_state_get(
+    #[string] namespace: String,
+    #[string] key: String,
+) -> std::result::Result<Option<Value>, JsErrorBox> {
+    state_store()
+        .get(&namespace, &key)
+        .map_err(|e| JsErrorBox::new("Error", format!("{e:#}")))
+}
+
+#[op2]
+fn op2_state_set(
+    #[string] namespace: String,
+    #[string] key: String,
+    #[serde] value: Value,
+) -> std::result::Result<(), JsErrorBox> {
+    plugin_permission::stub_in_dry_run(&state_set_plugin_function().function_id)?;
+    let value = if value.is_null() { None } else { Some(value) };
+    state_store()
+        .set(&namespace, &key, value)
+        .map_err(|e| JsErrorBox::new("Error", format!("{e:#}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use serde_json::json;
+
+    #[test]
+    fn test_memory_store_namespaces_and_deletes() {
+        let store = MemoryStateStore::default();
+        store.set("wf-1", "count", Some(json!(1))).unwrap();
+        store.set("wf-2", "count", Some(json!(2))).unwrap();
+
+        assert_eq!(store.get("wf-1", "count").unwrap(), Some(json!(1)));
+        assert_eq!(store.get("wf-2", "count").unwrap(), Some(json!(2)));
+
+        store.set("wf-1", "count", None).unwrap();
+        assert_eq!(store.get("wf-1", "count").unwrap(), None);
+    }
+
+    #[test]
+    fn test_namespace_prelude_escapes_id() {
+        let prelude = namespace_prelude("a\"b");
+        assert!(prelude.contains(r#"value: "a\"b""#));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_state_persists_across_runs() {
+        let code = format!(
+            "{}{}",
+            namespace_prelude("test-state-workflow"),
+            r#"
+            const state = app.sapphillon.core.state;
+            const runs = (state.get("runs") || 0) + 1;
+            state.set("runs", runs);
+            console.log(runs);
+            "#
+        );
+
+        for expected in ["1\n", "2\n"] {
+            let mut workflow = CoreWorkflowCode::new(
+                "test".to_string(),
+                code.clone(),
+                vec![Arc::new(core_state_plugin_package())],
+                1,
+                vec![],
+                vec![],
+            );
+            workflow.run(tokio::runtime::Handle::current(), None, None);
+            assert_eq!(workflow.result[0].result, expected);
+        }
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_state_requires_namespace() {
+        let code = r#"app.sapphillon.core.state.get("x");"#;
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_state_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert!(workflow.result[0].result.contains("Uncaught"));
+    }
+}
@@ -0,0 +1,65 @@
+function sleep(ms) {
+    Deno.core.ops.op2_std_sleep(Math.max(0, Math.floor(Number(ms) || 0)));
+}
+
+function retry(fn, options) {
+    const opts = options || {};
+    const attempts = Math.max(1, opts.attempts ?? 3);
+    const delayMs = opts.delayMs ?? 500;
+    const maxDelayMs = opts.maxDelayMs ?? 30000;
+
+    const again = (attempt, error) => {
+        if (attempt + 1 >= attempts) {
+            throw error;
+        }
+        sleep(Deno.core.ops.op2_std_retry_delay(attempt, delayMs, maxDelayMs));
+        return run(attempt + 1);
+    };
+    const run = (attempt) => {
+        let result;
+        try {
+            result = fn(attempt);
+        } catch (e) {
+            return again(attempt, e);
+        }
+        if (result && typeof result.then === "function") {
+            return result.then(undefined, (e) => again(attempt, e));
+        }
+        return result;
+    };
+    return run(0);
+}
+
+function parseJsonSafe(text, fallback) {
+    try {
+        return Deno.core.ops.op2_std_parse_json(String(text));
+    } catch (_) {
+        return fallback === undefined ? null : fallback;
+    }
+}
+
+function chunk(items, size) {
+    return Deno.core.ops.op2_std_chunk(Array.from(items), Math.floor(Number(size) || 0));
+}
+
+function formatDate(date, format, options) {
+    const millis = date === undefined ? Date.now() : new Date(date).getTime();
+    if (Number.isNaN(millis)) {
+        throw new Error("invalid date: " + date);
+    }
+    const local = Boolean(options && options.local);
+    return Deno.core.ops.op2_std_format_date(millis, format || "%Y-%m-%dT%H:%M:%S%:z", local);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.std = globalThis.app.sapphillon.core.std || {};
+
+globalThis.app.sapphillon.core.std.sleep = sleep;
+globalThis.app.sapphillon.core.std.retry = retry;
+globalThis.app.sapphillon.core.std.parseJsonSafe = parseJsonSafe;
+globalThis.app.sapphillon.core.std.chunk = chunk;
+globalThis.app.sapphillon.core.std.formatDate = formatDate;
+
+globalThis.std = globalThis.app.sapphillon.core.std;
@@ -0,0 +1,477 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Std plugin - common workflow helpers (sleep, retry, JSON, chunking, dates)
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Utc};
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+
+/// JS prepended to workflow code so every console line also reaches the sink installed
+/// with [`run_with_console_sink`] while the workflow is still running. The console
+/// itself keeps its output, so the stored result is unchanged.
+pub const CONSOLE_FORWARD_PRELUDE: &str = r#"
+(() => {
+    const format = (arg) => {
+        if (typeof arg === "string") {
+            return arg;
+        }
+        try {
+            const json = JSON.stringify(arg);
+            return json === undefined ? String(arg) : json;
+        } catch (_) {
+            return String(arg);
+        }
+    };
+    for (const method of ["log", "info", "warn", "error", "debug"]) {
+        const original = console[method];
+        if (typeof original !== "function") {
+            continue;
+        }
+        console[method] = (...args) => {
+            for (const line of args.map(format).join(" ").split("\n")) {
+                Deno.core.ops.op2_std_console_line(line);
+            }
+            return original.apply(console, args);
+        };
+    }
+})();
+"#;
+
+type ConsoleSink = Box<dyn FnMut(&str)>;
+
+thread_local! {
+    static CONSOLE_SINK: RefCell<Option<ConsoleSink>> = const { RefCell::new(None) };
+}
+
+/// Runs `run`, handing every console line the workflow writes on this thread to `sink`
+/// as soon as it is written. Only code prefixed with [`CONSOLE_FORWARD_PRELUDE`]
+/// forwards its console output.
+///
+/// Like the permission context, the sink is bound to the thread, so `run` must execute
+/// the workflow itself.
+pub fn run_with_console_sink<R>(sink: impl FnMut(&str) + 'static, run: impl FnOnce() -> R) -> R {
+    let previous = CONSOLE_SINK.with(|current| current.borrow_mut().replace(Box::new(sink)));
+    let value = run();
+    CONSOLE_SINK.with(|current| *current.borrow_mut() = previous);
+    value
+}
+
+pub fn std_sleep_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.std.sleep".to_string(),
+        function_name: "std.sleep".to_string(),
+        version: "".to_string(),
+        description: "Pauses the workflow for the given number of milliseconds.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "ms".to_string(),
+                r#type: "number".to_string(),
+                description: "Milliseconds to wait".to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
+pub fn std_retry_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.std.retry".to_string(),
+        function_name: "std.retry".to_string(),
+        version: "".to_string(),
+        description: "Calls a function until it succeeds, waiting with exponential backoff between attempts."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "fn".to_string(),
+                    r#type: "function".to_string(),
+                    description: "Function to call; receives the zero-based attempt number and may return a promise"
+                        .to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: "object".to_string(),
+                    description: "Optional { attempts = 3, delayMs = 500, maxDelayMs = 30000 }"
+                        .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "result".to_string(),
+                r#type: "any".to_string(),
+                description: "Return value of the first successful call".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn std_parse_json_safe_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.std.parseJsonSafe".to_string(),
+        function_name: "std.parseJsonSafe".to_string(),
+        version: "".to_string(),
+        description: "Parses JSON, returning a fallback instead of throwing on invalid input."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "text".to_string(),
+                    r#type: "string".to_string(),
+                    description: "JSON text".to_string(),
+                },
+                FunctionParameter {
+                    name: "fallback".to_string(),
+                    r#type: "any".to_string(),
+                    description: "Value returned when parsing fails (default null)".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "value".to_string(),
+                r#type: "any".to_string(),
+                description: "Parsed value or the fallback".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn std_chunk_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.std.chunk".to_string(),
+        function_name: "std.chunk".to_string(),
+        version: "".to_string(),
+        description: "Splits an array of JSON values into arrays of at most the given size."
+            .to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "items".to_string(),
+                    r#type: "array".to_string(),
+                    description: "Values to split".to_string(),
+                },
+                FunctionParameter {
+                    name: "size".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Maximum length of each chunk; must be at least 1".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "chunks".to_string(),
+                r#type: "array".to_string(),
+                description: "Array of chunks".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn std_format_date_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.std.formatDate".to_string(),
+        function_name: "std.formatDate".to_string(),
+        version: "".to_string(),
+        description: "Formats a date with a strftime-style format string.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "date".to_string(),
+                    r#type: "Date | number | string".to_string(),
+                    description: "Date to format; defaults to now".to_string(),
+                },
+                FunctionParameter {
+                    name: "format".to_string(),
+                    r#type: "string".to_string(),
+                    description: "strftime format, e.g. %Y-%m-%d (default RFC 3339)".to_string(),
+                },
+                FunctionParameter {
+                    name: "options".to_string(),
+                    r#type: "object".to_string(),
+                    description:
+                        "Optional { local: true } to use the local time zone instead of UTC"
+                            .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "text".to_string(),
+                r#type: "string".to_string(),
+                description: "Formatted date".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn std_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.std".to_string(),
+        package_name: "Std".to_string(),
+        provider_id: "".to_string(),
+        description: "Common helper functions available to every workflow.".to_string(),
+        functions: vec![
+            std_sleep_plugin_function(),
+            std_retry_plugin_function(),
+            std_parse_json_safe_plugin_function(),
+            std_chunk_plugin_function(),
+            std_format_date_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_std_sleep_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        std_sleep_plugin_function().function_id,
+        "Sleep".to_string(),
+        std_sleep_plugin_function().description,
+        op2_std_sleep(),
+        Some(include_str!("00_std.js").to_string()),
+    )
+}
+
+pub fn core_std_retry_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        std_retry_plugin_function().function_id,
+        "Retry".to_string(),
+        std_retry_plugin_function().description,
+        op2_std_retry_delay(),
+        Some(include_str!("00_std.js").to_string()),
+    )
+}
+
+pub fn core_std_parse_json_safe_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        std_parse_json_safe_plugin_function().function_id,
+        "ParseJsonSafe".to_string(),
+        std_parse_json_safe_plugin_function().description,
+        op2_std_parse_json(),
+        Some(include_str!("00_std.js").to_string()),
+    )
+}
+
+pub fn core_std_chunk_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        std_chunk_plugin_function().function_id,
+        "Chunk".to_string(),
+        std_chunk_plugin_function().description,
+        op2_std_chunk(),
+        Some(include_str!("00_std.js").to_string()),
+    )
+}
+
+pub fn core_std_format_date_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        std_format_date_plugin_function().function_id,
+        "FormatDate".to_string(),
+        std_format_date_plugin_function().description,
+        op2_std_format_date(),
+        Some(include_str!("00_std.js").to_string()),
+    )
+}
+
+/// Internal function receiving forwarded console lines; it is not listed in the package
+/// so it never shows up in the catalog.
+pub fn core_std_console_line_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        "app.sapphillon.core.std.consoleLine".to_string(),
+        "ConsoleLine".to_string(),
+        "Forwards a console line of the running workflow.".to_string(),
+        op2_std_console_line(),
+        Some(include_str!("00_std.js").to_string()),
+    )
+}
+
+pub fn core_std_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        std_plugin_package().package_id,
+        "Std".to_string(),
+        vec![
+            core_std_sleep_plugin(),
+            core_std_retry_plugin(),
+            core_std_parse_json_safe_plugin(),
+            core_std_chunk_plugin(),
+            core_std_format_date_plugin(),
+            core_std_console_line_plugin(),
+        ],
+    )
+}
+
+#[op2]
+fn op2_std_console_line(#[string] line: String) {
+    CONSOLE_SINK.with(|sink| {
+        if let Some(sink) = sink.borrow_mut().as_mut() {
+            sink(&line);
+        }
+    });
+}
+
+#[op2]
+fn op2_std_sleep(#[smi] ms: u32) {
+    std::thread::sleep(std::time::Duration::from_millis(u64::from(ms)));
+}
+
+#[op2]
+#[smi]
+fn op2_std_retry_delay(#[smi] attempt: u32, #[smi] base_ms: u32, #[smi] max_ms: u32) -> u32 {
+    retry_delay_ms(attempt, base_ms, max_ms)
+}
+
+#[op2]
+#[serde]
+fn op2_std_parse_json(#[string] text: String) -> std::result::Result<Value, JsErrorBox> {
+    serde_json::from_str(&text).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_std_chunk(
+    #[serde] items: Vec<Value>,
+    #[smi] size: u32,
+) -> std::result::Result<Vec<Vec<Value>>, JsErrorBox> {
+    chunk(items, size as usize).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+#[op2]
+#[string]
+fn op2_std_format_date(
+    millis: f64,
+    #[string] format: String,
+    local: bool,
+) -> std::result::Result<String, JsErrorBox> {
+    format_date(millis, &format, local).map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+/// Delay before the retry following `attempt` (zero-based): `base_ms * 2^attempt`, capped at `max_ms`.
+fn retry_delay_ms(attempt: u32, base_ms: u32, max_ms: u32) -> u32 {
+    let factor = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+    u64::from(base_ms)
+        .saturating_mul(factor)
+        .min(u64::from(max_ms)) as u32
+}
+
+fn chunk(items: Vec<Value>, size: usize) -> anyhow::Result<Vec<Vec<Value>>> {
+    if size == 0 {
+        anyhow::bail!("chunk size must be at least 1");
+    }
+    Ok(items.chunks(size).map(<[Value]>::to_vec).collect())
+}
+
+/// Formats an epoch timestamp in milliseconds. Invalid format strings are rejected
+/// up front because chrono only reports them while writing.
+fn format_date(millis: f64, format: &str, local: bool) -> anyhow::Result<String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        anyhow::bail!("invalid date format: {format}");
+    }
+
+    let utc = DateTime::<Utc>::from_timestamp_millis(millis as i64)
+        .ok_or_else(|| anyhow::anyhow!("date out of range: {millis}"))?;
+
+    let mut out = String::new();
+    if local {
+        write!(
+            out,
+            "{}",
+            utc.with_timezone(&Local).format_with_items(items.iter())
+        )?;
+    } else {
+        write!(out, "{}", utc.format_with_items(items.iter()))?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay_ms(0, 500, 30_000), 500);
+        assert_eq!(retry_delay_ms(3, 500, 30_000), 4_000);
+        assert_eq!(retry_delay_ms(10, 500, 30_000), 30_000);
+        assert_eq!(retry_delay_ms(80, 500, 30_000), 30_000);
+    }
+
+    #[test]
+    fn test_chunk() {
+        let chunks = chunk(vec![json!(1), json!(2), json!(3)], 2).unwrap();
+        assert_eq!(chunks, vec![vec![json!(1), json!(2)], vec![json!(3)]]);
+        assert!(chunk(vec![json!(1)], 0).is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(
+            format_date(86_400_000.0, "%Y-%m-%d %H:%M", false).unwrap(),
+            "1970-01-02 00:00"
+        );
+        assert!(format_date(0.0, "%Q", false).is_err());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_helpers_in_workflow() {
+        let code = r#"
+            let calls = 0;
+            const value = std.retry(() => {
+                calls += 1;
+                if (calls < 3) throw new Error("flaky");
+                return std.parseJsonSafe('{"n": 2}').n;
+            }, { delayMs: 1 });
+            const fallback = std.parseJsonSafe("not json", "none");
+            console.log(`${calls} ${value} ${fallback} ${std.chunk([1, 2, 3], 2).length} ${std.formatDate(0, "%Y")}`);
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_std_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result[0].result, "3 2 none 2 1970\n");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_console_lines_reach_the_sink_during_the_run() {
+        let code =
+            format!("{CONSOLE_FORWARD_PRELUDE}console.log(\"a\", 1);\nconsole.error({{ b: 2 }});");
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code,
+            vec![Arc::new(core_std_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+
+        let lines = std::rc::Rc::new(RefCell::new(Vec::new()));
+        let sink_lines = lines.clone();
+        run_with_console_sink(
+            move |line| sink_lines.borrow_mut().push(line.to_string()),
+            || workflow.run(tokio::runtime::Handle::current(), None, None),
+        );
+        assert_eq!(*lines.borrow(), vec!["a 1", "{\"b\":2}"]);
+    }
+}
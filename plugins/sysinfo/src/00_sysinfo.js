@@ -0,0 +1,25 @@
+function cpu() {
+    return Deno.core.ops.op2_sysinfo_cpu();
+}
+
+function memory() {
+    return Deno.core.ops.op2_sysinfo_memory();
+}
+
+function disks() {
+    return Deno.core.ops.op2_sysinfo_disks();
+}
+
+function battery() {
+    return Deno.core.ops.op2_sysinfo_battery();
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.sysinfo = globalThis.app.sapphillon.core.sysinfo || {};
+
+globalThis.app.sapphillon.core.sysinfo.cpu = cpu;
+globalThis.app.sapphillon.core.sysinfo.memory = memory;
+globalThis.app.sapphillon.core.sysinfo.disks = disks;
+globalThis.app.sapphillon.core.sysinfo.battery = battery;
@@ -0,0 +1,350 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// System info plugin - reports CPU, memory, disk and battery status as structured data
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use serde::Serialize;
+use sysinfo::{Disks, System};
+
+pub fn sysinfo_cpu_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sysinfo.cpu".to_string(),
+        function_name: "sysinfo.cpu".to_string(),
+        version: "".to_string(),
+        description: "Returns overall and per-core CPU usage and the load average.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "cpu".to_string(),
+                r#type: "{ usage: number, cores: { name: string, brand: string, usage: number, frequency: number }[], loadAverage: { one: number, five: number, fifteen: number } }".to_string(),
+                description: "Usage is in percent, frequency in MHz; the load average is 0 on Windows".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sysinfo_memory_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sysinfo.memory".to_string(),
+        function_name: "sysinfo.memory".to_string(),
+        version: "".to_string(),
+        description: "Returns RAM and swap usage.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "memory".to_string(),
+                r#type: "{ total: number, used: number, available: number, swapTotal: number, swapUsed: number }".to_string(),
+                description: "Sizes in bytes".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sysinfo_disks_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sysinfo.disks".to_string(),
+        function_name: "sysinfo.disks".to_string(),
+        version: "".to_string(),
+        description: "Returns total and free space for every mounted disk.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "disks".to_string(),
+                r#type: "{ name: string, mountPoint: string, fileSystem: string, total: number, available: number, removable: boolean }[]".to_string(),
+                description: "Mounted disks; sizes in bytes".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sysinfo_battery_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.sysinfo.battery".to_string(),
+        function_name: "sysinfo.battery".to_string(),
+        version: "".to_string(),
+        description: "Returns the charge and state of every battery.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "batteries".to_string(),
+                r#type: "{ charge: number, state: string, timeToFull: number | null, timeToEmpty: number | null }[]".to_string(),
+                description: "Charge is in percent and times in seconds; empty on machines without a battery".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn sysinfo_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.sysinfo".to_string(),
+        package_name: "System Info".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to read CPU, memory, disk and battery status.".to_string(),
+        functions: vec![
+            sysinfo_cpu_plugin_function(),
+            sysinfo_memory_plugin_function(),
+            sysinfo_disks_plugin_function(),
+            sysinfo_battery_plugin_function(),
+        ],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_sysinfo_cpu_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sysinfo_cpu_plugin_function().function_id,
+        "Cpu".to_string(),
+        sysinfo_cpu_plugin_function().description,
+        op2_sysinfo_cpu(),
+        Some(include_str!("00_sysinfo.js").to_string()),
+    )
+}
+
+pub fn core_sysinfo_memory_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sysinfo_memory_plugin_function().function_id,
+        "Memory".to_string(),
+        sysinfo_memory_plugin_function().description,
+        op2_sysinfo_memory(),
+        Some(include_str!("00_sysinfo.js").to_string()),
+    )
+}
+
+pub fn core_sysinfo_disks_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sysinfo_disks_plugin_function().function_id,
+        "Disks".to_string(),
+        sysinfo_disks_plugin_function().description,
+        op2_sysinfo_disks(),
+        Some(include_str!("00_sysinfo.js").to_string()),
+    )
+}
+
+pub fn core_sysinfo_battery_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        sysinfo_battery_plugin_function().function_id,
+        "Battery".to_string(),
+        sysinfo_battery_plugin_function().description,
+        op2_sysinfo_battery(),
+        Some(include_str!("00_sysinfo.js").to_string()),
+    )
+}
+
+pub fn core_sysinfo_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        sysinfo_plugin_package().package_id,
+        "System Info".to_string(),
+        vec![
+            core_sysinfo_cpu_plugin(),
+            core_sysinfo_memory_plugin(),
+            core_sysinfo_disks_plugin(),
+            core_sysinfo_battery_plugin(),
+        ],
+    )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CpuInfo {
+    usage: f32,
+    cores: Vec<CoreInfo>,
+    load_average: LoadAverage,
+}
+
+#[derive(Debug, Serialize)]
+struct CoreInfo {
+    name: String,
+    brand: String,
+    usage: f32,
+    frequency: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadAverage {
+    one: f64,
+    five: f64,
+    fifteen: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryInfo {
+    total: u64,
+    used: u64,
+    available: u64,
+    swap_total: u64,
+    swap_used: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskInfo {
+    name: String,
+    mount_point: String,
+    file_system: String,
+    total: u64,
+    available: u64,
+    removable: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatteryInfo {
+    charge: f32,
+    state: String,
+    time_to_full: Option<f32>,
+    time_to_empty: Option<f32>,
+}
+
+#[op2]
+#[serde]
+fn op2_sysinfo_cpu() -> CpuInfo {
+    cpu_info()
+}
+
+#[op2]
+#[serde]
+fn op2_sysinfo_memory() -> MemoryInfo {
+    memory_info()
+}
+
+#[op2]
+#[serde]
+fn op2_sysinfo_disks() -> Vec<DiskInfo> {
+    disk_info()
+}
+
+#[op2]
+#[serde]
+fn op2_sysinfo_battery() -> std::result::Result<Vec<BatteryInfo>, JsErrorBox> {
+    battery_info().map_err(|e| JsErrorBox::new("Error", e.to_string()))
+}
+
+fn cpu_info() -> CpuInfo {
+    // CPU usage is computed between two refreshes, so sample over the minimum interval.
+    let mut system = System::new();
+    system.refresh_cpu_all();
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu_all();
+
+    let load = System::load_average();
+    CpuInfo {
+        usage: system.global_cpu_usage(),
+        cores: system
+            .cpus()
+            .iter()
+            .map(|cpu| CoreInfo {
+                name: cpu.name().to_string(),
+                brand: cpu.brand().to_string(),
+                usage: cpu.cpu_usage(),
+                frequency: cpu.frequency(),
+            })
+            .collect(),
+        load_average: LoadAverage {
+            one: load.one,
+            five: load.five,
+            fifteen: load.fifteen,
+        },
+    }
+}
+
+fn memory_info() -> MemoryInfo {
+    let mut system = System::new();
+    system.refresh_memory();
+    MemoryInfo {
+        total: system.total_memory(),
+        used: system.used_memory(),
+        available: system.available_memory(),
+        swap_total: system.total_swap(),
+        swap_used: system.used_swap(),
+    }
+}
+
+fn disk_info() -> Vec<DiskInfo> {
+    Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|disk| DiskInfo {
+            name: disk.name().to_string_lossy().to_string(),
+            mount_point: disk.mount_point().to_string_lossy().to_string(),
+            file_system: disk.file_system().to_string_lossy().to_string(),
+            total: disk.total_space(),
+            available: disk.available_space(),
+            removable: disk.is_removable(),
+        })
+        .collect()
+}
+
+fn battery_info() -> anyhow::Result<Vec<BatteryInfo>> {
+    let manager = starship_battery::Manager::new()?;
+    let mut batteries = Vec::new();
+    for battery in manager.batteries()? {
+        let battery = battery?;
+        batteries.push(BatteryInfo {
+            charge: battery.state_of_charge().value * 100.0,
+            state: battery.state().to_string(),
+            time_to_full: battery.time_to_full().map(|time| time.value),
+            time_to_empty: battery.time_to_empty().map(|time| time.value),
+        });
+    }
+    Ok(batteries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_memory_info_is_consistent() {
+        let memory = memory_info();
+        assert!(memory.total > 0);
+        assert!(memory.used <= memory.total);
+    }
+
+    #[test]
+    fn test_cpu_info_reports_cores() {
+        let cpu = cpu_info();
+        assert!(!cpu.cores.is_empty());
+        assert!(cpu.usage >= 0.0);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_sysinfo_in_workflow() {
+        let code = r#"
+            const memory = app.sapphillon.core.sysinfo.memory();
+            console.log(memory.total > 0);
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_sysinfo_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "true\n");
+    }
+}
@@ -0,0 +1,10 @@
+function render(template, data) {
+    return Deno.core.ops.op2_template_render(template, data === undefined ? {} : data);
+}
+
+globalThis.app = globalThis.app || {};
+globalThis.app.sapphillon = globalThis.app.sapphillon || {};
+globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
+globalThis.app.sapphillon.core.template = globalThis.app.sapphillon.core.template || {};
+
+globalThis.app.sapphillon.core.template.render = render;
@@ -0,0 +1,154 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Template plugin - renders Tera templates against workflow data
+use deno_core::op2;
+use deno_error::JsErrorBox;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, PluginFunction, PluginPackage,
+};
+use tera::{Context, Tera};
+
+pub fn template_render_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.template.render".to_string(),
+        function_name: "template.render".to_string(),
+        version: "".to_string(),
+        description: "Renders a Tera template string with the given data object.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "template".to_string(),
+                    r#type: "string".to_string(),
+                    description: "Tera (Jinja2-like) template source".to_string(),
+                },
+                FunctionParameter {
+                    name: "data".to_string(),
+                    r#type: "object".to_string(),
+                    description: "Values available to the template as top-level variables"
+                        .to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "output".to_string(),
+                r#type: "string".to_string(),
+                description: "Rendered text".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn template_plugin_package() -> PluginPackage {
+    PluginPackage {
+        package_id: "app.sapphillon.core.template".to_string(),
+        package_name: "Template".to_string(),
+        provider_id: "".to_string(),
+        description: "A plugin to render text from templates.".to_string(),
+        functions: vec![template_render_plugin_function()],
+        package_version: env!("CARGO_PKG_VERSION").to_string(),
+        deprecated: None,
+        plugin_store_url: "BUILTIN".to_string(),
+        internal_plugin: Some(true),
+        installed_at: None,
+        updated_at: None,
+        verified: Some(true),
+    }
+}
+
+pub fn core_template_render_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        template_render_plugin_function().function_id,
+        "Render".to_string(),
+        template_render_plugin_function().description,
+        op2_template_render(),
+        Some(include_str!("00_template.js").to_string()),
+    )
+}
+
+pub fn core_template_plugin_package() -> CorePluginPackage {
+    CorePluginPackage::new(
+        template_plugin_package().package_id,
+        "Template".to_string(),
+        vec![core_template_render_plugin()],
+    )
+}
+
+#[op2]
+#[string]
+fn op2_template_render(
+    #[string] template: String,
+    #[serde] data: serde_json::Value,
+) -> std::result::Result<String, JsErrorBox> {
+    render(&template, &data).map_err(|e| JsErrorBox::new("Error", format!("{e:#}")))
+}
+
+/// Renders `template` once without registering it; autoescaping is off because
+/// workflows mostly produce plain text and Markdown rather than HTML.
+fn render(template: &str, data: &serde_json::Value) -> anyhow::Result<String> {
+    let context = match data {
+        serde_json::Value::Null => Context::new(),
+        serde_json::Value::Object(_) => Context::from_value(data.clone())?,
+        _ => anyhow::bail!("template data must be an object"),
+    };
+    Ok(Tera::one_off(template, &context, false)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_render_loops_and_filters() {
+        let data = json!({
+            "title": "Report",
+            "items": [{ "name": "a", "count": 2 }, { "name": "b", "count": 3 }],
+        });
+        let template = "# {{ title | upper }}\n{% for item in items %}- {{ item.name }}: {{ item.count }}\n{% endfor %}";
+
+        assert_eq!(
+            render(template, &data).unwrap(),
+            "# REPORT\n- a: 2\n- b: 3\n"
+        );
+    }
+
+    #[test]
+    fn test_render_does_not_escape_html() {
+        let data = json!({ "body": "<b>bold</b>" });
+        assert_eq!(render("{{ body }}", &data).unwrap(), "<b>bold</b>");
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert!(render("{{ missing }}", &json!({})).is_err());
+        assert!(render("{% for %}", &json!({})).is_err());
+        assert!(render("{{ x }}", &json!([1, 2])).is_err());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_render_in_workflow() {
+        let code = r#"
+            const out = app.sapphillon.core.template.render("Hello, {{ name }}!", { name: "Sapphillon" });
+            console.log(out);
+        "#;
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_template_plugin_package())],
+            1,
+            vec![],
+            vec![],
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        assert_eq!(workflow.result[0].result, "Hello, Sapphillon!\n");
+    }
+}
@@ -0,0 +1,558 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// WASM plugin - external plugins compiled to WebAssembly and run in wasmtime
+//
+// A WASM plugin is a directory with a `plugin.json` manifest and the module it names:
+//
+// {
+//   "meta": { "name": "thumbnailer", "version": "1.0.0", "description": "...",
+//             "author_id": "com.example", "package_id": "com.example.thumbnailer" },
+//   "module": "plugin.wasm",
+//   "functions": {
+//     "thumbnail": {
+//       "description": "...",
+//       "permissions": [{ "type": "filesystem-read", "resource": ["/srv/images"] }],
+//       "parameters": [{ "name": "path", "type": "string", "description": "..." }],
+//       "returns": [{ "name": "thumbnail", "type": "string", "description": "..." }]
+//     }
+//   }
+// }
+//
+// Each function runs in a fresh instance that can only reach the directories its
+// permissions name; see [`runtime::Capabilities`].
+
+mod runtime;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use deno_core::{OpState, op2};
+use deno_error::JsErrorBox;
+use plugin_permission::ensure_permission;
+use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionDefine, FunctionParameter, Permission, PermissionLevel, PluginFunction, PluginPackage,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use runtime::WasmRuntime;
+pub use runtime::{Capabilities, WasmLimits};
+
+/// Name of the manifest file in a WASM plugin directory.
+pub const MANIFEST_FILE: &str = "plugin.json";
+
+/// Package that carries the shared op for every registered WASM plugin.
+const WASM_HOST_PACKAGE_ID: &str = "app.sapphillon.wasm";
+
+/// Error types for WASM plugins.
+#[derive(Debug, thiserror::Error)]
+pub enum WasmPluginError {
+    #[error("invalid WASM plugin manifest: {0}")]
+    Manifest(String),
+
+    #[error("failed to compile WASM module: {0}")]
+    Compile(String),
+
+    #[error("WASM plugin capability error: {0}")]
+    Capability(String),
+
+    #[error("WASM plugin does not follow the call convention: {0}")]
+    Abi(String),
+
+    #[error("WASM plugin trapped: {0}")]
+    Trap(String),
+
+    #[error("WASM plugin exceeded its limits: {0}")]
+    Limit(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WasmPluginMeta {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub author_id: String,
+    pub package_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestParameter {
+    name: String,
+    #[serde(rename = "type")]
+    parameter_type: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestPermission {
+    #[serde(rename = "type")]
+    permission_type: String,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    resource: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestFunction {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    permissions: Vec<ManifestPermission>,
+    #[serde(default)]
+    parameters: Vec<ManifestParameter>,
+    #[serde(default)]
+    returns: Vec<ManifestParameter>,
+}
+
+/// Contents of a `plugin.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WasmPluginManifest {
+    pub meta: WasmPluginMeta,
+    /// Path of the module, relative to the manifest.
+    pub module: String,
+    functions: BTreeMap<String, ManifestFunction>,
+}
+
+impl WasmPluginManifest {
+    pub fn parse(json: &str) -> Result<Self, WasmPluginError> {
+        let manifest: Self =
+            serde_json::from_str(json).map_err(|e| WasmPluginError::Manifest(e.to_string()))?;
+        for (field, value) in [
+            ("name", &manifest.meta.name),
+            ("version", &manifest.meta.version),
+            ("author_id", &manifest.meta.author_id),
+            ("package_id", &manifest.meta.package_id),
+            ("module", &manifest.module),
+        ] {
+            if value.trim().is_empty() {
+                return Err(WasmPluginError::Manifest(format!(
+                    "{field} must not be empty"
+                )));
+            }
+        }
+        if manifest.functions.is_empty() {
+            return Err(WasmPluginError::Manifest(
+                "functions declares no functions".to_string(),
+            ));
+        }
+        Ok(manifest)
+    }
+}
+
+struct WasmFunction {
+    plugin_function: PluginFunction,
+    capabilities: Capabilities,
+}
+
+/// A loaded WASM plugin.
+pub struct WasmPlugin {
+    meta: WasmPluginMeta,
+    functions: BTreeMap<String, WasmFunction>,
+    runtime: WasmRuntime,
+}
+
+impl WasmPlugin {
+    /// Loads the plugin in `dir` from its `plugin.json` and module.
+    pub fn load(dir: &Path, limits: WasmLimits) -> Result<Self, WasmPluginError> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let json = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| WasmPluginError::Manifest(format!("{}: {e}", manifest_path.display())))?;
+        let manifest = WasmPluginManifest::parse(&json)?;
+        let module_path = dir.join(&manifest.module);
+        let bytes = std::fs::read(&module_path)
+            .map_err(|e| WasmPluginError::Compile(format!("{}: {e}", module_path.display())))?;
+        Self::new(manifest, &bytes, limits)
+    }
+
+    /// Builds a plugin from a parsed manifest and the module it names.
+    pub fn new(
+        manifest: WasmPluginManifest,
+        module: &[u8],
+        limits: WasmLimits,
+    ) -> Result<Self, WasmPluginError> {
+        let mut functions = BTreeMap::new();
+        for (name, function) in manifest.functions {
+            let permissions = function
+                .permissions
+                .iter()
+                .map(to_permission)
+                .collect::<Result<Vec<_>, _>>()?;
+            let capabilities = Capabilities::from_permissions(&permissions)?;
+            let to_parameters = |parameters: Vec<ManifestParameter>| -> Vec<FunctionParameter> {
+                parameters
+                    .into_iter()
+                    .map(|p| FunctionParameter {
+                        name: p.name,
+                        r#type: p.parameter_type,
+                        description: p.description,
+                    })
+                    .collect()
+            };
+            let plugin_function = PluginFunction {
+                function_id: format!("{}.{name}", manifest.meta.package_id),
+                function_name: name.clone(),
+                version: manifest.meta.version.clone(),
+                description: function.description,
+                permissions,
+                function_define: Some(FunctionDefine {
+                    parameters: to_parameters(function.parameters),
+                    returns: to_parameters(function.returns),
+                }),
+            };
+            functions.insert(
+                name,
+                WasmFunction {
+                    plugin_function,
+                    capabilities,
+                },
+            );
+        }
+        Ok(Self {
+            meta: manifest.meta,
+            functions,
+            runtime: WasmRuntime::new(module, limits)?,
+        })
+    }
+
+    pub fn meta(&self) -> &WasmPluginMeta {
+        &self.meta
+    }
+
+    /// Describes the plugin the way built-in plugins are registered.
+    pub fn plugin_package(&self) -> PluginPackage {
+        PluginPackage {
+            package_id: self.meta.package_id.clone(),
+            package_name: self.meta.name.clone(),
+            provider_id: "".to_string(),
+            package_version: self.meta.version.clone(),
+            description: self.meta.description.clone(),
+            functions: self
+                .functions
+                .values()
+                .map(|function| function.plugin_function.clone())
+                .collect(),
+            plugin_store_url: "".to_string(),
+            internal_plugin: Some(false),
+            verified: Some(false),
+            deprecated: Some(false),
+            installed_at: None,
+            updated_at: None,
+        }
+    }
+
+    /// Calls a function with the capabilities its permissions grant.
+    pub fn call(&self, function: &str, args: &[Value]) -> Result<Value, WasmPluginError> {
+        let Some(wasm_function) = self.functions.get(function) else {
+            return Err(WasmPluginError::Abi(format!(
+                "{} has no function '{function}'",
+                self.meta.package_id
+            )));
+        };
+        self.runtime
+            .call(function, args, &wasm_function.capabilities)
+    }
+}
+
+fn to_permission(permission: &ManifestPermission) -> Result<Permission, WasmPluginError> {
+    let permission_type = plugin_permission::parse_permission_type(&permission.permission_type)
+        .ok_or_else(|| {
+            WasmPluginError::Manifest(format!(
+                "unknown permission type '{}'",
+                permission.permission_type
+            ))
+        })?;
+    let permission_level = match permission.level.as_deref() {
+        None => PermissionLevel::Unspecified as i32,
+        Some(level) => plugin_permission::parse_permission_level(level).ok_or_else(|| {
+            WasmPluginError::Manifest(format!("unknown permission level '{level}'"))
+        })?,
+    };
+    Ok(Permission {
+        display_name: permission.permission_type.clone(),
+        description: "Declared by a WASM plugin".to_string(),
+        permission_type,
+        permission_level,
+        resource: permission.resource.clone(),
+    })
+}
+
+static WASM_PLUGINS: RwLock<Option<HashMap<String, Arc<WasmPlugin>>>> = RwLock::new(None);
+
+/// Makes a loaded plugin available to workflows, replacing one with the same package ID.
+pub fn register_wasm_plugin(plugin: WasmPlugin) {
+    let package_id = plugin.meta.package_id.clone();
+    WASM_PLUGINS
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(package_id, Arc::new(plugin));
+}
+
+/// Replaces every registered plugin, e.g. when the installed plugins are reloaded.
+pub fn replace_wasm_plugins(plugins: Vec<WasmPlugin>) {
+    let plugins = plugins
+        .into_iter()
+        .map(|plugin| (plugin.meta.package_id.clone(), Arc::new(plugin)))
+        .collect();
+    *WASM_PLUGINS.write().unwrap() = Some(plugins);
+}
+
+/// Removes a plugin registered with [`register_wasm_plugin`].
+pub fn unregister_wasm_plugin(package_id: &str) -> bool {
+    WASM_PLUGINS
+        .write()
+        .unwrap()
+        .as_mut()
+        .is_some_and(|plugins| plugins.remove(package_id).is_some())
+}
+
+fn registered_plugin(package_id: &str) -> Option<Arc<WasmPlugin>> {
+    WASM_PLUGINS
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|plugins| plugins.get(package_id).cloned())
+}
+
+fn registered_plugins() -> Vec<Arc<WasmPlugin>> {
+    let mut plugins: Vec<Arc<WasmPlugin>> = WASM_PLUGINS
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|plugins| plugins.values().cloned().collect())
+        .unwrap_or_default();
+    plugins.sort_by(|a, b| a.meta.package_id.cmp(&b.meta.package_id));
+    plugins
+}
+
+/// Descriptions of every registered WASM plugin, for the plugin registry.
+pub fn wasm_plugin_packages() -> Vec<PluginPackage> {
+    registered_plugins()
+        .iter()
+        .map(|plugin| plugin.plugin_package())
+        .collect()
+}
+
+fn wasm_call_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: format!("{WASM_HOST_PACKAGE_ID}.call"),
+        function_name: "wasm.call".to_string(),
+        version: "".to_string(),
+        description: "Calls a function of a registered WASM plugin.".to_string(),
+        permissions: vec![],
+        function_define: None,
+    }
+}
+
+/// The runtime package that exposes every registered WASM plugin to workflows as
+/// `<package_id>.<function>(...)`.
+pub fn core_wasm_plugin_package() -> CorePluginPackage {
+    let mut js = include_str!("00_wasm.js").to_string();
+    for plugin in registered_plugins() {
+        for name in plugin.functions.keys() {
+            js.push_str(&format!(
+                "registerWasmFunction({}, {});\n",
+                serde_json::Value::String(plugin.meta.package_id.clone()),
+                serde_json::Value::String(name.clone())
+            ));
+        }
+    }
+    let function = wasm_call_plugin_function();
+    CorePluginPackage::new(
+        WASM_HOST_PACKAGE_ID.to_string(),
+        "WASM".to_string(),
+        vec![CorePluginFunction::new(
+            function.function_id,
+            "Call".to_string(),
+            function.description,
+            op2_wasm_call(),
+            Some(js),
+        )],
+    )
+}
+
+#[op2]
+#[string]
+fn op2_wasm_call(
+    state: &mut OpState,
+    #[string] package_id: String,
+    #[string] function: String,
+    #[string] args_json: String,
+) -> std::result::Result<String, JsErrorBox> {
+    let plugin = registered_plugin(&package_id).ok_or_else(|| {
+        JsErrorBox::new("Error", format!("WASM plugin '{package_id}' is not loaded"))
+    })?;
+    let wasm_function = plugin.functions.get(&function).ok_or_else(|| {
+        JsErrorBox::new(
+            "Error",
+            format!("WASM plugin '{package_id}' has no function '{function}'"),
+        )
+    })?;
+
+    // Each declared resource is checked on its own, like the built-in plugins do.
+    let plugin_function = &wasm_function.plugin_function;
+    for permission in &plugin_function.permissions {
+        let unscoped = Permission {
+            resource: vec![],
+            ..permission.clone()
+        };
+        for resource in &permission.resource {
+            ensure_permission(
+                state,
+                &plugin_function.function_id,
+                vec![unscoped.clone()],
+                resource,
+            )?;
+        }
+    }
+
+    let args: Vec<Value> = serde_json::from_str(&args_json)
+        .map_err(|e| JsErrorBox::new("TypeError", e.to_string()))?;
+    let result = plugin
+        .call(&function, &args)
+        .map_err(|e| JsErrorBox::new("Error", e.to_string()))?;
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::proto::sapphillon::v1::PermissionType;
+
+    /// Echoes its JSON arguments back as the result.
+    const ECHO_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "sapphillon_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len))))
+          (func (export "spin") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    fn manifest(functions: &str) -> WasmPluginManifest {
+        WasmPluginManifest::parse(&format!(
+            r#"{{
+                "meta": {{ "name": "echo", "version": "1.0.0", "author_id": "com.test",
+                           "package_id": "com.test.echo" }},
+                "module": "plugin.wat",
+                "functions": {functions}
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn call_passes_json_arguments_and_results() {
+        let plugin = WasmPlugin::new(
+            manifest(r#"{ "echo": { "description": "Echoes" } }"#),
+            ECHO_WAT.as_bytes(),
+            WasmLimits::default(),
+        )
+        .unwrap();
+
+        let result = plugin
+            .call("echo", &[Value::from("hello"), Value::from(2)])
+            .unwrap();
+        assert_eq!(result, serde_json::json!(["hello", 2]));
+
+        let package = plugin.plugin_package();
+        assert_eq!(package.functions[0].function_id, "com.test.echo.echo");
+        assert!(matches!(
+            plugin.call("missing", &[]),
+            Err(WasmPluginError::Abi(_))
+        ));
+    }
+
+    #[test]
+    fn call_stops_when_fuel_runs_out() {
+        let plugin = WasmPlugin::new(
+            manifest(r#"{ "spin": {} }"#),
+            ECHO_WAT.as_bytes(),
+            WasmLimits {
+                fuel: 10_000,
+                ..WasmLimits::default()
+            },
+        )
+        .unwrap();
+        assert!(matches!(
+            plugin.call("spin", &[]),
+            Err(WasmPluginError::Limit(_))
+        ));
+    }
+
+    #[test]
+    fn permissions_map_to_preopened_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+        let plugin = WasmPlugin::new(
+            manifest(&format!(
+                r#"{{ "echo": {{ "permissions": [
+                    {{ "type": "filesystem-read", "resource": [{path:?}] }},
+                    {{ "type": "filesystem-write", "resource": [{path:?}] }}
+                ] }} }}"#
+            )),
+            ECHO_WAT.as_bytes(),
+            WasmLimits::default(),
+        )
+        .unwrap();
+        let capabilities = &plugin.functions["echo"].capabilities;
+        assert_eq!(capabilities.read_dirs, vec![path.clone()]);
+        assert_eq!(capabilities.write_dirs, vec![path]);
+        assert!(plugin.call("echo", &[]).is_ok());
+    }
+
+    #[test]
+    fn permissions_without_wasi_equivalent_are_rejected() {
+        let network = Permission {
+            display_name: "".to_string(),
+            description: "".to_string(),
+            permission_type: PermissionType::NetAccess as i32,
+            permission_level: PermissionLevel::Unspecified as i32,
+            resource: vec!["https://example.com".to_string()],
+        };
+        assert!(matches!(
+            Capabilities::from_permissions(&[network]),
+            Err(WasmPluginError::Capability(_))
+        ));
+
+        let unscoped = Permission {
+            permission_type: PermissionType::FilesystemRead as i32,
+            resource: vec![],
+            display_name: "".to_string(),
+            description: "".to_string(),
+            permission_level: PermissionLevel::Unspecified as i32,
+        };
+        assert!(matches!(
+            Capabilities::from_permissions(&[unscoped]),
+            Err(WasmPluginError::Capability(_))
+        ));
+    }
+
+    #[test]
+    fn manifest_requires_meta_and_functions() {
+        assert!(WasmPluginManifest::parse("{}").is_err());
+        assert!(
+            WasmPluginManifest::parse(
+                r#"{ "meta": { "name": "x", "version": "1", "author_id": "a", "package_id": "a.x" },
+                     "module": "x.wasm", "functions": {} }"#
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,191 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Runs WASM plugin functions in wasmtime with WASI capabilities derived from permissions
+
+use sapphillon_core::proto::sapphillon::v1::{Permission, PermissionType};
+use serde_json::Value;
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+use crate::WasmPluginError;
+
+/// Fuel (roughly, WASM instructions) a single call may use.
+const DEFAULT_FUEL: u64 = 10_000_000_000;
+/// Linear memory a single call may grow to.
+const DEFAULT_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+
+/// Export a module must provide to receive the JSON arguments of a call.
+pub(crate) const ALLOC_EXPORT: &str = "sapphillon_alloc";
+
+/// Resource limits applied to every call.
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    pub fuel: u64,
+    pub memory_bytes: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: DEFAULT_FUEL,
+            memory_bytes: DEFAULT_MEMORY_BYTES,
+        }
+    }
+}
+
+/// Host access a call receives, derived from the permissions of its function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Directories preopened read-only.
+    pub read_dirs: Vec<String>,
+    /// Directories preopened for reading and writing.
+    pub write_dirs: Vec<String>,
+}
+
+impl Capabilities {
+    /// Maps permissions onto WASI capabilities.
+    ///
+    /// `FilesystemRead` and `FilesystemWrite` preopen the directories named in their
+    /// resources. Every other permission type has no WASI equivalent and is rejected,
+    /// as are filesystem permissions that do not name a directory.
+    pub fn from_permissions(permissions: &[Permission]) -> Result<Self, WasmPluginError> {
+        let mut capabilities = Self::default();
+        for permission in permissions {
+            let dirs = match PermissionType::try_from(permission.permission_type) {
+                Ok(PermissionType::FilesystemRead) => &mut capabilities.read_dirs,
+                Ok(PermissionType::FilesystemWrite) => &mut capabilities.write_dirs,
+                _ => {
+                    return Err(WasmPluginError::Capability(format!(
+                        "{} is not available to WASM plugins",
+                        plugin_permission::describe_permission(permission)
+                    )));
+                }
+            };
+            if permission.resource.is_empty() {
+                return Err(WasmPluginError::Capability(format!(
+                    "{} must name the directories it covers",
+                    plugin_permission::describe_permission(permission)
+                )));
+            }
+            for resource in &permission.resource {
+                if !dirs.contains(resource) {
+                    dirs.push(resource.clone());
+                }
+            }
+        }
+        Ok(capabilities)
+    }
+}
+
+struct HostState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// A compiled WASM module.
+pub struct WasmRuntime {
+    engine: Engine,
+    module: Module,
+    linker: Linker<HostState>,
+    limits: WasmLimits,
+}
+
+impl WasmRuntime {
+    /// Compiles a module from its binary or text format.
+    pub fn new(bytes: &[u8], limits: WasmLimits) -> Result<Self, WasmPluginError> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(compile_error)?;
+        let module = Module::new(&engine, bytes).map_err(compile_error)?;
+        let mut linker = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut HostState| &mut state.wasi)
+            .map_err(compile_error)?;
+        Ok(Self {
+            engine,
+            module,
+            linker,
+            limits,
+        })
+    }
+
+    /// Calls an exported function in a fresh instance.
+    ///
+    /// The arguments are written into the instance as a JSON array using
+    /// `sapphillon_alloc(len) -> ptr`; the function is called as `(ptr, len) -> i64`
+    /// and returns the location of its JSON result as `ptr << 32 | len`.
+    pub fn call(
+        &self,
+        function: &str,
+        args: &[Value],
+        capabilities: &Capabilities,
+    ) -> Result<Value, WasmPluginError> {
+        let mut wasi = WasiCtxBuilder::new();
+        wasi.inherit_stderr();
+        for dir in &capabilities.read_dirs {
+            wasi.preopened_dir(dir, dir, DirPerms::READ, FilePerms::READ)
+                .map_err(|e| WasmPluginError::Capability(format!("cannot open {dir}: {e}")))?;
+        }
+        for dir in &capabilities.write_dirs {
+            wasi.preopened_dir(dir, dir, DirPerms::all(), FilePerms::all())
+                .map_err(|e| WasmPluginError::Capability(format!("cannot open {dir}: {e}")))?;
+        }
+
+        let state = HostState {
+            wasi: wasi.build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.limits.memory_bytes)
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.limits.fuel).map_err(call_error)?;
+
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .map_err(call_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| WasmPluginError::Abi("module does not export its memory".to_string()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, ALLOC_EXPORT)
+            .map_err(|e| WasmPluginError::Abi(format!("{ALLOC_EXPORT}: {e}")))?;
+        let func = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, function)
+            .map_err(|e| WasmPluginError::Abi(format!("{function}: {e}")))?;
+
+        let input = serde_json::to_vec(args).map_err(|e| WasmPluginError::Abi(e.to_string()))?;
+        let input_len = i32::try_from(input.len())
+            .map_err(|_| WasmPluginError::Abi("arguments are too large".to_string()))?;
+        let input_ptr = alloc.call(&mut store, input_len).map_err(call_error)?;
+        memory
+            .write(&mut store, input_ptr as u32 as usize, &input)
+            .map_err(|e| WasmPluginError::Abi(e.to_string()))?;
+
+        let packed = func
+            .call(&mut store, (input_ptr, input_len))
+            .map_err(call_error)?;
+        let output_ptr = (packed as u64 >> 32) as usize;
+        let output_len = (packed as u64 & 0xffff_ffff) as usize;
+        let mut output = vec![0; output_len];
+        memory
+            .read(&store, output_ptr, &mut output)
+            .map_err(|e| WasmPluginError::Abi(format!("invalid result location: {e}")))?;
+        serde_json::from_slice(&output)
+            .map_err(|e| WasmPluginError::Abi(format!("result is not JSON: {e}")))
+    }
+}
+
+fn compile_error(err: anyhow::Error) -> WasmPluginError {
+    WasmPluginError::Compile(format!("{err:#}"))
+}
+
+fn call_error(err: anyhow::Error) -> WasmPluginError {
+    match err.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => WasmPluginError::Limit("the call ran out of fuel".to_string()),
+        _ => WasmPluginError::Trap(format!("{err:#}")),
+    }
+}
@@ -6,6 +6,29 @@ function getInactiveWindowTitles() {
     return Deno.core.ops.op2_get_inactive_window_titles();
 }
 
+function getActive() {
+    return Deno.core.ops.op2_window_get_active();
+}
+
+function list() {
+    return Deno.core.ops.op2_window_list();
+}
+
+function watchActive(options) {
+    return Deno.core.ops.op2_window_watch_active(options === undefined ? null : options);
+}
+
+function takeActiveChanges(watcher, timeoutMs) {
+    return Deno.core.ops.op2_window_take_active_changes(
+        watcher,
+        timeoutMs === undefined ? null : timeoutMs,
+    );
+}
+
+function stopWatch(watcher) {
+    return Deno.core.ops.op2_window_stop_watch(watcher);
+}
+
 globalThis.app = globalThis.app || {};
 globalThis.app.sapphillon = globalThis.app.sapphillon || {};
 globalThis.app.sapphillon.core = globalThis.app.sapphillon.core || {};
@@ -13,3 +36,8 @@ globalThis.app.sapphillon.core.window = globalThis.app.sapphillon.core.window ||
 
 globalThis.app.sapphillon.core.window.getActiveWindowTitle = getActiveWindowTitle;
 globalThis.app.sapphillon.core.window.getInactiveWindowTitles = getInactiveWindowTitles;
+globalThis.app.sapphillon.core.window.getActive = getActive;
+globalThis.app.sapphillon.core.window.list = list;
+globalThis.app.sapphillon.core.window.watchActive = watchActive;
+globalThis.app.sapphillon.core.window.takeActiveChanges = takeActiveChanges;
+globalThis.app.sapphillon.core.window.stopWatch = stopWatch;
@@ -2,17 +2,25 @@
 // SPDX-FileCopyrightText: 2025 Yuta Takahashi
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
+mod watch;
+
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
-use sapphillon_core::permission::{CheckPermissionResult, Permissions, check_permission};
+use plugin_permission::ensure_permission;
 use sapphillon_core::plugin::{CorePluginFunction, CorePluginPackage};
 use sapphillon_core::proto::sapphillon::v1::{
     FunctionDefine, FunctionParameter, Permission, PermissionLevel, PermissionType, PluginFunction,
     PluginPackage,
 };
-use sapphillon_core::runtime::OpStateWorkflowData;
-use std::sync::{Arc, Mutex};
-use x_win::{get_active_window, get_open_windows};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use watch::{ActiveWindowWatcher, ActiveWindowWatchers, FocusChange};
+use x_win::{WindowInfo, get_active_window, get_open_windows};
+
+/// Permission resource for reading the active window.
+pub const ACTIVE_WINDOW_RESOURCE: &str = "window://active";
+/// Permission resource for reading every open window.
+pub const ALL_WINDOWS_RESOURCE: &str = "window://all";
 
 pub fn get_active_window_title_plugin_function() -> PluginFunction {
     PluginFunction {
@@ -20,7 +28,7 @@ pub fn get_active_window_title_plugin_function() -> PluginFunction {
         function_name: "Get Active Window Title".to_string(),
         version: "".to_string(),
         description: "Gets the title of the currently active window.".to_string(),
-        permissions: window_plugin_permissions(),
+        permissions: active_window_permissions(),
         function_define: Some(FunctionDefine {
             parameters: vec![],
             returns: vec![FunctionParameter {
@@ -38,7 +46,7 @@ pub fn get_inactive_window_titles_plugin_function() -> PluginFunction {
         function_name: "Get Inactive Window Titles".to_string(),
         version: "".to_string(),
         description: "Gets the titles of all inactive windows.".to_string(),
-        permissions: window_plugin_permissions(),
+        permissions: all_windows_permissions(),
         function_define: Some(FunctionDefine {
             parameters: vec![],
             returns: vec![FunctionParameter {
@@ -50,6 +58,123 @@ pub fn get_inactive_window_titles_plugin_function() -> PluginFunction {
     }
 }
 
+/// Type of the window objects returned by `getActive` and `list`.
+const WINDOW_DETAILS_TYPE: &str = "{ id: number, title: string, appName: string, pid: number, position: { x: number, y: number }, size: { width: number, height: number }, isFullScreen: boolean }";
+
+pub fn get_active_window_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.window.getActive".to_string(),
+        function_name: "window.getActive".to_string(),
+        version: "".to_string(),
+        description: "Gets the currently active window with its application, process and geometry."
+            .to_string(),
+        permissions: active_window_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "window".to_string(),
+                r#type: WINDOW_DETAILS_TYPE.to_string(),
+                description: "Active window; positions and sizes in pixels".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn list_windows_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.window.list".to_string(),
+        function_name: "window.list".to_string(),
+        version: "".to_string(),
+        description: "Lists the open windows with their application, process and geometry."
+            .to_string(),
+        permissions: all_windows_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![],
+            returns: vec![FunctionParameter {
+                name: "windows".to_string(),
+                r#type: format!("{WINDOW_DETAILS_TYPE}[]"),
+                description: "Open windows; positions and sizes in pixels".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn watch_active_window_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.window.watchActive".to_string(),
+        function_name: "window.watchActive".to_string(),
+        version: "".to_string(),
+        description: "Starts recording active-window changes in the background and returns a watcher handle. The watcher stops when the workflow ends or with window.stopWatch."
+            .to_string(),
+        permissions: active_window_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "options".to_string(),
+                r#type: "{ intervalMs?: number } | undefined".to_string(),
+                description: format!(
+                    "How often the active window is checked; defaults to {} ms, at least {} ms",
+                    watch::DEFAULT_INTERVAL_MS,
+                    watch::MIN_INTERVAL_MS
+                ),
+            }],
+            returns: vec![FunctionParameter {
+                name: "watcher".to_string(),
+                r#type: "number".to_string(),
+                description: "Watcher handle".to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn take_active_changes_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.window.takeActiveChanges".to_string(),
+        function_name: "window.takeActiveChanges".to_string(),
+        version: "".to_string(),
+        description: "Returns the active-window changes recorded since the last call, waiting up to timeoutMs for one when none are recorded."
+            .to_string(),
+        permissions: active_window_permissions(),
+        function_define: Some(FunctionDefine {
+            parameters: vec![
+                FunctionParameter {
+                    name: "watcher".to_string(),
+                    r#type: "number".to_string(),
+                    description: "Handle returned by window.watchActive".to_string(),
+                },
+                FunctionParameter {
+                    name: "timeoutMs".to_string(),
+                    r#type: "number | undefined".to_string(),
+                    description: "Longest time to wait for a change; defaults to 0".to_string(),
+                },
+            ],
+            returns: vec![FunctionParameter {
+                name: "changes".to_string(),
+                r#type: format!("({WINDOW_DETAILS_TYPE} & {{ at: number }})[]"),
+                description: "Windows in the order they became active; at is milliseconds since the Unix epoch"
+                    .to_string(),
+            }],
+        }),
+    }
+}
+
+pub fn stop_watch_plugin_function() -> PluginFunction {
+    PluginFunction {
+        function_id: "app.sapphillon.core.window.stopWatch".to_string(),
+        function_name: "window.stopWatch".to_string(),
+        version: "".to_string(),
+        description: "Stops an active-window watcher.".to_string(),
+        permissions: vec![],
+        function_define: Some(FunctionDefine {
+            parameters: vec![FunctionParameter {
+                name: "watcher".to_string(),
+                r#type: "number".to_string(),
+                description: "Handle returned by window.watchActive".to_string(),
+            }],
+            returns: vec![],
+        }),
+    }
+}
+
 pub fn window_plugin_package() -> PluginPackage {
     PluginPackage {
         package_id: "app.sapphillon.core.window".to_string(),
@@ -59,6 +184,11 @@ pub fn window_plugin_package() -> PluginPackage {
         functions: vec![
             get_active_window_title_plugin_function(),
             get_inactive_window_titles_plugin_function(),
+            get_active_window_plugin_function(),
+            list_windows_plugin_function(),
+            watch_active_window_plugin_function(),
+            take_active_changes_plugin_function(),
+            stop_watch_plugin_function(),
         ],
         package_version: env!("CARGO_PKG_VERSION").to_string(),
         deprecated: None,
@@ -90,6 +220,56 @@ pub fn core_get_inactive_window_titles_plugin() -> CorePluginFunction {
     )
 }
 
+pub fn core_get_active_window_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        get_active_window_plugin_function().function_id,
+        "GetActive".to_string(),
+        get_active_window_plugin_function().description,
+        op2_window_get_active(),
+        Some(include_str!("00_window.js").to_string()),
+    )
+}
+
+pub fn core_list_windows_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        list_windows_plugin_function().function_id,
+        "List".to_string(),
+        list_windows_plugin_function().description,
+        op2_window_list(),
+        Some(include_str!("00_window.js").to_string()),
+    )
+}
+
+pub fn core_watch_active_window_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        watch_active_window_plugin_function().function_id,
+        "WatchActive".to_string(),
+        watch_active_window_plugin_function().description,
+        op2_window_watch_active(),
+        Some(include_str!("00_window.js").to_string()),
+    )
+}
+
+pub fn core_take_active_changes_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        take_active_changes_plugin_function().function_id,
+        "TakeActiveChanges".to_string(),
+        take_active_changes_plugin_function().description,
+        op2_window_take_active_changes(),
+        Some(include_str!("00_window.js").to_string()),
+    )
+}
+
+pub fn core_stop_watch_plugin() -> CorePluginFunction {
+    CorePluginFunction::new(
+        stop_watch_plugin_function().function_id,
+        "StopWatch".to_string(),
+        stop_watch_plugin_function().description,
+        op2_window_stop_watch(),
+        Some(include_str!("00_window.js").to_string()),
+    )
+}
+
 pub fn core_window_plugin_package() -> CorePluginPackage {
     CorePluginPackage::new(
         "app.sapphillon.core.window".to_string(),
@@ -97,17 +277,30 @@ pub fn core_window_plugin_package() -> CorePluginPackage {
         vec![
             core_get_active_window_title_plugin(),
             core_get_inactive_window_titles_plugin(),
+            core_get_active_window_plugin(),
+            core_list_windows_plugin(),
+            core_watch_active_window_plugin(),
+            core_take_active_changes_plugin(),
+            core_stop_watch_plugin(),
         ],
     )
 }
 
+/// Returns the title of the active window, or `None` when it cannot be determined.
+///
+/// Unlike the plugin function this does not check workflow permissions; it is meant
+/// for the server itself, e.g. to watch for window changes.
+pub fn active_window_title() -> Option<String> {
+    get_active_window().ok().map(|window| window.title)
+}
+
 #[op2]
 #[string]
 fn op2_get_active_window_title(state: &mut OpState) -> Result<String, JsErrorBox> {
     ensure_permission(
         state,
         &get_active_window_title_plugin_function().function_id,
-        window_plugin_permissions(),
+        active_window_permissions(),
         "",
     )?;
     match get_active_window() {
@@ -125,7 +318,7 @@ fn op2_get_inactive_window_titles(state: &mut OpState) -> Result<Vec<String>, Js
     ensure_permission(
         state,
         &get_inactive_window_titles_plugin_function().function_id,
-        window_plugin_permissions(),
+        all_windows_permissions(),
         "",
     )?;
     match get_open_windows() {
@@ -151,52 +344,163 @@ fn op2_get_inactive_window_titles(state: &mut OpState) -> Result<Vec<String>, Js
     }
 }
 
-fn ensure_permission(
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowDetails {
+    id: u32,
+    title: String,
+    app_name: String,
+    pid: u32,
+    position: WindowPoint,
+    size: WindowSize,
+    is_full_screen: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct WindowPoint {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct WindowSize {
+    width: i32,
+    height: i32,
+}
+
+impl From<WindowInfo> for WindowDetails {
+    fn from(window: WindowInfo) -> Self {
+        Self {
+            id: window.id,
+            title: window.title,
+            app_name: window.info.name,
+            pid: window.info.process_id,
+            position: WindowPoint {
+                x: window.position.x,
+                y: window.position.y,
+            },
+            size: WindowSize {
+                width: window.position.width,
+                height: window.position.height,
+            },
+            is_full_screen: window.position.is_full_screen,
+        }
+    }
+}
+
+#[op2]
+#[serde]
+fn op2_window_get_active(state: &mut OpState) -> Result<WindowDetails, JsErrorBox> {
+    ensure_permission(
+        state,
+        &get_active_window_plugin_function().function_id,
+        active_window_permissions(),
+        "",
+    )?;
+    get_active_window()
+        .map(WindowDetails::from)
+        .map_err(|_| JsErrorBox::new("Error", "Could not get the active window".to_string()))
+}
+
+#[op2]
+#[serde]
+fn op2_window_list(state: &mut OpState) -> Result<Vec<WindowDetails>, JsErrorBox> {
+    ensure_permission(
+        state,
+        &list_windows_plugin_function().function_id,
+        all_windows_permissions(),
+        "",
+    )?;
+    get_open_windows()
+        .map(|windows| windows.into_iter().map(WindowDetails::from).collect())
+        .map_err(|_| JsErrorBox::new("Error", "Could not list open windows".to_string()))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchOptions {
+    interval_ms: Option<u64>,
+}
+
+#[op2]
+#[smi]
+fn op2_window_watch_active(
     state: &mut OpState,
-    plugin_function_id: &str,
-    required_permissions: Vec<Permission>,
-    resource: &str,
-) -> Result<(), JsErrorBox> {
-    let data = state
-        .borrow::<Arc<Mutex<OpStateWorkflowData>>>()
-        .lock()
-        .unwrap();
-    let allowed = data.get_allowed_permissions().clone().unwrap_or_default();
-
-    let required_permissions = Permissions::new(
-        required_permissions
-            .into_iter()
-            .map(|mut p| {
-                if !resource.is_empty() && p.resource.is_empty() {
-                    p.resource = vec![resource.to_string()];
-                }
-                p
-            })
-            .collect(),
-    );
-
-    let allowed_permissions = allowed
-        .into_iter()
-        .find(|p| p.plugin_function_id == plugin_function_id || p.plugin_function_id == "*")
-        .map(|p| p.permissions)
-        .unwrap_or_else(|| Permissions::new(vec![]));
-
-    match check_permission(&allowed_permissions, &required_permissions) {
-        CheckPermissionResult::Ok => Ok(()),
-        CheckPermissionResult::MissingPermission(perm) => Err(JsErrorBox::new(
-            "PermissionDenied. Missing Permissions:",
-            perm.to_string(),
-        )),
+    #[serde] options: Option<WatchOptions>,
+) -> Result<u32, JsErrorBox> {
+    ensure_permission(
+        state,
+        &watch_active_window_plugin_function().function_id,
+        active_window_permissions(),
+        "",
+    )?;
+    let interval_ms = options
+        .unwrap_or_default()
+        .interval_ms
+        .unwrap_or(watch::DEFAULT_INTERVAL_MS)
+        .max(watch::MIN_INTERVAL_MS);
+    let watcher = ActiveWindowWatcher::start(Duration::from_millis(interval_ms), || {
+        get_active_window().ok().map(WindowDetails::from)
+    });
+
+    if !state.has::<ActiveWindowWatchers>() {
+        state.put(ActiveWindowWatchers::default());
     }
+    Ok(state.borrow_mut::<ActiveWindowWatchers>().insert(watcher))
 }
 
-fn window_plugin_permissions() -> Vec<Permission> {
+#[op2]
+#[serde]
+fn op2_window_take_active_changes(
+    state: &mut OpState,
+    #[smi] handle: u32,
+    #[serde] timeout_ms: Option<u64>,
+) -> Result<Vec<FocusChange>, JsErrorBox> {
+    ensure_permission(
+        state,
+        &take_active_changes_plugin_function().function_id,
+        active_window_permissions(),
+        "",
+    )?;
+    let watcher = state
+        .try_borrow::<ActiveWindowWatchers>()
+        .and_then(|watchers| watchers.watchers.get(&handle))
+        .ok_or_else(|| unknown_watcher_error(handle))?;
+    Ok(watcher.take_changes(Duration::from_millis(timeout_ms.unwrap_or(0))))
+}
+
+#[op2]
+fn op2_window_stop_watch(state: &mut OpState, #[smi] handle: u32) -> Result<(), JsErrorBox> {
+    let removed = state
+        .try_borrow_mut::<ActiveWindowWatchers>()
+        .and_then(|watchers| watchers.watchers.remove(&handle));
+    match removed {
+        Some(_) => Ok(()),
+        None => Err(unknown_watcher_error(handle)),
+    }
+}
+
+fn unknown_watcher_error(handle: u32) -> JsErrorBox {
+    JsErrorBox::new("Error", format!("unknown window watcher {handle}"))
+}
+
+fn active_window_permissions() -> Vec<Permission> {
     vec![Permission {
-        display_name: "Window Access".to_string(),
-        description: "Allows the plugin to access window information.".to_string(),
+        display_name: "Active Window Access".to_string(),
+        description: "Allows the plugin to read information about the active window.".to_string(),
         permission_type: PermissionType::Execute as i32,
         permission_level: PermissionLevel::Unspecified as i32,
-        resource: vec![],
+        resource: vec![ACTIVE_WINDOW_RESOURCE.to_string()],
+    }]
+}
+
+fn all_windows_permissions() -> Vec<Permission> {
+    vec![Permission {
+        display_name: "Window List Access".to_string(),
+        description: "Allows the plugin to read information about every open window.".to_string(),
+        permission_type: PermissionType::Execute as i32,
+        permission_level: PermissionLevel::Unspecified as i32,
+        resource: vec![ALL_WINDOWS_RESOURCE.to_string()],
     }]
 }
 
@@ -205,6 +509,7 @@ mod tests {
     use super::*;
     use sapphillon_core::permission::PluginFunctionPermissions;
     use sapphillon_core::workflow::CoreWorkflowCode;
+    use std::sync::Arc;
 
     #[tokio::test]
     #[allow(clippy::arc_with_non_send_sync)]
@@ -217,7 +522,7 @@ mod tests {
         let perm = PluginFunctionPermissions {
             plugin_function_id: get_active_window_title_plugin_function().function_id,
             permissions: sapphillon_core::permission::Permissions {
-                permissions: window_plugin_permissions(),
+                permissions: active_window_permissions(),
             },
         };
 
@@ -249,7 +554,7 @@ mod tests {
         let perm = PluginFunctionPermissions {
             plugin_function_id: get_inactive_window_titles_plugin_function().function_id,
             permissions: sapphillon_core::permission::Permissions {
-                permissions: window_plugin_permissions(),
+                permissions: all_windows_permissions(),
             },
         };
 
@@ -275,4 +580,80 @@ mod tests {
             "Expected JSON array or error message, got: {result}"
         );
     }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_list_windows_in_workflow() {
+        let code = r#"
+            const windows = app.sapphillon.core.window.list();
+            console.log(JSON.stringify(windows.map((w) => Object.keys(w).sort())));
+        "#;
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: list_windows_plugin_function().function_id,
+            permissions: sapphillon_core::permission::Permissions {
+                permissions: all_windows_permissions(),
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_window_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        // Headless environments have no windows to list and may report an error instead.
+        let result = &workflow.result[0].result;
+        let expected_keys = r#"["appName","id","isFullScreen","pid","position","size","title"]"#;
+        let is_error = result.contains("Error") || result.contains("error");
+        assert!(
+            result.trim_end() == "[]" || result.contains(expected_keys) || is_error,
+            "Expected window objects or an error message, got: {result}"
+        );
+    }
+
+    #[tokio::test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    async fn test_active_window_permission_does_not_cover_all_windows() {
+        let code = r#"
+            try {
+                app.sapphillon.core.window.getInactiveWindowTitles();
+                console.log("allowed");
+            } catch (e) {
+                console.log(String(e));
+            }
+        "#;
+
+        let perm = PluginFunctionPermissions {
+            plugin_function_id: get_inactive_window_titles_plugin_function().function_id,
+            permissions: sapphillon_core::permission::Permissions {
+                permissions: active_window_permissions(),
+            },
+        };
+
+        let workflow_permissions = vec![perm];
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            code.to_string(),
+            vec![Arc::new(core_window_plugin_package())],
+            1,
+            workflow_permissions.clone(),
+            workflow_permissions,
+        );
+
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result.len(), 1);
+        // Headless environments fail to read windows, which is not the error expected here.
+        let result = &workflow.result[0].result;
+        assert!(
+            !result.contains("allowed") && !result.contains("Could not get"),
+            "Expected a permission error, got: {result}"
+        );
+    }
 }
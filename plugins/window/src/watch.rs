@@ -0,0 +1,163 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Background watcher that queues active-window changes for a workflow
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::WindowDetails;
+
+/// Poll interval used when a workflow does not pass one.
+pub(crate) const DEFAULT_INTERVAL_MS: u64 = 500;
+/// Shortest poll interval a workflow may ask for.
+pub(crate) const MIN_INTERVAL_MS: u64 = 50;
+/// Events kept per watcher; the oldest are dropped when a workflow stops reading.
+const MAX_QUEUED_CHANGES: usize = 10_000;
+
+/// The active window at the moment it became active.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FocusChange {
+    #[serde(flatten)]
+    pub window: WindowDetails,
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+}
+
+#[derive(Default)]
+struct Queue {
+    changes: Mutex<VecDeque<FocusChange>>,
+    ready: Condvar,
+}
+
+/// Polls the active window on a background thread until it is dropped.
+pub(crate) struct ActiveWindowWatcher {
+    queue: Arc<Queue>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ActiveWindowWatcher {
+    /// Starts polling `active_window` every `interval`. The window active when the
+    /// watcher starts is reported as the first change.
+    pub(crate) fn start<F>(interval: Duration, mut active_window: F) -> Self
+    where
+        F: FnMut() -> Option<WindowDetails> + Send + 'static,
+    {
+        let queue = Arc::new(Queue::default());
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (thread_queue, thread_stopped) = (queue.clone(), stopped.clone());
+        thread::spawn(move || {
+            let mut last: Option<(u32, String)> = None;
+            while !thread_stopped.load(Ordering::Relaxed) {
+                if let Some(window) = active_window() {
+                    let key = (window.id, window.title.clone());
+                    if last.as_ref() != Some(&key) {
+                        last = Some(key);
+                        let mut changes = thread_queue.changes.lock().unwrap();
+                        if changes.len() == MAX_QUEUED_CHANGES {
+                            changes.pop_front();
+                        }
+                        changes.push_back(FocusChange {
+                            window,
+                            at: now_millis(),
+                        });
+                        thread_queue.ready.notify_all();
+                    }
+                }
+                thread::sleep(interval);
+            }
+        });
+        Self { queue, stopped }
+    }
+
+    /// Takes the queued changes, waiting up to `timeout` for one when none are queued.
+    pub(crate) fn take_changes(&self, timeout: Duration) -> Vec<FocusChange> {
+        let changes = self.queue.changes.lock().unwrap();
+        let (mut changes, _) = self
+            .queue
+            .ready
+            .wait_timeout_while(changes, timeout, |changes| changes.is_empty())
+            .unwrap();
+        changes.drain(..).collect()
+    }
+}
+
+impl Drop for ActiveWindowWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Watchers started by a single workflow run, keyed by the handle returned to
+/// JavaScript. They stop when the run's `OpState` is dropped.
+#[derive(Default)]
+pub(crate) struct ActiveWindowWatchers {
+    next_handle: u32,
+    pub watchers: HashMap<u32, ActiveWindowWatcher>,
+}
+
+impl ActiveWindowWatchers {
+    pub(crate) fn insert(&mut self, watcher: ActiveWindowWatcher) -> u32 {
+        self.next_handle += 1;
+        self.watchers.insert(self.next_handle, watcher);
+        self.next_handle
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WindowPoint, WindowSize};
+
+    fn window(id: u32, title: &str) -> WindowDetails {
+        WindowDetails {
+            id,
+            title: title.to_string(),
+            app_name: "App".to_string(),
+            pid: 1,
+            position: WindowPoint { x: 0, y: 0 },
+            size: WindowSize {
+                width: 800,
+                height: 600,
+            },
+            is_full_screen: false,
+        }
+    }
+
+    #[test]
+    fn test_watcher_queues_only_changes() {
+        let mut polls = vec![
+            Some(window(1, "Editor")),
+            Some(window(1, "Editor")),
+            None,
+            Some(window(2, "Mail")),
+            Some(window(2, "Mail - Inbox")),
+        ]
+        .into_iter();
+        let watcher =
+            ActiveWindowWatcher::start(Duration::from_millis(1), move || polls.next().flatten());
+
+        let mut titles = Vec::new();
+        while titles.len() < 3 {
+            let changes = watcher.take_changes(Duration::from_secs(5));
+            assert!(!changes.is_empty(), "timed out waiting for changes");
+            titles.extend(changes.into_iter().map(|change| change.window.title));
+        }
+        assert_eq!(titles, vec!["Editor", "Mail", "Mail - Inbox"]);
+        assert!(watcher.take_changes(Duration::from_millis(20)).is_empty());
+    }
+}
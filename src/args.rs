@@ -12,18 +12,209 @@ pub struct Args {
     #[arg(long, value_enum, default_value_t = LogLevel::Info)]
     pub loglevel: LogLevel,
 
-    /// SQLite Database URL
+    /// Database URL: sqlite:, postgres:// or mysql://
     #[arg(long, default_value_t = String::from("sqlite:file::memory:?mode=memory&cache=shared"))]
     pub db_url: String,
 
+    /// Maximum open connections per database pool. If not set, the driver default is used.
+    #[arg(long)]
+    pub db_max_connections: Option<u32>,
+
+    /// Connections each database pool keeps open while idle
+    #[arg(long)]
+    pub db_min_connections: Option<u32>,
+
+    /// Seconds to wait for a database connection to open or become free in the pool
+    #[arg(long)]
+    pub db_connect_timeout_secs: Option<u64>,
+
+    /// Seconds an unused database connection stays open before it is closed
+    #[arg(long)]
+    pub db_idle_timeout_secs: Option<u64>,
+
+    /// Seconds a SQLite statement waits for another connection's write before failing with
+    /// "database is locked". If not set, 30 seconds.
+    #[arg(long)]
+    pub db_busy_timeout_secs: Option<u64>,
+
+    /// File of keys that encrypt sensitive database columns, one `<id> <base64 key>` per
+    /// line; the first key encrypts new values. If not set, those columns are stored in plaintext.
+    #[arg(long, conflicts_with = "encryption_key_from_keyring")]
+    pub encryption_key_file: Option<String>,
+
+    /// Read the column encryption keys from the `database.encryption-keys` secret instead of a file
+    #[arg(long)]
+    pub encryption_key_from_keyring: bool,
+
     /// Directory to save external plugin files. If not set, uses system temp directory.
     #[arg(long)]
     pub ext_plugin_save_dir: Option<String>,
 
+    /// Authentication provider for the gRPC API
+    #[arg(long, value_enum, default_value_t = AuthMode::None)]
+    pub auth: AuthMode,
+
+    /// Bearer token accepted by the `token` authentication provider with every scope,
+    /// in addition to the tokens created with `tokens create`
+    #[arg(long)]
+    pub auth_token: Option<String>,
+
+    /// OIDC issuer URL used by the `oidc` authentication provider
+    #[arg(long)]
+    pub oidc_issuer: Option<String>,
+
+    /// Expected token audience used by the `oidc` authentication provider
+    #[arg(long)]
+    pub oidc_audience: Option<String>,
+
+    /// Claim of OIDC tokens that lists the caller's scopes, e.g. scope. If not set, OIDC
+    /// callers get the --auth-default-scope scopes
+    #[arg(long)]
+    pub oidc_scopes_claim: Option<String>,
+
+    /// Scope of callers authenticated by the `peer-cred` provider, and by the `oidc`
+    /// provider without --oidc-scopes-claim; repeat for several
+    #[arg(
+        long = "auth-default-scope",
+        value_enum,
+        default_values_t = crate::auth::DEFAULT_USER_SCOPES
+    )]
+    pub auth_default_scopes: Vec<TokenScope>,
+
+    /// Address the gRPC server listens on: `ip:port`, or `unix:/path/sapphillon.sock` for a
+    /// Unix domain socket
+    #[arg(long, default_value_t = String::from("0.0.0.0:50051"))]
+    pub listen: String,
+
+    /// Octal permissions of the Unix domain socket created for `--listen unix:...`
+    #[arg(long, default_value_t = String::from("600"))]
+    pub listen_socket_mode: String,
+
+    /// Address of an additional HTTP/1.1 listener serving the gRPC API as gRPC-Web to
+    /// browser frontends, e.g. 127.0.0.1:50053
+    #[arg(long)]
+    pub grpc_web_listen: Option<String>,
+
+    /// Origin allowed to call the gRPC-Web listener, e.g. chrome://noraneko; may be given
+    /// more than once. If not set, every origin is allowed.
+    #[arg(long, requires = "grpc_web_listen")]
+    pub grpc_web_origin: Vec<String>,
+
+    /// PEM certificate chain the gRPC server presents; enables TLS together with --tls-key
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<String>,
+
+    /// PEM private key of --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<String>,
+
+    /// PEM CA certificates that issue client certificates; when set, clients must present
+    /// a certificate signed by one of them (mutual TLS)
+    #[arg(long, requires = "tls_cert")]
+    pub client_ca: Option<String>,
+
+    /// Maximum number of workflows that may run at the same time
+    #[arg(long, default_value_t = crate::workflow_pool::DEFAULT_MAX_CONCURRENT_WORKFLOWS)]
+    pub max_concurrent_workflows: usize,
+
+    /// GenerateWorkflow and FixWorkflow calls per minute each client may make; 0 disables
+    /// the limit
+    #[arg(long, default_value_t = crate::rate_limit::DEFAULT_GENERATE_PER_MINUTE)]
+    pub generate_rate_limit: u32,
+
+    /// GenerateWorkflow and FixWorkflow calls each client may have in progress; 0 disables
+    /// the cap
+    #[arg(long, default_value_t = crate::rate_limit::DEFAULT_GENERATE_MAX_CONCURRENT)]
+    pub generate_max_concurrent: usize,
+
+    /// RunWorkflow calls per minute each client may make; 0 disables the limit
+    #[arg(long, default_value_t = crate::rate_limit::DEFAULT_RUN_PER_MINUTE)]
+    pub run_rate_limit: u32,
+
+    /// RunWorkflow calls each client may have in progress; 0 disables the cap
+    #[arg(long, default_value_t = crate::rate_limit::DEFAULT_RUN_MAX_CONCURRENT)]
+    pub run_max_concurrent: usize,
+
+    /// Address of the HTTP listener serving webhook triggers at /hooks/{token}, e.g.
+    /// 127.0.0.1:50052; not opened when omitted
+    #[arg(long)]
+    pub webhook_addr: Option<String>,
+
+    /// Ask with a prompt instead of failing when a plugin call lacks a permission;
+    /// granted permissions are saved to the workflow code
+    #[arg(long)]
+    pub prompt_for_permissions: bool,
+
+    /// Also mask matches of this regular expression in workflow results and logs;
+    /// may be given more than once
+    #[arg(long)]
+    pub redact_pattern: Vec<String>,
+
+    /// Base URL of the plugin store registry used to search and install plugins
+    #[arg(long)]
+    pub plugin_store_url: Option<String>,
+
+    /// Maximum V8 heap of an external plugin server in MiB; 0 disables the limit
+    #[arg(long, default_value_t = crate::ext_sandbox::DEFAULT_MAX_HEAP_MB)]
+    pub ext_plugin_max_heap_mb: u64,
+
+    /// Seconds an external plugin server may run before it is stopped; 0 disables the limit
+    #[arg(long, default_value_t = crate::ext_sandbox::DEFAULT_TIMEOUT_SECS)]
+    pub ext_plugin_timeout_secs: u64,
+
+    /// Directory the file search keeps an index of when no OS search service is
+    /// available; may be given more than once
+    #[arg(long)]
+    pub search_index_root: Vec<String>,
+
+    /// SQLite file holding the file search index. If not set, uses system temp directory.
+    #[arg(long)]
+    pub search_index_path: Option<String>,
+
+    /// Model used to generate workflows when a request does not name one with the
+    /// x-sapphillon-model metadata, e.g. models/gpt-4o. If not set, the OPENAI_API_BASE,
+    /// OPENAI_API_KEY and OPENAI_MODEL environment variables are used.
+    #[arg(long)]
+    pub generation_model: Option<String>,
+
+    /// Times generated workflow code that fails to compile or calls unknown plugin
+    /// functions is sent back to the model for repair (0 disables repair)
+    #[arg(long, default_value_t = crate::workflow::DEFAULT_MAX_REPAIR_ATTEMPTS)]
+    pub generation_repair_attempts: usize,
+
+    /// Only generate with providers on this machine, e.g. a local Ollama, so prompts are
+    /// never sent to a remote API. Requests for remote models are rejected.
+    #[arg(long)]
+    pub offline_generation: bool,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMode {
+    /// Accept every request (local, single-user use only)
+    None,
+    /// Require a bearer token from --auth-token or `tokens create`
+    Token,
+    /// Require the Unix socket peer to run as the server's user
+    PeerCred,
+    /// Validate OIDC bearer tokens issued by --oidc-issuer
+    Oidc,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenScope {
+    /// Get and list workflows, plugins, providers and models
+    Read,
+    /// Create, change and delete workflows
+    Write,
+    /// Run workflows
+    Run,
+    /// Manage plugins, providers and models
+    Admin,
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum LogLevel {
     Trace,
@@ -102,11 +293,22 @@ pub enum Command {
     /// Start the gRPC server
     Start,
 
+    #[command(flatten)]
+    Cli(crate::cli::CliCommand),
+
     #[command(hide = true)]
     /// Run the External Plugin Server
     Ext {
         /// Name of the external plugin server to register.
         #[arg(value_name = "SERVER_NAME")]
         server_name: String,
+
+        /// Maximum V8 heap in MiB; 0 disables the limit.
+        #[arg(long, default_value_t = crate::ext_sandbox::DEFAULT_MAX_HEAP_MB)]
+        max_heap_mb: u64,
+
+        /// Seconds the server may run before it is stopped; 0 disables the limit.
+        #[arg(long, default_value_t = crate::ext_sandbox::DEFAULT_TIMEOUT_SECS)]
+        timeout_secs: u64,
     },
 }
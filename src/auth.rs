@@ -0,0 +1,994 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Authentication providers for the gRPC API
+
+use crate::args::{Args, AuthMode, TokenScope};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use database::api_token::{list_api_tokens, token_scopes};
+use entity::entity::api_token;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tonic::{Request, Status};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Subject of callers admitted without authentication.
+pub const ANONYMOUS_SUBJECT: &str = "anonymous";
+
+/// Scopes of callers authenticated by a user account rather than a token, unless other
+/// scopes are configured: they may look at and run workflows, but not change anything.
+pub const DEFAULT_USER_SCOPES: [TokenScope; 2] = [TokenScope::Read, TokenScope::Run];
+
+/// How often the token provider picks up tokens created or revoked in the database.
+pub const TOKEN_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the OIDC provider refetches the issuer's signing keys, so rotated keys are
+/// picked up without a restart.
+pub const OIDC_KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The shortest time between refetches caused by tokens signed with an unknown key, so
+/// such tokens cannot make the server hammer the issuer.
+const OIDC_KEYS_REFETCH_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Identity attached to every authenticated request as a request extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthIdentity {
+    /// Provider-specific subject (token holder, Unix uid, or OIDC `sub` claim).
+    pub subject: String,
+    /// What the caller may do: every scope without authentication or with `--auth-token`,
+    /// the scopes of a token created with `tokens create`, and otherwise the scopes
+    /// configured for the provider.
+    pub scopes: Vec<TokenScope>,
+}
+
+impl AuthIdentity {
+    /// An identity that may do everything.
+    pub fn with_all_scopes(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            scopes: TokenScope::value_variants().to_vec(),
+        }
+    }
+
+    /// Whether the caller may do what `scope` allows.
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    /// The user whose data the caller works with, or `None` for an anonymous caller.
+    ///
+    /// Without authentication there is a single user, so anonymous callers see every
+    /// workflow and the ones they create are shared.
+    pub fn owner_id(&self) -> Option<&str> {
+        (self.subject != ANONYMOUS_SUBJECT).then_some(self.subject.as_str())
+    }
+}
+
+/// Returns the owner a request acts for; see [`AuthIdentity::owner_id`].
+///
+/// Requests that did not pass the [`auth_interceptor`] have no owner.
+pub fn request_owner<T>(request: &Request<T>) -> Option<String> {
+    request
+        .extensions()
+        .get::<AuthIdentity>()
+        .and_then(AuthIdentity::owner_id)
+        .map(str::to_string)
+}
+
+/// Whether a request may change shared rows, which only admins may do; see
+/// [`database::user::is_changeable_by`].
+pub fn request_may_change_shared<T>(request: &Request<T>) -> bool {
+    request
+        .extensions()
+        .get::<AuthIdentity>()
+        .is_some_and(|identity| identity.has_scope(TokenScope::Admin))
+}
+
+/// Wraps `message` as a request from the command line, which may do everything and,
+/// like an anonymous caller, sees every row.
+pub fn local_request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .extensions_mut()
+        .insert(AuthIdentity::with_all_scopes(ANONYMOUS_SUBJECT));
+    request
+}
+
+/// Rejects the request unless its caller was granted `scope`.
+///
+/// Every RPC calls this with the scope it needs. Requests that did not pass the
+/// [`auth_interceptor`] have no identity and are rejected; calls made from within the
+/// server use [`local_request`].
+pub fn require_scope<T>(request: &Request<T>, scope: TokenScope) -> Result<(), Status> {
+    match request.extensions().get::<AuthIdentity>() {
+        Some(identity) if identity.has_scope(scope) => Ok(()),
+        Some(_) => Err(Status::permission_denied(format!(
+            "the caller lacks the '{}' scope",
+            scope_name(scope)
+        ))),
+        None => Err(Status::unauthenticated("the request was not authenticated")),
+    }
+}
+
+/// Name of a scope as written on the command line and stored in the database.
+pub fn scope_name(scope: TokenScope) -> &'static str {
+    match scope {
+        TokenScope::Read => "read",
+        TokenScope::Write => "write",
+        TokenScope::Run => "run",
+        TokenScope::Admin => "admin",
+    }
+}
+
+/// Decides whether an incoming gRPC request may reach the services.
+///
+/// Implementations must be cheap and synchronous because they run inside a tonic interceptor
+/// for every call; anything that needs I/O (such as fetching signing keys) belongs in the
+/// provider's constructor.
+pub trait AuthProvider: Send + Sync {
+    /// Short name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Authenticates the request metadata and connection information.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The incoming request with its metadata and extensions.
+    ///
+    /// # Returns
+    ///
+    /// Returns the caller's [`AuthIdentity`], or `Status::unauthenticated` when the caller
+    /// cannot be authenticated.
+    fn authenticate(&self, request: &Request<()>) -> Result<AuthIdentity, Status>;
+}
+
+/// Accepts every request. This is the default and matches the behavior before authentication
+/// providers existed, so it should only be used when the API is not reachable by other users.
+pub struct NoAuthProvider;
+
+impl AuthProvider for NoAuthProvider {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn authenticate(&self, _request: &Request<()>) -> Result<AuthIdentity, Status> {
+        Ok(AuthIdentity::with_all_scopes(ANONYMOUS_SUBJECT))
+    }
+}
+
+/// Returns the hex-encoded SHA-256 hash under which a token is stored.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Generates a new random API token.
+pub fn generate_token() -> String {
+    format!(
+        "sph_{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// A token created with `tokens create`.
+struct StoredToken {
+    name: String,
+    hash: String,
+    scopes: Vec<TokenScope>,
+}
+
+/// Requires an `authorization: Bearer <token>` header matching `--auth-token` or a token
+/// stored in the database.
+///
+/// `--auth-token` grants every scope and authenticates as `token`. A stored token grants
+/// its scopes and authenticates as `token:<name>`. Stored tokens are kept in memory, as
+/// authentication cannot wait for the database, and reloaded by [`reload_tokens_periodically`].
+pub struct StaticTokenProvider {
+    cli_token_hash: Option<String>,
+    stored: RwLock<Vec<StoredToken>>,
+}
+
+impl StaticTokenProvider {
+    pub fn new(token: String) -> Self {
+        Self::with_cli_token(Some(token))
+    }
+
+    /// Creates a provider that accepts `cli_token`, if any, until stored tokens are loaded.
+    pub fn with_cli_token(cli_token: Option<String>) -> Self {
+        Self {
+            cli_token_hash: cli_token.map(|token| hash_token(&token)),
+            stored: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Replaces the stored tokens the provider accepts.
+    pub fn set_stored_tokens(&self, tokens: &[api_token::Model]) {
+        let tokens = tokens
+            .iter()
+            .map(|token| StoredToken {
+                name: token.id.clone(),
+                hash: token.token_hash.clone(),
+                scopes: token_scopes(token)
+                    .into_iter()
+                    .filter_map(|scope| match TokenScope::from_str(scope, false) {
+                        Ok(scope) => Some(scope),
+                        Err(_) => {
+                            warn!("Ignoring unknown scope '{scope}' of token {}", token.id);
+                            None
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        *self.stored.write().unwrap_or_else(|e| e.into_inner()) = tokens;
+    }
+
+    /// Loads the stored tokens from the database.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of tokens the provider accepts, including `--auth-token`.
+    pub async fn reload(&self, db: &DatabaseConnection) -> Result<usize> {
+        let tokens = list_api_tokens(db)
+            .await
+            .context("cannot load API tokens")?;
+        self.set_stored_tokens(&tokens);
+        Ok(tokens.len() + usize::from(self.cli_token_hash.is_some()))
+    }
+}
+
+impl AuthProvider for StaticTokenProvider {
+    fn name(&self) -> &'static str {
+        "token"
+    }
+
+    fn authenticate(&self, request: &Request<()>) -> Result<AuthIdentity, Status> {
+        let presented = hash_token(bearer_token(request)?);
+        let is_cli_token = self
+            .cli_token_hash
+            .as_ref()
+            .is_some_and(|hash| constant_time_eq(presented.as_bytes(), hash.as_bytes()));
+        if is_cli_token {
+            return Ok(AuthIdentity::with_all_scopes("token"));
+        }
+        let stored = self.stored.read().unwrap_or_else(|e| e.into_inner());
+        stored
+            .iter()
+            .find(|token| constant_time_eq(presented.as_bytes(), token.hash.as_bytes()))
+            .map(|token| AuthIdentity {
+                subject: format!("token:{}", token.name),
+                scopes: token.scopes.clone(),
+            })
+            .ok_or_else(|| Status::unauthenticated("invalid bearer token"))
+    }
+}
+
+/// Reloads the stored tokens of `provider` every [`TOKEN_RELOAD_INTERVAL`], so that tokens
+/// created or revoked while the server runs take effect.
+pub async fn reload_tokens_periodically(
+    provider: Arc<StaticTokenProvider>,
+    db: DatabaseConnection,
+) {
+    let mut interval = tokio::time::interval(TOKEN_RELOAD_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(e) = provider.reload(&db).await {
+            warn!("Keeping the previous API tokens: {e:#}");
+        }
+    }
+}
+
+/// Accepts requests whose Unix domain socket peer runs as the given user, granting them
+/// the configured scopes.
+///
+/// Peer credentials only exist for Unix socket connections, so TCP callers are always rejected.
+#[cfg(unix)]
+pub struct PeerCredentialProvider {
+    allowed_uid: u32,
+    scopes: Vec<TokenScope>,
+}
+
+#[cfg(unix)]
+impl PeerCredentialProvider {
+    /// Creates a provider that only admits the user the server is running as.
+    pub fn for_current_user(scopes: Vec<TokenScope>) -> Self {
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        let allowed_uid = unsafe { libc::geteuid() };
+        Self {
+            allowed_uid,
+            scopes,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl AuthProvider for PeerCredentialProvider {
+    fn name(&self) -> &'static str {
+        "peer-cred"
+    }
+
+    fn authenticate(&self, request: &Request<()>) -> Result<AuthIdentity, Status> {
+        let uid = request
+            .extensions()
+            .get::<tonic::transport::server::UdsConnectInfo>()
+            .and_then(|info| info.peer_cred)
+            .map(|cred| cred.uid())
+            .ok_or_else(|| {
+                Status::unauthenticated(
+                    "peer credentials are only available for Unix domain socket connections",
+                )
+            })?;
+
+        if uid == self.allowed_uid {
+            Ok(AuthIdentity {
+                subject: format!("uid:{uid}"),
+                scopes: self.scopes.clone(),
+            })
+        } else {
+            Err(Status::unauthenticated(format!(
+                "uid {uid} is not allowed to use this server"
+            )))
+        }
+    }
+}
+
+/// Validates OIDC ID/access tokens (JWTs) against the issuer's published signing keys.
+///
+/// Callers get the scopes named by the token's scopes claim when one is configured (see
+/// [`OidcProvider::with_scopes`]), and [`DEFAULT_USER_SCOPES`] otherwise.
+///
+/// The keys are refetched every [`OIDC_KEYS_REFRESH_INTERVAL`] by
+/// [`refresh_oidc_keys_periodically`], and soon after a token names a key the provider does
+/// not know, so tokens signed with a rotated key are accepted once the new keys are loaded.
+pub struct OidcProvider {
+    issuer: String,
+    audience: String,
+    keys: Arc<OidcKeys>,
+    scopes_claim: Option<String>,
+    default_scopes: Vec<TokenScope>,
+}
+
+/// The issuer's signing keys and where they are refetched from.
+struct OidcKeys {
+    /// `None` for a fixed key set, which is never refetched.
+    jwks_uri: Option<String>,
+    set: RwLock<JwkSet>,
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl OidcKeys {
+    /// Refetches the key set, keeping the current one when that fails.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of keys now in use.
+    async fn refresh(&self) -> Result<usize> {
+        let Some(jwks_uri) = &self.jwks_uri else {
+            return Ok(self
+                .set
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .keys
+                .len());
+        };
+        *self.last_fetch.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        let keys = fetch_jwks(&reqwest::Client::new(), jwks_uri).await?;
+        let count = keys.keys.len();
+        *self.set.write().unwrap_or_else(|e| e.into_inner()) = keys;
+        Ok(count)
+    }
+
+    /// Refetches the key set in the background, unless that happened within
+    /// [`OIDC_KEYS_REFETCH_COOLDOWN`].
+    fn refresh_soon(self: &Arc<Self>) {
+        if self.jwks_uri.is_none() {
+            return;
+        }
+        {
+            let mut last_fetch = self.last_fetch.lock().unwrap_or_else(|e| e.into_inner());
+            if last_fetch.is_some_and(|at| at.elapsed() < OIDC_KEYS_REFETCH_COOLDOWN) {
+                return;
+            }
+            *last_fetch = Some(Instant::now());
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let keys = self.clone();
+        runtime.spawn(async move {
+            match keys.refresh().await {
+                Ok(count) => info!("Refetched {count} OIDC signing key(s) for an unknown key ID"),
+                Err(e) => warn!("Keeping the previous OIDC signing keys: {e:#}"),
+            }
+        });
+    }
+}
+
+async fn fetch_jwks(client: &reqwest::Client, jwks_uri: &str) -> Result<JwkSet> {
+    serde_json::from_str(
+        &client
+            .get(jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?,
+    )
+    .with_context(|| format!("invalid JWKS at {jwks_uri}"))
+}
+
+/// The algorithm tokens signed with `jwk` must use: the key's own `alg`, or the usual
+/// one for its key type. The token header never chooses it, and symmetric algorithms
+/// are refused, as a published key set cannot hold a shared secret.
+fn jwk_algorithm(jwk: &Jwk) -> Option<Algorithm> {
+    let algorithm = match jwk.common.key_algorithm {
+        Some(algorithm) => Algorithm::from_str(&algorithm.to_string()).ok()?,
+        None => match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Algorithm::RS256,
+            AlgorithmParameters::EllipticCurve(params) => match params.curve {
+                EllipticCurve::P256 => Algorithm::ES256,
+                EllipticCurve::P384 => Algorithm::ES384,
+                _ => return None,
+            },
+            AlgorithmParameters::OctetKeyPair(params) if params.curve == EllipticCurve::Ed25519 => {
+                Algorithm::EdDSA
+            }
+            _ => return None,
+        },
+    };
+    let symmetric = matches!(
+        algorithm,
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+    );
+    (!symmetric).then_some(algorithm)
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcDiscovery {
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcClaims {
+    sub: String,
+    /// Every other claim, where the scopes claim is looked up.
+    #[serde(flatten)]
+    other: HashMap<String, serde_json::Value>,
+}
+
+impl OidcProvider {
+    /// Creates a provider from a fixed key set, which is never refetched.
+    pub fn new(issuer: String, audience: String, keys: JwkSet) -> Self {
+        Self::with_keys(issuer, audience, None, keys)
+    }
+
+    /// Creates a provider whose key set, initially `keys`, is refetched from `jwks_uri`.
+    fn with_keys(issuer: String, audience: String, jwks_uri: Option<String>, keys: JwkSet) -> Self {
+        Self {
+            issuer,
+            audience,
+            keys: Arc::new(OidcKeys {
+                jwks_uri,
+                set: RwLock::new(keys),
+                last_fetch: Mutex::new(Some(Instant::now())),
+            }),
+            scopes_claim: None,
+            default_scopes: DEFAULT_USER_SCOPES.to_vec(),
+        }
+    }
+
+    /// Sets the scopes callers get.
+    ///
+    /// # Arguments
+    ///
+    /// * `scopes_claim` - Claim listing the caller's scopes, either as a space-separated
+    ///   string like the standard `scope` claim or as an array of strings. Names that are
+    ///   not scopes of this server are ignored, and a token without the claim gets none.
+    /// * `default_scopes` - Scopes of every caller when `scopes_claim` is `None`.
+    pub fn with_scopes(
+        mut self,
+        scopes_claim: Option<String>,
+        default_scopes: Vec<TokenScope>,
+    ) -> Self {
+        self.scopes_claim = scopes_claim;
+        self.default_scopes = default_scopes;
+        self
+    }
+
+    /// Returns the scopes of a caller with the given claims.
+    fn scopes(&self, claims: &OidcClaims) -> Vec<TokenScope> {
+        let Some(claim) = &self.scopes_claim else {
+            return self.default_scopes.clone();
+        };
+        let names: Vec<&str> = match claims.other.get(claim) {
+            Some(serde_json::Value::String(names)) => names.split_whitespace().collect(),
+            Some(serde_json::Value::Array(names)) => {
+                names.iter().filter_map(serde_json::Value::as_str).collect()
+            }
+            _ => Vec::new(),
+        };
+        TokenScope::value_variants()
+            .iter()
+            .copied()
+            .filter(|scope| names.contains(&scope_name(*scope)))
+            .collect()
+    }
+
+    /// Refetches the issuer's signing keys, keeping the current ones when that fails.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of keys now in use.
+    pub async fn refresh_keys(&self) -> Result<usize> {
+        self.keys.refresh().await
+    }
+
+    /// Fetches the issuer's discovery document and signing keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `issuer` - The issuer URL, e.g. `https://accounts.example.com`.
+    /// * `audience` - The expected `aud` claim.
+    ///
+    /// # Returns
+    ///
+    /// Returns the provider, or an error if the discovery document or key set cannot be loaded.
+    pub async fn discover(issuer: String, audience: String) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let discovery: OidcDiscovery = serde_json::from_str(
+            &client
+                .get(&discovery_url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?,
+        )
+        .with_context(|| format!("invalid OIDC discovery document at {discovery_url}"))?;
+
+        let keys = fetch_jwks(&client, &discovery.jwks_uri).await?;
+
+        info!(
+            "Loaded {} OIDC signing key(s) from {}",
+            keys.keys.len(),
+            discovery.jwks_uri
+        );
+        Ok(Self::with_keys(
+            issuer,
+            audience,
+            Some(discovery.jwks_uri),
+            keys,
+        ))
+    }
+}
+
+/// Refetches the signing keys of `provider` every [`OIDC_KEYS_REFRESH_INTERVAL`], so keys
+/// the issuer rotates take effect.
+pub async fn refresh_oidc_keys_periodically(provider: Arc<OidcProvider>) {
+    let mut interval = tokio::time::interval(OIDC_KEYS_REFRESH_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(e) = provider.refresh_keys().await {
+            warn!("Keeping the previous OIDC signing keys: {e:#}");
+        }
+    }
+}
+
+impl AuthProvider for OidcProvider {
+    fn name(&self) -> &'static str {
+        "oidc"
+    }
+
+    fn authenticate(&self, request: &Request<()>) -> Result<AuthIdentity, Status> {
+        let token = bearer_token(request)?;
+        let header = decode_header(token)
+            .map_err(|e| Status::unauthenticated(format!("malformed token: {e}")))?;
+        let (key, algorithm) = {
+            let keys = self.keys.set.read().unwrap_or_else(|e| e.into_inner());
+            let jwk = match &header.kid {
+                Some(kid) => keys.find(kid),
+                // Without a key ID the key is only unambiguous when there is a single one.
+                None if keys.keys.len() <= 1 => keys.keys.first(),
+                None => {
+                    return Err(Status::unauthenticated(
+                        "token has no key ID (kid) but the issuer has several signing keys",
+                    ));
+                }
+            };
+            let Some(jwk) = jwk else {
+                // The issuer may have rotated its keys since they were fetched.
+                self.keys.refresh_soon();
+                return Err(Status::unauthenticated("token signed with an unknown key"));
+            };
+            let algorithm = jwk_algorithm(jwk).ok_or_else(|| {
+                Status::unauthenticated("signing key has no supported signature algorithm")
+            })?;
+            let key = DecodingKey::from_jwk(jwk)
+                .map_err(|e| Status::unauthenticated(format!("unusable signing key: {e}")))?;
+            (key, algorithm)
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+
+        let claims = decode::<OidcClaims>(token, &key, &validation)
+            .map_err(|e| Status::unauthenticated(format!("invalid token: {e}")))?
+            .claims;
+        Ok(AuthIdentity {
+            scopes: self.scopes(&claims),
+            subject: claims.sub,
+        })
+    }
+}
+
+/// Builds the provider selected on the command line.
+///
+/// # Arguments
+///
+/// * `args` - Parsed command-line arguments.
+///
+/// # Returns
+///
+/// Returns the configured provider, or an error when required options are missing or the
+/// provider cannot be initialized.
+pub async fn build_auth_provider(args: &Args) -> Result<Arc<dyn AuthProvider>> {
+    let provider: Arc<dyn AuthProvider> = match args.auth {
+        AuthMode::None => Arc::new(NoAuthProvider),
+        AuthMode::Token => {
+            let cli_token = args.auth_token.clone().filter(|token| !token.is_empty());
+            let provider = Arc::new(StaticTokenProvider::with_cli_token(cli_token));
+            let db = crate::GLOBAL_STATE.wait_init_and_get_connection().await?;
+            if provider.reload(&db).await? == 0 {
+                anyhow::bail!(
+                    "--auth token needs --auth-token or a token created with `tokens create`"
+                );
+            }
+            tokio::spawn(reload_tokens_periodically(provider.clone(), db));
+            provider
+        }
+        #[cfg(unix)]
+        AuthMode::PeerCred => Arc::new(PeerCredentialProvider::for_current_user(
+            args.auth_default_scopes.clone(),
+        )),
+        #[cfg(not(unix))]
+        AuthMode::PeerCred => {
+            anyhow::bail!("--auth peer-cred is only supported on Unix platforms")
+        }
+        AuthMode::Oidc => {
+            let issuer = args
+                .oidc_issuer
+                .clone()
+                .context("--oidc-issuer is required when --auth oidc is used")?;
+            let audience = args
+                .oidc_audience
+                .clone()
+                .context("--oidc-audience is required when --auth oidc is used")?;
+            let provider = Arc::new(OidcProvider::discover(issuer, audience).await?.with_scopes(
+                args.oidc_scopes_claim.clone(),
+                args.auth_default_scopes.clone(),
+            ));
+            tokio::spawn(refresh_oidc_keys_periodically(provider.clone()));
+            provider
+        }
+    };
+    info!("gRPC authentication provider: {}", provider.name());
+    Ok(provider)
+}
+
+/// Wraps a provider as a tonic interceptor that rejects unauthenticated calls and stores the
+/// resulting [`AuthIdentity`] in the request extensions.
+pub fn auth_interceptor(
+    provider: Arc<dyn AuthProvider>,
+) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |mut request: Request<()>| {
+        let identity = provider.authenticate(&request).inspect_err(|status| {
+            debug!("Rejected gRPC request: {}", status.message());
+        })?;
+        request.extensions_mut().insert(identity);
+        Ok(request)
+    }
+}
+
+fn bearer_token(request: &Request<()>) -> Result<&str, Status> {
+    let header = request
+        .metadata()
+        .get("authorization")
+        .ok_or_else(|| Status::unauthenticated("missing authorization header"))?
+        .to_str()
+        .map_err(|_| Status::unauthenticated("malformed authorization header"))?;
+    header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| Status::unauthenticated("authorization header must use the Bearer scheme"))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with_auth(value: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", value.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn static_token_accepts_matching_token() {
+        let provider = StaticTokenProvider::new("secret".to_string());
+        let identity = provider
+            .authenticate(&request_with_auth("Bearer secret"))
+            .unwrap();
+        assert_eq!(identity.subject, "token");
+    }
+
+    #[test]
+    fn static_token_rejects_wrong_or_missing_token() {
+        let provider = StaticTokenProvider::new("secret".to_string());
+        let wrong = provider.authenticate(&request_with_auth("Bearer nope"));
+        assert_eq!(wrong.unwrap_err().code(), tonic::Code::Unauthenticated);
+        let scheme = provider.authenticate(&request_with_auth("Basic secret"));
+        assert_eq!(scheme.unwrap_err().code(), tonic::Code::Unauthenticated);
+        let missing = provider.authenticate(&Request::new(()));
+        assert_eq!(missing.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn stored_tokens_grant_their_scopes() {
+        let provider = StaticTokenProvider::with_cli_token(None);
+        provider.set_stored_tokens(&[api_token::Model {
+            id: "ci".to_string(),
+            token_hash: hash_token("sph_ci"),
+            scopes: "read run unknown".to_string(),
+            created_at: None,
+        }]);
+        let identity = provider
+            .authenticate(&request_with_auth("Bearer sph_ci"))
+            .unwrap();
+        assert_eq!(identity.subject, "token:ci");
+        assert_eq!(identity.scopes, vec![TokenScope::Read, TokenScope::Run]);
+
+        provider.set_stored_tokens(&[]);
+        let revoked = provider.authenticate(&request_with_auth("Bearer sph_ci"));
+        assert_eq!(revoked.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn require_scope_checks_the_identity() {
+        let mut request = Request::new(());
+        let unauthenticated = require_scope(&request, TokenScope::Read).unwrap_err();
+        assert_eq!(unauthenticated.code(), tonic::Code::Unauthenticated);
+        assert!(require_scope(&local_request(()), TokenScope::Admin).is_ok());
+
+        request.extensions_mut().insert(AuthIdentity {
+            subject: "token:ci".to_string(),
+            scopes: vec![TokenScope::Read],
+        });
+        assert!(require_scope(&request, TokenScope::Read).is_ok());
+        let denied = require_scope(&request, TokenScope::Run).unwrap_err();
+        assert_eq!(denied.code(), tonic::Code::PermissionDenied);
+        assert!(denied.message().contains("'run'"));
+    }
+
+    #[test]
+    fn generated_tokens_are_unique() {
+        let token = generate_token();
+        assert!(token.starts_with("sph_") && token.len() == 68);
+        assert_ne!(token, generate_token());
+        assert_eq!(hash_token(&token).len(), 64);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn peer_credential_rejects_tcp_requests() {
+        let provider = PeerCredentialProvider::for_current_user(DEFAULT_USER_SCOPES.to_vec());
+        let result = provider.authenticate(&Request::new(()));
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn oidc_rejects_malformed_token() {
+        let provider = OidcProvider::new(
+            "https://issuer.example".to_string(),
+            "sapphillon".to_string(),
+            JwkSet { keys: vec![] },
+        );
+        let result = provider.authenticate(&request_with_auth("Bearer not-a-jwt"));
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn oidc_scopes_come_from_the_configured_claim() {
+        let claims: OidcClaims = serde_json::from_value(serde_json::json!({
+            "sub": "alice",
+            "scope": "openid read write",
+            "roles": ["admin", "run"],
+        }))
+        .unwrap();
+        let provider = |claim: Option<&str>| {
+            oidc_provider(vec![], None)
+                .with_scopes(claim.map(str::to_string), DEFAULT_USER_SCOPES.to_vec())
+        };
+
+        assert_eq!(
+            provider(None).scopes(&claims),
+            vec![TokenScope::Read, TokenScope::Run]
+        );
+        assert_eq!(
+            provider(Some("scope")).scopes(&claims),
+            vec![TokenScope::Read, TokenScope::Write]
+        );
+        assert_eq!(
+            provider(Some("roles")).scopes(&claims),
+            vec![TokenScope::Run, TokenScope::Admin]
+        );
+        assert!(provider(Some("groups")).scopes(&claims).is_empty());
+    }
+
+    /// An RSA signing key as published in a JWKS; the modulus is not a real key.
+    fn rsa_jwk(kid: &str, alg: Option<&str>) -> serde_json::Value {
+        let mut jwk = serde_json::json!({
+            "kty": "RSA",
+            "kid": kid,
+            "use": "sig",
+            "n": "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri23bOdgWp4Dy1WlUzewbgBHod5pcM9H95GQRV3JDXboIRROSBigeC5yjU1hGzHHyXss8UDprecbAYxknTcQkhslANGRUZmdTOQ5qTRsLAt6BTYuyvVRdhS8exSZEy_c4gs_7svlJJQ4H9_NxsiIoLwAEk7-Q3UXERGYw_75IDrGA84-lA_-Ct4eTlXHBIY2EaV7t7LjJaynVJCpkv4LKjTTAumiGUIuQhrNhZLuF_RJLqHpM2kgWFLU7-VTdL1VbC2tejvcI2BlMkEpk1BzBZI0KQB0GaDWFLN-aEAw3vRw",
+            "e": "AQAB",
+        });
+        if let Some(alg) = alg {
+            jwk["alg"] = serde_json::json!(alg);
+        }
+        jwk
+    }
+
+    fn oidc_provider(keys: Vec<serde_json::Value>, jwks_uri: Option<String>) -> OidcProvider {
+        let keys: JwkSet = serde_json::from_value(serde_json::json!({ "keys": keys })).unwrap();
+        OidcProvider::with_keys(
+            "https://issuer.example".to_string(),
+            "sapphillon".to_string(),
+            jwks_uri,
+            keys,
+        )
+    }
+
+    /// A token with the given header and a signature that does not verify.
+    fn unsigned_token(header: serde_json::Value) -> String {
+        use base64::Engine;
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": "https://issuer.example",
+            "aud": "sapphillon",
+            "exp": 4_102_444_800u64,
+        });
+        format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string()),
+            URL_SAFE_NO_PAD.encode("not a signature"),
+        )
+    }
+
+    fn oidc_error(provider: &OidcProvider, header: serde_json::Value) -> String {
+        let token = unsigned_token(header);
+        let result = provider.authenticate(&request_with_auth(&format!("Bearer {token}")));
+        let status = result.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        status.message().to_string()
+    }
+
+    #[test]
+    fn oidc_requires_a_kid_when_there_are_several_keys() {
+        let provider = oidc_provider(
+            vec![rsa_jwk("a", Some("RS256")), rsa_jwk("b", Some("RS256"))],
+            None,
+        );
+        let message = oidc_error(&provider, serde_json::json!({ "alg": "RS256" }));
+        assert!(message.contains("no key ID"), "{message}");
+
+        let single = oidc_provider(vec![rsa_jwk("a", Some("RS256"))], None);
+        let message = oidc_error(&single, serde_json::json!({ "alg": "RS256" }));
+        assert!(message.contains("InvalidSignature"), "{message}");
+    }
+
+    #[test]
+    fn oidc_takes_the_algorithm_from_the_key() {
+        let provider = oidc_provider(vec![rsa_jwk("a", Some("RS256")), rsa_jwk("b", None)], None);
+        for (kid, alg) in [("a", "PS256"), ("a", "HS256"), ("b", "RS384")] {
+            let message = oidc_error(&provider, serde_json::json!({ "alg": alg, "kid": kid }));
+            assert!(
+                message.contains("InvalidAlgorithm"),
+                "{kid} {alg}: {message}"
+            );
+        }
+        let message = oidc_error(&provider, serde_json::json!({ "alg": "RS256", "kid": "b" }));
+        assert!(message.contains("InvalidSignature"), "{message}");
+
+        let hmac: Jwk =
+            serde_json::from_value(serde_json::json!({ "kty": "oct", "k": "c2VjcmV0" })).unwrap();
+        assert_eq!(jwk_algorithm(&hmac), None);
+    }
+
+    #[tokio::test]
+    async fn oidc_refetches_keys_for_an_unknown_kid() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let jwks_uri = format!("http://{}/jwks", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let body = serde_json::json!({ "keys": [rsa_jwk("rotated", Some("RS256"))] });
+            let body = body.to_string();
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        let provider = oidc_provider(vec![rsa_jwk("old", Some("RS256"))], Some(jwks_uri));
+        // The key set was just loaded, so only an expired cooldown allows a refetch.
+        *provider.keys.last_fetch.lock().unwrap() = None;
+        let header = serde_json::json!({ "alg": "RS256", "kid": "rotated" });
+        let message = oidc_error(&provider, header.clone());
+        assert!(message.contains("unknown key"), "{message}");
+
+        for _ in 0..50 {
+            if provider.keys.set.read().unwrap().find("rotated").is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let message = oidc_error(&provider, header);
+        assert!(message.contains("InvalidSignature"), "{message}");
+    }
+
+    #[test]
+    fn anonymous_requests_have_no_owner() {
+        let mut interceptor = auth_interceptor(Arc::new(NoAuthProvider));
+        assert_eq!(request_owner(&interceptor(Request::new(())).unwrap()), None);
+        assert_eq!(request_owner(&Request::new(())), None);
+
+        let mut interceptor =
+            auth_interceptor(Arc::new(StaticTokenProvider::new("secret".to_string())));
+        let request = interceptor(request_with_auth("Bearer secret")).unwrap();
+        assert_eq!(request_owner(&request).as_deref(), Some("token"));
+    }
+
+    #[test]
+    fn interceptor_attaches_identity() {
+        let mut interceptor = auth_interceptor(Arc::new(NoAuthProvider));
+        let request = interceptor(Request::new(())).unwrap();
+        assert_eq!(
+            request.extensions().get::<AuthIdentity>().unwrap().subject,
+            "anonymous"
+        );
+    }
+}
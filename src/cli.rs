@@ -0,0 +1,287 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Commands that work on the database directly instead of through a running server.
+// Clients of a running server use the gRPC services instead.
+
+mod encryption;
+mod examples;
+mod permissions;
+mod plugins;
+mod prompts;
+mod providers;
+mod recordings;
+mod results;
+mod revisions;
+mod schedules;
+mod secrets;
+mod state;
+mod tokens;
+mod triggers;
+mod workflows;
+
+pub use encryption::EncryptionCommand;
+pub use examples::ExamplesCommand;
+pub use permissions::PermissionsCommand;
+pub use plugins::PluginsCommand;
+pub use prompts::PromptsCommand;
+pub use providers::ProvidersCommand;
+pub use recordings::RecordingsCommand;
+pub use revisions::RevisionsCommand;
+pub use schedules::SchedulesCommand;
+pub use secrets::SecretsCommand;
+pub use state::StateCommand;
+pub use tokens::TokensCommand;
+pub use triggers::TriggersCommand;
+pub use workflows::WorkflowsCommand;
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::init;
+
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Delete stored workflow results
+    DeleteResults {
+        /// Delete the single result with this ID.
+        #[arg(long, conflicts_with = "before", required_unless_present = "before")]
+        result_id: Option<String>,
+
+        /// Delete every result that ran before this RFC 3339 timestamp (e.g. 2025-01-01T00:00:00Z).
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Only delete results of this workflow (used with --before).
+        #[arg(long, requires = "before")]
+        workflow_id: Option<String>,
+    },
+
+    /// Delete the workflow results the retention policy does not keep
+    PruneResults,
+
+    /// Run a stored workflow without side effects and print what it would change
+    DryRun {
+        /// ID of the workflow to plan
+        workflow_id: String,
+
+        /// Code revision to plan; defaults to the latest revision.
+        #[arg(long)]
+        workflow_code_id: Option<String>,
+    },
+
+    /// Explain what a stored workflow does and how risky its permissions are
+    Explain {
+        /// ID of the workflow to explain
+        workflow_id: String,
+
+        /// Code revision to explain; defaults to the latest revision.
+        #[arg(long)]
+        workflow_code_id: Option<String>,
+
+        /// Model to explain with, e.g. models/gpt-4o; defaults to --generation-model.
+        #[arg(long)]
+        model: Option<String>,
+    },
+
+    /// Ask the model why a stored run failed and suggest a fix
+    Diagnose {
+        /// ID of the failed workflow result
+        result_id: String,
+
+        /// Model to diagnose with, e.g. models/gpt-4o; defaults to --generation-model.
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Store the fix as the next code revision of the workflow.
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// List stored workflow results, newest first
+    ListResults {
+        /// Only list results of this workflow.
+        #[arg(long)]
+        workflow_id: Option<String>,
+
+        /// Only list results that ran at or after this RFC 3339 timestamp.
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only list results that ran before this RFC 3339 timestamp.
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only list results with this exit code.
+        #[arg(long, allow_negative_numbers = true)]
+        exit_code: Option<i32>,
+
+        /// Maximum number of results to print.
+        #[arg(long, default_value_t = 20)]
+        page_size: u32,
+
+        /// Token printed by a previous call to continue listing.
+        #[arg(long)]
+        page_token: Option<String>,
+    },
+
+    /// Work with the bundled example workflows
+    Examples {
+        #[command(subcommand)]
+        command: ExamplesCommand,
+    },
+
+    /// Manage secrets (such as SMTP credentials) stored in the OS keyring
+    Secrets {
+        #[command(subcommand)]
+        command: SecretsCommand,
+    },
+
+    /// Manage the API tokens accepted by `--auth token`
+    Tokens {
+        #[command(subcommand)]
+        command: TokensCommand,
+    },
+
+    /// Manage the keys that encrypt sensitive database columns
+    Encryption {
+        #[command(subcommand)]
+        command: EncryptionCommand,
+    },
+
+    /// Manage cron schedules that run workflows automatically
+    Schedules {
+        #[command(subcommand)]
+        command: SchedulesCommand,
+    },
+
+    /// Manage event triggers that run workflows on file changes, webhooks or window changes
+    Triggers {
+        #[command(subcommand)]
+        command: TriggersCommand,
+    },
+
+    /// List and answer questions asked by running workflows
+    Prompts {
+        #[command(subcommand)]
+        command: PromptsCommand,
+    },
+
+    /// Inspect or clear the key/value state kept by workflows
+    State {
+        #[command(subcommand)]
+        command: StateCommand,
+    },
+
+    /// Manage permissions denied to a workflow code
+    Permissions {
+        #[command(subcommand)]
+        command: PermissionsCommand,
+    },
+
+    /// Browse the registered plugins and their functions
+    Plugins {
+        #[command(subcommand)]
+        command: PluginsCommand,
+    },
+
+    /// Configure the language model providers used to generate workflows
+    Providers {
+        #[command(subcommand)]
+        command: ProvidersCommand,
+    },
+
+    /// Record workflow runs and replay them deterministically
+    Recordings {
+        #[command(subcommand)]
+        command: RecordingsCommand,
+    },
+
+    /// List the code revisions of a workflow and roll back to an earlier one
+    Revisions {
+        #[command(subcommand)]
+        command: RevisionsCommand,
+    },
+
+    /// Organize workflows with tags and folders
+    Workflows {
+        #[command(subcommand)]
+        command: WorkflowsCommand,
+    },
+}
+
+/// Runs a command against the database given with `--db-url`.
+///
+/// # Arguments
+///
+/// * `command` - The command to run.
+/// * `webhook_addr` - The `--webhook-addr` that printed webhook URLs point at.
+///
+/// # Returns
+///
+/// Returns `Ok(())` when the command succeeds, or an error describing why it failed.
+pub async fn run(command: CliCommand, webhook_addr: Option<&str>) -> Result<()> {
+    match command {
+        CliCommand::DeleteResults {
+            result_id,
+            before,
+            workflow_id,
+        } => {
+            init::setup_database().await?;
+            results::delete_results(result_id, before, workflow_id).await
+        }
+        CliCommand::PruneResults => {
+            init::setup_database().await?;
+            results::prune_results().await
+        }
+        CliCommand::DryRun {
+            workflow_id,
+            workflow_code_id,
+        } => {
+            init::setup_database().await?;
+            workflows::dry_run(workflow_id, workflow_code_id).await
+        }
+        CliCommand::Explain {
+            workflow_id,
+            workflow_code_id,
+            model,
+        } => {
+            init::setup_database().await?;
+            workflows::explain(workflow_id, workflow_code_id, model).await
+        }
+        CliCommand::Diagnose {
+            result_id,
+            model,
+            apply,
+        } => {
+            init::setup_database().await?;
+            results::diagnose_result(result_id, model, apply).await
+        }
+        CliCommand::ListResults {
+            workflow_id,
+            since,
+            until,
+            exit_code,
+            page_size,
+            page_token,
+        } => {
+            init::setup_database().await?;
+            results::list_results(workflow_id, since, until, exit_code, page_size, page_token).await
+        }
+        CliCommand::Examples { command } => examples::run(command).await,
+        CliCommand::Secrets { command } => secrets::run(command).await,
+        CliCommand::Tokens { command } => tokens::run(command).await,
+        CliCommand::Encryption { command } => encryption::run(command).await,
+        CliCommand::Schedules { command } => schedules::run(command).await,
+        CliCommand::Triggers { command } => triggers::run(command, webhook_addr).await,
+        CliCommand::Prompts { command } => prompts::run(command).await,
+        CliCommand::State { command } => state::run(command).await,
+        CliCommand::Permissions { command } => permissions::run(command).await,
+        CliCommand::Plugins { command } => plugins::run(command).await,
+        CliCommand::Providers { command } => providers::run(command).await,
+        CliCommand::Recordings { command } => recordings::run(command).await,
+        CliCommand::Revisions { command } => revisions::run(command).await,
+        CliCommand::Workflows { command } => workflows::run(command).await,
+    }
+}
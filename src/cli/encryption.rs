@@ -0,0 +1,54 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Keys that encrypt sensitive database columns, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum EncryptionCommand {
+    /// Print a new random key as a line for the key file
+    GenerateKey {
+        /// ID stored with every value the key encrypts, e.g. 2026-10
+        id: String,
+    },
+
+    /// Encrypt every sensitive column value with the first key, e.g. after adding a new key
+    Rotate,
+}
+
+/// Runs a `encryption` subcommand.
+pub(super) async fn run(command: EncryptionCommand) -> Result<()> {
+    match command {
+        EncryptionCommand::GenerateKey { id } => {
+            println!(
+                "{}",
+                entity::encryption::FieldKeyring::generate_key_line(&id)
+            );
+        }
+        EncryptionCommand::Rotate => {
+            let keyring = entity::encryption::field_keyring().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "pass --encryption-key-file or --encryption-key-from-keyring to rotate"
+                )
+            })?;
+            init::setup_database().await?;
+            let db = GLOBAL_STATE.get_db_connection().await?;
+            let updated = database::provider::reencrypt_provider_api_keys(&db, keyring)
+                .await
+                .map_err(|err| anyhow::anyhow!("cannot re-encrypt provider API keys: {err}"))?;
+            info!(
+                "Re-encrypted {updated} provider API key(s) with key {}",
+                keyring.current_key_id()
+            );
+        }
+    }
+    Ok(())
+}
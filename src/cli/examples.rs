@@ -0,0 +1,48 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Lists and runs the bundled example workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::examples;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum ExamplesCommand {
+    /// List the bundled example workflows
+    List,
+
+    /// Run the bundled example workflows against mock backends
+    Run {
+        /// Run only the example with this name
+        name: Option<String>,
+    },
+}
+
+/// Runs a `examples` subcommand.
+pub(super) async fn run(command: ExamplesCommand) -> Result<()> {
+    match command {
+        ExamplesCommand::List => {
+            for example in examples::examples() {
+                println!("{:<20} {}", example.name, example.description);
+            }
+        }
+        ExamplesCommand::Run { name } => {
+            let outcomes = examples::run_examples(name.as_deref())?;
+            let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+            info!(
+                "{} example(s) passed, {failed} failed",
+                outcomes.len() - failed
+            );
+            if failed > 0 {
+                anyhow::bail!("{failed} example(s) failed");
+            }
+        }
+    }
+    Ok(())
+}
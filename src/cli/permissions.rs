@@ -0,0 +1,401 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Denied permissions, limited grants and permission profiles, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, permission_profiles, services};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum PermissionsCommand {
+    /// Deny a permission to a workflow code; denials win over allowed permissions
+    Deny {
+        /// ID of the workflow code
+        workflow_code_id: String,
+
+        /// Permission type, e.g. net-access, filesystem-read or execute
+        permission_type: String,
+
+        /// Only deny this plugin function; all functions by default
+        #[arg(long, default_value = "*")]
+        function: String,
+
+        /// Only deny these resources (a path, URL prefix or host); all resources by default
+        #[arg(long)]
+        resource: Vec<String>,
+    },
+
+    /// List the permissions denied to a workflow code
+    ListDenied {
+        /// ID of the workflow code
+        workflow_code_id: String,
+    },
+
+    /// Remove a denial
+    RemoveDenied {
+        /// ID of the denial
+        denied_permission_id: String,
+    },
+
+    /// Grant a permission to a workflow code until it expires or has been used
+    Grant {
+        /// ID of the workflow code
+        workflow_code_id: String,
+
+        /// Permission type, e.g. net-access, filesystem-write or execute
+        permission_type: String,
+
+        /// Only grant it to this plugin function; all functions by default
+        #[arg(long, default_value = "*")]
+        function: String,
+
+        /// Only grant these resources (a path or URL); all resources by default
+        #[arg(long)]
+        resource: Vec<String>,
+
+        /// Highest level granted, medium or high; every level by default
+        #[arg(long)]
+        level: Option<String>,
+
+        /// Minutes until the grant expires
+        #[arg(long)]
+        expires_in_minutes: Option<u32>,
+
+        /// Number of runs that may use the grant, e.g. 1 for a single run
+        #[arg(long)]
+        max_uses: Option<u32>,
+    },
+
+    /// List the limited grants of a workflow code, including expired ones
+    ListGrants {
+        /// ID of the workflow code
+        workflow_code_id: String,
+    },
+
+    /// Revoke a limited grant
+    RevokeGrant {
+        /// ID of the grant
+        grant_id: String,
+    },
+
+    /// List the permission profiles
+    ListProfiles,
+
+    /// Print the permissions of a profile
+    ShowProfile {
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Create a profile or replace its permissions
+    SaveProfile {
+        /// Name of the profile
+        name: String,
+
+        /// JSON array of entries like {"function": "*", "type": "net-access", "level": "medium"}
+        permissions: String,
+
+        /// Description shown in list-profiles
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// Delete a profile and detach it from every workflow
+    DeleteProfile {
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Allow a workflow the permissions of a profile on every run
+    AttachProfile {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Stop allowing a workflow the permissions of a profile
+    DetachProfile {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Name of the profile
+        name: String,
+    },
+
+    /// List the profiles attached to a workflow
+    ListAttached {
+        /// ID of the workflow
+        workflow_id: String,
+    },
+
+    /// Print the permissions a workflow needs without running it
+    Preview {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Code revision to inspect; defaults to the latest revision.
+        #[arg(long)]
+        workflow_code_id: Option<String>,
+    },
+}
+
+/// Runs a `permissions` subcommand.
+pub(super) async fn run(command: PermissionsCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        PermissionsCommand::Deny {
+            workflow_code_id,
+            permission_type,
+            function,
+            resource,
+        } => {
+            let Some(permission_type) = plugin_permission::parse_permission_type(&permission_type)
+            else {
+                anyhow::bail!("unknown permission type: {permission_type}");
+            };
+            let resource_json = if resource.is_empty() {
+                None
+            } else {
+                Some(serde_json::to_string(&resource)?)
+            };
+            let denied = database::denied_permission::create_denied_permission(
+                &db,
+                &workflow_code_id,
+                &function,
+                permission_type,
+                resource_json.as_deref(),
+            )
+            .await?;
+            info!("Created denied permission {}", denied.id);
+        }
+        PermissionsCommand::ListDenied { workflow_code_id } => {
+            for denied in
+                database::denied_permission::list_denied_permissions(&db, &workflow_code_id).await?
+            {
+                let resource: Vec<String> = denied
+                    .resource_json
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?
+                    .unwrap_or_default();
+                let permission = sapphillon_core::proto::sapphillon::v1::Permission {
+                    permission_type: denied.permission_type,
+                    resource,
+                    ..Default::default()
+                };
+                println!(
+                    "{}  {}  {}",
+                    denied.id,
+                    denied.plugin_function_id,
+                    plugin_permission::describe_permission(&permission)
+                );
+            }
+        }
+        PermissionsCommand::RemoveDenied {
+            denied_permission_id,
+        } => {
+            if database::denied_permission::delete_denied_permission(&db, &denied_permission_id)
+                .await?
+                == 0
+            {
+                anyhow::bail!("denied permission not found: {denied_permission_id}");
+            }
+            info!("Removed denied permission: {denied_permission_id}");
+        }
+        PermissionsCommand::Grant {
+            workflow_code_id,
+            permission_type,
+            function,
+            resource,
+            level,
+            expires_in_minutes,
+            max_uses,
+        } => {
+            if expires_in_minutes.is_none() && max_uses.is_none() {
+                anyhow::bail!(
+                    "a grant needs --expires-in-minutes or --max-uses; add standing permissions to the workflow code instead"
+                );
+            }
+            let Some(permission_type) = plugin_permission::parse_permission_type(&permission_type)
+            else {
+                anyhow::bail!("unknown permission type: {permission_type}");
+            };
+            let permission_level = match level.as_deref() {
+                Some(level) => plugin_permission::parse_permission_level(level)
+                    .ok_or_else(|| anyhow::anyhow!("unknown permission level: {level}"))?,
+                None => 0,
+            };
+            let resource_json = if resource.is_empty() {
+                None
+            } else {
+                Some(serde_json::to_string(&resource)?)
+            };
+            let grant = database::permission_grant::create_permission_grant(
+                &db,
+                &workflow_code_id,
+                database::permission_grant::NewPermissionGrant {
+                    plugin_function_id: &function,
+                    permission_type,
+                    permission_level,
+                    resource_json: resource_json.as_deref(),
+                    expires_at: expires_in_minutes.map(|minutes| {
+                        chrono::Utc::now() + chrono::Duration::minutes(minutes.into())
+                    }),
+                    max_uses: max_uses.map(|uses| i32::try_from(uses).unwrap_or(i32::MAX)),
+                },
+            )
+            .await?;
+            info!("Created permission grant {}", grant.id);
+        }
+        PermissionsCommand::ListGrants { workflow_code_id } => {
+            for grant in
+                database::permission_grant::list_permission_grants(&db, &workflow_code_id).await?
+            {
+                let resource: Vec<String> = grant
+                    .resource_json
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?
+                    .unwrap_or_default();
+                let permission = sapphillon_core::proto::sapphillon::v1::Permission {
+                    permission_type: grant.permission_type,
+                    permission_level: grant.permission_level,
+                    resource,
+                    ..Default::default()
+                };
+                let expires = grant
+                    .expires_at
+                    .map(|at| format!("expires {}", at.to_rfc3339()))
+                    .unwrap_or_else(|| "no expiry".to_string());
+                let uses = match grant.max_uses {
+                    Some(max) => format!("used {}/{max}", grant.uses),
+                    None => format!("used {}", grant.uses),
+                };
+                println!(
+                    "{}  {}  {}  {expires}  {uses}",
+                    grant.id,
+                    grant.plugin_function_id,
+                    plugin_permission::describe_permission(&permission)
+                );
+            }
+        }
+        PermissionsCommand::RevokeGrant { grant_id } => {
+            if database::permission_grant::delete_permission_grant(&db, &grant_id).await? == 0 {
+                anyhow::bail!("permission grant not found: {grant_id}");
+            }
+            info!("Revoked permission grant: {grant_id}");
+        }
+        PermissionsCommand::ListProfiles => {
+            for profile in database::permission_profile::list_permission_profiles(&db).await? {
+                println!(
+                    "{}  {}",
+                    profile.name,
+                    profile.description.unwrap_or_default()
+                );
+            }
+        }
+        PermissionsCommand::ShowProfile { name } => {
+            let Some(profile) =
+                database::permission_profile::get_permission_profile(&db, &name).await?
+            else {
+                anyhow::bail!("permission profile not found: {name}");
+            };
+            for allowed in permission_profiles::parse_profile_permissions(&profile.permissions)? {
+                for permission in &allowed.permissions {
+                    println!(
+                        "{}  {}",
+                        allowed.plugin_function_id,
+                        plugin_permission::describe_permission(permission)
+                    );
+                }
+            }
+        }
+        PermissionsCommand::SaveProfile {
+            name,
+            permissions,
+            description,
+        } => {
+            permission_profiles::parse_profile_permissions(&permissions)?;
+            database::permission_profile::save_permission_profile(
+                &db,
+                &name,
+                description.as_deref(),
+                &permissions,
+            )
+            .await?;
+            info!("Saved permission profile: {name}");
+        }
+        PermissionsCommand::DeleteProfile { name } => {
+            if database::permission_profile::delete_permission_profile(&db, &name).await? == 0 {
+                anyhow::bail!("permission profile not found: {name}");
+            }
+            info!("Deleted permission profile: {name}");
+        }
+        PermissionsCommand::AttachProfile { workflow_id, name } => {
+            if database::permission_profile::get_permission_profile(&db, &name)
+                .await?
+                .is_none()
+            {
+                anyhow::bail!("permission profile not found: {name}");
+            }
+            database::permission_profile::attach_permission_profile(&db, &workflow_id, &name)
+                .await?;
+            info!("Attached permission profile {name} to workflow {workflow_id}");
+        }
+        PermissionsCommand::DetachProfile { workflow_id, name } => {
+            if database::permission_profile::detach_permission_profile(&db, &workflow_id, &name)
+                .await?
+                == 0
+            {
+                anyhow::bail!("permission profile {name} is not attached to {workflow_id}");
+            }
+            info!("Detached permission profile {name} from workflow {workflow_id}");
+        }
+        PermissionsCommand::ListAttached { workflow_id } => {
+            for profile in
+                database::permission_profile::list_workflow_permission_profiles(&db, &workflow_id)
+                    .await?
+            {
+                println!("{}", profile.name);
+            }
+        }
+        PermissionsCommand::Preview {
+            workflow_id,
+            workflow_code_id,
+        } => {
+            let preview = services::MyWorkflowService::new(db)
+                .preview_workflow_permissions(&workflow_id, workflow_code_id.as_deref())
+                .await
+                .map_err(|status| {
+                    anyhow::anyhow!("permission preview failed: {}", status.message())
+                })?;
+            for (label, entries) in [
+                ("required", &preview.required),
+                ("missing", &preview.missing),
+            ] {
+                for entry in entries {
+                    for permission in &entry.permissions {
+                        println!(
+                            "{label}  {}  {}",
+                            entry.plugin_function_id,
+                            plugin_permission::describe_permission(permission)
+                        );
+                    }
+                }
+            }
+            if preview.missing.is_empty() {
+                info!("The workflow is allowed every permission it needs");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,324 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Registered, installed and store plugins, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, auth, init, services};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum PluginsCommand {
+    /// List the registered plugin packages
+    List {
+        /// Maximum number of packages to print.
+        #[arg(long, default_value_t = 20)]
+        page_size: u32,
+
+        /// Token printed by a previous call to continue listing.
+        #[arg(long)]
+        page_token: Option<String>,
+    },
+
+    /// Print a plugin package and its functions
+    Show {
+        /// ID of the package, e.g. app.sapphillon.core.fetch
+        package_id: String,
+    },
+
+    /// Print the parameters, return values and permissions of a plugin function
+    Describe {
+        /// ID of the function, e.g. app.sapphillon.core.fetch.fetch
+        function_id: String,
+    },
+
+    /// List the installed external plugins and whether they are enabled
+    Installed,
+
+    /// Validate the built-in and enabled external plugins and print their problems
+    Check,
+
+    /// Print the plugin catalog the workflow generator describes to the model
+    Catalog,
+
+    /// Install an external plugin from a package.js
+    Install {
+        /// Local path or https://, http:// or file:// URI of the package.js
+        uri: String,
+
+        /// Only validate the package metadata; nothing is installed.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Search the plugin store
+    Search {
+        /// Text to look for in package IDs, names and descriptions
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Only list packages with this tag.
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
+    /// Download an external plugin from the plugin store and install it
+    InstallFromStore {
+        /// ID of the package, e.g. com.example.math-plugin
+        package_id: String,
+
+        /// Version to install; defaults to the newest.
+        #[arg(long)]
+        version: Option<String>,
+    },
+
+    /// List installed external plugins that have a newer version in the plugin store
+    Updates {
+        /// Compare with the plugin store now instead of using the last periodic check.
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Install the newer version of an external plugin next to the installed one
+    Update {
+        /// ID of the installed plugin, e.g. author/package/1.0.0
+        plugin_package_id: String,
+    },
+
+    /// Remove an installed external plugin
+    Uninstall {
+        /// ID of the installed plugin, e.g. author/package/1.0.0
+        plugin_package_id: String,
+    },
+
+    /// Let workflows use an installed external plugin again
+    Enable {
+        /// ID of the installed plugin, e.g. author/package/1.0.0
+        plugin_package_id: String,
+    },
+
+    /// Keep an external plugin installed but stop offering it to workflows
+    Disable {
+        /// ID of the installed plugin, e.g. author/package/1.0.0
+        plugin_package_id: String,
+    },
+}
+
+/// Runs a `plugins` subcommand.
+pub(super) async fn run(command: PluginsCommand) -> Result<()> {
+    init::setup_database().await?;
+    init::register_initial_plugins().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        PluginsCommand::List {
+            page_size,
+            page_token,
+        } => {
+            let (packages, next_page_token) =
+                database::plugin::list_plugins(&db, page_token, Some(page_size)).await?;
+            for package in packages {
+                println!(
+                    "{:<40} {:<10} {}",
+                    package.package_id, package.package_version, package.description
+                );
+            }
+            if !next_page_token.is_empty() {
+                println!("next page: --page-token {next_page_token}");
+            }
+        }
+        PluginsCommand::Show { package_id } => {
+            let package = services::MyPluginService::new(db)
+                .get_plugin(&package_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!(
+                "{} {}\n{}",
+                package.package_id, package.package_version, package.description
+            );
+            for function in package.functions {
+                println!("  {:<48} {}", function.function_id, function.description);
+            }
+        }
+        PluginsCommand::Describe { function_id } => {
+            let function = services::MyPluginService::new(db)
+                .get_plugin_function(&function_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!("{}\n{}", function.function_id, function.description);
+            let define = function.function_define.unwrap_or_default();
+            for (label, parameters) in [
+                ("parameter", define.parameters),
+                ("returns", define.returns),
+            ] {
+                for parameter in parameters {
+                    println!(
+                        "  {label}  {}: {}  {}",
+                        parameter.name, parameter.r#type, parameter.description
+                    );
+                }
+            }
+            for permission in &function.permissions {
+                println!(
+                    "  permission  {}",
+                    plugin_permission::describe_permission(permission)
+                );
+            }
+        }
+        PluginsCommand::Installed => {
+            for plugin in database::ext_plugin::list_ext_plugin_packages(&db).await? {
+                let state = match (plugin.missing, plugin.enabled) {
+                    (true, _) => "missing",
+                    (false, true) => "enabled",
+                    (false, false) => "disabled",
+                };
+                println!(
+                    "{:<48} {:<8} {}",
+                    plugin.plugin_package_id, state, plugin.install_dir
+                );
+            }
+        }
+        PluginsCommand::Check => {
+            let diagnostics = services::MyPluginService::new(db)
+                .check_plugins()
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+            }
+            if !diagnostics.is_empty() {
+                anyhow::bail!("{} plugin problem(s) found", diagnostics.len());
+            }
+            info!("Every plugin is valid");
+        }
+        PluginsCommand::Catalog => {
+            let catalog = services::MyWorkflowService::new(db)
+                .generation_tool_catalog()
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!("{catalog}");
+        }
+        PluginsCommand::Install { uri, dry_run } => {
+            use sapphillon_core::proto::google::rpc::Code as RpcCode;
+            use sapphillon_core::proto::sapphillon::v1::InstallPluginRequest;
+            use sapphillon_core::proto::sapphillon::v1::plugin_service_server::PluginService;
+
+            // The installer only takes absolute paths, so resolve local ones here.
+            let uri = match std::fs::canonicalize(&uri) {
+                Ok(path) if !uri.contains("://") => path.to_string_lossy().into_owned(),
+                _ => uri,
+            };
+            let service = services::MyPluginService::new(db);
+            if dry_run {
+                let inspection = service
+                    .validate_plugin(&uri)
+                    .await
+                    .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+                let manifest = inspection.manifest;
+                println!(
+                    "{} {}\n{}",
+                    manifest.package_id, manifest.version, manifest.description
+                );
+                println!("  functions  {}", manifest.functions.join(", "));
+                println!(
+                    "  installs as  {}/{}/{}",
+                    inspection.metadata.author_id,
+                    inspection.metadata.package_id,
+                    inspection.metadata.version
+                );
+            } else {
+                let status = service
+                    .install_plugin(auth::local_request(InstallPluginRequest { uri }))
+                    .await
+                    .map_err(|status| anyhow::anyhow!("{}", status.message()))?
+                    .into_inner()
+                    .status
+                    .unwrap_or_default();
+                if status.code != RpcCode::Ok as i32 {
+                    anyhow::bail!("{}", status.message);
+                }
+                println!("{}", status.message);
+            }
+        }
+        PluginsCommand::Search { query, tag } => {
+            let packages = services::MyPluginService::new(db)
+                .search_plugin_store(&query, tag.as_deref())
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            for package in packages {
+                println!(
+                    "{:<40} {:<10} {}",
+                    package.package_id, package.version, package.description
+                );
+            }
+        }
+        PluginsCommand::InstallFromStore {
+            package_id,
+            version,
+        } => {
+            let plugin_package_id = services::MyPluginService::new(db)
+                .install_from_store(&package_id, version.as_deref())
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!("plugin installed: {plugin_package_id}");
+        }
+        PluginsCommand::Updates { check } => {
+            let updates = services::MyPluginService::new(db)
+                .list_plugin_updates(check)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            for plugin in updates {
+                println!(
+                    "{:<48} -> {}",
+                    plugin.plugin_package_id,
+                    plugin.available_version.unwrap_or_default()
+                );
+            }
+        }
+        PluginsCommand::Update { plugin_package_id } => {
+            let updated = services::MyPluginService::new(db)
+                .update_plugin(&plugin_package_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!("plugin installed: {updated}");
+        }
+        PluginsCommand::Uninstall { plugin_package_id } => {
+            use sapphillon_core::proto::google::rpc::Code as RpcCode;
+            use sapphillon_core::proto::sapphillon::v1::UninstallPluginRequest;
+            use sapphillon_core::proto::sapphillon::v1::plugin_service_server::PluginService;
+
+            let status = services::MyPluginService::new(db)
+                .uninstall_plugin(auth::local_request(UninstallPluginRequest {
+                    package_id: plugin_package_id,
+                }))
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?
+                .into_inner()
+                .status
+                .unwrap_or_default();
+            if status.code != RpcCode::Ok as i32 {
+                anyhow::bail!("{}", status.message);
+            }
+            println!("{}", status.message);
+        }
+        PluginsCommand::Enable { plugin_package_id } => {
+            services::MyPluginService::new(db)
+                .enable_plugin(&plugin_package_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!("enabled {plugin_package_id}");
+        }
+        PluginsCommand::Disable { plugin_package_id } => {
+            services::MyPluginService::new(db)
+                .disable_plugin(&plugin_package_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            println!("disabled {plugin_package_id}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Questions asked by running workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, prompt_handler};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum PromptsCommand {
+    /// List prompts waiting for an answer
+    List {
+        /// Also list answered and expired prompts
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Answer a pending prompt
+    Answer {
+        /// ID of the prompt
+        prompt_id: String,
+
+        /// yes or no for confirmations, otherwise the text to return
+        answer: String,
+    },
+}
+
+/// Runs a `prompts` subcommand.
+pub(super) async fn run(command: PromptsCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        PromptsCommand::List { all } => {
+            for prompt in database::prompt::list_prompts(&db, !all).await? {
+                println!(
+                    "{}  {}  {:<7} {:<8} {}{}",
+                    prompt.id,
+                    prompt.workflow_id,
+                    prompt.kind,
+                    prompt.status,
+                    prompt.message,
+                    prompt
+                        .answer
+                        .map(|answer| format!(" -> {answer}"))
+                        .unwrap_or_default()
+                );
+            }
+        }
+        PromptsCommand::Answer { prompt_id, answer } => {
+            prompt_handler::answer_prompt(&db, &prompt_id, &answer).await?;
+            info!("Answered prompt: {prompt_id}");
+        }
+    }
+    Ok(())
+}
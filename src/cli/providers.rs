@@ -0,0 +1,81 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Language model providers, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, llm};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum ProvidersCommand {
+    /// List the providers with their API kind and options
+    List,
+
+    /// Set the API kind and the provider-specific options of a provider
+    Configure {
+        /// Name of the provider, e.g. providers/anthropic
+        name: String,
+
+        /// API the provider speaks: openai, anthropic, ollama or gemini.
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// JSON object of request options, e.g. '{"temperature": 0.2}'; '{}' clears them.
+        #[arg(long)]
+        options: Option<String>,
+    },
+}
+
+/// Runs a `providers` subcommand.
+pub(super) async fn run(command: ProvidersCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        ProvidersCommand::List => {
+            let (providers, _) =
+                database::provider::list_providers_entity(&db, None, Some(1000)).await?;
+            for provider in providers {
+                println!(
+                    "{}  {}  {}  options={}",
+                    provider.name,
+                    provider.kind.as_deref().unwrap_or("openai"),
+                    provider.api_endpoint,
+                    provider.options.as_deref().unwrap_or("{}")
+                );
+            }
+        }
+        ProvidersCommand::Configure {
+            name,
+            kind,
+            options,
+        } => {
+            let Some(existing) = database::provider::get_provider_entity(&db, &name).await? else {
+                anyhow::bail!("provider not found: {name}");
+            };
+            let kind = match kind {
+                Some(kind) => Some(llm::ProviderKind::parse(&kind)?.as_str().to_string()),
+                None => existing.kind,
+            };
+            let options = match options {
+                Some(options) => {
+                    let parsed: serde_json::Value = serde_json::from_str(&options)?;
+                    match parsed.as_object() {
+                        Some(map) if map.is_empty() => None,
+                        Some(_) => Some(parsed.to_string()),
+                        None => anyhow::bail!("--options must be a JSON object"),
+                    }
+                }
+                None => existing.options,
+            };
+            database::provider::set_provider_options(&db, &name, kind, options).await?;
+            info!("Configured provider: {name}");
+        }
+    }
+    Ok(())
+}
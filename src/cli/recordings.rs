@@ -0,0 +1,101 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Recorded runs and their replays, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, services};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum RecordingsCommand {
+    /// Run a stored workflow and record every plugin call it makes
+    Record {
+        /// ID of the workflow to run
+        workflow_id: String,
+
+        /// Code revision to run; defaults to the latest revision.
+        #[arg(long)]
+        workflow_code_id: Option<String>,
+    },
+
+    /// List recordings, newest first
+    List {
+        /// Only list recordings of this workflow
+        #[arg(long)]
+        workflow_id: Option<String>,
+    },
+
+    /// Re-run a recording against the recorded plugin responses
+    Replay {
+        /// ID of the recording
+        recording_id: String,
+    },
+
+    /// Remove a recording
+    Delete {
+        /// ID of the recording
+        recording_id: String,
+    },
+}
+
+/// Runs a `recordings` subcommand.
+pub(super) async fn run(command: RecordingsCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        RecordingsCommand::Record {
+            workflow_id,
+            workflow_code_id,
+        } => {
+            let (run, recording_id) = services::MyWorkflowService::new(db)
+                .record_workflow(&workflow_id, workflow_code_id.as_deref())
+                .await
+                .map_err(|status| anyhow::anyhow!("recording failed: {}", status.message()))?;
+            print!("{}", run.result.result);
+            info!("Created recording {recording_id}");
+        }
+        RecordingsCommand::List { workflow_id } => {
+            for recording in
+                database::recording::list_recordings(&db, workflow_id.as_deref()).await?
+            {
+                println!(
+                    "{}  {}  code={} created={}",
+                    recording.id,
+                    recording.workflow_id,
+                    recording.workflow_code_id,
+                    recording
+                        .created_at
+                        .map(|at| at.to_rfc3339())
+                        .unwrap_or_else(|| "-".to_string())
+                );
+            }
+        }
+        RecordingsCommand::Replay { recording_id } => {
+            let replay = services::MyWorkflowService::new(db)
+                .replay_workflow(&recording_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("replay failed: {}", status.message()))?;
+            print!("{}", replay.result.result);
+            if !replay.matches() {
+                anyhow::bail!(
+                    "replay output differs from the recorded output:\n{}",
+                    replay.expected_output
+                );
+            }
+            info!("Replay matches the recorded run");
+        }
+        RecordingsCommand::Delete { recording_id } => {
+            if database::recording::delete_recording(&db, &recording_id).await? == 0 {
+                anyhow::bail!("recording not found: {recording_id}");
+            }
+            info!("Deleted recording: {recording_id}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Stored workflow results, from the command line
+
+use crate::{GLOBAL_STATE, services, workflow_revisions};
+use anyhow::{Context, Result};
+use database::workflow::workflow_result_crud::{
+    WorkflowResultFilter, list_workflow_results_filtered,
+};
+use database::workflow::{delete_workflow_result_by_id, delete_workflow_results_before};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Deletes stored workflow results, either a single result or every result older than a cutoff.
+///
+/// # Arguments
+///
+/// * `result_id` - Identifier of a single result to delete.
+/// * `before` - RFC 3339 timestamp; results that ran before it are deleted.
+/// * `workflow_id` - Restricts the `before` deletion to a single workflow.
+///
+/// # Returns
+///
+/// Returns `Ok(())` after the deletion completes, or an error when the arguments are invalid
+/// or the database operation fails.
+pub(super) async fn delete_results(
+    result_id: Option<String>,
+    before: Option<String>,
+    workflow_id: Option<String>,
+) -> Result<()> {
+    let db = GLOBAL_STATE.get_db_connection().await?;
+
+    if let Some(result_id) = result_id {
+        delete_workflow_result_by_id(&db, &result_id).await?;
+        info!("Deleted workflow result: {result_id}");
+        return Ok(());
+    }
+
+    let before = before.context("either --result-id or --before must be provided")?;
+    let cutoff = parse_cutoff(&before)?;
+    let deleted = delete_workflow_results_before(&db, cutoff, workflow_id.as_deref()).await?;
+    info!("Deleted {deleted} workflow result(s) that ran before {cutoff}");
+    Ok(())
+}
+
+/// Prints stored workflow results matching the given filters, newest first.
+///
+/// # Arguments
+///
+/// * `workflow_id` - Restricts the listing to a single workflow.
+/// * `since` - RFC 3339 timestamp; only results that ran at or after it are listed.
+/// * `until` - RFC 3339 timestamp; only results that ran before it are listed.
+/// * `exit_code` - Only results with this exit code are listed.
+/// * `page_size` - Maximum number of results to print.
+/// * `page_token` - Token printed by a previous call to continue listing.
+///
+/// # Returns
+///
+/// Returns `Ok(())` after printing the page, or an error when a timestamp is invalid
+/// or the query fails.
+pub(super) async fn list_results(
+    workflow_id: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    exit_code: Option<i32>,
+    page_size: u32,
+    page_token: Option<String>,
+) -> Result<()> {
+    let db = GLOBAL_STATE.get_db_connection().await?;
+
+    let filter = WorkflowResultFilter {
+        workflow_id,
+        ran_after: since.as_deref().map(parse_cutoff).transpose()?,
+        ran_before: until.as_deref().map(parse_cutoff).transpose()?,
+        exit_code,
+        owner_id: None,
+    };
+    let (results, next_page_token) =
+        list_workflow_results_filtered(&db, &filter, page_token, Some(page_size)).await?;
+
+    for result in results {
+        println!(
+            "{}  {}  rev={} exit={} ran_at={}",
+            result.id,
+            result.workflow_id,
+            result.workflow_result_revision,
+            result
+                .exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            result
+                .ran_at
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+    if !next_page_token.is_empty() {
+        println!("next page: --page-token {next_page_token}");
+    }
+    Ok(())
+}
+
+/// Deletes the stored workflow results that the retention policy of the system
+/// configuration does not keep.
+///
+/// # Returns
+///
+/// Returns `Ok(())` after pruning, or an error when the database operation fails.
+pub(super) async fn prune_results() -> Result<()> {
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    let policy = crate::sysconfig::sysconfig().result_retention;
+    let deleted = crate::result_retention::prune_results(&db, &policy).await?;
+    info!("Pruned {deleted} workflow result(s)");
+    Ok(())
+}
+
+/// Asks the model why a stored run failed and prints the suggested fix as a diff.
+///
+/// # Arguments
+///
+/// * `result_id` - The failed run.
+/// * `model` - Model to diagnose with; the default model is used when `None`.
+/// * `apply` - Whether to store the fix as the next code revision of the workflow.
+///
+/// # Returns
+///
+/// Returns `Ok(())` after printing the fix, or an error when the diagnosis fails.
+pub(super) async fn diagnose_result(
+    result_id: String,
+    model: Option<String>,
+    apply: bool,
+) -> Result<()> {
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    let diagnosis = services::MyWorkflowService::new(db)
+        .diagnose_workflow_result(&result_id, model.as_deref(), apply)
+        .await
+        .map_err(|status| anyhow::anyhow!("diagnosis failed: {}", status.message()))?;
+
+    if !diagnosis.explanation.is_empty() {
+        println!("{}\n", diagnosis.explanation);
+    }
+    print!("{}", workflow_revisions::render_diff(&diagnosis.diff));
+    match diagnosis.applied {
+        Some(code) => info!(
+            "Stored the fix as revision {} of {}",
+            code.code_revision, diagnosis.workflow_id
+        ),
+        None => info!("Run again with --apply to store the fix as a new revision"),
+    }
+    Ok(())
+}
+
+fn parse_cutoff(value: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("invalid RFC 3339 timestamp: {value}"))?;
+    Ok(parsed.with_timezone(&chrono::Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cutoff_accepts_rfc3339() {
+        let cutoff = parse_cutoff("2025-01-02T03:04:05+09:00").unwrap();
+        assert_eq!(cutoff.to_rfc3339(), "2025-01-01T18:04:05+00:00");
+    }
+
+    #[test]
+    fn parse_cutoff_rejects_plain_dates() {
+        assert!(parse_cutoff("2025-01-01").is_err());
+    }
+}
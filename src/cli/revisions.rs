@@ -0,0 +1,82 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Code revisions of workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, services, workflow_revisions};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum RevisionsCommand {
+    /// List the code revisions of a workflow with the summary of each change
+    List {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Also print the diff of each revision.
+        #[arg(long)]
+        diff: bool,
+    },
+
+    /// Store a copy of an earlier revision as the latest revision
+    Rollback {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Revision number to restore
+        code_revision: i32,
+    },
+}
+
+/// Runs a `revisions` subcommand.
+pub(super) async fn run(command: RevisionsCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    let service = services::MyWorkflowService::new(db);
+    match command {
+        RevisionsCommand::List { workflow_id, diff } => {
+            let revisions = service
+                .list_code_revisions(&workflow_id)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            for revision in revisions {
+                println!(
+                    "{:>4}  {}  created={}  {}",
+                    revision.code_revision,
+                    revision.workflow_code_id,
+                    revision
+                        .created_at
+                        .and_then(|at| {
+                            chrono::DateTime::from_timestamp(at.seconds, at.nanos as u32)
+                        })
+                        .map(|at| at.to_rfc3339())
+                        .unwrap_or_else(|| "-".to_string()),
+                    revision.summary.as_deref().unwrap_or("-")
+                );
+                if diff {
+                    print!("{}", workflow_revisions::render_diff(&revision.diff));
+                }
+            }
+        }
+        RevisionsCommand::Rollback {
+            workflow_id,
+            code_revision,
+        } => {
+            let code = service
+                .rollback_workflow_code(&workflow_id, code_revision)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            info!(
+                "Rolled back {workflow_id} to revision {code_revision} as revision {}",
+                code.code_revision
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Cron schedules that run workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, scheduler};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum SchedulesCommand {
+    /// Schedule a workflow; its latest code runs whenever the cron expression matches
+    Add {
+        /// ID of the workflow to run
+        workflow_id: String,
+
+        /// Five-field cron expression in UTC, e.g. "0 9 * * 1-5"
+        cron: String,
+    },
+
+    /// List schedules
+    List {
+        /// Only list schedules of this workflow
+        #[arg(long)]
+        workflow_id: Option<String>,
+    },
+
+    /// Remove a schedule
+    Delete {
+        /// ID of the schedule
+        schedule_id: String,
+    },
+}
+
+/// Runs a `schedules` subcommand.
+pub(super) async fn run(command: SchedulesCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        SchedulesCommand::Add { workflow_id, cron } => {
+            let schedule = scheduler::create_schedule(&db, &workflow_id, &cron).await?;
+            info!(
+                "Created schedule {} (next run: {})",
+                schedule.id,
+                schedule
+                    .next_run_at
+                    .map(|at| at.to_rfc3339())
+                    .unwrap_or_default()
+            );
+        }
+        SchedulesCommand::List { workflow_id } => {
+            for schedule in database::schedule::list_schedules(&db, workflow_id.as_deref()).await? {
+                println!(
+                    "{}  {}  {:<16} next={} enabled={}",
+                    schedule.id,
+                    schedule.workflow_id,
+                    schedule.cron,
+                    schedule
+                        .next_run_at
+                        .map(|at| at.to_rfc3339())
+                        .unwrap_or_else(|| "-".to_string()),
+                    schedule.enabled
+                );
+            }
+        }
+        SchedulesCommand::Delete { schedule_id } => {
+            if database::schedule::delete_schedule(&db, &schedule_id).await? == 0 {
+                anyhow::bail!("schedule not found: {schedule_id}");
+            }
+            info!("Deleted schedule: {schedule_id}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Secrets kept in the OS keyring, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum SecretsCommand {
+    /// Store a secret; the value is read from standard input
+    Set {
+        /// Name of the secret, e.g. smtp.password
+        name: String,
+
+        /// Store the secret for the workflows of this user instead of shared workflows
+        #[arg(long)]
+        owner: Option<String>,
+    },
+
+    /// Remove a secret
+    Delete {
+        /// Name of the secret
+        name: String,
+
+        /// Remove the secret of this user instead of the shared one
+        #[arg(long)]
+        owner: Option<String>,
+    },
+}
+
+/// Runs a `secrets` subcommand.
+pub(super) async fn run(command: SecretsCommand) -> Result<()> {
+    match command {
+        SecretsCommand::Set { name, owner } => {
+            // Read from stdin so the value does not end up in shell history.
+            let mut value = String::new();
+            std::io::stdin().read_line(&mut value)?;
+            let value = value.trim_end_matches(['\r', '\n']);
+            match &owner {
+                Some(owner) => secrets::set_owned_secret(owner, &name, value)?,
+                None => secrets::set_secret(&name, value)?,
+            }
+            info!(
+                "Stored secret: {}",
+                secrets::owned_secret_name(owner.as_deref(), &name)
+            );
+        }
+        SecretsCommand::Delete { name, owner } => {
+            let name = secrets::owned_secret_name(owner.as_deref(), &name);
+            secrets::delete_secret(&name)?;
+            info!("Deleted secret: {name}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Key/value state kept by workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum StateCommand {
+    /// List the state entries of a workflow
+    List {
+        /// ID of the workflow
+        workflow_id: String,
+    },
+
+    /// Clear the state of a workflow
+    Clear {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Only remove this key
+        #[arg(long)]
+        key: Option<String>,
+    },
+}
+
+/// Runs a `state` subcommand.
+pub(super) async fn run(command: StateCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        StateCommand::List { workflow_id } => {
+            for entry in database::state::list_state(&db, &workflow_id).await? {
+                println!(
+                    "{:<24} {} (updated: {})",
+                    entry.key,
+                    entry.value,
+                    entry
+                        .updated_at
+                        .map(|at| at.to_rfc3339())
+                        .unwrap_or_else(|| "-".to_string())
+                );
+            }
+        }
+        StateCommand::Clear { workflow_id, key } => {
+            let deleted = database::state::delete_state(&db, &workflow_id, key.as_deref()).await?;
+            info!("Deleted {deleted} state entries of workflow {workflow_id}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// API tokens accepted by `--auth token`, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::args::TokenScope;
+use crate::{GLOBAL_STATE, auth, init};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum TokensCommand {
+    /// Create a token and print it; it cannot be shown again
+    Create {
+        /// Unique name of the token, e.g. ci
+        name: String,
+
+        /// Scope granted to the token; repeat for several
+        #[arg(long = "scope", value_enum, required = true)]
+        scopes: Vec<TokenScope>,
+    },
+
+    /// List tokens with their scopes
+    List,
+
+    /// Revoke a token; a running server stops accepting it within 30 seconds
+    Revoke {
+        /// Name of the token
+        name: String,
+    },
+}
+
+/// Runs a `tokens` subcommand.
+pub(super) async fn run(command: TokensCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        TokensCommand::Create { name, scopes } => {
+            let token = auth::generate_token();
+            let scopes: Vec<String> = scopes
+                .into_iter()
+                .map(|scope| auth::scope_name(scope).to_string())
+                .collect();
+            database::api_token::create_api_token(&db, &name, &auth::hash_token(&token), &scopes)
+                .await
+                .map_err(|err| anyhow::anyhow!("cannot create token {name}: {err}"))?;
+            info!("Created token {name} with scopes: {}", scopes.join(" "));
+            // The token is only stored hashed, so this is the only time it is shown.
+            println!("{token}");
+        }
+        TokensCommand::List => {
+            for token in database::api_token::list_api_tokens(&db).await? {
+                println!(
+                    "{:<24} {:<24} created={}",
+                    token.id,
+                    token.scopes,
+                    token
+                        .created_at
+                        .map(|at| at.to_rfc3339())
+                        .unwrap_or_else(|| "-".to_string())
+                );
+            }
+        }
+        TokensCommand::Revoke { name } => {
+            if database::api_token::delete_api_token(&db, &name).await? == 0 {
+                anyhow::bail!("token not found: {name}");
+            }
+            info!("Revoked token: {name}");
+        }
+    }
+    Ok(())
+}
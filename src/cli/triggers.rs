@@ -0,0 +1,134 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Event triggers that run workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, triggers};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum TriggersCommand {
+    /// Run a workflow whenever a file or directory changes
+    AddFile {
+        /// ID of the workflow to run
+        workflow_id: String,
+
+        /// File or directory to watch
+        path: String,
+
+        /// Also watch subdirectories
+        #[arg(long)]
+        recursive: bool,
+    },
+
+    /// Run a workflow whenever a webhook URL is called; the URL is printed on success
+    AddWebhook {
+        /// ID of the workflow to run
+        workflow_id: String,
+    },
+
+    /// Run a workflow whenever the active window changes
+    AddWindow {
+        /// ID of the workflow to run
+        workflow_id: String,
+
+        /// Only fire when the new window title contains this text (case-insensitive)
+        #[arg(long)]
+        title_contains: Option<String>,
+    },
+
+    /// List triggers
+    List {
+        /// Only list triggers of this workflow
+        #[arg(long)]
+        workflow_id: Option<String>,
+    },
+
+    /// Resume a disabled trigger
+    Enable {
+        /// ID of the trigger
+        trigger_id: String,
+    },
+
+    /// Stop a trigger without removing it
+    Disable {
+        /// ID of the trigger
+        trigger_id: String,
+    },
+
+    /// Remove a trigger
+    Delete {
+        /// ID of the trigger
+        trigger_id: String,
+    },
+}
+
+/// Runs a `triggers` subcommand. `webhook_addr` is the `--webhook-addr` the printed
+/// webhook URLs point at.
+pub(super) async fn run(command: TriggersCommand, webhook_addr: Option<&str>) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    match command {
+        TriggersCommand::AddFile {
+            workflow_id,
+            path,
+            recursive,
+        } => {
+            let spec = triggers::TriggerSpec::File { path, recursive };
+            let trigger = triggers::create_trigger(&db, &workflow_id, &spec).await?;
+            info!("Created trigger {}", trigger.id);
+        }
+        TriggersCommand::AddWebhook { workflow_id } => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            let spec = triggers::TriggerSpec::Webhook {
+                token: token.clone(),
+            };
+            let trigger = triggers::create_trigger(&db, &workflow_id, &spec).await?;
+            info!("Created trigger {}", trigger.id);
+            match webhook_addr {
+                Some(addr) => println!("http://{addr}/hooks/{token}"),
+                None => {
+                    println!("/hooks/{token}");
+                    warn!("Webhooks are only served by a server started with --webhook-addr");
+                }
+            }
+        }
+        TriggersCommand::AddWindow {
+            workflow_id,
+            title_contains,
+        } => {
+            let spec = triggers::TriggerSpec::Window { title_contains };
+            let trigger = triggers::create_trigger(&db, &workflow_id, &spec).await?;
+            info!("Created trigger {}", trigger.id);
+        }
+        TriggersCommand::List { workflow_id } => {
+            for trigger in database::trigger::list_triggers(&db, workflow_id.as_deref()).await? {
+                println!(
+                    "{}  {}  {:<8} {} enabled={}",
+                    trigger.id, trigger.workflow_id, trigger.kind, trigger.config, trigger.enabled
+                );
+            }
+        }
+        TriggersCommand::Enable { trigger_id } => {
+            database::trigger::set_trigger_enabled(&db, &trigger_id, true).await?;
+            info!("Enabled trigger: {trigger_id}");
+        }
+        TriggersCommand::Disable { trigger_id } => {
+            database::trigger::set_trigger_enabled(&db, &trigger_id, false).await?;
+            info!("Disabled trigger: {trigger_id}");
+        }
+        TriggersCommand::Delete { trigger_id } => {
+            if database::trigger::delete_trigger(&db, &trigger_id).await? == 0 {
+                anyhow::bail!("trigger not found: {trigger_id}");
+            }
+            info!("Deleted trigger: {trigger_id}");
+        }
+    }
+    Ok(())
+}
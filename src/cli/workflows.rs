@@ -0,0 +1,168 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Tags, folders and search of workflows, from the command line
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{GLOBAL_STATE, init, services};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Subcommand, Debug)]
+pub enum WorkflowsCommand {
+    /// List workflows with their folder and tags
+    List {
+        /// Only list workflows with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only list workflows in this folder or its subfolders, e.g. work/reports
+        #[arg(long)]
+        folder: Option<String>,
+    },
+
+    /// Replace the tags of a workflow; give no tags to remove them all
+    Tag {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// The new tags
+        tags: Vec<String>,
+    },
+
+    /// Move a workflow to a folder; give no folder to move it to the top level
+    Move {
+        /// ID of the workflow
+        workflow_id: String,
+
+        /// Slash-separated folder path, e.g. work/reports
+        folder: Option<String>,
+    },
+
+    /// Search workflow names and descriptions, code and result output
+    Search {
+        /// Words that must all occur in a hit
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Maximum number of hits, at most 100
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+    },
+}
+
+/// Runs a `workflows` subcommand.
+pub(super) async fn run(command: WorkflowsCommand) -> Result<()> {
+    init::setup_database().await?;
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    let service = services::MyWorkflowService::new(db);
+    match command {
+        WorkflowsCommand::List { tag, folder } => {
+            let workflows = service
+                .list_organized_workflows(tag.as_deref(), folder.as_deref(), None)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            for workflow in workflows {
+                println!(
+                    "{}  {}  folder={}  tags={}",
+                    workflow.workflow_id,
+                    workflow.display_name,
+                    workflow.folder.as_deref().unwrap_or("/"),
+                    workflow.tags.join(",")
+                );
+            }
+        }
+        WorkflowsCommand::Tag { workflow_id, tags } => {
+            service
+                .set_workflow_tags(&workflow_id, &tags)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+        }
+        WorkflowsCommand::Move {
+            workflow_id,
+            folder,
+        } => {
+            service
+                .move_workflow_to_folder(&workflow_id, folder.as_deref().unwrap_or(""))
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+        }
+        WorkflowsCommand::Search { query, limit } => {
+            let hits = service
+                .search_workflow_content(&query.join(" "), limit, None)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}", status.message()))?;
+            for hit in hits {
+                println!(
+                    "{}  {} {}  {:.3}  {}",
+                    hit.workflow_id,
+                    hit.kind,
+                    hit.source_id,
+                    hit.score,
+                    hit.snippet.replace('\n', " ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs a stored workflow without side effects and prints the calls it would make.
+pub(super) async fn dry_run(workflow_id: String, workflow_code_id: Option<String>) -> Result<()> {
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    let plan = services::MyWorkflowService::new(db)
+        .plan_workflow(&workflow_id, workflow_code_id.as_deref(), None)
+        .await
+        .map_err(|status| anyhow::anyhow!("dry run failed: {}", status.message()))?;
+
+    print!("{}", plan.result.result);
+    if plan.actions.is_empty() {
+        info!("The workflow would not make any plugin call that needs a permission");
+    }
+    for action in plan.actions {
+        println!(
+            "would call {}({})",
+            action.function,
+            action
+                .args
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Prints what a stored workflow does and how risky its permissions are.
+pub(super) async fn explain(
+    workflow_id: String,
+    workflow_code_id: Option<String>,
+    model: Option<String>,
+) -> Result<()> {
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    let explanation = services::MyWorkflowService::new(db)
+        .explain_workflow(&workflow_id, workflow_code_id.as_deref(), model.as_deref())
+        .await
+        .map_err(|status| anyhow::anyhow!("explanation failed: {}", status.message()))?;
+
+    println!("Workflow code {}", explanation.workflow_code_id);
+    for (index, step) in explanation.steps.iter().enumerate() {
+        println!("{:>3}. {step}", index + 1);
+    }
+    println!("Risk: {}", explanation.risk.level.as_str());
+    for reason in &explanation.risk.reasons {
+        println!("  {reason}");
+    }
+    if !explanation.permissions.missing.is_empty() {
+        println!(
+            "{} permission(s) are not allowed yet; see `permissions preview {workflow_id}`",
+            explanation.permissions.missing.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Connection pool settings shared by every database connection
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use sea_orm::sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbBackend, DbErr};
+
+/// Pool settings; unset fields keep the driver defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct PoolConfig {
+    /// Maximum open connections per pool.
+    pub max_connections: Option<u32>,
+    /// Connections a pool keeps open even when idle.
+    pub min_connections: Option<u32>,
+    /// Seconds to wait for a connection to open or to become free in the pool.
+    pub connect_timeout_secs: Option<u64>,
+    /// Seconds an unused connection stays open before it is closed.
+    pub idle_timeout_secs: Option<u64>,
+    /// Seconds a SQLite statement waits for another connection's write to finish;
+    /// [`DEFAULT_SQLITE_BUSY_TIMEOUT`] when unset.
+    pub sqlite_busy_timeout_secs: Option<u64>,
+}
+
+/// How long a SQLite statement waits for the write lock unless configured otherwise.
+pub(crate) const DEFAULT_SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+static POOL_CONFIG: OnceLock<PoolConfig> = OnceLock::new();
+
+/// Sets the pool settings of connections opened afterwards. Only the first call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the settings were applied, `false` if they were already set.
+pub(crate) fn set_pool_config(config: PoolConfig) -> bool {
+    POOL_CONFIG.set(config).is_ok()
+}
+
+/// Returns the backend a database URL is for, or `None` when it is not supported.
+pub(crate) fn backend_of(db_url: &str) -> Option<DbBackend> {
+    let scheme = db_url.split(':').next()?.to_ascii_lowercase();
+    match scheme.as_str() {
+        "sqlite" => Some(DbBackend::Sqlite),
+        "postgres" | "postgresql" => Some(DbBackend::Postgres),
+        "mysql" | "mariadb" => Some(DbBackend::MySql),
+        _ => None,
+    }
+}
+
+/// Whether `db_url` names an in-memory SQLite database, which no other process can open.
+pub(crate) fn is_in_memory(db_url: &str) -> bool {
+    if backend_of(db_url) != Some(DbBackend::Sqlite) {
+        return false;
+    }
+    // Tolerate `sqlite::memory:`, `sqlite://:memory:` and
+    // `sqlite:file::memory:?mode=memory&cache=shared`.
+    let path_part = db_url
+        .trim_start_matches("sqlite:")
+        .strip_prefix("//")
+        .unwrap_or_else(|| db_url.trim_start_matches("sqlite:"));
+    path_part.starts_with(":memory:")
+        || path_part.starts_with("file::memory:")
+        || db_url.contains("mode=memory")
+}
+
+fn connect_options(db_url: &str, config: PoolConfig) -> ConnectOptions {
+    let mut options = ConnectOptions::new(db_url);
+    if let Some(max) = config.max_connections {
+        options.max_connections(max);
+    }
+    if let Some(min) = config.min_connections {
+        options.min_connections(min);
+    }
+    if let Some(secs) = config.connect_timeout_secs {
+        options
+            .connect_timeout(Duration::from_secs(secs))
+            .acquire_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = config.idle_timeout_secs {
+        options.idle_timeout(Duration::from_secs(secs));
+    }
+    if backend_of(db_url) == Some(DbBackend::Sqlite) {
+        let busy_timeout = config
+            .sqlite_busy_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SQLITE_BUSY_TIMEOUT);
+        // WAL lets reads proceed while a write is in progress; in-memory databases keep
+        // their own journal mode.
+        options.map_sqlx_sqlite_opts(move |sqlite| {
+            sqlite
+                .journal_mode(SqliteJournalMode::Wal)
+                .synchronous(SqliteSynchronous::Normal)
+                .busy_timeout(busy_timeout)
+        });
+    }
+    options
+}
+
+/// Opens a connection pool to `db_url` with the configured pool settings.
+pub(crate) async fn connect(db_url: &str) -> Result<DatabaseConnection, DbErr> {
+    let config = POOL_CONFIG.get().copied().unwrap_or_default();
+    Database::connect(connect_options(db_url, config)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_follows_url_scheme() {
+        assert_eq!(
+            backend_of("sqlite:file::memory:?mode=memory&cache=shared"),
+            Some(DbBackend::Sqlite)
+        );
+        assert_eq!(
+            backend_of("postgresql://app@db.internal/sapphillon"),
+            Some(DbBackend::Postgres)
+        );
+        assert_eq!(
+            backend_of("postgres://localhost/sapphillon"),
+            Some(DbBackend::Postgres)
+        );
+        assert_eq!(
+            backend_of("mysql://root@localhost/sapphillon"),
+            Some(DbBackend::MySql)
+        );
+        assert_eq!(backend_of("mssql://localhost/sapphillon"), None);
+    }
+
+    #[test]
+    fn in_memory_urls_are_recognized() {
+        assert!(is_in_memory(
+            "sqlite:file::memory:?mode=memory&cache=shared"
+        ));
+        assert!(is_in_memory("sqlite::memory:"));
+        assert!(is_in_memory("sqlite://:memory:"));
+        assert!(is_in_memory(
+            "sqlite:file:sapphillon?mode=memory&cache=shared"
+        ));
+        assert!(!is_in_memory("sqlite://sapphillon.db"));
+        assert!(!is_in_memory("postgres://localhost/sapphillon"));
+    }
+
+    #[test]
+    fn connect_options_apply_only_configured_settings() {
+        let options = connect_options(
+            "postgres://localhost/sapphillon",
+            PoolConfig {
+                max_connections: Some(20),
+                connect_timeout_secs: Some(5),
+                ..PoolConfig::default()
+            },
+        );
+        assert_eq!(options.get_max_connections(), Some(20));
+        assert_eq!(options.get_min_connections(), None);
+        assert_eq!(options.get_connect_timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(options.get_acquire_timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(options.get_idle_timeout(), None);
+    }
+}
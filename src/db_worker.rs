@@ -0,0 +1,70 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Dedicated database thread for synchronous callers such as plugin ops
+
+use anyhow::Context;
+use sea_orm::DatabaseConnection;
+use std::sync::mpsc;
+use tokio::runtime::Runtime;
+
+/// Spawns a thread with its own runtime and connection that passes every request sent
+/// on the returned channel to `handle`.
+///
+/// Plugin ops are synchronous and run inside the workflow's runtime, where they cannot
+/// await, so stores backing them send requests here and block on a reply channel.
+///
+/// # Arguments
+///
+/// * `name` - Thread name, also used in error messages.
+/// * `db_url` - Database URL to connect to.
+/// * `handle` - Serves one request; use `runtime.block_on` to run queries.
+///
+/// # Returns
+///
+/// Returns the request sender once the connection is established.
+pub(crate) fn spawn_db_worker<R, F>(
+    name: &str,
+    db_url: String,
+    mut handle: F,
+) -> anyhow::Result<mpsc::Sender<R>>
+where
+    R: Send + 'static,
+    F: FnMut(&Runtime, &DatabaseConnection, R) + Send + 'static,
+{
+    let (requests, receiver) = mpsc::channel::<R>();
+    let (ready_tx, ready_rx) = mpsc::channel::<anyhow::Result<()>>();
+
+    std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.into()));
+                    return;
+                }
+            };
+            let db = match runtime.block_on(crate::db_pool::connect(&db_url)) {
+                Ok(db) => db,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.into()));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            for request in receiver {
+                handle(&runtime, &db, request);
+            }
+        })?;
+
+    ready_rx
+        .recv()
+        .with_context(|| format!("{name} thread exited during startup"))??;
+    Ok(requests)
+}
@@ -0,0 +1,299 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// In-process event bus that frontends subscribe to instead of polling
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+use tokio::sync::broadcast;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Events kept for a subscriber that falls behind before it misses some.
+const EVENT_BUFFER: usize = 1024;
+
+/// [`Event::kind`] of every event, as named in `SubscribeEventsRequest.types`.
+pub(crate) const EVENT_TYPES: [&str; 5] = [
+    "workflow_started",
+    "workflow_finished",
+    "schedule_fired",
+    "permission_requested",
+    "plugin_installed",
+];
+
+/// Something that happened in the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Event {
+    WorkflowStarted {
+        workflow_id: String,
+        workflow_code_id: String,
+    },
+    WorkflowFinished {
+        workflow_id: String,
+        workflow_code_id: String,
+        succeeded: bool,
+        error: Option<String>,
+    },
+    ScheduleFired {
+        schedule_id: String,
+        workflow_id: String,
+    },
+    PermissionRequested {
+        workflow_id: String,
+        workflow_code_id: String,
+        plugin_function_id: String,
+        /// Prompt that answers the request.
+        prompt_id: String,
+    },
+    PluginInstalled {
+        plugin_package_id: String,
+    },
+}
+
+impl Event {
+    /// The type of the event, as named in the `event` oneof of `SubscribeEventsResponse`.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Event::WorkflowStarted { .. } => "workflow_started",
+            Event::WorkflowFinished { .. } => "workflow_finished",
+            Event::ScheduleFired { .. } => "schedule_fired",
+            Event::PermissionRequested { .. } => "permission_requested",
+            Event::PluginInstalled { .. } => "plugin_installed",
+        }
+    }
+
+    /// The workflow the event is about, if any.
+    pub(crate) fn workflow_id(&self) -> Option<&str> {
+        match self {
+            Event::WorkflowStarted { workflow_id, .. }
+            | Event::WorkflowFinished { workflow_id, .. }
+            | Event::ScheduleFired { workflow_id, .. }
+            | Event::PermissionRequested { workflow_id, .. } => Some(workflow_id),
+            Event::PluginInstalled { .. } => None,
+        }
+    }
+}
+
+/// An event as delivered to subscribers.
+#[derive(Debug, Clone)]
+pub(crate) struct PublishedEvent {
+    pub at: SystemTime,
+    /// Owner of the workflow the event is about; `None` for shared workflows and events
+    /// about the whole server.
+    pub owner_id: Option<String>,
+    pub event: Event,
+}
+
+/// Selects the events a subscriber receives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct EventFilter {
+    /// Event types to receive; empty receives every type.
+    pub types: Vec<String>,
+    /// Only receive events about this workflow.
+    pub workflow_id: Option<String>,
+}
+
+impl EventFilter {
+    /// Builds a filter from a list of event types and a workflow ID.
+    ///
+    /// # Returns
+    ///
+    /// Returns the filter, or an error naming the first unknown event type.
+    pub(crate) fn parse(types: &[String], workflow_id: Option<String>) -> Result<Self, String> {
+        let mut parsed = Vec::new();
+        for kind in types.iter().map(|kind| kind.trim()) {
+            if kind.is_empty() {
+                continue;
+            }
+            if !EVENT_TYPES.contains(&kind) {
+                return Err(format!(
+                    "unknown event type '{kind}'; expected one of {}",
+                    EVENT_TYPES.join(", ")
+                ));
+            }
+            parsed.push(kind.to_string());
+        }
+        Ok(Self {
+            types: parsed,
+            workflow_id: workflow_id.filter(|id| !id.is_empty()),
+        })
+    }
+
+    pub(crate) fn matches(&self, event: &Event) -> bool {
+        let type_matches = self.types.is_empty() || self.types.iter().any(|t| t == event.kind());
+        let workflow_matches = self
+            .workflow_id
+            .as_deref()
+            .is_none_or(|id| event.workflow_id() == Some(id));
+        type_matches && workflow_matches
+    }
+}
+
+struct EventBus {
+    sender: broadcast::Sender<PublishedEvent>,
+    /// Owners of the workflows that are running, so events raised during a run without
+    /// access to the database, such as permission requests, reach the right subscribers.
+    running: Mutex<HashMap<String, (Option<String>, usize)>>,
+}
+
+static BUS: LazyLock<EventBus> = LazyLock::new(|| EventBus {
+    sender: broadcast::channel(EVENT_BUFFER).0,
+    running: Mutex::new(HashMap::new()),
+});
+
+/// Sends `event` to every subscriber. Events published without subscribers are dropped.
+pub(crate) fn publish(event: Event, owner_id: Option<String>) {
+    debug!("Event: {event:?}");
+    let _ = BUS.sender.send(PublishedEvent {
+        at: SystemTime::now(),
+        owner_id,
+        event,
+    });
+}
+
+/// Publishes an event raised while `event.workflow_id()` runs, on behalf of the owner the
+/// run was started for.
+pub(crate) fn publish_for_running_workflow(event: Event) {
+    let owner_id = event.workflow_id().and_then(|workflow_id| {
+        let running = BUS.running.lock().unwrap_or_else(|e| e.into_inner());
+        running
+            .get(workflow_id)
+            .and_then(|(owner_id, _)| owner_id.clone())
+    });
+    publish(event, owner_id);
+}
+
+/// Publishes `WorkflowStarted` and remembers the owner of the run until
+/// [`workflow_finished`] is called for it.
+pub(crate) fn workflow_started(
+    workflow_id: &str,
+    workflow_code_id: &str,
+    owner_id: Option<String>,
+) {
+    {
+        let mut running = BUS.running.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = running
+            .entry(workflow_id.to_string())
+            .or_insert_with(|| (owner_id.clone(), 0));
+        entry.1 += 1;
+    }
+    publish(
+        Event::WorkflowStarted {
+            workflow_id: workflow_id.to_string(),
+            workflow_code_id: workflow_code_id.to_string(),
+        },
+        owner_id,
+    );
+}
+
+/// Publishes `WorkflowFinished` for a run announced with [`workflow_started`].
+pub(crate) fn workflow_finished(
+    workflow_id: &str,
+    workflow_code_id: &str,
+    owner_id: Option<String>,
+    error: Option<String>,
+) {
+    {
+        let mut running = BUS.running.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = running.get_mut(workflow_id) {
+            entry.1 = entry.1.saturating_sub(1);
+            if entry.1 == 0 {
+                running.remove(workflow_id);
+            }
+        }
+    }
+    publish(
+        Event::WorkflowFinished {
+            workflow_id: workflow_id.to_string(),
+            workflow_code_id: workflow_code_id.to_string(),
+            succeeded: error.is_none(),
+            error,
+        },
+        owner_id,
+    );
+}
+
+/// Subscribes to the events published from now on.
+pub(crate) fn subscribe() -> broadcast::Receiver<PublishedEvent> {
+    BUS.sender.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_select_types_and_workflows() {
+        let started = Event::WorkflowStarted {
+            workflow_id: "wf".to_string(),
+            workflow_code_id: "wc".to_string(),
+        };
+        let installed = Event::PluginInstalled {
+            plugin_package_id: "pkg".to_string(),
+        };
+        assert!(EventFilter::default().matches(&started));
+
+        let by_type = EventFilter {
+            types: vec!["plugin_installed".to_string()],
+            workflow_id: None,
+        };
+        assert!(!by_type.matches(&started));
+        assert!(by_type.matches(&installed));
+
+        let by_workflow = EventFilter {
+            types: Vec::new(),
+            workflow_id: Some("wf".to_string()),
+        };
+        assert!(by_workflow.matches(&started));
+        assert!(!by_workflow.matches(&installed));
+    }
+
+    #[test]
+    fn filters_parse_type_lists() {
+        let types = ["workflow_started", " workflow_finished", ""].map(String::from);
+        let filter = EventFilter::parse(&types, Some("wf".to_string())).unwrap();
+        assert_eq!(filter.types, vec!["workflow_started", "workflow_finished"]);
+        assert_eq!(filter.workflow_id.as_deref(), Some("wf"));
+
+        assert_eq!(
+            EventFilter::parse(&[], Some(String::new())).unwrap(),
+            EventFilter::default()
+        );
+        let err = EventFilter::parse(&["workflow_deleted".to_string()], None).unwrap_err();
+        assert!(err.contains("workflow_deleted"));
+    }
+
+    #[tokio::test]
+    async fn events_during_a_run_carry_its_owner() {
+        let mut events = subscribe();
+        workflow_started("events-test-wf", "wc", Some("alice".to_string()));
+        publish_for_running_workflow(Event::PermissionRequested {
+            workflow_id: "events-test-wf".to_string(),
+            workflow_code_id: "wc".to_string(),
+            plugin_function_id: "fs.read".to_string(),
+            prompt_id: "prompt".to_string(),
+        });
+        workflow_finished("events-test-wf", "wc", Some("alice".to_string()), None);
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            // Other tests may publish on the same bus.
+            if event.event.workflow_id() == Some("events-test-wf") {
+                received.push((event.event.kind(), event.owner_id));
+            }
+        }
+        let alice = Some("alice".to_string());
+        assert_eq!(
+            received,
+            vec![
+                ("workflow_started", alice.clone()),
+                ("permission_requested", alice.clone()),
+                ("workflow_finished", alice),
+            ]
+        );
+        assert!(!BUS.running.lock().unwrap().contains_key("events-test-wf"));
+    }
+}
@@ -0,0 +1,296 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Bundled example workflows.
+//!
+//! Each example is a small JS workflow run against mock backends (a temporary
+//! working directory, a local HTTP server and a fixture-backed search) through
+//! the real plugin runtime and permission checks. `sapphillon examples run`
+//! uses them as a smoke test, and they double as reference workflows for the
+//! generator's few-shot prompts.
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
+use sapphillon_core::proto::sapphillon::v1::{Permission, PermissionLevel, PermissionType};
+use sapphillon_core::workflow::CoreWorkflowCode;
+use tokio::runtime::Handle;
+
+const MOCK_PRELUDE: &str = include_str!("examples/mocks.js");
+const MOCK_TODO_JSON: &str = r#"{"id":1,"title":"Buy milk","done":false}"#;
+const REPORT_CONTENTS: &str = "# Weekly report\n\nAll systems nominal.\n";
+
+/// What a successful run of an example looks like.
+#[derive(Debug, Clone, Copy)]
+pub enum Expectation {
+    /// The workflow output must contain every listed string.
+    Output(&'static [&'static str]),
+    /// The workflow must fail, e.g. because a permission was not granted.
+    Error,
+}
+
+/// A bundled example workflow.
+#[derive(Clone, Copy)]
+pub struct Example {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub code: &'static str,
+    pub expectation: Expectation,
+    /// Grants for the example, scoped to the paths and URLs in the mock environment.
+    permissions: fn(&ExampleEnv) -> Vec<PluginFunctionPermissions>,
+}
+
+/// Paths and URLs of the mock backends, exposed to workflows as `example`.
+pub struct ExampleEnv {
+    pub workdir: String,
+    pub http_base: String,
+}
+
+impl ExampleEnv {
+    fn file(&self, name: &str) -> String {
+        format!("{}/{name}", self.workdir)
+    }
+}
+
+/// Returns the registry of bundled examples.
+///
+/// # Returns
+///
+/// Returns every example in the order `examples run` executes them.
+pub fn examples() -> Vec<Example> {
+    vec![
+        Example {
+            name: "filesystem-notes",
+            description: "Writes a note to disk and reads it back.",
+            code: include_str!("examples/filesystem_notes.js"),
+            expectation: Expectation::Output(&["hello from sapphillon"]),
+            permissions: |env| {
+                let path = env.file("notes.txt");
+                vec![
+                    grant(
+                        filesystem::filesystem_write_plugin_function().function_id,
+                        PermissionType::FilesystemWrite,
+                        &path,
+                    ),
+                    grant(
+                        filesystem::filesystem_read_plugin_function().function_id,
+                        PermissionType::FilesystemRead,
+                        &path,
+                    ),
+                ]
+            },
+        },
+        Example {
+            name: "fetch-json",
+            description: "Fetches a JSON document and prints one field.",
+            code: include_str!("examples/fetch_json.js"),
+            expectation: Expectation::Output(&["Buy milk"]),
+            permissions: |env| {
+                vec![grant(
+                    fetch::fetch_plugin_function().function_id,
+                    PermissionType::NetAccess,
+                    &format!("{}/todo.json", env.http_base),
+                )]
+            },
+        },
+        Example {
+            name: "search-and-read",
+            description: "Finds a report with file search and prints its title.",
+            code: include_str!("examples/search_and_read.js"),
+            expectation: Expectation::Output(&["# Weekly report"]),
+            permissions: |env| {
+                vec![grant(
+                    filesystem::filesystem_read_plugin_function().function_id,
+                    PermissionType::FilesystemRead,
+                    &env.file("report.md"),
+                )]
+            },
+        },
+        Example {
+            name: "csv-summary",
+            description: "Parses CSV text and totals a column.",
+            code: include_str!("examples/csv_summary.js"),
+            expectation: Expectation::Output(&["total=6"]),
+            permissions: |_| vec![],
+        },
+        Example {
+            name: "permission-denied",
+            description: "Reads a file without a grant and must be rejected.",
+            code: include_str!("examples/permission_denied.js"),
+            expectation: Expectation::Error,
+            permissions: |_| vec![],
+        },
+    ]
+}
+
+/// Outcome of a single example run.
+#[derive(Debug)]
+pub struct ExampleOutcome {
+    pub name: &'static str,
+    pub passed: bool,
+    pub output: String,
+}
+
+/// Runs the bundled examples, optionally only the one named `filter`.
+///
+/// # Arguments
+///
+/// * `filter` - Name of a single example to run; all examples run when `None`.
+///
+/// # Returns
+///
+/// Returns the outcome of every example that ran, or an error if the mock
+/// environment could not be prepared or `filter` matches no example.
+pub fn run_examples(filter: Option<&str>) -> Result<Vec<ExampleOutcome>> {
+    let selected: Vec<Example> = examples()
+        .into_iter()
+        .filter(|example| filter.is_none_or(|name| example.name == name))
+        .collect();
+    if selected.is_empty() {
+        anyhow::bail!("unknown example: {}", filter.unwrap_or_default());
+    }
+
+    let workdir = tempfile::tempdir().context("failed to create example working directory")?;
+    let env = ExampleEnv {
+        workdir: workdir.path().to_string_lossy().replace('\\', "/"),
+        http_base: start_mock_http_server()?,
+    };
+    write_fixtures(workdir.path())?;
+
+    Ok(selected
+        .into_iter()
+        .map(|example| run_example(&example, &env))
+        .collect())
+}
+
+fn run_example(example: &Example, env: &ExampleEnv) -> ExampleOutcome {
+    let context = serde_json::json!({
+        "workdir": env.workdir,
+        "httpBase": env.http_base,
+        "searchIndex": [env.file("notes.txt"), env.file("report.md")],
+    });
+    let code = format!(
+        "globalThis.example = {context};\n{MOCK_PRELUDE}\n{}",
+        example.code
+    );
+
+    let permissions = (example.permissions)(env);
+    let sysconfig = crate::sysconfig::sysconfig();
+    let mut workflow = CoreWorkflowCode::new(
+        example.name.to_string(),
+        code,
+        sysconfig.core_plugin_package,
+        1,
+        permissions.clone(),
+        permissions,
+    );
+    workflow.run(
+        Handle::current(),
+        sysconfig.external_plugin_runner_path,
+        Some(sysconfig.external_plugin_runner_args),
+    );
+
+    let output = workflow
+        .result
+        .last()
+        .map(|result| result.result.clone())
+        .unwrap_or_default();
+    let failed = output.contains("Uncaught");
+    let passed = match example.expectation {
+        Expectation::Output(expected) => {
+            !failed && expected.iter().all(|needle| output.contains(needle))
+        }
+        Expectation::Error => failed,
+    };
+
+    if passed {
+        info!("PASS {}", example.name);
+    } else {
+        warn!("FAIL {}: {}", example.name, output.trim_end());
+    }
+    ExampleOutcome {
+        name: example.name,
+        passed,
+        output,
+    }
+}
+
+fn write_fixtures(workdir: &Path) -> Result<()> {
+    std::fs::write(workdir.join("report.md"), REPORT_CONTENTS)?;
+    std::fs::write(workdir.join("secret.txt"), "do not read")?;
+    Ok(())
+}
+
+/// Starts a tiny HTTP server on a background thread that answers every request
+/// with the same JSON document. A plain thread is used because workflow runs
+/// block the async runtime's thread.
+fn start_mock_http_server() -> Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("failed to start mock HTTP server")?;
+    let addr = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{MOCK_TODO_JSON}",
+                MOCK_TODO_JSON.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    Ok(format!("http://{addr}"))
+}
+
+fn grant(
+    plugin_function_id: String,
+    permission_type: PermissionType,
+    resource: &str,
+) -> PluginFunctionPermissions {
+    PluginFunctionPermissions {
+        plugin_function_id,
+        permissions: Permissions {
+            permissions: vec![Permission {
+                display_name: "Example".to_string(),
+                description: "Grant for a bundled example workflow".to_string(),
+                permission_type: permission_type as i32,
+                permission_level: PermissionLevel::Unspecified as i32,
+                resource: vec![resource.to_string()],
+            }],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_names_are_unique() {
+        let mut names: Vec<_> = examples().iter().map(|example| example.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), examples().len());
+    }
+
+    #[test]
+    fn unknown_example_is_an_error() {
+        assert!(run_examples(Some("does-not-exist")).is_err());
+    }
+
+    #[tokio::test]
+    async fn bundled_examples_pass() {
+        let outcomes = run_examples(None).unwrap();
+        for outcome in &outcomes {
+            assert!(
+                outcome.passed,
+                "{} failed: {}",
+                outcome.name, outcome.output
+            );
+        }
+    }
+}
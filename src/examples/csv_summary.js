@@ -0,0 +1,4 @@
+// Parse CSV text and total one of its columns.
+const rows = app.sapphillon.core.csv.parse("item,qty\napple,1\npear,2\nplum,3\n");
+const total = rows.reduce((sum, row) => sum + Number(row.qty), 0);
+console.log("total=" + total);
@@ -0,0 +1,4 @@
+// Fetch a JSON document and print one of its fields.
+const body = app.sapphillon.core.fetch.fetch(example.httpBase + "/todo.json");
+const todo = JSON.parse(body);
+console.log(todo.title);
@@ -0,0 +1,4 @@
+// Write a note to disk, then read it back.
+const path = example.workdir + "/notes.txt";
+app.sapphillon.core.filesystem.write(path, "hello from sapphillon");
+console.log(app.sapphillon.core.filesystem.read(path));
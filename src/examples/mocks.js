@@ -0,0 +1,11 @@
+// Mock backends injected in front of every bundled example.
+//
+// `example` carries the paths and URLs prepared by the runner. Native OS search
+// is replaced with a lookup over the fixture files so examples behave the same
+// on every machine.
+globalThis.app.sapphillon.core.search.file = function (rootPath, query) {
+    const matches = example.searchIndex.filter(
+        (path) => path.startsWith(rootPath) && path.includes(query),
+    );
+    return JSON.stringify(matches);
+};
@@ -0,0 +1,2 @@
+// Reading a file the workflow was not granted must fail.
+console.log(app.sapphillon.core.filesystem.read(example.workdir + "/secret.txt"));
@@ -0,0 +1,4 @@
+// Find a report by name and print its first line.
+const hits = JSON.parse(app.sapphillon.core.search.file(example.workdir, "report"));
+const report = app.sapphillon.core.filesystem.read(hits[0]);
+console.log(report.split("\n")[0]);
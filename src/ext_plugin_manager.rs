@@ -137,7 +137,8 @@ fn cleanup_empty_parent_dirs(path: &Path) {
 /// Scans a directory for installed external plugins.
 ///
 /// Traverses the directory structure `{save_dir}/{author_id}/{package_id}/{version}/`
-/// and returns plugin IDs for directories containing `package.js`.
+/// and returns plugin IDs for directories containing `package.js`, or `plugin.json`
+/// for WASM plugins.
 ///
 /// # Arguments
 ///
@@ -154,7 +155,7 @@ pub fn scan_ext_plugin_dir(save_dir: &str) -> Result<HashSet<String>> {
         return Ok(plugin_ids);
     }
 
-    // Traverse: author-id/package-id/ver/package.js
+    // Traverse: author-id/package-id/ver/{package.js,plugin.json}
     for author_entry in
         fs::read_dir(base_path).with_context(|| format!("Failed to read directory: {save_dir}"))?
     {
@@ -178,9 +179,10 @@ pub fn scan_ext_plugin_dir(save_dir: &str) -> Result<HashSet<String>> {
                 }
                 let version = version_entry.file_name().to_string_lossy().to_string();
 
-                // Check if package.js exists
+                // Check if package.js or a WASM plugin manifest exists
                 let package_js = version_entry.path().join("package.js");
-                if package_js.exists() {
+                let wasm_manifest = version_entry.path().join(wasm_plugin::MANIFEST_FILE);
+                if package_js.exists() || wasm_manifest.exists() {
                     let plugin_id = format!("{author_id}/{package_id}/{version}");
                     plugin_ids.insert(plugin_id);
                 }
@@ -278,20 +280,24 @@ mod tests {
         // Create mock plugin structures
         let plugin1_dir = temp_dir.path().join("author1/pkg1/1.0.0");
         let plugin2_dir = temp_dir.path().join("author2/pkg2/2.0.0");
+        let wasm_dir = temp_dir.path().join("author4/pkg4/4.0.0");
         let incomplete_dir = temp_dir.path().join("author3/pkg3/3.0.0"); // No package.js
 
         fs::create_dir_all(&plugin1_dir)?;
         fs::create_dir_all(&plugin2_dir)?;
         fs::create_dir_all(&incomplete_dir)?;
+        fs::create_dir_all(&wasm_dir)?;
 
         fs::write(plugin1_dir.join("package.js"), b"content1")?;
         fs::write(plugin2_dir.join("package.js"), b"content2")?;
+        fs::write(wasm_dir.join("plugin.json"), b"{}")?;
 
         let found = scan_ext_plugin_dir(&save_dir)?;
 
-        assert_eq!(found.len(), 2);
+        assert_eq!(found.len(), 3);
         assert!(found.contains("author1/pkg1/1.0.0"));
         assert!(found.contains("author2/pkg2/2.0.0"));
+        assert!(found.contains("author4/pkg4/4.0.0"));
         assert!(!found.contains("author3/pkg3/3.0.0"));
 
         Ok(())
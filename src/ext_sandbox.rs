@@ -0,0 +1,160 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Resource limits for the external plugin server process
+
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// V8 heap an external plugin server may use when not configured.
+pub(crate) const DEFAULT_MAX_HEAP_MB: u64 = 512;
+/// Wall-clock time an external plugin server may run when not configured.
+pub(crate) const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Exit code of a server the sandbox stopped, matching `timeout(1)`.
+const SANDBOX_EXIT_CODE: i32 = 124;
+
+/// Address space allowed on top of the V8 heap for code, stacks and native buffers.
+const ADDRESS_SPACE_HEADROOM_MB: u64 = 4096;
+
+/// Limits applied to each external plugin server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SandboxLimits {
+    /// Maximum V8 heap in MiB; `0` disables the limit.
+    pub max_heap_mb: u64,
+    /// Maximum run time in seconds; `0` disables the limit.
+    pub timeout_secs: u64,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            max_heap_mb: DEFAULT_MAX_HEAP_MB,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        }
+    }
+}
+
+static LIMITS: OnceLock<SandboxLimits> = OnceLock::new();
+
+/// Sets the limits passed to external plugin servers. Only the first call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the limits were applied, `false` if they were already configured.
+pub(crate) fn set_sandbox_limits(limits: SandboxLimits) -> bool {
+    LIMITS.set(limits).is_ok()
+}
+
+/// Arguments the server passes to itself to start an external plugin server, so the
+/// child process enforces the configured limits.
+pub(crate) fn runner_args() -> Vec<String> {
+    let limits = LIMITS.get().copied().unwrap_or_default();
+    vec![
+        "ext".to_string(),
+        "--max-heap-mb".to_string(),
+        limits.max_heap_mb.to_string(),
+        "--timeout-secs".to_string(),
+        limits.timeout_secs.to_string(),
+    ]
+}
+
+/// Confines the current process before it starts serving external plugins.
+///
+/// The V8 heap is capped with `--max-old-space-size`; on Unix the address space is
+/// capped as well so native allocations cannot bypass it. A watchdog ends the process
+/// when the timeout expires, after writing a JSON line to stderr that names the limit,
+/// so the workflow that called the plugin fails with a recognisable error.
+///
+/// Must be called before the first JS runtime is created.
+pub(crate) fn enter_sandbox(server_name: &str, limits: SandboxLimits) {
+    if limits.max_heap_mb > 0 {
+        let unrecognized =
+            deno_core::v8_set_flags(vec![format!("--max-old-space-size={}", limits.max_heap_mb)]);
+        // The first entry is the program name.
+        if unrecognized.len() > 1 {
+            log::warn!("V8 did not accept flags: {:?}", &unrecognized[1..]);
+        }
+        limit_address_space(limits.max_heap_mb + ADDRESS_SPACE_HEADROOM_MB);
+    }
+
+    if limits.timeout_secs > 0 {
+        let server_name = server_name.to_string();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(limits.timeout_secs));
+            let _ = writeln!(
+                std::io::stderr(),
+                "{}",
+                sandbox_error(&server_name, "timeout", limits.timeout_secs)
+            );
+            std::process::exit(SANDBOX_EXIT_CODE);
+        });
+    }
+}
+
+/// The structured error written when the sandbox stops a server.
+fn sandbox_error(server_name: &str, limit: &str, value: u64) -> serde_json::Value {
+    serde_json::json!({
+        "error": "ExtPluginSandboxKilled",
+        "server": server_name,
+        "limit": limit,
+        "value": value,
+        "message": format!("external plugin server '{server_name}' exceeded its {limit} limit ({value})"),
+    })
+}
+
+#[cfg(unix)]
+fn limit_address_space(mb: u64) {
+    let bytes = mb.saturating_mul(1024 * 1024) as libc::rlim_t;
+    let limit = libc::rlimit {
+        rlim_cur: bytes,
+        rlim_max: bytes,
+    };
+    // SAFETY: setrlimit only reads the struct passed to it.
+    if unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) } != 0 {
+        log::warn!(
+            "Failed to limit external plugin address space: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn limit_address_space(_mb: u64) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Args, Command};
+    use clap::Parser;
+
+    #[test]
+    fn runner_args_parse_as_ext_command() {
+        let mut argv = vec!["sapphillon".to_string()];
+        argv.extend(runner_args());
+        argv.push("server-1".to_string());
+
+        let args = Args::try_parse_from(argv).unwrap();
+        let Command::Ext {
+            server_name,
+            max_heap_mb,
+            timeout_secs,
+        } = args.command
+        else {
+            panic!("expected the ext command");
+        };
+        assert_eq!(server_name, "server-1");
+        assert_eq!(max_heap_mb, DEFAULT_MAX_HEAP_MB);
+        assert_eq!(timeout_secs, DEFAULT_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn sandbox_error_names_the_limit() {
+        let error = sandbox_error("server-1", "timeout", 30);
+        assert_eq!(error["error"], "ExtPluginSandboxKilled");
+        assert_eq!(error["limit"], "timeout");
+        assert_eq!(error["value"], 30);
+    }
+}
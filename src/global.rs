@@ -2,7 +2,7 @@
 // SPDX-FileCopyrightText: 2025 Yuta Takahashi
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
-use sea_orm::{Database, DatabaseConnection};
+use sea_orm::DatabaseConnection;
 use std::sync::LazyLock;
 use tokio::sync::RwLock;
 
@@ -11,6 +11,7 @@ pub struct GlobalStateData {
     db_initialized: bool,
     db_url: String,
     ext_plugin_save_dir: Option<String>,
+    loaded_ext_plugins: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -35,6 +36,7 @@ impl GlobalState {
                     db_initialized: false,
                     db_url: String::new(),
                     ext_plugin_save_dir: None,
+                    loaded_ext_plugins: Vec::new(),
                 })
             }),
         }
@@ -59,7 +61,7 @@ impl GlobalState {
         if data.db_url.is_empty() {
             anyhow::bail!("Database URL is not set");
         }
-        let conn = Database::connect(&data.db_url).await?;
+        let conn = crate::db_pool::connect(&data.db_url).await?;
         Ok(conn)
     }
 
@@ -153,6 +155,34 @@ impl GlobalState {
         }
     }
 
+    /// Records the external plugins currently loaded into the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `plugin_package_ids` - IDs of the loaded plugins, e.g. `author/package/1.0.0`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `()` once the list has been written to the shared state.
+    pub async fn async_set_loaded_ext_plugins(&self, plugin_package_ids: Vec<String>) {
+        let mut data = self.data.write().await;
+        data.loaded_ext_plugins = plugin_package_ids;
+    }
+
+    /// Lists the external plugins currently loaded into the server.
+    ///
+    /// # Arguments
+    ///
+    /// This method takes no additional arguments beyond the borrowed [`GlobalState`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the IDs recorded by the last plugin reload.
+    pub async fn get_loaded_ext_plugins(&self) -> Vec<String> {
+        let data = self.data.read().await;
+        data.loaded_ext_plugins.clone()
+    }
+
     /// Obtains the database URL by blocking within a Tokio-compatible context.
     ///
     /// # Arguments
@@ -605,6 +635,27 @@ mod tests {
         assert_eq!(got, "sqlite://async-test");
     }
 
+    /// Confirms the loaded external plugins are replaced by each reload.
+    ///
+    /// # Arguments
+    ///
+    /// This asynchronous test takes no arguments.
+    ///
+    /// # Returns
+    ///
+    /// Returns `()` after ensuring the recorded list matches the latest input.
+    #[tokio::test]
+    async fn loaded_ext_plugins_roundtrip() {
+        let gs = GlobalState::new();
+        assert!(gs.get_loaded_ext_plugins().await.is_empty());
+
+        gs.async_set_loaded_ext_plugins(vec!["a/b/1.0.0".to_string()])
+            .await;
+        gs.async_set_loaded_ext_plugins(vec!["a/b/1.1.0".to_string()])
+            .await;
+        assert_eq!(gs.get_loaded_ext_plugins().await, vec!["a/b/1.1.0"]);
+    }
+
     /// Verifies the blocking getter can be used safely from a non-async context.
     ///
     /// # Arguments
@@ -6,6 +6,7 @@ use crate::GLOBAL_STATE;
 use crate::args::Args;
 use anyhow::{Error, Result};
 use migration::MigratorTrait;
+use sea_orm::DbBackend;
 
 #[allow(unused)]
 use log::{debug, error, info, warn};
@@ -23,6 +24,10 @@ pub async fn initialize_system(args: &Args) -> Result<()> {
     // Sync External Plugins with filesystem
     sync_ext_plugins().await?;
 
+    // Load external plugins so workflows can call them
+    let db = GLOBAL_STATE.get_db_connection().await?;
+    crate::plugin_registry::reload_plugins(&db).await?;
+
     // Register Initial Workflows
     register_initial_workflows().await?;
 
@@ -31,30 +36,89 @@ pub async fn initialize_system(args: &Args) -> Result<()> {
     Ok(())
 }
 
-async fn setup_database() -> Result<()> {
+/// Name of the secret that holds the column encryption keys.
+const ENCRYPTION_KEYS_SECRET: &str = "database.encryption-keys";
+
+/// Installs the keys that encrypt sensitive database columns, read from
+/// `--encryption-key-file` or the [`ENCRYPTION_KEYS_SECRET`] secret. Without either,
+/// those columns are stored in plaintext.
+///
+/// # Returns
+///
+/// Returns an error when the keys cannot be read or are malformed.
+pub(crate) fn install_field_keyring(args: &Args) -> Result<()> {
+    let keys = if let Some(path) = &args.encryption_key_file {
+        std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("cannot read encryption key file {path}: {e}"))?
+    } else if args.encryption_key_from_keyring {
+        secrets::get_secret(ENCRYPTION_KEYS_SECRET)?
+            .ok_or_else(|| anyhow::anyhow!("secret {ENCRYPTION_KEYS_SECRET} is not set"))?
+    } else {
+        debug!("No encryption keys given; sensitive columns are stored in plaintext");
+        return Ok(());
+    };
+
+    let keyring = entity::encryption::FieldKeyring::parse(&keys)?;
+    info!(
+        "Sensitive columns are encrypted with key {}",
+        keyring.current_key_id()
+    );
+    entity::encryption::set_field_keyring(keyring);
+    Ok(())
+}
+
+pub(crate) async fn setup_database() -> Result<()> {
     // Run migrations immediately after setting DB URL so the schema
     // is ready before the server starts accepting requests.
     info!("Running database migrations...");
 
-    let mut db_url = GLOBAL_STATE.async_get_db_url().await;
+    let db_url = GLOBAL_STATE.async_get_db_url().await;
+    let db_url = match crate::db_pool::backend_of(&db_url) {
+        Some(DbBackend::Sqlite) => {
+            // SQLite has a single writer; run transactional writes one at a time.
+            database::writer::set_writer(database::writer::Writer::spawn());
+            prepare_sqlite_database(db_url).await?
+        }
+        Some(_) => db_url,
+        None => {
+            error!("Unsupported database URL; use a sqlite:, postgres:// or mysql:// URL.");
+            return Err(anyhow::anyhow!("Unsupported database type"));
+        }
+    };
 
-    if !db_url.starts_with("sqlite:") {
-        error!("Database migrations are only supported for SQLite databases in this version.");
-        return Err(anyhow::anyhow!("Unsupported database type for migrations"));
+    let database_connection = crate::db_pool::connect(&db_url).await;
+    match database_connection {
+        Ok(conn) => {
+            // Attempt to run migrations from the `migration` crate.
+            // If this fails, log the error and exit since the server
+            // depends on a correct schema state.
+            if let Err(e) = migration::Migrator::up(&conn, None).await {
+                error!("Database migration failed: {e:#?}");
+                // Ensure we don't continue in a bad state.
+                std::process::exit(1);
+            }
+
+            // Mark DB as initialized so other tasks can proceed.
+            GLOBAL_STATE.async_set_db_initialized(true).await;
+            info!("Database migrations applied");
+        }
+        Err(e) => {
+            error!("Failed to obtain DB connection for migrations: {e:#?}");
+            std::process::exit(1);
+        }
     }
 
-    // Normalize the sqlite path portion for path checks while tolerating common forms
-    // such as `sqlite::memory:`, `sqlite://:memory:`, and `sqlite:file::memory:?mode=memory&cache=shared`.
-    let path_part = db_url
-        .trim_start_matches("sqlite:")
-        // Handle the optional `//` after the scheme (sqlite://<path>). We remove exactly
-        // two slashes so absolute paths keep a single leading slash.
-        .strip_prefix("//")
-        .unwrap_or_else(|| db_url.trim_start_matches("sqlite:"));
+    Ok(())
+}
 
-    let is_memory = path_part.starts_with(":memory:")
-        || path_part.starts_with("file::memory:")
-        || db_url.contains("mode=memory");
+/// Prepares a SQLite database before it is connected to: in-memory URLs are rewritten to
+/// use a shared cache, and missing database files are created.
+///
+/// # Returns
+///
+/// Returns the URL to connect to, or an error when the database file cannot be created.
+async fn prepare_sqlite_database(mut db_url: String) -> Result<String> {
+    let is_memory = crate::db_pool::is_in_memory(&db_url);
 
     // Ensure all in-memory URLs use a shared cache so migrations and subsequent connections
     // see the same schema. This rewrites common short forms like `sqlite::memory:` into the
@@ -68,6 +132,8 @@ async fn setup_database() -> Result<()> {
         GLOBAL_STATE.async_set_db_url(normalized_memory_url).await;
     }
 
+    // Handle the optional `//` after the scheme (sqlite://<path>). We remove exactly
+    // two slashes so absolute paths keep a single leading slash.
     let path_part = db_url
         .trim_start_matches("sqlite:")
         .strip_prefix("//")
@@ -85,29 +151,7 @@ async fn setup_database() -> Result<()> {
         }
     }
 
-    let database_connection = sea_orm::Database::connect(db_url.as_str()).await;
-    match database_connection {
-        Ok(conn) => {
-            // Attempt to run migrations from the `migration` crate.
-            // If this fails, log the error and exit since the server
-            // depends on a correct schema state.
-            if let Err(e) = migration::Migrator::up(&conn, None).await {
-                error!("Database migration failed: {e:#?}");
-                // Ensure we don't continue in a bad state.
-                std::process::exit(1);
-            }
-
-            // Mark DB as initialized so other tasks can proceed.
-            GLOBAL_STATE.async_set_db_initialized(true).await;
-            info!("Database migrations applied");
-        }
-        Err(e) => {
-            error!("Failed to obtain DB connection for migrations: {e:#?}");
-            std::process::exit(1);
-        }
-    }
-
-    Ok(())
+    Ok(db_url)
 }
 
 async fn register_initial_workflows() -> Result<()> {
@@ -147,12 +191,15 @@ async fn register_initial_workflows() -> Result<()> {
     Ok(())
 }
 
-async fn register_initial_plugins() -> Result<()> {
+pub(crate) async fn register_initial_plugins() -> Result<()> {
     use database::plugin::init_register_plugins;
 
     let database_connection = GLOBAL_STATE.get_db_connection().await?;
 
-    let plugin_packages = crate::sysconfig::sysconfig().initial_plugins;
+    let (plugin_packages, diagnostics) = crate::plugin_registry::builtin_plugin_packages();
+    for diagnostic in &diagnostics {
+        error!("Rejected plugin {diagnostic}");
+    }
 
     init_register_plugins(&database_connection, plugin_packages).await?;
 
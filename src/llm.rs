@@ -0,0 +1,661 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Language model providers used to generate workflows
+
+use std::env;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use async_openai::{
+    Client,
+    config::OpenAIConfig,
+    types::{ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs},
+};
+use entity::entity::provider::Model as ProviderEntity;
+use log::debug;
+use sea_orm::DatabaseConnection;
+use serde_json::{Map, Value, json};
+
+/// gRPC metadata key naming the model a generation request should use, e.g.
+/// `models/claude-sonnet-4`.
+pub(crate) const MODEL_METADATA_KEY: &str = "x-sapphillon-model";
+
+/// Output token limit sent to APIs that require one when the provider sets none.
+const DEFAULT_MAX_TOKENS: u64 = 4096;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Name of the function offered to Ollama models that support tool calling.
+const SUBMIT_WORKFLOW_TOOL: &str = "submit_workflow";
+
+static DEFAULT_MODEL: OnceLock<String> = OnceLock::new();
+static OFFLINE_ONLY: OnceLock<bool> = OnceLock::new();
+
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A chat API that turns a prompt into a reply.
+pub(crate) trait LlmProvider: Send + Sync {
+    /// Sends `prompt` as a single user message and returns the reply text.
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>>;
+
+    /// Sends a prompt asking for workflow code and returns a reply holding the code in a
+    /// ```` ```javascript ```` fence. Providers that can return the code in a structured
+    /// way override this; by default the prompt is sent as is.
+    fn complete_code<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        self.complete(prompt)
+    }
+}
+
+/// The API a provider speaks, stored in the provider's `kind` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProviderKind {
+    /// OpenAI and compatible APIs such as OpenRouter; the default.
+    OpenAi,
+    Anthropic,
+    Ollama,
+    Gemini,
+}
+
+impl ProviderKind {
+    pub(crate) fn parse(kind: &str) -> Result<Self> {
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "" | "openai" => Ok(Self::OpenAi),
+            "anthropic" => Ok(Self::Anthropic),
+            "ollama" => Ok(Self::Ollama),
+            "gemini" => Ok(Self::Gemini),
+            other => anyhow::bail!(
+                "unknown provider kind '{other}' (expected openai, anthropic, ollama or gemini)"
+            ),
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Ollama => "ollama",
+            Self::Gemini => "gemini",
+        }
+    }
+
+    /// Endpoint used when the provider does not set one.
+    fn default_endpoint(self) -> &'static str {
+        match self {
+            Self::OpenAi => "https://api.openai.com/v1",
+            Self::Anthropic => "https://api.anthropic.com",
+            Self::Ollama => "http://localhost:11434",
+            Self::Gemini => "https://generativelanguage.googleapis.com",
+        }
+    }
+}
+
+/// Everything needed to call one model of a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProviderConfig {
+    pub kind: ProviderKind,
+    /// Base URL of the API; empty uses the provider's public endpoint.
+    pub endpoint: String,
+    pub api_key: String,
+    /// Model ID sent to the API.
+    pub model: String,
+    /// Provider-specific request options, see [`build_provider`].
+    pub options: Map<String, Value>,
+}
+
+impl ProviderConfig {
+    /// Builds the configuration of a stored provider for the model `model`.
+    pub(crate) fn from_entity(provider: &ProviderEntity, model: &str) -> Result<Self> {
+        let options = match provider.options.as_deref().map(str::trim) {
+            None | Some("") => Map::new(),
+            Some(options) => serde_json::from_str(options)
+                .with_context(|| format!("options of {} must be a JSON object", provider.name))?,
+        };
+        Ok(Self {
+            kind: ProviderKind::parse(provider.kind.as_deref().unwrap_or_default())?,
+            endpoint: provider.api_endpoint.clone(),
+            api_key: entity::encryption::decrypt_field(&provider.api_key)
+                .with_context(|| format!("cannot decrypt the API key of {}", provider.name))?,
+            model: model.to_string(),
+            options,
+        })
+    }
+
+    /// The OpenAI-compatible provider described by `OPENAI_API_BASE`, `OPENAI_API_KEY`
+    /// and `OPENAI_MODEL`, used when no model is selected.
+    pub(crate) fn from_env() -> Result<Self> {
+        let var = |name: &str| env::var(name).with_context(|| format!("{name} is not set"));
+        Ok(Self {
+            kind: ProviderKind::OpenAi,
+            endpoint: var("OPENAI_API_BASE")?,
+            api_key: var("OPENAI_API_KEY")?,
+            model: var("OPENAI_MODEL")?,
+            options: Map::new(),
+        })
+    }
+
+    /// Whether requests stay on this machine, i.e. the endpoint is a loopback address.
+    pub(crate) fn is_local(&self) -> bool {
+        let Ok(url) = reqwest::Url::parse(endpoint_or(self, self.kind.default_endpoint())) else {
+            return false;
+        };
+        match url.host_str() {
+            Some(host) if host.eq_ignore_ascii_case("localhost") => true,
+            Some(host) => host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback()),
+            None => false,
+        }
+    }
+}
+
+/// Creates the client for `config`.
+///
+/// The options are applied per kind:
+/// - OpenAI: `temperature` and `max_tokens`.
+/// - Anthropic: merged into the Messages API request, e.g. `max_tokens`, `temperature`;
+///   `anthropic_version` sets the API version header.
+/// - Ollama: sent as the request's `options`, e.g. `temperature`, `num_ctx`; `tools: false`
+///   skips native tool calling for models known not to support it.
+/// - Gemini: sent as the request's `generationConfig`, e.g. `maxOutputTokens`.
+pub(crate) fn build_provider(config: ProviderConfig) -> Box<dyn LlmProvider> {
+    match config.kind {
+        ProviderKind::OpenAi => Box::new(OpenAiProvider(config)),
+        ProviderKind::Anthropic => Box::new(AnthropicProvider(config)),
+        ProviderKind::Ollama => Box::new(OllamaProvider::new(config)),
+        ProviderKind::Gemini => Box::new(GeminiProvider(config)),
+    }
+}
+
+/// Sets the model used when a request does not name one. Only the first call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the model was set, `false` if one was already set.
+pub(crate) fn set_default_model(model: String) -> bool {
+    DEFAULT_MODEL.set(model).is_ok()
+}
+
+/// Restricts generation to providers on this machine, so prompts are never sent to a
+/// remote API. Only the first call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the setting was applied, `false` if one was already set.
+pub(crate) fn set_offline_only(offline: bool) -> bool {
+    OFFLINE_ONLY.set(offline).is_ok()
+}
+
+fn offline_only() -> bool {
+    OFFLINE_ONLY.get().copied().unwrap_or(false)
+}
+
+/// Resolves the provider to generate with.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to look up the model and its provider.
+/// * `model` - Model requested by the caller; falls back to the default model, then to
+///   the `OPENAI_*` environment variables.
+///
+/// # Returns
+///
+/// Returns the provider client, or an error when the model or its provider is unknown
+/// or not configured, or when generation is offline-only and the provider is remote.
+pub(crate) async fn provider_for_model(
+    db: &DatabaseConnection,
+    model: Option<&str>,
+) -> Result<Box<dyn LlmProvider>> {
+    let Some(model_name) = model
+        .filter(|model| !model.trim().is_empty())
+        .or(DEFAULT_MODEL.get().map(String::as_str))
+    else {
+        let config = ProviderConfig::from_env()?;
+        if offline_only() && !config.is_local() {
+            anyhow::bail!("OPENAI_API_BASE is not a local endpoint and generation is offline-only");
+        }
+        return Ok(build_provider(config));
+    };
+
+    let model = database::model::get_model_entity(db, model_name)
+        .await?
+        .with_context(|| format!("model not found: {model_name}"))?;
+    let provider = database::provider::get_provider_entity(db, &model.provider_name)
+        .await?
+        .with_context(|| format!("provider not found: {}", model.provider_name))?;
+    let config = ProviderConfig::from_entity(&provider, api_model_id(&model.name))?;
+    if offline_only() && !config.is_local() {
+        anyhow::bail!(
+            "{} is not a local provider and generation is offline-only",
+            provider.name
+        );
+    }
+    Ok(build_provider(config))
+}
+
+/// Model resource names are `models/<id>`; the API expects the bare ID.
+fn api_model_id(name: &str) -> &str {
+    name.strip_prefix("models/").unwrap_or(name)
+}
+
+fn endpoint_or<'a>(config: &'a ProviderConfig, default: &'a str) -> &'a str {
+    let endpoint = config.endpoint.trim().trim_end_matches('/');
+    if endpoint.is_empty() {
+        default
+    } else {
+        endpoint
+    }
+}
+
+/// Posts `body` as JSON and returns the parsed response, failing on non-2xx statuses.
+async fn post_json(request: reqwest::RequestBuilder, body: &Value) -> Result<Value> {
+    let response = request
+        .header("content-type", "application/json")
+        .body(serde_json::to_vec(body)?)
+        .send()
+        .await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        anyhow::bail!("HTTP {status}: {text}");
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+struct OpenAiProvider(ProviderConfig);
+
+impl LlmProvider for OpenAiProvider {
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let config = &self.0;
+            let mut client_config = OpenAIConfig::new().with_api_key(&config.api_key);
+            if !config.endpoint.trim().is_empty() {
+                client_config = client_config.with_api_base(&config.endpoint);
+            }
+            let client = Client::with_config(client_config);
+
+            let mut request = CreateChatCompletionRequestArgs::default();
+            request
+                .model(&config.model)
+                .messages([ChatCompletionRequestUserMessageArgs::default()
+                    .content(prompt)
+                    .build()?
+                    .into()]);
+            if let Some(temperature) = config.options.get("temperature").and_then(Value::as_f64) {
+                request.temperature(temperature as f32);
+            }
+            if let Some(max_tokens) = config.options.get("max_tokens").and_then(Value::as_u64) {
+                request.max_tokens(u16::try_from(max_tokens).unwrap_or(u16::MAX));
+            }
+
+            let response = client.chat().create(request.build()?).await?;
+            Ok(response
+                .choices
+                .first()
+                .and_then(|c| c.message.content.clone())
+                .unwrap_or_default())
+        })
+    }
+}
+
+struct AnthropicProvider(ProviderConfig);
+
+impl AnthropicProvider {
+    fn body(&self, prompt: &str) -> Value {
+        let mut body = json!({
+            "model": self.0.model,
+            "max_tokens": DEFAULT_MAX_TOKENS,
+            "messages": [{ "role": "user", "content": prompt }],
+        });
+        for (key, value) in &self.0.options {
+            if key != "anthropic_version" {
+                body[key] = value.clone();
+            }
+        }
+        body
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let config = &self.0;
+            let version = config
+                .options
+                .get("anthropic_version")
+                .and_then(Value::as_str)
+                .unwrap_or(ANTHROPIC_VERSION);
+            let url = format!(
+                "{}/v1/messages",
+                endpoint_or(config, config.kind.default_endpoint())
+            );
+            let request = reqwest::Client::new()
+                .post(url)
+                .header("x-api-key", &config.api_key)
+                .header("anthropic-version", version);
+            let response = post_json(request, &self.body(prompt)).await?;
+            anthropic_text(&response)
+        })
+    }
+}
+
+fn anthropic_text(response: &Value) -> Result<String> {
+    let blocks = response["content"]
+        .as_array()
+        .context("Anthropic response has no content")?;
+    Ok(blocks
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect())
+}
+
+struct OllamaProvider {
+    config: ProviderConfig,
+    /// Cleared once the model rejects tool definitions, so later calls prompt directly.
+    tools: AtomicBool,
+}
+
+impl OllamaProvider {
+    fn new(config: ProviderConfig) -> Self {
+        let tools = config.options.get("tools").and_then(Value::as_bool) != Some(false);
+        Self {
+            config,
+            tools: AtomicBool::new(tools),
+        }
+    }
+
+    fn body(&self, prompt: &str) -> Value {
+        let mut options = self.config.options.clone();
+        options.remove("tools");
+        json!({
+            "model": self.config.model,
+            "messages": [{ "role": "user", "content": prompt }],
+            "stream": false,
+            "options": options,
+        })
+    }
+
+    /// The request body offering [`SUBMIT_WORKFLOW_TOOL`] to the model.
+    fn tool_body(&self, prompt: &str) -> Value {
+        let mut body = self.body(prompt);
+        body["tools"] = json!([{
+            "type": "function",
+            "function": {
+                "name": SUBMIT_WORKFLOW_TOOL,
+                "description": "Submits the JavaScript source of the workflow.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "code": {
+                            "type": "string",
+                            "description": "JavaScript source defining function workflow().",
+                        },
+                    },
+                    "required": ["code"],
+                },
+            },
+        }]);
+        body
+    }
+
+    async fn chat(&self, body: &Value) -> Result<Value> {
+        let config = &self.config;
+        let url = format!(
+            "{}/api/chat",
+            endpoint_or(config, config.kind.default_endpoint())
+        );
+        let mut request = reqwest::Client::new().post(url);
+        if !config.api_key.is_empty() {
+            request = request.bearer_auth(&config.api_key);
+        }
+        post_json(request, body).await
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let response = self.chat(&self.body(prompt)).await?;
+            ollama_text(&response)
+        })
+    }
+
+    /// Offers the model a `submit_workflow` function and wraps the submitted code in a
+    /// fence. Models without tool calling are prompted to reply with the fence only.
+    fn complete_code<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            if self.tools.load(Ordering::Relaxed) {
+                match self.chat(&self.tool_body(prompt)).await {
+                    Ok(response) => {
+                        return match submitted_code(&response) {
+                            Some(code) => Ok(format!("```javascript\n{code}\n```")),
+                            None => ollama_text(&response),
+                        };
+                    }
+                    Err(err) if err.to_string().contains("does not support tools") => {
+                        debug!("{} has no tool calling: {err}", self.config.model);
+                        self.tools.store(false, Ordering::Relaxed);
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            self.complete(&fenced_code_prompt(prompt)).await
+        })
+    }
+}
+
+fn ollama_text(response: &Value) -> Result<String> {
+    response["message"]["content"]
+        .as_str()
+        .map(str::to_string)
+        .context("Ollama response has no message")
+}
+
+/// The `code` argument of the model's `submit_workflow` call, if it made one.
+fn submitted_code(response: &Value) -> Option<String> {
+    response["message"]["tool_calls"]
+        .as_array()?
+        .iter()
+        .map(|call| &call["function"])
+        .find(|function| function["name"] == SUBMIT_WORKFLOW_TOOL)
+        .and_then(|function| {
+            // Some models send the arguments as a JSON string instead of an object.
+            match &function["arguments"] {
+                Value::String(arguments) => serde_json::from_str::<Value>(arguments).ok()?["code"]
+                    .as_str()
+                    .map(str::to_string),
+                arguments => arguments["code"].as_str().map(str::to_string),
+            }
+        })
+}
+
+/// Restates the output format at the end of `prompt`, where small models without tool
+/// calling are most likely to follow it.
+fn fenced_code_prompt(prompt: &str) -> String {
+    format!(
+        "{prompt}\n\n\
+         # 回答形式\n\
+         説明や前置きは書かず、```javascript で始まり ``` で終わるコードブロックを1つだけ出力してください。"
+    )
+}
+
+struct GeminiProvider(ProviderConfig);
+
+impl GeminiProvider {
+    fn body(&self, prompt: &str) -> Value {
+        json!({
+            "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+            "generationConfig": self.0.options,
+        })
+    }
+}
+
+impl LlmProvider for GeminiProvider {
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let config = &self.0;
+            let url = format!(
+                "{}/v1beta/models/{}:generateContent",
+                endpoint_or(config, config.kind.default_endpoint()),
+                config.model
+            );
+            let request = reqwest::Client::new()
+                .post(url)
+                .header("x-goog-api-key", &config.api_key);
+            let response = post_json(request, &self.body(prompt)).await?;
+            gemini_text(&response)
+        })
+    }
+}
+
+fn gemini_text(response: &Value) -> Result<String> {
+    let parts = response["candidates"][0]["content"]["parts"]
+        .as_array()
+        .context("Gemini response has no candidates")?;
+    Ok(parts
+        .iter()
+        .filter_map(|part| part["text"].as_str())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(kind: ProviderKind, options: Value) -> ProviderConfig {
+        ProviderConfig {
+            kind,
+            endpoint: String::new(),
+            api_key: "key".to_string(),
+            model: "model-1".to_string(),
+            options: options.as_object().cloned().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn test_provider_config_from_entity() {
+        let mut provider = ProviderEntity {
+            name: "providers/anthropic".to_string(),
+            display_name: "Anthropic".to_string(),
+            api_key: "key".to_string(),
+            api_endpoint: "".to_string(),
+            kind: Some("Anthropic".to_string()),
+            options: Some(r#"{"max_tokens": 8192}"#.to_string()),
+        };
+        let config =
+            ProviderConfig::from_entity(&provider, api_model_id("models/claude-sonnet-4")).unwrap();
+        assert_eq!(config.kind, ProviderKind::Anthropic);
+        assert_eq!(config.model, "claude-sonnet-4");
+        assert_eq!(config.options["max_tokens"], 8192);
+
+        provider.kind = None;
+        provider.options = None;
+        let config = ProviderConfig::from_entity(&provider, "gpt-4o").unwrap();
+        assert_eq!(config.kind, ProviderKind::OpenAi);
+        assert!(config.options.is_empty());
+
+        provider.kind = Some("mistral".to_string());
+        assert!(ProviderConfig::from_entity(&provider, "x").is_err());
+        provider.kind = None;
+        provider.options = Some("[1]".to_string());
+        assert!(ProviderConfig::from_entity(&provider, "x").is_err());
+    }
+
+    #[test]
+    fn test_request_bodies_carry_provider_options() {
+        let anthropic = AnthropicProvider(config(
+            ProviderKind::Anthropic,
+            json!({ "max_tokens": 1000, "temperature": 0.5, "anthropic_version": "2024-01-01" }),
+        ));
+        let body = anthropic.body("hi");
+        assert_eq!(body["max_tokens"], 1000);
+        assert_eq!(body["temperature"], 0.5);
+        assert!(body.get("anthropic_version").is_none());
+        assert_eq!(body["messages"][0]["content"], "hi");
+
+        let ollama = OllamaProvider::new(config(ProviderKind::Ollama, json!({ "num_ctx": 8192 })));
+        assert_eq!(ollama.body("hi")["options"]["num_ctx"], 8192);
+        assert_eq!(ollama.body("hi")["stream"], false);
+        assert!(ollama.body("hi").get("tools").is_none());
+        assert_eq!(
+            ollama.tool_body("hi")["tools"][0]["function"]["name"],
+            SUBMIT_WORKFLOW_TOOL
+        );
+
+        let gemini = GeminiProvider(config(
+            ProviderKind::Gemini,
+            json!({ "maxOutputTokens": 10 }),
+        ));
+        let body = gemini.body("hi");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 10);
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "hi");
+    }
+
+    #[test]
+    fn test_response_text_is_extracted() {
+        let anthropic = json!({
+            "content": [
+                { "type": "text", "text": "```javascript\n" },
+                { "type": "text", "text": "workflow\n```" },
+            ]
+        });
+        assert_eq!(
+            anthropic_text(&anthropic).unwrap(),
+            "```javascript\nworkflow\n```"
+        );
+
+        let gemini = json!({ "candidates": [{ "content": { "parts": [{ "text": "a" }, { "text": "b" }] } }] });
+        assert_eq!(gemini_text(&gemini).unwrap(), "ab");
+        assert!(gemini_text(&json!({ "error": {} })).is_err());
+    }
+
+    #[test]
+    fn test_ollama_tool_calls_are_read() {
+        let call = |arguments: Value| {
+            json!({ "message": { "content": "", "tool_calls": [
+                { "function": { "name": SUBMIT_WORKFLOW_TOOL, "arguments": arguments } },
+            ] } })
+        };
+        let code = "function workflow() {}";
+        assert_eq!(
+            submitted_code(&call(json!({ "code": code }))).as_deref(),
+            Some(code)
+        );
+        assert_eq!(
+            submitted_code(&call(json!(r#"{"code":"function workflow() {}"}"#))).as_deref(),
+            Some(code)
+        );
+        assert_eq!(
+            submitted_code(&json!({ "message": { "content": "```javascript\nx\n```" } })),
+            None
+        );
+
+        let ollama = OllamaProvider::new(config(ProviderKind::Ollama, json!({ "tools": false })));
+        assert!(!ollama.tools.load(Ordering::Relaxed));
+        assert!(ollama.body("hi")["options"].get("tools").is_none());
+        assert!(fenced_code_prompt("prompt").starts_with("prompt\n\n# 回答形式"));
+    }
+
+    #[test]
+    fn test_local_providers_are_detected() {
+        let with_endpoint = |kind, endpoint: &str| ProviderConfig {
+            endpoint: endpoint.to_string(),
+            ..config(kind, json!({}))
+        };
+        assert!(with_endpoint(ProviderKind::Ollama, "").is_local());
+        assert!(with_endpoint(ProviderKind::Ollama, "http://127.0.0.1:11434/").is_local());
+        assert!(with_endpoint(ProviderKind::OpenAi, "http://[::1]:8080/v1").is_local());
+        assert!(with_endpoint(ProviderKind::OpenAi, "http://LOCALHOST:1234/v1").is_local());
+        assert!(!with_endpoint(ProviderKind::OpenAi, "").is_local());
+        assert!(!with_endpoint(ProviderKind::Anthropic, "").is_local());
+        assert!(!with_endpoint(ProviderKind::Ollama, "http://gpu-box:11434").is_local());
+        assert!(!with_endpoint(ProviderKind::Ollama, "not a url").is_local());
+    }
+}
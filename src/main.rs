@@ -3,14 +3,47 @@
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
 mod args;
+mod auth;
+mod cli;
+mod db_pool;
+mod db_worker;
 mod dummy_plugin;
+mod events;
+mod examples;
 #[allow(unused)]
 mod ext_plugin_manager;
+mod ext_sandbox;
 mod init;
+mod llm;
+mod permission_profiles;
+mod permission_requester;
 mod plugin_installer;
+mod plugin_registry;
+mod plugin_store;
+mod plugin_updater;
+mod plugin_validation;
+mod prompt_handler;
+mod proto;
+mod rate_limit;
+mod redaction;
+mod result_retention;
+mod scheduler;
 mod server;
 mod services;
+mod state_store;
+mod triggers;
+mod webhook;
 mod workflow;
+mod workflow_dry_run;
+mod workflow_modules;
+mod workflow_organization;
+mod workflow_permissions;
+mod workflow_pool;
+mod workflow_replay;
+mod workflow_revisions;
+mod workflow_steps;
+mod workflow_typescript;
+mod workflow_validation;
 
 #[cfg(debug_assertions)]
 mod debug_workflow;
@@ -53,6 +86,9 @@ pub(crate) static GLOBAL_STATE: global::GlobalState = global::GlobalState::new()
 async fn main() -> Result<()> {
     let args = Args::parse();
 
+    // Configure masking before the first log line is written
+    redaction::set_redact_patterns(&args.redact_pattern)?;
+
     // Initialize tracing/logging once (combine settings to avoid double init)
     let log_level_tracing: tracing::Level = args.loglevel.clone().into();
 
@@ -64,6 +100,7 @@ async fn main() -> Result<()> {
         .with_max_level(log_level_tracing)
         .with_thread_ids(true)
         .with_thread_names(true)
+        .with_writer(redaction::RedactingWriter)
         .init();
 
     // Display application information
@@ -80,6 +117,38 @@ async fn main() -> Result<()> {
     }
     // Initialize Database Connection
 
+    db_pool::set_pool_config(db_pool::PoolConfig {
+        max_connections: args.db_max_connections,
+        min_connections: args.db_min_connections,
+        connect_timeout_secs: args.db_connect_timeout_secs,
+        idle_timeout_secs: args.db_idle_timeout_secs,
+        sqlite_busy_timeout_secs: args.db_busy_timeout_secs,
+    });
+    init::install_field_keyring(&args)?;
+    workflow_pool::set_max_concurrent_workflows(args.max_concurrent_workflows);
+    if let Some(model) = args.generation_model.clone() {
+        llm::set_default_model(model);
+    }
+    workflow::set_max_repair_attempts(args.generation_repair_attempts);
+    llm::set_offline_only(args.offline_generation);
+    if let Some(url) = args.plugin_store_url.clone() {
+        plugin_store::set_store_url(url);
+    }
+    ext_sandbox::set_sandbox_limits(ext_sandbox::SandboxLimits {
+        max_heap_mb: args.ext_plugin_max_heap_mb,
+        timeout_secs: args.ext_plugin_timeout_secs,
+    });
+    rate_limit::set_quotas(rate_limit::Quotas {
+        generate: rate_limit::Quota {
+            per_minute: args.generate_rate_limit,
+            max_concurrent: args.generate_max_concurrent,
+        },
+        run: rate_limit::Quota {
+            per_minute: args.run_rate_limit,
+            max_concurrent: args.run_max_concurrent,
+        },
+    });
+
     GLOBAL_STATE.async_set_db_url(args.db_url.clone()).await;
     GLOBAL_STATE
         .async_set_ext_plugin_save_dir(args.ext_plugin_save_dir.clone())
@@ -90,14 +159,94 @@ async fn main() -> Result<()> {
             // Initialize system (migrations, etc.)
 
             init::initialize_system(&args).await?;
+            let auth_provider = auth::build_auth_provider(&args).await?;
+            let listen_addr = server::parse_listen_addr(&args.listen, &args.listen_socket_mode)?;
+            let tls_config = server::load_tls_config(&args)?;
+            let grpc_web = server::parse_grpc_web_config(&args)?;
 
             // Start server in a background task
-            let server_handle = tokio::spawn(async {
-                if let Err(e) = start_server().await {
+            let server_addr = listen_addr.clone();
+            let server_handle = tokio::spawn(async move {
+                if let Err(e) = start_server(auth_provider, server_addr, tls_config, grpc_web).await
+                {
                     error!("Server error: {e}");
                 }
             });
 
+            // Persist workflow state in the database instead of process memory
+            let state_store =
+                state_store::DbStateStore::connect(GLOBAL_STATE.async_get_db_url().await)?;
+            state::set_state_store(std::sync::Arc::new(state_store));
+
+            // Let workflows wait for answers stored in the database
+            let prompt_handler = std::sync::Arc::new(prompt_handler::DbPromptHandler::connect(
+                GLOBAL_STATE.async_get_db_url().await,
+            )?);
+            prompt::set_prompt_handler(prompt_handler.clone());
+            if !prompt_handler.is_answerable() {
+                warn!("Workflow prompts fail at once because the database is in memory");
+            }
+
+            // Ask before failing plugin calls that lack a permission
+            if args.prompt_for_permissions && !prompt_handler.is_answerable() {
+                warn!(
+                    "--prompt-for-permissions has no effect because the database is in memory; \
+                     missing permissions are denied"
+                );
+            } else if args.prompt_for_permissions {
+                plugin_permission::set_permission_requester(std::sync::Arc::new(
+                    permission_requester::PromptPermissionRequester::new(prompt_handler),
+                ));
+                info!("Missing permissions are requested with prompts");
+            }
+
+            // Keep a file index for searches that would otherwise walk the filesystem
+            if !args.search_index_root.is_empty() {
+                let index_path = args
+                    .search_index_path
+                    .clone()
+                    .map(std::path::PathBuf::from)
+                    .unwrap_or_else(|| std::env::temp_dir().join("sapphillon-search-index.sqlite"));
+                let roots: Vec<std::path::PathBuf> = args
+                    .search_index_root
+                    .iter()
+                    .map(std::path::PathBuf::from)
+                    .collect();
+                match search::start_file_index(&index_path, &roots) {
+                    Ok(()) => info!("File search index: {}", index_path.display()),
+                    Err(e) => error!("Cannot start the file search index: {e:#}"),
+                }
+            }
+
+            // Run scheduled workflows in the background
+            let scheduler_db = GLOBAL_STATE.wait_init_and_get_connection().await?;
+            tokio::spawn(scheduler::start_scheduler(scheduler_db));
+
+            // Remove workflow results beyond the retention policy
+            let pruner_db = GLOBAL_STATE.wait_init_and_get_connection().await?;
+            tokio::spawn(result_retention::start_result_pruner(
+                pruner_db,
+                sysconfig::sysconfig().result_retention,
+            ));
+
+            // Look for plugin updates when a plugin store is configured
+            let updater_db = GLOBAL_STATE.wait_init_and_get_connection().await?;
+            tokio::spawn(plugin_updater::start_plugin_updater(updater_db));
+
+            // Start listeners for file and window triggers
+            let triggers_db = GLOBAL_STATE.wait_init_and_get_connection().await?;
+            tokio::spawn(triggers::start_triggers(triggers_db));
+
+            // Serve webhook triggers over HTTP when asked to
+            if let Some(webhook_addr) = args.webhook_addr.clone() {
+                let webhook_db = GLOBAL_STATE.wait_init_and_get_connection().await?;
+                tokio::spawn(async move {
+                    if let Err(e) = webhook::start_webhook_server(webhook_db, &webhook_addr).await {
+                        error!("Webhook server error: {e:#}");
+                    }
+                });
+            }
+
             // Start debug workflow scanner in debug builds only
             #[cfg(debug_assertions)]
             {
@@ -113,12 +262,24 @@ async fn main() -> Result<()> {
             tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
 
             // Keep server running
-            info!("Server running on [::1]:50051. Press Ctrl+C to stop.");
+            info!("Server running on {listen_addr}. Press Ctrl+C to stop.");
             server_handle.await?;
         }
-        Command::Ext { server_name } => {
+        Command::Cli(command) => cli::run(command, args.webhook_addr.as_deref()).await?,
+        Command::Ext {
+            server_name,
+            max_heap_mb,
+            timeout_secs,
+        } => {
             info!("Starting External Plugin Server {server_name}...");
             use sapphillon_core::ext_plugin::extplugin_server;
+            ext_sandbox::enter_sandbox(
+                &server_name,
+                ext_sandbox::SandboxLimits {
+                    max_heap_mb,
+                    timeout_secs,
+                },
+            );
             extplugin_server(&server_name).await?;
         }
     }
@@ -0,0 +1,187 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Expands the permission profiles attached to a workflow into allowed permissions
+
+use anyhow::{Context, bail};
+use sapphillon_core::proto::sapphillon::v1::{AllowedPermission, Permission, PermissionLevel};
+use serde::Deserialize;
+
+/// One entry of a profile's `permissions` JSON, e.g.
+/// `{"function": "*", "type": "filesystem-read", "level": "medium"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct ProfilePermission {
+    /// Plugin function the entry applies to, or `*` for every function.
+    pub function: String,
+    /// Permission type, written as accepted by [`plugin_permission::parse_permission_type`].
+    #[serde(rename = "type")]
+    pub permission_type: String,
+    /// Highest level covered (`medium` or `high`); every level when omitted.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Resources covered; every resource when empty.
+    #[serde(default)]
+    pub resource: Vec<String>,
+}
+
+/// Parses a profile's `permissions` JSON into allowed permissions, one per entry.
+pub(crate) fn parse_profile_permissions(json: &str) -> anyhow::Result<Vec<AllowedPermission>> {
+    let entries: Vec<ProfilePermission> =
+        serde_json::from_str(json).context("profile permissions must be a JSON array")?;
+    entries.into_iter().map(to_allowed_permission).collect()
+}
+
+fn to_allowed_permission(entry: ProfilePermission) -> anyhow::Result<AllowedPermission> {
+    if entry.function.trim().is_empty() {
+        bail!("profile permission is missing its function");
+    }
+    let Some(permission_type) = plugin_permission::parse_permission_type(&entry.permission_type)
+    else {
+        bail!("unknown permission type '{}'", entry.permission_type);
+    };
+    let permission_level = match entry.level.as_deref() {
+        None => PermissionLevel::Unspecified as i32,
+        Some(level) => plugin_permission::parse_permission_level(level)
+            .ok_or_else(|| anyhow::anyhow!("unknown permission level '{level}'"))?,
+    };
+    Ok(AllowedPermission {
+        plugin_function_id: entry.function,
+        permissions: vec![Permission {
+            display_name: entry.permission_type,
+            description: "Granted by a permission profile".to_string(),
+            permission_type,
+            permission_level,
+            resource: entry.resource,
+        }],
+    })
+}
+
+/// Adds profile permissions to the allowed permissions of a workflow code.
+///
+/// Plugins use the first allowed entry naming their function or `*`, so permissions
+/// granted to `*` are also added to every function-specific entry, and `*` entries
+/// are kept last.
+pub(crate) fn expand_profile_permissions(
+    allowed: &mut Vec<AllowedPermission>,
+    profile_permissions: Vec<AllowedPermission>,
+) {
+    let (wildcard, specific): (Vec<_>, Vec<_>) = profile_permissions
+        .into_iter()
+        .partition(|entry| entry.plugin_function_id == "*");
+
+    for entry in specific {
+        merge_into(allowed, entry);
+    }
+    for entry in wildcard {
+        for existing in allowed.iter_mut() {
+            add_missing(&mut existing.permissions, &entry.permissions);
+        }
+        if !allowed
+            .iter()
+            .any(|existing| existing.plugin_function_id == "*")
+        {
+            allowed.push(entry);
+        }
+    }
+    allowed.sort_by_key(|entry| entry.plugin_function_id == "*");
+}
+
+fn merge_into(allowed: &mut Vec<AllowedPermission>, entry: AllowedPermission) {
+    match allowed
+        .iter_mut()
+        .find(|existing| existing.plugin_function_id == entry.plugin_function_id)
+    {
+        Some(existing) => add_missing(&mut existing.permissions, &entry.permissions),
+        None => {
+            // A new function entry also needs the `*` grants it would otherwise shadow.
+            let mut entry = entry;
+            if let Some(wildcard) = allowed.iter().find(|e| e.plugin_function_id == "*") {
+                add_missing(&mut entry.permissions, &wildcard.permissions);
+            }
+            allowed.push(entry);
+        }
+    }
+}
+
+fn add_missing(permissions: &mut Vec<Permission>, additions: &[Permission]) {
+    for permission in additions {
+        if !permissions.contains(permission) {
+            permissions.push(permission.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::proto::sapphillon::v1::PermissionType;
+
+    fn allowed(function: &str, permission_type: PermissionType) -> AllowedPermission {
+        AllowedPermission {
+            plugin_function_id: function.to_string(),
+            permissions: vec![Permission {
+                display_name: "".to_string(),
+                description: "".to_string(),
+                permission_type: permission_type as i32,
+                permission_level: PermissionLevel::Unspecified as i32,
+                resource: vec![],
+            }],
+        }
+    }
+
+    #[test]
+    fn parse_profile_permissions_reads_type_level_and_resource() {
+        let parsed = parse_profile_permissions(
+            r#"[{"function":"*","type":"filesystem-read","level":"medium","resource":["/tmp"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].plugin_function_id, "*");
+        let permission = &parsed[0].permissions[0];
+        assert_eq!(
+            permission.permission_type,
+            PermissionType::FilesystemRead as i32
+        );
+        assert_eq!(permission.permission_level, PermissionLevel::Medium as i32);
+        assert_eq!(permission.resource, vec!["/tmp".to_string()]);
+    }
+
+    #[test]
+    fn parse_profile_permissions_rejects_unknown_names() {
+        assert!(parse_profile_permissions(r#"[{"function":"*","type":"teleport"}]"#).is_err());
+        assert!(
+            parse_profile_permissions(r#"[{"function":"*","type":"execute","level":"max"}]"#)
+                .is_err()
+        );
+        assert!(parse_profile_permissions(r#"{"function":"*"}"#).is_err());
+    }
+
+    #[test]
+    fn expand_profile_permissions_covers_specific_functions_and_keeps_wildcard_last() {
+        let mut permissions = vec![allowed("app.exec", PermissionType::Execute)];
+        expand_profile_permissions(
+            &mut permissions,
+            vec![
+                allowed("*", PermissionType::FilesystemRead),
+                allowed("app.fetch", PermissionType::NetAccess),
+            ],
+        );
+
+        let ids: Vec<&str> = permissions
+            .iter()
+            .map(|p| p.plugin_function_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["app.exec", "app.fetch", "*"]);
+        for entry in &permissions {
+            assert!(
+                entry
+                    .permissions
+                    .iter()
+                    .any(|p| p.permission_type == PermissionType::FilesystemRead as i32),
+                "{} lacks the wildcard grant",
+                entry.plugin_function_id
+            );
+        }
+    }
+}
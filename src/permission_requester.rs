@@ -0,0 +1,124 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Turns missing plugin permissions into prompts a person can answer
+
+use crate::events::{self, Event};
+use crate::prompt_handler::{DbPromptHandler, PERMISSION_PROMPT_KIND};
+use plugin_permission::{PermissionRequest, PermissionRequester, describe_permission};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// How long a workflow waits for a permission request to be answered before failing.
+const PERMISSION_PROMPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Asks for missing permissions with a `permission` prompt, answered like any other
+/// prompt with `prompts answer <id> yes|no` or with the `AnswerPermissionRequest` RPC
+/// for the prompt named in the `permission_requested` event.
+///
+/// Granted permissions are added to the allowed permissions of the workflow code once
+/// the run finishes, so the same permission is not asked for again. When nobody can
+/// answer the prompts, missing permissions are denied without asking.
+pub(crate) struct PromptPermissionRequester {
+    prompts: Arc<DbPromptHandler>,
+}
+
+impl PromptPermissionRequester {
+    pub(crate) fn new(prompts: Arc<DbPromptHandler>) -> Self {
+        Self { prompts }
+    }
+}
+
+impl PermissionRequester for PromptPermissionRequester {
+    fn request(&self, request: &PermissionRequest) -> anyhow::Result<bool> {
+        if !self.prompts.is_answerable() {
+            debug!(
+                "Not asking for {}: prompts cannot be answered",
+                request.plugin_function_id
+            );
+            return Ok(false);
+        }
+        let message = permission_prompt_message(request);
+        let prompt_id =
+            self.prompts
+                .create(&request.workflow_id, PERMISSION_PROMPT_KIND, &message)?;
+        events::publish_for_running_workflow(Event::PermissionRequested {
+            workflow_id: request.workflow_id.clone(),
+            workflow_code_id: request.workflow_code_id.clone(),
+            plugin_function_id: request.plugin_function_id.clone(),
+            prompt_id: prompt_id.clone(),
+        });
+        let answer = self.prompts.wait(&prompt_id, PERMISSION_PROMPT_TIMEOUT)?;
+        let granted = answer == Some(Value::Bool(true));
+        info!(
+            "Permission request {}: workflow_code_id={}, function={}",
+            if granted { "granted" } else { "denied" },
+            request.workflow_code_id,
+            request.plugin_function_id
+        );
+        Ok(granted)
+    }
+}
+
+fn permission_prompt_message(request: &PermissionRequest) -> String {
+    let permissions: Vec<String> = request
+        .permissions
+        .iter()
+        .map(describe_permission)
+        .collect();
+    format!(
+        "Allow {} to use {} (workflow code {})?",
+        request.plugin_function_id,
+        permissions.join(", "),
+        request.workflow_code_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::proto::sapphillon::v1::{Permission, PermissionLevel, PermissionType};
+
+    #[test]
+    fn unanswerable_requests_are_denied_at_once() {
+        let prompts = DbPromptHandler::connect(
+            "sqlite:file:permission-requester-test?mode=memory&cache=shared".to_string(),
+        )
+        .unwrap();
+        let requester = PromptPermissionRequester::new(Arc::new(prompts));
+        let request = PermissionRequest {
+            workflow_id: "wf".to_string(),
+            workflow_code_id: "wc".to_string(),
+            plugin_function_id: "app.sapphillon.core.filesystem.read".to_string(),
+            permissions: Vec::new(),
+        };
+        let started = std::time::Instant::now();
+        assert!(!requester.request(&request).unwrap());
+        assert!(started.elapsed() < PERMISSION_PROMPT_TIMEOUT);
+    }
+
+    #[test]
+    fn permission_prompt_message_names_function_and_resource() {
+        let request = PermissionRequest {
+            workflow_id: "wf".to_string(),
+            workflow_code_id: "wc".to_string(),
+            plugin_function_id: "app.sapphillon.core.filesystem.read".to_string(),
+            permissions: vec![Permission {
+                display_name: "Filesystem Read".to_string(),
+                description: "".to_string(),
+                permission_type: PermissionType::FilesystemRead as i32,
+                permission_level: PermissionLevel::Unspecified as i32,
+                resource: vec!["/tmp/report.txt".to_string()],
+            }],
+        };
+        assert_eq!(
+            permission_prompt_message(&request),
+            "Allow app.sapphillon.core.filesystem.read to use FilesystemRead on /tmp/report.txt (workflow code wc)?"
+        );
+    }
+}
@@ -5,11 +5,16 @@
 //! Plugin installer module.
 //!
 //! Handles downloading and installing external plugins from various URI schemes
-//! (https, http, file).
+//! (https, http, file) or local paths, after checking the package metadata.
 
 use anyhow::Result;
+use deno_ast::swc::ast::{Expr, Lit, ObjectLit, Program, Prop, PropName, PropOrSpread, Stmt};
+use deno_ast::{MediaType, ModuleSpecifier, ParseParams, SourceRangedForSpanned};
+use python_plugin::PythonPluginManifest;
 use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
 use std::path::Path;
+use wasm_plugin::{MANIFEST_FILE, WasmPluginManifest};
 
 /// Result of a plugin installation operation.
 #[derive(Debug)]
@@ -42,6 +47,15 @@ pub enum InstallError {
 
     #[error("installation failed: {0}")]
     InstallFailed(String),
+
+    #[error("invalid plugin package: {0}")]
+    InvalidPackage(String),
+
+    #[error("missing dependencies: {}", .0.join(", "))]
+    MissingDependencies(Vec<String>),
+
+    #[error("failed to check dependencies: {0}")]
+    DependencyCheckFailed(String),
 }
 
 /// Supported URI schemes for plugin installation.
@@ -54,6 +68,8 @@ pub enum UriScheme {
 
 impl UriScheme {
     /// Parse a URI string and return the scheme.
+    ///
+    /// Absolute local paths are treated as `file://` URIs.
     pub fn parse(uri: &str) -> Result<(Self, &str), InstallError> {
         if uri.starts_with("https://") {
             Ok((UriScheme::Https, uri.trim_start_matches("https://")))
@@ -61,6 +77,8 @@ impl UriScheme {
             Ok((UriScheme::Http, uri.trim_start_matches("http://")))
         } else if uri.starts_with("file://") {
             Ok((UriScheme::File, uri.trim_start_matches("file://")))
+        } else if uri.starts_with('/') {
+            Ok((UriScheme::File, uri))
         } else {
             // Try to extract scheme for error message
             let scheme = uri.split("://").next().unwrap_or("unknown");
@@ -118,6 +136,362 @@ impl PluginMetadata {
     }
 }
 
+/// Package metadata declared by a `package.js`, read without running it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageManifest {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub author_id: String,
+    pub package_id: String,
+    /// Names of the functions the package exports.
+    pub functions: Vec<String>,
+    /// Other plugin packages this package calls.
+    pub dependencies: Vec<PackageDependency>,
+}
+
+/// A dependency declared in `meta.dependencies`, e.g. `{ "com.example.math": "^1.2" }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageDependency {
+    pub package_id: String,
+    /// Semver range the installed version must satisfy.
+    pub version_req: String,
+}
+
+impl PackageManifest {
+    /// Reads the `globalThis.Sapphillon = { Package: { meta, functions } }` assignment
+    /// of a `package.js`.
+    ///
+    /// `meta.name`, `meta.version`, `meta.author_id` and `meta.package_id` must be
+    /// string literals, and `functions` must declare at least one function. The
+    /// optional `meta.dependencies` maps package IDs to semver ranges.
+    pub fn parse(content: &[u8]) -> Result<Self, InstallError> {
+        let invalid = |message: &str| InstallError::InvalidPackage(message.to_string());
+
+        let source =
+            std::str::from_utf8(content).map_err(|_| invalid("package.js is not valid UTF-8"))?;
+        let specifier = ModuleSpecifier::parse("file:///package.js")
+            .map_err(|e| InstallError::InvalidPackage(e.to_string()))?;
+        let parsed = deno_ast::parse_script(ParseParams {
+            specifier,
+            text: source.into(),
+            media_type: MediaType::JavaScript,
+            capture_tokens: false,
+            scope_analysis: false,
+            maybe_syntax: None,
+        })
+        .map_err(|e| InstallError::InvalidPackage(e.to_string()))?;
+
+        let text: &str = parsed.text();
+        let start = parsed.text_info_lazy().range().start;
+        let slice = |range: std::ops::Range<usize>| &text[range];
+
+        let program = parsed.program();
+        let Program::Script(script) = &*program else {
+            return Err(invalid("package.js must be a script"));
+        };
+        let sapphillon = script
+            .body
+            .iter()
+            .find_map(|stmt| {
+                let Stmt::Expr(expr_stmt) = stmt else {
+                    return None;
+                };
+                let Expr::Assign(assign) = &*expr_stmt.expr else {
+                    return None;
+                };
+                let target = slice(assign.left.range().as_byte_range(start));
+                (target == "globalThis.Sapphillon").then_some(&*assign.right)
+            })
+            .ok_or_else(|| invalid("package.js does not assign globalThis.Sapphillon"))?;
+
+        let property = |object: &Expr, name: &str| -> Option<Expr> {
+            let Expr::Object(object) = object else {
+                return None;
+            };
+            find_property(object, name, text, start)
+        };
+        let package = property(sapphillon, "Package")
+            .ok_or_else(|| invalid("globalThis.Sapphillon.Package must be an object"))?;
+        let meta =
+            property(&package, "meta").ok_or_else(|| invalid("Package.meta must be an object"))?;
+        let string = |name: &str, required: bool| -> Result<String, InstallError> {
+            match property(&meta, name) {
+                Some(Expr::Lit(Lit::Str(literal))) => {
+                    let value = unquote(slice(literal.range().as_byte_range(start)));
+                    if required && value.trim().is_empty() {
+                        return Err(InstallError::InvalidPackage(format!(
+                            "Package.meta.{name} must not be empty"
+                        )));
+                    }
+                    Ok(value.to_string())
+                }
+                None if !required => Ok(String::new()),
+                _ => Err(InstallError::InvalidPackage(format!(
+                    "Package.meta.{name} must be a string literal"
+                ))),
+            }
+        };
+
+        let functions = match property(&package, "functions") {
+            Some(Expr::Object(object)) => object
+                .props
+                .iter()
+                .filter_map(|prop| match prop {
+                    PropOrSpread::Prop(prop) => match &**prop {
+                        Prop::KeyValue(kv) => Some(&kv.key),
+                        Prop::Method(method) => Some(&method.key),
+                        _ => None,
+                    },
+                    PropOrSpread::Spread(_) => None,
+                })
+                .map(|key| unquote(slice(key.range().as_byte_range(start))).to_string())
+                .collect::<Vec<_>>(),
+            _ => return Err(invalid("Package.functions must be an object")),
+        };
+        if functions.is_empty() {
+            return Err(invalid("Package.functions declares no functions"));
+        }
+
+        let dependencies = match property(&meta, "dependencies") {
+            None => vec![],
+            Some(Expr::Object(object)) => object
+                .props
+                .iter()
+                .map(|prop| {
+                    let not_a_range = || {
+                        invalid("Package.meta.dependencies must map package IDs to version ranges")
+                    };
+                    let PropOrSpread::Prop(prop) = prop else {
+                        return Err(not_a_range());
+                    };
+                    let Prop::KeyValue(kv) = &**prop else {
+                        return Err(not_a_range());
+                    };
+                    let Expr::Lit(Lit::Str(literal)) = &*kv.value else {
+                        return Err(not_a_range());
+                    };
+                    let package_id = unquote(slice(kv.key.range().as_byte_range(start)));
+                    let version_req = unquote(slice(literal.range().as_byte_range(start)));
+                    semver::VersionReq::parse(version_req).map_err(|e| {
+                        InstallError::InvalidPackage(format!(
+                            "dependency {package_id} has an invalid version range '{version_req}': {e}"
+                        ))
+                    })?;
+                    Ok(PackageDependency {
+                        package_id: package_id.to_string(),
+                        version_req: version_req.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(invalid("Package.meta.dependencies must be an object")),
+        };
+
+        Ok(Self {
+            name: string("name", true)?,
+            version: string("version", true)?,
+            description: string("description", false)?,
+            author_id: string("author_id", true)?,
+            package_id: string("package_id", true)?,
+            functions,
+            dependencies,
+        })
+    }
+}
+
+/// Returns the value of the `name` property of an object literal.
+fn find_property(
+    object: &ObjectLit,
+    name: &str,
+    text: &str,
+    start: deno_ast::SourcePos,
+) -> Option<Expr> {
+    object.props.iter().find_map(|prop| {
+        let PropOrSpread::Prop(prop) = prop else {
+            return None;
+        };
+        let Prop::KeyValue(kv) = &**prop else {
+            return None;
+        };
+        let key = match &kv.key {
+            PropName::Ident(_) | PropName::Str(_) => {
+                unquote(&text[kv.key.range().as_byte_range(start)])
+            }
+            _ => return None,
+        };
+        (key == name).then(|| (*kv.value).clone())
+    })
+}
+
+/// Strips the quotes of a string literal.
+fn unquote(literal: &str) -> &str {
+    if literal.len() >= 2 && (literal.starts_with('"') || literal.starts_with('\'')) {
+        &literal[1..literal.len() - 1]
+    } else {
+        literal
+    }
+}
+
+/// Returns whether `segment` can be used as a directory name in the install layout.
+fn is_safe_path_segment(segment: &str) -> bool {
+    !segment.is_empty() && segment != "." && segment != ".." && !segment.contains(['/', '\\'])
+}
+
+/// Decides the `author/package/version` directories a plugin is installed into.
+///
+/// URIs that follow the install layout keep their directories, but the version
+/// directory must match the version the package declares. Other URIs, such as a
+/// `package.js` downloaded into an arbitrary directory, use the package metadata.
+fn resolve_metadata(
+    scheme: &UriScheme,
+    path: &str,
+    manifest: &PackageManifest,
+) -> Result<PluginMetadata, InstallError> {
+    let metadata = match PluginMetadata::from_uri_path(path, scheme) {
+        Ok(metadata) => {
+            if metadata.version != manifest.version {
+                return Err(InstallError::InvalidPackage(format!(
+                    "version directory '{}' does not match package version '{}'",
+                    metadata.version, manifest.version
+                )));
+            }
+            metadata
+        }
+        Err(InstallError::InvalidUriFormat(_)) => return metadata_from_manifest(manifest),
+        Err(e) => return Err(e),
+    };
+    check_directories(metadata)
+}
+
+/// Uses the `author_id`, `name` and `version` of a package as its install directories.
+fn metadata_from_manifest(manifest: &PackageManifest) -> Result<PluginMetadata, InstallError> {
+    check_directories(PluginMetadata {
+        author_id: manifest.author_id.clone(),
+        package_id: manifest.name.clone(),
+        version: manifest.version.clone(),
+    })
+}
+
+fn check_directories(metadata: PluginMetadata) -> Result<PluginMetadata, InstallError> {
+    for segment in [&metadata.author_id, &metadata.package_id, &metadata.version] {
+        if !is_safe_path_segment(segment) {
+            return Err(InstallError::InvalidPackage(format!(
+                "'{segment}' cannot be used as a plugin directory name"
+            )));
+        }
+    }
+    Ok(metadata)
+}
+
+/// Checks that every dependency of a package is installed in a version that satisfies
+/// its range.
+///
+/// Built-in and other registered plugin packages count as installed, as do enabled
+/// external plugins that are present on disk.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `manifest` - Package whose dependencies are checked
+///
+/// # Returns
+///
+/// Returns [`InstallError::MissingDependencies`] listing every unsatisfied dependency.
+pub async fn check_dependencies(
+    db: &DatabaseConnection,
+    manifest: &PackageManifest,
+) -> Result<(), InstallError> {
+    if manifest.dependencies.is_empty() {
+        return Ok(());
+    }
+    let installed = installed_package_versions(db, &manifest.dependencies)
+        .await
+        .map_err(|e| InstallError::DependencyCheckFailed(e.to_string()))?;
+    let missing = unsatisfied_dependencies(&manifest.dependencies, &installed);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(InstallError::MissingDependencies(missing))
+    }
+}
+
+/// Collects the installed versions of the packages named by `dependencies`.
+async fn installed_package_versions(
+    db: &DatabaseConnection,
+    dependencies: &[PackageDependency],
+) -> Result<HashMap<String, Vec<String>>, sea_orm::DbErr> {
+    let mut installed: HashMap<String, Vec<String>> = HashMap::new();
+
+    for dependency in dependencies {
+        if let Some(package) = database::plugin::get_plugin(db, &dependency.package_id).await? {
+            installed
+                .entry(package.package_id)
+                .or_default()
+                .push(package.package_version);
+        }
+    }
+
+    for ext_plugin in database::ext_plugin::list_ext_plugin_packages(db).await? {
+        if ext_plugin.missing || !ext_plugin.enabled {
+            continue;
+        }
+        let install_dir = Path::new(&ext_plugin.install_dir);
+        let declared = if let Ok(content) = std::fs::read(install_dir.join("package.js")) {
+            PackageManifest::parse(&content)
+                .ok()
+                .map(|manifest| (manifest.package_id, manifest.version))
+        } else if let Ok(json) = std::fs::read_to_string(install_dir.join(MANIFEST_FILE)) {
+            WasmPluginManifest::parse(&json)
+                .map(|manifest| (manifest.meta.package_id, manifest.meta.version))
+                .or_else(|_| {
+                    PythonPluginManifest::parse(&json)
+                        .map(|manifest| (manifest.meta.package_id, manifest.meta.version))
+                })
+                .ok()
+        } else {
+            None
+        };
+        if let Some((package_id, version)) = declared {
+            installed.entry(package_id).or_default().push(version);
+        }
+    }
+
+    Ok(installed)
+}
+
+/// Describes every dependency that none of the installed versions satisfies.
+fn unsatisfied_dependencies(
+    dependencies: &[PackageDependency],
+    installed: &HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    dependencies
+        .iter()
+        .filter_map(|dependency| {
+            // Ranges were validated when the manifest was parsed.
+            let req = semver::VersionReq::parse(&dependency.version_req).ok()?;
+            let versions = installed
+                .get(&dependency.package_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let satisfied = versions.iter().any(|version| {
+                semver::Version::parse(version).is_ok_and(|version| req.matches(&version))
+            });
+            if satisfied {
+                return None;
+            }
+            let found = if versions.is_empty() {
+                "not installed".to_string()
+            } else {
+                format!("installed: {}", versions.join(", "))
+            };
+            Some(format!(
+                "{} {} ({found})",
+                dependency.package_id, dependency.version_req
+            ))
+        })
+        .collect()
+}
+
 /// Fetch plugin content from a URI.
 ///
 /// Supports https, http, and file schemes.
@@ -151,13 +525,51 @@ pub async fn fetch_plugin_content(uri: &str) -> Result<Vec<u8>, InstallError> {
     }
 }
 
+/// A plugin package that was fetched and validated but not installed.
+#[derive(Debug, Clone)]
+pub struct PluginInspection {
+    /// Directories the package would be installed into.
+    pub metadata: PluginMetadata,
+    pub manifest: PackageManifest,
+}
+
+/// Fetches a plugin and validates its metadata without installing it.
+///
+/// # Arguments
+///
+/// * `uri` - URI of the plugin (https://, http://, file://, or an absolute path)
+///
+/// # Returns
+///
+/// Returns the package metadata and the directories it would be installed into.
+pub async fn inspect_plugin_from_uri(uri: &str) -> Result<PluginInspection, InstallError> {
+    let (inspection, _) = fetch_and_validate(uri).await?;
+    Ok(inspection)
+}
+
+async fn fetch_and_validate(uri: &str) -> Result<(PluginInspection, Vec<u8>), InstallError> {
+    // Validate URI
+    let uri = uri.trim();
+    if uri.is_empty() {
+        return Err(InstallError::EmptyUri);
+    }
+    let (scheme, path) = UriScheme::parse(uri)?;
+
+    // Fetch content and read its metadata
+    let content = fetch_plugin_content(uri).await?;
+    let manifest = PackageManifest::parse(&content)?;
+    let metadata = resolve_metadata(&scheme, path, &manifest)?;
+
+    Ok((PluginInspection { metadata, manifest }, content))
+}
+
 /// Install a plugin from a URI.
 ///
 /// # Arguments
 ///
 /// * `db` - Database connection
 /// * `save_dir` - Base directory to save plugins
-/// * `uri` - URI of the plugin (https://, http://, or file://)
+/// * `uri` - URI of the plugin (https://, http://, file://, or an absolute path)
 ///
 /// # Returns
 ///
@@ -167,29 +579,52 @@ pub async fn install_plugin_from_uri(
     save_dir: &str,
     uri: &str,
 ) -> Result<InstallResult, InstallError> {
-    use crate::ext_plugin_manager::install_ext_plugin;
-
-    // Validate URI
-    let uri = uri.trim();
-    if uri.is_empty() {
-        return Err(InstallError::EmptyUri);
-    }
+    let (PluginInspection { metadata, manifest }, content) = fetch_and_validate(uri).await?;
+    check_dependencies(db, &manifest).await?;
+    install_validated(db, save_dir, &metadata, &content).await
+}
 
-    // Parse scheme and extract metadata
-    let (scheme, path) = UriScheme::parse(uri)?;
-    let metadata = PluginMetadata::from_uri_path(path, &scheme)?;
+/// Install a `package.js` that was obtained without a URI, e.g. from the plugin store.
+///
+/// The package is installed into the directories named by its `author_id`, `name`
+/// and `version`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection
+/// * `save_dir` - Base directory to save plugins
+/// * `content` - Contents of the `package.js`
+///
+/// # Returns
+///
+/// Returns the installation result and the package metadata.
+pub async fn install_plugin_content(
+    db: &DatabaseConnection,
+    save_dir: &str,
+    content: &[u8],
+) -> Result<(InstallResult, PackageManifest), InstallError> {
+    let manifest = PackageManifest::parse(content)?;
+    let metadata = metadata_from_manifest(&manifest)?;
+    check_dependencies(db, &manifest).await?;
+    let result = install_validated(db, save_dir, &metadata, content).await?;
+    Ok((result, manifest))
+}
 
-    // Fetch content
-    let content = fetch_plugin_content(uri).await?;
+async fn install_validated(
+    db: &DatabaseConnection,
+    save_dir: &str,
+    metadata: &PluginMetadata,
+    content: &[u8],
+) -> Result<InstallResult, InstallError> {
+    use crate::ext_plugin_manager::install_ext_plugin;
 
-    // Install
     let plugin_package_id = install_ext_plugin(
         db,
         save_dir,
         &metadata.author_id,
         &metadata.package_id,
         &metadata.version,
-        &content,
+        content,
     )
     .await
     .map_err(|e| {
@@ -274,4 +709,160 @@ mod tests {
         let result = PluginMetadata::from_uri_path("example.com/short", &UriScheme::Https);
         assert!(matches!(result, Err(InstallError::InvalidUriFormat(_))));
     }
+
+    const PACKAGE_JS: &str = r#"
+        globalThis.Sapphillon = {
+            Package: {
+                meta: {
+                    name: "math-plugin",
+                    version: "1.2.0",
+                    description: "Math functions",
+                    author_id: "com.example",
+                    "package_id": 'com.example.math-plugin'
+                },
+                functions: {
+                    add: { handler: (a, b) => a + b },
+                    "sub": { handler: (a, b) => a - b },
+                    mul(a, b) { return a * b; }
+                }
+            }
+        };
+    "#;
+
+    #[test]
+    fn test_manifest_parse() {
+        let manifest = PackageManifest::parse(PACKAGE_JS.as_bytes()).unwrap();
+        assert_eq!(manifest.name, "math-plugin");
+        assert_eq!(manifest.version, "1.2.0");
+        assert_eq!(manifest.description, "Math functions");
+        assert_eq!(manifest.author_id, "com.example");
+        assert_eq!(manifest.package_id, "com.example.math-plugin");
+        assert_eq!(manifest.functions, vec!["add", "sub", "mul"]);
+    }
+
+    #[test]
+    fn test_manifest_parse_rejects_invalid_packages() {
+        for content in [
+            "console.log('hello');",
+            "globalThis.Sapphillon = { Package: { meta: { name: 'x' }, functions: {} } };",
+            "globalThis.Sapphillon = { Package: { meta: { name: 'x', version: v, author_id: 'a', package_id: 'a.x' }, functions: { f: {} } } };",
+            "globalThis.Sapphillon = {",
+        ] {
+            let result = PackageManifest::parse(content.as_bytes());
+            assert!(
+                matches!(result, Err(InstallError::InvalidPackage(_))),
+                "{content} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_manifest_parse_dependencies() {
+        let manifest = PackageManifest::parse(PACKAGE_JS.as_bytes()).unwrap();
+        assert!(manifest.dependencies.is_empty());
+
+        let content = PACKAGE_JS.replace(
+            "description: \"Math functions\",",
+            "description: \"Math functions\", dependencies: { \"com.example.base\": \"^1.2\", numbers: '>=0.3, <0.5' },",
+        );
+        let manifest = PackageManifest::parse(content.as_bytes()).unwrap();
+        assert_eq!(
+            manifest.dependencies,
+            vec![
+                PackageDependency {
+                    package_id: "com.example.base".to_string(),
+                    version_req: "^1.2".to_string(),
+                },
+                PackageDependency {
+                    package_id: "numbers".to_string(),
+                    version_req: ">=0.3, <0.5".to_string(),
+                },
+            ]
+        );
+
+        for dependencies in ["[]", "{ base: 1 }", "{ base: 'not a range' }"] {
+            let content = PACKAGE_JS.replace(
+                "description: \"Math functions\",",
+                &format!("description: \"Math functions\", dependencies: {dependencies},"),
+            );
+            assert!(
+                matches!(
+                    PackageManifest::parse(content.as_bytes()),
+                    Err(InstallError::InvalidPackage(_))
+                ),
+                "{dependencies} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsatisfied_dependencies() {
+        let dependencies = vec![
+            PackageDependency {
+                package_id: "com.example.base".to_string(),
+                version_req: "^1.2".to_string(),
+            },
+            PackageDependency {
+                package_id: "com.example.old".to_string(),
+                version_req: "^2".to_string(),
+            },
+            PackageDependency {
+                package_id: "com.example.absent".to_string(),
+                version_req: "*".to_string(),
+            },
+        ];
+        let installed = HashMap::from([
+            (
+                "com.example.base".to_string(),
+                vec!["1.0.0".to_string(), "1.4.1".to_string()],
+            ),
+            ("com.example.old".to_string(), vec!["1.9.0".to_string()]),
+        ]);
+
+        assert_eq!(
+            unsatisfied_dependencies(&dependencies, &installed),
+            vec![
+                "com.example.old ^2 (installed: 1.9.0)".to_string(),
+                "com.example.absent * (not installed)".to_string(),
+            ]
+        );
+        assert_eq!(
+            InstallError::MissingDependencies(unsatisfied_dependencies(&dependencies, &installed))
+                .to_string(),
+            "missing dependencies: com.example.old ^2 (installed: 1.9.0), com.example.absent * (not installed)"
+        );
+    }
+
+    #[test]
+    fn test_resolve_metadata_checks_version_directory() {
+        let manifest = PackageManifest::parse(PACKAGE_JS.as_bytes()).unwrap();
+
+        let meta = resolve_metadata(
+            &UriScheme::File,
+            "/plugins/myauthor/math/1.2.0/package.js",
+            &manifest,
+        )
+        .unwrap();
+        assert_eq!(meta.author_id, "myauthor");
+        assert_eq!(meta.package_id, "math");
+
+        let result = resolve_metadata(
+            &UriScheme::File,
+            "/plugins/myauthor/math/1.0.0/package.js",
+            &manifest,
+        );
+        assert!(matches!(result, Err(InstallError::InvalidPackage(_))));
+
+        let meta = resolve_metadata(&UriScheme::File, "/tmp/package.js", &manifest).unwrap();
+        assert_eq!(meta.author_id, "com.example");
+        assert_eq!(meta.package_id, "math-plugin");
+        assert_eq!(meta.version, "1.2.0");
+    }
+
+    #[test]
+    fn test_uri_scheme_parse_local_path() {
+        let (scheme, path) = UriScheme::parse("/home/user/package.js").unwrap();
+        assert_eq!(scheme, UriScheme::File);
+        assert_eq!(path, "/home/user/package.js");
+    }
 }
@@ -0,0 +1,220 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Loads the installed external plugins into the running server
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use database::ext_plugin::list_ext_plugin_packages;
+use database::plugin::init_register_plugins;
+use entity::entity::ext_plugin_package::Model as ExtPluginPackage;
+use python_plugin::{PythonLimits, PythonPlugin, replace_python_plugins};
+use sapphillon_core::proto::sapphillon::v1::PluginPackage;
+use sea_orm::DatabaseConnection;
+use wasm_plugin::{MANIFEST_FILE, WasmLimits, WasmPlugin, replace_wasm_plugins};
+
+use crate::GLOBAL_STATE;
+use crate::plugin_installer::{PackageManifest, check_dependencies};
+use crate::plugin_store::compare_versions;
+use crate::plugin_validation::{PluginDiagnostic, function_ids, validate_plugin_packages};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Reloads the enabled external plugins so workflows started afterwards use them.
+///
+/// The WASM and Python plugins are loaded again and replace the ones loaded before,
+/// and their packages are registered. `sysconfig()` builds the runtime packages for every
+/// workflow run, so nothing else has to be invalidated. Plugins whose dependencies
+/// are no longer installed are logged.
+///
+/// A plugin that fails to load or to validate is logged and skipped so it cannot keep
+/// the others from loading.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to read installed plugins and register packages.
+///
+/// # Returns
+///
+/// Returns the IDs of the loaded plugins, e.g. `author/package/1.0.0`.
+pub(crate) async fn reload_plugins(db: &DatabaseConnection) -> Result<Vec<String>> {
+    let (builtin, _) = builtin_plugin_packages();
+    let ExtPlugins {
+        plugins,
+        diagnostics,
+    } = load_ext_plugins(db, &function_ids(&builtin)).await?;
+    for diagnostic in &diagnostics {
+        error!("Rejected plugin {diagnostic}");
+    }
+
+    let mut loaded = Vec::new();
+    let mut packages = Vec::new();
+    let mut wasm_plugins = Vec::new();
+    let mut python_plugins = Vec::new();
+    for (plugin_package_id, plugin) in plugins {
+        info!("Loaded plugin: {plugin_package_id}");
+        packages.push(plugin.plugin_package());
+        match plugin {
+            LoadedPlugin::Wasm(wasm) => wasm_plugins.push(wasm),
+            LoadedPlugin::Python(python) => python_plugins.push(python),
+        }
+        loaded.push(plugin_package_id);
+    }
+
+    init_register_plugins(db, packages).await?;
+    replace_wasm_plugins(wasm_plugins);
+    replace_python_plugins(python_plugins);
+    GLOBAL_STATE
+        .async_set_loaded_ext_plugins(loaded.clone())
+        .await;
+
+    Ok(loaded)
+}
+
+/// Validates the built-in and the enabled external plugins without loading them.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to read installed plugins.
+///
+/// # Returns
+///
+/// Returns the problems of every plugin that is rejected when plugins are loaded.
+pub(crate) async fn check_plugins(db: &DatabaseConnection) -> Result<Vec<PluginDiagnostic>> {
+    let (builtin, mut diagnostics) = builtin_plugin_packages();
+    diagnostics.extend(
+        load_ext_plugins(db, &function_ids(&builtin))
+            .await?
+            .diagnostics,
+    );
+    Ok(diagnostics)
+}
+
+/// The built-in plugin packages that pass validation, and the problems of the others.
+pub(crate) fn builtin_plugin_packages() -> (Vec<PluginPackage>, Vec<PluginDiagnostic>) {
+    validate_plugin_packages(
+        crate::sysconfig::sysconfig().initial_plugins,
+        &HashSet::new(),
+    )
+}
+
+/// External plugins ready to be loaded, sorted by plugin package ID.
+struct ExtPlugins {
+    plugins: Vec<(String, LoadedPlugin)>,
+    diagnostics: Vec<PluginDiagnostic>,
+}
+
+async fn load_ext_plugins(
+    db: &DatabaseConnection,
+    builtin_function_ids: &HashSet<String>,
+) -> Result<ExtPlugins> {
+    let enabled: Vec<ExtPluginPackage> = list_ext_plugin_packages(db)
+        .await?
+        .into_iter()
+        .filter(|plugin| plugin.enabled && !plugin.missing)
+        .collect();
+
+    for plugin in &enabled {
+        let package_js = Path::new(&plugin.install_dir).join("package.js");
+        let Ok(content) = std::fs::read(&package_js) else {
+            continue;
+        };
+        let Ok(manifest) = PackageManifest::parse(&content) else {
+            continue;
+        };
+        if let Err(e) = check_dependencies(db, &manifest).await {
+            warn!("External plugin {}: {e}", plugin.plugin_package_id);
+        }
+    }
+
+    // Versions installed side by side share a package ID; only the newest is loaded.
+    let mut diagnostics = Vec::new();
+    let mut newest: HashMap<String, (String, LoadedPlugin)> = HashMap::new();
+    for plugin in &enabled {
+        let install_dir = Path::new(&plugin.install_dir);
+        let Ok(json) = std::fs::read_to_string(install_dir.join(MANIFEST_FILE)) else {
+            continue;
+        };
+        let loaded = match LoadedPlugin::load(install_dir, &json) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                diagnostics.push(PluginDiagnostic {
+                    package_id: plugin.plugin_package_id.clone(),
+                    function_id: None,
+                    message: format!("failed to load: {e}"),
+                });
+                continue;
+            }
+        };
+        let (package_id, version) = loaded.package_id_and_version();
+        let replace = newest.get(package_id).is_none_or(|(_, current)| {
+            compare_versions(version, current.package_id_and_version().1) == Ordering::Greater
+        });
+        if replace {
+            newest.insert(
+                package_id.to_string(),
+                (plugin.plugin_package_id.clone(), loaded),
+            );
+        }
+    }
+
+    let mut candidates: Vec<(String, LoadedPlugin)> = newest.into_values().collect();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+    let packages = candidates
+        .iter()
+        .map(|(_, plugin)| plugin.plugin_package())
+        .collect();
+    let (valid, rejected) = validate_plugin_packages(packages, builtin_function_ids);
+    diagnostics.extend(rejected);
+
+    let valid_ids: HashSet<&str> = valid.iter().map(|p| p.package_id.as_str()).collect();
+    let plugins = candidates
+        .into_iter()
+        .filter(|(_, plugin)| valid_ids.contains(plugin.package_id_and_version().0))
+        .collect();
+    Ok(ExtPlugins {
+        plugins,
+        diagnostics,
+    })
+}
+
+/// An external plugin described by a `plugin.json`.
+enum LoadedPlugin {
+    Wasm(WasmPlugin),
+    Python(PythonPlugin),
+}
+
+impl LoadedPlugin {
+    /// Loads the plugin with the runtime named by the manifest's `runtime` field;
+    /// manifests without one are WASM plugins.
+    fn load(dir: &Path, manifest_json: &str) -> Result<Self> {
+        let manifest: serde_json::Value = serde_json::from_str(manifest_json)?;
+        match manifest.get("runtime").and_then(|runtime| runtime.as_str()) {
+            None | Some("wasm") => Ok(Self::Wasm(WasmPlugin::load(dir, WasmLimits::default())?)),
+            Some(python_plugin::RUNTIME) => Ok(Self::Python(PythonPlugin::load(
+                dir,
+                PythonLimits::default(),
+            )?)),
+            Some(runtime) => anyhow::bail!("unknown plugin runtime '{runtime}'"),
+        }
+    }
+
+    fn plugin_package(&self) -> PluginPackage {
+        match self {
+            Self::Wasm(wasm) => wasm.plugin_package(),
+            Self::Python(python) => python.plugin_package(),
+        }
+    }
+
+    fn package_id_and_version(&self) -> (&str, &str) {
+        match self {
+            Self::Wasm(wasm) => (&wasm.meta().package_id, &wasm.meta().version),
+            Self::Python(python) => (&python.meta().package_id, &python.meta().version),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Plugin store client.
+//!
+//! A plugin store is a registry served over HTTP(S) or from a local directory. Its
+//! `index.json` lists the published package versions:
+//!
+//! ```json
+//! {
+//!   "packages": [{
+//!     "package_id": "com.example.math-plugin",
+//!     "name": "math-plugin",
+//!     "author_id": "com.example",
+//!     "version": "1.2.0",
+//!     "description": "Math functions",
+//!     "tags": ["math"],
+//!     "download_url": "packages/math-plugin-1.2.0.tar.gz",
+//!     "sha256": "9f86d081..."
+//!   }]
+//! }
+//! ```
+//!
+//! Each download is a gzipped tarball containing a `package.js`. Relative download
+//! URLs are resolved against the registry URL.
+
+use std::cmp::Ordering;
+use std::io::Read;
+use std::sync::OnceLock;
+
+use flate2::read::GzDecoder;
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::plugin_installer::{
+    InstallError, InstallResult, PackageManifest, fetch_plugin_content, install_plugin_content,
+};
+
+static STORE_URL: OnceLock<String> = OnceLock::new();
+
+/// Sets the registry used by the plugin store commands. Only the first call takes effect.
+///
+/// # Arguments
+///
+/// * `url` - Base URL of the registry, e.g. `https://plugins.example.com`.
+///
+/// # Returns
+///
+/// Returns `true` if the registry was set, `false` if one was already configured.
+pub(crate) fn set_store_url(url: String) -> bool {
+    STORE_URL.set(url.trim_end_matches('/').to_string()).is_ok()
+}
+
+/// Returns the configured registry URL, if any.
+pub(crate) fn store_url() -> Option<&'static str> {
+    STORE_URL.get().map(String::as_str)
+}
+
+/// Error types for plugin store operations.
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("no plugin store is configured; start with --plugin-store-url")]
+    NotConfigured,
+
+    #[error("failed to read the plugin store index: {0}")]
+    InvalidIndex(String),
+
+    #[error("package not found in the plugin store: {0}")]
+    NotFound(String),
+
+    #[error("hash mismatch for {package}: expected {expected}, got {actual}")]
+    HashMismatch {
+        package: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("invalid package archive: {0}")]
+    InvalidArchive(String),
+
+    #[error(transparent)]
+    Install(#[from] InstallError),
+}
+
+/// A package version published in a plugin store.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StorePackage {
+    pub package_id: String,
+    pub name: String,
+    pub author_id: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Location of the package tarball, absolute or relative to the registry URL.
+    pub download_url: String,
+    /// Hex-encoded SHA-256 of the tarball.
+    pub sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoreIndex {
+    packages: Vec<StorePackage>,
+}
+
+/// Client for a plugin store registry.
+#[derive(Debug, Clone)]
+pub struct PluginStoreClient {
+    registry_url: String,
+}
+
+impl PluginStoreClient {
+    /// Creates a client for the registry at `registry_url`.
+    pub fn new(registry_url: impl Into<String>) -> Self {
+        let registry_url: String = registry_url.into();
+        Self {
+            registry_url: registry_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates a client for the registry set with [`set_store_url`].
+    pub fn configured() -> Result<Self, StoreError> {
+        store_url().map(Self::new).ok_or(StoreError::NotConfigured)
+    }
+
+    /// Fetches every package version listed by the registry.
+    pub async fn index(&self) -> Result<Vec<StorePackage>, StoreError> {
+        let content = fetch_plugin_content(&self.resolve("index.json")).await?;
+        let index: StoreIndex = serde_json::from_slice(&content)
+            .map_err(|e| StoreError::InvalidIndex(e.to_string()))?;
+        Ok(index.packages)
+    }
+
+    /// Searches the registry, newest version of each package first.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Text matched case-insensitively against the package ID, name and
+    ///   description; an empty query matches every package.
+    /// * `tag` - Only return packages carrying this tag.
+    pub async fn search(
+        &self,
+        query: &str,
+        tag: Option<&str>,
+    ) -> Result<Vec<StorePackage>, StoreError> {
+        Ok(search_packages(self.index().await?, query, tag))
+    }
+
+    /// Finds a package version; the newest version when `version` is `None`.
+    pub async fn find(
+        &self,
+        package_id: &str,
+        version: Option<&str>,
+    ) -> Result<StorePackage, StoreError> {
+        self.index()
+            .await?
+            .into_iter()
+            .filter(|package| package.package_id == package_id)
+            .filter(|package| version.is_none_or(|version| package.version == version))
+            .max_by(|a, b| compare_versions(&a.version, &b.version))
+            .ok_or_else(|| match version {
+                Some(version) => StoreError::NotFound(format!("{package_id}@{version}")),
+                None => StoreError::NotFound(package_id.to_string()),
+            })
+    }
+
+    /// Downloads a package tarball, verifies its hash and returns its `package.js`.
+    pub async fn download(&self, package: &StorePackage) -> Result<Vec<u8>, StoreError> {
+        let archive = fetch_plugin_content(&self.resolve(&package.download_url)).await?;
+        verify_sha256(package, &archive)?;
+        let content = extract_package_js(&archive)?;
+
+        // The package must be the one the index advertised.
+        let manifest = PackageManifest::parse(&content)?;
+        if manifest.package_id != package.package_id || manifest.version != package.version {
+            return Err(StoreError::InvalidArchive(format!(
+                "archive contains {}@{} instead of {}@{}",
+                manifest.package_id, manifest.version, package.package_id, package.version
+            )));
+        }
+        Ok(content)
+    }
+
+    /// Downloads a package from the registry and installs it.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - Database connection
+    /// * `save_dir` - Base directory to save plugins
+    /// * `package_id` - Package to install, e.g. `com.example.math-plugin`
+    /// * `version` - Version to install; the newest when `None`
+    ///
+    /// # Returns
+    ///
+    /// Returns the installation result and the store entry that was installed.
+    pub async fn install(
+        &self,
+        db: &DatabaseConnection,
+        save_dir: &str,
+        package_id: &str,
+        version: Option<&str>,
+    ) -> Result<(InstallResult, StorePackage), StoreError> {
+        let package = self.find(package_id, version).await?;
+        let content = self.download(&package).await?;
+        let (result, _) = install_plugin_content(db, save_dir, &content).await?;
+        Ok((result, package))
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        if url.contains("://") {
+            url.to_string()
+        } else {
+            format!("{}/{}", self.registry_url, url.trim_start_matches('/'))
+        }
+    }
+}
+
+fn search_packages(
+    packages: Vec<StorePackage>,
+    query: &str,
+    tag: Option<&str>,
+) -> Vec<StorePackage> {
+    let query = query.trim().to_lowercase();
+    let mut found: Vec<StorePackage> = packages
+        .into_iter()
+        .filter(|package| {
+            query.is_empty()
+                || package.package_id.to_lowercase().contains(&query)
+                || package.name.to_lowercase().contains(&query)
+                || package.description.to_lowercase().contains(&query)
+        })
+        .filter(|package| tag.is_none_or(|tag| package.tags.iter().any(|t| t == tag)))
+        .collect();
+    found.sort_by(|a, b| {
+        a.package_id
+            .cmp(&b.package_id)
+            .then_with(|| compare_versions(&b.version, &a.version))
+    });
+    found
+}
+
+/// Orders versions by semantic version, falling back to plain text for others.
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (semver::Version::parse(a), semver::Version::parse(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Greater,
+        (Err(_), Ok(_)) => Ordering::Less,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+fn verify_sha256(package: &StorePackage, archive: &[u8]) -> Result<(), StoreError> {
+    let actual = hex::encode(Sha256::digest(archive));
+    if !actual.eq_ignore_ascii_case(package.sha256.trim()) {
+        return Err(StoreError::HashMismatch {
+            package: format!("{}@{}", package.package_id, package.version),
+            expected: package.sha256.clone(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Reads the `package.js` of a gzipped tarball, at its root or in one top-level directory.
+fn extract_package_js(archive: &[u8]) -> Result<Vec<u8>, StoreError> {
+    let invalid = |e: std::io::Error| StoreError::InvalidArchive(e.to_string());
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    for entry in tar.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        let path = entry.path().map_err(invalid)?.into_owned();
+        let depth = path.components().count();
+        if entry.header().entry_type().is_file()
+            && depth <= 2
+            && path.file_name().is_some_and(|name| name == "package.js")
+        {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(invalid)?;
+            return Ok(content);
+        }
+    }
+    Err(StoreError::InvalidArchive(
+        "archive does not contain a package.js".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::MigratorTrait;
+    use sea_orm::Database;
+    use tempfile::TempDir;
+
+    const PACKAGE_JS: &str = r#"globalThis.Sapphillon = {
+    Package: {
+        meta: {
+            name: "math-plugin",
+            version: "1.2.0",
+            description: "Math functions",
+            author_id: "com.example",
+            package_id: "com.example.math-plugin"
+        },
+        functions: {
+            add: { handler: (a, b) => a + b }
+        }
+    }
+};
+"#;
+
+    fn tarball(package_js: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(package_js.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "math-plugin/package.js", package_js.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn store_package(name: &str, version: &str, tags: &[&str]) -> StorePackage {
+        StorePackage {
+            package_id: format!("com.example.{name}"),
+            name: name.to_string(),
+            author_id: "com.example".to_string(),
+            version: version.to_string(),
+            description: format!("The {name} package"),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            download_url: format!("packages/{name}-{version}.tar.gz"),
+            sha256: String::new(),
+        }
+    }
+
+    /// Writes a registry with one package into a directory and returns a client for it.
+    fn local_registry(dir: &TempDir, sha256: Option<&str>) -> PluginStoreClient {
+        let archive = tarball(PACKAGE_JS);
+        std::fs::create_dir_all(dir.path().join("packages")).unwrap();
+        std::fs::write(
+            dir.path().join("packages/math-plugin-1.2.0.tar.gz"),
+            &archive,
+        )
+        .unwrap();
+        let sha256 = sha256
+            .map(str::to_string)
+            .unwrap_or_else(|| hex::encode(Sha256::digest(&archive)));
+        let index = serde_json::json!({
+            "packages": [{
+                "package_id": "com.example.math-plugin",
+                "name": "math-plugin",
+                "author_id": "com.example",
+                "version": "1.2.0",
+                "tags": ["math"],
+                "download_url": "packages/math-plugin-1.2.0.tar.gz",
+                "sha256": sha256,
+            }]
+        });
+        std::fs::write(dir.path().join("index.json"), index.to_string()).unwrap();
+        PluginStoreClient::new(format!("file://{}", dir.path().to_string_lossy()))
+    }
+
+    #[test]
+    fn search_filters_by_query_and_tag_newest_first() {
+        let packages = vec![
+            store_package("math", "1.2.0", &["math"]),
+            store_package("math", "1.10.0", &["math"]),
+            store_package("mail", "0.1.0", &["network"]),
+        ];
+
+        let found = search_packages(packages.clone(), "MATH", None);
+        let versions: Vec<&str> = found.iter().map(|p| p.version.as_str()).collect();
+        assert_eq!(versions, vec!["1.10.0", "1.2.0"]);
+
+        let found = search_packages(packages.clone(), "", Some("network"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "mail");
+
+        assert!(search_packages(packages, "ma", Some("missing")).is_empty());
+    }
+
+    #[test]
+    fn extract_package_js_reads_nested_entry() {
+        let content = extract_package_js(&tarball(PACKAGE_JS)).unwrap();
+        assert_eq!(content, PACKAGE_JS.as_bytes());
+        assert!(matches!(
+            extract_package_js(b"not a tarball"),
+            Err(StoreError::InvalidArchive(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn install_downloads_verifies_and_installs() {
+        let registry = TempDir::new().unwrap();
+        let save_dir = TempDir::new().unwrap();
+        let client = local_registry(&registry, None);
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        migration::Migrator::up(&db, None).await.unwrap();
+
+        let (result, package) = client
+            .install(
+                &db,
+                &save_dir.path().to_string_lossy(),
+                "com.example.math-plugin",
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.plugin_package_id, "com.example/math-plugin/1.2.0");
+        assert_eq!(package.version, "1.2.0");
+        assert!(
+            save_dir
+                .path()
+                .join("com.example/math-plugin/1.2.0/package.js")
+                .exists()
+        );
+
+        let missing = client
+            .install(&db, "/unused", "com.example.math-plugin", Some("9.9.9"))
+            .await;
+        assert!(matches!(missing, Err(StoreError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn download_rejects_hash_mismatch() {
+        let registry = TempDir::new().unwrap();
+        let client = local_registry(&registry, Some("00"));
+        let package = client.find("com.example.math-plugin", None).await.unwrap();
+        assert!(matches!(
+            client.download(&package).await,
+            Err(StoreError::HashMismatch { .. })
+        ));
+    }
+}
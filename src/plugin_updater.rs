@@ -0,0 +1,261 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Checks the plugin store for newer versions of installed external plugins
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use database::ext_plugin::{
+    get_ext_plugin_package, list_ext_plugin_packages, set_ext_plugin_available_version,
+};
+use entity::entity::ext_plugin_package::Model as ExtPluginPackage;
+use sea_orm::DatabaseConnection;
+
+use crate::plugin_store::{PluginStoreClient, StorePackage, compare_versions};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// How often installed plugins are compared with the plugin store.
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Checks for plugin updates now and then every [`CHECK_INTERVAL`], as long as a plugin
+/// store is configured.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to read installed plugins and record updates.
+pub async fn start_plugin_updater(db: DatabaseConnection) {
+    let Ok(client) = PluginStoreClient::configured() else {
+        debug!("No plugin store configured; plugin update checks are disabled");
+        return;
+    };
+    info!(
+        "Plugin update checker started (interval: {}h)",
+        CHECK_INTERVAL.as_secs() / 3600
+    );
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        match check_for_updates(&db, &client).await {
+            Ok(updates) if !updates.is_empty() => {
+                info!("{} plugin update(s) available", updates.len())
+            }
+            Ok(_) => {}
+            Err(err) => error!("Plugin update check failed: {err:#}"),
+        }
+    }
+}
+
+/// Compares the installed external plugins with the plugin store and records the
+/// newer versions it finds.
+///
+/// Only the newest installed version of a package is marked; older versions installed
+/// next to it are cleared.
+///
+/// # Arguments
+///
+/// * `db` - Database connection.
+/// * `client` - Plugin store to compare with.
+///
+/// # Returns
+///
+/// Returns the installed plugins that have an update after the check.
+pub async fn check_for_updates(
+    db: &DatabaseConnection,
+    client: &PluginStoreClient,
+) -> Result<Vec<ExtPluginPackage>> {
+    let store = client.index().await?;
+    let installed = list_ext_plugin_packages(db).await?;
+
+    let mut updates = Vec::new();
+    for (plugin, available_version) in available_versions(&installed, &store) {
+        if plugin.available_version == available_version {
+            if available_version.is_some() {
+                updates.push(plugin.clone());
+            }
+            continue;
+        }
+        let updated =
+            set_ext_plugin_available_version(db, &plugin.plugin_package_id, available_version)
+                .await?;
+        if updated.available_version.is_some() {
+            updates.push(updated);
+        }
+    }
+    Ok(updates)
+}
+
+/// Installs the available update of a plugin next to the installed version.
+///
+/// # Arguments
+///
+/// * `db` - Database connection.
+/// * `save_dir` - Base directory to save plugins.
+/// * `client` - Plugin store to download from.
+/// * `plugin_package_id` - Installed plugin with an update, e.g. `author/package/1.0.0`.
+///
+/// # Returns
+///
+/// Returns the ID of the newly installed version.
+pub async fn update_plugin(
+    db: &DatabaseConnection,
+    save_dir: &str,
+    client: &PluginStoreClient,
+    plugin_package_id: &str,
+) -> Result<String> {
+    let plugin = get_ext_plugin_package(db, plugin_package_id)
+        .await?
+        .with_context(|| format!("plugin not found: {plugin_package_id}"))?;
+    let version = plugin
+        .available_version
+        .with_context(|| format!("no update is available for {plugin_package_id}"))?;
+    let (author_id, name, _) = split_plugin_package_id(plugin_package_id)
+        .with_context(|| format!("invalid plugin package id: {plugin_package_id}"))?;
+
+    let package = client
+        .index()
+        .await?
+        .into_iter()
+        .find(|package| {
+            package.author_id == author_id && package.name == name && package.version == version
+        })
+        .with_context(|| {
+            format!("{author_id}/{name}/{version} is no longer in the plugin store")
+        })?;
+    let (result, _) = client
+        .install(db, save_dir, &package.package_id, Some(&version))
+        .await?;
+
+    set_ext_plugin_available_version(db, plugin_package_id, None).await?;
+    info!(
+        "Updated plugin {plugin_package_id} to {}",
+        result.plugin_package_id
+    );
+    Ok(result.plugin_package_id)
+}
+
+/// Splits `author/package/version` into its parts.
+fn split_plugin_package_id(plugin_package_id: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = plugin_package_id.split('/');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(author_id), Some(name), Some(version), None) => Some((author_id, name, version)),
+        _ => None,
+    }
+}
+
+/// Pairs every installed plugin with the newer store version it should be marked with.
+fn available_versions<'a>(
+    installed: &'a [ExtPluginPackage],
+    store: &[StorePackage],
+) -> Vec<(&'a ExtPluginPackage, Option<String>)> {
+    let mut newest_in_store: HashMap<(&str, &str), &str> = HashMap::new();
+    for package in store {
+        let newest = newest_in_store
+            .entry((package.author_id.as_str(), package.name.as_str()))
+            .or_insert(package.version.as_str());
+        if compare_versions(&package.version, newest) == Ordering::Greater {
+            *newest = package.version.as_str();
+        }
+    }
+
+    let mut newest_installed: HashMap<(&str, &str), &str> = HashMap::new();
+    for plugin in installed {
+        if let Some((author_id, name, version)) = split_plugin_package_id(&plugin.plugin_package_id)
+        {
+            let newest = newest_installed.entry((author_id, name)).or_insert(version);
+            if compare_versions(version, newest) == Ordering::Greater {
+                *newest = version;
+            }
+        }
+    }
+
+    installed
+        .iter()
+        .map(|plugin| {
+            let available = split_plugin_package_id(&plugin.plugin_package_id).and_then(
+                |(author_id, name, version)| {
+                    let key = (author_id, name);
+                    let store_version = *newest_in_store.get(&key)?;
+                    let is_newest_installed = newest_installed.get(&key) == Some(&version);
+                    (is_newest_installed
+                        && compare_versions(store_version, version) == Ordering::Greater)
+                        .then(|| store_version.to_string())
+                },
+            );
+            (plugin, available)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(id: &str, available_version: Option<&str>) -> ExtPluginPackage {
+        ExtPluginPackage {
+            plugin_package_id: id.to_string(),
+            install_dir: format!("/plugins/{id}"),
+            missing: false,
+            enabled: true,
+            available_version: available_version.map(str::to_string),
+        }
+    }
+
+    fn published(author_id: &str, name: &str, version: &str) -> StorePackage {
+        StorePackage {
+            package_id: format!("{author_id}.{name}"),
+            name: name.to_string(),
+            author_id: author_id.to_string(),
+            version: version.to_string(),
+            description: String::new(),
+            tags: vec![],
+            download_url: format!("{name}-{version}.tar.gz"),
+            sha256: String::new(),
+        }
+    }
+
+    #[test]
+    fn available_versions_marks_only_the_newest_installed_version() {
+        let installed = vec![
+            installed("acme/math/1.0.0", None),
+            installed("acme/math/1.2.0", None),
+            installed("acme/mail/2.0.0", Some("2.1.0")),
+            installed("acme/local/0.1.0", None),
+        ];
+        let store = vec![
+            published("acme", "math", "1.10.0"),
+            published("acme", "math", "1.9.0"),
+            published("acme", "mail", "2.0.0"),
+            published("other", "local", "9.0.0"),
+        ];
+
+        let marked: Vec<(&str, Option<String>)> = available_versions(&installed, &store)
+            .into_iter()
+            .map(|(plugin, version)| (plugin.plugin_package_id.as_str(), version))
+            .collect();
+        assert_eq!(
+            marked,
+            vec![
+                ("acme/math/1.0.0", None),
+                ("acme/math/1.2.0", Some("1.10.0".to_string())),
+                ("acme/mail/2.0.0", None),
+                ("acme/local/0.1.0", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_plugin_package_id_requires_three_parts() {
+        assert_eq!(
+            split_plugin_package_id("acme/math/1.0.0"),
+            Some(("acme", "math", "1.0.0"))
+        );
+        assert_eq!(split_plugin_package_id("acme/math"), None);
+        assert_eq!(split_plugin_package_id("a/b/c/d"), None);
+    }
+}
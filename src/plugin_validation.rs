@@ -0,0 +1,262 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Checks plugin packages before their functions are offered to workflows
+
+use std::collections::HashSet;
+use std::fmt;
+
+use sapphillon_core::proto::sapphillon::v1::{PermissionLevel, PermissionType, PluginPackage};
+
+/// Function ID of the wildcard entry used for allow-all permissions.
+const WILDCARD_FUNCTION_ID: &str = "*";
+
+/// A problem found in a plugin package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PluginDiagnostic {
+    pub package_id: String,
+    /// The function the problem is in, if it is not about the package as a whole.
+    pub function_id: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for PluginDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function_id {
+            Some(function_id) => write!(f, "{function_id}: {}", self.message),
+            None => write!(f, "{}: {}", self.package_id, self.message),
+        }
+    }
+}
+
+/// Checks that every function of every package has a unique function ID under its
+/// package, a `FunctionDefine` whose parameters and returns are named and typed, and
+/// permissions of a known type and level.
+///
+/// # Arguments
+///
+/// * `packages` - Packages to check.
+/// * `known_function_ids` - Function IDs that are already taken, e.g. by built-in
+///   plugins when external plugins are checked.
+///
+/// # Returns
+///
+/// Returns the packages without problems and the problems of the others. A package
+/// with any problem is rejected as a whole.
+pub(crate) fn validate_plugin_packages(
+    packages: Vec<PluginPackage>,
+    known_function_ids: &HashSet<String>,
+) -> (Vec<PluginPackage>, Vec<PluginDiagnostic>) {
+    let mut taken = known_function_ids.clone();
+    let mut valid = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for package in packages {
+        let problems = package_problems(&package, &taken);
+        if problems.is_empty() {
+            taken.extend(package.functions.iter().map(|f| f.function_id.clone()));
+            valid.push(package);
+        } else {
+            diagnostics.extend(problems);
+        }
+    }
+    (valid, diagnostics)
+}
+
+fn package_problems(package: &PluginPackage, taken: &HashSet<String>) -> Vec<PluginDiagnostic> {
+    let mut problems = Vec::new();
+    let mut problem = |function_id: Option<&str>, message: String| {
+        problems.push(PluginDiagnostic {
+            package_id: package.package_id.clone(),
+            function_id: function_id.map(str::to_string),
+            message,
+        })
+    };
+
+    if package.package_id.trim().is_empty() {
+        problem(None, "package_id is empty".to_string());
+    }
+    if package.functions.is_empty() {
+        problem(None, "declares no functions".to_string());
+    }
+
+    let prefix = format!("{}.", package.package_id);
+    let mut seen = HashSet::new();
+    for function in &package.functions {
+        let id = function.function_id.as_str();
+        if id == WILDCARD_FUNCTION_ID {
+            continue;
+        }
+        if !id.starts_with(&prefix) || id.len() == prefix.len() {
+            problem(Some(id), format!("function_id must be '{prefix}<name>'"));
+        }
+        if !seen.insert(id) || taken.contains(id) {
+            problem(
+                Some(id),
+                "function_id is declared more than once".to_string(),
+            );
+        }
+
+        match &function.function_define {
+            None => problem(Some(id), "has no FunctionDefine".to_string()),
+            Some(define) => {
+                for (kind, parameters) in [
+                    ("parameter", &define.parameters),
+                    ("return", &define.returns),
+                ] {
+                    for (index, parameter) in parameters.iter().enumerate() {
+                        if parameter.name.trim().is_empty() || parameter.r#type.trim().is_empty() {
+                            problem(
+                                Some(id),
+                                format!("{kind} {index} must have a name and a type"),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        for permission in &function.permissions {
+            match PermissionType::try_from(permission.permission_type) {
+                Ok(PermissionType::Unspecified) | Err(_) => problem(
+                    Some(id),
+                    format!(
+                        "declares a permission of unknown type {}",
+                        permission.permission_type
+                    ),
+                ),
+                Ok(_) => {}
+            }
+            if PermissionLevel::try_from(permission.permission_level).is_err() {
+                problem(
+                    Some(id),
+                    format!(
+                        "declares a permission of unknown level {}",
+                        permission.permission_level
+                    ),
+                );
+            }
+        }
+    }
+    problems
+}
+
+/// Function IDs of packages that passed validation, for checking packages loaded later.
+pub(crate) fn function_ids(packages: &[PluginPackage]) -> HashSet<String> {
+    packages
+        .iter()
+        .flat_map(|package| package.functions.iter().map(|f| f.function_id.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::proto::sapphillon::v1::{
+        FunctionDefine, FunctionParameter, Permission, PluginFunction,
+    };
+
+    fn function(function_id: &str) -> PluginFunction {
+        PluginFunction {
+            function_id: function_id.to_string(),
+            function_name: "Fn".to_string(),
+            version: "".to_string(),
+            description: "".to_string(),
+            permissions: vec![Permission {
+                display_name: "".to_string(),
+                description: "".to_string(),
+                permission_type: PermissionType::NetAccess as i32,
+                permission_level: PermissionLevel::Unspecified as i32,
+                resource: vec![],
+            }],
+            function_define: Some(FunctionDefine {
+                parameters: vec![FunctionParameter {
+                    name: "url".to_string(),
+                    r#type: "string".to_string(),
+                    description: "".to_string(),
+                }],
+                returns: vec![],
+            }),
+        }
+    }
+
+    fn package(package_id: &str, functions: Vec<PluginFunction>) -> PluginPackage {
+        PluginPackage {
+            package_id: package_id.to_string(),
+            package_name: "Pkg".to_string(),
+            provider_id: "".to_string(),
+            package_version: "1.0.0".to_string(),
+            description: "".to_string(),
+            functions,
+            plugin_store_url: "".to_string(),
+            internal_plugin: Some(false),
+            verified: Some(false),
+            deprecated: Some(false),
+            installed_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn builtin_plugins_are_valid() {
+        let (_, diagnostics) = validate_plugin_packages(
+            crate::sysconfig::sysconfig().initial_plugins,
+            &HashSet::new(),
+        );
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn invalid_packages_are_rejected_with_diagnostics() {
+        let mut untyped = function("pkg.b.untyped");
+        untyped.function_define.as_mut().unwrap().parameters[0].r#type = String::new();
+        let mut undefined = function("pkg.b.undefined");
+        undefined.function_define = None;
+        let mut unknown_permission = function("pkg.b.permission");
+        unknown_permission.permissions[0].permission_type = PermissionType::Unspecified as i32;
+
+        let (valid, diagnostics) = validate_plugin_packages(
+            vec![
+                package("pkg.a", vec![function("pkg.a.fetch")]),
+                package(
+                    "pkg.b",
+                    vec![
+                        untyped,
+                        undefined,
+                        unknown_permission,
+                        function("pkg.a.fetch"),
+                    ],
+                ),
+                package("pkg.c", vec![function("pkg.c.x"), function("pkg.c.x")]),
+            ],
+            &HashSet::new(),
+        );
+
+        assert_eq!(valid.len(), 1);
+        assert_eq!(valid[0].package_id, "pkg.a");
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "pkg.b.untyped: parameter 0 must have a name and a type",
+                "pkg.b.undefined: has no FunctionDefine",
+                "pkg.b.permission: declares a permission of unknown type 0",
+                "pkg.a.fetch: function_id must be 'pkg.b.<name>'",
+                "pkg.a.fetch: function_id is declared more than once",
+                "pkg.c.x: function_id is declared more than once",
+            ]
+        );
+    }
+
+    #[test]
+    fn known_function_ids_cannot_be_taken_again() {
+        let known = HashSet::from(["pkg.a.fetch".to_string()]);
+        let (valid, diagnostics) = validate_plugin_packages(
+            vec![package("pkg.a", vec![function("pkg.a.fetch")])],
+            &known,
+        );
+        assert!(valid.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+    }
+}
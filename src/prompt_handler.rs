@@ -0,0 +1,357 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Database-backed handler for the `prompt` plugin
+
+use crate::db_worker::spawn_db_worker;
+use anyhow::Context;
+use database::prompt::{PROMPT_STATUS_ANSWERED, PROMPT_STATUS_PENDING};
+use prompt::{PromptHandler, PromptKind};
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Kind of the prompts that ask for a missing permission, answered like confirmations.
+pub(crate) const PERMISSION_PROMPT_KIND: &str = "permission";
+
+/// How often a waiting workflow checks whether its prompt was answered.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a prompt stands when it is polled.
+enum PromptState {
+    Pending,
+    Answered(Value),
+    Closed,
+}
+
+enum Request {
+    Create {
+        workflow_id: String,
+        kind: &'static str,
+        message: String,
+        reply: mpsc::Sender<anyhow::Result<String>>,
+    },
+    Poll {
+        prompt_id: String,
+        reply: mpsc::Sender<anyhow::Result<PromptState>>,
+    },
+    Expire {
+        prompt_id: String,
+        reply: mpsc::Sender<anyhow::Result<bool>>,
+    },
+}
+
+/// Stores prompts in the `workflow_prompt` table and waits until they are answered,
+/// e.g. with `prompts answer` or the `AnswerPrompt` RPC.
+///
+/// The workflow keeps its worker-pool slot while it waits. `prompts answer` runs in
+/// another process, so prompts fail at once when the database is in memory.
+pub(crate) struct DbPromptHandler {
+    requests: Mutex<mpsc::Sender<Request>>,
+    answerable: bool,
+}
+
+impl DbPromptHandler {
+    pub(crate) fn connect(db_url: String) -> anyhow::Result<Self> {
+        let answerable = !crate::db_pool::is_in_memory(&db_url);
+        let requests = spawn_db_worker("workflow-prompt", db_url, serve_request)?;
+        Ok(Self {
+            requests: Mutex::new(requests),
+            answerable,
+        })
+    }
+
+    /// Whether another process can see the prompts to answer them.
+    pub(crate) fn is_answerable(&self) -> bool {
+        self.answerable
+    }
+
+    fn call<T>(&self, request: impl FnOnce(mpsc::Sender<T>) -> Request) -> anyhow::Result<T> {
+        let (reply, response) = mpsc::channel();
+        self.requests
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .send(request(reply))
+            .context("workflow prompt thread has stopped")?;
+        response
+            .recv()
+            .context("workflow prompt thread has stopped")
+    }
+}
+
+fn serve_request(runtime: &Runtime, db: &DatabaseConnection, request: Request) {
+    match request {
+        Request::Create {
+            workflow_id,
+            kind,
+            message,
+            reply,
+        } => {
+            let result = runtime
+                .block_on(database::prompt::create_prompt(
+                    db,
+                    &workflow_id,
+                    kind,
+                    &message,
+                ))
+                .map(|prompt| prompt.id)
+                .map_err(anyhow::Error::from);
+            let _ = reply.send(result);
+        }
+        Request::Poll { prompt_id, reply } => {
+            let result = runtime
+                .block_on(database::prompt::get_prompt(db, &prompt_id))
+                .map_err(anyhow::Error::from)
+                .and_then(|prompt| {
+                    let prompt =
+                        prompt.with_context(|| format!("prompt {prompt_id} was deleted"))?;
+                    Ok(match (prompt.status.as_str(), prompt.answer) {
+                        (PROMPT_STATUS_PENDING, _) => PromptState::Pending,
+                        (PROMPT_STATUS_ANSWERED, Some(answer)) => {
+                            PromptState::Answered(serde_json::from_str(&answer)?)
+                        }
+                        _ => PromptState::Closed,
+                    })
+                });
+            let _ = reply.send(result);
+        }
+        Request::Expire { prompt_id, reply } => {
+            let result = runtime
+                .block_on(database::prompt::expire_prompt(db, &prompt_id))
+                .map(|updated| updated > 0)
+                .map_err(anyhow::Error::from);
+            let _ = reply.send(result);
+        }
+    }
+}
+
+impl DbPromptHandler {
+    /// Stores a pending prompt for a workflow run.
+    ///
+    /// # Returns
+    ///
+    /// Returns the ID of the prompt, or an error when nobody could answer it.
+    pub(crate) fn create(
+        &self,
+        workflow_id: &str,
+        kind: &'static str,
+        message: &str,
+    ) -> anyhow::Result<String> {
+        if workflow_id.is_empty() {
+            anyhow::bail!("prompts are only available inside a stored workflow run");
+        }
+        if !self.answerable {
+            anyhow::bail!(
+                "prompts cannot be answered while the database is in memory; start the server \
+                 with a database file, e.g. --db-url sqlite://sapphillon.db"
+            );
+        }
+        let prompt_id = self.call(|reply| Request::Create {
+            workflow_id: workflow_id.to_string(),
+            kind,
+            message: message.to_string(),
+            reply,
+        })??;
+        info!("Workflow {workflow_id} is waiting for an answer to prompt {prompt_id}: {message}");
+        Ok(prompt_id)
+    }
+
+    /// Waits until a prompt from [`Self::create`] is answered, expiring it after
+    /// `timeout`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the answer, or `None` when the prompt expired or was closed.
+    pub(crate) fn wait(&self, prompt_id: &str, timeout: Duration) -> anyhow::Result<Option<Value>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let state = self.call(|reply| Request::Poll {
+                prompt_id: prompt_id.to_string(),
+                reply,
+            })??;
+            match state {
+                PromptState::Answered(answer) => return Ok(Some(answer)),
+                PromptState::Closed => return Ok(None),
+                PromptState::Pending => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                let expired = self.call(|reply| Request::Expire {
+                    prompt_id: prompt_id.to_string(),
+                    reply,
+                })??;
+                if expired {
+                    info!("Prompt {prompt_id} expired without an answer");
+                    return Ok(None);
+                }
+                // Answered between the last poll and the expiry; pick the answer up.
+                continue;
+            }
+            std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+}
+
+impl PromptHandler for DbPromptHandler {
+    fn ask(
+        &self,
+        workflow_id: &str,
+        kind: PromptKind,
+        message: &str,
+        timeout: Duration,
+    ) -> anyhow::Result<Option<Value>> {
+        let prompt_id = self.create(workflow_id, kind.as_str(), message)?;
+        self.wait(&prompt_id, timeout)
+    }
+}
+
+/// Answers a pending prompt with an answer typed on the command line.
+///
+/// # Returns
+///
+/// Returns an error when the prompt does not exist, is no longer pending, or the answer
+/// does not fit its kind.
+pub(crate) async fn answer_prompt(
+    db: &DatabaseConnection,
+    prompt_id: &str,
+    answer: &str,
+) -> anyhow::Result<()> {
+    let prompt = database::prompt::get_prompt(db, prompt_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("prompt not found: {prompt_id}"))?;
+    let answer = parse_answer(&prompt.kind, answer)?;
+    if database::prompt::answer_prompt(db, prompt_id, &answer.to_string()).await? == 0 {
+        anyhow::bail!("prompt is no longer pending: {prompt_id}");
+    }
+    Ok(())
+}
+
+/// Converts an answer typed on the command line into the JSON stored for `kind`.
+///
+/// Confirmations and permission requests accept `yes`/`no` (or `y`, `n`, `true`,
+/// `false`); inputs are stored as the given text.
+pub(crate) fn parse_answer(kind: &str, answer: &str) -> anyhow::Result<Value> {
+    let parsed = match kind {
+        PERMISSION_PROMPT_KIND => Some(PromptKind::Confirm),
+        kind => PromptKind::parse(kind),
+    };
+    match parsed {
+        Some(PromptKind::Confirm) => match answer.trim().to_lowercase().as_str() {
+            "yes" | "y" | "true" => Ok(Value::Bool(true)),
+            "no" | "n" | "false" => Ok(Value::Bool(false)),
+            other => anyhow::bail!("expected yes or no, got {other:?}"),
+        },
+        Some(PromptKind::Input) => Ok(Value::String(answer.to_string())),
+        None => anyhow::bail!("unknown prompt kind: {kind}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+    #[test]
+    fn prompts_are_answered_from_another_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_url = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("prompts.sqlite").display()
+        );
+        let runtime = Runtime::new().unwrap();
+        // The connection `prompts answer` would open in its own process.
+        let answerer = runtime.block_on(async {
+            let db = crate::db_pool::connect(&db_url).await.unwrap();
+            let sql = r#"
+                CREATE TABLE workflow_prompt (
+                    id TEXT NOT NULL PRIMARY KEY,
+                    workflow_id TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    message TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    answer TEXT,
+                    created_at TEXT,
+                    answered_at TEXT
+                )
+            "#;
+            db.execute(Statement::from_string(DbBackend::Sqlite, sql.to_string()))
+                .await
+                .unwrap();
+            db
+        });
+
+        let handler = DbPromptHandler::connect(db_url).unwrap();
+        assert!(handler.is_answerable());
+        let waiting = std::thread::spawn(move || {
+            handler.ask(
+                "wf-1",
+                PromptKind::Confirm,
+                "Delete 200 files?",
+                Duration::from_secs(30),
+            )
+        });
+
+        let prompt = loop {
+            let pending = runtime
+                .block_on(database::prompt::list_prompts(&answerer, true))
+                .unwrap();
+            if let Some(prompt) = pending.into_iter().next() {
+                break prompt;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(prompt.message, "Delete 200 files?");
+        runtime
+            .block_on(answer_prompt(&answerer, &prompt.id, "yes"))
+            .unwrap();
+        assert!(
+            runtime
+                .block_on(answer_prompt(&answerer, &prompt.id, "no"))
+                .is_err()
+        );
+
+        let answer = waiting.join().unwrap().unwrap();
+        assert_eq!(answer, Some(Value::Bool(true)));
+    }
+
+    #[test]
+    fn prompts_fail_at_once_with_an_in_memory_database() {
+        let handler =
+            DbPromptHandler::connect("sqlite:file:prompt-test?mode=memory&cache=shared".into())
+                .unwrap();
+        assert!(!handler.is_answerable());
+        let err = handler
+            .ask(
+                "wf-1",
+                PromptKind::Confirm,
+                "Delete 200 files?",
+                Duration::from_secs(600),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("in memory"), "{err}");
+    }
+
+    #[test]
+    fn parse_answer_matches_prompt_kind() {
+        assert_eq!(parse_answer("confirm", "Yes").unwrap(), Value::Bool(true));
+        assert_eq!(parse_answer("confirm", "n").unwrap(), Value::Bool(false));
+        assert!(parse_answer("confirm", "maybe").is_err());
+        assert_eq!(
+            parse_answer("input", " reports ").unwrap(),
+            Value::String(" reports ".to_string())
+        );
+        assert_eq!(
+            parse_answer(PERMISSION_PROMPT_KIND, "no").unwrap(),
+            Value::Bool(false)
+        );
+        assert!(parse_answer("choice", "a").is_err());
+    }
+}
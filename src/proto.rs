@@ -0,0 +1,37 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// gRPC services of this server, generated from `proto/` by build.rs
+
+pub mod sapphillon {
+    pub mod server {
+        pub mod v1 {
+            tonic::include_proto!("sapphillon.server.v1");
+
+            /// Encoded descriptors of the services, for gRPC reflection.
+            pub const FILE_DESCRIPTOR_SET: &[u8] =
+                tonic::include_file_descriptor_set!("sapphillon_server_descriptor");
+        }
+    }
+}
+
+/// Converts a time stored in the database to a protobuf timestamp.
+pub(crate) fn timestamp(at: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Converts a permission of a plugin function, adding its description in words.
+pub(crate) fn permission(
+    permission: sapphillon_core::proto::sapphillon::v1::Permission,
+) -> sapphillon::server::v1::Permission {
+    sapphillon::server::v1::Permission {
+        description: plugin_permission::describe_permission(&permission),
+        permission_type: permission.permission_type,
+        permission_level: permission.permission_level,
+        resource: permission.resource,
+    }
+}
@@ -0,0 +1,467 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Per-client rate limits and concurrency caps for the expensive gRPC methods
+
+use crate::auth::{ANONYMOUS_SUBJECT, AuthIdentity};
+use axum::body::Bytes;
+use axum::http::{Request, Response};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::Status;
+use tonic::body::Body;
+use tonic::metadata::MetadataValue;
+use tower::{Layer, Service};
+
+/// GenerateWorkflow and FixWorkflow calls per minute a client may make when not configured.
+pub(crate) const DEFAULT_GENERATE_PER_MINUTE: u32 = 30;
+/// GenerateWorkflow and FixWorkflow calls a client may have in progress when not configured.
+pub(crate) const DEFAULT_GENERATE_MAX_CONCURRENT: usize = 2;
+/// RunWorkflow calls per minute a client may make when not configured.
+pub(crate) const DEFAULT_RUN_PER_MINUTE: u32 = 120;
+/// RunWorkflow calls a client may have in progress when not configured.
+pub(crate) const DEFAULT_RUN_MAX_CONCURRENT: usize = 8;
+
+/// Metadata key telling a rejected client how many seconds to wait before retrying.
+pub(crate) const RETRY_AFTER_METADATA_KEY: &str = "retry-after";
+
+/// Limits applied to each client of a group of methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Quota {
+    /// Calls per minute; `0` disables the limit.
+    pub per_minute: u32,
+    /// Calls in progress at the same time; `0` disables the cap.
+    pub max_concurrent: usize,
+}
+
+/// Quotas of the expensive methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Quotas {
+    /// GenerateWorkflow and FixWorkflow, which call a language model.
+    pub generate: Quota,
+    /// RunWorkflow.
+    pub run: Quota,
+}
+
+impl Default for Quotas {
+    fn default() -> Self {
+        Self {
+            generate: Quota {
+                per_minute: DEFAULT_GENERATE_PER_MINUTE,
+                max_concurrent: DEFAULT_GENERATE_MAX_CONCURRENT,
+            },
+            run: Quota {
+                per_minute: DEFAULT_RUN_PER_MINUTE,
+                max_concurrent: DEFAULT_RUN_MAX_CONCURRENT,
+            },
+        }
+    }
+}
+
+static QUOTAS: OnceLock<Quotas> = OnceLock::new();
+
+/// Sets the quotas enforced by [`RateLimitLayer`]. Only the first call takes effect.
+///
+/// # Returns
+///
+/// Returns `true` if the quotas were applied, `false` if they were already configured.
+pub(crate) fn set_quotas(quotas: Quotas) -> bool {
+    QUOTAS.set(quotas).is_ok()
+}
+
+/// The group of limited methods a gRPC path belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Group {
+    Generate,
+    Run,
+}
+
+impl Group {
+    fn for_path(path: &str) -> Option<Self> {
+        match path {
+            "/sapphillon.v1.WorkflowService/GenerateWorkflow"
+            | "/sapphillon.v1.WorkflowService/FixWorkflow"
+            | "/sapphillon.server.v1.WorkflowManagementService/DiagnoseWorkflowResult"
+            | "/sapphillon.server.v1.WorkflowManagementService/ExplainWorkflow" => {
+                Some(Group::Generate)
+            }
+            "/sapphillon.v1.WorkflowService/RunWorkflow"
+            | "/sapphillon.server.v1.WorkflowManagementService/RunWorkflowStream" => {
+                Some(Group::Run)
+            }
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Group::Generate => "workflow generation",
+            Group::Run => "workflow runs",
+        }
+    }
+}
+
+/// Calls a client may still make, refilled continuously up to the per-minute limit.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct ClientState {
+    bucket: Option<Bucket>,
+    in_progress: usize,
+}
+
+/// Tracks the calls of every client. Clients are told apart by the subject they
+/// authenticated as, so each API token has its own quota.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    quotas: Quotas,
+    clients: Mutex<HashMap<(Group, String), ClientState>>,
+}
+
+/// Holds a client's slot of the concurrency cap until dropped.
+#[derive(Debug)]
+pub(crate) struct CallGuard {
+    limiter: Arc<RateLimiter>,
+    key: (Group, String),
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        let mut clients = self
+            .limiter
+            .clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(state) = clients.get_mut(&self.key) {
+            state.in_progress = state.in_progress.saturating_sub(1);
+        }
+    }
+}
+
+impl RateLimiter {
+    pub(crate) fn new(quotas: Quotas) -> Arc<Self> {
+        Arc::new(Self {
+            quotas,
+            clients: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn quota(&self, group: Group) -> Quota {
+        match group {
+            Group::Generate => self.quotas.generate,
+            Group::Run => self.quotas.run,
+        }
+    }
+
+    /// Admits a call of `client` to `group` at `now`, or returns the status rejecting it.
+    fn admit(
+        self: &Arc<Self>,
+        group: Group,
+        client: &str,
+        now: Instant,
+    ) -> Result<CallGuard, Status> {
+        let quota = self.quota(group);
+        let key = (group, client.to_string());
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let state = clients.entry(key.clone()).or_default();
+
+        if quota.max_concurrent > 0 && state.in_progress >= quota.max_concurrent {
+            return Err(exhausted(
+                format!(
+                    "at most {} calls for {} may be in progress at a time",
+                    quota.max_concurrent,
+                    group.name()
+                ),
+                1,
+            ));
+        }
+        if quota.per_minute > 0 {
+            let capacity = f64::from(quota.per_minute);
+            let per_second = capacity / 60.0;
+            let bucket = state.bucket.get_or_insert(Bucket {
+                tokens: capacity,
+                refilled_at: now,
+            });
+            let elapsed = now.saturating_duration_since(bucket.refilled_at);
+            bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * per_second).min(capacity);
+            bucket.refilled_at = now;
+            if bucket.tokens < 1.0 {
+                let wait = ((1.0 - bucket.tokens) / per_second).ceil() as u64;
+                return Err(exhausted(
+                    format!(
+                        "at most {} calls per minute are allowed for {}",
+                        quota.per_minute,
+                        group.name()
+                    ),
+                    wait.max(1),
+                ));
+            }
+            bucket.tokens -= 1.0;
+        }
+        state.in_progress += 1;
+        Ok(CallGuard {
+            limiter: self.clone(),
+            key,
+        })
+    }
+
+    /// Forgets clients without calls in progress whose quota has fully refilled.
+    fn prune(&self, now: Instant) {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.retain(|(group, _), state| {
+            let refilled = state.bucket.as_ref().is_none_or(|bucket| {
+                now.saturating_duration_since(bucket.refilled_at) >= Duration::from_secs(60)
+            });
+            let quota = self.quota(*group);
+            state.in_progress > 0 || (quota.per_minute > 0 && !refilled)
+        });
+    }
+}
+
+fn exhausted(message: String, retry_after_secs: u64) -> Status {
+    let mut status = Status::resource_exhausted(message);
+    status.metadata_mut().insert(
+        RETRY_AFTER_METADATA_KEY,
+        MetadataValue::from(retry_after_secs),
+    );
+    status
+}
+
+/// Tower layer enforcing the configured [`Quotas`].
+///
+/// It must run after the authentication interceptor, which attaches the
+/// [`AuthIdentity`] that tells clients apart.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    /// Creates a layer enforcing the quotas set with [`set_quotas`].
+    pub(crate) fn new() -> Self {
+        Self::with_quotas(QUOTAS.get().copied().unwrap_or_default())
+    }
+
+    pub(crate) fn with_quotas(quotas: Quotas) -> Self {
+        Self {
+            limiter: RateLimiter::new(quotas),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+            calls: 0,
+        }
+    }
+}
+
+/// Number of admitted calls between two prunes of idle clients.
+const PRUNE_INTERVAL_CALLS: u32 = 1024;
+
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+    calls: u32,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RateLimitService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // Call the service that was polled ready and keep a fresh clone for the next call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let Some(group) = Group::for_path(request.uri().path()) else {
+            let future = inner.call(request);
+            return Box::pin(async move { Ok(future.await?.map(Body::new)) });
+        };
+        let client = request
+            .extensions()
+            .get::<AuthIdentity>()
+            .map_or(ANONYMOUS_SUBJECT, |identity| identity.subject.as_str())
+            .to_string();
+        let now = Instant::now();
+        self.calls = self.calls.wrapping_add(1);
+        if self.calls % PRUNE_INTERVAL_CALLS == 0 {
+            self.limiter.prune(now);
+        }
+        match self.limiter.admit(group, &client, now) {
+            Ok(guard) => {
+                let future = inner.call(request);
+                Box::pin(async move {
+                    // Streamed responses keep working after the call returns, so the slot
+                    // is held until the response body is dropped.
+                    let response = future.await?;
+                    Ok(response.map(|body| {
+                        Body::new(GuardedBody {
+                            body: Body::new(body),
+                            _guard: guard,
+                        })
+                    }))
+                })
+            }
+            Err(status) => {
+                log::info!("Rate limited {client}: {}", status.message());
+                Box::pin(async move { Ok(status.into_http()) })
+            }
+        }
+    }
+}
+
+/// A response body holding a [`CallGuard`].
+struct GuardedBody {
+    body: Body,
+    _guard: CallGuard,
+}
+
+impl HttpBody for GuardedBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.body).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_minute: u32, max_concurrent: usize) -> Arc<RateLimiter> {
+        let quota = Quota {
+            per_minute,
+            max_concurrent,
+        };
+        RateLimiter::new(Quotas {
+            generate: quota,
+            run: quota,
+        })
+    }
+
+    fn retry_after(status: &Status) -> u64 {
+        status
+            .metadata()
+            .get(RETRY_AFTER_METADATA_KEY)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn only_expensive_methods_are_limited() {
+        assert_eq!(
+            Group::for_path("/sapphillon.v1.WorkflowService/GenerateWorkflow"),
+            Some(Group::Generate)
+        );
+        assert_eq!(
+            Group::for_path("/sapphillon.v1.WorkflowService/RunWorkflow"),
+            Some(Group::Run)
+        );
+        assert_eq!(
+            Group::for_path("/sapphillon.server.v1.WorkflowManagementService/ExplainWorkflow"),
+            Some(Group::Generate)
+        );
+        assert_eq!(
+            Group::for_path("/sapphillon.server.v1.WorkflowManagementService/RunWorkflowStream"),
+            Some(Group::Run)
+        );
+        assert_eq!(
+            Group::for_path("/sapphillon.v1.WorkflowService/ListWorkflows"),
+            None
+        );
+        assert_eq!(
+            Group::for_path("/sapphillon.server.v1.WorkflowManagementService/ListWorkflowResults"),
+            None
+        );
+    }
+
+    #[test]
+    fn calls_beyond_the_rate_are_rejected_until_refilled() {
+        let limiter = limiter(2, 0);
+        let start = Instant::now();
+        drop(limiter.admit(Group::Run, "alice", start).unwrap());
+        drop(limiter.admit(Group::Run, "alice", start).unwrap());
+
+        let status = limiter.admit(Group::Run, "alice", start).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(retry_after(&status), 30);
+        // Other clients and other methods have their own quotas.
+        assert!(limiter.admit(Group::Run, "bob", start).is_ok());
+        assert!(limiter.admit(Group::Generate, "alice", start).is_ok());
+
+        let later = start + Duration::from_secs(30);
+        assert!(limiter.admit(Group::Run, "alice", later).is_ok());
+    }
+
+    #[test]
+    fn concurrency_is_capped_until_calls_finish() {
+        let limiter = limiter(0, 1);
+        let now = Instant::now();
+        let guard = limiter.admit(Group::Generate, "alice", now).unwrap();
+        let status = limiter.admit(Group::Generate, "alice", now).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(retry_after(&status), 1);
+
+        drop(guard);
+        assert!(limiter.admit(Group::Generate, "alice", now).is_ok());
+    }
+
+    #[test]
+    fn idle_clients_are_pruned() {
+        let limiter = limiter(10, 1);
+        let start = Instant::now();
+        let guard = limiter.admit(Group::Run, "alice", start).unwrap();
+        drop(limiter.admit(Group::Run, "bob", start).unwrap());
+
+        limiter.prune(start + Duration::from_secs(60));
+        let clients = limiter.clients.lock().unwrap();
+        assert_eq!(clients.len(), 1);
+        assert!(clients.contains_key(&(Group::Run, "alice".to_string())));
+        drop(clients);
+        drop(guard);
+    }
+}
@@ -0,0 +1,194 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Retention policy for stored workflow results and the background task applying it
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use database::workflow::delete_workflow_results_by_ids;
+use database::workflow::workflow_result_crud::{WorkflowResultSize, list_workflow_result_sizes};
+use sea_orm::DatabaseConnection;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// How often the background task prunes results.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Limits on the workflow results kept in the database; `None` disables a limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResultRetention {
+    /// Newest results kept per workflow.
+    pub max_results_per_workflow: Option<usize>,
+    /// Results that ran longer ago than this are removed.
+    pub max_age: Option<chrono::Duration>,
+    /// Total output size kept across all workflows; the oldest results go first.
+    pub max_total_bytes: Option<u64>,
+}
+
+impl ResultRetention {
+    fn is_unlimited(&self) -> bool {
+        self.max_results_per_workflow.is_none()
+            && self.max_age.is_none()
+            && self.max_total_bytes.is_none()
+    }
+}
+
+/// Prunes results now and then every [`PRUNE_INTERVAL`], unless the policy sets no limits.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to read and delete results.
+/// * `policy` - The limits to enforce.
+pub async fn start_result_pruner(db: DatabaseConnection, policy: ResultRetention) {
+    if policy.is_unlimited() {
+        debug!("No result retention limits set; results are kept forever");
+        return;
+    }
+    info!(
+        "Result pruning started (interval: {}m)",
+        PRUNE_INTERVAL.as_secs() / 60
+    );
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match prune_results(&db, &policy).await {
+            Ok(0) => {}
+            Ok(deleted) => info!("Pruned {deleted} workflow result(s)"),
+            Err(err) => error!("Result pruning failed: {err:#}"),
+        }
+    }
+}
+
+/// Deletes the results the policy does not keep.
+///
+/// # Arguments
+///
+/// * `db` - Database connection.
+/// * `policy` - The limits to enforce.
+///
+/// # Returns
+///
+/// Returns the number of deleted results.
+pub async fn prune_results(db: &DatabaseConnection, policy: &ResultRetention) -> Result<u64> {
+    if policy.is_unlimited() {
+        return Ok(0);
+    }
+    let results = list_workflow_result_sizes(db).await?;
+    let expired = results_to_prune(policy, results, Utc::now());
+    Ok(delete_workflow_results_by_ids(db, &expired).await?)
+}
+
+/// Picks the results the policy does not keep.
+///
+/// The age limit applies first, then the per-workflow count and last the total size,
+/// each keeping the newest results. Once a result exceeds the size limit, every older
+/// result is removed as well. Results without a `ran_at` timestamp are never
+/// removed for their age but count as the oldest for the other limits.
+fn results_to_prune(
+    policy: &ResultRetention,
+    mut results: Vec<WorkflowResultSize>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    // Newest first; `None` sorts below every timestamp, so undated results come last.
+    results.sort_by(|a, b| b.ran_at.cmp(&a.ran_at).then_with(|| a.id.cmp(&b.id)));
+
+    let cutoff = policy.max_age.map(|age| now - age);
+    let mut kept_per_workflow: HashMap<&str, usize> = HashMap::new();
+    let mut kept_bytes: u64 = 0;
+    let mut size_exceeded = false;
+    let mut expired = Vec::new();
+    for result in &results {
+        let too_old =
+            matches!((cutoff, result.ran_at), (Some(cutoff), Some(ran_at)) if ran_at < cutoff);
+        let kept = kept_per_workflow.entry(&result.workflow_id).or_default();
+        let too_many = policy
+            .max_results_per_workflow
+            .is_some_and(|max| *kept >= max);
+        // Once a result does not fit, every older result goes too.
+        size_exceeded = size_exceeded
+            || policy
+                .max_total_bytes
+                .is_some_and(|max| kept_bytes.saturating_add(result.size) > max);
+
+        if too_old || too_many || size_exceeded {
+            expired.push(result.id.clone());
+        } else {
+            *kept += 1;
+            kept_bytes += result.size;
+        }
+    }
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn result(id: &str, workflow_id: &str, days_ago: Option<i64>, size: u64) -> WorkflowResultSize {
+        WorkflowResultSize {
+            id: id.to_string(),
+            workflow_id: workflow_id.to_string(),
+            ran_at: days_ago.map(|days| now() - chrono::Duration::days(days)),
+            size,
+        }
+    }
+
+    fn prune(policy: ResultRetention, results: Vec<WorkflowResultSize>) -> Vec<String> {
+        let mut expired = results_to_prune(&policy, results, now());
+        expired.sort();
+        expired
+    }
+
+    #[test]
+    fn unlimited_policy_keeps_everything() {
+        let results = vec![
+            result("a", "wf1", Some(400), 10),
+            result("b", "wf1", None, 10),
+        ];
+        assert!(prune(ResultRetention::default(), results).is_empty());
+    }
+
+    #[test]
+    fn oldest_results_beyond_the_per_workflow_count_are_pruned() {
+        let policy = ResultRetention {
+            max_results_per_workflow: Some(2),
+            ..ResultRetention::default()
+        };
+        let results = vec![
+            result("undated", "wf1", None, 1),
+            result("old", "wf1", Some(3), 1),
+            result("mid", "wf1", Some(2), 1),
+            result("new", "wf1", Some(1), 1),
+            result("other", "wf2", Some(9), 1),
+        ];
+        assert_eq!(prune(policy, results), vec!["old", "undated"]);
+    }
+
+    #[test]
+    fn age_and_size_limits_keep_the_newest_results() {
+        let policy = ResultRetention {
+            max_age: Some(chrono::Duration::days(30)),
+            max_total_bytes: Some(100),
+            ..ResultRetention::default()
+        };
+        let results = vec![
+            result("expired", "wf1", Some(31), 1),
+            result("undated", "wf1", None, 1),
+            result("screenshot", "wf2", Some(2), 80),
+            result("log", "wf1", Some(1), 15),
+            result("report", "wf1", Some(3), 10),
+        ];
+        assert_eq!(prune(policy, results), vec!["expired", "report", "undated"]);
+    }
+}
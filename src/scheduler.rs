@@ -0,0 +1,192 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Scheduler that runs workflows automatically from persisted cron triggers
+
+use crate::events::{self, Event};
+use crate::services::{MyWorkflowService, WorkflowRunEvent};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use croner::Cron;
+use database::schedule::{list_due_schedules, mark_schedule_run};
+use database::workflow::get_workflow_owner;
+use entity::entity::workflow_schedule::Model as WorkflowSchedule;
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// How often the scheduler looks for due schedules.
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Runs the scheduler loop forever, executing every due schedule on each tick.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to read schedules and persist results.
+pub async fn start_scheduler(db: DatabaseConnection) {
+    info!(
+        "Workflow scheduler started (tick interval: {}s)",
+        TICK_INTERVAL.as_secs()
+    );
+    let service = MyWorkflowService::new(db.clone());
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = run_due_schedules(&db, &service, Utc::now()).await {
+            error!("Scheduler tick failed: {err:#}");
+        }
+    }
+}
+
+/// Validates a cron expression and stores a schedule for an existing workflow.
+///
+/// # Arguments
+///
+/// * `db` - Database connection.
+/// * `workflow_id` - Workflow to run on the schedule.
+/// * `cron` - Standard five-field cron expression (an optional leading seconds field is accepted).
+///
+/// # Returns
+///
+/// Returns the stored schedule, or an error when the workflow does not exist or the expression is invalid.
+pub async fn create_schedule(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    cron: &str,
+) -> Result<WorkflowSchedule> {
+    // The schedule belongs to whoever owns the workflow.
+    let owner_id = get_workflow_owner(db, workflow_id)
+        .await
+        .with_context(|| format!("workflow not found: {workflow_id}"))?;
+    let next_run_at = next_run_after(cron, Utc::now())?;
+    Ok(
+        database::schedule::create_schedule(
+            db,
+            workflow_id,
+            owner_id.as_deref(),
+            cron,
+            next_run_at,
+        )
+        .await?,
+    )
+}
+
+/// Executes every schedule that is due at `now` and advances it to its next occurrence.
+///
+/// A schedule is advanced before its workflow runs, so a failing workflow is retried at
+/// the next occurrence rather than on every tick.
+async fn run_due_schedules(
+    db: &DatabaseConnection,
+    service: &MyWorkflowService,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let due = list_due_schedules(db, now).await?;
+    for schedule in &due {
+        let next_run_at = match next_run_after(&schedule.cron, now) {
+            Ok(next) => Some(next),
+            Err(err) => {
+                warn!(
+                    "Disabling schedule {} with invalid cron {:?}: {err:#}",
+                    schedule.id, schedule.cron
+                );
+                None
+            }
+        };
+        mark_schedule_run(db, &schedule.id, now, next_run_at).await?;
+        events::publish(
+            Event::ScheduleFired {
+                schedule_id: schedule.id.clone(),
+                workflow_id: schedule.workflow_id.clone(),
+            },
+            schedule.owner_id.clone(),
+        );
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _ = service
+            .execute_workflow_with_events(&schedule.workflow_id, None, None, &tx)
+            .await;
+        drop(tx);
+        while let Some(event) = rx.recv().await {
+            log_run_event(&schedule.id, event);
+        }
+    }
+    Ok(due.len())
+}
+
+fn log_run_event(schedule_id: &str, event: WorkflowRunEvent) {
+    match event {
+        WorkflowRunEvent::Started {
+            workflow_id,
+            workflow_code_id,
+        } => info!(
+            "Running scheduled workflow: schedule_id={schedule_id}, workflow_id={workflow_id}, workflow_code_id={}",
+            workflow_code_id.as_deref().unwrap_or("latest")
+        ),
+        WorkflowRunEvent::Console { line } => info!("[schedule {schedule_id}] {line}"),
+        WorkflowRunEvent::Step { step } => {
+            info!(
+                "[schedule {schedule_id}] step {:?} {} in {}ms",
+                step.name, step.status, step.duration_ms
+            );
+            debug!(
+                "[schedule {schedule_id}] step {:?} output:\n{}",
+                step.name, step.output
+            );
+        }
+        WorkflowRunEvent::Finished { result } => info!(
+            "Scheduled workflow finished: schedule_id={schedule_id}, result_revision={}",
+            result.workflow_result_revision
+        ),
+        WorkflowRunEvent::Planned { action } => {
+            debug!("[schedule {schedule_id}] planned call {}", action.function)
+        }
+        WorkflowRunEvent::Failed { status } => error!(
+            "Scheduled workflow failed: schedule_id={schedule_id}, error={}",
+            status.message()
+        ),
+    }
+}
+
+/// Returns the first occurrence of `cron` strictly after `after`.
+pub(crate) fn next_run_after(cron: &str, after: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let parsed = Cron::new(cron)
+        .with_seconds_optional()
+        .parse()
+        .with_context(|| format!("invalid cron expression: {cron:?}"))?;
+    parsed
+        .find_next_occurrence(&after, false)
+        .with_context(|| format!("cron expression never fires: {cron:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn next_run_after_uses_five_field_cron() {
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 10, 7, 30).unwrap();
+        let next = next_run_after("*/15 * * * *", after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 10, 15, 0).unwrap());
+
+        let daily = next_run_after("0 9 * * *", after).unwrap();
+        assert_eq!(daily, Utc.with_ymd_and_hms(2025, 1, 2, 9, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn next_run_after_is_strictly_later() {
+        let at = Utc.with_ymd_and_hms(2025, 1, 1, 10, 15, 0).unwrap();
+        let next = next_run_after("*/15 * * * *", at).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 10, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn next_run_after_rejects_invalid_cron() {
+        assert!(next_run_after("every minute", Utc::now()).is_err());
+        assert!(next_run_after("61 * * * *", Utc::now()).is_err());
+    }
+}
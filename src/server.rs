@@ -4,29 +4,227 @@
 
 // gRPC server startup logic
 
+use crate::args::Args;
+use crate::auth::{AuthProvider, auth_interceptor};
+use crate::proto::sapphillon::server::v1::event_service_server::EventServiceServer;
+use crate::proto::sapphillon::server::v1::permission_management_service_server::PermissionManagementServiceServer;
+use crate::proto::sapphillon::server::v1::plugin_management_service_server::PluginManagementServiceServer;
+use crate::proto::sapphillon::server::v1::prompt_service_server::PromptServiceServer;
+use crate::proto::sapphillon::server::v1::schedule_service_server::ScheduleServiceServer;
+use crate::proto::sapphillon::server::v1::workflow_management_service_server::WorkflowManagementServiceServer;
+use crate::rate_limit::RateLimitLayer;
 use crate::services::{
-    MyModelService, MyPluginService, MyProviderService, MyVersionService, MyWorkflowService,
+    MyEventService, MyModelService, MyPermissionManagementService, MyPluginService,
+    MyPromptService, MyProviderService, MyScheduleService, MyVersionService, MyWorkflowService,
 };
-use log::info;
+use anyhow::Context;
+use axum::http::HeaderValue;
+use log::{error, info};
 use sapphillon_core::proto::sapphillon::ai::v1::model_service_server::ModelServiceServer;
 use sapphillon_core::proto::sapphillon::ai::v1::provider_service_server::ProviderServiceServer;
 use sapphillon_core::proto::sapphillon::v1::plugin_service_server::PluginServiceServer;
 use sapphillon_core::proto::sapphillon::v1::version_service_server::VersionServiceServer;
 use sapphillon_core::proto::sapphillon::v1::workflow_service_server::WorkflowServiceServer;
-use tonic::transport::Server;
-use tower_http::cors::CorsLayer;
+use std::fmt;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tonic::service::Routes;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Where the gRPC server listens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    /// A Unix domain socket created with the given permissions.
+    #[cfg(unix)]
+    Unix {
+        path: PathBuf,
+        mode: u32,
+    },
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{addr}"),
+            #[cfg(unix)]
+            ListenAddr::Unix { path, .. } => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Parses `--listen` and `--listen-socket-mode`.
+///
+/// # Arguments
+///
+/// * `listen` - `ip:port`, or `unix:` followed by the path of a Unix domain socket
+/// * `socket_mode` - Octal permissions of the socket, e.g. `660`; unused for `ip:port`
+pub fn parse_listen_addr(listen: &str, socket_mode: &str) -> anyhow::Result<ListenAddr> {
+    let Some(path) = listen.strip_prefix("unix:") else {
+        let addr = listen.parse().with_context(|| {
+            format!("invalid --listen {listen}: expected ip:port or unix:/path")
+        })?;
+        return Ok(ListenAddr::Tcp(addr));
+    };
+    #[cfg(unix)]
+    {
+        if path.is_empty() {
+            anyhow::bail!("invalid --listen {listen}: the socket path is empty");
+        }
+        let mode = u32::from_str_radix(socket_mode.trim_start_matches("0o"), 8)
+            .ok()
+            .filter(|mode| *mode <= 0o777)
+            .with_context(|| format!("invalid --listen-socket-mode {socket_mode}"))?;
+        Ok(ListenAddr::Unix {
+            path: PathBuf::from(path),
+            mode,
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, socket_mode);
+        anyhow::bail!("--listen unix:... is only supported on Unix platforms")
+    }
+}
+
+/// Binds a Unix domain socket at `path` and restricts it to `mode`.
+///
+/// A socket left behind by a previous run is replaced; any other file at `path` is an error.
+#[cfg(unix)]
+fn bind_unix_socket(path: &Path, mode: u32) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("cannot remove the stale socket {}", path.display()))?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("cannot bind the gRPC server to {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("cannot set the permissions of {}", path.display()))?;
+    Ok(listener)
+}
+
+/// The gRPC-Web listener for browser frontends, from `--grpc-web-listen`.
+#[derive(Debug, Clone)]
+pub struct GrpcWebConfig {
+    pub addr: SocketAddr,
+    /// Origins allowed by CORS; empty allows every origin.
+    pub allowed_origins: Vec<HeaderValue>,
+}
+
+/// Parses `--grpc-web-listen` and `--grpc-web-origin`.
+///
+/// # Returns
+///
+/// Returns `None` when `--grpc-web-listen` is not set.
+pub fn parse_grpc_web_config(args: &Args) -> anyhow::Result<Option<GrpcWebConfig>> {
+    let Some(listen) = &args.grpc_web_listen else {
+        return Ok(None);
+    };
+    let addr = listen
+        .parse()
+        .with_context(|| format!("invalid --grpc-web-listen {listen}: expected ip:port"))?;
+    let allowed_origins = args
+        .grpc_web_origin
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin)
+                .with_context(|| format!("invalid --grpc-web-origin {origin}"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Some(GrpcWebConfig {
+        addr,
+        allowed_origins,
+    }))
+}
+
+/// Serves `routes` as gRPC-Web over HTTP/1.1, with CORS restricted to the configured origins.
+async fn serve_grpc_web(
+    routes: Routes,
+    auth_provider: Arc<dyn AuthProvider>,
+    rate_limit: RateLimitLayer,
+    config: GrpcWebConfig,
+    tls_config: Option<ServerTlsConfig>,
+) -> Result<(), tonic::transport::Error> {
+    let allow_origin = if config.allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(config.allowed_origins)
+    };
+    // Browsers only let gRPC-Web clients read the status and metadata headers exposed here.
+    let cors = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(tower_http::cors::Any)
+        .allow_headers(tower_http::cors::Any)
+        .expose_headers(tower_http::cors::Any);
+
+    let mut builder = Server::builder();
+    if let Some(tls_config) = tls_config {
+        builder = builder.tls_config(tls_config)?;
+    }
+    info!("gRPC-Web gateway starting on {}", config.addr);
+    builder
+        .trace_fn(|_| tracing::info_span!("grpc_web_gateway"))
+        .accept_http1(true)
+        .layer(cors)
+        .layer(tonic_web::GrpcWebLayer::new())
+        .layer(tonic::service::InterceptorLayer::new(auth_interceptor(
+            auth_provider,
+        )))
+        .layer(rate_limit)
+        .add_routes(routes)
+        .serve(config.addr)
+        .await
+}
+
+/// Reads the TLS configuration named by `--tls-cert`, `--tls-key` and `--client-ca`.
+///
+/// # Returns
+///
+/// Returns `None` when `--tls-cert` is not set and the server listens in plaintext, or an
+/// error when a file cannot be read.
+pub fn load_tls_config(args: &Args) -> anyhow::Result<Option<ServerTlsConfig>> {
+    let (Some(cert_path), Some(key_path)) = (&args.tls_cert, &args.tls_key) else {
+        return Ok(None);
+    };
+    let cert = std::fs::read(cert_path)
+        .with_context(|| format!("cannot read the TLS certificate {cert_path}"))?;
+    let key =
+        std::fs::read(key_path).with_context(|| format!("cannot read the TLS key {key_path}"))?;
+    let mut config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    if let Some(ca_path) = &args.client_ca {
+        let ca = std::fs::read(ca_path)
+            .with_context(|| format!("cannot read the client CA certificates {ca_path}"))?;
+        config = config.client_ca_root(Certificate::from_pem(ca));
+    }
+    Ok(Some(config))
+}
 
 /// Boots the gRPC server, wiring service implementations and enabling web compatibility.
 ///
 /// # Arguments
 ///
-/// This asynchronous function takes no arguments.
+/// * `auth_provider` - Provider used to authenticate every incoming request.
+/// * `listen_addr` - Address from [`parse_listen_addr`].
+/// * `tls_config` - TLS configuration from [`load_tls_config`], or `None` for plaintext.
+/// * `grpc_web` - gRPC-Web listener from [`parse_grpc_web_config`] to serve alongside.
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` when the server shuts down cleanly or an error if any initialization step fails.
-pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "0.0.0.0:50051".parse()?;
+pub async fn start_server(
+    auth_provider: Arc<dyn AuthProvider>,
+    listen_addr: ListenAddr,
+    tls_config: Option<ServerTlsConfig>,
+    grpc_web: Option<GrpcWebConfig>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let version_service = MyVersionService {};
     let workflow_connection = crate::GLOBAL_STATE
         .wait_init_and_get_connection()
@@ -63,6 +261,33 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
         })?;
     let plugin_service = MyPluginService::new(plugin_connection);
 
+    let schedule_connection = crate::GLOBAL_STATE
+        .wait_init_and_get_connection()
+        .await
+        .map_err(|err| {
+            log::error!("Failed to obtain database connection for schedule service: {err:?}");
+            err
+        })?;
+    let schedule_service = MyScheduleService::new(schedule_connection);
+
+    let prompt_connection = crate::GLOBAL_STATE
+        .wait_init_and_get_connection()
+        .await
+        .map_err(|err| {
+            log::error!("Failed to obtain database connection for prompt service: {err:?}");
+            err
+        })?;
+    let prompt_service = MyPromptService::new(prompt_connection);
+
+    let permission_connection = crate::GLOBAL_STATE
+        .wait_init_and_get_connection()
+        .await
+        .map_err(|err| {
+            log::error!("Failed to obtain database connection for permission service: {err:?}");
+            err
+        })?;
+    let permission_service = MyPermissionManagementService::new(permission_connection);
+
     let reflection_service_v1 = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(
             sapphillon_core::proto::sapphillon::v1::FILE_DESCRIPTOR_SET,
@@ -103,6 +328,9 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
         .register_encoded_file_descriptor_set(
             sapphillon_core::proto::google::protobuf::compiler::FILE_DESCRIPTOR_SET,
         )
+        .register_encoded_file_descriptor_set(
+            crate::proto::sapphillon::server::v1::FILE_DESCRIPTOR_SET,
+        )
         .build_v1()
         .unwrap();
 
@@ -146,30 +374,172 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
         .register_encoded_file_descriptor_set(
             sapphillon_core::proto::google::protobuf::compiler::FILE_DESCRIPTOR_SET,
         )
+        .register_encoded_file_descriptor_set(
+            crate::proto::sapphillon::server::v1::FILE_DESCRIPTOR_SET,
+        )
         .build_v1alpha()
         .unwrap();
 
-    info!("gRPC Server starting on {addr}");
-
-    let cors = CorsLayer::new()
-        .allow_origin(tower_http::cors::Any)
-        .allow_methods(tower_http::cors::Any)
-        .allow_headers(tower_http::cors::Any);
-
-    Server::builder()
-        .trace_fn(|_| tracing::info_span!("grpc_server")) // Add tracing span
-        .accept_http1(true)
-        .layer(cors)
-        .layer(tonic_web::GrpcWebLayer::new())
-        .add_service(reflection_service_v1_alpha)
+    let routes = Routes::new(reflection_service_v1_alpha)
         .add_service(reflection_service_v1)
         .add_service(VersionServiceServer::new(version_service))
+        .add_service(WorkflowManagementServiceServer::new(
+            workflow_service.clone(),
+        ))
         .add_service(WorkflowServiceServer::new(workflow_service))
         .add_service(ModelServiceServer::new(model_service))
         .add_service(ProviderServiceServer::new(provider_service))
+        .add_service(PluginManagementServiceServer::new(plugin_service.clone()))
         .add_service(PluginServiceServer::new(plugin_service))
-        .serve(addr)
-        .await?;
+        .add_service(ScheduleServiceServer::new(schedule_service))
+        .add_service(PromptServiceServer::new(prompt_service))
+        .add_service(PermissionManagementServiceServer::new(permission_service))
+        .add_service(EventServiceServer::new(MyEventService));
+
+    // Both ports share one limiter, so a client cannot double its quota.
+    let rate_limit = RateLimitLayer::new();
+    if let Some(grpc_web) = grpc_web {
+        let gateway = serve_grpc_web(
+            routes.clone(),
+            auth_provider.clone(),
+            rate_limit.clone(),
+            grpc_web,
+            tls_config.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = gateway.await {
+                error!("gRPC-Web gateway error: {e}");
+            }
+        });
+    }
+
+    let mut builder = Server::builder();
+    if let Some(tls_config) = tls_config {
+        builder = builder.tls_config(tls_config)?;
+        info!("gRPC Server starting on {listen_addr} with TLS");
+    } else {
+        info!("gRPC Server starting on {listen_addr}");
+    }
+
+    // Browsers are served by the gRPC-Web port, whose CORS policy follows
+    // --grpc-web-origin, so this port only speaks HTTP/2 gRPC.
+    let router = builder
+        .trace_fn(|_| tracing::info_span!("grpc_server")) // Add tracing span
+        .layer(tonic::service::InterceptorLayer::new(auth_interceptor(
+            auth_provider,
+        )))
+        .layer(rate_limit)
+        .add_routes(routes);
+
+    match listen_addr {
+        ListenAddr::Tcp(addr) => router.serve(addr).await?,
+        #[cfg(unix)]
+        ListenAddr::Unix { path, mode } => {
+            let listener = bind_unix_socket(&path, mode)?;
+            router
+                .serve_with_incoming(tokio_stream::wrappers::UnixListenerStream::new(listener))
+                .await?
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn parse(extra: &[&str]) -> Result<Args, clap::Error> {
+        let mut argv = vec!["sapphillon"];
+        argv.extend_from_slice(extra);
+        argv.push("start");
+        Args::try_parse_from(argv)
+    }
+
+    #[test]
+    fn tls_is_off_without_a_certificate() {
+        assert!(load_tls_config(&parse(&[]).unwrap()).unwrap().is_none());
+    }
+
+    #[test]
+    fn tls_options_require_a_certificate_and_key() {
+        assert!(parse(&["--tls-cert", "cert.pem"]).is_err());
+        assert!(parse(&["--tls-key", "key.pem"]).is_err());
+        assert!(parse(&["--client-ca", "ca.pem"]).is_err());
+        assert!(parse(&["--tls-cert", "cert.pem", "--tls-key", "key.pem"]).is_ok());
+    }
+
+    #[test]
+    fn listen_addresses_are_parsed() {
+        assert_eq!(
+            parse_listen_addr("127.0.0.1:8080", "600").unwrap(),
+            ListenAddr::Tcp("127.0.0.1:8080".parse().unwrap())
+        );
+        assert!(parse_listen_addr("localhost", "600").is_err());
+        #[cfg(unix)]
+        {
+            assert_eq!(
+                parse_listen_addr("unix:/run/sapphillon.sock", "0o660").unwrap(),
+                ListenAddr::Unix {
+                    path: PathBuf::from("/run/sapphillon.sock"),
+                    mode: 0o660,
+                }
+            );
+            assert!(parse_listen_addr("unix:", "600").is_err());
+            assert!(parse_listen_addr("unix:/run/sapphillon.sock", "999").is_err());
+        }
+    }
+
+    #[test]
+    fn grpc_web_listener_is_optional() {
+        assert!(
+            parse_grpc_web_config(&parse(&[]).unwrap())
+                .unwrap()
+                .is_none()
+        );
+        assert!(parse(&["--grpc-web-origin", "chrome://noraneko"]).is_err());
+
+        let args = parse(&[
+            "--grpc-web-listen",
+            "127.0.0.1:50053",
+            "--grpc-web-origin",
+            "chrome://noraneko",
+        ])
+        .unwrap();
+        let config = parse_grpc_web_config(&args).unwrap().unwrap();
+        assert_eq!(config.addr, "127.0.0.1:50053".parse().unwrap());
+        assert_eq!(config.allowed_origins, vec!["chrome://noraneko"]);
+
+        let args = parse(&["--grpc-web-listen", "localhost"]).unwrap();
+        assert!(parse_grpc_web_config(&args).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_sockets_replace_stale_sockets_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sapphillon.sock");
+        drop(bind_unix_socket(&path, 0o600).unwrap());
+        // The socket file outlives its listener and is replaced on the next start.
+        let _listener = bind_unix_socket(&path, 0o660).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        let file = dir.path().join("not-a-socket");
+        std::fs::write(&file, "").unwrap();
+        assert!(bind_unix_socket(&file, 0o600).is_err());
+    }
+
+    #[test]
+    fn unreadable_tls_files_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.pem");
+        let missing = missing.to_str().unwrap();
+        let args = parse(&["--tls-cert", missing, "--tls-key", missing]).unwrap();
+        let err = load_tls_config(&args).unwrap_err();
+        assert!(err.to_string().contains("TLS certificate"), "{err}");
+    }
+}
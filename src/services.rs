@@ -4,14 +4,25 @@
 
 // Service root module
 
+mod event;
 mod model;
+mod permission_management;
 mod plugin;
+mod plugin_management;
+mod prompt;
 mod provider;
+mod schedule;
+mod validation;
 mod version;
 mod workflow;
+mod workflow_management;
 
+pub use event::*;
 pub use model::*;
+pub use permission_management::*;
 pub use plugin::*;
+pub use prompt::*;
 pub use provider::*;
+pub use schedule::*;
 pub use version::*;
 pub use workflow::*;
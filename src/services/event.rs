@@ -0,0 +1,198 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Streams the event bus to gRPC subscribers
+
+use crate::args::TokenScope;
+use crate::auth::{request_owner, require_scope};
+use crate::events::{self, Event, EventFilter, PublishedEvent};
+use crate::proto::sapphillon::server::v1::event_service_server::EventService;
+use crate::proto::sapphillon::server::v1::subscribe_events_response::Event as EventMessage;
+use crate::proto::sapphillon::server::v1::{
+    EventsLagged, PermissionRequested, PluginInstalled, ScheduleFired, SubscribeEventsRequest,
+    SubscribeEventsResponse, WorkflowFinished, WorkflowStarted,
+};
+use database::user::is_visible_to;
+use std::pin::Pin;
+use std::time::SystemTime;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Debug, Default)]
+pub struct MyEventService;
+
+#[tonic::async_trait]
+impl EventService for MyEventService {
+    type SubscribeEventsStream =
+        Pin<Box<dyn Stream<Item = Result<SubscribeEventsResponse, Status>> + Send + 'static>>;
+
+    /// Streams the events published from now on that the caller may see.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Event types and workflow to narrow the stream to.
+    ///
+    /// # Returns
+    ///
+    /// Returns the stream, which ends when the caller cancels it. A subscriber that falls
+    /// too far behind receives `lagged` with the number of events it missed.
+    async fn subscribe_events(
+        &self,
+        request: Request<SubscribeEventsRequest>,
+    ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let request = request.into_inner();
+        let filter = EventFilter::parse(&request.types, Some(request.workflow_id))
+            .map_err(Status::invalid_argument)?;
+        debug!("Event subscription opened: owner={owner:?}, filter={filter:?}");
+
+        let stream =
+            BroadcastStream::new(events::subscribe()).filter_map(move |received| match received {
+                Ok(published) => (filter.matches(&published.event)
+                    && is_visible_to(published.owner_id.as_deref(), owner.as_deref()))
+                .then(|| Ok(event_response(published))),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                    Some(Ok(SubscribeEventsResponse {
+                        published_at: Some(SystemTime::now().into()),
+                        event: Some(EventMessage::Lagged(EventsLagged { missed })),
+                    }))
+                }
+            });
+        Ok(Response::new(
+            Box::pin(stream) as Self::SubscribeEventsStream
+        ))
+    }
+}
+
+fn event_response(published: PublishedEvent) -> SubscribeEventsResponse {
+    let event = match published.event {
+        Event::WorkflowStarted {
+            workflow_id,
+            workflow_code_id,
+        } => EventMessage::WorkflowStarted(WorkflowStarted {
+            workflow_id,
+            workflow_code_id,
+        }),
+        Event::WorkflowFinished {
+            workflow_id,
+            workflow_code_id,
+            succeeded,
+            error,
+        } => EventMessage::WorkflowFinished(WorkflowFinished {
+            workflow_id,
+            workflow_code_id,
+            succeeded,
+            error: error.unwrap_or_default(),
+        }),
+        Event::ScheduleFired {
+            schedule_id,
+            workflow_id,
+        } => EventMessage::ScheduleFired(ScheduleFired {
+            schedule_id,
+            workflow_id,
+        }),
+        Event::PermissionRequested {
+            workflow_id,
+            workflow_code_id,
+            plugin_function_id,
+            prompt_id,
+        } => EventMessage::PermissionRequested(PermissionRequested {
+            workflow_id,
+            workflow_code_id,
+            plugin_function_id,
+            prompt_id,
+        }),
+        Event::PluginInstalled { plugin_package_id } => {
+            EventMessage::PluginInstalled(PluginInstalled { plugin_package_id })
+        }
+    };
+    SubscribeEventsResponse {
+        published_at: Some(published.at.into()),
+        event: Some(event),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthIdentity;
+
+    fn request_as(subject: &str, scopes: Vec<TokenScope>) -> Request<SubscribeEventsRequest> {
+        let mut request = Request::new(SubscribeEventsRequest {
+            types: vec!["workflow_started".to_string()],
+            workflow_id: String::new(),
+        });
+        request.extensions_mut().insert(AuthIdentity {
+            subject: subject.to_string(),
+            scopes,
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_the_events_they_may_see() {
+        let mut stream = MyEventService
+            .subscribe_events(request_as("alice", vec![TokenScope::Read]))
+            .await
+            .unwrap()
+            .into_inner();
+
+        for (workflow_id, owner) in [("event-svc-bob", "bob"), ("event-svc-alice", "alice")] {
+            events::publish(
+                Event::PluginInstalled {
+                    plugin_package_id: "pkg".to_string(),
+                },
+                None,
+            );
+            events::workflow_started(workflow_id, "wc", Some(owner.to_string()));
+            events::workflow_finished(workflow_id, "wc", Some(owner.to_string()), None);
+        }
+
+        // Other tests may publish on the same bus, so skip to the event of this one.
+        let received = loop {
+            let response = stream.next().await.unwrap().unwrap();
+            let from_this_test = matches!(
+                &response.event,
+                Some(EventMessage::WorkflowStarted(started))
+                    if started.workflow_id.starts_with("event-svc-")
+            );
+            if from_this_test {
+                break response;
+            }
+        };
+        assert!(received.published_at.is_some());
+        assert_eq!(
+            received.event,
+            Some(EventMessage::WorkflowStarted(WorkflowStarted {
+                workflow_id: "event-svc-alice".to_string(),
+                workflow_code_id: "wc".to_string(),
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribing_needs_the_read_scope_and_known_types() {
+        let status = MyEventService
+            .subscribe_events(request_as("ci", vec![TokenScope::Run]))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        let mut request = request_as("alice", vec![TokenScope::Read]);
+        request.get_mut().types = vec!["workflow_deleted".to_string()];
+        let status = MyEventService
+            .subscribe_events(request)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
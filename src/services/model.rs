@@ -13,6 +13,10 @@ use log::{debug, error, info};
 use sea_orm::{DatabaseConnection, DbErr};
 use tonic::{Request, Response, Status};
 
+use crate::args::TokenScope;
+use crate::auth::require_scope;
+use crate::services::validation::validate;
+
 use database::{model as model_db, provider as provider_db};
 use sapphillon_core::proto::google::rpc::{Code as RpcCode, Status as RpcStatus};
 use sapphillon_core::proto::sapphillon::ai::v1::model_service_server::ModelService;
@@ -122,22 +126,13 @@ impl ModelService for MyModelService {
         &self,
         request: Request<CreateModelRequest>,
     ) -> Result<Response<CreateModelResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        validate(&request)?;
         let req = request.into_inner();
         let incoming = req
             .model
             .ok_or_else(|| Status::invalid_argument("model field is required"))?;
 
-        if incoming.display_name.trim().is_empty() {
-            return Err(Status::invalid_argument(
-                "model.display_name must not be empty",
-            ));
-        }
-        if incoming.provider_name.trim().is_empty() {
-            return Err(Status::invalid_argument(
-                "model.provider_name must not be empty",
-            ));
-        }
-
         let has_custom_name = !incoming.name.trim().is_empty();
         let provider_name_requested = incoming.provider_name.trim().to_string();
         info!(
@@ -189,10 +184,9 @@ impl ModelService for MyModelService {
         &self,
         request: Request<GetModelRequest>,
     ) -> Result<Response<GetModelResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
         let req = request.into_inner();
-        if req.name.trim().is_empty() {
-            return Err(Status::invalid_argument("name must not be empty"));
-        }
 
         debug!(
             "get_model request received: model_name={model_name}",
@@ -230,6 +224,8 @@ impl ModelService for MyModelService {
         &self,
         request: Request<ListModelsRequest>,
     ) -> Result<Response<ListModelsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
         let req = request.into_inner();
 
         debug!(
@@ -280,10 +276,9 @@ impl ModelService for MyModelService {
         &self,
         request: Request<DeleteModelRequest>,
     ) -> Result<Response<DeleteModelResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        validate(&request)?;
         let req = request.into_inner();
-        if req.name.trim().is_empty() {
-            return Err(Status::invalid_argument("name must not be empty"));
-        }
 
         info!(
             "delete_model request received: model_name={model_name}",
@@ -324,15 +319,13 @@ impl ModelService for MyModelService {
         &self,
         request: Request<UpdateModelRequest>,
     ) -> Result<Response<UpdateModelResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        validate(&request)?;
         let req = request.into_inner();
         let incoming = req
             .model
             .ok_or_else(|| Status::invalid_argument("model field is required"))?;
 
-        if incoming.name.trim().is_empty() {
-            return Err(Status::invalid_argument("model.name must not be empty"));
-        }
-
         let existing = model_db::get_model(&self.db, &incoming.name)
             .await
             .map_err(Self::map_db_error)?
@@ -350,11 +343,6 @@ impl ModelService for MyModelService {
         let mut desired = existing.clone();
 
         if update_all || mask_paths.iter().any(|path| path == "display_name") {
-            if incoming.display_name.trim().is_empty() {
-                return Err(Status::invalid_argument(
-                    "model.display_name must not be empty",
-                ));
-            }
             desired.display_name = incoming.display_name.clone();
         }
 
@@ -363,11 +351,6 @@ impl ModelService for MyModelService {
         }
 
         if update_all || mask_paths.iter().any(|path| path == "provider_name") {
-            if incoming.provider_name.trim().is_empty() {
-                return Err(Status::invalid_argument(
-                    "model.provider_name must not be empty",
-                ));
-            }
             let provider_name = incoming.provider_name.trim().to_string();
             self.ensure_provider_exists(&provider_name).await?;
             desired.provider_name = provider_name;
@@ -395,6 +378,7 @@ impl ModelService for MyModelService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::local_request;
     use migration::MigratorTrait;
     use sapphillon_core::proto::google::protobuf::FieldMask;
     use sapphillon_core::proto::sapphillon::ai::v1::Provider;
@@ -457,7 +441,7 @@ mod tests {
     async fn create_and_get_model_roundtrip() {
         let service = setup_service_with_providers(vec![provider("providers/test", "Test")]).await;
 
-        let create_req = Request::new(CreateModelRequest {
+        let create_req = local_request(CreateModelRequest {
             model: Some(Models {
                 name: String::new(),
                 display_name: "Sample Model".to_string(),
@@ -479,7 +463,7 @@ mod tests {
         assert_eq!(model.description.as_deref(), Some("desc"));
 
         let fetched = service
-            .get_model(Request::new(GetModelRequest {
+            .get_model(local_request(GetModelRequest {
                 name: model.name.clone(),
             }))
             .await
@@ -505,7 +489,7 @@ mod tests {
     async fn update_and_list_models() {
         let service = setup_service_with_providers(vec![provider("providers/base", "Base")]).await;
 
-        let create_req = Request::new(CreateModelRequest {
+        let create_req = local_request(CreateModelRequest {
             model: Some(Models {
                 name: String::new(),
                 display_name: "Initial".to_string(),
@@ -522,7 +506,7 @@ mod tests {
             .into_inner();
         let created = create_resp.model.expect("model");
 
-        let update_req = Request::new(UpdateModelRequest {
+        let update_req = local_request(UpdateModelRequest {
             model: Some(Models {
                 name: created.name.clone(),
                 display_name: "Updated".to_string(),
@@ -546,7 +530,7 @@ mod tests {
         assert!(updated.description.is_none());
 
         let list_resp = service
-            .list_models(Request::new(ListModelsRequest {
+            .list_models(local_request(ListModelsRequest {
                 page_size: 10,
                 page_token: String::new(),
             }))
@@ -573,7 +557,7 @@ mod tests {
     async fn delete_model_removes_record() {
         let service = setup_service_with_providers(vec![provider("providers/main", "Main")]).await;
 
-        let create_req = Request::new(CreateModelRequest {
+        let create_req = local_request(CreateModelRequest {
             model: Some(Models {
                 name: String::new(),
                 display_name: "ToDelete".to_string(),
@@ -592,14 +576,14 @@ mod tests {
             .expect("model");
 
         service
-            .delete_model(Request::new(DeleteModelRequest {
+            .delete_model(local_request(DeleteModelRequest {
                 name: created.name.clone(),
             }))
             .await
             .expect("delete");
 
         let err = service
-            .get_model(Request::new(GetModelRequest { name: created.name }))
+            .get_model(local_request(GetModelRequest { name: created.name }))
             .await
             .expect_err("get should fail after delete");
 
@@ -620,7 +604,7 @@ mod tests {
         let service = setup_service_with_providers(vec![]).await;
 
         let err = service
-            .create_model(Request::new(CreateModelRequest {
+            .create_model(local_request(CreateModelRequest {
                 model: Some(Models {
                     name: String::new(),
                     display_name: "Invalid".to_string(),
@@ -0,0 +1,807 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Permissions of workflows beyond the allowed permissions of their code
+
+use std::sync::Arc;
+
+use database::denied_permission::{
+    create_denied_permission, delete_denied_permission, get_denied_permission,
+    list_denied_permissions,
+};
+use database::permission_profile::{
+    attach_permission_profile, delete_permission_profile, detach_permission_profile,
+    get_permission_profile, list_permission_profiles, list_workflow_permission_profiles,
+    save_permission_profile,
+};
+use database::prompt::{answer_prompt, get_prompt};
+use database::user::{is_changeable_by, is_visible_to};
+use database::workflow::{get_workflow_code_workflow_id, get_workflow_owner};
+use entity::entity::permission_profile::Model as StoredPermissionProfile;
+use entity::entity::workflow_code_denied_permission::Model as WorkflowCodeDeniedPermission;
+use sapphillon_core::proto::sapphillon::v1::PermissionType;
+use sea_orm::{DatabaseConnection, DbErr};
+use serde_json::Value;
+use tonic::{Request, Response, Status};
+
+use crate::args::TokenScope;
+use crate::auth::{request_may_change_shared, request_owner, require_scope};
+use crate::permission_profiles::parse_profile_permissions;
+use crate::prompt_handler::PERMISSION_PROMPT_KIND;
+use crate::proto::sapphillon::server::v1::permission_management_service_server::PermissionManagementService;
+use crate::proto::sapphillon::server::v1::{
+    AnswerPermissionRequestRequest, AnswerPermissionRequestResponse,
+    AttachPermissionProfileRequest, AttachPermissionProfileResponse,
+    DeletePermissionProfileRequest, DeletePermissionProfileResponse, DeniedPermission,
+    DenyPermissionRequest, DenyPermissionResponse, DetachPermissionProfileRequest,
+    DetachPermissionProfileResponse, FunctionPermissions, GetPermissionProfileRequest,
+    GetPermissionProfileResponse, ListAttachedPermissionProfilesRequest,
+    ListAttachedPermissionProfilesResponse, ListDeniedPermissionsRequest,
+    ListDeniedPermissionsResponse, ListPermissionProfilesRequest, ListPermissionProfilesResponse,
+    PermissionProfile, RemoveDeniedPermissionRequest, RemoveDeniedPermissionResponse,
+    SavePermissionProfileRequest, SavePermissionProfileResponse,
+};
+use crate::proto::{permission, timestamp};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Clone, Debug)]
+pub struct MyPermissionManagementService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl MyPermissionManagementService {
+    /// Creates a new permission management service backed by the provided database
+    /// connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    fn map_db_error(err: DbErr) -> Status {
+        error!("database operation failed: {err:?}");
+        Status::internal("database operation failed")
+    }
+
+    /// Checks that the workflow exists and that `owner` may use it, reporting the
+    /// workflows of other users as not found.
+    async fn authorize_workflow(
+        &self,
+        workflow_id: &str,
+        owner: Option<&str>,
+    ) -> Result<(), Status> {
+        match get_workflow_owner(&self.db, workflow_id).await {
+            Ok(row_owner) if is_visible_to(row_owner.as_deref(), owner) => Ok(()),
+            Ok(_) | Err(DbErr::RecordNotFound(_)) => {
+                Err(Status::not_found(format!("workflow '{workflow_id}'")))
+            }
+            Err(err) => Err(Self::map_db_error(err)),
+        }
+    }
+
+    /// Like [`Self::authorize_workflow`] for requests that change the workflow, which
+    /// callers may only do to shared workflows with `may_change_shared`.
+    async fn authorize_workflow_change(
+        &self,
+        workflow_id: &str,
+        owner: Option<&str>,
+        may_change_shared: bool,
+    ) -> Result<(), Status> {
+        match get_workflow_owner(&self.db, workflow_id).await {
+            Ok(row_owner) if !is_visible_to(row_owner.as_deref(), owner) => {
+                Err(Status::not_found(format!("workflow '{workflow_id}'")))
+            }
+            Ok(row_owner) if is_changeable_by(row_owner.as_deref(), owner, may_change_shared) => {
+                Ok(())
+            }
+            Ok(_) => Err(Status::permission_denied(format!(
+                "workflow '{workflow_id}' is shared and only admins may change it"
+            ))),
+            Err(DbErr::RecordNotFound(_)) => {
+                Err(Status::not_found(format!("workflow '{workflow_id}'")))
+            }
+            Err(err) => Err(Self::map_db_error(err)),
+        }
+    }
+
+    /// Checks that the workflow code exists and that `owner` may use its workflow,
+    /// reporting the codes of other users' workflows as not found.
+    async fn authorize_workflow_code(
+        &self,
+        workflow_code_id: &str,
+        owner: Option<&str>,
+    ) -> Result<(), Status> {
+        let workflow_id = self.workflow_code_workflow_id(workflow_code_id).await?;
+        self.authorize_workflow(&workflow_id, owner)
+            .await
+            .map_err(|status| Self::code_not_found(status, workflow_code_id))
+    }
+
+    /// Like [`Self::authorize_workflow_code`] for requests that change the workflow; see
+    /// [`Self::authorize_workflow_change`].
+    async fn authorize_workflow_code_change(
+        &self,
+        workflow_code_id: &str,
+        owner: Option<&str>,
+        may_change_shared: bool,
+    ) -> Result<(), Status> {
+        let workflow_id = self.workflow_code_workflow_id(workflow_code_id).await?;
+        self.authorize_workflow_change(&workflow_id, owner, may_change_shared)
+            .await
+            .map_err(|status| Self::code_not_found(status, workflow_code_id))
+    }
+
+    async fn workflow_code_workflow_id(&self, workflow_code_id: &str) -> Result<String, Status> {
+        match get_workflow_code_workflow_id(&self.db, workflow_code_id).await {
+            Ok(workflow_id) => Ok(workflow_id),
+            Err(DbErr::RecordNotFound(_)) => Err(Status::not_found(format!(
+                "workflow code '{workflow_code_id}'"
+            ))),
+            Err(err) => Err(Self::map_db_error(err)),
+        }
+    }
+
+    /// Reports a workflow that was not found as its code, so the workflow ID is not leaked.
+    fn code_not_found(status: Status, workflow_code_id: &str) -> Status {
+        match status.code() {
+            tonic::Code::NotFound => {
+                Status::not_found(format!("workflow code '{workflow_code_id}'"))
+            }
+            _ => status,
+        }
+    }
+}
+
+fn denied_permission_message(
+    denied: WorkflowCodeDeniedPermission,
+) -> Result<DeniedPermission, Status> {
+    let resource: Vec<String> = denied
+        .resource_json
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(|err| {
+            error!(
+                "invalid resources of denied permission {}: {err}",
+                denied.id
+            );
+            Status::internal("invalid denied permission")
+        })?
+        .unwrap_or_default();
+    Ok(DeniedPermission {
+        id: denied.id,
+        workflow_code_id: denied.workflow_code_id,
+        plugin_function_id: denied.plugin_function_id,
+        permission: Some(permission(
+            sapphillon_core::proto::sapphillon::v1::Permission {
+                permission_type: denied.permission_type,
+                resource,
+                ..Default::default()
+            },
+        )),
+        created_at: denied.created_at.map(timestamp),
+    })
+}
+
+fn permission_profile_message(
+    profile: StoredPermissionProfile,
+) -> Result<PermissionProfile, Status> {
+    let permissions = parse_profile_permissions(&profile.permissions)
+        .map_err(|err| {
+            error!("invalid permissions of profile {}: {err:#}", profile.name);
+            Status::internal("invalid permission profile")
+        })?
+        .into_iter()
+        .map(|allowed| FunctionPermissions {
+            plugin_function_id: allowed.plugin_function_id,
+            permissions: allowed.permissions.into_iter().map(permission).collect(),
+        })
+        .collect();
+    Ok(PermissionProfile {
+        name: profile.name,
+        description: profile.description.unwrap_or_default(),
+        permissions_json: profile.permissions,
+        permissions,
+        created_at: profile.created_at.map(timestamp),
+    })
+}
+
+#[tonic::async_trait]
+impl PermissionManagementService for MyPermissionManagementService {
+    async fn answer_permission_request(
+        &self,
+        request: Request<AnswerPermissionRequestRequest>,
+    ) -> Result<Response<AnswerPermissionRequestResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found = || Status::not_found(format!("permission request '{}'", req.prompt_id));
+
+        let prompt = get_prompt(&self.db, &req.prompt_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .filter(|prompt| prompt.kind == PERMISSION_PROMPT_KIND)
+            .ok_or_else(not_found)?;
+        self.authorize_workflow_change(&prompt.workflow_id, owner.as_deref(), may_change_shared)
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::PermissionDenied => status,
+                _ => not_found(),
+            })?;
+        let answer = Value::Bool(req.granted).to_string();
+        if answer_prompt(&self.db, &prompt.id, &answer)
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(Status::failed_precondition(format!(
+                "permission request is no longer pending: {}",
+                prompt.id
+            )));
+        }
+        info!(
+            "Permission request {} {}",
+            prompt.id,
+            if req.granted { "granted" } else { "denied" }
+        );
+
+        Ok(Response::new(AnswerPermissionRequestResponse {}))
+    }
+
+    async fn deny_permission(
+        &self,
+        request: Request<DenyPermissionRequest>,
+    ) -> Result<Response<DenyPermissionResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        debug!(
+            "deny_permission request received: workflow_code_id={}, permission_type={}",
+            req.workflow_code_id, req.permission_type
+        );
+
+        self.authorize_workflow_code_change(
+            &req.workflow_code_id,
+            owner.as_deref(),
+            may_change_shared,
+        )
+        .await?;
+        if PermissionType::try_from(req.permission_type).is_err() {
+            return Err(Status::invalid_argument(format!(
+                "unknown permission type: {}",
+                req.permission_type
+            )));
+        }
+        let plugin_function_id = match req.plugin_function_id.as_str() {
+            "" => "*",
+            id => id,
+        };
+        let resource_json = if req.resource.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::to_string(&req.resource)
+                    .map_err(|err| Status::invalid_argument(format!("invalid resources: {err}")))?,
+            )
+        };
+        let denied = create_denied_permission(
+            &self.db,
+            &req.workflow_code_id,
+            plugin_function_id,
+            req.permission_type,
+            resource_json.as_deref(),
+        )
+        .await
+        .map_err(Self::map_db_error)?;
+        info!("Created denied permission {}", denied.id);
+
+        Ok(Response::new(DenyPermissionResponse {
+            denied_permission: Some(denied_permission_message(denied)?),
+        }))
+    }
+
+    async fn list_denied_permissions(
+        &self,
+        request: Request<ListDeniedPermissionsRequest>,
+    ) -> Result<Response<ListDeniedPermissionsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+
+        self.authorize_workflow_code(&req.workflow_code_id, owner.as_deref())
+            .await?;
+        let denied_permissions = list_denied_permissions(&self.db, &req.workflow_code_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(denied_permission_message)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Response::new(ListDeniedPermissionsResponse {
+            denied_permissions,
+        }))
+    }
+
+    async fn remove_denied_permission(
+        &self,
+        request: Request<RemoveDeniedPermissionRequest>,
+    ) -> Result<Response<RemoveDeniedPermissionResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found =
+            || Status::not_found(format!("denied permission '{}'", req.denied_permission_id));
+
+        let denied = get_denied_permission(&self.db, &req.denied_permission_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        self.authorize_workflow_code_change(
+            &denied.workflow_code_id,
+            owner.as_deref(),
+            may_change_shared,
+        )
+        .await
+        .map_err(|status| match status.code() {
+            tonic::Code::PermissionDenied => status,
+            _ => not_found(),
+        })?;
+        if delete_denied_permission(&self.db, &denied.id)
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(not_found());
+        }
+        info!("Removed denied permission: {}", denied.id);
+
+        Ok(Response::new(RemoveDeniedPermissionResponse {}))
+    }
+
+    async fn list_permission_profiles(
+        &self,
+        request: Request<ListPermissionProfilesRequest>,
+    ) -> Result<Response<ListPermissionProfilesResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+
+        let profiles = list_permission_profiles(&self.db)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(permission_profile_message)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Response::new(ListPermissionProfilesResponse { profiles }))
+    }
+
+    async fn get_permission_profile(
+        &self,
+        request: Request<GetPermissionProfileRequest>,
+    ) -> Result<Response<GetPermissionProfileResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let req = request.into_inner();
+
+        let profile = get_permission_profile(&self.db, &req.name)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(|| Status::not_found(format!("permission profile '{}'", req.name)))?;
+
+        Ok(Response::new(GetPermissionProfileResponse {
+            profile: Some(permission_profile_message(profile)?),
+        }))
+    }
+
+    async fn save_permission_profile(
+        &self,
+        request: Request<SavePermissionProfileRequest>,
+    ) -> Result<Response<SavePermissionProfileResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+        debug!(
+            "save_permission_profile request received: name={}",
+            req.name
+        );
+
+        if req.name.trim().is_empty() {
+            return Err(Status::invalid_argument("a profile needs a name"));
+        }
+        parse_profile_permissions(&req.permissions_json)
+            .map_err(|err| Status::invalid_argument(format!("{err:#}")))?;
+        let description = Some(req.description.as_str()).filter(|d| !d.is_empty());
+        let profile =
+            save_permission_profile(&self.db, &req.name, description, &req.permissions_json)
+                .await
+                .map_err(Self::map_db_error)?;
+        info!("Saved permission profile: {}", profile.name);
+
+        Ok(Response::new(SavePermissionProfileResponse {
+            profile: Some(permission_profile_message(profile)?),
+        }))
+    }
+
+    async fn delete_permission_profile(
+        &self,
+        request: Request<DeletePermissionProfileRequest>,
+    ) -> Result<Response<DeletePermissionProfileResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+
+        if delete_permission_profile(&self.db, &req.name)
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(Status::not_found(format!(
+                "permission profile '{}'",
+                req.name
+            )));
+        }
+        info!("Deleted permission profile: {}", req.name);
+
+        Ok(Response::new(DeletePermissionProfileResponse {}))
+    }
+
+    async fn attach_permission_profile(
+        &self,
+        request: Request<AttachPermissionProfileRequest>,
+    ) -> Result<Response<AttachPermissionProfileResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+        if get_permission_profile(&self.db, &req.name)
+            .await
+            .map_err(Self::map_db_error)?
+            .is_none()
+        {
+            return Err(Status::not_found(format!(
+                "permission profile '{}'",
+                req.name
+            )));
+        }
+        attach_permission_profile(&self.db, &req.workflow_id, &req.name)
+            .await
+            .map_err(Self::map_db_error)?;
+        info!(
+            "Attached permission profile {} to workflow {}",
+            req.name, req.workflow_id
+        );
+
+        Ok(Response::new(AttachPermissionProfileResponse {}))
+    }
+
+    async fn detach_permission_profile(
+        &self,
+        request: Request<DetachPermissionProfileRequest>,
+    ) -> Result<Response<DetachPermissionProfileResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+        if detach_permission_profile(&self.db, &req.workflow_id, &req.name)
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(Status::not_found(format!(
+                "permission profile '{}' attached to '{}'",
+                req.name, req.workflow_id
+            )));
+        }
+        info!(
+            "Detached permission profile {} from workflow {}",
+            req.name, req.workflow_id
+        );
+
+        Ok(Response::new(DetachPermissionProfileResponse {}))
+    }
+
+    async fn list_attached_permission_profiles(
+        &self,
+        request: Request<ListAttachedPermissionProfilesRequest>,
+    ) -> Result<Response<ListAttachedPermissionProfilesResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+        let profiles = list_workflow_permission_profiles(&self.db, &req.workflow_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(permission_profile_message)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Response::new(ListAttachedPermissionProfilesResponse {
+            profiles,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthIdentity;
+    use database::prompt::create_prompt;
+    use entity::entity::{workflow, workflow_code};
+    use migration::MigratorTrait;
+    use sea_orm::{ActiveModelTrait, IntoActiveModel};
+
+    async fn setup_service() -> MyPermissionManagementService {
+        let conn = sea_orm::Database::connect("sqlite::memory:?cache=shared")
+            .await
+            .expect("connect sqlite memory db");
+        migration::Migrator::up(&conn, None)
+            .await
+            .expect("apply migrations");
+        for (id, owner_id) in [("wf-alice", Some("alice")), ("wf-bob", Some("bob"))] {
+            workflow::Model {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                description: None,
+                workflow_language: 0,
+                created_at: None,
+                updated_at: None,
+                version: 1,
+                owner_id: owner_id.map(str::to_string),
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow");
+            workflow_code::Model {
+                id: format!("{id}-code"),
+                workflow_id: id.to_string(),
+                code_revision: 1,
+                code: format!("{id}-code"),
+                language: 0,
+                created_at: None,
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow code");
+        }
+        MyPermissionManagementService::new(conn)
+    }
+
+    fn request_as<T>(subject: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuthIdentity {
+            subject: subject.to_string(),
+            scopes: vec![TokenScope::Read, TokenScope::Write],
+        });
+        request
+    }
+
+    fn admin_request<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuthIdentity {
+            subject: "admin".to_string(),
+            scopes: vec![TokenScope::Read, TokenScope::Write, TokenScope::Admin],
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn permission_requests_are_answered_by_the_owner_of_their_workflow() {
+        let service = setup_service().await;
+        let request = create_prompt(
+            &service.db,
+            "wf-alice",
+            PERMISSION_PROMPT_KIND,
+            "Allow app.read?",
+        )
+        .await
+        .expect("create permission prompt");
+        let question = create_prompt(&service.db, "wf-alice", "confirm", "Continue?")
+            .await
+            .expect("create prompt");
+
+        let answer = |subject: &str, prompt_id: &str, granted: bool| {
+            service.answer_permission_request(request_as(
+                subject,
+                AnswerPermissionRequestRequest {
+                    prompt_id: prompt_id.to_string(),
+                    granted,
+                },
+            ))
+        };
+        assert_eq!(
+            answer("bob", &request.id, true).await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        assert_eq!(
+            answer("alice", &question.id, true)
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::NotFound
+        );
+        answer("alice", &request.id, false)
+            .await
+            .expect("deny permission request");
+        let stored = get_prompt(&service.db, &request.id).await.unwrap().unwrap();
+        assert_eq!(stored.answer.as_deref(), Some("false"));
+        assert_eq!(
+            answer("alice", &request.id, true).await.unwrap_err().code(),
+            tonic::Code::FailedPrecondition
+        );
+    }
+
+    #[tokio::test]
+    async fn denied_permissions_are_limited_to_the_owner_of_their_workflow() {
+        let service = setup_service().await;
+        let deny = |subject: &str, workflow_code_id: &str| {
+            service.deny_permission(request_as(
+                subject,
+                DenyPermissionRequest {
+                    workflow_code_id: workflow_code_id.to_string(),
+                    plugin_function_id: String::new(),
+                    permission_type: PermissionType::NetAccess as i32,
+                    resource: vec!["internal.corp".to_string()],
+                },
+            ))
+        };
+        let denied = deny("alice", "wf-alice-code")
+            .await
+            .expect("deny permission")
+            .into_inner()
+            .denied_permission
+            .expect("denied permission in response");
+        assert_eq!(denied.plugin_function_id, "*");
+        assert_eq!(
+            denied.permission.unwrap().resource,
+            vec!["internal.corp".to_string()]
+        );
+        assert_eq!(
+            deny("alice", "wf-bob-code").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+
+        let listed = |subject: &str| {
+            service.list_denied_permissions(request_as(
+                subject,
+                ListDeniedPermissionsRequest {
+                    workflow_code_id: "wf-alice-code".to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            listed("alice")
+                .await
+                .unwrap()
+                .into_inner()
+                .denied_permissions
+                .len(),
+            1
+        );
+        assert_eq!(
+            listed("bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+
+        let remove = |subject: &str| {
+            service.remove_denied_permission(request_as(
+                subject,
+                RemoveDeniedPermissionRequest {
+                    denied_permission_id: denied.id.clone(),
+                },
+            ))
+        };
+        assert_eq!(
+            remove("bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        remove("alice").await.expect("remove denied permission");
+        assert!(
+            listed("alice")
+                .await
+                .unwrap()
+                .into_inner()
+                .denied_permissions
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn profiles_are_saved_by_admins_and_attached_by_workflow_owners() {
+        let service = setup_service().await;
+        let save = SavePermissionProfileRequest {
+            name: "read-only".to_string(),
+            description: "Reads files".to_string(),
+            permissions_json: r#"[{"function": "*", "type": "filesystem-read"}]"#.to_string(),
+        };
+        assert_eq!(
+            service
+                .save_permission_profile(request_as("alice", save.clone()))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        let invalid = SavePermissionProfileRequest {
+            permissions_json: r#"[{"function": "*", "type": "teleport"}]"#.to_string(),
+            ..save.clone()
+        };
+        assert_eq!(
+            service
+                .save_permission_profile(admin_request(invalid))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::InvalidArgument
+        );
+        let profile = service
+            .save_permission_profile(admin_request(save))
+            .await
+            .expect("save profile")
+            .into_inner()
+            .profile
+            .expect("profile in response");
+        assert_eq!(profile.permissions.len(), 1);
+        assert_eq!(profile.permissions[0].plugin_function_id, "*");
+
+        let attach = |subject: &str, workflow_id: &str| {
+            service.attach_permission_profile(request_as(
+                subject,
+                AttachPermissionProfileRequest {
+                    workflow_id: workflow_id.to_string(),
+                    name: "read-only".to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            attach("alice", "wf-bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        attach("alice", "wf-alice").await.expect("attach profile");
+
+        let attached = |subject: &str| {
+            service.list_attached_permission_profiles(request_as(
+                subject,
+                ListAttachedPermissionProfilesRequest {
+                    workflow_id: "wf-alice".to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            attached("alice").await.unwrap().into_inner().profiles,
+            vec![profile]
+        );
+        assert_eq!(
+            attached("bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+
+        service
+            .detach_permission_profile(request_as(
+                "alice",
+                DetachPermissionProfileRequest {
+                    workflow_id: "wf-alice".to_string(),
+                    name: "read-only".to_string(),
+                },
+            ))
+            .await
+            .expect("detach profile");
+        assert!(
+            attached("alice")
+                .await
+                .unwrap()
+                .into_inner()
+                .profiles
+                .is_empty()
+        );
+    }
+}
@@ -9,13 +9,25 @@
 
 use std::sync::Arc;
 
-use database::plugin::list_plugins;
-use log::{debug, error};
+use crate::args::TokenScope;
+use crate::auth::require_scope;
+use crate::events::{self, Event};
+use crate::plugin_installer::{
+    InstallError, PluginInspection, check_dependencies, inspect_plugin_from_uri,
+};
+use crate::plugin_store::{PluginStoreClient, StoreError, StorePackage};
+use crate::plugin_updater::check_for_updates;
+use crate::plugin_validation::PluginDiagnostic;
+use crate::services::validation::{validate, violation_status};
+use database::ext_plugin::{list_ext_plugin_updates, set_ext_plugin_enabled};
+use database::plugin::{get_plugin, get_plugin_function, list_plugins};
+use entity::entity::ext_plugin_package::Model as ExtPluginPackageModel;
+use log::{debug, error, warn};
 use sapphillon_core::proto::google::rpc::{Code as RpcCode, Status as RpcStatus};
 use sapphillon_core::proto::sapphillon::v1::plugin_service_server::PluginService;
 use sapphillon_core::proto::sapphillon::v1::{
     InstallPluginRequest, InstallPluginResponse, ListPluginsRequest, ListPluginsResponse,
-    UninstallPluginRequest, UninstallPluginResponse,
+    PluginFunction, PluginPackage, UninstallPluginRequest, UninstallPluginResponse,
 };
 use sea_orm::{DatabaseConnection, DbErr};
 use tonic::{Request, Response, Status};
@@ -43,6 +55,274 @@ impl MyPluginService {
         error!("database operation failed: {err:?}");
         Status::internal("database operation failed")
     }
+
+    /// Retrieves a registered plugin package with its functions and their permissions.
+    ///
+    /// # Arguments
+    ///
+    /// * `package_id` - Package to look up, e.g. `app.sapphillon.core.fetch`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the package, or a `NOT_FOUND` status when it is not registered.
+    pub(crate) async fn get_plugin(&self, package_id: &str) -> Result<PluginPackage, Status> {
+        debug!("get_plugin request received: package_id='{package_id}'");
+        get_plugin(&self.db, package_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(|| Status::not_found(format!("plugin '{package_id}' not found")))
+    }
+
+    /// Retrieves a registered plugin function with its parameters and permissions.
+    ///
+    /// # Arguments
+    ///
+    /// * `function_id` - Function to look up, e.g. `app.sapphillon.core.fetch.fetch`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the function, or a `NOT_FOUND` status when it is not registered.
+    pub(crate) async fn get_plugin_function(
+        &self,
+        function_id: &str,
+    ) -> Result<PluginFunction, Status> {
+        debug!("get_plugin_function request received: function_id='{function_id}'");
+        get_plugin_function(&self.db, function_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(|| Status::not_found(format!("plugin function '{function_id}' not found")))
+    }
+
+    /// Fetches a plugin and validates its package metadata without installing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - Location of the `package.js`, as accepted by `InstallPlugin`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the package metadata and the directories it would be installed into,
+    /// or an `INVALID_ARGUMENT` status describing why it cannot be installed and a
+    /// `FAILED_PRECONDITION` status listing missing dependencies.
+    pub(crate) async fn validate_plugin(&self, uri: &str) -> Result<PluginInspection, Status> {
+        debug!("validate_plugin request received: uri='{uri}'");
+        let map_error = |e: InstallError| match e {
+            InstallError::DownloadFailed(_) | InstallError::FileReadFailed(_) => {
+                Status::unavailable(e.to_string())
+            }
+            InstallError::MissingDependencies(_) => Status::failed_precondition(e.to_string()),
+            InstallError::DependencyCheckFailed(_) => Status::internal(e.to_string()),
+            _ => Status::invalid_argument(e.to_string()),
+        };
+        let inspection = inspect_plugin_from_uri(uri).await.map_err(map_error)?;
+        check_dependencies(&self.db, &inspection.manifest)
+            .await
+            .map_err(map_error)?;
+        Ok(inspection)
+    }
+
+    /// Searches the configured plugin store.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Text matched against package IDs, names and descriptions.
+    /// * `tag` - Only return packages carrying this tag.
+    ///
+    /// # Returns
+    ///
+    /// Returns the matching package versions, newest version of each package first.
+    pub(crate) async fn search_plugin_store(
+        &self,
+        query: &str,
+        tag: Option<&str>,
+    ) -> Result<Vec<StorePackage>, Status> {
+        debug!("search_plugin_store request received: query='{query}', tag={tag:?}");
+        let client = PluginStoreClient::configured().map_err(Self::map_store_error)?;
+        client
+            .search(query, tag)
+            .await
+            .map_err(Self::map_store_error)
+    }
+
+    /// Downloads a package from the configured plugin store and installs it.
+    ///
+    /// # Arguments
+    ///
+    /// * `package_id` - Package to install, e.g. `com.example.math-plugin`.
+    /// * `version` - Version to install; the newest when `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the ID of the installed plugin, e.g. `com.example/math-plugin/1.2.0`.
+    pub(crate) async fn install_from_store(
+        &self,
+        package_id: &str,
+        version: Option<&str>,
+    ) -> Result<String, Status> {
+        debug!(
+            "install_from_store request received: package_id='{package_id}', version={version:?}"
+        );
+        let client = PluginStoreClient::configured().map_err(Self::map_store_error)?;
+        let save_dir = crate::GLOBAL_STATE.get_ext_plugin_save_dir().await;
+        let (result, _) = client
+            .install(&self.db, &save_dir, package_id, version)
+            .await
+            .map_err(Self::map_store_error)?;
+        self.reload_after_change().await;
+        events::publish(
+            Event::PluginInstalled {
+                plugin_package_id: result.plugin_package_id.clone(),
+            },
+            None,
+        );
+        Ok(result.plugin_package_id)
+    }
+
+    /// Lists the installed external plugins that have a newer version in the plugin store.
+    ///
+    /// # Arguments
+    ///
+    /// * `check` - Compare with the plugin store first instead of returning the result
+    ///   of the last periodic check.
+    pub(crate) async fn list_plugin_updates(
+        &self,
+        check: bool,
+    ) -> Result<Vec<ExtPluginPackageModel>, Status> {
+        debug!("list_plugin_updates request received: check={check}");
+        if check {
+            let client = PluginStoreClient::configured().map_err(Self::map_store_error)?;
+            return check_for_updates(&self.db, &client).await.map_err(|err| {
+                error!("plugin update check failed: {err:#}");
+                Status::unavailable(format!("plugin update check failed: {err}"))
+            });
+        }
+        list_ext_plugin_updates(&self.db)
+            .await
+            .map_err(Self::map_db_error)
+    }
+
+    /// Installs the available update of an external plugin next to the installed version.
+    ///
+    /// # Arguments
+    ///
+    /// * `plugin_package_id` - Installed plugin, e.g. `author/package/1.0.0`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the ID of the newly installed version.
+    pub(crate) async fn update_plugin(&self, plugin_package_id: &str) -> Result<String, Status> {
+        debug!("update_plugin request received: plugin_package_id='{plugin_package_id}'");
+        let client = PluginStoreClient::configured().map_err(Self::map_store_error)?;
+        let save_dir = crate::GLOBAL_STATE.get_ext_plugin_save_dir().await;
+        let updated =
+            crate::plugin_updater::update_plugin(&self.db, &save_dir, &client, plugin_package_id)
+                .await
+                .map_err(|err| {
+                    error!("failed to update plugin: {err:#}");
+                    Status::failed_precondition(format!("failed to update plugin: {err}"))
+                })?;
+        self.reload_after_change().await;
+        Ok(updated)
+    }
+
+    /// Reloads the installed external plugins into the running server, the body of
+    /// the `ReloadPlugins` RPC.
+    ///
+    /// # Returns
+    ///
+    /// Returns the IDs of the loaded plugins, e.g. `author/package/1.0.0`.
+    pub(crate) async fn reload_plugins(&self) -> Result<Vec<String>, Status> {
+        debug!("reload_plugins request received");
+        crate::plugin_registry::reload_plugins(&self.db)
+            .await
+            .map_err(|err| {
+                error!("failed to reload plugins: {err:#}");
+                Status::internal(format!("failed to reload plugins: {err}"))
+            })
+    }
+
+    /// Validates the built-in and enabled external plugins, the body of the
+    /// `CheckPlugins` RPC. Plugins with problems are not offered to workflows.
+    ///
+    /// # Returns
+    ///
+    /// Returns the problems found; an empty list means every plugin is valid.
+    pub(crate) async fn check_plugins(&self) -> Result<Vec<PluginDiagnostic>, Status> {
+        debug!("check_plugins request received");
+        crate::plugin_registry::check_plugins(&self.db)
+            .await
+            .map_err(|err| {
+                error!("failed to check plugins: {err:#}");
+                Status::internal(format!("failed to check plugins: {err}"))
+            })
+    }
+
+    /// Reloads plugins after an install, update or state change. The change itself has
+    /// succeeded, so a failed reload is only logged.
+    async fn reload_after_change(&self) {
+        if let Err(status) = self.reload_plugins().await {
+            warn!("{}", status.message());
+        }
+    }
+
+    fn map_store_error(err: StoreError) -> Status {
+        error!("plugin store operation failed: {err}");
+        match &err {
+            StoreError::NotConfigured => Status::failed_precondition(err.to_string()),
+            StoreError::NotFound(_) => Status::not_found(err.to_string()),
+            StoreError::Install(InstallError::AlreadyInstalled(_)) => {
+                Status::already_exists(err.to_string())
+            }
+            StoreError::Install(
+                InstallError::DownloadFailed(_) | InstallError::FileReadFailed(_),
+            ) => Status::unavailable(err.to_string()),
+            StoreError::Install(
+                InstallError::InstallFailed(_) | InstallError::DependencyCheckFailed(_),
+            ) => Status::internal(err.to_string()),
+            _ => Status::failed_precondition(err.to_string()),
+        }
+    }
+
+    /// Lets workflows use an installed external plugin again.
+    ///
+    /// # Arguments
+    ///
+    /// * `plugin_package_id` - Installed plugin, e.g. `author/package/1.0.0`.
+    pub(crate) async fn enable_plugin(&self, plugin_package_id: &str) -> Result<(), Status> {
+        self.set_plugin_enabled(plugin_package_id, true).await
+    }
+
+    /// Keeps an installed external plugin on disk but stops offering it to workflows.
+    ///
+    /// # Arguments
+    ///
+    /// * `plugin_package_id` - Installed plugin, e.g. `author/package/1.0.0`.
+    pub(crate) async fn disable_plugin(&self, plugin_package_id: &str) -> Result<(), Status> {
+        self.set_plugin_enabled(plugin_package_id, false).await
+    }
+
+    async fn set_plugin_enabled(
+        &self,
+        plugin_package_id: &str,
+        enabled: bool,
+    ) -> Result<(), Status> {
+        debug!(
+            "set_plugin_enabled request received: plugin_package_id='{plugin_package_id}', enabled={enabled}"
+        );
+        if plugin_package_id.trim().is_empty() {
+            return Err(Status::invalid_argument("package_id field is required"));
+        }
+        match set_ext_plugin_enabled(&self.db, plugin_package_id, enabled).await {
+            Ok(_) => {
+                self.reload_after_change().await;
+                Ok(())
+            }
+            Err(DbErr::RecordNotFound(_)) => Err(Status::not_found(format!(
+                "plugin '{plugin_package_id}' is not installed"
+            ))),
+            Err(err) => Err(Self::map_db_error(err)),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -51,6 +331,8 @@ impl PluginService for MyPluginService {
         &self,
         request: Request<ListPluginsRequest>,
     ) -> Result<Response<ListPluginsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
         let req = request.into_inner();
         debug!(
             "list_plugins request received: page_size={page_size}, page_token='{page_token}'",
@@ -87,9 +369,16 @@ impl PluginService for MyPluginService {
         &self,
         request: Request<InstallPluginRequest>,
     ) -> Result<Response<InstallPluginResponse>, Status> {
-        use crate::plugin_installer::{InstallError, install_plugin_from_uri};
+        require_scope(&request, TokenScope::Admin)?;
+        use crate::plugin_installer::install_plugin_from_uri;
         use sapphillon_core::proto::google::rpc::Code as RpcCode;
 
+        if let Some(status) = violation_status(&request) {
+            return Ok(Response::new(InstallPluginResponse {
+                plugin: None,
+                status: Some(status),
+            }));
+        }
         let req = request.into_inner();
         debug!("install_plugin request received: uri='{}'", req.uri);
 
@@ -103,6 +392,13 @@ impl PluginService for MyPluginService {
                     "plugin installed successfully: {}",
                     result.plugin_package_id
                 );
+                self.reload_after_change().await;
+                events::publish(
+                    Event::PluginInstalled {
+                        plugin_package_id: result.plugin_package_id.clone(),
+                    },
+                    None,
+                );
                 Ok(Response::new(InstallPluginResponse {
                     plugin: None, // Plugin metadata not available from raw download
                     status: Self::ok_status(format!(
@@ -116,12 +412,16 @@ impl PluginService for MyPluginService {
                 let code = match &e {
                     InstallError::EmptyUri
                     | InstallError::UnsupportedScheme(_)
-                    | InstallError::InvalidUriFormat(_) => RpcCode::InvalidArgument,
+                    | InstallError::InvalidUriFormat(_)
+                    | InstallError::InvalidPackage(_) => RpcCode::InvalidArgument,
                     InstallError::DownloadFailed(_) | InstallError::FileReadFailed(_) => {
                         RpcCode::Unavailable
                     }
                     InstallError::AlreadyInstalled(_) => RpcCode::AlreadyExists,
-                    InstallError::InstallFailed(_) => RpcCode::Internal,
+                    InstallError::MissingDependencies(_) => RpcCode::FailedPrecondition,
+                    InstallError::InstallFailed(_) | InstallError::DependencyCheckFailed(_) => {
+                        RpcCode::Internal
+                    }
                 };
                 Ok(Response::new(InstallPluginResponse {
                     plugin: None,
@@ -139,29 +439,25 @@ impl PluginService for MyPluginService {
         &self,
         request: Request<UninstallPluginRequest>,
     ) -> Result<Response<UninstallPluginResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
         use sapphillon_core::proto::google::rpc::Code as RpcCode;
 
+        if let Some(status) = violation_status(&request) {
+            return Ok(Response::new(UninstallPluginResponse {
+                status: Some(status),
+            }));
+        }
         let req = request.into_inner();
         debug!(
             "uninstall_plugin request received: package_id='{}'",
             req.package_id
         );
 
-        // Validate package_id is not empty
-        if req.package_id.trim().is_empty() {
-            return Ok(Response::new(UninstallPluginResponse {
-                status: Some(RpcStatus {
-                    code: RpcCode::InvalidArgument as i32,
-                    message: "package_id field is required".to_string(),
-                    details: vec![],
-                }),
-            }));
-        }
-
         // Uninstall the plugin
         match crate::ext_plugin_manager::uninstall_ext_plugin(&self.db, &req.package_id).await {
             Ok(()) => {
                 debug!("plugin uninstalled successfully: {}", req.package_id);
+                self.reload_after_change().await;
                 Ok(Response::new(UninstallPluginResponse {
                     status: Self::ok_status(format!("plugin uninstalled: {}", req.package_id)),
                 }))
@@ -188,6 +484,7 @@ impl PluginService for MyPluginService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::local_request;
     use sea_orm::{ConnectionTrait, DbBackend, Statement};
     use tempfile::TempDir;
 
@@ -271,7 +568,9 @@ mod tests {
 			CREATE TABLE ext_plugin_package (
 				plugin_package_id TEXT NOT NULL PRIMARY KEY,
 				install_dir TEXT NOT NULL,
-				missing INTEGER NOT NULL DEFAULT 0
+				missing INTEGER NOT NULL DEFAULT 0,
+				enabled INTEGER NOT NULL DEFAULT 1,
+				available_version TEXT
 			)
 		"#;
         db.execute(Statement::from_string(
@@ -283,12 +582,32 @@ mod tests {
         Ok(db)
     }
 
+    fn package_js(name: &str, version: &str) -> String {
+        format!(
+            r#"globalThis.Sapphillon = {{
+    Package: {{
+        meta: {{
+            name: "{name}",
+            version: "{version}",
+            description: "Test plugin",
+            author_id: "com.test",
+            package_id: "com.test.{name}"
+        }},
+        functions: {{
+            hello: {{ description: "Says hello", permissions: [], handler: () => "hello" }}
+        }}
+    }}
+}};
+"#
+        )
+    }
+
     #[tokio::test]
     async fn test_list_plugins_empty() {
         let db = setup_db().await.expect("db setup failed");
         let service = MyPluginService::new(db);
 
-        let req = Request::new(ListPluginsRequest {
+        let req = local_request(ListPluginsRequest {
             page_size: 10,
             page_token: "".to_string(),
         });
@@ -302,12 +621,30 @@ mod tests {
         assert!(inner.next_page_token.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_get_plugin_not_found() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db);
+
+        let err = service
+            .get_plugin("app.sapphillon.core.missing")
+            .await
+            .expect_err("missing plugin should not be found");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let err = service
+            .get_plugin_function("app.sapphillon.core.missing.fn")
+            .await
+            .expect_err("missing function should not be found");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
     #[tokio::test]
     async fn test_install_plugin_empty_uri() {
         let db = setup_db().await.expect("db setup failed");
         let service = MyPluginService::new(db);
 
-        let req = Request::new(InstallPluginRequest {
+        let req = local_request(InstallPluginRequest {
             uri: "".to_string(),
         });
 
@@ -332,7 +669,7 @@ mod tests {
         let db = setup_db().await.expect("db setup failed");
         let service = MyPluginService::new(db);
 
-        let req = Request::new(InstallPluginRequest {
+        let req = local_request(InstallPluginRequest {
             uri: "ftp://example.com/author/pkg/1.0.0/package.js".to_string(),
         });
 
@@ -363,7 +700,7 @@ mod tests {
         std::fs::create_dir_all(&plugin_dir).expect("failed to create plugin dir");
 
         let plugin_file = plugin_dir.join("package.js");
-        std::fs::write(&plugin_file, b"console.log('test plugin');")
+        std::fs::write(&plugin_file, package_js("test-pkg", "1.0.0"))
             .expect("failed to write plugin");
 
         // Set ext_plugin_save_dir in global state
@@ -372,7 +709,7 @@ mod tests {
             .await;
 
         let file_uri = format!("file://{}", plugin_file.to_string_lossy());
-        let req = Request::new(InstallPluginRequest { uri: file_uri });
+        let req = local_request(InstallPluginRequest { uri: file_uri });
 
         let resp = service
             .install_plugin(req)
@@ -395,7 +732,7 @@ mod tests {
         let db = setup_db().await.expect("db setup failed");
         let service = MyPluginService::new(db);
 
-        let req = Request::new(UninstallPluginRequest {
+        let req = local_request(UninstallPluginRequest {
             package_id: "".to_string(),
         });
 
@@ -420,7 +757,7 @@ mod tests {
         let db = setup_db().await.expect("db setup failed");
         let service = MyPluginService::new(db);
 
-        let req = Request::new(UninstallPluginRequest {
+        let req = local_request(UninstallPluginRequest {
             package_id: "nonexistent/plugin/1.0.0".to_string(),
         });
 
@@ -453,7 +790,7 @@ mod tests {
         let plugin_source_dir = source_dir.path().join("myauthor/mypkg/2.0.0");
         std::fs::create_dir_all(&plugin_source_dir).expect("failed to create source dir");
         let plugin_file = plugin_source_dir.join("package.js");
-        std::fs::write(&plugin_file, b"console.log('my plugin');").expect("failed to write plugin");
+        std::fs::write(&plugin_file, package_js("mypkg", "2.0.0")).expect("failed to write plugin");
 
         // Set save directory
         crate::GLOBAL_STATE
@@ -462,7 +799,7 @@ mod tests {
 
         // Install the plugin
         let file_uri = format!("file://{}", plugin_file.to_string_lossy());
-        let install_req = Request::new(InstallPluginRequest {
+        let install_req = local_request(InstallPluginRequest {
             uri: file_uri.clone(),
         });
 
@@ -484,7 +821,7 @@ mod tests {
         assert!(installed_path.exists());
 
         // Uninstall the plugin
-        let uninstall_req = Request::new(UninstallPluginRequest {
+        let uninstall_req = local_request(UninstallPluginRequest {
             package_id: "myauthor/mypkg/2.0.0".to_string(),
         });
 
@@ -504,4 +841,275 @@ mod tests {
         // Verify plugin file was removed
         assert!(!installed_path.exists());
     }
+
+    #[tokio::test]
+    async fn test_install_plugin_rejects_invalid_package() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db);
+
+        let source_dir = TempDir::new().expect("failed to create source dir");
+        let plugin_dir = source_dir.path().join("badauthor/badpkg/1.0.0");
+        std::fs::create_dir_all(&plugin_dir).expect("failed to create plugin dir");
+        let plugin_file = plugin_dir.join("package.js");
+        std::fs::write(&plugin_file, b"console.log('not a package');")
+            .expect("failed to write plugin");
+
+        let req = local_request(InstallPluginRequest {
+            uri: format!("file://{}", plugin_file.to_string_lossy()),
+        });
+        let status = service
+            .install_plugin(req)
+            .await
+            .expect("install_plugin should not fail")
+            .into_inner()
+            .status
+            .unwrap();
+        assert_eq!(
+            status.code,
+            sapphillon_core::proto::google::rpc::Code::InvalidArgument as i32
+        );
+        assert!(status.message.contains("globalThis.Sapphillon"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_plugin_does_not_install() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db.clone());
+
+        let source_dir = TempDir::new().expect("failed to create source dir");
+        let plugin_dir = source_dir.path().join("someone/dry-run/0.3.0");
+        std::fs::create_dir_all(&plugin_dir).expect("failed to create plugin dir");
+        let plugin_file = plugin_dir.join("package.js");
+        std::fs::write(&plugin_file, package_js("dry-run", "0.3.0"))
+            .expect("failed to write plugin");
+
+        let inspection = service
+            .validate_plugin(&plugin_file.to_string_lossy())
+            .await
+            .expect("package should be valid");
+        assert_eq!(inspection.manifest.package_id, "com.test.dry-run");
+        assert_eq!(inspection.manifest.functions, vec!["hello".to_string()]);
+        assert_eq!(inspection.metadata.author_id, "someone");
+        assert_eq!(inspection.metadata.package_id, "dry-run");
+        assert_eq!(inspection.metadata.version, "0.3.0");
+
+        let installed = database::ext_plugin::list_ext_plugin_packages(&db)
+            .await
+            .expect("list should succeed");
+        assert!(installed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_plugin_checks_dependencies() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db.clone());
+
+        let source_dir = TempDir::new().expect("failed to create source dir");
+        let app_file = source_dir.path().join("app.js");
+        std::fs::write(
+            &app_file,
+            package_js("app", "1.0.0").replace(
+                r#"package_id: "com.test.app""#,
+                r#"package_id: "com.test.app",
+            dependencies: { "com.test.base": "^1.0" }"#,
+            ),
+        )
+        .expect("failed to write plugin");
+
+        let status = service
+            .validate_plugin(&app_file.to_string_lossy())
+            .await
+            .expect_err("dependency is not installed");
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert!(
+            status
+                .message()
+                .contains("com.test.base ^1.0 (not installed)")
+        );
+
+        let base_dir = source_dir.path().join("com.test/base/1.2.0");
+        std::fs::create_dir_all(&base_dir).expect("failed to create plugin dir");
+        std::fs::write(base_dir.join("package.js"), package_js("base", "1.2.0"))
+            .expect("failed to write plugin");
+        database::ext_plugin::create_ext_plugin_package(
+            &db,
+            "com.test/base/1.2.0".to_string(),
+            base_dir.to_string_lossy().to_string(),
+        )
+        .await
+        .expect("create should succeed");
+
+        service
+            .validate_plugin(&app_file.to_string_lossy())
+            .await
+            .expect("dependency is installed");
+    }
+
+    #[tokio::test]
+    async fn test_reload_plugins_loads_wasm_plugins() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db.clone());
+
+        let plugin_dir = TempDir::new().expect("failed to create plugin dir");
+        std::fs::write(
+            plugin_dir.path().join("plugin.json"),
+            r#"{
+                "meta": { "name": "reload", "version": "1.0.0", "author_id": "com.test",
+                          "package_id": "com.test.reload" },
+                "module": "plugin.wat",
+                "functions": { "noop": { "description": "Does nothing" } }
+            }"#,
+        )
+        .expect("failed to write manifest");
+        std::fs::write(
+            plugin_dir.path().join("plugin.wat"),
+            r#"(module (memory (export "memory") 1))"#,
+        )
+        .expect("failed to write module");
+        database::ext_plugin::create_ext_plugin_package(
+            &db,
+            "com.test/reload/1.0.0".to_string(),
+            plugin_dir.path().to_string_lossy().to_string(),
+        )
+        .await
+        .expect("create should succeed");
+
+        let loaded = service
+            .reload_plugins()
+            .await
+            .expect("reload should succeed");
+        assert_eq!(loaded, vec!["com.test/reload/1.0.0".to_string()]);
+
+        let package = service
+            .get_plugin("com.test.reload")
+            .await
+            .expect("package should be registered");
+        assert_eq!(package.functions[0].function_id, "com.test.reload.noop");
+
+        service
+            .disable_plugin("com.test/reload/1.0.0")
+            .await
+            .expect("disable should succeed");
+        let loaded = service
+            .reload_plugins()
+            .await
+            .expect("reload should succeed");
+        assert!(loaded.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_plugins_rejects_function_ids_of_builtin_plugins() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db.clone());
+
+        let plugin_dir = TempDir::new().expect("failed to create plugin dir");
+        std::fs::write(
+            plugin_dir.path().join("plugin.json"),
+            r#"{
+                "meta": { "name": "fetch", "version": "1.0.0", "author_id": "com.test",
+                          "package_id": "app.sapphillon.core.fetch" },
+                "module": "plugin.wat",
+                "functions": { "fetch": { "description": "Shadows the built-in fetch" } }
+            }"#,
+        )
+        .expect("failed to write manifest");
+        std::fs::write(
+            plugin_dir.path().join("plugin.wat"),
+            r#"(module (memory (export "memory") 1))"#,
+        )
+        .expect("failed to write module");
+        database::ext_plugin::create_ext_plugin_package(
+            &db,
+            "com.test/fetch/1.0.0".to_string(),
+            plugin_dir.path().to_string_lossy().to_string(),
+        )
+        .await
+        .expect("create should succeed");
+
+        let diagnostics = service.check_plugins().await.expect("check should succeed");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].function_id.as_deref(),
+            Some("app.sapphillon.core.fetch.fetch")
+        );
+
+        let loaded = service
+            .reload_plugins()
+            .await
+            .expect("reload should succeed");
+        assert!(loaded.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enable_and_disable_plugin() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db.clone());
+
+        database::ext_plugin::create_ext_plugin_package(
+            &db,
+            "author/pkg/1.0.0".to_string(),
+            "/tmp/author/pkg/1.0.0".to_string(),
+        )
+        .await
+        .expect("create should succeed");
+
+        service
+            .disable_plugin("author/pkg/1.0.0")
+            .await
+            .expect("disable should succeed");
+        let record = database::ext_plugin::get_ext_plugin_package(&db, "author/pkg/1.0.0")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!record.enabled);
+
+        service
+            .enable_plugin("author/pkg/1.0.0")
+            .await
+            .expect("enable should succeed");
+        let record = database::ext_plugin::get_ext_plugin_package(&db, "author/pkg/1.0.0")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(record.enabled);
+
+        let err = service
+            .disable_plugin("nonexistent/pkg/1.0.0")
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        let err = service.enable_plugin(" ").await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_list_plugin_updates_returns_recorded_updates() {
+        let db = setup_db().await.expect("db setup failed");
+        let service = MyPluginService::new(db.clone());
+
+        for id in ["author/pkg/1.0.0", "author/other/1.0.0"] {
+            database::ext_plugin::create_ext_plugin_package(
+                &db,
+                id.to_string(),
+                format!("/tmp/{id}"),
+            )
+            .await
+            .expect("create should succeed");
+        }
+        database::ext_plugin::set_ext_plugin_available_version(
+            &db,
+            "author/pkg/1.0.0",
+            Some("1.1.0".to_string()),
+        )
+        .await
+        .expect("update should succeed");
+
+        let updates = service
+            .list_plugin_updates(false)
+            .await
+            .expect("list should succeed");
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].plugin_package_id, "author/pkg/1.0.0");
+        assert_eq!(updates[0].available_version.as_deref(), Some("1.1.0"));
+    }
 }
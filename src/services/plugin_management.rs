@@ -0,0 +1,265 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// RPCs on plugins beyond those of PluginService
+
+use sapphillon_core::proto::sapphillon::v1::{
+    FunctionParameter as CoreFunctionParameter, PluginFunction as CorePluginFunction,
+};
+use tonic::{Request, Response, Status};
+
+use super::plugin::MyPluginService;
+use crate::args::TokenScope;
+use crate::auth::require_scope;
+use crate::proto::permission;
+use crate::proto::sapphillon::server::v1::plugin_management_service_server::PluginManagementService;
+use crate::proto::sapphillon::server::v1::{
+    CheckPluginsRequest, CheckPluginsResponse, DisablePluginRequest, DisablePluginResponse,
+    EnablePluginRequest, EnablePluginResponse, FunctionParameter, GetPluginFunctionRequest,
+    GetPluginFunctionResponse, GetPluginRequest, GetPluginResponse, InstallPluginFromStoreRequest,
+    InstallPluginFromStoreResponse, ListPluginUpdatesRequest, ListPluginUpdatesResponse,
+    PluginDiagnostic, PluginFunction, PluginPackage, PluginUpdate, ReloadPluginsRequest,
+    ReloadPluginsResponse, SearchPluginStoreRequest, SearchPluginStoreResponse, StorePackage,
+    UpdatePluginRequest, UpdatePluginResponse, ValidatePluginRequest, ValidatePluginResponse,
+};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+fn parameters_message(parameters: Vec<CoreFunctionParameter>) -> Vec<FunctionParameter> {
+    parameters
+        .into_iter()
+        .map(|parameter| FunctionParameter {
+            name: parameter.name,
+            r#type: parameter.r#type,
+            description: parameter.description,
+        })
+        .collect()
+}
+
+fn function_message(function: CorePluginFunction) -> PluginFunction {
+    let define = function.function_define.unwrap_or_default();
+    PluginFunction {
+        function_id: function.function_id,
+        function_name: function.function_name,
+        description: function.description,
+        parameters: parameters_message(define.parameters),
+        returns: parameters_message(define.returns),
+        permissions: function.permissions.into_iter().map(permission).collect(),
+    }
+}
+
+#[tonic::async_trait]
+impl PluginManagementService for MyPluginService {
+    async fn get_plugin(
+        &self,
+        request: Request<GetPluginRequest>,
+    ) -> Result<Response<GetPluginResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let req = request.into_inner();
+        let package = MyPluginService::get_plugin(self, &req.package_id).await?;
+
+        Ok(Response::new(GetPluginResponse {
+            plugin: Some(PluginPackage {
+                package_id: package.package_id,
+                package_name: package.package_name,
+                package_version: package.package_version,
+                description: package.description,
+                functions: package
+                    .functions
+                    .into_iter()
+                    .map(function_message)
+                    .collect(),
+            }),
+        }))
+    }
+
+    async fn get_plugin_function(
+        &self,
+        request: Request<GetPluginFunctionRequest>,
+    ) -> Result<Response<GetPluginFunctionResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let req = request.into_inner();
+        let function = MyPluginService::get_plugin_function(self, &req.function_id).await?;
+
+        Ok(Response::new(GetPluginFunctionResponse {
+            function: Some(function_message(function)),
+        }))
+    }
+
+    async fn validate_plugin(
+        &self,
+        request: Request<ValidatePluginRequest>,
+    ) -> Result<Response<ValidatePluginResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+        let inspection = MyPluginService::validate_plugin(self, &req.uri).await?;
+        let (metadata, manifest) = (inspection.metadata, inspection.manifest);
+
+        Ok(Response::new(ValidatePluginResponse {
+            package_id: manifest.package_id,
+            name: manifest.name,
+            version: manifest.version,
+            description: manifest.description,
+            functions: manifest.functions,
+            install_path: format!(
+                "{}/{}/{}",
+                metadata.author_id, metadata.package_id, metadata.version
+            ),
+        }))
+    }
+
+    async fn enable_plugin(
+        &self,
+        request: Request<EnablePluginRequest>,
+    ) -> Result<Response<EnablePluginResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+        MyPluginService::enable_plugin(self, &req.plugin_package_id).await?;
+        Ok(Response::new(EnablePluginResponse {}))
+    }
+
+    async fn disable_plugin(
+        &self,
+        request: Request<DisablePluginRequest>,
+    ) -> Result<Response<DisablePluginResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+        MyPluginService::disable_plugin(self, &req.plugin_package_id).await?;
+        Ok(Response::new(DisablePluginResponse {}))
+    }
+
+    async fn search_plugin_store(
+        &self,
+        request: Request<SearchPluginStoreRequest>,
+    ) -> Result<Response<SearchPluginStoreResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let req = request.into_inner();
+        let tag = Some(req.tag.trim()).filter(|tag| !tag.is_empty());
+        let packages = MyPluginService::search_plugin_store(self, &req.query, tag)
+            .await?
+            .into_iter()
+            .map(|package| StorePackage {
+                package_id: package.package_id,
+                name: package.name,
+                author_id: package.author_id,
+                version: package.version,
+                description: package.description,
+                tags: package.tags,
+            })
+            .collect();
+
+        Ok(Response::new(SearchPluginStoreResponse { packages }))
+    }
+
+    async fn install_plugin_from_store(
+        &self,
+        request: Request<InstallPluginFromStoreRequest>,
+    ) -> Result<Response<InstallPluginFromStoreResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+        let version = Some(req.version.trim()).filter(|version| !version.is_empty());
+        let plugin_package_id = self.install_from_store(&req.package_id, version).await?;
+
+        Ok(Response::new(InstallPluginFromStoreResponse {
+            plugin_package_id,
+        }))
+    }
+
+    async fn list_plugin_updates(
+        &self,
+        request: Request<ListPluginUpdatesRequest>,
+    ) -> Result<Response<ListPluginUpdatesResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let req = request.into_inner();
+        let updates = MyPluginService::list_plugin_updates(self, req.check)
+            .await?
+            .into_iter()
+            .map(|plugin| PluginUpdate {
+                plugin_package_id: plugin.plugin_package_id,
+                available_version: plugin.available_version.unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(Response::new(ListPluginUpdatesResponse { updates }))
+    }
+
+    async fn update_plugin(
+        &self,
+        request: Request<UpdatePluginRequest>,
+    ) -> Result<Response<UpdatePluginResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let req = request.into_inner();
+        let plugin_package_id =
+            MyPluginService::update_plugin(self, &req.plugin_package_id).await?;
+
+        Ok(Response::new(UpdatePluginResponse { plugin_package_id }))
+    }
+
+    async fn reload_plugins(
+        &self,
+        request: Request<ReloadPluginsRequest>,
+    ) -> Result<Response<ReloadPluginsResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let plugin_package_ids = MyPluginService::reload_plugins(self).await?;
+        Ok(Response::new(ReloadPluginsResponse { plugin_package_ids }))
+    }
+
+    async fn check_plugins(
+        &self,
+        request: Request<CheckPluginsRequest>,
+    ) -> Result<Response<CheckPluginsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let diagnostics = MyPluginService::check_plugins(self)
+            .await?
+            .into_iter()
+            .map(|diagnostic| PluginDiagnostic {
+                package_id: diagnostic.package_id,
+                function_id: diagnostic.function_id.unwrap_or_default(),
+                message: diagnostic.message,
+            })
+            .collect();
+
+        Ok(Response::new(CheckPluginsResponse { diagnostics }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthIdentity;
+
+    fn read_only<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuthIdentity {
+            subject: "alice".to_string(),
+            scopes: vec![TokenScope::Read],
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn changing_installed_plugins_needs_the_admin_scope() {
+        let db = sea_orm::Database::connect("sqlite::memory:?cache=shared")
+            .await
+            .expect("connect sqlite memory db");
+        let service = MyPluginService::new(db);
+
+        let status = PluginManagementService::disable_plugin(
+            &service,
+            read_only(DisablePluginRequest {
+                plugin_package_id: "author/pkg/1.0.0".to_string(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        let status =
+            PluginManagementService::reload_plugins(&service, read_only(ReloadPluginsRequest {}))
+                .await
+                .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+}
@@ -0,0 +1,239 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Questions asked by running workflows
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use database::prompt::{answer_prompt, get_prompt, list_prompts};
+use database::user::{is_changeable_by, is_visible_to};
+use database::workflow::get_workflow_owner;
+use entity::entity::workflow_prompt::Model as WorkflowPrompt;
+use sea_orm::{DatabaseConnection, DbErr};
+use tonic::{Request, Response, Status};
+
+use crate::args::TokenScope;
+use crate::auth::{request_may_change_shared, request_owner, require_scope};
+use crate::prompt_handler::parse_answer;
+use crate::proto::sapphillon::server::v1::prompt_service_server::PromptService;
+use crate::proto::sapphillon::server::v1::{
+    AnswerPromptRequest, AnswerPromptResponse, ListPromptsRequest, ListPromptsResponse, Prompt,
+};
+use crate::proto::timestamp;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Clone, Debug)]
+pub struct MyPromptService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl MyPromptService {
+    /// Creates a new prompt service backed by the provided database connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    fn map_db_error(err: DbErr) -> Status {
+        error!("database operation failed: {err:?}");
+        Status::internal("database operation failed")
+    }
+}
+
+fn prompt_message(prompt: WorkflowPrompt) -> Prompt {
+    Prompt {
+        id: prompt.id,
+        workflow_id: prompt.workflow_id,
+        kind: prompt.kind,
+        message: prompt.message,
+        status: prompt.status,
+        answer: prompt.answer.unwrap_or_default(),
+        created_at: prompt.created_at.map(timestamp),
+        answered_at: prompt.answered_at.map(timestamp),
+    }
+}
+
+#[tonic::async_trait]
+impl PromptService for MyPromptService {
+    async fn list_prompts(
+        &self,
+        request: Request<ListPromptsRequest>,
+    ) -> Result<Response<ListPromptsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+
+        let mut workflow_owners = HashMap::new();
+        let mut prompts = Vec::new();
+        for prompt in list_prompts(&self.db, !req.all)
+            .await
+            .map_err(Self::map_db_error)?
+        {
+            if !workflow_owners.contains_key(&prompt.workflow_id) {
+                let row_owner = get_workflow_owner(&self.db, &prompt.workflow_id)
+                    .await
+                    .map_err(Self::map_db_error)?;
+                workflow_owners.insert(prompt.workflow_id.clone(), row_owner);
+            }
+            if is_visible_to(
+                workflow_owners[&prompt.workflow_id].as_deref(),
+                owner.as_deref(),
+            ) {
+                prompts.push(prompt_message(prompt));
+            }
+        }
+
+        Ok(Response::new(ListPromptsResponse { prompts }))
+    }
+
+    async fn answer_prompt(
+        &self,
+        request: Request<AnswerPromptRequest>,
+    ) -> Result<Response<AnswerPromptResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found = || Status::not_found(format!("prompt '{}'", req.prompt_id));
+
+        let prompt = get_prompt(&self.db, &req.prompt_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        let row_owner = get_workflow_owner(&self.db, &prompt.workflow_id)
+            .await
+            .map_err(|_| not_found())?;
+        if !is_visible_to(row_owner.as_deref(), owner.as_deref()) {
+            return Err(not_found());
+        }
+        if !is_changeable_by(row_owner.as_deref(), owner.as_deref(), may_change_shared) {
+            return Err(Status::permission_denied(format!(
+                "prompts of shared workflow '{}' are answered by admins",
+                prompt.workflow_id
+            )));
+        }
+        let answer = parse_answer(&prompt.kind, &req.answer)
+            .map_err(|err| Status::invalid_argument(format!("{err:#}")))?;
+        if answer_prompt(&self.db, &prompt.id, &answer.to_string())
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(Status::failed_precondition(format!(
+                "prompt is no longer pending: {}",
+                prompt.id
+            )));
+        }
+        info!("Answered prompt: {}", prompt.id);
+
+        let prompt = get_prompt(&self.db, &prompt.id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        Ok(Response::new(AnswerPromptResponse {
+            prompt: Some(prompt_message(prompt)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthIdentity;
+    use database::prompt::{PROMPT_STATUS_ANSWERED, create_prompt};
+    use entity::entity::workflow;
+    use migration::MigratorTrait;
+    use sea_orm::{ActiveModelTrait, IntoActiveModel};
+
+    async fn setup_service() -> MyPromptService {
+        let conn = sea_orm::Database::connect("sqlite::memory:?cache=shared")
+            .await
+            .expect("connect sqlite memory db");
+        migration::Migrator::up(&conn, None)
+            .await
+            .expect("apply migrations");
+        for (id, owner_id) in [("wf-alice", Some("alice")), ("wf-bob", Some("bob"))] {
+            workflow::Model {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                description: None,
+                workflow_language: 0,
+                created_at: None,
+                updated_at: None,
+                version: 1,
+                owner_id: owner_id.map(str::to_string),
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow");
+        }
+        MyPromptService::new(conn)
+    }
+
+    fn request_as<T>(subject: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuthIdentity {
+            subject: subject.to_string(),
+            scopes: vec![TokenScope::Read, TokenScope::Write],
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn prompts_are_limited_to_the_owner_of_their_workflow() {
+        let service = setup_service().await;
+        let prompt = create_prompt(&service.db, "wf-alice", "confirm", "Delete 200 files?")
+            .await
+            .expect("create prompt");
+
+        let listed = |subject: &str| {
+            service.list_prompts(request_as(subject, ListPromptsRequest::default()))
+        };
+        let prompts = listed("alice").await.unwrap().into_inner().prompts;
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].message, "Delete 200 files?");
+        assert!(listed("bob").await.unwrap().into_inner().prompts.is_empty());
+
+        let answer = |subject: &str, answer: &str| {
+            service.answer_prompt(request_as(
+                subject,
+                AnswerPromptRequest {
+                    prompt_id: prompt.id.clone(),
+                    answer: answer.to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            answer("bob", "yes").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        assert_eq!(
+            answer("alice", "maybe").await.unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+        let answered = answer("alice", "yes")
+            .await
+            .expect("answer prompt")
+            .into_inner()
+            .prompt
+            .expect("prompt in response");
+        assert_eq!(answered.status, PROMPT_STATUS_ANSWERED);
+        assert_eq!(answered.answer, "true");
+        assert_eq!(
+            answer("alice", "no").await.unwrap_err().code(),
+            tonic::Code::FailedPrecondition
+        );
+        assert!(
+            listed("alice")
+                .await
+                .unwrap()
+                .into_inner()
+                .prompts
+                .is_empty()
+        );
+    }
+}
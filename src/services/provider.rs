@@ -13,6 +13,10 @@ use log::{debug, error, info};
 use sea_orm::{DatabaseConnection, DbErr};
 use tonic::{Request, Response, Status};
 
+use crate::args::TokenScope;
+use crate::auth::require_scope;
+use crate::services::validation::validate;
+
 use database::provider as provider_db;
 use sapphillon_core::proto::google::rpc::{Code as RpcCode, Status as RpcStatus};
 use sapphillon_core::proto::sapphillon::ai::v1::provider_service_server::ProviderService;
@@ -102,27 +106,13 @@ impl ProviderService for MyProviderService {
         &self,
         request: Request<CreateProviderRequest>,
     ) -> Result<Response<CreateProviderResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        validate(&request)?;
         let req = request.into_inner();
         let incoming = req
             .provider
             .ok_or_else(|| Status::invalid_argument("provider field is required"))?;
 
-        if incoming.display_name.trim().is_empty() {
-            return Err(Status::invalid_argument(
-                "provider.display_name must not be empty",
-            ));
-        }
-        if incoming.api_key.trim().is_empty() {
-            return Err(Status::invalid_argument(
-                "provider.api_key must not be empty",
-            ));
-        }
-        if incoming.api_endpoint.trim().is_empty() {
-            return Err(Status::invalid_argument(
-                "provider.api_endpoint must not be empty",
-            ));
-        }
-
         let has_custom_name = !incoming.name.trim().is_empty();
         let has_api_key = !incoming.api_key.trim().is_empty();
         let api_endpoint = incoming.api_endpoint.trim().to_string();
@@ -174,10 +164,9 @@ impl ProviderService for MyProviderService {
         &self,
         request: Request<GetProviderRequest>,
     ) -> Result<Response<GetProviderResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
         let req = request.into_inner();
-        if req.name.trim().is_empty() {
-            return Err(Status::invalid_argument("name must not be empty"));
-        }
 
         debug!(
             "get_provider request received: provider_name={provider_name}",
@@ -215,6 +204,8 @@ impl ProviderService for MyProviderService {
         &self,
         request: Request<ListProvidersRequest>,
     ) -> Result<Response<ListProvidersResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
         let req = request.into_inner();
 
         debug!(
@@ -266,10 +257,9 @@ impl ProviderService for MyProviderService {
         &self,
         request: Request<DeleteProviderRequest>,
     ) -> Result<Response<DeleteProviderResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        validate(&request)?;
         let req = request.into_inner();
-        if req.name.trim().is_empty() {
-            return Err(Status::invalid_argument("name must not be empty"));
-        }
 
         info!(
             "delete_provider request received: provider_name={provider_name}",
@@ -310,15 +300,13 @@ impl ProviderService for MyProviderService {
         &self,
         request: Request<UpdateProviderRequest>,
     ) -> Result<Response<UpdateProviderResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        validate(&request)?;
         let req = request.into_inner();
         let incoming = req
             .provider
             .ok_or_else(|| Status::invalid_argument("provider field is required"))?;
 
-        if incoming.name.trim().is_empty() {
-            return Err(Status::invalid_argument("provider.name must not be empty"));
-        }
-
         let mut existing = provider_db::get_provider(&self.db, &incoming.name)
             .await
             .map_err(Self::map_db_error)?
@@ -334,27 +322,12 @@ impl ProviderService for MyProviderService {
         );
 
         if update_all || mask_paths.iter().any(|path| path == "display_name") {
-            if incoming.display_name.trim().is_empty() {
-                return Err(Status::invalid_argument(
-                    "provider.display_name must not be empty",
-                ));
-            }
             existing.display_name = incoming.display_name.clone();
         }
         if update_all || mask_paths.iter().any(|path| path == "api_key") {
-            if incoming.api_key.trim().is_empty() {
-                return Err(Status::invalid_argument(
-                    "provider.api_key must not be empty",
-                ));
-            }
             existing.api_key = incoming.api_key.clone();
         }
         if update_all || mask_paths.iter().any(|path| path == "api_endpoint") {
-            if incoming.api_endpoint.trim().is_empty() {
-                return Err(Status::invalid_argument(
-                    "provider.api_endpoint must not be empty",
-                ));
-            }
             existing.api_endpoint = incoming.api_endpoint.clone();
         }
 
@@ -380,6 +353,7 @@ impl ProviderService for MyProviderService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::local_request;
     use migration::MigratorTrait;
     use sapphillon_core::proto::google::protobuf::FieldMask;
 
@@ -416,7 +390,7 @@ mod tests {
     async fn create_and_get_provider_roundtrip() {
         let service = setup_service().await;
 
-        let create_req = Request::new(CreateProviderRequest {
+        let create_req = local_request(CreateProviderRequest {
             provider: Some(Provider {
                 name: String::new(),
                 display_name: "Test Provider".to_string(),
@@ -436,7 +410,7 @@ mod tests {
         assert!(provider.api_key.is_empty(), "api_key should be sanitized");
 
         let fetched = service
-            .get_provider(Request::new(GetProviderRequest {
+            .get_provider(local_request(GetProviderRequest {
                 name: provider.name.clone(),
                 status: None,
             }))
@@ -463,7 +437,7 @@ mod tests {
     async fn update_and_list_providers() {
         let service = setup_service().await;
 
-        let create_req = Request::new(CreateProviderRequest {
+        let create_req = local_request(CreateProviderRequest {
             provider: Some(Provider {
                 name: String::new(),
                 display_name: "Initial".to_string(),
@@ -479,7 +453,7 @@ mod tests {
             .into_inner();
         let created = create_resp.provider.expect("provider");
 
-        let update_req = Request::new(UpdateProviderRequest {
+        let update_req = local_request(UpdateProviderRequest {
             provider: Some(Provider {
                 name: created.name.clone(),
                 display_name: "Updated".to_string(),
@@ -501,7 +475,7 @@ mod tests {
         assert_eq!(updated.api_endpoint, "https://updated.test");
 
         let list_resp = service
-            .list_providers(Request::new(ListProvidersRequest {
+            .list_providers(local_request(ListProvidersRequest {
                 page_size: 10,
                 page_token: String::new(),
             }))
@@ -516,14 +490,14 @@ mod tests {
         assert!(list_resp.next_page_token.is_empty());
 
         service
-            .delete_provider(Request::new(DeleteProviderRequest {
+            .delete_provider(local_request(DeleteProviderRequest {
                 name: created.name.clone(),
             }))
             .await
             .expect("delete");
 
         let err = service
-            .get_provider(Request::new(GetProviderRequest {
+            .get_provider(local_request(GetProviderRequest {
                 name: created.name.clone(),
                 status: None,
             }))
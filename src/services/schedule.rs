@@ -0,0 +1,364 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Schedules that run workflows from cron expressions
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use database::schedule::{delete_schedule, get_schedule, list_schedules};
+use database::user::{is_changeable_by, is_visible_to};
+use database::workflow::get_workflow_owner;
+use entity::entity::workflow_schedule::Model as WorkflowSchedule;
+use sea_orm::{DatabaseConnection, DbErr};
+use tonic::{Request, Response, Status};
+
+use crate::args::TokenScope;
+use crate::auth::{request_may_change_shared, request_owner, require_scope};
+use crate::proto::sapphillon::server::v1::schedule_service_server::ScheduleService;
+use crate::proto::sapphillon::server::v1::{
+    CreateScheduleRequest, CreateScheduleResponse, DeleteScheduleRequest, DeleteScheduleResponse,
+    ListSchedulesRequest, ListSchedulesResponse, Schedule,
+};
+use crate::proto::timestamp;
+use crate::scheduler::{create_schedule, next_run_after};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Clone, Debug)]
+pub struct MyScheduleService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl MyScheduleService {
+    /// Creates a new schedule service backed by the provided database connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    fn map_db_error(err: DbErr) -> Status {
+        error!("database operation failed: {err:?}");
+        Status::internal("database operation failed")
+    }
+
+    /// Checks that the workflow exists and that `owner` may use it, reporting the
+    /// workflows of other users as not found.
+    async fn authorize_workflow(
+        &self,
+        workflow_id: &str,
+        owner: Option<&str>,
+    ) -> Result<(), Status> {
+        match get_workflow_owner(&self.db, workflow_id).await {
+            Ok(row_owner) if is_visible_to(row_owner.as_deref(), owner) => Ok(()),
+            Ok(_) | Err(DbErr::RecordNotFound(_)) => {
+                Err(Status::not_found(format!("workflow '{workflow_id}'")))
+            }
+            Err(err) => Err(Self::map_db_error(err)),
+        }
+    }
+
+    /// Like [`Self::authorize_workflow`] for requests that change the workflow, which
+    /// callers may only do to shared workflows with `may_change_shared`.
+    async fn authorize_workflow_change(
+        &self,
+        workflow_id: &str,
+        owner: Option<&str>,
+        may_change_shared: bool,
+    ) -> Result<(), Status> {
+        match get_workflow_owner(&self.db, workflow_id).await {
+            Ok(row_owner) if !is_visible_to(row_owner.as_deref(), owner) => {
+                Err(Status::not_found(format!("workflow '{workflow_id}'")))
+            }
+            Ok(row_owner) if is_changeable_by(row_owner.as_deref(), owner, may_change_shared) => {
+                Ok(())
+            }
+            Ok(_) => Err(Status::permission_denied(format!(
+                "workflow '{workflow_id}' is shared and only admins may change it"
+            ))),
+            Err(DbErr::RecordNotFound(_)) => {
+                Err(Status::not_found(format!("workflow '{workflow_id}'")))
+            }
+            Err(err) => Err(Self::map_db_error(err)),
+        }
+    }
+}
+
+fn schedule_message(schedule: WorkflowSchedule) -> Schedule {
+    Schedule {
+        id: schedule.id,
+        workflow_id: schedule.workflow_id,
+        cron: schedule.cron,
+        enabled: schedule.enabled,
+        next_run_at: schedule.next_run_at.map(timestamp),
+        last_run_at: schedule.last_run_at.map(timestamp),
+        created_at: schedule.created_at.map(timestamp),
+    }
+}
+
+#[tonic::async_trait]
+impl ScheduleService for MyScheduleService {
+    async fn create_schedule(
+        &self,
+        request: Request<CreateScheduleRequest>,
+    ) -> Result<Response<CreateScheduleResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        debug!(
+            "create_schedule request received: workflow_id={}, cron={:?}",
+            req.workflow_id, req.cron
+        );
+
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+        next_run_after(&req.cron, Utc::now())
+            .map_err(|err| Status::invalid_argument(format!("{err:#}")))?;
+        let schedule = create_schedule(&self.db, &req.workflow_id, &req.cron)
+            .await
+            .map_err(|err| {
+                error!("failed to create schedule: {err:#}");
+                Status::internal("failed to create schedule")
+            })?;
+        info!(
+            "Created schedule {} for workflow {}",
+            schedule.id, schedule.workflow_id
+        );
+
+        Ok(Response::new(CreateScheduleResponse {
+            schedule: Some(schedule_message(schedule)),
+        }))
+    }
+
+    async fn list_schedules(
+        &self,
+        request: Request<ListSchedulesRequest>,
+    ) -> Result<Response<ListSchedulesResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        let workflow_id = Some(req.workflow_id.as_str()).filter(|id| !id.is_empty());
+
+        let schedules = list_schedules(&self.db, workflow_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .filter(|schedule| is_visible_to(schedule.owner_id.as_deref(), owner.as_deref()))
+            .map(schedule_message)
+            .collect();
+
+        Ok(Response::new(ListSchedulesResponse { schedules }))
+    }
+
+    async fn delete_schedule(
+        &self,
+        request: Request<DeleteScheduleRequest>,
+    ) -> Result<Response<DeleteScheduleResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found = || Status::not_found(format!("schedule '{}'", req.schedule_id));
+
+        let schedule = get_schedule(&self.db, &req.schedule_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .filter(|schedule| is_visible_to(schedule.owner_id.as_deref(), owner.as_deref()))
+            .ok_or_else(not_found)?;
+        if !is_changeable_by(
+            schedule.owner_id.as_deref(),
+            owner.as_deref(),
+            may_change_shared,
+        ) {
+            return Err(Status::permission_denied(format!(
+                "schedule '{}' is shared and only admins may delete it",
+                schedule.id
+            )));
+        }
+        if delete_schedule(&self.db, &schedule.id)
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(not_found());
+        }
+        info!("Deleted schedule: {}", schedule.id);
+
+        Ok(Response::new(DeleteScheduleResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthIdentity;
+    use entity::entity::workflow;
+    use migration::MigratorTrait;
+    use sea_orm::{ActiveModelTrait, IntoActiveModel};
+
+    async fn setup_service() -> MyScheduleService {
+        let conn = sea_orm::Database::connect("sqlite::memory:?cache=shared")
+            .await
+            .expect("connect sqlite memory db");
+        migration::Migrator::up(&conn, None)
+            .await
+            .expect("apply migrations");
+        for (id, owner_id) in [
+            ("wf-alice", Some("alice")),
+            ("wf-bob", Some("bob")),
+            ("wf-shared", None),
+        ] {
+            workflow::Model {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                description: None,
+                workflow_language: 0,
+                created_at: None,
+                updated_at: None,
+                version: 1,
+                owner_id: owner_id.map(str::to_string),
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow");
+        }
+        MyScheduleService::new(conn)
+    }
+
+    fn request_as<T>(subject: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuthIdentity {
+            subject: subject.to_string(),
+            scopes: vec![TokenScope::Read, TokenScope::Write],
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn schedules_are_limited_to_their_owner() {
+        let service = setup_service().await;
+        let created = service
+            .create_schedule(request_as(
+                "alice",
+                CreateScheduleRequest {
+                    workflow_id: "wf-alice".to_string(),
+                    cron: "0 9 * * 1-5".to_string(),
+                },
+            ))
+            .await
+            .expect("create schedule")
+            .into_inner()
+            .schedule
+            .expect("schedule in response");
+        assert!(created.enabled);
+        assert!(created.next_run_at.is_some());
+
+        let status = service
+            .create_schedule(request_as(
+                "alice",
+                CreateScheduleRequest {
+                    workflow_id: "wf-bob".to_string(),
+                    cron: "0 9 * * *".to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let listed = |subject: &str| {
+            service.list_schedules(request_as(subject, ListSchedulesRequest::default()))
+        };
+        assert_eq!(
+            listed("alice").await.unwrap().into_inner().schedules,
+            vec![created.clone()]
+        );
+        assert!(
+            listed("bob")
+                .await
+                .unwrap()
+                .into_inner()
+                .schedules
+                .is_empty()
+        );
+
+        let delete = |subject: &str| {
+            service.delete_schedule(request_as(
+                subject,
+                DeleteScheduleRequest {
+                    schedule_id: created.id.clone(),
+                },
+            ))
+        };
+        assert_eq!(
+            delete("bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        delete("alice").await.expect("delete schedule");
+        assert!(
+            listed("alice")
+                .await
+                .unwrap()
+                .into_inner()
+                .schedules
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn shared_workflows_are_scheduled_only_by_admins() {
+        let service = setup_service().await;
+        let create = |request: Request<CreateScheduleRequest>| service.create_schedule(request);
+        let message = || CreateScheduleRequest {
+            workflow_id: "wf-shared".to_string(),
+            cron: "0 9 * * *".to_string(),
+        };
+        assert_eq!(
+            create(request_as("alice", message()))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+
+        let mut admin_request = request_as("admin", message());
+        admin_request
+            .extensions_mut()
+            .insert(AuthIdentity::with_all_scopes("admin"));
+        let created = create(admin_request)
+            .await
+            .expect("create schedule as admin")
+            .into_inner()
+            .schedule
+            .expect("schedule in response");
+
+        let status = service
+            .delete_schedule(request_as(
+                "alice",
+                DeleteScheduleRequest {
+                    schedule_id: created.id.clone(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn invalid_cron_expressions_are_rejected() {
+        let service = setup_service().await;
+        let status = service
+            .create_schedule(request_as(
+                "alice",
+                CreateScheduleRequest {
+                    workflow_id: "wf-alice".to_string(),
+                    cron: "every morning".to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
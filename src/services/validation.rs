@@ -0,0 +1,426 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+//! Request validation shared by the gRPC services.
+//!
+//! Each request type lists its constraints in [`Validate`], and every RPC checks its
+//! request with [`validate`] before acting on it. All violated constraints are reported
+//! together as a `google.rpc.BadRequest` detail of an `INVALID_ARGUMENT` status, so a
+//! client can point out each invalid field.
+
+use prost::Message;
+use sapphillon_core::proto::google::protobuf::Any;
+use sapphillon_core::proto::google::rpc::bad_request::FieldViolation;
+use sapphillon_core::proto::google::rpc::{BadRequest, Code as RpcCode, Status as RpcStatus};
+use sapphillon_core::proto::sapphillon::ai::v1::{
+    CreateModelRequest, CreateProviderRequest, DeleteModelRequest, DeleteProviderRequest,
+    GetModelRequest, GetProviderRequest, ListModelsRequest, ListProvidersRequest,
+    UpdateModelRequest, UpdateProviderRequest,
+};
+use sapphillon_core::proto::sapphillon::v1::{
+    DeleteWorkflowRequest, FixWorkflowRequest, GenerateWorkflowRequest, GetWorkflowRequest,
+    InstallPluginRequest, ListPluginsRequest, ListWorkflowsRequest, RunWorkflowRequest,
+    UninstallPluginRequest, UpdateWorkflowRequest,
+};
+use tonic::{Code, Request, Status};
+
+/// Longest accepted resource name or ID, in bytes.
+pub(crate) const MAX_ID_BYTES: usize = 256;
+/// Largest accepted `page_size`; 0 selects the service default.
+pub(crate) const MAX_PAGE_SIZE: i32 = 1000;
+/// Largest accepted workflow code, in bytes.
+pub(crate) const MAX_CODE_BYTES: usize = 1024 * 1024;
+/// Largest accepted prompt or description sent to a language model, in bytes.
+pub(crate) const MAX_PROMPT_BYTES: usize = 64 * 1024;
+
+const BAD_REQUEST_TYPE_URL: &str = "type.googleapis.com/google.rpc.BadRequest";
+
+/// The constraints a request must satisfy.
+pub(crate) trait Validate {
+    /// Records every constraint the request violates in `violations`.
+    fn validate(&self, violations: &mut Violations);
+}
+
+/// Rejects `request` with `INVALID_ARGUMENT` unless it satisfies its constraints.
+pub(crate) fn validate<T: Validate>(request: &Request<T>) -> Result<(), Status> {
+    let mut violations = Violations::default();
+    request.get_ref().validate(&mut violations);
+    violations.into_result()
+}
+
+/// The `INVALID_ARGUMENT` status of an invalid request, for the RPCs that report errors in
+/// the response body instead of failing.
+pub(crate) fn violation_status<T: Validate>(request: &Request<T>) -> Option<RpcStatus> {
+    let mut violations = Violations::default();
+    request.get_ref().validate(&mut violations);
+    violations.into_rpc_status()
+}
+
+/// The invalid fields of a request, by proto field path such as `workflow.id`.
+#[derive(Debug, Default)]
+pub(crate) struct Violations {
+    violations: Vec<FieldViolation>,
+}
+
+impl Violations {
+    pub(crate) fn add(&mut self, field: &str, description: impl Into<String>) {
+        self.violations.push(FieldViolation {
+            field: field.to_string(),
+            description: description.into(),
+            ..Default::default()
+        });
+    }
+
+    /// Requires a message field to be set and returns it.
+    pub(crate) fn message<'a, T>(&mut self, field: &str, value: &'a Option<T>) -> Option<&'a T> {
+        if value.is_none() {
+            self.add(field, "is required");
+        }
+        value.as_ref()
+    }
+
+    /// Requires a non-blank string of at most `max_bytes`.
+    pub(crate) fn required(&mut self, field: &str, value: &str, max_bytes: usize) {
+        if value.trim().is_empty() {
+            self.add(field, "is required");
+        } else {
+            self.max_bytes(field, value, max_bytes);
+        }
+    }
+
+    /// Requires a non-blank resource name or ID.
+    pub(crate) fn id(&mut self, field: &str, value: &str) {
+        self.required(field, value, MAX_ID_BYTES);
+    }
+
+    pub(crate) fn max_bytes(&mut self, field: &str, value: &str, max_bytes: usize) {
+        if value.len() > max_bytes {
+            self.add(field, format!("must be at most {max_bytes} bytes"));
+        }
+    }
+
+    pub(crate) fn page_size(&mut self, field: &str, value: i32) {
+        if !(0..=MAX_PAGE_SIZE).contains(&value) {
+            self.add(field, format!("must be between 0 and {MAX_PAGE_SIZE}"));
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn message_text(&self) -> String {
+        let fields: Vec<String> = self
+            .violations
+            .iter()
+            .map(|violation| format!("{} {}", violation.field, violation.description))
+            .collect();
+        fields.join("; ")
+    }
+
+    fn bad_request(self) -> Any {
+        Any {
+            type_url: BAD_REQUEST_TYPE_URL.to_string(),
+            value: BadRequest {
+                field_violations: self.violations,
+            }
+            .encode_to_vec(),
+        }
+    }
+
+    /// Converts the violations into an `INVALID_ARGUMENT` status, if there are any.
+    pub(crate) fn into_result(self) -> Result<(), Status> {
+        match self.into_rpc_status() {
+            None => Ok(()),
+            Some(status) => Err(Status::with_details(
+                Code::InvalidArgument,
+                status.message.clone(),
+                status.encode_to_vec().into(),
+            )),
+        }
+    }
+
+    /// Converts the violations into a `google.rpc.Status`, if there are any.
+    pub(crate) fn into_rpc_status(self) -> Option<RpcStatus> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(RpcStatus {
+            code: RpcCode::InvalidArgument as i32,
+            message: self.message_text(),
+            details: vec![self.bad_request()],
+        })
+    }
+}
+
+/// Whether an update with `mask_paths` writes `path`; an empty mask writes every field.
+fn updates(mask_paths: &[String], path: &str) -> bool {
+    mask_paths.is_empty() || mask_paths.iter().any(|mask_path| mask_path == path)
+}
+
+impl Validate for CreateModelRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if let Some(model) = violations.message("model", &self.model) {
+            violations.max_bytes("model.name", &model.name, MAX_ID_BYTES);
+            violations.id("model.display_name", &model.display_name);
+            violations.id("model.provider_name", &model.provider_name);
+        }
+    }
+}
+
+impl Validate for GetModelRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("name", &self.name);
+    }
+}
+
+impl Validate for ListModelsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.page_size("page_size", self.page_size);
+    }
+}
+
+impl Validate for DeleteModelRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("name", &self.name);
+    }
+}
+
+impl Validate for UpdateModelRequest {
+    fn validate(&self, violations: &mut Violations) {
+        let mask_paths = self
+            .update_mask
+            .as_ref()
+            .map(|mask| mask.paths.as_slice())
+            .unwrap_or_default();
+        if let Some(model) = violations.message("model", &self.model) {
+            violations.id("model.name", &model.name);
+            if updates(mask_paths, "display_name") {
+                violations.id("model.display_name", &model.display_name);
+            }
+            if updates(mask_paths, "provider_name") {
+                violations.id("model.provider_name", &model.provider_name);
+            }
+        }
+    }
+}
+
+impl Validate for CreateProviderRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if let Some(provider) = violations.message("provider", &self.provider) {
+            violations.max_bytes("provider.name", &provider.name, MAX_ID_BYTES);
+            violations.id("provider.display_name", &provider.display_name);
+            violations.required("provider.api_key", &provider.api_key, MAX_PROMPT_BYTES);
+            violations.required(
+                "provider.api_endpoint",
+                &provider.api_endpoint,
+                MAX_PROMPT_BYTES,
+            );
+        }
+    }
+}
+
+impl Validate for GetProviderRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("name", &self.name);
+    }
+}
+
+impl Validate for ListProvidersRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.page_size("page_size", self.page_size);
+    }
+}
+
+impl Validate for DeleteProviderRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("name", &self.name);
+    }
+}
+
+impl Validate for UpdateProviderRequest {
+    fn validate(&self, violations: &mut Violations) {
+        let mask_paths = self
+            .update_mask
+            .as_ref()
+            .map(|mask| mask.paths.as_slice())
+            .unwrap_or_default();
+        if let Some(provider) = violations.message("provider", &self.provider) {
+            violations.id("provider.name", &provider.name);
+            if updates(mask_paths, "display_name") {
+                violations.id("provider.display_name", &provider.display_name);
+            }
+            if updates(mask_paths, "api_key") {
+                violations.required("provider.api_key", &provider.api_key, MAX_PROMPT_BYTES);
+            }
+            if updates(mask_paths, "api_endpoint") {
+                violations.required(
+                    "provider.api_endpoint",
+                    &provider.api_endpoint,
+                    MAX_PROMPT_BYTES,
+                );
+            }
+        }
+    }
+}
+
+impl Validate for ListPluginsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.page_size("page_size", self.page_size);
+    }
+}
+
+impl Validate for InstallPluginRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.required("uri", &self.uri, MAX_PROMPT_BYTES);
+    }
+}
+
+impl Validate for UninstallPluginRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("package_id", &self.package_id);
+    }
+}
+
+impl Validate for UpdateWorkflowRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if let Some(workflow) = violations.message("workflow", &self.workflow) {
+            violations.id("workflow.id", &workflow.id);
+            violations.id("workflow.display_name", &workflow.display_name);
+            for (index, code) in workflow.workflow_code.iter().enumerate() {
+                violations.max_bytes(
+                    &format!("workflow.workflow_code[{index}].code"),
+                    &code.code,
+                    MAX_CODE_BYTES,
+                );
+            }
+        }
+    }
+}
+
+impl Validate for DeleteWorkflowRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("workflow_id", &self.workflow_id);
+    }
+}
+
+impl Validate for ListWorkflowsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.page_size("page_size", self.page_size);
+    }
+}
+
+impl Validate for FixWorkflowRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.required(
+            "workflow_definition",
+            &self.workflow_definition,
+            MAX_CODE_BYTES,
+        );
+        violations.required("description", &self.description, MAX_PROMPT_BYTES);
+    }
+}
+
+impl Validate for GetWorkflowRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("workflow_id", &self.workflow_id);
+    }
+}
+
+impl Validate for GenerateWorkflowRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.required("prompt", &self.prompt, MAX_PROMPT_BYTES);
+    }
+}
+
+impl Validate for RunWorkflowRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if let Some(by_id) = violations.message("by_id", &self.by_id) {
+            violations.id("by_id.workflow_id", &by_id.workflow_id);
+            violations.id("by_id.workflow_code_id", &by_id.workflow_code_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::proto::sapphillon::ai::v1::Provider;
+    use sapphillon_core::proto::sapphillon::v1::Workflow;
+
+    fn bad_request(status: &Status) -> BadRequest {
+        let rpc_status = RpcStatus::decode(status.details()).unwrap();
+        assert_eq!(rpc_status.details.len(), 1);
+        assert_eq!(rpc_status.details[0].type_url, BAD_REQUEST_TYPE_URL);
+        BadRequest::decode(rpc_status.details[0].value.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn every_violation_is_reported() {
+        let request = Request::new(UpdateWorkflowRequest {
+            workflow: Some(Workflow {
+                id: " ".to_string(),
+                display_name: "x".repeat(MAX_ID_BYTES + 1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let status = validate(&request).unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "workflow.id is required; workflow.display_name must be at most 256 bytes"
+        );
+        let fields: Vec<String> = bad_request(&status)
+            .field_violations
+            .into_iter()
+            .map(|violation| violation.field)
+            .collect();
+        assert_eq!(fields, vec!["workflow.id", "workflow.display_name"]);
+    }
+
+    #[test]
+    fn valid_requests_pass() {
+        let request = Request::new(ListWorkflowsRequest {
+            page_size: MAX_PAGE_SIZE,
+            ..Default::default()
+        });
+        assert!(validate(&request).is_ok());
+
+        let request = Request::new(ListWorkflowsRequest {
+            page_size: -1,
+            ..Default::default()
+        });
+        let status = validate(&request).unwrap_err();
+        assert_eq!(bad_request(&status).field_violations[0].field, "page_size");
+    }
+
+    #[test]
+    fn update_masks_limit_the_checked_fields() {
+        let mut request = UpdateProviderRequest {
+            provider: Some(Provider {
+                name: "providers/openai".to_string(),
+                display_name: "OpenAI".to_string(),
+                ..Default::default()
+            }),
+            update_mask: Some(Default::default()),
+        };
+        // Without paths the mask writes every field, including the missing API key.
+        assert!(validate(&Request::new(request.clone())).is_err());
+        if let Some(mask) = request.update_mask.as_mut() {
+            mask.paths = vec!["display_name".to_string()];
+        }
+        assert!(validate(&Request::new(request)).is_ok());
+    }
+
+    #[test]
+    fn violations_convert_to_response_statuses() {
+        let mut violations = Violations::default();
+        UninstallPluginRequest::default().validate(&mut violations);
+        let status = violations.into_rpc_status().unwrap();
+        assert_eq!(status.code, RpcCode::InvalidArgument as i32);
+        assert_eq!(status.message, "package_id is required");
+        assert!(Violations::default().into_rpc_status().is_none());
+    }
+}
@@ -7,13 +7,30 @@
 //
 //
 
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use chrono::Utc;
-use database::workflow::{get_workflow_by_id, update_workflow_from_proto};
+use database::code_change::{create_code_change, list_code_changes};
+use database::denied_permission::list_denied_permissions;
+use database::organization;
+use database::permission_grant::{claim_permission_grant_use, list_active_permission_grants};
+use database::permission_profile::list_workflow_permission_profiles;
+use database::plugin::list_plugins;
+use database::search::{self, SearchHit};
+use database::user::{self, is_visible_to, visible_to};
+use database::workflow::workflow_result_crud::get_workflow_result;
+use database::workflow::{
+    create_workflow_from_proto, find_workflow_id_by_code, get_workflow_by_id, get_workflow_owner,
+    get_workflow_version, is_version_conflict, update_workflow_from_proto,
+    update_workflow_from_proto_at_version,
+};
 use entity::entity::workflow as workflow_entity;
+use entity::entity::{workflow_folder, workflow_tag};
 use log::{debug, error, info, warn};
+use plugin_permission::{DeniedPermission, LimitedGrant, PermissionUsage};
 use sapphillon_core::permission::{Permissions, PluginFunctionPermissions};
 use sapphillon_core::proto::google::protobuf::Timestamp;
 use sapphillon_core::proto::google::rpc::{Code as RpcCode, Status as RpcStatus};
@@ -21,29 +38,160 @@ use sapphillon_core::proto::sapphillon::v1::workflow_service_server::WorkflowSer
 use sapphillon_core::proto::sapphillon::v1::{
     AllowedPermission, DeleteWorkflowRequest, DeleteWorkflowResponse, FixWorkflowRequest,
     FixWorkflowResponse, GenerateWorkflowRequest, GenerateWorkflowResponse, GetWorkflowRequest,
-    GetWorkflowResponse, ListWorkflowsRequest, ListWorkflowsResponse, RunWorkflowRequest,
-    RunWorkflowResponse, UpdateWorkflowRequest, UpdateWorkflowResponse, Workflow, WorkflowCode,
-    WorkflowResult,
+    GetWorkflowResponse, ListWorkflowsRequest, ListWorkflowsResponse, Permission, PluginPackage,
+    RunWorkflowRequest, RunWorkflowResponse, UpdateWorkflowRequest, UpdateWorkflowResponse,
+    Workflow, WorkflowCode, WorkflowResult,
 };
 use sapphillon_core::workflow::CoreWorkflowCode;
-use sea_orm::{DatabaseConnection, DbErr, EntityTrait, QueryOrder, QuerySelect};
+use sea_orm::sea_query::LikeExpr;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait, Select,
+};
 use tokio::runtime::Handle;
 use tokio::sync::mpsc;
 use tokio_stream::Stream;
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataValue;
 use tonic::{Request, Response, Status};
 
-use crate::workflow::generate_workflow_async;
+use crate::args::TokenScope;
+use crate::auth::{request_may_change_shared, request_owner, require_scope};
+use crate::events;
+use crate::llm::{LlmProvider, MODEL_METADATA_KEY, provider_for_model};
+use crate::permission_profiles::{expand_profile_permissions, parse_profile_permissions};
+use crate::redaction::redact;
+use crate::services::validation::validate;
+use crate::triggers::trigger_prelude;
+use crate::workflow::{
+    WorkflowFailure, diagnose_workflow_failure, explain_workflow_code, format_tool_catalog,
+    generate_workflow_async, summarize_code_change,
+};
+use crate::workflow_dry_run::{
+    PlannedAction, instrument_dry_run, parse_dry_run, parse_dry_run_line,
+};
+use crate::workflow_modules::WorkflowBundle;
+use crate::workflow_organization::{OrganizedWorkflow, normalize_folder, normalize_tags};
+use crate::workflow_permissions::{
+    PermissionPreview, RiskAssessment, assess_risk, find_missing_permissions,
+    infer_required_permissions,
+};
+use crate::workflow_replay::{
+    RecordedCall, format_calls, instrument_recording, instrument_replay, parse_recording,
+};
+use crate::workflow_revisions::{CodeRevision, DiffHunk, describe_diff, diff_code, render_diff};
+use crate::workflow_steps::{
+    StepStatus, StepTracker, TrackedLine, WorkflowStep, instrument_steps, parse_steps,
+};
+use crate::workflow_typescript::{WORKFLOW_LANGUAGE_TS, transpile_typescript};
 
 /// Maximum number of characters to keep when deriving workflow display names from prompts.
 const MAX_DISPLAY_NAME_LEN: usize = 64;
 const DEFAULT_PAGE_SIZE: u64 = 100;
+/// Most hits a content search returns.
+const MAX_SEARCH_HITS: u64 = 100;
 const WORKFLOW_LANGUAGE_JS: i32 = 2;
+/// gRPC metadata key naming the stored workflow a `FixWorkflow` request fixes.
+const WORKFLOW_ID_METADATA_KEY: &str = "x-sapphillon-workflow-id";
+/// gRPC metadata key carrying a workflow's version: returned by `GetWorkflow` and
+/// `UpdateWorkflow`, and required on `UpdateWorkflow` to reject edits of a stale copy.
+const WORKFLOW_VERSION_METADATA_KEY: &str = "x-sapphillon-workflow-version";
 const WORKFLOW_LANGUAGE_UNSPECIFIED: i32 = 0;
 
+/// Progress of a single workflow run, in the order the events are emitted.
+#[derive(Debug)]
+pub(crate) enum WorkflowRunEvent {
+    /// The workflow was loaded and is about to run.
+    Started {
+        workflow_id: String,
+        workflow_code_id: Option<String>,
+    },
+    /// One line written with `console.log` (or another console method).
+    Console { line: String },
+    /// A named `step(...)` finished (or was left incomplete when the run ended).
+    Step { step: WorkflowStep },
+    /// A dry run stubbed a plugin call.
+    Planned { action: PlannedAction },
+    /// The run completed and its result was persisted, unless it was a dry run.
+    Finished { result: WorkflowResult },
+    /// The run could not be started or failed before producing a result.
+    Failed { status: Status },
+}
+
+/// Result of running a stored workflow.
+#[derive(Debug)]
+pub(crate) struct WorkflowRun {
+    /// Latest result of the run, with step markers removed from its output.
+    pub result: WorkflowResult,
+    /// Named steps recorded during the run, in the order they started.
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// Result of a dry run of a stored workflow.
+#[derive(Debug)]
+pub(crate) struct WorkflowPlan {
+    /// Latest result of the dry run, with marker lines removed from its output.
+    pub result: WorkflowResult,
+    /// Side-effecting calls that were intercepted, in call order.
+    pub actions: Vec<PlannedAction>,
+}
+
+/// Plain-language explanation of a stored workflow code, shown before approving a run.
+#[derive(Debug)]
+pub(crate) struct WorkflowExplanation {
+    /// The code revision that was explained.
+    pub workflow_code_id: String,
+    /// What the code does, in order, as written by the model.
+    pub steps: Vec<String>,
+    /// Permissions read from the code, see [`MyWorkflowService::preview_workflow_permissions`].
+    pub permissions: PermissionPreview,
+    /// Risk rated from `permissions.required`, independent of the model.
+    pub risk: RiskAssessment,
+}
+
+/// Diagnosis of a failed run, see [`MyWorkflowService::diagnose_workflow_result`].
+#[derive(Debug)]
+pub(crate) struct ResultDiagnosis {
+    pub workflow_id: String,
+    /// Why the run failed, as explained by the model; empty when it only returned code.
+    pub explanation: String,
+    /// The suggested fix as a diff against the code that failed.
+    pub diff: Vec<DiffHunk>,
+    /// The code revision the fix was stored as, when it was applied.
+    pub applied: Option<WorkflowCode>,
+}
+
+/// Permissions a run receives besides the allowed permissions of its code.
+struct RunPermissions {
+    denied: Vec<DeniedPermission>,
+    /// Permissions of the workflow's permission profiles.
+    profile: Vec<AllowedPermission>,
+    grants: Vec<LimitedGrant>,
+    /// Claims a use of one of `grants` from the thread running the workflow.
+    claim_grant: Box<dyn FnMut(&str) -> bool + Send>,
+    /// Owner of the workflow, whose secrets the run reads and stores.
+    owner: Option<String>,
+}
+
+/// Result of replaying a recorded run.
+#[derive(Debug)]
+pub(crate) struct WorkflowReplay {
+    /// Latest result of the replay, with marker lines removed from its output.
+    pub result: WorkflowResult,
+    /// Console output of the recorded run.
+    pub expected_output: String,
+}
+
+impl WorkflowReplay {
+    /// Returns whether the replay printed exactly what the recorded run printed.
+    pub fn matches(&self) -> bool {
+        self.result.result == self.expected_output
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MyWorkflowService {
-    db: Arc<DatabaseConnection>,
+    pub(super) db: Arc<DatabaseConnection>,
 }
 
 impl MyWorkflowService {
@@ -60,7 +208,7 @@ impl MyWorkflowService {
         })
     }
 
-    fn map_db_error(err: DbErr) -> Status {
+    pub(super) fn map_db_error(err: DbErr) -> Status {
         error!("database operation failed: {err:?}");
         Status::internal("database operation failed")
     }
@@ -73,6 +221,81 @@ impl MyWorkflowService {
         }
     }
 
+    /// The value of the request's `key` metadata, if any.
+    fn metadata_value<T>(request: &Request<T>, key: &str) -> Result<Option<String>, Status> {
+        request
+            .metadata()
+            .get(key)
+            .map(|value| value.to_str().map(str::to_string))
+            .transpose()
+            .map_err(|_| Status::invalid_argument(format!("malformed {key}")))
+    }
+
+    /// The workflow version named by the request's `x-sapphillon-workflow-version` metadata.
+    fn requested_version<T>(request: &Request<T>) -> Result<i32, Status> {
+        let value =
+            Self::metadata_value(request, WORKFLOW_VERSION_METADATA_KEY)?.ok_or_else(|| {
+                Status::invalid_argument(format!(
+                    "{WORKFLOW_VERSION_METADATA_KEY} metadata is required; GetWorkflow returns it"
+                ))
+            })?;
+        value
+            .trim()
+            .parse::<i32>()
+            .ok()
+            .filter(|version| *version > 0)
+            .ok_or_else(|| {
+                Status::invalid_argument(format!("malformed {WORKFLOW_VERSION_METADATA_KEY}"))
+            })
+    }
+
+    /// Adds a workflow's version to the response metadata.
+    fn with_workflow_version<T>(mut response: Response<T>, version: i32) -> Response<T> {
+        response
+            .metadata_mut()
+            .insert(WORKFLOW_VERSION_METADATA_KEY, MetadataValue::from(version));
+        response
+    }
+
+    /// The model named by the request's `x-sapphillon-model` metadata, if any.
+    fn requested_model<T>(request: &Request<T>) -> Result<Option<String>, Status> {
+        Self::metadata_value(request, MODEL_METADATA_KEY)
+    }
+
+    /// Resolves the language model provider for `model`, or the default one.
+    async fn generation_provider(
+        &self,
+        model: Option<&str>,
+    ) -> Result<Box<dyn LlmProvider>, Status> {
+        provider_for_model(&self.db, model).await.map_err(|err| {
+            error!("cannot select a model for workflow generation: {err:#}");
+            Status::failed_precondition(format!("cannot select a model: {err}"))
+        })
+    }
+
+    /// Loads every registered plugin package, built-in and external, so generation
+    /// describes the plugins that are actually installed.
+    async fn plugin_catalog(&self) -> Result<Vec<PluginPackage>, Status> {
+        let mut packages = Vec::new();
+        let mut page_token = None;
+        loop {
+            let (page, next_page_token) = list_plugins(&self.db, page_token, Some(100))
+                .await
+                .map_err(Self::map_db_error)?;
+            packages.extend(page);
+            if next_page_token.is_empty() {
+                return Ok(packages);
+            }
+            page_token = Some(next_page_token);
+        }
+    }
+
+    /// Returns the tool catalog that workflow generation puts in its prompt, for
+    /// inspecting what the model is told about the installed plugins.
+    pub(crate) async fn generation_tool_catalog(&self) -> Result<String, Status> {
+        Ok(format_tool_catalog(&self.plugin_catalog().await?))
+    }
+
     fn now_timestamp() -> Timestamp {
         let now = Utc::now();
         Timestamp {
@@ -169,58 +392,1417 @@ impl MyWorkflowService {
             desired.updated_at = incoming.updated_at;
         }
 
-        desired
-    }
+        desired
+    }
+
+    fn sanitize_generated_code(code: &str) -> String {
+        let trimmed = code.trim();
+        if trimmed.ends_with("workflow();") {
+            trimmed.to_string()
+        } else {
+            format!("{trimmed}\nworkflow();")
+        }
+    }
+
+    /// Selects the workflows matching a `ListWorkflows` filter. Filtering in the query
+    /// keeps every page full and page tokens counting only matching workflows.
+    ///
+    /// A `folder` filter matches the workflows in that folder and in its subfolders.
+    fn filtered_workflows(
+        display_name: Option<&str>,
+        workflow_language: Option<i32>,
+        tag: Option<&str>,
+        folder: Option<&str>,
+    ) -> Select<workflow_entity::Entity> {
+        let mut query = workflow_entity::Entity::find();
+        if let Some(name) = display_name {
+            let pattern = format!("%{}%", Self::escape_like(name));
+            query = query.filter(
+                workflow_entity::Column::DisplayName.like(LikeExpr::new(pattern).escape('\\')),
+            );
+        }
+        if let Some(language) = workflow_language {
+            query = query.filter(workflow_entity::Column::WorkflowLanguage.eq(language));
+        }
+        if let Some(tag) = tag {
+            let tagged = workflow_tag::Entity::find()
+                .select_only()
+                .column(workflow_tag::Column::WorkflowId)
+                .filter(workflow_tag::Column::Tag.eq(tag))
+                .into_query();
+            query = query.filter(workflow_entity::Column::Id.in_subquery(tagged));
+        }
+        if let Some(folder) = folder {
+            let subfolders = format!("{}/%", Self::escape_like(folder));
+            let filed = workflow_folder::Entity::find()
+                .select_only()
+                .column(workflow_folder::Column::WorkflowId)
+                .filter(
+                    Condition::any()
+                        .add(workflow_folder::Column::Folder.eq(folder))
+                        .add(
+                            workflow_folder::Column::Folder
+                                .like(LikeExpr::new(subfolders).escape('\\')),
+                        ),
+                )
+                .into_query();
+            query = query.filter(workflow_entity::Column::Id.in_subquery(filed));
+        }
+        query
+    }
+
+    /// Escapes the `LIKE` wildcards in `value` so it only matches itself.
+    fn escape_like(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '\\' | '%' | '_') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    fn decode_page_token(token: &str) -> u64 {
+        token.trim().parse::<u64>().unwrap_or(0)
+    }
+
+    fn encode_page_token(offset: u64) -> String {
+        offset.to_string()
+    }
+
+    async fn persist_workflow_results(
+        &self,
+        workflow: &mut Workflow,
+        workflow_code_id: &str,
+        new_results: &[WorkflowResult],
+    ) -> Result<(), Status> {
+        if let Some(code) = workflow
+            .workflow_code
+            .iter_mut()
+            .find(|c| c.id == workflow_code_id)
+        {
+            let mut combined = code.result.clone();
+            combined.extend_from_slice(new_results);
+            combined.sort_by_key(|r| r.workflow_result_revision);
+            combined.dedup_by(|a, b| a.id == b.id);
+            code.result = combined;
+        }
+
+        if !new_results.is_empty() {
+            let mut combined = workflow.workflow_results.clone();
+            combined.extend_from_slice(new_results);
+            combined.sort_by_key(|r| r.workflow_result_revision);
+            combined.dedup_by(|a, b| a.id == b.id);
+            workflow.workflow_results = combined;
+        }
+
+        workflow.updated_at = Some(Self::now_timestamp());
+
+        update_workflow_from_proto(&self.db, workflow)
+            .await
+            .map_err(Self::map_db_error)?;
+        Ok(())
+    }
+
+    fn latest_code(workflow: &Workflow) -> Option<&WorkflowCode> {
+        workflow
+            .workflow_code
+            .iter()
+            .max_by_key(|code| code.code_revision)
+    }
+
+    /// Stores `code` as the next revision of `workflow` and records how it differs from
+    /// the latest revision.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow` - Workflow receiving the revision.
+    /// * `code` - The new revision; its ID, revision number, creation time and results
+    ///   are assigned here.
+    /// * `diff` - The change from the latest revision.
+    /// * `summary` - Description of the change for people.
+    ///
+    /// # Returns
+    ///
+    /// Returns the stored workflow and the new revision, or a gRPC status describing the
+    /// failure.
+    async fn append_code_revision(
+        &self,
+        mut workflow: Workflow,
+        mut code: WorkflowCode,
+        diff: &[DiffHunk],
+        summary: &str,
+    ) -> Result<(Workflow, WorkflowCode), Status> {
+        let previous_code_id = Self::latest_code(&workflow).map(|latest| latest.id.clone());
+        let now = Self::now_timestamp();
+        code.id = uuid::Uuid::new_v4().to_string();
+        code.code_revision =
+            Self::latest_code(&workflow).map_or(0, |latest| latest.code_revision) + 1;
+        code.created_at = Some(now);
+        code.result = vec![];
+        workflow.workflow_code.push(code.clone());
+        workflow.updated_at = Some(now);
+
+        let stored = update_workflow_from_proto(&self.db, &workflow)
+            .await
+            .map_err(Self::map_db_error)?;
+        let diff_json = serde_json::to_string(diff)
+            .map_err(|err| Status::internal(format!("failed to encode diff: {err}")))?;
+        create_code_change(
+            &self.db,
+            &code.id,
+            &workflow.id,
+            previous_code_id.as_deref(),
+            &diff_json,
+            summary,
+        )
+        .await
+        .map_err(Self::map_db_error)?;
+
+        info!(
+            "workflow code revision stored: workflow_id={workflow_id}, code_revision={code_revision}",
+            workflow_id = workflow.id.as_str(),
+            code_revision = code.code_revision
+        );
+        Ok((stored, code))
+    }
+
+    /// Lists the code revisions of a workflow with the recorded change of each.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow whose revisions are listed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the revisions ordered by revision number, or a gRPC status describing the
+    /// failure.
+    pub(crate) async fn list_code_revisions(
+        &self,
+        workflow_id: &str,
+    ) -> Result<Vec<CodeRevision>, Status> {
+        let workflow = get_workflow_by_id(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+        let mut changes: HashMap<String, _> = list_code_changes(&self.db, workflow_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(|change| (change.workflow_code_id.clone(), change))
+            .collect();
+
+        let mut revisions = workflow
+            .workflow_code
+            .iter()
+            .map(|code| {
+                let change = changes.remove(&code.id);
+                let diff = match &change {
+                    Some(change) => serde_json::from_str(&change.diff_json).map_err(|err| {
+                        Status::data_loss(format!("diff of revision {} is corrupt: {err}", code.id))
+                    })?,
+                    None => Vec::new(),
+                };
+                Ok(CodeRevision {
+                    workflow_code_id: code.id.clone(),
+                    code_revision: code.code_revision,
+                    created_at: code.created_at,
+                    previous_code_id: change.as_ref().and_then(|c| c.previous_code_id.clone()),
+                    summary: change.map(|c| c.summary),
+                    diff,
+                })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+        revisions.sort_by_key(|revision| revision.code_revision);
+        Ok(revisions)
+    }
+
+    /// Makes an earlier code revision current again by storing a copy of it, with its
+    /// plugins and allowed permissions, as the next revision. History is never removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to roll back.
+    /// * `code_revision` - Revision number to restore.
+    ///
+    /// # Returns
+    ///
+    /// Returns the new revision, or a gRPC status describing the failure.
+    pub(crate) async fn rollback_workflow_code(
+        &self,
+        workflow_id: &str,
+        code_revision: i32,
+    ) -> Result<WorkflowCode, Status> {
+        let workflow = get_workflow_by_id(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+        let target = workflow
+            .workflow_code
+            .iter()
+            .find(|code| code.code_revision == code_revision)
+            .cloned()
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "revision {code_revision} of workflow '{workflow_id}' not found"
+                ))
+            })?;
+        let latest = Self::latest_code(&workflow).cloned().unwrap_or_default();
+        if latest.id == target.id {
+            return Err(Status::failed_precondition(format!(
+                "revision {code_revision} is already the latest revision"
+            )));
+        }
+
+        let diff = diff_code(&latest.code, &target.code);
+        let summary = format!("Rolled back to revision {code_revision}");
+        let (_, code) = self
+            .append_code_revision(workflow, target, &diff, &summary)
+            .await?;
+        Ok(code)
+    }
+
+    /// Replaces the tags of a workflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to tag.
+    /// * `tags` - The new tags; an empty list removes every tag.
+    ///
+    /// # Returns
+    ///
+    /// Returns the stored tags after trimming and removing duplicates, or a gRPC status
+    /// describing the failure.
+    pub(crate) async fn set_workflow_tags(
+        &self,
+        workflow_id: &str,
+        tags: &[String],
+    ) -> Result<Vec<String>, Status> {
+        let tags = normalize_tags(tags).map_err(Status::invalid_argument)?;
+        self.ensure_workflow_exists(workflow_id).await?;
+        organization::set_workflow_tags(&self.db, workflow_id, &tags)
+            .await
+            .map_err(Self::map_db_error)?;
+        info!("Tagged workflow {workflow_id} with {tags:?}");
+        Ok(tags)
+    }
+
+    /// Files a workflow in a folder, creating the folder path implicitly.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to move.
+    /// * `folder` - Slash-separated folder path; an empty path or `/` moves the workflow
+    ///   to the top level.
+    ///
+    /// # Returns
+    ///
+    /// Returns the normalized folder, `None` for the top level, or a gRPC status
+    /// describing the failure.
+    pub(crate) async fn move_workflow_to_folder(
+        &self,
+        workflow_id: &str,
+        folder: &str,
+    ) -> Result<Option<String>, Status> {
+        let folder = normalize_folder(folder).map_err(Status::invalid_argument)?;
+        self.ensure_workflow_exists(workflow_id).await?;
+        organization::set_workflow_folder(&self.db, workflow_id, folder.as_deref())
+            .await
+            .map_err(Self::map_db_error)?;
+        info!(
+            "Moved workflow {workflow_id} to {}",
+            folder.as_deref().unwrap_or("the top level")
+        );
+        Ok(folder)
+    }
+
+    /// Lists workflows with their folder and tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - Only list workflows with this tag.
+    /// * `folder` - Only list workflows in this folder or its subfolders.
+    /// * `owner` - Only list the workflows this owner may see; `None` lists every workflow.
+    ///
+    /// # Returns
+    ///
+    /// Returns the workflows ordered by ID, or a gRPC status describing the failure.
+    pub(crate) async fn list_organized_workflows(
+        &self,
+        tag: Option<&str>,
+        folder: Option<&str>,
+        owner: Option<&str>,
+    ) -> Result<Vec<OrganizedWorkflow>, Status> {
+        let tag = tag.map(str::trim).filter(|tag| !tag.is_empty());
+        let folder = match folder {
+            Some(folder) => normalize_folder(folder).map_err(Status::invalid_argument)?,
+            None => None,
+        };
+        let items = Self::filtered_workflows(None, None, tag, folder.as_deref())
+            .filter(visible_to(workflow_entity::Column::OwnerId, owner))
+            .order_by_asc(workflow_entity::Column::Id)
+            .all(&*self.db)
+            .await
+            .map_err(Self::map_db_error)?;
+
+        let mut workflows = Vec::with_capacity(items.len());
+        for item in items {
+            let tags = organization::list_workflow_tags(&self.db, &item.id)
+                .await
+                .map_err(Self::map_db_error)?;
+            let folder = organization::get_workflow_folder(&self.db, &item.id)
+                .await
+                .map_err(Self::map_db_error)?;
+            workflows.push(OrganizedWorkflow {
+                workflow_id: item.id,
+                display_name: item.display_name,
+                folder,
+                tags,
+            });
+        }
+        Ok(workflows)
+    }
+
+    /// Searches workflow names and descriptions, code and result output.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Words that must all occur in a hit.
+    /// * `limit` - Maximum number of hits, at most 100; `0` returns the first 100.
+    /// * `owner` - Leave out hits in workflows this owner may not see; `None` keeps
+    ///   every hit.
+    ///
+    /// # Returns
+    ///
+    /// Returns the hits, best first, with the matching words highlighted in their
+    /// snippets, or a gRPC status describing the failure.
+    pub(crate) async fn search_workflow_content(
+        &self,
+        query: &str,
+        limit: u64,
+        owner: Option<&str>,
+    ) -> Result<Vec<SearchHit>, Status> {
+        if query.trim().is_empty() {
+            return Err(Status::invalid_argument("search query must not be empty"));
+        }
+        let limit = match limit {
+            0 => MAX_SEARCH_HITS,
+            limit => limit.min(MAX_SEARCH_HITS),
+        };
+        let hits = search::search_workflow_content(&self.db, query, limit)
+            .await
+            .map_err(Self::map_db_error)?;
+        if owner.is_none() {
+            return Ok(hits);
+        }
+
+        let mut visible: HashMap<String, bool> = HashMap::new();
+        let mut kept = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let is_visible = match visible.get(&hit.workflow_id) {
+                Some(is_visible) => *is_visible,
+                None => {
+                    let row_owner = get_workflow_owner(&self.db, &hit.workflow_id)
+                        .await
+                        .map_err(Self::map_db_error)?;
+                    let is_visible = is_visible_to(row_owner.as_deref(), owner);
+                    visible.insert(hit.workflow_id.clone(), is_visible);
+                    is_visible
+                }
+            };
+            if is_visible {
+                kept.push(hit);
+            }
+        }
+        Ok(kept)
+    }
+
+    async fn ensure_workflow_exists(&self, workflow_id: &str) -> Result<(), Status> {
+        workflow_entity::Entity::find_by_id(workflow_id.to_string())
+            .one(&*self.db)
+            .await
+            .map_err(Self::map_db_error)?
+            .map(|_| ())
+            .ok_or_else(|| Status::not_found(format!("workflow '{workflow_id}'")))
+    }
+
+    /// Checks that the workflow exists and that `owner` may use it. Workflows of other
+    /// users are reported as not found so that their IDs cannot be probed.
+    pub(super) async fn authorize_workflow(
+        &self,
+        workflow_id: &str,
+        owner: Option<&str>,
+    ) -> Result<(), Status> {
+        let row_owner = get_workflow_owner(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+        if is_visible_to(row_owner.as_deref(), owner) {
+            Ok(())
+        } else {
+            Err(Status::not_found(format!("workflow '{workflow_id}'")))
+        }
+    }
+
+    /// Like [`Self::authorize_workflow`] for requests that change or run the workflow,
+    /// which callers may only do to shared workflows with `may_change_shared`; see
+    /// [`user::is_changeable_by`].
+    pub(super) async fn authorize_workflow_change(
+        &self,
+        workflow_id: &str,
+        owner: Option<&str>,
+        may_change_shared: bool,
+    ) -> Result<(), Status> {
+        let row_owner = get_workflow_owner(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+        if !is_visible_to(row_owner.as_deref(), owner) {
+            return Err(Status::not_found(format!("workflow '{workflow_id}'")));
+        }
+        if user::is_changeable_by(row_owner.as_deref(), owner, may_change_shared) {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(format!(
+                "workflow '{workflow_id}' is shared and only admins may change it"
+            )))
+        }
+    }
+
+    /// Stores a new workflow owned by `owner`, recording the owner as a user.
+    async fn create_owned_workflow(
+        &self,
+        workflow: &Workflow,
+        owner: Option<&str>,
+    ) -> Result<Workflow, Status> {
+        if let Some(owner) = owner {
+            user::touch_user(&self.db, owner)
+                .await
+                .map_err(Self::map_db_error)?;
+        }
+        create_workflow_from_proto(&self.db, workflow, owner)
+            .await
+            .map_err(Self::map_db_error)
+    }
+
+    /// Asks the model why a stored run failed and for code that fixes it.
+    ///
+    /// The model sees the code that ran, the run's output and exit code and, when the
+    /// same code revision has been recorded, the plugin calls of its latest recording.
+    /// Plain runs keep no call trace.
+    ///
+    /// # Arguments
+    ///
+    /// * `result_id` - The failed run.
+    /// * `model` - Model to diagnose with; the default model is used when `None`.
+    /// * `apply` - Whether to store the fix as the next code revision of the workflow. It
+    ///   keeps the plugins and allowed permissions of the code that failed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the explanation and the fix, or a gRPC status describing the failure.
+    /// Results with exit code 0 are rejected with `failed_precondition`.
+    pub(crate) async fn diagnose_workflow_result(
+        &self,
+        result_id: &str,
+        model: Option<&str>,
+        apply: bool,
+    ) -> Result<ResultDiagnosis, Status> {
+        let result = get_workflow_result(&self.db, result_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(|| Status::not_found(format!("workflow result '{result_id}' not found")))?;
+        if result.exit_code == Some(0) {
+            return Err(Status::failed_precondition(format!(
+                "workflow result '{result_id}' did not fail"
+            )));
+        }
+        let workflow = get_workflow_by_id(&self.db, &result.workflow_id)
+            .await
+            .map_err(|err| {
+                Self::map_not_found(err, format!("workflow '{}'", result.workflow_id))
+            })?;
+        let failed_code = workflow
+            .workflow_code
+            .iter()
+            .find(|code| code.id == result.workflow_code_id)
+            .cloned()
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "workflow code '{}' not found",
+                    result.workflow_code_id
+                ))
+            })?;
+
+        let recording = database::recording::list_recordings(&self.db, Some(&workflow.id))
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .find(|recording| recording.workflow_code_id == failed_code.id);
+        let trace = match recording {
+            Some(recording) => {
+                let calls: Vec<RecordedCall> = serde_json::from_str(&recording.calls)
+                    .map_err(|err| Status::data_loss(format!("recording is corrupt: {err}")))?;
+                Some(format_calls(&calls))
+            }
+            None => None,
+        };
+        let task = if workflow.description.trim().is_empty() {
+            workflow.display_name.as_str()
+        } else {
+            workflow.description.as_str()
+        };
+        let output = result.result.unwrap_or_default();
+
+        let provider = self.generation_provider(model).await?;
+        let plugins = self.plugin_catalog().await?;
+        let failure = WorkflowFailure {
+            task,
+            code: &failed_code.code,
+            output: &output,
+            exit_code: result.exit_code,
+            trace: trace.as_deref(),
+        };
+        let diagnosis = diagnose_workflow_failure(provider.as_ref(), &failure, &plugins)
+            .await
+            .map_err(|err| {
+                error!("failed to diagnose workflow result {result_id}: {err}");
+                Status::internal("failed to diagnose workflow result")
+            })?;
+        let code = Self::sanitize_generated_code(&diagnosis.code);
+        let diff = diff_code(&failed_code.code, &code);
+        info!(
+            "workflow result diagnosed: result_id={result_id}, workflow_id={workflow_id}, hunks={hunks}",
+            workflow_id = workflow.id.as_str(),
+            hunks = diff.len()
+        );
+
+        let workflow_id = workflow.id.clone();
+        let applied = if apply {
+            let latest = Self::latest_code(&workflow).map(|latest| latest.code.clone());
+            let summary = if diagnosis.explanation.is_empty() {
+                format!("Fixed the failure of result {result_id}")
+            } else {
+                diagnosis.explanation.clone()
+            };
+            let revision = WorkflowCode {
+                code: code.clone(),
+                language: WORKFLOW_LANGUAGE_JS,
+                ..failed_code
+            };
+            let latest_diff = diff_code(&latest.unwrap_or_default(), &code);
+            let (_, code) = self
+                .append_code_revision(workflow, revision, &latest_diff, &summary)
+                .await?;
+            Some(code)
+        } else {
+            None
+        };
+
+        Ok(ResultDiagnosis {
+            workflow_id,
+            explanation: diagnosis.explanation,
+            diff,
+            applied,
+        })
+    }
+
+    /// Runs a stored workflow and persists its results.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to run.
+    /// * `workflow_code_id` - Code revision to run; the latest revision is used when `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the latest result and the step breakdown of the run, or a gRPC status
+    /// describing the failure.
+    pub(crate) async fn execute_workflow(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+    ) -> Result<WorkflowRun, Status> {
+        let (run, _, _) = self
+            .execute_workflow_inner(workflow_id, workflow_code_id, false, None, None)
+            .await?;
+        Ok(run)
+    }
+
+    /// Runs a stored workflow like [`Self::execute_workflow`] and stores every plugin
+    /// call it makes, so the run can later be replayed with [`Self::replay_workflow`].
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to run.
+    /// * `workflow_code_id` - Code revision to run; the latest revision is used when `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the run and the ID of the stored recording, or a gRPC status describing
+    /// the failure.
+    pub(crate) async fn record_workflow(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+    ) -> Result<(WorkflowRun, String), Status> {
+        let (run, workflow_code_id, calls) = self
+            .execute_workflow_inner(workflow_id, workflow_code_id, true, None, None)
+            .await?;
+
+        let calls = serde_json::to_string(&calls)
+            .map_err(|err| Status::internal(format!("failed to encode recording: {err}")))?;
+        let recording = database::recording::create_recording(
+            &self.db,
+            workflow_id,
+            &workflow_code_id,
+            &calls,
+            &run.result.result,
+        )
+        .await
+        .map_err(Self::map_db_error)?;
+
+        info!(
+            "workflow recorded: workflow_id={workflow_id}, recording_id={recording_id}",
+            recording_id = recording.id
+        );
+        Ok((run, recording.id))
+    }
+
+    /// Re-executes a recorded run, serving every plugin call from the recording.
+    ///
+    /// Nothing is persisted and no plugin reaches the outside world, so a replay can
+    /// be repeated freely while debugging.
+    ///
+    /// # Arguments
+    ///
+    /// * `recording_id` - Recording created by [`Self::record_workflow`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the replayed result together with the recorded output, or a gRPC status
+    /// describing the failure.
+    pub(crate) async fn replay_workflow(
+        &self,
+        recording_id: &str,
+    ) -> Result<WorkflowReplay, Status> {
+        let recording = database::recording::get_recording(&self.db, recording_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(|| Status::not_found(format!("recording '{recording_id}' not found")))?;
+        let calls: Vec<RecordedCall> = serde_json::from_str(&recording.calls)
+            .map_err(|err| Status::data_loss(format!("recording is corrupt: {err}")))?;
+
+        let mut workflow = get_workflow_by_id(&self.db, &recording.workflow_id)
+            .await
+            .map_err(|err| {
+                Self::map_not_found(err, format!("workflow '{}'", recording.workflow_id))
+            })?;
+        let workflow_code =
+            Self::select_workflow_code(&mut workflow, Some(&recording.workflow_code_id))?;
+        let permissions = self
+            .load_run_permissions(&recording.workflow_id, &workflow_code.id)
+            .await?;
+        let (results, _) = Self::run_workflow_code(
+            &recording.workflow_id,
+            workflow_code,
+            permissions,
+            false,
+            None,
+            |code| instrument_replay(&calls, &instrument_steps(code)),
+        )
+        .await?;
+
+        let mut result = results
+            .into_iter()
+            .max_by_key(|r| r.workflow_result_revision)
+            .ok_or_else(|| Status::not_found("workflow result missing"))?;
+        let (_, output) = parse_steps(&result.result);
+        result.result = output;
+
+        let replay = WorkflowReplay {
+            result,
+            expected_output: recording.output,
+        };
+        info!(
+            "workflow replayed: recording_id={recording_id}, matches={matches}",
+            matches = replay.matches()
+        );
+        Ok(replay)
+    }
+
+    /// Runs and persists a stored workflow, returning the run, the code revision that
+    /// ran and, when `record` is set, the plugin calls of the latest result. `trigger`
+    /// is exposed to the workflow as `globalThis.trigger`.
+    async fn execute_workflow_inner(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+        record: bool,
+        trigger: Option<&serde_json::Value>,
+        events: Option<&mpsc::UnboundedSender<WorkflowRunEvent>>,
+    ) -> Result<(WorkflowRun, String, Vec<RecordedCall>), Status> {
+        let mut workflow = get_workflow_by_id(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+
+        let workflow_code = Self::select_workflow_code(&mut workflow, workflow_code_id)?;
+        let workflow_code_id = workflow_code.id.clone();
+        let owner = get_workflow_owner(&self.db, workflow_id)
+            .await
+            .map_err(Self::map_db_error)?;
+        events::workflow_started(workflow_id, &workflow_code_id, owner.clone());
+
+        let outcome: Result<(WorkflowRun, Vec<RecordedCall>), Status> = async {
+            let permissions = self
+                .load_run_permissions(workflow_id, &workflow_code_id)
+                .await?;
+            let (mut results, usage) = Self::run_workflow_code(
+                workflow_id,
+                workflow_code,
+                permissions,
+                false,
+                events.cloned(),
+                |code| {
+                    let code = instrument_steps(code);
+                    let code = if record {
+                        instrument_recording(&code)
+                    } else {
+                        code
+                    };
+                    match trigger {
+                        Some(event) => format!("{}{code}", trigger_prelude(event)),
+                        None => code,
+                    }
+                })
+                .await?;
+
+            let mut steps_by_revision = Vec::with_capacity(results.len());
+            for result in &mut results {
+                let (steps, output) = parse_steps(&result.result);
+                let (calls, output) = if record {
+                    parse_recording(&output)
+                } else {
+                    (Vec::new(), output)
+                };
+                result.result = output;
+                steps_by_revision.push((result.workflow_result_revision, steps, calls));
+            }
+
+            let latest_result_revision = results
+                .iter()
+                .map(|r| r.workflow_result_revision)
+                .max()
+                .unwrap_or(0);
+
+            let latest_result = results
+                .iter()
+                .find(|r| r.workflow_result_revision == latest_result_revision)
+                .cloned()
+                .ok_or_else(|| Status::not_found("workflow result missing"))?;
+
+            let PermissionUsage {
+                granted,
+                used_grants,
+            } = usage;
+            let mut workflow_clone = workflow.clone();
+            if !granted.is_empty() {
+                info!(
+                    "persisting permissions granted during the run: workflow_code_id={workflow_code_id}, functions={count}",
+                    count = granted.len()
+                );
+                if let Some(code) = workflow_clone
+                    .workflow_code
+                    .iter_mut()
+                    .find(|c| c.id == workflow_code_id)
+                {
+                    merge_granted_permissions(code, granted);
+                }
+            }
+            if !used_grants.is_empty() {
+                debug!(
+                    "run relied on limited grants: workflow_code_id={workflow_code_id}, grants={used_grants:?}"
+                );
+            }
+            self.persist_workflow_results(&mut workflow_clone, &workflow_code_id, &results)
+                .await?;
+
+            info!(
+                "workflow executed: workflow_id={workflow_id}, workflow_code_id={workflow_code_id}, result_revision={result_revision}",
+                workflow_id = workflow.id.as_str(),
+                workflow_code_id = workflow_code_id.as_str(),
+                result_revision = latest_result.workflow_result_revision
+            );
+
+            let (steps, calls) = steps_by_revision
+                .into_iter()
+                .find(|(revision, _, _)| *revision == latest_result_revision)
+                .map(|(_, steps, calls)| (steps, calls))
+                .unwrap_or_default();
+            Ok((
+                WorkflowRun {
+                    result: latest_result,
+                    steps,
+                },
+                calls,
+            ))
+        }
+        .await;
+
+        let error = match &outcome {
+            Ok((run, _)) if run.result.exit_code != 0 => {
+                Some(format!("exited with code {}", run.result.exit_code))
+            }
+            Ok(_) => None,
+            Err(status) => Some(status.message().to_string()),
+        };
+        events::workflow_finished(workflow_id, &workflow_code_id, owner, error);
+
+        let (run, calls) = outcome?;
+        Ok((run, workflow_code_id, calls))
+    }
+
+    /// Runs a stored workflow in dry-run mode without persisting a result.
+    ///
+    /// Every plugin call that needs a permission is stubbed by the permission layer and
+    /// reported instead, as are the few calls that change something without needing one.
+    /// External plugins are not loaded, so calling them fails the dry run.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to plan.
+    /// * `workflow_code_id` - Code revision to plan; the latest revision is used when `None`.
+    /// * `events` - Channel receiving `Console`, `Step` and `Planned` events while the
+    ///   dry run is in progress, if any.
+    ///
+    /// # Returns
+    ///
+    /// Returns the latest result and the intercepted calls, or a gRPC status describing
+    /// the failure.
+    pub(crate) async fn plan_workflow(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+        events: Option<&mpsc::UnboundedSender<WorkflowRunEvent>>,
+    ) -> Result<WorkflowPlan, Status> {
+        let mut workflow = get_workflow_by_id(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+
+        let workflow_code = Self::select_workflow_code(&mut workflow, workflow_code_id)?;
+        let permissions = self
+            .load_run_permissions(workflow_id, &workflow_code.id)
+            .await?;
+        let (results, _) = Self::run_workflow_code(
+            workflow_id,
+            workflow_code,
+            permissions,
+            true,
+            events.cloned(),
+            |code| instrument_dry_run(&instrument_steps(code)),
+        )
+        .await?;
+
+        let mut result = results
+            .into_iter()
+            .max_by_key(|r| r.workflow_result_revision)
+            .ok_or_else(|| Status::not_found("workflow result missing"))?;
+        let (_, output) = parse_steps(&result.result);
+        let (actions, output) = parse_dry_run(&output);
+        result.result = output;
+
+        info!(
+            "workflow planned: workflow_id={workflow_id}, actions={count}",
+            count = actions.len()
+        );
+
+        Ok(WorkflowPlan { result, actions })
+    }
+
+    /// Infers the permissions a stored workflow code needs from its source, without
+    /// running it, so they can be shown for consent before the first run.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to inspect.
+    /// * `workflow_code_id` - Code revision to inspect; the latest revision is used when `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the required permissions and those not yet covered by the code's allowed
+    /// permissions and attached profiles, or a gRPC status describing the failure.
+    pub(crate) async fn preview_workflow_permissions(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+    ) -> Result<PermissionPreview, Status> {
+        let mut workflow = get_workflow_by_id(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+        let profile = self.load_profile_permissions(workflow_id).await?;
+        let workflow_code = Self::select_workflow_code(&mut workflow, workflow_code_id)?;
+
+        let bundle = WorkflowBundle::parse(&workflow_code.code);
+        let sources: Vec<&str> = match &bundle {
+            Some(bundle) => bundle.files.values().map(String::as_str).collect(),
+            None => vec![workflow_code.code.as_str()],
+        };
+        let mut plugins = crate::sysconfig::sysconfig().initial_plugins;
+        for package in &workflow_code.plugin_packages {
+            if !plugins.iter().any(|p| p.package_id == package.package_id) {
+                plugins.push(package.clone());
+            }
+        }
+        let required = infer_required_permissions(&sources, &plugins);
+
+        let mut allowed = workflow_code.allowed_permissions.clone();
+        expand_profile_permissions(&mut allowed, profile);
+        let missing = find_missing_permissions(&required, &allowed);
+
+        info!(
+            "workflow permissions previewed: workflow_id={workflow_id}, required={required_count}, missing={missing_count}",
+            required_count = required.len(),
+            missing_count = missing.len()
+        );
+
+        Ok(PermissionPreview { required, missing })
+    }
+
+    /// Explains a stored workflow code in plain language and rates the risk of the
+    /// permissions it needs, for review before the user approves a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to explain.
+    /// * `workflow_code_id` - Code revision to explain; the latest revision is used when `None`.
+    /// * `model` - Model to explain with; the default model is used when `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the steps, permissions and risk of the code, or a gRPC status describing
+    /// the failure.
+    pub(crate) async fn explain_workflow(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<WorkflowExplanation, Status> {
+        let mut workflow = get_workflow_by_id(&self.db, workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+        let workflow_code = Self::select_workflow_code(&mut workflow, workflow_code_id)?;
+        let permissions = self
+            .preview_workflow_permissions(workflow_id, Some(&workflow_code.id))
+            .await?;
+        let risk = assess_risk(&permissions.required);
+
+        let provider = self.generation_provider(model).await?;
+        let steps = explain_workflow_code(
+            provider.as_ref(),
+            &workflow_code.code,
+            &permissions.required,
+        )
+        .await
+        .map_err(|err| {
+            error!("failed to explain workflow {workflow_id}: {err}");
+            Status::internal("failed to explain workflow")
+        })?;
+
+        info!(
+            "workflow explained: workflow_id={workflow_id}, steps={step_count}, risk={risk}",
+            step_count = steps.len(),
+            risk = risk.level.as_str()
+        );
+
+        Ok(WorkflowExplanation {
+            workflow_code_id: workflow_code.id.clone(),
+            steps,
+            permissions,
+            risk,
+        })
+    }
+
+    /// Loads everything the permission checks of a run need besides the code's own
+    /// allowed permissions.
+    async fn load_run_permissions(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: &str,
+    ) -> Result<RunPermissions, Status> {
+        Ok(RunPermissions {
+            denied: self.load_denied_permissions(workflow_code_id).await?,
+            profile: self.load_profile_permissions(workflow_id).await?,
+            grants: self.load_permission_grants(workflow_code_id).await?,
+            claim_grant: self.grant_claimer(),
+            owner: get_workflow_owner(&self.db, workflow_id)
+                .await
+                .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?,
+        })
+    }
+
+    /// Returns a function that claims a use of a limited grant for a run.
+    ///
+    /// Plugins call it on the thread running the workflow, which must not block on
+    /// the async runtime, so the claim runs as a task on the runtime and the thread
+    /// waits for its answer.
+    fn grant_claimer(&self) -> Box<dyn FnMut(&str) -> bool + Send> {
+        let db = self.db.clone();
+        let handle = Handle::current();
+        Box::new(move |grant_id: &str| {
+            let (reply, response) = std::sync::mpsc::channel();
+            let db = db.clone();
+            let id = grant_id.to_string();
+            handle.spawn(async move {
+                let _ = reply.send(claim_permission_grant_use(&db, &id).await);
+            });
+            match response.recv() {
+                Ok(Ok(claimed)) => {
+                    if !claimed {
+                        info!("Limited grant {grant_id} is used up");
+                    }
+                    claimed
+                }
+                Ok(Err(err)) => {
+                    error!("failed to claim a use of limited grant {grant_id}: {err:?}");
+                    false
+                }
+                Err(_) => false,
+            }
+        })
+    }
+
+    /// Loads the permissions denied to a workflow code for the permission checks of a run.
+    async fn load_denied_permissions(
+        &self,
+        workflow_code_id: &str,
+    ) -> Result<Vec<DeniedPermission>, Status> {
+        list_denied_permissions(&self.db, workflow_code_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(|row| {
+                let resource = match row.resource_json.as_deref() {
+                    Some(json) => serde_json::from_str(json).map_err(|err| {
+                        Status::data_loss(format!("denied permission {} is corrupt: {err}", row.id))
+                    })?,
+                    None => Vec::new(),
+                };
+                Ok(DeniedPermission {
+                    plugin_function_id: row.plugin_function_id,
+                    permission_type: row.permission_type,
+                    resource,
+                })
+            })
+            .collect()
+    }
+
+    /// Loads the allowed permissions of the profiles attached to a workflow.
+    ///
+    /// They only apply to the run and are never persisted with the workflow code.
+    async fn load_profile_permissions(
+        &self,
+        workflow_id: &str,
+    ) -> Result<Vec<AllowedPermission>, Status> {
+        let profiles = list_workflow_permission_profiles(&self.db, workflow_id)
+            .await
+            .map_err(Self::map_db_error)?;
+        let mut permissions = Vec::new();
+        for profile in profiles {
+            permissions.extend(
+                parse_profile_permissions(&profile.permissions).map_err(|err| {
+                    Status::data_loss(format!(
+                        "permission profile '{}' is corrupt: {err:#}",
+                        profile.name
+                    ))
+                })?,
+            );
+        }
+        Ok(permissions)
+    }
+
+    /// Loads the limited grants of a workflow code that have neither expired nor been
+    /// used up.
+    async fn load_permission_grants(
+        &self,
+        workflow_code_id: &str,
+    ) -> Result<Vec<LimitedGrant>, Status> {
+        list_active_permission_grants(&self.db, workflow_code_id, Utc::now())
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(|row| {
+                let resource = match row.resource_json.as_deref() {
+                    Some(json) => serde_json::from_str(json).map_err(|err| {
+                        Status::data_loss(format!("permission grant {} is corrupt: {err}", row.id))
+                    })?,
+                    None => Vec::new(),
+                };
+                Ok(LimitedGrant {
+                    id: row.id,
+                    plugin_function_id: row.plugin_function_id,
+                    permission: Permission {
+                        display_name: "Limited grant".to_string(),
+                        description: "".to_string(),
+                        permission_type: row.permission_type,
+                        permission_level: row.permission_level,
+                        resource,
+                    },
+                    expires_at: row.expires_at.map(SystemTime::from),
+                })
+            })
+            .collect()
+    }
+
+    /// Picks the requested code revision (or the latest one) and unescapes its source in place.
+    fn select_workflow_code<'a>(
+        workflow: &'a mut Workflow,
+        workflow_code_id: Option<&str>,
+    ) -> Result<&'a mut WorkflowCode, Status> {
+        let latest_revision = workflow
+            .workflow_code
+            .iter()
+            .map(|code| code.code_revision)
+            .max()
+            .unwrap_or(0);
+
+        let workflow_code = if let Some(code_id) = workflow_code_id {
+            workflow
+                .workflow_code
+                .iter_mut()
+                .find(|code| code.id == code_id)
+                .ok_or_else(|| Status::not_found(format!("workflow code '{code_id}' not found")))?
+        } else {
+            workflow
+                .workflow_code
+                .iter_mut()
+                .find(|code| code.code_revision == latest_revision)
+                .ok_or_else(|| Status::not_found("Latest workflow code not found"))?
+        };
+
+        // Bundles are JSON, where unescaping would turn escaped newlines into invalid strings.
+        if WorkflowBundle::parse(&workflow_code.code).is_some() {
+            return Ok(workflow_code);
+        }
+
+        workflow_code.code = match unescaper::unescape(&workflow_code.code) {
+            Ok(code) => code,
+            Err(err) => {
+                warn!("failed to unescape workflow code: {err}");
+                workflow_code.code.clone()
+            }
+        };
+
+        Ok(workflow_code)
+    }
+
+    /// Runs `workflow_code` on the worker pool after passing its source through `instrument`.
+    async fn run_workflow_code(
+        workflow_id: &str,
+        workflow_code: &WorkflowCode,
+        permissions: RunPermissions,
+        dry_run: bool,
+        events: Option<mpsc::UnboundedSender<WorkflowRunEvent>>,
+        instrument: impl FnOnce(&str) -> String,
+    ) -> Result<(Vec<WorkflowResult>, PermissionUsage), Status> {
+        let RunPermissions {
+            denied,
+            profile,
+            grants,
+            claim_grant,
+            owner,
+        } = permissions;
+        // Run an instrumented copy so neither the helpers nor the profile permissions
+        // are ever persisted with the code.
+        let mut instrumented_code = workflow_code.clone();
+        expand_profile_permissions(&mut instrumented_code.allowed_permissions, profile);
+        if dry_run {
+            // External plugins run in their own process where calls cannot be stubbed.
+            instrumented_code
+                .plugin_packages
+                .retain(|package| package.internal_plugin == Some(true));
+        }
+        let (required_permissions, allowed_permissions) =
+            Self::build_core_permissions(&instrumented_code);
+
+        let source = if let Some(bundle) = WorkflowBundle::parse(&workflow_code.code) {
+            bundle
+                .link()
+                .map_err(|err| Status::invalid_argument(format!("module link error: {err}")))?
+        } else if workflow_code.language == WORKFLOW_LANGUAGE_TS {
+            transpile_typescript(&workflow_code.code).map_err(|err| {
+                Status::invalid_argument(format!("TypeScript compile error: {err}"))
+            })?
+        } else {
+            workflow_code.code.clone()
+        };
+
+        instrumented_code.code = format!(
+            "{}{}{}",
+            if events.is_some() {
+                std_plugin::CONSOLE_FORWARD_PRELUDE
+            } else {
+                ""
+            },
+            state::namespace_prelude(workflow_id),
+            instrument(&source)
+        );
 
-    fn sanitize_generated_code(code: &str) -> String {
-        let trimmed = code.trim();
-        if trimmed.ends_with("workflow();") {
-            trimmed.to_string()
-        } else {
-            format!("{trimmed}\nworkflow();")
-        }
-    }
+        // Each run gets its own JS runtime on the worker pool so concurrent requests
+        // do not serialize behind one another on the async runtime.
+        let workflow_id = workflow_id.to_string();
+        let workflow_code_id = workflow_code.id.clone();
+        let (results, usage) = crate::workflow_pool::run_workflow_job(move || {
+            let sysconfig = crate::sysconfig::sysconfig();
+            let mut workflow_core = CoreWorkflowCode::new_from_proto(
+                &instrumented_code,
+                sysconfig.core_plugin_package,
+                required_permissions,
+                allowed_permissions,
+            );
 
-    fn decode_page_token(token: &str) -> u64 {
-        token.trim().parse::<u64>().unwrap_or(0)
-    }
+            let run = || {
+                plugin_permission::run_with_permission_context(
+                    &workflow_id,
+                    &workflow_code_id,
+                    denied,
+                    grants,
+                    claim_grant,
+                    || {
+                        secrets::run_as_owner(owner.as_deref(), || {
+                            workflow_core.run(
+                                Handle::current(),
+                                sysconfig.external_plugin_runner_path,
+                                Some(sysconfig.external_plugin_runner_args),
+                            )
+                        })
+                    },
+                )
+            };
+            let run = || {
+                if dry_run {
+                    plugin_permission::run_as_dry_run(run)
+                } else {
+                    run()
+                }
+            };
+            let ((), usage) = match events {
+                Some(events) => {
+                    std_plugin::run_with_console_sink(console_forwarder(events, dry_run), run)
+                }
+                None => run(),
+            };
 
-    fn encode_page_token(offset: u64) -> String {
-        offset.to_string()
+            (std::mem::take(&mut workflow_core.result), usage)
+        })
+        .await
+        .map_err(|err| Status::internal(format!("workflow execution failed: {err}")))?;
+
+        if results.is_empty() {
+            return Err(Status::internal("workflow execution produced no result"));
+        }
+        // Mask secrets before the output is parsed, streamed or persisted.
+        let results = results
+            .into_iter()
+            .map(|mut result| {
+                result.result = redact(&result.result);
+                result
+            })
+            .collect();
+        Ok((results, usage))
     }
 
-    async fn persist_workflow_results(
+    /// Runs a stored workflow like [`Self::execute_workflow`], reporting progress on `events`.
+    ///
+    /// `Console` and `Step` events are sent while the script is running, as each line is
+    /// written; steps left incomplete are sent once it has finished. Send errors are
+    /// ignored so a disconnected listener never aborts the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to run.
+    /// * `workflow_code_id` - Code revision to run; the latest revision is used when `None`.
+    /// * `trigger` - Event that started the run, exposed to the workflow as `globalThis.trigger`.
+    /// * `events` - Channel receiving the run events.
+    ///
+    /// # Returns
+    ///
+    /// Returns the same value as [`Self::execute_workflow`].
+    pub(crate) async fn execute_workflow_with_events(
         &self,
-        workflow: &mut Workflow,
-        workflow_code_id: &str,
-        new_results: &[WorkflowResult],
-    ) -> Result<(), Status> {
-        if let Some(code) = workflow
-            .workflow_code
-            .iter_mut()
-            .find(|c| c.id == workflow_code_id)
-        {
-            let mut combined = code.result.clone();
-            combined.extend_from_slice(new_results);
-            combined.sort_by_key(|r| r.workflow_result_revision);
-            combined.dedup_by(|a, b| a.id == b.id);
-            code.result = combined;
-        }
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+        trigger: Option<&serde_json::Value>,
+        events: &mpsc::UnboundedSender<WorkflowRunEvent>,
+    ) -> Result<WorkflowRun, Status> {
+        let _ = events.send(WorkflowRunEvent::Started {
+            workflow_id: workflow_id.to_string(),
+            workflow_code_id: workflow_code_id.map(str::to_string),
+        });
 
-        if !new_results.is_empty() {
-            let mut combined = workflow.workflow_results.clone();
-            combined.extend_from_slice(new_results);
-            combined.sort_by_key(|r| r.workflow_result_revision);
-            combined.dedup_by(|a, b| a.id == b.id);
-            workflow.workflow_results = combined;
+        let run = self
+            .execute_workflow_inner(workflow_id, workflow_code_id, false, trigger, Some(events))
+            .await;
+        match run {
+            Ok((run, _, _)) => {
+                // Finished steps were sent while the run was in progress.
+                for step in run
+                    .steps
+                    .iter()
+                    .filter(|step| step.status == StepStatus::Incomplete)
+                {
+                    let _ = events.send(WorkflowRunEvent::Step { step: step.clone() });
+                }
+                let _ = events.send(WorkflowRunEvent::Finished {
+                    result: run.result.clone(),
+                });
+                Ok(run)
+            }
+            Err(status) => {
+                let _ = events.send(WorkflowRunEvent::Failed {
+                    status: status.clone(),
+                });
+                Err(status)
+            }
         }
+    }
 
-        workflow.updated_at = Some(Self::now_timestamp());
+    /// Dry-runs a stored workflow like [`Self::plan_workflow`], reporting progress on
+    /// `events` like [`Self::execute_workflow_with_events`]. Each stubbed call is sent as a
+    /// `Planned` event, and `Finished` carries the result that was not persisted.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - Workflow to plan.
+    /// * `workflow_code_id` - Code revision to plan; the latest revision is used when `None`.
+    /// * `events` - Channel receiving the run events.
+    ///
+    /// # Returns
+    ///
+    /// Returns the same value as [`Self::plan_workflow`].
+    pub(crate) async fn plan_workflow_with_events(
+        &self,
+        workflow_id: &str,
+        workflow_code_id: Option<&str>,
+        events: &mpsc::UnboundedSender<WorkflowRunEvent>,
+    ) -> Result<WorkflowPlan, Status> {
+        let _ = events.send(WorkflowRunEvent::Started {
+            workflow_id: workflow_id.to_string(),
+            workflow_code_id: workflow_code_id.map(str::to_string),
+        });
 
-        update_workflow_from_proto(&self.db, workflow)
-            .await
-            .map_err(Self::map_db_error)?;
-        Ok(())
+        let plan = self
+            .plan_workflow(workflow_id, workflow_code_id, Some(events))
+            .await;
+        let _ = events.send(match &plan {
+            // The result was not stored, so it has no ID to look it up by.
+            Ok(plan) => WorkflowRunEvent::Finished {
+                result: WorkflowResult {
+                    id: String::new(),
+                    ..plan.result.clone()
+                },
+            },
+            Err(status) => WorkflowRunEvent::Failed {
+                status: status.clone(),
+            },
+        });
+        plan
     }
 
     fn build_core_permissions(
@@ -251,6 +1833,56 @@ impl MyWorkflowService {
     }
 }
 
+/// Adds permissions granted while a run was waiting on a permission request to the
+/// allowed permissions of the code that ran, skipping ones it already has.
+fn merge_granted_permissions(workflow_code: &mut WorkflowCode, granted: Vec<AllowedPermission>) {
+    for grant in granted {
+        match workflow_code
+            .allowed_permissions
+            .iter_mut()
+            .find(|allowed| allowed.plugin_function_id == grant.plugin_function_id)
+        {
+            Some(allowed) => {
+                for permission in grant.permissions {
+                    if !allowed.permissions.contains(&permission) {
+                        allowed.permissions.push(permission);
+                    }
+                }
+            }
+            None => workflow_code.allowed_permissions.push(grant),
+        }
+    }
+}
+
+/// Returns a console sink that sends each line of a running workflow to `events` as it
+/// is written: step markers become `Step` events, in a dry run the recorded calls
+/// become `Planned` events, and every other line a redacted `Console` event.
+fn console_forwarder(
+    events: mpsc::UnboundedSender<WorkflowRunEvent>,
+    dry_run: bool,
+) -> impl FnMut(&str) {
+    let mut steps = StepTracker::default();
+    move |line| {
+        if let Some(action) = dry_run.then(|| parse_dry_run_line(line)).flatten() {
+            let _ = events.send(WorkflowRunEvent::Planned { action });
+            return;
+        }
+        let event = match steps.push(&format!("{line}\n")) {
+            TrackedLine::Output => WorkflowRunEvent::Console { line: redact(line) },
+            TrackedLine::Marker => return,
+            TrackedLine::Finished(step) => {
+                let mut step = step.clone();
+                step.output = redact(&step.output);
+                if let StepStatus::Failed { error } = &mut step.status {
+                    *error = redact(error);
+                }
+                WorkflowRunEvent::Step { step }
+            }
+        };
+        let _ = events.send(event);
+    }
+}
+
 #[tonic::async_trait]
 impl WorkflowService for MyWorkflowService {
     type FixWorkflowStream =
@@ -262,20 +1894,16 @@ impl WorkflowService for MyWorkflowService {
         &self,
         request: Request<UpdateWorkflowRequest>,
     ) -> Result<Response<UpdateWorkflowResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        validate(&request)?;
+        let expected_version = Self::requested_version(&request)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
         let req = request.into_inner();
         let incoming = req
             .workflow
             .ok_or_else(|| Status::invalid_argument("workflow is required"))?;
 
-        if incoming.id.trim().is_empty() {
-            return Err(Status::invalid_argument("workflow.id must not be empty"));
-        }
-        if incoming.display_name.trim().is_empty() {
-            return Err(Status::invalid_argument(
-                "workflow.display_name must not be empty",
-            ));
-        }
-
         let has_update_mask = req
             .update_mask
             .as_ref()
@@ -287,6 +1915,8 @@ impl WorkflowService for MyWorkflowService {
             has_update_mask = has_update_mask
         );
 
+        self.authorize_workflow_change(&incoming.id, owner.as_deref(), may_change_shared)
+            .await?;
         let existing = get_workflow_by_id(&self.db, &incoming.id)
             .await
             .map_err(|err| Self::map_not_found(err, format!("workflow '{}'", incoming.id)))?;
@@ -299,9 +1929,19 @@ impl WorkflowService for MyWorkflowService {
             desired.created_at = existing.created_at;
         }
 
-        let updated = update_workflow_from_proto(&self.db, &desired)
-            .await
-            .map_err(Self::map_db_error)?;
+        let updated =
+            update_workflow_from_proto_at_version(&self.db, &desired, Some(expected_version))
+                .await
+                .map_err(|err| {
+                    if is_version_conflict(&err) {
+                        Status::failed_precondition(format!(
+                            "workflow '{}' was changed since version {expected_version}; fetch it again and retry",
+                            incoming.id
+                        ))
+                    } else {
+                        Self::map_db_error(err)
+                    }
+                })?;
 
         let response = UpdateWorkflowResponse {
             workflow: Some(updated),
@@ -309,30 +1949,35 @@ impl WorkflowService for MyWorkflowService {
         };
 
         info!(
-            "workflow updated successfully: workflow_id={workflow_id}",
-            workflow_id = incoming.id.as_str()
+            "workflow updated successfully: workflow_id={workflow_id}, version={version}",
+            workflow_id = incoming.id.as_str(),
+            version = expected_version + 1
         );
 
-        Ok(Response::new(response))
+        // The write matched `expected_version` and incremented it.
+        Ok(Self::with_workflow_version(
+            Response::new(response),
+            expected_version + 1,
+        ))
     }
 
     async fn delete_workflow(
         &self,
         request: Request<DeleteWorkflowRequest>,
     ) -> Result<Response<DeleteWorkflowResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        validate(&request)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
         let req = request.into_inner();
-        if req.workflow_id.trim().is_empty() {
-            return Err(Status::invalid_argument("workflow_id must not be empty"));
-        }
 
         info!(
             "delete_workflow request received: workflow_id={workflow_id}",
             workflow_id = req.workflow_id.as_str()
         );
 
-        get_workflow_by_id(&self.db, &req.workflow_id)
-            .await
-            .map_err(|err| Self::map_not_found(err, format!("workflow '{}'", req.workflow_id)))?;
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
 
         workflow_entity::Entity::delete_by_id(req.workflow_id.clone())
             .exec(&*self.db)
@@ -351,6 +1996,9 @@ impl WorkflowService for MyWorkflowService {
         &self,
         request: Request<ListWorkflowsRequest>,
     ) -> Result<Response<ListWorkflowsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
+        let owner = request_owner(&request);
         let req = request.into_inner();
         debug!(
             "list_workflows request received: page_size={page_size}, page_token='{page_token}', has_filter={has_filter}",
@@ -386,13 +2034,18 @@ impl WorkflowService for MyWorkflowService {
             .unwrap_or(DEFAULT_PAGE_SIZE)
             .max(1);
 
-        let mut items = workflow_entity::Entity::find()
-            .order_by_asc(workflow_entity::Column::Id)
-            .offset(offset)
-            .limit(limit.saturating_add(1))
-            .all(&*self.db)
-            .await
-            .map_err(Self::map_db_error)?;
+        let mut items =
+            Self::filtered_workflows(filter_name.as_deref(), filter_language, None, None)
+                .filter(visible_to(
+                    workflow_entity::Column::OwnerId,
+                    owner.as_deref(),
+                ))
+                .order_by_asc(workflow_entity::Column::Id)
+                .offset(offset)
+                .limit(limit.saturating_add(1))
+                .all(&*self.db)
+                .await
+                .map_err(Self::map_db_error)?;
 
         let has_next = (items.len() as u64) > limit;
         if has_next {
@@ -410,15 +2063,6 @@ impl WorkflowService for MyWorkflowService {
             let workflow = get_workflow_by_id(&self.db, &item.id)
                 .await
                 .map_err(|err| Self::map_not_found(err, format!("workflow '{}'", item.id)))?;
-
-            if matches!(filter_name.as_deref(), Some(name) if !workflow.display_name.contains(name))
-            {
-                continue;
-            }
-            if matches!(filter_language, Some(lang) if workflow.workflow_language != lang) {
-                continue;
-            }
-
             workflows.push(workflow);
         }
 
@@ -440,17 +2084,15 @@ impl WorkflowService for MyWorkflowService {
         &self,
         request: Request<FixWorkflowRequest>,
     ) -> Result<Response<Self::FixWorkflowStream>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        validate(&request)?;
+        let model = Self::requested_model(&request)?;
+        let requested_workflow_id = Self::metadata_value(&request, WORKFLOW_ID_METADATA_KEY)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
         let req = request.into_inner();
         let definition = req.workflow_definition.trim().to_string();
-        if definition.is_empty() {
-            return Err(Status::invalid_argument(
-                "workflow_definition must not be empty",
-            ));
-        }
         let description = req.description.trim().to_string();
-        if description.is_empty() {
-            return Err(Status::invalid_argument("description must not be empty"));
-        }
 
         info!(
             "fix_workflow request received: definition_len={definition_len}, description_len={description_len}",
@@ -462,47 +2104,113 @@ impl WorkflowService for MyWorkflowService {
             "Fix the following workflow definition based on the issues described.\\n\\nDefinition:```\\n{definition}\\n```\\n\\nIssues: {description}.\\n\\nProduce an updated workflow.js implementation.",
         );
 
-        let generated = generate_workflow_async(&prompt).await.map_err(|err| {
-            error!("failed to fix workflow via generator: {err}");
-            Status::internal("failed to fix workflow")
-        })?;
-
-        let workflow_id = uuid::Uuid::new_v4().to_string();
-        let workflow_code_id = uuid::Uuid::new_v4().to_string();
-        let timestamp = Self::now_timestamp();
-        let workflow = Workflow {
-            id: workflow_id.clone(),
-            display_name: "Fixed Workflow".to_string(),
-            description,
-            workflow_language: WORKFLOW_LANGUAGE_JS,
-            workflow_code: vec![WorkflowCode {
-                id: workflow_code_id,
-                code_revision: 1,
-                code: Self::sanitize_generated_code(&generated),
+        let provider = self.generation_provider(model.as_deref()).await?;
+        let plugins = self.plugin_catalog().await?;
+        let generated = generate_workflow_async(provider.as_ref(), &prompt, &plugins)
+            .await
+            .map_err(|err| {
+                error!("failed to fix workflow via generator: {err}");
+                Status::internal("failed to fix workflow")
+            })?;
+        let code = Self::sanitize_generated_code(&generated);
+
+        // A fix becomes the next revision of the workflow it was made for: the one named
+        // by the metadata, or else the one that has the definition as a revision.
+        let target_workflow_id = match requested_workflow_id {
+            Some(workflow_id) => {
+                self.authorize_workflow_change(&workflow_id, owner.as_deref(), may_change_shared)
+                    .await?;
+                Some(workflow_id)
+            }
+            None => match find_workflow_id_by_code(&self.db, &definition)
+                .await
+                .map_err(Self::map_db_error)?
+            {
+                // Code shared by another user's workflow does not make the fix theirs.
+                Some(workflow_id)
+                    if self
+                        .authorize_workflow_change(
+                            &workflow_id,
+                            owner.as_deref(),
+                            may_change_shared,
+                        )
+                        .await
+                        .is_ok() =>
+                {
+                    Some(workflow_id)
+                }
+                _ => None,
+            },
+        };
+        let (stored, change_summary) = if let Some(workflow_id) = target_workflow_id {
+            let workflow = get_workflow_by_id(&self.db, &workflow_id)
+                .await
+                .map_err(|err| Self::map_not_found(err, format!("workflow '{workflow_id}'")))?;
+            let previous = Self::latest_code(&workflow)
+                .map(|latest| latest.code.clone())
+                .unwrap_or_else(|| definition.clone());
+            let diff = diff_code(&previous, &code);
+            let summary =
+                match summarize_code_change(provider.as_ref(), &description, &render_diff(&diff))
+                    .await
+                {
+                    Ok(summary) => summary,
+                    Err(err) => {
+                        warn!("failed to summarize workflow fix: {err}");
+                        describe_diff(&diff)
+                    }
+                };
+            let revision = WorkflowCode {
+                code,
                 language: WORKFLOW_LANGUAGE_JS,
+                ..WorkflowCode::default()
+            };
+            let (stored, _) = self
+                .append_code_revision(workflow, revision, &diff, &summary)
+                .await?;
+            (stored, summary)
+        } else {
+            let workflow_id = uuid::Uuid::new_v4().to_string();
+            let workflow_code_id = uuid::Uuid::new_v4().to_string();
+            let timestamp = Self::now_timestamp();
+            let workflow = Workflow {
+                id: workflow_id,
+                display_name: "Fixed Workflow".to_string(),
+                description,
+                workflow_language: WORKFLOW_LANGUAGE_JS,
+                workflow_code: vec![WorkflowCode {
+                    id: workflow_code_id,
+                    code_revision: 1,
+                    code,
+                    language: WORKFLOW_LANGUAGE_JS,
+                    created_at: Some(timestamp),
+                    result: vec![],
+                    plugin_packages: vec![],
+                    plugin_function_ids: vec![],
+                    allowed_permissions: vec![],
+                }],
                 created_at: Some(timestamp),
-                result: vec![],
-                plugin_packages: vec![],
-                plugin_function_ids: vec![],
-                allowed_permissions: vec![],
-            }],
-            created_at: Some(timestamp),
-            updated_at: Some(timestamp),
-            workflow_results: vec![],
+                updated_at: Some(timestamp),
+                workflow_results: vec![],
+            };
+
+            let stored = self
+                .create_owned_workflow(&workflow, owner.as_deref())
+                .await?;
+            (stored, "Generated updated workflow definition".to_string())
         };
 
-        let stored = update_workflow_from_proto(&self.db, &workflow)
-            .await
-            .map_err(Self::map_db_error)?;
+        info!(
+            "workflow fix generated: workflow_id={workflow_id}",
+            workflow_id = stored.id.as_str()
+        );
 
         let response = FixWorkflowResponse {
             fixed_workflow_definition: Some(stored),
-            change_summary: "Generated updated workflow definition".to_string(),
+            change_summary,
             status: Self::ok_status("workflow fixed"),
         };
 
-        info!("workflow fix generated: workflow_id={workflow_id}");
-
         let (tx, rx) = mpsc::channel(1);
         tokio::spawn(async move {
             let _ = tx.send(Ok(response)).await;
@@ -517,16 +2225,22 @@ impl WorkflowService for MyWorkflowService {
         &self,
         request: Request<GetWorkflowRequest>,
     ) -> Result<Response<GetWorkflowResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        validate(&request)?;
+        let owner = request_owner(&request);
         let req = request.into_inner();
-        if req.workflow_id.trim().is_empty() {
-            return Err(Status::invalid_argument("workflow_id must not be empty"));
-        }
 
         debug!(
             "get_workflow request received: workflow_id={workflow_id}",
             workflow_id = req.workflow_id.as_str()
         );
 
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+        // Read the version first; a write in between only makes it stale, never newer.
+        let version = get_workflow_version(&self.db, &req.workflow_id)
+            .await
+            .map_err(|err| Self::map_not_found(err, format!("workflow '{}'", req.workflow_id)))?;
         let workflow = get_workflow_by_id(&self.db, &req.workflow_id)
             .await
             .map_err(|err| Self::map_not_found(err, format!("workflow '{}'", req.workflow_id)))?;
@@ -537,31 +2251,39 @@ impl WorkflowService for MyWorkflowService {
         };
 
         debug!(
-            "workflow retrieved: workflow_id={workflow_id}",
+            "workflow retrieved: workflow_id={workflow_id}, version={version}",
             workflow_id = req.workflow_id.as_str()
         );
 
-        Ok(Response::new(response))
+        Ok(Self::with_workflow_version(
+            Response::new(response),
+            version,
+        ))
     }
 
     async fn generate_workflow(
         &self,
         request: Request<GenerateWorkflowRequest>,
     ) -> Result<Response<Self::GenerateWorkflowStream>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        validate(&request)?;
+        let model = Self::requested_model(&request)?;
+        let owner = request_owner(&request);
         let req = request.into_inner();
-        if req.prompt.trim().is_empty() {
-            return Err(Status::invalid_argument("prompt must not be empty"));
-        }
 
         info!(
             "generate_workflow request received: prompt_len={prompt_len}",
             prompt_len = req.prompt.len()
         );
 
-        let generated = generate_workflow_async(&req.prompt).await.map_err(|err| {
-            error!("failed to generate workflow via generator: {err}");
-            Status::internal("failed to generate workflow")
-        })?;
+        let provider = self.generation_provider(model.as_deref()).await?;
+        let plugins = self.plugin_catalog().await?;
+        let generated = generate_workflow_async(provider.as_ref(), &req.prompt, &plugins)
+            .await
+            .map_err(|err| {
+                error!("failed to generate workflow via generator: {err}");
+                Status::internal("failed to generate workflow")
+            })?;
 
         let workflow_id = uuid::Uuid::new_v4().to_string();
         let workflow_code_id = uuid::Uuid::new_v4().to_string();
@@ -589,9 +2311,9 @@ impl WorkflowService for MyWorkflowService {
             workflow_results: vec![],
         };
 
-        let stored = update_workflow_from_proto(&self.db, &workflow)
-            .await
-            .map_err(Self::map_db_error)?;
+        let stored = self
+            .create_owned_workflow(&workflow, owner.as_deref())
+            .await?;
 
         let response = GenerateWorkflowResponse {
             workflow_definition: Some(stored),
@@ -619,9 +2341,11 @@ impl WorkflowService for MyWorkflowService {
         &self,
         request: Request<RunWorkflowRequest>,
     ) -> Result<Response<RunWorkflowResponse>, Status> {
+        require_scope(&request, TokenScope::Run)?;
+        validate(&request)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
         let req = request.into_inner();
-        let persist_results = true;
-        let target_code_id: Option<String>;
 
         let source_label = match &req.by_id {
             Some(_) => "by_id",
@@ -629,116 +2353,21 @@ impl WorkflowService for MyWorkflowService {
         };
         info!("run_workflow request received: source={source_label}");
 
-        let mut workflow = match req.by_id {
-            Some(by_id) => {
-                if by_id.workflow_id.trim().is_empty() {
-                    return Err(Status::invalid_argument("workflow_id must not be empty"));
-                }
-                if by_id.workflow_code_id.trim().is_empty() {
-                    return Err(Status::invalid_argument(
-                        "workflow_code_id must not be empty",
-                    ));
-                }
-                target_code_id = Some(by_id.workflow_code_id.clone());
-                get_workflow_by_id(&self.db, &by_id.workflow_id)
-                    .await
-                    .map_err(|err| {
-                        Self::map_not_found(err, format!("workflow '{}'", by_id.workflow_id))
-                    })?
-            }
-            None => {
-                return Err(Status::invalid_argument(
-                    "RunWorkflowRequest.by_id is required",
-                ));
-            }
-        };
-
-        let latest_revision = workflow
-            .workflow_code
-            .iter()
-            .map(|code| code.code_revision)
-            .max()
-            .unwrap_or(0);
-
-        let workflow_code = if let Some(ref code_id) = target_code_id {
-            workflow
-                .workflow_code
-                .iter_mut()
-                .find(|code| code.id == *code_id)
-                .ok_or_else(|| Status::not_found(format!("workflow code '{code_id}' not found")))?
-        } else {
-            workflow
-                .workflow_code
-                .iter_mut()
-                .find(|code| code.code_revision == latest_revision)
-                .ok_or_else(|| Status::not_found("Latest workflow code not found"))?
-        };
-
-        workflow_code.code = match unescaper::unescape(&workflow_code.code) {
-            Ok(code) => code,
-            Err(err) => {
-                warn!("failed to unescape workflow code: {err}");
-                workflow_code.code.clone()
-            }
-        };
-
-        let workflow_code_id = workflow_code.id.clone();
-
-        let (required_permissions, allowed_permissions) =
-            Self::build_core_permissions(workflow_code);
-
-        let results = {
-            let mut workflow_core = CoreWorkflowCode::new_from_proto(
-                workflow_code,
-                crate::sysconfig::sysconfig().core_plugin_package,
-                required_permissions,
-                allowed_permissions,
-            );
-
-            let sysconfig = crate::sysconfig::sysconfig();
-            workflow_core.run(
-                Handle::current(),
-                sysconfig.external_plugin_runner_path,
-                Some(sysconfig.external_plugin_runner_args),
-            );
-
-            if workflow_core.result.is_empty() {
-                return Err(Status::internal("workflow execution produced no result"));
-            }
-
-            workflow_core.result.clone()
-        };
-
-        let latest_result_revision = results
-            .iter()
-            .map(|r| r.workflow_result_revision)
-            .max()
-            .unwrap_or(0);
-
-        let latest_result = results
-            .iter()
-            .find(|r| r.workflow_result_revision == latest_result_revision)
-            .cloned()
-            .ok_or_else(|| Status::not_found("workflow result missing"))?;
+        let by_id = req
+            .by_id
+            .ok_or_else(|| Status::invalid_argument("RunWorkflowRequest.by_id is required"))?;
 
-        if persist_results {
-            let mut workflow_clone = workflow.clone();
-            self.persist_workflow_results(&mut workflow_clone, &workflow_code_id, &results)
-                .await?;
-        }
+        self.authorize_workflow_change(&by_id.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+        let run = self
+            .execute_workflow(&by_id.workflow_id, Some(&by_id.workflow_code_id))
+            .await?;
 
         let response = RunWorkflowResponse {
-            workflow_result: Some(latest_result.clone()),
+            workflow_result: Some(run.result),
             status: Self::ok_status("workflow executed successfully"),
         };
 
-        info!(
-            "workflow executed: workflow_id={workflow_id}, workflow_code_id={workflow_code_id}, result_revision={result_revision}",
-            workflow_id = workflow.id.as_str(),
-            workflow_code_id = workflow_code_id.as_str(),
-            result_revision = latest_result.workflow_result_revision
-        );
-
         Ok(Response::new(response))
     }
 }
@@ -801,6 +2430,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn list_filters_are_part_of_the_query() {
+        use sea_orm::{DbBackend, QueryTrait};
+
+        assert_eq!(MyWorkflowService::escape_like(r"50%_off\"), r"50\%\_off\\");
+
+        let sql = MyWorkflowService::filtered_workflows(
+            Some("report"),
+            Some(WORKFLOW_LANGUAGE_JS),
+            None,
+            None,
+        )
+        .build(DbBackend::Sqlite)
+        .to_string();
+        assert!(
+            sql.contains(r#""workflow"."display_name" LIKE '%report%' ESCAPE"#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(&format!(
+                r#""workflow"."workflow_language" = {WORKFLOW_LANGUAGE_JS}"#
+            )),
+            "{sql}"
+        );
+
+        let unfiltered = MyWorkflowService::filtered_workflows(None, None, None, None)
+            .build(DbBackend::Sqlite)
+            .to_string();
+        assert!(!unfiltered.contains("WHERE"), "{unfiltered}");
+    }
+
+    #[test]
+    fn tag_and_folder_filters_select_from_their_tables() {
+        use sea_orm::{DbBackend, QueryTrait};
+
+        let sql = MyWorkflowService::filtered_workflows(None, None, Some("daily"), Some("work_1"))
+            .build(DbBackend::Sqlite)
+            .to_string();
+        assert!(
+            sql.contains(r#"FROM "workflow_tag" WHERE "workflow_tag"."tag" = 'daily'"#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#""workflow_folder"."folder" = 'work_1'"#),
+            "{sql}"
+        );
+        // The escaped `_` is quoted differently across sea-query versions.
+        assert!(
+            sql.contains(r#""workflow_folder"."folder" LIKE 'work"#),
+            "{sql}"
+        );
+        assert!(sql.contains(r#"_1/%' ESCAPE"#), "{sql}");
+    }
+
+    #[test]
+    fn update_requires_a_workflow_version() {
+        let request = |version: Option<&'static str>| {
+            let mut request = Request::new(());
+            if let Some(version) = version {
+                request
+                    .metadata_mut()
+                    .insert(WORKFLOW_VERSION_METADATA_KEY, version.parse().unwrap());
+            }
+            request
+        };
+
+        assert_eq!(
+            MyWorkflowService::requested_version(&request(Some(" 7 "))).unwrap(),
+            7
+        );
+        for version in [None, Some("0"), Some("seven")] {
+            let err = MyWorkflowService::requested_version(&request(version)).unwrap_err();
+            assert_eq!(err.code(), Code::InvalidArgument);
+        }
+
+        let response = MyWorkflowService::with_workflow_version(Response::new(()), 8);
+        assert_eq!(
+            response
+                .metadata()
+                .get(WORKFLOW_VERSION_METADATA_KEY)
+                .unwrap(),
+            "8"
+        );
+    }
+
     #[test]
     fn sanitize_generated_code_appends_workflow_call() {
         let raw = "function workflow() {\n  return 42;\n}";
@@ -901,6 +2615,47 @@ mod tests {
         assert_eq!(required[1].permissions.permissions.len(), 1);
     }
 
+    #[test]
+    fn merge_granted_permissions_skips_known_permissions() {
+        let permission = |resource: &str| Permission {
+            display_name: "read".to_string(),
+            description: "".to_string(),
+            permission_type: PermissionType::FilesystemRead as i32,
+            permission_level: PermissionLevel::Unspecified as i32,
+            resource: vec![resource.to_string()],
+        };
+        let mut workflow = base_workflow();
+        let workflow_code = &mut workflow.workflow_code[0];
+        workflow_code.allowed_permissions = vec![AllowedPermission {
+            plugin_function_id: "func1".to_string(),
+            permissions: vec![permission("/a")],
+        }];
+
+        merge_granted_permissions(
+            workflow_code,
+            vec![
+                AllowedPermission {
+                    plugin_function_id: "func1".to_string(),
+                    permissions: vec![permission("/a"), permission("/b")],
+                },
+                AllowedPermission {
+                    plugin_function_id: "func2".to_string(),
+                    permissions: vec![permission("/c")],
+                },
+            ],
+        );
+
+        assert_eq!(workflow_code.allowed_permissions.len(), 2);
+        assert_eq!(
+            workflow_code.allowed_permissions[0].permissions,
+            vec![permission("/a"), permission("/b")]
+        );
+        assert_eq!(
+            workflow_code.allowed_permissions[1].plugin_function_id,
+            "func2"
+        );
+    }
+
     #[test]
     fn missing_allowed_permission_results_in_denial() {
         let mut workflow = base_workflow();
@@ -0,0 +1,956 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// RPCs on stored workflows beyond those of WorkflowService
+
+use std::pin::Pin;
+
+use chrono::{DateTime, Utc};
+use database::recording::{delete_recording, get_recording, list_recordings};
+use database::workflow::workflow_result_crud::{
+    WorkflowResultFilter, get_workflow_result, list_workflow_results_filtered,
+};
+use database::workflow::{delete_workflow_result_by_id, delete_workflow_results_before};
+use entity::entity::workflow_result::Model as WorkflowResultModel;
+use sapphillon_core::proto::google::protobuf::Timestamp as CoreTimestamp;
+use sapphillon_core::proto::sapphillon::v1::AllowedPermission;
+use sea_orm::DbErr;
+use tokio::sync::mpsc;
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use super::workflow::{MyWorkflowService, WorkflowRunEvent};
+use crate::args::TokenScope;
+use crate::auth::{request_may_change_shared, request_owner, require_scope};
+use crate::proto::sapphillon::server::v1::run_workflow_stream_response::Event as RunEvent;
+use crate::proto::sapphillon::server::v1::workflow_management_service_server::WorkflowManagementService;
+use crate::proto::sapphillon::server::v1::{
+    ClearWorkflowStateRequest, ClearWorkflowStateResponse, CodeRevision,
+    DeleteWorkflowArtifactRequest, DeleteWorkflowArtifactResponse, DeleteWorkflowResultRequest,
+    DeleteWorkflowResultResponse, DeleteWorkflowResultsRequest, DeleteWorkflowResultsResponse,
+    DiagnoseWorkflowResultRequest, DiagnoseWorkflowResultResponse, DiffHunk,
+    ExplainWorkflowRequest, ExplainWorkflowResponse, FunctionPermissions, GetToolCatalogRequest,
+    GetToolCatalogResponse, ListOrganizedWorkflowsRequest, ListOrganizedWorkflowsResponse,
+    ListWorkflowCodeRevisionsRequest, ListWorkflowCodeRevisionsResponse,
+    ListWorkflowRecordingsRequest, ListWorkflowRecordingsResponse, ListWorkflowResultsRequest,
+    ListWorkflowResultsResponse, ListWorkflowStateRequest, ListWorkflowStateResponse,
+    MoveWorkflowToFolderRequest, MoveWorkflowToFolderResponse, OrganizedWorkflow, PlannedCall,
+    PreviewWorkflowPermissionsRequest, PreviewWorkflowPermissionsResponse, PruneResultsRequest,
+    PruneResultsResponse, ReplayWorkflowRecordingRequest, ReplayWorkflowRecordingResponse,
+    RollbackWorkflowCodeRequest, RollbackWorkflowCodeResponse, RunStarted, RunStep,
+    RunWorkflowStreamRequest, RunWorkflowStreamResponse, SearchHit, SearchWorkflowContentRequest,
+    SearchWorkflowContentResponse, SetWorkflowTagsRequest, SetWorkflowTagsResponse,
+    WorkflowRecording, WorkflowRunResult, WorkflowStateEntry,
+};
+use crate::proto::{permission, timestamp};
+use crate::workflow_revisions;
+use crate::workflow_steps::StepStatus;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// Treats an empty string field as unset.
+fn non_empty(value: &str) -> Option<&str> {
+    Some(value.trim()).filter(|value| !value.is_empty())
+}
+
+fn parse_timestamp(field: &str, value: prost_types::Timestamp) -> Result<DateTime<Utc>, Status> {
+    u32::try_from(value.nanos)
+        .ok()
+        .and_then(|nanos| DateTime::from_timestamp(value.seconds, nanos))
+        .ok_or_else(|| Status::invalid_argument(format!("{field} is not a valid timestamp")))
+}
+
+fn core_timestamp(value: CoreTimestamp) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: value.seconds,
+        nanos: value.nanos,
+    }
+}
+
+fn result_message(result: WorkflowResultModel) -> WorkflowRunResult {
+    WorkflowRunResult {
+        id: result.id,
+        workflow_id: result.workflow_id,
+        workflow_code_id: result.workflow_code_id,
+        ran_at: result.ran_at.map(timestamp),
+        result_type: result.result_type,
+        exit_code: result.exit_code.unwrap_or_default(),
+        workflow_result_revision: result.workflow_result_revision,
+        output: result.result.unwrap_or_default(),
+    }
+}
+
+fn diff_message(diff: Vec<workflow_revisions::DiffHunk>) -> Vec<DiffHunk> {
+    diff.into_iter()
+        .map(|hunk| DiffHunk {
+            old_start: hunk.old_start as u64,
+            old_lines: hunk.old_lines as u64,
+            new_start: hunk.new_start as u64,
+            new_lines: hunk.new_lines as u64,
+            lines: hunk.lines,
+        })
+        .collect()
+}
+
+fn permissions_message(permissions: Vec<AllowedPermission>) -> Vec<FunctionPermissions> {
+    permissions
+        .into_iter()
+        .map(|allowed| FunctionPermissions {
+            plugin_function_id: allowed.plugin_function_id,
+            permissions: allowed.permissions.into_iter().map(permission).collect(),
+        })
+        .collect()
+}
+
+impl MyWorkflowService {
+    /// Turns a run event into the message streamed by `RunWorkflowStream`.
+    async fn run_event_message(
+        &self,
+        workflow_id: &str,
+        event: WorkflowRunEvent,
+    ) -> Result<RunWorkflowStreamResponse, Status> {
+        let event = match event {
+            WorkflowRunEvent::Started {
+                workflow_id,
+                workflow_code_id,
+            } => RunEvent::Started(RunStarted {
+                workflow_id,
+                workflow_code_id: workflow_code_id.unwrap_or_default(),
+            }),
+            WorkflowRunEvent::Console { line } => RunEvent::Console(line),
+            WorkflowRunEvent::Step { step } => {
+                let (status, error) = match step.status {
+                    StepStatus::Succeeded => ("succeeded", String::new()),
+                    StepStatus::Failed { error } => ("failed", error),
+                    StepStatus::Incomplete => ("incomplete", String::new()),
+                };
+                RunEvent::Step(RunStep {
+                    name: step.name,
+                    status: status.to_string(),
+                    error,
+                    duration_ms: step.duration_ms,
+                    output: step.output,
+                })
+            }
+            WorkflowRunEvent::Finished { result } => {
+                let stored = get_workflow_result(&self.db, &result.id)
+                    .await
+                    .map_err(Self::map_db_error)?;
+                RunEvent::Finished(match stored {
+                    Some(stored) => result_message(stored),
+                    None => WorkflowRunResult {
+                        id: result.id,
+                        workflow_id: workflow_id.to_string(),
+                        workflow_code_id: String::new(),
+                        ran_at: result.ran_at.map(core_timestamp),
+                        result_type: result.result_type,
+                        exit_code: result.exit_code,
+                        workflow_result_revision: result.workflow_result_revision,
+                        output: result.result,
+                    },
+                })
+            }
+            WorkflowRunEvent::Planned { action } => RunEvent::Planned(PlannedCall {
+                function: action.function,
+                args: serde_json::to_string(&action.args).unwrap_or_default(),
+            }),
+            WorkflowRunEvent::Failed { status } => return Err(status),
+        };
+        Ok(RunWorkflowStreamResponse { event: Some(event) })
+    }
+}
+
+#[tonic::async_trait]
+impl WorkflowManagementService for MyWorkflowService {
+    type RunWorkflowStreamStream =
+        Pin<Box<dyn Stream<Item = Result<RunWorkflowStreamResponse, Status>> + Send + 'static>>;
+
+    async fn run_workflow_stream(
+        &self,
+        request: Request<RunWorkflowStreamRequest>,
+    ) -> Result<Response<Self::RunWorkflowStreamStream>, Status> {
+        require_scope(&request, TokenScope::Run)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        debug!(
+            "run_workflow_stream request received: workflow_id={}, workflow_code_id={:?}, dry_run={}",
+            req.workflow_id, req.workflow_code_id, req.dry_run
+        );
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+
+        let workflow_id = req.workflow_id.clone();
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let runner = self.clone();
+        tokio::spawn(async move {
+            let workflow_code_id = non_empty(&req.workflow_code_id);
+            if req.dry_run {
+                let _ = runner
+                    .plan_workflow_with_events(&req.workflow_id, workflow_code_id, &events_tx)
+                    .await;
+            } else {
+                let _ = runner
+                    .execute_workflow_with_events(
+                        &req.workflow_id,
+                        workflow_code_id,
+                        None,
+                        &events_tx,
+                    )
+                    .await;
+            }
+        });
+
+        let (tx, rx) = mpsc::channel(16);
+        let service = self.clone();
+        tokio::spawn(async move {
+            // Keep draining after the caller disconnects so the run is never aborted.
+            while let Some(event) = events.recv().await {
+                let message = service.run_event_message(&workflow_id, event).await;
+                let failed = message.is_err();
+                let _ = tx.send(message).await;
+                if failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as Self::RunWorkflowStreamStream
+        ))
+    }
+
+    async fn list_workflow_results(
+        &self,
+        request: Request<ListWorkflowResultsRequest>,
+    ) -> Result<Response<ListWorkflowResultsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        debug!(
+            "list_workflow_results request received: workflow_id={}, page_size={}, page_token='{}'",
+            req.workflow_id, req.page_size, req.page_token
+        );
+
+        let workflow_id = non_empty(&req.workflow_id).map(str::to_string);
+        if let Some(workflow_id) = &workflow_id {
+            self.authorize_workflow(workflow_id, owner.as_deref())
+                .await?;
+        }
+        let filter = WorkflowResultFilter {
+            workflow_id,
+            ran_after: req
+                .since
+                .map(|since| parse_timestamp("since", since))
+                .transpose()?,
+            ran_before: req
+                .until
+                .map(|until| parse_timestamp("until", until))
+                .transpose()?,
+            exit_code: req.exit_code,
+            owner_id: owner,
+        };
+        let page_size = if req.page_size <= 0 {
+            None
+        } else {
+            Some(req.page_size as u32)
+        };
+        let page_token = non_empty(&req.page_token).map(str::to_string);
+
+        let (results, next_page_token) =
+            list_workflow_results_filtered(&self.db, &filter, page_token, page_size)
+                .await
+                .map_err(Self::map_db_error)?;
+
+        Ok(Response::new(ListWorkflowResultsResponse {
+            results: results.into_iter().map(result_message).collect(),
+            next_page_token,
+        }))
+    }
+
+    async fn prune_results(
+        &self,
+        request: Request<PruneResultsRequest>,
+    ) -> Result<Response<PruneResultsResponse>, Status> {
+        require_scope(&request, TokenScope::Admin)?;
+        let policy = crate::sysconfig::sysconfig().result_retention;
+        let deleted = crate::result_retention::prune_results(&self.db, &policy)
+            .await
+            .map_err(|err| {
+                error!("failed to prune workflow results: {err:#}");
+                Status::internal("failed to prune workflow results")
+            })?;
+        info!("Pruned {deleted} workflow result(s)");
+
+        Ok(Response::new(PruneResultsResponse { deleted }))
+    }
+
+    async fn delete_workflow_result(
+        &self,
+        request: Request<DeleteWorkflowResultRequest>,
+    ) -> Result<Response<DeleteWorkflowResultResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found =
+            || Status::not_found(format!("workflow result '{}' not found", req.result_id));
+
+        let result = get_workflow_result(&self.db, &req.result_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        self.authorize_workflow_change(&result.workflow_id, owner.as_deref(), may_change_shared)
+            .await
+            .map_err(|_| not_found())?;
+        match delete_workflow_result_by_id(&self.db, &result.id).await {
+            Ok(()) => {}
+            Err(DbErr::RecordNotFound(_)) => return Err(not_found()),
+            Err(err) => return Err(Self::map_db_error(err)),
+        }
+        info!("Deleted workflow result: {}", result.id);
+
+        Ok(Response::new(DeleteWorkflowResultResponse {}))
+    }
+
+    async fn delete_workflow_results(
+        &self,
+        request: Request<DeleteWorkflowResultsRequest>,
+    ) -> Result<Response<DeleteWorkflowResultsResponse>, Status> {
+        let workflow_id = non_empty(&request.get_ref().workflow_id).map(str::to_string);
+        match &workflow_id {
+            Some(_) => require_scope(&request, TokenScope::Write)?,
+            // Results of every owner
+            None => require_scope(&request, TokenScope::Admin)?,
+        }
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let before = req
+            .before
+            .map(|before| parse_timestamp("before", before))
+            .transpose()?
+            .ok_or_else(|| Status::invalid_argument("before is required"))?;
+        if let Some(workflow_id) = &workflow_id {
+            self.authorize_workflow_change(workflow_id, owner.as_deref(), may_change_shared)
+                .await?;
+        }
+
+        let deleted = delete_workflow_results_before(&self.db, before, workflow_id.as_deref())
+            .await
+            .map_err(Self::map_db_error)?;
+        info!("Deleted {deleted} workflow result(s) that ran before {before}");
+
+        Ok(Response::new(DeleteWorkflowResultsResponse { deleted }))
+    }
+
+    async fn delete_workflow_artifact(
+        &self,
+        request: Request<DeleteWorkflowArtifactRequest>,
+    ) -> Result<Response<DeleteWorkflowArtifactResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found = || Status::not_found(format!("artifact '{}' not found", req.artifact_id));
+
+        let recording = get_recording(&self.db, &req.artifact_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        self.authorize_workflow_change(&recording.workflow_id, owner.as_deref(), may_change_shared)
+            .await
+            .map_err(|_| not_found())?;
+        if delete_recording(&self.db, &recording.id)
+            .await
+            .map_err(Self::map_db_error)?
+            == 0
+        {
+            return Err(not_found());
+        }
+        info!("Deleted recording: {}", recording.id);
+
+        Ok(Response::new(DeleteWorkflowArtifactResponse {}))
+    }
+
+    async fn diagnose_workflow_result(
+        &self,
+        request: Request<DiagnoseWorkflowResultRequest>,
+    ) -> Result<Response<DiagnoseWorkflowResultResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found =
+            || Status::not_found(format!("workflow result '{}' not found", req.result_id));
+
+        let result = get_workflow_result(&self.db, &req.result_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        self.authorize_workflow_change(&result.workflow_id, owner.as_deref(), may_change_shared)
+            .await
+            .map_err(|_| not_found())?;
+
+        let diagnosis = MyWorkflowService::diagnose_workflow_result(
+            self,
+            &req.result_id,
+            non_empty(&req.model),
+            req.apply,
+        )
+        .await?;
+        let applied = diagnosis.applied.unwrap_or_default();
+
+        Ok(Response::new(DiagnoseWorkflowResultResponse {
+            workflow_id: diagnosis.workflow_id,
+            explanation: diagnosis.explanation,
+            diff: diff_message(diagnosis.diff),
+            applied_workflow_code_id: applied.id,
+            applied_code_revision: applied.code_revision,
+        }))
+    }
+
+    async fn preview_workflow_permissions(
+        &self,
+        request: Request<PreviewWorkflowPermissionsRequest>,
+    ) -> Result<Response<PreviewWorkflowPermissionsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+
+        let preview = MyWorkflowService::preview_workflow_permissions(
+            self,
+            &req.workflow_id,
+            non_empty(&req.workflow_code_id),
+        )
+        .await?;
+
+        Ok(Response::new(PreviewWorkflowPermissionsResponse {
+            required: permissions_message(preview.required),
+            missing: permissions_message(preview.missing),
+        }))
+    }
+
+    async fn explain_workflow(
+        &self,
+        request: Request<ExplainWorkflowRequest>,
+    ) -> Result<Response<ExplainWorkflowResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+
+        let explanation = MyWorkflowService::explain_workflow(
+            self,
+            &req.workflow_id,
+            non_empty(&req.workflow_code_id),
+            non_empty(&req.model),
+        )
+        .await?;
+
+        Ok(Response::new(ExplainWorkflowResponse {
+            workflow_code_id: explanation.workflow_code_id,
+            steps: explanation.steps,
+            risk_level: explanation.risk.level.as_str().to_string(),
+            risk_reasons: explanation.risk.reasons,
+            required: permissions_message(explanation.permissions.required),
+            missing: permissions_message(explanation.permissions.missing),
+        }))
+    }
+
+    async fn list_workflow_code_revisions(
+        &self,
+        request: Request<ListWorkflowCodeRevisionsRequest>,
+    ) -> Result<Response<ListWorkflowCodeRevisionsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+
+        let revisions = self
+            .list_code_revisions(&req.workflow_id)
+            .await?
+            .into_iter()
+            .map(|revision| CodeRevision {
+                workflow_code_id: revision.workflow_code_id,
+                code_revision: revision.code_revision,
+                created_at: revision.created_at.map(core_timestamp),
+                previous_code_id: revision.previous_code_id.unwrap_or_default(),
+                summary: revision.summary.unwrap_or_default(),
+                diff: diff_message(revision.diff),
+            })
+            .collect();
+
+        Ok(Response::new(ListWorkflowCodeRevisionsResponse {
+            revisions,
+        }))
+    }
+
+    async fn rollback_workflow_code(
+        &self,
+        request: Request<RollbackWorkflowCodeRequest>,
+    ) -> Result<Response<RollbackWorkflowCodeResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+
+        let code =
+            MyWorkflowService::rollback_workflow_code(self, &req.workflow_id, req.code_revision)
+                .await?;
+
+        Ok(Response::new(RollbackWorkflowCodeResponse {
+            workflow_code_id: code.id,
+            code_revision: code.code_revision,
+        }))
+    }
+
+    async fn set_workflow_tags(
+        &self,
+        request: Request<SetWorkflowTagsRequest>,
+    ) -> Result<Response<SetWorkflowTagsResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+
+        let tags = MyWorkflowService::set_workflow_tags(self, &req.workflow_id, &req.tags).await?;
+        Ok(Response::new(SetWorkflowTagsResponse { tags }))
+    }
+
+    async fn move_workflow_to_folder(
+        &self,
+        request: Request<MoveWorkflowToFolderRequest>,
+    ) -> Result<Response<MoveWorkflowToFolderResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+
+        let folder =
+            MyWorkflowService::move_workflow_to_folder(self, &req.workflow_id, &req.folder).await?;
+        Ok(Response::new(MoveWorkflowToFolderResponse {
+            folder: folder.unwrap_or_default(),
+        }))
+    }
+
+    async fn list_organized_workflows(
+        &self,
+        request: Request<ListOrganizedWorkflowsRequest>,
+    ) -> Result<Response<ListOrganizedWorkflowsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+
+        let workflows = MyWorkflowService::list_organized_workflows(
+            self,
+            non_empty(&req.tag),
+            non_empty(&req.folder),
+            owner.as_deref(),
+        )
+        .await?
+        .into_iter()
+        .map(|workflow| OrganizedWorkflow {
+            workflow_id: workflow.workflow_id,
+            display_name: workflow.display_name,
+            folder: workflow.folder.unwrap_or_default(),
+            tags: workflow.tags,
+        })
+        .collect();
+
+        Ok(Response::new(ListOrganizedWorkflowsResponse { workflows }))
+    }
+
+    async fn search_workflow_content(
+        &self,
+        request: Request<SearchWorkflowContentRequest>,
+    ) -> Result<Response<SearchWorkflowContentResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+
+        let hits = MyWorkflowService::search_workflow_content(
+            self,
+            &req.query,
+            req.limit,
+            owner.as_deref(),
+        )
+        .await?
+        .into_iter()
+        .map(|hit| SearchHit {
+            kind: hit.kind,
+            source_id: hit.source_id,
+            workflow_id: hit.workflow_id,
+            score: hit.score,
+            snippet: hit.snippet,
+        })
+        .collect();
+
+        Ok(Response::new(SearchWorkflowContentResponse { hits }))
+    }
+
+    async fn get_tool_catalog(
+        &self,
+        request: Request<GetToolCatalogRequest>,
+    ) -> Result<Response<GetToolCatalogResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let catalog = self.generation_tool_catalog().await?;
+        Ok(Response::new(GetToolCatalogResponse { catalog }))
+    }
+
+    async fn list_workflow_state(
+        &self,
+        request: Request<ListWorkflowStateRequest>,
+    ) -> Result<Response<ListWorkflowStateResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+
+        let entries = database::state::list_state(&self.db, &req.workflow_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(|entry| WorkflowStateEntry {
+                key: entry.key,
+                value: entry.value,
+                updated_at: entry.updated_at.map(timestamp),
+            })
+            .collect();
+
+        Ok(Response::new(ListWorkflowStateResponse { entries }))
+    }
+
+    async fn clear_workflow_state(
+        &self,
+        request: Request<ClearWorkflowStateRequest>,
+    ) -> Result<Response<ClearWorkflowStateResponse>, Status> {
+        require_scope(&request, TokenScope::Write)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        self.authorize_workflow_change(&req.workflow_id, owner.as_deref(), may_change_shared)
+            .await?;
+
+        let deleted =
+            database::state::delete_state(&self.db, &req.workflow_id, non_empty(&req.key))
+                .await
+                .map_err(Self::map_db_error)?;
+        info!(
+            "Deleted {deleted} state entries of workflow {}",
+            req.workflow_id
+        );
+
+        Ok(Response::new(ClearWorkflowStateResponse { deleted }))
+    }
+
+    async fn list_workflow_recordings(
+        &self,
+        request: Request<ListWorkflowRecordingsRequest>,
+    ) -> Result<Response<ListWorkflowRecordingsResponse>, Status> {
+        require_scope(&request, TokenScope::Read)?;
+        let owner = request_owner(&request);
+        let req = request.into_inner();
+        self.authorize_workflow(&req.workflow_id, owner.as_deref())
+            .await?;
+
+        let recordings = list_recordings(&self.db, Some(&req.workflow_id))
+            .await
+            .map_err(Self::map_db_error)?
+            .into_iter()
+            .map(|recording| WorkflowRecording {
+                id: recording.id,
+                workflow_id: recording.workflow_id,
+                workflow_code_id: recording.workflow_code_id,
+                created_at: recording.created_at.map(timestamp),
+            })
+            .collect();
+
+        Ok(Response::new(ListWorkflowRecordingsResponse { recordings }))
+    }
+
+    async fn replay_workflow_recording(
+        &self,
+        request: Request<ReplayWorkflowRecordingRequest>,
+    ) -> Result<Response<ReplayWorkflowRecordingResponse>, Status> {
+        require_scope(&request, TokenScope::Run)?;
+        let owner = request_owner(&request);
+        let may_change_shared = request_may_change_shared(&request);
+        let req = request.into_inner();
+        let not_found = || Status::not_found(format!("recording '{}' not found", req.recording_id));
+
+        let recording = get_recording(&self.db, &req.recording_id)
+            .await
+            .map_err(Self::map_db_error)?
+            .ok_or_else(not_found)?;
+        self.authorize_workflow_change(&recording.workflow_id, owner.as_deref(), may_change_shared)
+            .await
+            .map_err(|_| not_found())?;
+
+        let replay = self.replay_workflow(&recording.id).await?;
+        Ok(Response::new(ReplayWorkflowRecordingResponse {
+            matches: replay.matches(),
+            output: replay.result.result,
+            expected_output: replay.expected_output,
+            exit_code: replay.result.exit_code,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthIdentity;
+    use entity::entity::{workflow, workflow_code, workflow_result};
+    use migration::MigratorTrait;
+    use sea_orm::{ActiveModelTrait, IntoActiveModel};
+
+    async fn setup_service() -> MyWorkflowService {
+        let conn = sea_orm::Database::connect("sqlite::memory:?cache=shared")
+            .await
+            .expect("connect sqlite memory db");
+        migration::Migrator::up(&conn, None)
+            .await
+            .expect("apply migrations");
+        for (id, owner_id) in [("wf-alice", "alice"), ("wf-bob", "bob")] {
+            workflow::Model {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                description: None,
+                workflow_language: 0,
+                created_at: None,
+                updated_at: None,
+                version: 1,
+                owner_id: Some(owner_id.to_string()),
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow");
+            workflow_code::Model {
+                id: format!("{id}-code"),
+                workflow_id: id.to_string(),
+                code_revision: 1,
+                code: "throw new Error('boom');".to_string(),
+                language: 0,
+                created_at: None,
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow code");
+            workflow_result::Model {
+                id: format!("{id}-result"),
+                workflow_id: id.to_string(),
+                workflow_code_id: format!("{id}-code"),
+                display_name: None,
+                description: None,
+                result: Some("boom".to_string()),
+                ran_at: DateTime::from_timestamp(1_700_000_000, 0),
+                result_type: 1,
+                exit_code: Some(1),
+                workflow_result_revision: 1,
+            }
+            .into_active_model()
+            .insert(&conn)
+            .await
+            .expect("insert workflow result");
+        }
+        MyWorkflowService::new(conn)
+    }
+
+    fn request_as<T>(subject: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuthIdentity {
+            subject: subject.to_string(),
+            scopes: vec![TokenScope::Read, TokenScope::Write],
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn results_are_limited_to_visible_workflows() {
+        let service = setup_service().await;
+        let listed = service
+            .list_workflow_results(request_as("alice", ListWorkflowResultsRequest::default()))
+            .await
+            .expect("list results")
+            .into_inner();
+        let ids: Vec<_> = listed.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["wf-alice-result"]);
+        assert_eq!(listed.results[0].output, "boom");
+
+        let status = service
+            .list_workflow_results(request_as(
+                "alice",
+                ListWorkflowResultsRequest {
+                    workflow_id: "wf-bob".to_string(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn other_owners_cannot_diagnose_a_result() {
+        let service = setup_service().await;
+        let status = WorkflowManagementService::diagnose_workflow_result(
+            &service,
+            request_as(
+                "alice",
+                DiagnoseWorkflowResultRequest {
+                    result_id: "wf-bob-result".to_string(),
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn other_owners_cannot_retag_a_workflow() {
+        let service = setup_service().await;
+        let status = WorkflowManagementService::set_workflow_tags(
+            &service,
+            request_as(
+                "alice",
+                SetWorkflowTagsRequest {
+                    workflow_id: "wf-bob".to_string(),
+                    tags: vec!["mine".to_string()],
+                },
+            ),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn other_owners_cannot_delete_a_result() {
+        let service = setup_service().await;
+        let delete = |subject: &str| {
+            service.delete_workflow_result(request_as(
+                subject,
+                DeleteWorkflowResultRequest {
+                    result_id: "wf-bob-result".to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            delete("alice").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        delete("bob").await.expect("delete result");
+        assert_eq!(
+            delete("bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn deleting_results_of_every_workflow_needs_the_admin_scope() {
+        let service = setup_service().await;
+        let delete = |workflow_id: &str| {
+            service.delete_workflow_results(request_as(
+                "alice",
+                DeleteWorkflowResultsRequest {
+                    before: Some(timestamp(Utc::now())),
+                    workflow_id: workflow_id.to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            delete("").await.unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            delete("wf-bob").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        let deleted = delete("wf-alice").await.expect("delete results");
+        assert_eq!(deleted.into_inner().deleted, 1);
+    }
+
+    #[tokio::test]
+    async fn other_owners_cannot_clear_the_state_of_a_workflow() {
+        let service = setup_service().await;
+        database::state::set_state(&service.db, "wf-bob", "cursor", "42")
+            .await
+            .expect("set state");
+
+        let status = service
+            .clear_workflow_state(request_as(
+                "alice",
+                ClearWorkflowStateRequest {
+                    workflow_id: "wf-bob".to_string(),
+                    key: String::new(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let listed = service
+            .list_workflow_state(request_as(
+                "bob",
+                ListWorkflowStateRequest {
+                    workflow_id: "wf-bob".to_string(),
+                },
+            ))
+            .await
+            .expect("list state")
+            .into_inner();
+        assert_eq!(listed.entries.len(), 1);
+        assert_eq!(listed.entries[0].value, "42");
+    }
+
+    #[tokio::test]
+    async fn other_owners_cannot_replay_a_recording() {
+        let service = setup_service().await;
+        let recording =
+            database::recording::create_recording(&service.db, "wf-bob", "wf-bob-code", "[]", "")
+                .await
+                .expect("create recording");
+
+        let mut request = Request::new(ReplayWorkflowRecordingRequest {
+            recording_id: recording.id,
+        });
+        request.extensions_mut().insert(AuthIdentity {
+            subject: "alice".to_string(),
+            scopes: vec![TokenScope::Run],
+        });
+        let status = service
+            .replay_workflow_recording(request)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn pruning_needs_the_admin_scope() {
+        let service = setup_service().await;
+        let status = service
+            .prune_results(request_as("alice", PruneResultsRequest {}))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+}
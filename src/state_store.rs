@@ -0,0 +1,119 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Database-backed store for the `state` plugin
+
+use crate::db_worker::spawn_db_worker;
+use anyhow::Context;
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use state::StateStore;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use tokio::runtime::Runtime;
+
+enum Request {
+    Get {
+        namespace: String,
+        key: String,
+        reply: mpsc::Sender<anyhow::Result<Option<Value>>>,
+    },
+    Set {
+        namespace: String,
+        key: String,
+        value: Option<Value>,
+        reply: mpsc::Sender<anyhow::Result<()>>,
+    },
+}
+
+/// Stores workflow state in the `workflow_state` table.
+///
+/// Plugin ops are synchronous and cannot await, so queries are served by a dedicated
+/// database thread (see [`spawn_db_worker`]).
+pub(crate) struct DbStateStore {
+    requests: Mutex<mpsc::Sender<Request>>,
+}
+
+impl DbStateStore {
+    pub(crate) fn connect(db_url: String) -> anyhow::Result<Self> {
+        let requests = spawn_db_worker("workflow-state", db_url, serve_request)?;
+        Ok(Self {
+            requests: Mutex::new(requests),
+        })
+    }
+
+    fn send(&self, request: Request) -> anyhow::Result<()> {
+        self.requests
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .send(request)
+            .context("workflow state thread has stopped")
+    }
+}
+
+fn serve_request(runtime: &Runtime, db: &DatabaseConnection, request: Request) {
+    match request {
+        Request::Get {
+            namespace,
+            key,
+            reply,
+        } => {
+            let result = runtime
+                .block_on(database::state::get_state(db, &namespace, &key))
+                .map_err(anyhow::Error::from)
+                .and_then(|value| {
+                    value
+                        .map(|v| serde_json::from_str(&v))
+                        .transpose()
+                        .map_err(anyhow::Error::from)
+                });
+            let _ = reply.send(result);
+        }
+        Request::Set {
+            namespace,
+            key,
+            value,
+            reply,
+        } => {
+            let result = runtime.block_on(async {
+                match value {
+                    Some(value) => {
+                        database::state::set_state(db, &namespace, &key, &value.to_string()).await
+                    }
+                    None => database::state::delete_state(db, &namespace, Some(&key))
+                        .await
+                        .map(|_| ()),
+                }
+            });
+            let _ = reply.send(result.map_err(anyhow::Error::from));
+        }
+    }
+}
+
+impl StateStore for DbStateStore {
+    fn get(&self, namespace: &str, key: &str) -> anyhow::Result<Option<Value>> {
+        let (reply, response) = mpsc::channel();
+        self.send(Request::Get {
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            reply,
+        })?;
+        response
+            .recv()
+            .context("workflow state thread has stopped")?
+    }
+
+    fn set(&self, namespace: &str, key: &str, value: Option<Value>) -> anyhow::Result<()> {
+        let (reply, response) = mpsc::channel();
+        self.send(Request::Set {
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            value,
+            reply,
+        })?;
+        response
+            .recv()
+            .context("workflow state thread has stopped")?
+    }
+}
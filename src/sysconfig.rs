@@ -9,10 +9,32 @@ use std::fmt;
 use std::sync::Arc;
 
 use crate::dummy_plugin::dummy_plugin_package;
+use crate::result_retention::ResultRetention;
+use archive::{archive_plugin_package, core_archive_plugin_package};
+use crypto::{core_crypto_plugin_package, crypto_plugin_package};
+use csv_plugin::{core_csv_plugin_package, csv_plugin_package};
 use exec::{core_exec_plugin_package, exec_plugin_package};
 use fetch::{core_fetch_plugin_package, fetch_plugin_package};
 use filesystem::{core_filesystem_plugin_package, filesystem_plugin_package};
-use search::{core_search_plugin_package, search_plugin_package};
+use html::{core_html_plugin_package, html_plugin_package};
+use image_plugin::{core_image_plugin_package, image_plugin_package};
+use input::{core_input_plugin_package, input_plugin_package};
+use mail::{core_mail_plugin_package, mail_plugin_package};
+use process::{core_process_plugin_package, process_plugin_package};
+use prompt::{core_prompt_plugin_package, prompt_plugin_package};
+use python_plugin::core_python_plugin_package;
+use screen::{core_screen_plugin_package, screen_plugin_package};
+use search::{
+    apps_plugin_package, core_apps_plugin_package, core_search_plugin_package,
+    search_plugin_package,
+};
+use secrets::{core_secrets_plugin_package, secrets_plugin_package};
+use sqlite::{core_sqlite_plugin_package, sqlite_plugin_package};
+use state::{core_state_plugin_package, state_plugin_package};
+use std_plugin::{core_std_plugin_package, std_plugin_package};
+use sysinfo_plugin::{core_sysinfo_plugin_package, sysinfo_plugin_package};
+use template::{core_template_plugin_package, template_plugin_package};
+use wasm_plugin::core_wasm_plugin_package;
 use window::{core_window_plugin_package, window_plugin_package};
 
 /// Builds the static system configuration used during application startup.
@@ -36,15 +58,51 @@ pub fn sysconfig() -> SysConfig {
             Arc::new(core_fetch_plugin_package()),
             Arc::new(core_filesystem_plugin_package()),
             Arc::new(core_search_plugin_package()),
+            Arc::new(core_apps_plugin_package()),
             Arc::new(core_window_plugin_package()),
             Arc::new(core_exec_plugin_package()),
+            Arc::new(core_sqlite_plugin_package()),
+            Arc::new(core_csv_plugin_package()),
+            Arc::new(core_archive_plugin_package()),
+            Arc::new(core_image_plugin_package()),
+            Arc::new(core_mail_plugin_package()),
+            Arc::new(core_secrets_plugin_package()),
+            Arc::new(core_process_plugin_package()),
+            Arc::new(core_sysinfo_plugin_package()),
+            Arc::new(core_input_plugin_package()),
+            Arc::new(core_screen_plugin_package()),
+            Arc::new(core_template_plugin_package()),
+            Arc::new(core_crypto_plugin_package()),
+            Arc::new(core_html_plugin_package()),
+            Arc::new(core_state_plugin_package()),
+            Arc::new(core_std_plugin_package()),
+            Arc::new(core_prompt_plugin_package()),
+            Arc::new(core_wasm_plugin_package()),
+            Arc::new(core_python_plugin_package()),
         ],
         initial_plugins: vec![
             fetch_plugin_package(),
             filesystem_plugin_package(),
             search_plugin_package(),
+            apps_plugin_package(),
             window_plugin_package(),
             exec_plugin_package(),
+            sqlite_plugin_package(),
+            csv_plugin_package(),
+            archive_plugin_package(),
+            image_plugin_package(),
+            mail_plugin_package(),
+            secrets_plugin_package(),
+            process_plugin_package(),
+            sysinfo_plugin_package(),
+            input_plugin_package(),
+            screen_plugin_package(),
+            template_plugin_package(),
+            crypto_plugin_package(),
+            html_plugin_package(),
+            state_plugin_package(),
+            std_plugin_package(),
+            prompt_plugin_package(),
             dummy_plugin_package(),
         ],
 
@@ -53,7 +111,14 @@ pub fn sysconfig() -> SysConfig {
         external_plugin_runner_path: env::current_exe()
             .ok()
             .map(|path| path.to_string_lossy().into_owned()),
-        external_plugin_runner_args: vec!["ext".to_string()],
+        external_plugin_runner_args: crate::ext_sandbox::runner_args(),
+
+        // Results keep growing with every run, and screenshots make them large.
+        result_retention: ResultRetention {
+            max_results_per_workflow: Some(1000),
+            max_age: Some(chrono::Duration::days(180)),
+            max_total_bytes: Some(1024 * 1024 * 1024),
+        },
     }
 }
 
@@ -77,6 +142,8 @@ pub struct SysConfig {
     pub initial_workflows: Vec<InitialWorkflow>,
     pub external_plugin_runner_path: Option<String>,
     pub external_plugin_runner_args: Vec<String>,
+
+    pub result_retention: ResultRetention,
 }
 
 impl fmt::Debug for SysConfig {
@@ -100,6 +167,7 @@ impl fmt::Debug for SysConfig {
                 "external_plugin_runner_args",
                 &self.external_plugin_runner_args,
             )
+            .field("result_retention", &self.result_retention)
             .finish()
     }
 }
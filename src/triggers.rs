@@ -0,0 +1,433 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Event triggers that run workflows when files change, webhooks arrive or the active window changes
+
+use crate::permission_profiles::{expand_profile_permissions, parse_profile_permissions};
+use crate::services::{MyWorkflowService, WorkflowRunEvent};
+use anyhow::{Context, Result};
+use database::permission_profile::list_workflow_permission_profiles;
+use database::trigger::list_enabled_triggers;
+use database::workflow::get_workflow_by_id;
+use entity::entity::workflow_trigger::Model as WorkflowTrigger;
+use notify::{RecursiveMode, Watcher};
+use sapphillon_core::proto::sapphillon::v1::AllowedPermission;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+/// How often the trigger manager reloads trigger definitions from the database.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often window triggers poll the active window.
+const WINDOW_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Quiet period used to fold a burst of filesystem events into a single run.
+const FILE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Event source of a trigger, stored as JSON in the `config` column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TriggerSpec {
+    /// Runs when a file or directory changes.
+    File {
+        path: String,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Runs when `/hooks/{token}` is requested on the webhook listener.
+    Webhook { token: String },
+    /// Runs when the active window changes, optionally only to a matching title.
+    Window {
+        #[serde(default)]
+        title_contains: Option<String>,
+    },
+}
+
+impl TriggerSpec {
+    /// Returns the value stored in the `kind` column.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TriggerSpec::File { .. } => "file",
+            TriggerSpec::Webhook { .. } => "webhook",
+            TriggerSpec::Window { .. } => "window",
+        }
+    }
+}
+
+/// Validates a trigger and stores it for an existing workflow.
+///
+/// A file trigger reveals which files change below its path, so the latest code of the
+/// workflow must be allowed to list that path, directly or through a permission profile.
+///
+/// # Arguments
+///
+/// * `db` - Database connection.
+/// * `workflow_id` - Workflow to run when the trigger fires.
+/// * `spec` - Event source of the trigger.
+///
+/// # Returns
+///
+/// Returns the stored trigger, or an error when the workflow does not exist or may not
+/// watch the path.
+pub async fn create_trigger(
+    db: &DatabaseConnection,
+    workflow_id: &str,
+    spec: &TriggerSpec,
+) -> Result<WorkflowTrigger> {
+    get_workflow_by_id(db, workflow_id)
+        .await
+        .with_context(|| format!("workflow not found: {workflow_id}"))?;
+    if let TriggerSpec::File { path, .. } = spec {
+        let allowed = may_watch(db, workflow_id, path).await?;
+        anyhow::ensure!(
+            allowed,
+            "workflow {workflow_id} is not allowed to read {path}; allow {} for it first",
+            filesystem::filesystem_list_files_plugin_function().function_id
+        );
+    }
+    let config = serde_json::to_string(spec)?;
+    Ok(database::trigger::create_trigger(db, workflow_id, spec.kind(), &config).await?)
+}
+
+/// Returns whether the latest code of a workflow may list `path`, the access a file
+/// trigger on `path` gives it.
+async fn may_watch(db: &DatabaseConnection, workflow_id: &str, path: &str) -> Result<bool> {
+    let workflow = get_workflow_by_id(db, workflow_id).await?;
+    let mut allowed = workflow
+        .workflow_code
+        .into_iter()
+        .max_by_key(|code| code.code_revision)
+        .map(|code| code.allowed_permissions)
+        .unwrap_or_default();
+    let mut profile = Vec::new();
+    for entry in list_workflow_permission_profiles(db, workflow_id).await? {
+        profile.extend(parse_profile_permissions(&entry.permissions)?);
+    }
+    expand_profile_permissions(&mut allowed, profile);
+
+    Ok(allows_watching(&allowed, path))
+}
+
+/// Returns whether `allowed` lets a workflow list `path`.
+fn allows_watching(allowed: &[AllowedPermission], path: &str) -> bool {
+    let function = filesystem::filesystem_list_files_plugin_function();
+    let required: Vec<_> = function
+        .permissions
+        .into_iter()
+        .map(|mut permission| {
+            permission.resource = vec![path.to_string()];
+            permission
+        })
+        .collect();
+    plugin_permission::allowed_permissions_cover(allowed, &function.function_id, &required)
+}
+
+/// Returns a JS prelude exposing the event that started a run as `globalThis.trigger`.
+pub(crate) fn trigger_prelude(event: &serde_json::Value) -> String {
+    format!(
+        "Object.defineProperty(globalThis, \"trigger\", {{ value: Object.freeze({event}), writable: false }});\n"
+    )
+}
+
+/// Runs the trigger manager forever.
+///
+/// Enabled triggers are loaded on start and reloaded periodically; a listener is started
+/// for each new trigger and stopped once its trigger is disabled, changed or deleted.
+/// Webhook triggers are served by the webhook listener instead.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to read triggers and persist results.
+pub async fn start_triggers(db: DatabaseConnection) {
+    info!(
+        "Workflow trigger manager started (reload interval: {}s)",
+        RELOAD_INTERVAL.as_secs()
+    );
+    let service = MyWorkflowService::new(db.clone());
+    let mut listeners: HashMap<String, (WorkflowTrigger, JoinHandle<()>)> = HashMap::new();
+    let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+    loop {
+        interval.tick().await;
+        match list_enabled_triggers(&db, None).await {
+            Ok(triggers) => reconcile_listeners(&db, &service, &mut listeners, triggers),
+            Err(err) => error!("Failed to load workflow triggers: {err}"),
+        }
+    }
+}
+
+/// Starts and stops listeners so exactly the given triggers are running.
+fn reconcile_listeners(
+    db: &DatabaseConnection,
+    service: &MyWorkflowService,
+    listeners: &mut HashMap<String, (WorkflowTrigger, JoinHandle<()>)>,
+    triggers: Vec<WorkflowTrigger>,
+) {
+    listeners.retain(|id, (running, handle)| {
+        let keep = triggers.iter().any(|t| t == running) && !handle.is_finished();
+        if !keep {
+            info!("Stopping trigger listener: trigger_id={id}");
+            handle.abort();
+        }
+        keep
+    });
+
+    for trigger in triggers {
+        if listeners.contains_key(&trigger.id) {
+            continue;
+        }
+        let spec = match serde_json::from_str::<TriggerSpec>(&trigger.config) {
+            Ok(TriggerSpec::Webhook { .. }) => continue,
+            Ok(spec) => spec,
+            Err(err) => {
+                warn!("Skipping trigger {} with invalid config: {err}", trigger.id);
+                continue;
+            }
+        };
+        info!(
+            "Starting trigger listener: trigger_id={}, kind={}, workflow_id={}",
+            trigger.id, trigger.kind, trigger.workflow_id
+        );
+        let db = db.clone();
+        let service = service.clone();
+        let listener = trigger.clone();
+        let handle = tokio::spawn(async move {
+            match spec {
+                TriggerSpec::File { path, recursive } => {
+                    watch_file(&db, &service, &listener, &path, recursive).await
+                }
+                TriggerSpec::Window { title_contains } => {
+                    watch_window(&service, &listener, title_contains.as_deref()).await
+                }
+                TriggerSpec::Webhook { .. } => {}
+            }
+        });
+        listeners.insert(trigger.id.clone(), (trigger, handle));
+    }
+}
+
+/// Runs the trigger's workflow whenever `path` changes.
+///
+/// Events arriving while the workflow runs, or within the debounce period after it, are
+/// dropped, so a workflow writing into the watched path does not trigger itself. The
+/// workflow's permissions are checked again before each run, since they may have been
+/// narrowed after the trigger was created.
+async fn watch_file(
+    db: &DatabaseConnection,
+    service: &MyWorkflowService,
+    trigger: &WorkflowTrigger,
+    path: &str,
+    recursive: bool,
+) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let _ = tx.send(event);
+        }) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                error!("Trigger {}: cannot create file watcher: {err}", trigger.id);
+                return;
+            }
+        };
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    if let Err(err) = watcher.watch(Path::new(path), mode) {
+        error!("Trigger {}: cannot watch {path}: {err}", trigger.id);
+        return;
+    }
+
+    while let Some(first) = rx.recv().await {
+        tokio::time::sleep(FILE_DEBOUNCE).await;
+        let mut events = vec![first];
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+
+        let paths: BTreeSet<String> = events
+            .into_iter()
+            .filter_map(|event| event.ok())
+            .flat_map(|event| event.paths)
+            .map(|path| path.display().to_string())
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+        match may_watch(db, &trigger.workflow_id, path).await {
+            Ok(true) => {
+                let event = json!({ "kind": "file", "triggerId": trigger.id, "paths": paths });
+                run_trigger(service, trigger, &event).await;
+            }
+            Ok(false) => warn!(
+                "Trigger {}: workflow {} is no longer allowed to read {path}; not running it",
+                trigger.id, trigger.workflow_id
+            ),
+            Err(err) => error!("Trigger {}: cannot check permissions: {err:#}", trigger.id),
+        }
+
+        // Whatever the run changed below the path arrives now; it must not fire again.
+        tokio::time::sleep(FILE_DEBOUNCE).await;
+        let mut ignored = 0;
+        while rx.try_recv().is_ok() {
+            ignored += 1;
+        }
+        if ignored > 0 {
+            debug!(
+                "Trigger {}: ignored {ignored} file event(s) from during the run",
+                trigger.id
+            );
+        }
+    }
+}
+
+/// Runs the trigger's workflow whenever the active window changes to a matching title.
+///
+/// The window active when the listener starts only sets the baseline and does not fire.
+async fn watch_window(
+    service: &MyWorkflowService,
+    trigger: &WorkflowTrigger,
+    title_contains: Option<&str>,
+) {
+    let mut interval = tokio::time::interval(WINDOW_POLL_INTERVAL);
+    let mut last_title: Option<Option<String>> = None;
+    loop {
+        interval.tick().await;
+        let title = tokio::task::spawn_blocking(window::active_window_title)
+            .await
+            .ok()
+            .flatten();
+        let Some(previous) = last_title.replace(title.clone()) else {
+            continue;
+        };
+        if previous == title {
+            continue;
+        }
+        let Some(title) = title else {
+            continue;
+        };
+        if !window_title_matches(title_contains, &title) {
+            continue;
+        }
+        let event = json!({
+            "kind": "window",
+            "triggerId": trigger.id,
+            "title": title,
+            "previousTitle": previous,
+        });
+        run_trigger(service, trigger, &event).await;
+    }
+}
+
+fn window_title_matches(title_contains: Option<&str>, title: &str) -> bool {
+    title_contains.is_none_or(|needle| title.to_lowercase().contains(&needle.to_lowercase()))
+}
+
+/// Runs the latest code of the trigger's workflow with `event` as `globalThis.trigger`.
+pub(crate) async fn run_trigger(
+    service: &MyWorkflowService,
+    trigger: &WorkflowTrigger,
+    event: &serde_json::Value,
+) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let _ = service
+        .execute_workflow_with_events(&trigger.workflow_id, None, Some(event), &tx)
+        .await;
+    drop(tx);
+    while let Some(event) = rx.recv().await {
+        log_run_event(&trigger.id, event);
+    }
+}
+
+fn log_run_event(trigger_id: &str, event: WorkflowRunEvent) {
+    match event {
+        WorkflowRunEvent::Started { workflow_id, .. } => {
+            info!("Running triggered workflow: trigger_id={trigger_id}, workflow_id={workflow_id}")
+        }
+        WorkflowRunEvent::Console { line } => info!("[trigger {trigger_id}] {line}"),
+        WorkflowRunEvent::Step { step } => debug!(
+            "[trigger {trigger_id}] step {:?} {} in {}ms",
+            step.name, step.status, step.duration_ms
+        ),
+        WorkflowRunEvent::Finished { result } => info!(
+            "Triggered workflow finished: trigger_id={trigger_id}, result_revision={}",
+            result.workflow_result_revision
+        ),
+        WorkflowRunEvent::Planned { action } => {
+            debug!("[trigger {trigger_id}] planned call {}", action.function)
+        }
+        WorkflowRunEvent::Failed { status } => error!(
+            "Triggered workflow failed: trigger_id={trigger_id}, error={}",
+            status.message()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_spec_round_trips_through_config() {
+        let spec: TriggerSpec =
+            serde_json::from_str(r#"{"kind":"file","path":"/tmp/in"}"#).unwrap();
+        assert_eq!(
+            spec,
+            TriggerSpec::File {
+                path: "/tmp/in".to_string(),
+                recursive: false,
+            }
+        );
+        assert_eq!(spec.kind(), "file");
+
+        let window = TriggerSpec::Window {
+            title_contains: Some("Slack".to_string()),
+        };
+        let config = serde_json::to_string(&window).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TriggerSpec>(&config).unwrap(),
+            window
+        );
+    }
+
+    #[test]
+    fn file_triggers_need_read_access_to_their_path() {
+        use sapphillon_core::proto::sapphillon::v1::{Permission, PermissionType};
+
+        let allowed = vec![AllowedPermission {
+            plugin_function_id: "*".to_string(),
+            permissions: vec![Permission {
+                permission_type: PermissionType::FilesystemRead as i32,
+                resource: vec!["/srv/inbox".to_string()],
+                ..Default::default()
+            }],
+        }];
+        assert!(allows_watching(&allowed, "/srv/inbox"));
+        assert!(!allows_watching(&allowed, "/etc"));
+        assert!(!allows_watching(&[], "/srv/inbox"));
+    }
+
+    #[test]
+    fn window_title_matches_ignores_case() {
+        assert!(window_title_matches(None, "anything"));
+        assert!(window_title_matches(Some("slack"), "Slack - general"));
+        assert!(!window_title_matches(Some("slack"), "Terminal"));
+    }
+
+    #[test]
+    fn trigger_prelude_freezes_event() {
+        let prelude = trigger_prelude(&json!({ "kind": "file", "paths": ["/tmp/a"] }));
+        assert!(prelude.contains(r#"Object.freeze({"kind":"file","paths":["/tmp/a"]})"#));
+    }
+}
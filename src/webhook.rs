@@ -0,0 +1,191 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// HTTP listener that starts workflows from incoming webhooks
+
+use crate::services::MyWorkflowService;
+use crate::triggers::{TriggerSpec, run_trigger};
+use anyhow::{Context, Result};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, Method, StatusCode, header};
+use axum::routing::any;
+use database::trigger::list_enabled_triggers;
+use entity::entity::workflow_trigger::Model as WorkflowTrigger;
+use sea_orm::DatabaseConnection;
+use serde_json::{Value, json};
+
+#[allow(unused)]
+use log::{debug, error, info, warn};
+
+#[derive(Clone)]
+struct WebhookState {
+    db: DatabaseConnection,
+    service: MyWorkflowService,
+}
+
+/// Serves `/hooks/{token}` on `addr` until the process exits.
+///
+/// A request whose token matches an enabled webhook trigger starts the trigger's
+/// workflow in the background and is answered with `202 Accepted`; the request is
+/// available to the workflow as `globalThis.trigger`.
+///
+/// # Arguments
+///
+/// * `db` - Database connection used to look up triggers and persist results.
+/// * `addr` - Socket address to listen on, e.g. `127.0.0.1:50052`.
+///
+/// # Returns
+///
+/// Returns an error if the address cannot be bound or the server fails.
+pub async fn start_webhook_server(db: DatabaseConnection, addr: &str) -> Result<()> {
+    let state = WebhookState {
+        service: MyWorkflowService::new(db.clone()),
+        db,
+    };
+    let router = Router::new()
+        .route("/hooks/{token}", any(handle_hook))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("cannot bind webhook listener to {addr}"))?;
+    info!("Webhook server starting on {addr}");
+    axum::serve(listener, router).await?;
+    Ok(())
+}
+
+async fn handle_hook(
+    State(state): State<WebhookState>,
+    Path(token): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, axum::Json<Value>) {
+    let trigger = match find_webhook_trigger(&state.db, &token).await {
+        Ok(Some(trigger)) => trigger,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                axum::Json(json!({ "error": "unknown webhook" })),
+            );
+        }
+        Err(err) => {
+            error!("Failed to look up webhook trigger: {err:#}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(json!({ "error": "internal error" })),
+            );
+        }
+    };
+
+    info!(
+        "Webhook received: trigger_id={}, workflow_id={}",
+        trigger.id, trigger.workflow_id
+    );
+    let event = webhook_event(&trigger.id, &method, &headers, &body);
+    let service = state.service.clone();
+    let trigger_id = trigger.id.clone();
+    tokio::spawn(async move {
+        run_trigger(&service, &trigger, &event).await;
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        axum::Json(json!({ "triggerId": trigger_id })),
+    )
+}
+
+/// Returns the enabled webhook trigger owning `token`, if any.
+async fn find_webhook_trigger(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<Option<WorkflowTrigger>> {
+    let triggers = list_enabled_triggers(db, Some("webhook")).await?;
+    Ok(triggers.into_iter().find(|trigger| {
+        matches!(
+            serde_json::from_str::<TriggerSpec>(&trigger.config),
+            Ok(TriggerSpec::Webhook { token: ref expected }) if tokens_equal(expected, token)
+        )
+    }))
+}
+
+/// Compares tokens without stopping at the first differing byte, so response times do
+/// not reveal how much of a guessed token was right.
+fn tokens_equal(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Builds the `globalThis.trigger` value for a webhook request.
+///
+/// JSON bodies are parsed; any other body is passed as a string.
+fn webhook_event(trigger_id: &str, method: &Method, headers: &HeaderMap, body: &[u8]) -> Value {
+    let header_values: serde_json::Map<String, Value> = headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_string(), Value::from(value)))
+        })
+        .collect();
+
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("json"));
+    let text = String::from_utf8_lossy(body);
+    let body = if is_json {
+        serde_json::from_str(&text).unwrap_or_else(|_| Value::from(text.as_ref()))
+    } else {
+        Value::from(text.as_ref())
+    };
+
+    json!({
+        "kind": "webhook",
+        "triggerId": trigger_id,
+        "method": method.as_str(),
+        "headers": header_values,
+        "body": body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn webhook_event_parses_json_bodies() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert("x-github-event", HeaderValue::from_static("push"));
+
+        let event = webhook_event("t-1", &Method::POST, &headers, br#"{"ref":"main"}"#);
+        assert_eq!(event["kind"], "webhook");
+        assert_eq!(event["method"], "POST");
+        assert_eq!(event["headers"]["x-github-event"], "push");
+        assert_eq!(event["body"]["ref"], "main");
+    }
+
+    #[test]
+    fn webhook_event_keeps_other_bodies_as_text() {
+        let event = webhook_event("t-1", &Method::POST, &HeaderMap::new(), b"a=1&b=2");
+        assert_eq!(event["body"], "a=1&b=2");
+    }
+
+    #[test]
+    fn tokens_equal_requires_exact_match() {
+        assert!(tokens_equal("abc123", "abc123"));
+        assert!(!tokens_equal("abc123", "abc124"));
+        assert!(!tokens_equal("abc123", "abc12"));
+    }
+}
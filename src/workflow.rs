@@ -2,14 +2,37 @@
 // SPDX-FileCopyrightText: 2025 Yuta Takahashi
 // SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
 
-use std::env;
 use std::error::Error;
+use std::sync::OnceLock;
 
-use async_openai::{
-    Client,
-    config::OpenAIConfig,
-    types::{ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs},
-};
+use log::warn;
+use sapphillon_core::proto::sapphillon::v1::{AllowedPermission, PluginFunction, PluginPackage};
+
+use crate::llm::{LlmProvider, ProviderConfig, build_provider};
+use crate::workflow_validation::validate_workflow_code;
+
+/// Times generated code that fails validation is sent back for repair when not configured.
+pub(crate) const DEFAULT_MAX_REPAIR_ATTEMPTS: usize = 2;
+
+static MAX_REPAIR_ATTEMPTS: OnceLock<usize> = OnceLock::new();
+
+/// Sets how many times invalid generated code is sent back to the model for repair.
+/// Only the first call takes effect.
+///
+/// # Arguments
+///
+/// * `attempts` - Maximum number of repair requests per generation; `0` disables repair.
+///
+/// # Returns
+///
+/// Returns `true` if the limit was applied, `false` if one was already set.
+pub(crate) fn set_max_repair_attempts(attempts: usize) -> bool {
+    MAX_REPAIR_ATTEMPTS.set(attempts).is_ok()
+}
+
+fn max_repair_attempts() -> usize {
+    *MAX_REPAIR_ATTEMPTS.get_or_init(|| DEFAULT_MAX_REPAIR_ATTEMPTS)
+}
 
 #[allow(dead_code)]
 /// Generates a JavaScript workflow synchronously by issuing a blocking LLM call.
@@ -22,7 +45,8 @@ use async_openai::{
 ///
 /// Returns the extracted JavaScript snippet on success, or an error when prompt building or LLM execution fails.
 pub fn generate_workflow(user_query: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let prompt = generate_prompt(user_query)?;
+    let catalog = format_tool_catalog(&crate::sysconfig::sysconfig().initial_plugins);
+    let prompt = generate_prompt(user_query, &catalog)?;
     let workflow_raw = llm_call(&prompt)?;
     let workflow_code = extract_first_code(&workflow_raw);
     workflow_code.ok_or_else(|| "No code section found in the response".into())
@@ -30,20 +54,349 @@ pub fn generate_workflow(user_query: &str) -> Result<String, Box<dyn std::error:
 
 /// Generates a JavaScript workflow asynchronously using the non-blocking LLM client.
 ///
+/// The generated code is compiled and scanned for unknown plugin functions. When it
+/// fails, the problems are sent back to the model for repair, up to the configured
+/// number of attempts.
+///
 /// # Arguments
 ///
+/// * `provider` - The language model provider to generate with.
 /// * `user_query` - The natural-language prompt describing the desired workflow.
+/// * `plugins` - The installed plugin packages the workflow may call.
 ///
 /// # Returns
 ///
-/// Returns the extracted JavaScript snippet on success, or an error when the LLM request fails.
+/// Returns the extracted JavaScript snippet on success, or an error when the LLM request
+/// fails or the code is still invalid after the last repair attempt.
 pub async fn generate_workflow_async(
+    provider: &dyn LlmProvider,
     user_query: &str,
+    plugins: &[PluginPackage],
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let prompt = generate_prompt(user_query)?;
-    let workflow_raw = _llm_call_async(&prompt).await?;
-    let workflow_code = extract_first_code(&workflow_raw);
-    workflow_code.ok_or_else(|| "No code section found in the response".into())
+    let prompt = generate_prompt(user_query, &format_tool_catalog(plugins))?;
+    let workflow_raw = provider.complete_code(&prompt).await?;
+    repair_until_valid(provider, &prompt, workflow_raw, plugins).await
+}
+
+/// A failed run of a stored workflow, as shown to the model for diagnosis.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WorkflowFailure<'a> {
+    /// What the workflow is meant to do, e.g. its description.
+    pub task: &'a str,
+    /// The code that failed.
+    pub code: &'a str,
+    /// Console output of the run, including the error it ended with.
+    pub output: &'a str,
+    pub exit_code: Option<i32>,
+    /// Plugin calls of a recorded run of the same code, if there is one.
+    pub trace: Option<&'a str>,
+}
+
+/// The model's explanation of a failed run and the code it suggests instead.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WorkflowDiagnosis {
+    /// Why the run failed; empty when the model only returned code.
+    pub explanation: String,
+    /// The fixed code, validated like generated code.
+    pub code: String,
+}
+
+/// Asks the model why a run failed and for code that fixes it.
+///
+/// The suggested code goes through the same validation and repair as generated code.
+///
+/// # Arguments
+///
+/// * `provider` - The language model provider to diagnose with.
+/// * `failure` - The failed run.
+/// * `plugins` - The installed plugin packages the fixed code may call.
+///
+/// # Returns
+///
+/// Returns the explanation and fixed code, or an error when the LLM request fails or the
+/// code is still invalid after the last repair attempt.
+pub(crate) async fn diagnose_workflow_failure(
+    provider: &dyn LlmProvider,
+    failure: &WorkflowFailure<'_>,
+    plugins: &[PluginPackage],
+) -> Result<WorkflowDiagnosis, Box<dyn std::error::Error>> {
+    let prompt = diagnosis_prompt(
+        &generate_prompt(failure.task, &format_tool_catalog(plugins))?,
+        failure,
+    );
+    let reply = provider.complete_code(&prompt).await?;
+    let explanation = text_outside_code(&reply);
+    let code = repair_until_valid(provider, &prompt, reply, plugins).await?;
+    Ok(WorkflowDiagnosis { explanation, code })
+}
+
+/// Extracts the code from `workflow_raw` and validates it, sending the problems back to
+/// the model until the code is valid or the repair attempts run out.
+async fn repair_until_valid(
+    provider: &dyn LlmProvider,
+    prompt: &str,
+    mut workflow_raw: String,
+    plugins: &[PluginPackage],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut repairs = 0;
+    loop {
+        let workflow_code = extract_first_code(&workflow_raw);
+        let problems = match &workflow_code {
+            Some(code) => validate_workflow_code(code, plugins),
+            None => vec!["No code section found in the response".to_string()],
+        };
+        match workflow_code {
+            Some(code) if problems.is_empty() => return Ok(code),
+            _ if repairs >= max_repair_attempts() => {
+                return Err(format!(
+                    "generated workflow is invalid after {repairs} repair attempts: {}",
+                    problems.join("; ")
+                )
+                .into());
+            }
+            workflow_code => {
+                repairs += 1;
+                warn!(
+                    "generated workflow is invalid, requesting repair {repairs}: {}",
+                    problems.join("; ")
+                );
+                let code = workflow_code.unwrap_or(workflow_raw);
+                workflow_raw = provider
+                    .complete_code(&repair_prompt(prompt, &code, &problems))
+                    .await?;
+            }
+        }
+    }
+}
+
+/// Describes plugin functions for the generation prompt, one entry per function with
+/// its signature, description and required permissions.
+///
+/// # Arguments
+///
+/// * `plugins` - The plugin packages to describe.
+///
+/// # Returns
+///
+/// Returns the catalog as a markdown list, or a note that no plugins are installed.
+pub(crate) fn format_tool_catalog(plugins: &[PluginPackage]) -> String {
+    let entries: Vec<String> = plugins
+        .iter()
+        .flat_map(|package| &package.functions)
+        .map(describe_tool)
+        .collect();
+    if entries.is_empty() {
+        return "    (プラグインはインストールされていない)".to_string();
+    }
+    entries.join("\n")
+}
+
+fn describe_tool(function: &PluginFunction) -> String {
+    let define = function.function_define.clone().unwrap_or_default();
+    let parameters: Vec<String> = define
+        .parameters
+        .iter()
+        .map(|p| format!("{}: {}", p.name, p.r#type))
+        .collect();
+    let returns: Vec<&str> = define.returns.iter().map(|r| r.r#type.as_str()).collect();
+    let mut signature = format!("{}({})", function.function_id, parameters.join(", "));
+    if !returns.is_empty() {
+        signature = format!("{signature} -> {}", returns.join(", "));
+    }
+
+    let mut lines = vec![format!("    - `{signature}`")];
+    if !function.description.is_empty() {
+        lines[0] = format!("{}: {}", lines[0], function.description);
+    }
+    for parameter in &define.parameters {
+        if !parameter.description.is_empty() {
+            lines.push(format!(
+                "      - `{}`: {}",
+                parameter.name, parameter.description
+            ));
+        }
+    }
+    for permission in &function.permissions {
+        let permission = plugin_permission::describe_permission(permission);
+        lines.push(format!("      - 権限: {permission}"));
+    }
+    lines.join("\n")
+}
+
+/// Asks the model to summarize a change to a workflow's code for its revision history.
+///
+/// # Arguments
+///
+/// * `provider` - The language model provider to summarize with.
+/// * `request` - What the change was asked to do, e.g. the issues a fix addresses.
+/// * `diff` - The change in the unified diff format.
+///
+/// # Returns
+///
+/// Returns the summary, or an error when the LLM request fails or returns nothing.
+pub async fn summarize_code_change(
+    provider: &dyn LlmProvider,
+    request: &str,
+    diff: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let prompt = format!(
+        "Summarize in one or two sentences what the following change to a workflow.js does, \
+         in the language of the request. Reply with the summary only.\n\n\
+         Request: {request}\n\nChange:\n```diff\n{diff}```"
+    );
+    let summary = provider.complete(&prompt).await?;
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err("The model returned an empty summary".into());
+    }
+    Ok(summary.to_string())
+}
+
+/// Asks the model to explain a workflow's code as steps a user can follow before
+/// approving its run.
+///
+/// # Arguments
+///
+/// * `provider` - The language model provider to explain with.
+/// * `code` - The workflow code to explain.
+/// * `permissions` - The permissions the code needs, mentioned in the steps that use them.
+///
+/// # Returns
+///
+/// Returns the steps in order, or an error when the LLM request fails or the reply
+/// holds no steps.
+pub async fn explain_workflow_code(
+    provider: &dyn LlmProvider,
+    code: &str,
+    permissions: &[AllowedPermission],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let permissions: String = permissions
+        .iter()
+        .flat_map(|entry| {
+            entry.permissions.iter().map(|permission| {
+                format!(
+                    "- {}: {}\n",
+                    entry.plugin_function_id,
+                    plugin_permission::describe_permission(permission)
+                )
+            })
+        })
+        .collect();
+    let prompt = format!(
+        "Explain what the following workflow.js does for someone deciding whether to run \
+         it, as a numbered list with one short step per line, in the language of the \
+         comments and strings in the code. Mention the files, URLs and programs each step \
+         touches. Reply with the list only.\n\n\
+         Permissions the code needs:\n{permissions}\n\
+         Code:\n```javascript\n{code}\n```"
+    );
+    let steps = parse_step_list(&provider.complete(&prompt).await?);
+    if steps.is_empty() {
+        return Err("The model returned no steps".into());
+    }
+    Ok(steps)
+}
+
+/// Reads the items of a numbered or bulleted list, ignoring any other lines.
+fn parse_step_list(reply: &str) -> Vec<String> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let item = match line.strip_prefix(['-', '*', '•']) {
+                Some(item) => item,
+                None => {
+                    let number_end = line.find(|c: char| !c.is_ascii_digit())?;
+                    if number_end == 0 {
+                        return None;
+                    }
+                    line[number_end..].strip_prefix(['.', ')'])?
+                }
+            };
+            let item = item.trim();
+            (!item.is_empty()).then(|| item.to_string())
+        })
+        .collect()
+}
+
+/// Builds the follow-up prompt showing the model a failed run of its code.
+///
+/// # Arguments
+///
+/// * `prompt` - The generation prompt for the workflow's task.
+/// * `failure` - The failed run.
+///
+/// # Returns
+///
+/// Returns the generation prompt followed by the failed code, its output and calls.
+fn diagnosis_prompt(prompt: &str, failure: &WorkflowFailure<'_>) -> String {
+    let exit_code = failure
+        .exit_code
+        .map_or_else(|| "不明".to_string(), |code| code.to_string());
+    let trace = failure.trace.unwrap_or("(記録なし)");
+    format!(
+        r#"{prompt}
+    ## Assistant
+    ```javascript
+{code}
+    ```
+    ## User
+    上記の `workflow.js` を実行したところ失敗しました(終了コード: {exit_code})。
+    ### 出力
+{output}
+    ### プラグイン呼び出しの記録
+{trace}
+    失敗の原因を1〜3文で説明し、続けて修正した `workflow.js` 全体を出力ルールに従って出力してください。
+    利用可能なTool以外の関数は使わないこと。
+    "#,
+        code = failure.code,
+        output = failure.output,
+    )
+}
+
+/// Returns the reply without its code block, i.e. the model's explanation.
+fn text_outside_code(reply: &str) -> String {
+    match reply.find("```") {
+        Some(start) => {
+            let after = &reply[start + 3..];
+            let rest = after.find("```").map_or("", |end| &after[end + 3..]);
+            format!("{} {}", reply[..start].trim(), rest.trim())
+                .trim()
+                .to_string()
+        }
+        None => reply.trim().to_string(),
+    }
+}
+
+/// Builds the follow-up prompt asking the model to fix code that failed validation.
+///
+/// # Arguments
+///
+/// * `prompt` - The original generation prompt.
+/// * `code` - The code, or the whole reply when it had no code block, that failed.
+/// * `problems` - The validation problems found in `code`.
+///
+/// # Returns
+///
+/// Returns the original prompt followed by the failed answer and the problems.
+fn repair_prompt(prompt: &str, code: &str, problems: &[String]) -> String {
+    let problems: String = problems
+        .iter()
+        .map(|problem| format!("    - {problem}\n"))
+        .collect();
+    format!(
+        r#"{prompt}
+    ## Assistant
+    ```javascript
+{code}
+    ```
+    ## User
+    上記の `workflow.js` を検証したところ、次の問題が見つかりました。
+{problems}
+    問題をすべて修正した `workflow.js` 全体を、出力ルールに従って出力してください。
+    利用可能なTool以外の関数は使わないこと。
+    "#
+    )
 }
 
 #[allow(dead_code)]
@@ -52,11 +405,15 @@ pub async fn generate_workflow_async(
 /// # Arguments
 ///
 /// * `user_query` - The user's task description incorporated into the prompt.
+/// * `tool_catalog` - The plugin functions the workflow may call, see [`format_tool_catalog`].
 ///
 /// # Returns
 ///
 /// Returns the fully formatted prompt string or an error when formatting fails.
-fn generate_prompt(user_query: &str) -> Result<String, Box<dyn std::error::Error>> {
+fn generate_prompt(
+    user_query: &str,
+    tool_catalog: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
     let today_date = chrono::Utc::now().format("%Y-%m-%d").to_string();
     let prompt = format!(
         r#"
@@ -79,6 +436,7 @@ fn generate_prompt(user_query: &str) -> Result<String, Box<dyn std::error::Error
     - 各ステップにおいて **コメントで意図や処理内容を説明** すること。
     - 必要に応じて例外処理を入れることで、失敗時の理由を明確にする。
     - 実行結果の出力はすべてconsole.log()を使用すること。
+    - 処理は `step("setup", () => {{ ... }})`、`step("fetch", ...)`、`step("transform", ...)`、`step("output", ...)` のような名前付きステップに分割すること。各ステップの成否と所要時間が記録される。
 
     ### ワークフロー設計ガイドライン
     1. **目的の正確な理解**  
@@ -102,8 +460,12 @@ fn generate_prompt(user_query: &str) -> Result<String, Box<dyn std::error::Error
     ---
 
     ### 利用可能なTool
-    - `fetch(url: str) -> str`
     - `console.log(str) -> stdout`
+    - `step(name: str, fn: () -> any) -> any` (fn の戻り値をそのまま返す)
+
+    プラグイン関数は完全なIDで呼び出すこと（例: `app.sapphillon.core.fetch.fetch(url)`）。
+    各関数の実行には記載された権限が必要になる。破壊的な操作の前には `app.sapphillon.core.prompt.confirm` でユーザーの承認を得ること。
+{tool_catalog}
     ---
 
     ### 出力例
@@ -113,7 +475,7 @@ fn generate_prompt(user_query: &str) -> Result<String, Box<dyn std::error::Error
 
         try {{
             // fetch は文字列を返す（ツール仕様）のでそのまま受け取る
-            const body = fetch(url);
+            const body = app.sapphillon.core.fetch.fetch(url);
 
             // 受け取った文字列を JSON.parse でパースする（失敗検出）
             let data;
@@ -177,7 +539,8 @@ fn extract_first_code(xml: &str) -> Option<String> {
 }
 
 #[allow(dead_code)]
-/// Performs a blocking LLM call by spinning up a temporary Tokio runtime.
+/// Performs a blocking LLM call with the provider configured by the `OPENAI_*`
+/// environment variables by spinning up a temporary Tokio runtime.
 ///
 /// # Arguments
 ///
@@ -187,53 +550,62 @@ fn extract_first_code(xml: &str) -> Option<String> {
 ///
 /// Returns the raw LLM response string or an error when runtime creation or the request fails.
 pub fn llm_call(user_query: &str) -> Result<String, Box<dyn Error>> {
+    let provider = build_provider(ProviderConfig::from_env()?);
     let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(_llm_call_async(user_query))
+    Ok(rt.block_on(provider.complete(user_query))?)
 }
 
-/// Sends the prompt to the configured LLM provider asynchronously and yields the response content.
+/// Ensures `extract_first_code` returns the inner JavaScript block when present.
 ///
 /// # Arguments
 ///
-/// * `user_query` - The prompt to send to the LLM backend.
+/// This test takes no arguments.
 ///
 /// # Returns
 ///
-/// Returns the response text produced by the model, or an error when environment variables or the API call fail.
-pub async fn _llm_call_async(user_query: &str) -> Result<String, Box<dyn Error>> {
-    // OpenRouter のエンドポイント
-    let api_base = &env::var("OPENAI_API_BASE")?;
-    let api_key = &env::var("OPENAI_API_KEY")?;
-    let model = &env::var("OPENAI_MODEL")?;
-
-    let client = Client::with_config(
-        OpenAIConfig::new()
-            .with_api_key(api_key)
-            .with_api_base(api_base),
-    );
+/// Returns `Ok(())` once the assertion on the extracted code succeeds.
+/// Ensures `repair_prompt` keeps the original prompt and lists every problem.
+///
+/// # Arguments
+///
+/// This test takes no arguments.
+///
+/// # Returns
+///
+/// This test returns nothing; it panics when an expected part is missing.
+#[test]
+fn test_repair_prompt_lists_problems() {
+    let problems = vec![
+        "SyntaxError: Unexpected token '}'".to_string(),
+        "unknown plugin function: app.sapphillon.core.fetch.download".to_string(),
+    ];
+    let prompt = repair_prompt("original prompt", "function workflow() {", &problems);
+    assert!(prompt.starts_with("original prompt"));
+    assert!(prompt.contains("function workflow() {"));
+    for problem in &problems {
+        assert!(prompt.contains(&format!("- {problem}")));
+    }
+}
 
-    // ユーザー入力をメッセージに反映
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(model)
-        .messages([ChatCompletionRequestUserMessageArgs::default()
-            .content(user_query)
-            .build()?
-            .into()])
-        .build()?;
-
-    let response = client.chat().create(request).await?;
-
-    // 最初のレスポンスの message.content を取得
-    let content = response
-        .choices
-        .first()
-        .and_then(|c| c.message.content.clone())
-        .unwrap_or_else(|| "".to_string());
-
-    Ok(content)
+/// Ensures `format_tool_catalog` lists each function with its signature and permissions.
+///
+/// # Arguments
+///
+/// This test takes no arguments.
+///
+/// # Returns
+///
+/// This test returns nothing; it panics when an expected part is missing.
+#[test]
+fn test_format_tool_catalog_describes_functions() {
+    let catalog = format_tool_catalog(&[fetch::fetch_plugin_package()]);
+    assert!(catalog.contains("`app.sapphillon.core.fetch.fetch(url: string) -> string`"));
+    assert!(catalog.contains("権限: NetAccess"));
+    assert!(format_tool_catalog(&[]).contains("インストールされていない"));
 }
 
-/// Ensures `extract_first_code` returns the inner JavaScript block when present.
+/// Ensures the diagnosis prompt carries the failed run and the explanation is read
+/// around the code block.
 ///
 /// # Arguments
 ///
@@ -241,7 +613,54 @@ pub async fn _llm_call_async(user_query: &str) -> Result<String, Box<dyn Error>>
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` once the assertion on the extracted code succeeds.
+/// This test returns nothing; it panics when an expected part is missing.
+#[test]
+fn test_diagnosis_prompt_and_explanation() {
+    let failure = WorkflowFailure {
+        task: "fetch a page",
+        code: "function workflow() { fetch(); }",
+        output: "ReferenceError: fetch is not defined",
+        exit_code: Some(1),
+        trace: None,
+    };
+    let prompt = diagnosis_prompt("original prompt", &failure);
+    assert!(prompt.starts_with("original prompt"));
+    assert!(prompt.contains("function workflow() { fetch(); }"));
+    assert!(prompt.contains("ReferenceError: fetch is not defined"));
+    assert!(prompt.contains("終了コード: 1"));
+    assert!(prompt.contains("(記録なし)"));
+
+    let reply =
+        "`fetch` is not a global.\n```javascript\nfunction workflow() {}\n```\nUse the plugin.";
+    assert_eq!(
+        text_outside_code(reply),
+        "`fetch` is not a global. Use the plugin."
+    );
+    assert_eq!(text_outside_code("```javascript\nx\n```"), "");
+}
+
+/// Ensures `parse_step_list` keeps list items and drops surrounding text.
+///
+/// # Arguments
+///
+/// This test takes no arguments.
+///
+/// # Returns
+///
+/// This test returns nothing; it panics when an expected step is missing.
+#[test]
+fn test_parse_step_list() {
+    let reply = "Here is what it does:\n1. Fetch https://example.com\n2) Save it to /tmp/a\n- Print done\n\n10. \n2024 was a year";
+    assert_eq!(
+        parse_step_list(reply),
+        vec![
+            "Fetch https://example.com",
+            "Save it to /tmp/a",
+            "Print done"
+        ]
+    );
+}
+
 #[test]
 fn test_extract_first_code() -> Result<(), Box<dyn Error>> {
     let result = extract_first_code("```javascript\nHello World\n```");
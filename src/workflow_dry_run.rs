@@ -0,0 +1,122 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Dry-run mode: plugin calls that need a permission are recorded instead of executed
+
+use plugin_permission::DRY_RUN_STUB;
+use serde::Deserialize;
+
+/// Prefix of the console lines written for each intercepted call.
+const DRY_RUN_MARKER: &str = "::sapphillon-dry-run::";
+
+/// A call the workflow would have made outside dry-run mode.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct PlannedAction {
+    /// Function path relative to `app.sapphillon.core`, e.g. `filesystem.write`.
+    pub function: String,
+    /// Arguments the function was called with.
+    pub args: Vec<serde_json::Value>,
+}
+
+/// Prepends a prelude that turns plugin calls stubbed by the permission layer into
+/// recorded actions.
+///
+/// The run itself must be wrapped in [`plugin_permission::run_as_dry_run`]: every plugin
+/// op that needs a permission then fails before doing anything, whichever way it is
+/// called. The prelude wraps the plugin functions so that such a failure is recorded
+/// and the call returns `null`, so workflows that use the return value may take a
+/// different path than in a real run. Calling an op directly still fails the run.
+pub(crate) fn instrument_dry_run(code: &str) -> String {
+    format!(
+        r#"
+(() => {{
+    const isStub = (error) => String(error && error.message).includes("{DRY_RUN_STUB}");
+    const stubbed = (path, args) => (error) => {{
+        if (!isStub(error)) throw error;
+        console.log("{DRY_RUN_MARKER}" + JSON.stringify({{ function: path, args }}));
+        return null;
+    }};
+    const seen = new Set();
+    const wrap = (object, prefix) => {{
+        if (!object || typeof object !== "object" || seen.has(object)) return;
+        seen.add(object);
+        for (const name of Object.keys(object)) {{
+            const value = object[name];
+            const path = prefix ? prefix + "." + name : name;
+            if (typeof value !== "function") {{
+                wrap(value, path);
+                continue;
+            }}
+            object[name] = function (...args) {{
+                let result;
+                try {{
+                    result = value.apply(this, args);
+                }} catch (e) {{
+                    return stubbed(path, args)(e);
+                }}
+                if (result && typeof result.then === "function") {{
+                    return result.then(undefined, stubbed(path, args));
+                }}
+                return result;
+            }};
+        }}
+    }};
+    wrap(globalThis.app?.sapphillon?.core, "");
+    wrap(globalThis.app, "app");
+}})();
+{code}"#
+    )
+}
+
+/// Returns the action recorded by a console line of a dry run, or `None` for other lines.
+pub(crate) fn parse_dry_run_line(line: &str) -> Option<PlannedAction> {
+    line.trim_end_matches(['\r', '\n'])
+        .strip_prefix(DRY_RUN_MARKER)
+        .and_then(|json| serde_json::from_str(json).ok())
+}
+
+/// Splits console output from a dry run into the recorded actions and the plain output.
+pub(crate) fn parse_dry_run(output: &str) -> (Vec<PlannedAction>, String) {
+    let mut actions = Vec::new();
+    let mut plain = String::new();
+
+    for line in output.split_inclusive('\n') {
+        match parse_dry_run_line(line) {
+            Some(action) => actions.push(action),
+            None => plain.push_str(line),
+        }
+    }
+
+    (actions, plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_dry_run_extracts_actions() {
+        let output = format!(
+            "reading\n{DRY_RUN_MARKER}{}\ndone\n",
+            r#"{"function":"filesystem.write","args":["/tmp/a.txt","hi"]}"#
+        );
+        let (actions, plain) = parse_dry_run(&output);
+        assert_eq!(
+            actions,
+            vec![PlannedAction {
+                function: "filesystem.write".to_string(),
+                args: vec![json!("/tmp/a.txt"), json!("hi")],
+            }]
+        );
+        assert_eq!(plain, "reading\ndone\n");
+    }
+
+    #[test]
+    fn instrument_dry_run_recognizes_stubbed_calls() {
+        let code = instrument_dry_run("workflow();");
+        assert!(code.contains(DRY_RUN_STUB));
+        assert!(code.ends_with("workflow();"));
+    }
+}
@@ -0,0 +1,424 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Multi-file workflows: ES modules stored as a bundle and linked into a single script
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::ops::Range;
+use std::path::Path;
+
+use deno_ast::swc::ast::{
+    Decl, DefaultDecl, ExportSpecifier, ImportSpecifier, ModuleDecl, ModuleItem, Pat, Program,
+};
+use deno_ast::{
+    EmitOptions, MediaType, ModuleSpecifier, ParseParams, ParsedSource, SourceMapOption,
+    SourceRangedForSpanned, TranspileModuleOptions, TranspileOptions,
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of the bundle format understood by [`WorkflowBundle::parse`].
+pub(crate) const BUNDLE_FORMAT: u32 = 1;
+
+/// Runtime used by the linked script: each module is a factory that receives a
+/// `__require` function and fills in its `__exports` object on first use.
+const LOADER: &str = r#"const __cache = {};
+const __require = (path) => {
+    if (!(path in __cache)) {
+        __cache[path] = {};
+        __modules[path](__require, __cache[path]);
+    }
+    return __cache[path];
+};
+"#;
+
+/// Workflow code made of several ES modules, stored as JSON in `workflow_code.code`.
+///
+/// ```json
+/// { "sapphillonBundle": 1, "entry": "main.ts", "files": { "main.ts": "...", "lib/util.ts": "..." } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkflowBundle {
+    pub sapphillon_bundle: u32,
+    /// Path of the module that is evaluated first.
+    pub entry: String,
+    /// Module sources keyed by their path inside the bundle.
+    pub files: BTreeMap<String, String>,
+}
+
+impl WorkflowBundle {
+    /// Returns the bundle stored in `code`, or `None` when `code` is a plain script.
+    pub(crate) fn parse(code: &str) -> Option<Self> {
+        let trimmed = code.trim_start();
+        if !trimmed.starts_with('{') {
+            return None;
+        }
+        serde_json::from_str::<Self>(trimmed)
+            .ok()
+            .filter(|bundle| bundle.sapphillon_bundle == BUNDLE_FORMAT)
+    }
+
+    /// Links the modules into a single script that runs the entry module.
+    ///
+    /// Only relative imports between files of the bundle are supported. Imported
+    /// bindings are read once when the import runs, and top-level `await` is not
+    /// available because modules are evaluated as functions.
+    ///
+    /// # Returns
+    ///
+    /// Returns the linked JavaScript, or a message describing the first module that
+    /// failed to parse or import.
+    pub(crate) fn link(&self) -> Result<String, String> {
+        if !self.files.contains_key(&self.entry) {
+            return Err(format!("entry module '{}' not found in bundle", self.entry));
+        }
+
+        let mut script = String::from("(() => {\nconst __modules = {\n");
+        for (path, source) in &self.files {
+            let body = self
+                .link_module(path, source)
+                .map_err(|err| format!("{path}: {err}"))?;
+            let _ = writeln!(
+                script,
+                "{}: function (__require, __exports) {{\n{body}\n}},",
+                quote(path)
+            );
+        }
+        script.push_str("};\n");
+        script.push_str(LOADER);
+        let _ = writeln!(script, "__require({});\n}})();", quote(&self.entry));
+        Ok(script)
+    }
+
+    /// Rewrites `import`/`export` declarations of one module into `__require` calls
+    /// and getters on `__exports`.
+    fn link_module(&self, path: &str, source: &str) -> Result<String, String> {
+        let media_type = MediaType::from_path(Path::new(path));
+        let js = transpile_module(path, source, media_type)?;
+        let parsed = parse_module(path, &js, MediaType::JavaScript)?;
+
+        let text: &str = parsed.text();
+        let start = parsed.text_info_lazy().range().start;
+        let slice = |range: Range<usize>| &text[range];
+
+        let program = parsed.program();
+        let Program::Module(module) = &*program else {
+            return Err("expected an ES module".to_string());
+        };
+
+        let mut prologue = String::new();
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+        let mut getter = |name: &str, value: &str| {
+            let _ = writeln!(
+                prologue,
+                "Object.defineProperty(__exports, {}, {{ get: () => {value}, enumerable: true }});",
+                quote(name)
+            );
+        };
+
+        for (index, item) in module.body.iter().enumerate() {
+            let ModuleItem::ModuleDecl(decl) = item else {
+                continue;
+            };
+            let item_range = item.range().as_byte_range(start);
+            let import_name = format!("__import{index}");
+
+            match decl {
+                ModuleDecl::Import(import) => {
+                    let specifier = unquote(slice(import.src.range().as_byte_range(start)));
+                    let resolved = self.resolve(path, specifier)?;
+                    let mut replacement =
+                        format!("const {import_name} = __require({});", quote(&resolved));
+                    for spec in &import.specifiers {
+                        let (local, value) = match spec {
+                            ImportSpecifier::Default(spec) => (
+                                slice(spec.local.range().as_byte_range(start)),
+                                format!("{import_name}.default"),
+                            ),
+                            ImportSpecifier::Namespace(spec) => (
+                                slice(spec.local.range().as_byte_range(start)),
+                                import_name.clone(),
+                            ),
+                            ImportSpecifier::Named(spec) => {
+                                let local = slice(spec.local.range().as_byte_range(start));
+                                let imported = spec
+                                    .imported
+                                    .as_ref()
+                                    .map(|name| slice(name.range().as_byte_range(start)))
+                                    .unwrap_or(local);
+                                (local, member(&import_name, imported))
+                            }
+                        };
+                        let _ = write!(replacement, " const {local} = {value};");
+                    }
+                    replacements.push((item_range, replacement));
+                }
+                ModuleDecl::ExportDecl(export) => {
+                    let decl_range = export.decl.range().as_byte_range(start);
+                    match &export.decl {
+                        Decl::Fn(f) => {
+                            let name = slice(f.ident.range().as_byte_range(start));
+                            getter(name, name);
+                        }
+                        Decl::Class(c) => {
+                            let name = slice(c.ident.range().as_byte_range(start));
+                            getter(name, name);
+                        }
+                        Decl::Var(var) => {
+                            for declarator in &var.decls {
+                                let Pat::Ident(binding) = &declarator.name else {
+                                    return Err(
+                                        "destructuring in exported declarations is not supported"
+                                            .to_string(),
+                                    );
+                                };
+                                let name = slice(binding.id.range().as_byte_range(start));
+                                getter(name, name);
+                            }
+                        }
+                        _ => return Err("unsupported export declaration".to_string()),
+                    }
+                    replacements.push((item_range.start..decl_range.start, String::new()));
+                }
+                ModuleDecl::ExportDefaultDecl(export) => {
+                    let decl_range = export.decl.range().as_byte_range(start);
+                    let ident = match &export.decl {
+                        DefaultDecl::Fn(f) => f.ident.as_ref(),
+                        DefaultDecl::Class(c) => c.ident.as_ref(),
+                        _ => return Err("unsupported default export".to_string()),
+                    };
+                    match ident {
+                        Some(ident) => {
+                            let name = slice(ident.range().as_byte_range(start));
+                            getter("default", name);
+                            replacements.push((item_range.start..decl_range.start, String::new()));
+                        }
+                        None => {
+                            getter("default", "__default");
+                            replacements.push((
+                                item_range,
+                                format!("const __default = {};", slice(decl_range)),
+                            ));
+                        }
+                    }
+                }
+                ModuleDecl::ExportDefaultExpr(export) => {
+                    getter("default", "__default");
+                    let expr = slice(export.expr.range().as_byte_range(start));
+                    replacements.push((item_range, format!("const __default = {expr};")));
+                }
+                ModuleDecl::ExportNamed(export) => {
+                    let mut replacement = String::new();
+                    let source_module = match &export.src {
+                        Some(src) => {
+                            let specifier = unquote(slice(src.range().as_byte_range(start)));
+                            let resolved = self.resolve(path, specifier)?;
+                            replacement =
+                                format!("const {import_name} = __require({});", quote(&resolved));
+                            Some(import_name.clone())
+                        }
+                        None => None,
+                    };
+                    for spec in &export.specifiers {
+                        match spec {
+                            ExportSpecifier::Named(spec) => {
+                                let orig = slice(spec.orig.range().as_byte_range(start));
+                                let exported = spec
+                                    .exported
+                                    .as_ref()
+                                    .map(|name| slice(name.range().as_byte_range(start)))
+                                    .unwrap_or(orig);
+                                let value = match &source_module {
+                                    Some(module) => member(module, orig),
+                                    None => orig.to_string(),
+                                };
+                                getter(unquote(exported), &value);
+                            }
+                            ExportSpecifier::Namespace(spec) => {
+                                let Some(module) = &source_module else {
+                                    return Err("namespace export without a source".to_string());
+                                };
+                                let name = slice(spec.name.range().as_byte_range(start));
+                                getter(unquote(name), module);
+                            }
+                            ExportSpecifier::Default(_) => {
+                                return Err("unsupported default re-export".to_string());
+                            }
+                        }
+                    }
+                    replacements.push((item_range, replacement));
+                }
+                ModuleDecl::ExportAll(export) => {
+                    let specifier = unquote(slice(export.src.range().as_byte_range(start)));
+                    let resolved = self.resolve(path, specifier)?;
+                    replacements.push((
+                        item_range,
+                        format!(
+                            "const {import_name} = __require({}); for (const key of Object.keys({import_name})) {{ if (key !== \"default\" && !(key in __exports)) Object.defineProperty(__exports, key, {{ get: () => {import_name}[key], enumerable: true }}); }}",
+                            quote(&resolved)
+                        ),
+                    ));
+                }
+                _ => return Err("unsupported module declaration".to_string()),
+            }
+        }
+
+        let mut body = text.to_string();
+        for (range, replacement) in replacements.into_iter().rev() {
+            body.replace_range(range, &replacement);
+        }
+        Ok(format!("{prologue}{body}"))
+    }
+
+    /// Resolves a relative import against the importing module, trying the usual
+    /// extensions and `index` files.
+    fn resolve(&self, from: &str, specifier: &str) -> Result<String, String> {
+        if !(specifier.starts_with("./") || specifier.starts_with("../")) {
+            return Err(format!(
+                "only relative imports are supported, got '{specifier}'"
+            ));
+        }
+
+        let mut parts: Vec<&str> = from.split('/').collect();
+        parts.pop();
+        for segment in specifier.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                segment => parts.push(segment),
+            }
+        }
+        let base = parts.join("/");
+
+        [
+            base.clone(),
+            format!("{base}.ts"),
+            format!("{base}.js"),
+            format!("{base}/index.ts"),
+            format!("{base}/index.js"),
+        ]
+        .into_iter()
+        .find(|candidate| self.files.contains_key(candidate))
+        .ok_or_else(|| format!("cannot resolve '{specifier}'"))
+    }
+}
+
+fn parse_module(path: &str, source: &str, media_type: MediaType) -> Result<ParsedSource, String> {
+    let specifier =
+        ModuleSpecifier::parse(&format!("file:///{path}")).map_err(|e| e.to_string())?;
+    deno_ast::parse_module(ParseParams {
+        specifier,
+        text: source.into(),
+        media_type,
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Strips TypeScript syntax from a module while keeping its `import`/`export` declarations.
+fn transpile_module(path: &str, source: &str, media_type: MediaType) -> Result<String, String> {
+    let parsed = parse_module(path, source, media_type)?;
+    let transpiled = parsed
+        .transpile(
+            &TranspileOptions::default(),
+            &TranspileModuleOptions::default(),
+            &EmitOptions {
+                source_map: SourceMapOption::None,
+                ..Default::default()
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(transpiled.into_source().text)
+}
+
+fn quote(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// Strips the quotes of a string literal; identifiers are returned unchanged.
+fn unquote(literal: &str) -> &str {
+    if literal.len() >= 2 && (literal.starts_with('"') || literal.starts_with('\'')) {
+        &literal[1..literal.len() - 1]
+    } else {
+        literal
+    }
+}
+
+/// Property access on `object`, using brackets for string-literal export names.
+fn member(object: &str, name: &str) -> String {
+    if name.starts_with('"') || name.starts_with('\'') {
+        format!("{object}[{name}]")
+    } else {
+        format!("{object}.{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapphillon_core::workflow::CoreWorkflowCode;
+
+    fn bundle(files: &[(&str, &str)]) -> WorkflowBundle {
+        WorkflowBundle {
+            sapphillon_bundle: BUNDLE_FORMAT,
+            entry: "main.ts".to_string(),
+            files: files
+                .iter()
+                .map(|(path, source)| (path.to_string(), source.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parse_detects_bundles() {
+        let json = serde_json::to_string(&bundle(&[("main.ts", "")])).unwrap();
+        assert!(WorkflowBundle::parse(&json).is_some());
+        assert!(WorkflowBundle::parse("console.log(1);").is_none());
+        assert!(WorkflowBundle::parse(r#"{"entry":"main.ts","files":{}}"#).is_none());
+    }
+
+    #[test]
+    fn link_rejects_missing_and_bare_imports() {
+        let missing = bundle(&[("main.ts", "import { a } from \"./nope\";")]);
+        assert!(missing.link().unwrap_err().contains("cannot resolve"));
+
+        let bare = bundle(&[("main.ts", "import x from \"lodash\";")]);
+        assert!(bare.link().unwrap_err().contains("only relative imports"));
+    }
+
+    #[tokio::test]
+    async fn linked_bundle_runs() {
+        let bundle = bundle(&[
+            (
+                "main.ts",
+                "import add, { twice } from \"./lib/math\";\nimport * as names from \"./lib/names.js\";\nconst total: number = twice(add(1, 2));\nconsole.log(`${names.greeting} ${total}`);",
+            ),
+            (
+                "lib/math.ts",
+                "export default function add(a: number, b: number): number { return a + b; }\nexport const twice = (n: number) => n * 2;",
+            ),
+            ("lib/names.js", "export { greeting } from \"./greeting\";"),
+            (
+                "lib/greeting.ts",
+                "export const greeting: string = \"total\";",
+            ),
+        ]);
+
+        let mut workflow = CoreWorkflowCode::new(
+            "test".to_string(),
+            bundle.link().unwrap(),
+            vec![],
+            1,
+            vec![],
+            vec![],
+        );
+        workflow.run(tokio::runtime::Handle::current(), None, None);
+        assert_eq!(workflow.result[0].result, "total 6\n");
+    }
+}
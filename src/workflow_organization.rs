@@ -0,0 +1,128 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Tags and folders that organize workflows
+
+/// Longest tag accepted, in characters.
+pub(crate) const MAX_TAG_LEN: usize = 64;
+/// Most tags a workflow may have.
+pub(crate) const MAX_TAGS: usize = 32;
+/// Longest folder path accepted, in characters.
+pub(crate) const MAX_FOLDER_LEN: usize = 255;
+
+/// A workflow with where it is organized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OrganizedWorkflow {
+    pub workflow_id: String,
+    pub display_name: String,
+    /// `None` when the workflow is at the top level.
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Trims tags, drops empty and repeated ones and sorts the rest.
+///
+/// # Returns
+///
+/// Returns the tags to store, or a message describing the first invalid tag.
+pub(crate) fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(format!(
+                "tag '{tag}' is longer than {MAX_TAG_LEN} characters"
+            ));
+        }
+        if tag.chars().any(char::is_control) {
+            return Err(format!(
+                "tag '{}' contains control characters",
+                tag.escape_default()
+            ));
+        }
+        normalized.push(tag.to_string());
+    }
+    normalized.sort();
+    normalized.dedup();
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("a workflow can have at most {MAX_TAGS} tags"));
+    }
+    Ok(normalized)
+}
+
+/// Normalizes a folder path such as `/work//reports/` to `work/reports`.
+///
+/// # Returns
+///
+/// Returns the folder, `None` when the path names the top level, or a message
+/// describing why the path is invalid.
+pub(crate) fn normalize_folder(folder: &str) -> Result<Option<String>, String> {
+    let segments: Vec<&str> = folder
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments.is_empty() {
+        return Ok(None);
+    }
+    if segments
+        .iter()
+        .any(|segment| matches!(*segment, "." | ".."))
+    {
+        return Err(format!("folder '{folder}' must not contain '.' or '..'"));
+    }
+    if segments
+        .iter()
+        .any(|segment| segment.chars().any(char::is_control))
+    {
+        return Err(format!(
+            "folder '{}' contains control characters",
+            folder.escape_default()
+        ));
+    }
+    let normalized = segments.join("/");
+    if normalized.chars().count() > MAX_FOLDER_LEN {
+        return Err(format!(
+            "folder '{normalized}' is longer than {MAX_FOLDER_LEN} characters"
+        ));
+    }
+    Ok(Some(normalized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn tags_are_trimmed_deduplicated_and_sorted() {
+        assert_eq!(
+            normalize_tags(&tags(&[" reports", "daily", "", "reports "])).unwrap(),
+            tags(&["daily", "reports"])
+        );
+        assert!(normalize_tags(&tags(&[&"x".repeat(MAX_TAG_LEN + 1)])).is_err());
+        assert!(normalize_tags(&tags(&["line\nbreak"])).is_err());
+
+        let many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag-{i}")).collect();
+        assert!(normalize_tags(&many).is_err());
+    }
+
+    #[test]
+    fn folders_are_normalized_paths() {
+        assert_eq!(
+            normalize_folder("/work// reports /").unwrap().as_deref(),
+            Some("work/reports")
+        );
+        assert_eq!(normalize_folder(" / ").unwrap(), None);
+        assert_eq!(normalize_folder("").unwrap(), None);
+        assert!(normalize_folder("work/../private").is_err());
+        assert!(normalize_folder(&"x".repeat(MAX_FOLDER_LEN + 1)).is_err());
+    }
+}
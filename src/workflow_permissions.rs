@@ -0,0 +1,407 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Static analysis of the permissions a workflow needs, so they can be shown before it runs
+
+use sapphillon_core::proto::sapphillon::v1::{
+    AllowedPermission, Permission, PermissionLevel, PermissionType, PluginPackage,
+};
+
+/// Plugin functions (relative to `app.sapphillon.core`) whose first argument is the
+/// resource their permission check uses.
+const RESOURCE_ARGUMENT_FUNCTIONS: &[&str] = &[
+    "apps.launch",
+    "exec.exec",
+    "fetch.fetch",
+    "fetch.post",
+    "filesystem.list_files",
+    "filesystem.read",
+    "filesystem.write",
+    "image.info",
+    "search.file",
+    "search.start",
+    "sqlite.open",
+];
+
+const CORE_NAMESPACE: &str = "app.sapphillon.core.";
+
+/// Permissions a workflow code needs, compared with what it is already allowed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct PermissionPreview {
+    /// Permissions of every plugin function the code refers to, one entry per function.
+    pub required: Vec<AllowedPermission>,
+    /// The part of `required` that allowed permissions and profiles do not cover.
+    pub missing: Vec<AllowedPermission>,
+}
+
+/// How much harm a workflow could do with the permissions it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RiskLevel {
+    /// Only calls that read metadata or compute locally.
+    Low,
+    /// Reads user data or uses the network.
+    Medium,
+    /// Writes or sends data, runs programs or reads secrets.
+    High,
+}
+
+impl RiskLevel {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// The overall risk of a workflow code and the permissions that raise it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RiskAssessment {
+    /// The highest risk of any required permission.
+    pub level: RiskLevel,
+    /// One line per permission above [`RiskLevel::Low`], riskiest first.
+    pub reasons: Vec<String>,
+}
+
+/// How a source refers to a plugin function.
+#[derive(Debug, Default)]
+struct FunctionUse {
+    used: bool,
+    /// Literal resources the function is called with.
+    resources: Vec<String>,
+    /// Set when the function is called with a computed resource or used indirectly.
+    dynamic: bool,
+}
+
+/// Infers the permissions `sources` need by scanning them for plugin function calls.
+///
+/// A call whose resource is a string literal requires the permission for that
+/// resource only. Any other reference to the function, including a reference to its
+/// whole package such as `const fs = app.sapphillon.core.filesystem`, requires the
+/// permission for every resource. References built at run time, e.g.
+/// `core["filesystem"]`, are not found.
+pub(crate) fn infer_required_permissions(
+    sources: &[&str],
+    plugins: &[PluginPackage],
+) -> Vec<AllowedPermission> {
+    let mut required = Vec::new();
+    for package in plugins {
+        let package_aliased = sources
+            .iter()
+            .any(|source| package_is_aliased(source, &package.package_id));
+        for function in &package.functions {
+            if function.permissions.is_empty() {
+                continue;
+            }
+            let takes_resource = function
+                .function_id
+                .strip_prefix(CORE_NAMESPACE)
+                .is_some_and(|name| RESOURCE_ARGUMENT_FUNCTIONS.contains(&name));
+            let mut usage = FunctionUse {
+                used: package_aliased,
+                dynamic: package_aliased,
+                ..FunctionUse::default()
+            };
+            for source in sources {
+                scan_function(source, &function.function_id, takes_resource, &mut usage);
+            }
+            if !usage.used {
+                continue;
+            }
+            let resources = if usage.dynamic {
+                Vec::new()
+            } else {
+                usage.resources
+            };
+            required.push(AllowedPermission {
+                plugin_function_id: function.function_id.clone(),
+                permissions: function
+                    .permissions
+                    .iter()
+                    .cloned()
+                    .map(|mut permission| {
+                        if permission.resource.is_empty() {
+                            permission.resource = resources.clone();
+                        }
+                        permission
+                    })
+                    .collect(),
+            });
+        }
+    }
+    required
+}
+
+/// Returns the permissions of `required` that `allowed` does not cover, checking
+/// each resource on its own.
+pub(crate) fn find_missing_permissions(
+    required: &[AllowedPermission],
+    allowed: &[AllowedPermission],
+) -> Vec<AllowedPermission> {
+    required
+        .iter()
+        .filter_map(|entry| {
+            let permissions: Vec<Permission> = entry
+                .permissions
+                .iter()
+                .flat_map(split_resources)
+                .filter(|permission| {
+                    !plugin_permission::allowed_permissions_cover(
+                        allowed,
+                        &entry.plugin_function_id,
+                        std::slice::from_ref(permission),
+                    )
+                })
+                .collect();
+            (!permissions.is_empty()).then(|| AllowedPermission {
+                plugin_function_id: entry.plugin_function_id.clone(),
+                permissions,
+            })
+        })
+        .collect()
+}
+
+/// Rates the permissions a workflow code needs, e.g. the `required` permissions of a
+/// [`PermissionPreview`].
+///
+/// A permission's level decides its risk when the plugin declares one; otherwise its
+/// type does, with writing and running programs rated high and reading or network
+/// access medium.
+pub(crate) fn assess_risk(required: &[AllowedPermission]) -> RiskAssessment {
+    let mut rated: Vec<(RiskLevel, String)> = required
+        .iter()
+        .flat_map(|entry| {
+            entry.permissions.iter().map(|permission| {
+                let mut reason = format!(
+                    "{}: {}",
+                    entry.plugin_function_id,
+                    plugin_permission::describe_permission(permission)
+                );
+                if permission.resource.is_empty() {
+                    reason.push_str(" on any resource");
+                }
+                (permission_risk(permission), reason)
+            })
+        })
+        .filter(|(level, _)| *level > RiskLevel::Low)
+        .collect();
+    rated.sort_by(|a, b| b.0.cmp(&a.0));
+    RiskAssessment {
+        level: rated.first().map_or(RiskLevel::Low, |(level, _)| *level),
+        reasons: rated
+            .into_iter()
+            .map(|(level, reason)| format!("[{}] {reason}", level.as_str()))
+            .collect(),
+    }
+}
+
+fn permission_risk(permission: &Permission) -> RiskLevel {
+    match PermissionLevel::try_from(permission.permission_level) {
+        Ok(PermissionLevel::High) => return RiskLevel::High,
+        Ok(PermissionLevel::Medium) => return RiskLevel::Medium,
+        _ => {}
+    }
+    match PermissionType::try_from(permission.permission_type) {
+        Ok(PermissionType::Execute | PermissionType::FilesystemWrite) => RiskLevel::High,
+        Ok(PermissionType::NetAccess | PermissionType::FilesystemRead) => RiskLevel::Medium,
+        _ => RiskLevel::Low,
+    }
+}
+
+fn split_resources(permission: &Permission) -> Vec<Permission> {
+    if permission.resource.len() <= 1 {
+        return vec![permission.clone()];
+    }
+    permission
+        .resource
+        .iter()
+        .map(|resource| Permission {
+            resource: vec![resource.clone()],
+            ..permission.clone()
+        })
+        .collect()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Finds the references to `path` that are not part of a longer identifier or path,
+/// returning the source following each of them.
+fn references<'a>(source: &'a str, path: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    source.match_indices(path).filter_map(move |(start, _)| {
+        let before = source[..start].chars().next_back();
+        let rest = &source[start + path.len()..];
+        let standalone = !before.is_some_and(|c| is_identifier_char(c) || c == '.')
+            && !rest.chars().next().is_some_and(is_identifier_char);
+        standalone.then_some(rest)
+    })
+}
+
+fn package_is_aliased(source: &str, package_id: &str) -> bool {
+    references(source, package_id).any(|rest| !rest.trim_start().starts_with('.'))
+}
+
+fn scan_function(source: &str, function_id: &str, takes_resource: bool, usage: &mut FunctionUse) {
+    for rest in references(source, function_id) {
+        usage.used = true;
+        if !takes_resource {
+            continue;
+        }
+        let literal = rest
+            .trim_start()
+            .strip_prefix('(')
+            .and_then(|args| string_literal(args.trim_start()));
+        match literal {
+            Some(resource) => {
+                if !usage.resources.contains(&resource) {
+                    usage.resources.push(resource);
+                }
+            }
+            None => usage.dynamic = true,
+        }
+    }
+}
+
+/// Reads the string literal `source` starts with, if its value is known statically.
+fn string_literal(source: &str) -> Option<String> {
+    let mut chars = source.chars();
+    let quote = chars.next().filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                escaped => value.push(escaped),
+            },
+            '$' if quote == '`' && chars.clone().next() == Some('{') => return None,
+            c if c == quote => return Some(value),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugins() -> Vec<PluginPackage> {
+        vec![
+            fetch::fetch_plugin_package(),
+            filesystem::filesystem_plugin_package(),
+            crypto::crypto_plugin_package(),
+        ]
+    }
+
+    fn resources(required: &[AllowedPermission], function: &str) -> Vec<String> {
+        required
+            .iter()
+            .find(|entry| entry.plugin_function_id == function)
+            .map(|entry| entry.permissions[0].resource.clone())
+            .unwrap_or_else(|| panic!("{function} is not required"))
+    }
+
+    #[test]
+    fn literal_arguments_become_resources() {
+        let code = r#"
+            const a = app.sapphillon.core.fetch.fetch("https://example.com/a");
+            const b = app.sapphillon.core.fetch.fetch('https://example.com/b');
+            app.sapphillon.core.filesystem.write(`/tmp/out.txt`, a + b);
+            app.sapphillon.core.crypto.sha256(a);
+        "#;
+        let required = infer_required_permissions(&[code], &plugins());
+
+        let functions: Vec<&str> = required
+            .iter()
+            .map(|entry| entry.plugin_function_id.as_str())
+            .collect();
+        assert_eq!(
+            functions,
+            vec![
+                "app.sapphillon.core.fetch.fetch",
+                "app.sapphillon.core.filesystem.write"
+            ]
+        );
+        assert_eq!(
+            resources(&required, "app.sapphillon.core.fetch.fetch"),
+            vec!["https://example.com/a", "https://example.com/b"]
+        );
+        assert_eq!(
+            resources(&required, "app.sapphillon.core.filesystem.write"),
+            vec!["/tmp/out.txt"]
+        );
+    }
+
+    #[test]
+    fn computed_and_indirect_uses_require_every_resource() {
+        let computed = r#"
+            app.sapphillon.core.filesystem.read("/tmp/a");
+            app.sapphillon.core.filesystem.read(`/tmp/${name}`);
+        "#;
+        let required = infer_required_permissions(&[computed], &plugins());
+        assert!(resources(&required, "app.sapphillon.core.filesystem.read").is_empty());
+
+        let aliased = "const fs = app.sapphillon.core.filesystem;\nfs.read('/tmp/a');";
+        let required = infer_required_permissions(&[aliased], &plugins());
+        assert_eq!(required.len(), 3);
+        assert!(resources(&required, "app.sapphillon.core.filesystem.list_files").is_empty());
+
+        let unrelated = "const x = my.app.sapphillon.core.fetch.fetch_all('https://a.test');";
+        assert!(infer_required_permissions(&[unrelated], &plugins()).is_empty());
+    }
+
+    #[test]
+    fn missing_permissions_leave_out_allowed_resources() {
+        let code = r#"
+            app.sapphillon.core.fetch.fetch("https://example.com/a");
+            app.sapphillon.core.fetch.fetch("https://other.test/");
+        "#;
+        let required = infer_required_permissions(&[code], &plugins());
+        let allowed = vec![AllowedPermission {
+            plugin_function_id: "app.sapphillon.core.fetch.fetch".to_string(),
+            permissions: vec![Permission {
+                display_name: "".to_string(),
+                description: "".to_string(),
+                permission_type: PermissionType::NetAccess as i32,
+                permission_level: PermissionLevel::Unspecified as i32,
+                resource: vec!["https://example.com".to_string()],
+            }],
+        }];
+
+        let missing = find_missing_permissions(&required, &allowed);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(
+            missing[0].permissions[0].resource,
+            vec!["https://other.test/".to_string()]
+        );
+        assert!(find_missing_permissions(&required, &required).is_empty());
+    }
+
+    #[test]
+    fn risk_follows_the_riskiest_permission() {
+        assert_eq!(assess_risk(&[]).level, RiskLevel::Low);
+
+        let code = r#"
+            const body = app.sapphillon.core.fetch.fetch("https://example.com/a");
+            app.sapphillon.core.filesystem.write(path, body);
+        "#;
+        let risk = assess_risk(&infer_required_permissions(&[code], &plugins()));
+        assert_eq!(risk.level, RiskLevel::High);
+        assert_eq!(risk.reasons.len(), 2);
+        assert!(
+            risk.reasons[0].starts_with("[high] app.sapphillon.core.filesystem.write: "),
+            "{risk:?}"
+        );
+        assert!(risk.reasons[0].ends_with(" on any resource"), "{risk:?}");
+        assert!(risk.reasons[1].starts_with("[medium] app.sapphillon.core.fetch.fetch: "));
+        assert!(
+            risk.reasons[1].ends_with("on https://example.com/a"),
+            "{risk:?}"
+        );
+    }
+}
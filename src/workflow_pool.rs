@@ -0,0 +1,88 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Worker pool that runs workflows off the async runtime with bounded concurrency
+
+use std::sync::{Arc, OnceLock};
+
+use tokio::sync::Semaphore;
+
+/// Number of workflows allowed to run at the same time when not configured.
+pub(crate) const DEFAULT_MAX_CONCURRENT_WORKFLOWS: usize = 4;
+
+static WORKFLOW_SLOTS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+
+/// Sets how many workflows may run at once. Only the first call takes effect.
+///
+/// # Arguments
+///
+/// * `max` - Maximum number of concurrently running workflows; `0` is treated as `1`.
+///
+/// # Returns
+///
+/// Returns `true` if the limit was applied, `false` if the pool was already configured.
+pub(crate) fn set_max_concurrent_workflows(max: usize) -> bool {
+    WORKFLOW_SLOTS
+        .set(Arc::new(Semaphore::new(max.max(1))))
+        .is_ok()
+}
+
+fn workflow_slots() -> Arc<Semaphore> {
+    WORKFLOW_SLOTS
+        .get_or_init(|| Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_WORKFLOWS)))
+        .clone()
+}
+
+/// Runs a blocking workflow job on the blocking thread pool once a slot is free.
+///
+/// Each job creates its own JS runtime, so jobs never share isolate state. Waiting
+/// callers queue on the semaphore instead of blocking the async runtime.
+///
+/// # Arguments
+///
+/// * `job` - Closure that builds and runs the workflow.
+///
+/// # Returns
+///
+/// Returns the closure's output, or an error if the job panicked.
+pub(crate) async fn run_workflow_job<F, T>(job: F) -> anyhow::Result<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let _permit = workflow_slots().acquire_owned().await?;
+    Ok(tokio::task::spawn_blocking(job).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::task::JoinSet;
+
+    #[tokio::test]
+    async fn test_jobs_respect_concurrency_limit() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let mut jobs = JoinSet::new();
+        for _ in 0..DEFAULT_MAX_CONCURRENT_WORKFLOWS * 3 {
+            let running = running.clone();
+            let peak = peak.clone();
+            jobs.spawn(run_workflow_job(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+            }));
+        }
+
+        while let Some(result) = jobs.join_next().await {
+            result.unwrap().unwrap();
+        }
+        assert!(peak.load(Ordering::SeqCst) <= DEFAULT_MAX_CONCURRENT_WORKFLOWS);
+        assert_eq!(running.load(Ordering::SeqCst), 0);
+    }
+}
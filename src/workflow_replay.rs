@@ -0,0 +1,276 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Record/replay mode: plugin calls are captured during a run and served back on replay
+
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the console lines written for each recorded call.
+const RECORD_MARKER: &str = "::sapphillon-record::";
+
+/// Plugin functions (relative to `app.sapphillon.core`) that are never recorded.
+/// `std.retry` takes a callback, so it keeps running and the calls it makes are
+/// recorded individually instead.
+const UNRECORDED_FUNCTIONS: &[&str] = &["std.retry"];
+
+/// Longest argument or result value kept by [`format_calls`], in characters.
+const MAX_TRACE_VALUE_CHARS: usize = 200;
+
+/// How a recorded call finished.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(crate) enum RecordedOutcome {
+    /// The call returned a JSON-serializable value.
+    Value { value: serde_json::Value },
+    /// The call returned `undefined`.
+    Undefined,
+    /// The call threw; only the message is kept.
+    Error { message: String },
+}
+
+/// A plugin call captured during a recorded run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RecordedCall {
+    /// Position of the call in the run, counted when the call started.
+    pub seq: u64,
+    /// Function path relative to `app.sapphillon.core`, e.g. `fetch.get`.
+    pub function: String,
+    /// Arguments the function was called with.
+    pub args: Vec<serde_json::Value>,
+    /// Whether the function returned a promise.
+    #[serde(rename = "async", default)]
+    pub is_async: bool,
+    /// Value or error the call produced.
+    pub outcome: RecordedOutcome,
+}
+
+/// Prepends a prelude wrapping every plugin function with a recorder.
+///
+/// Calls still run normally; their arguments and results are logged as marker
+/// lines that [`parse_recording`] extracts after the run.
+pub(crate) fn instrument_recording(code: &str) -> String {
+    let skipped = serde_json::to_string(UNRECORDED_FUNCTIONS).unwrap_or_default();
+    format!(
+        r#"
+(() => {{
+    const core = globalThis.app?.sapphillon?.core;
+    if (!core) return;
+    let seq = 0;
+    const log = (entry) => console.log("{RECORD_MARKER}" + JSON.stringify(entry));
+    const errorMessage = (e) => String(e && e.message !== undefined ? e.message : e);
+    const outcome = (value) =>
+        value === undefined ? {{ kind: "undefined" }} : {{ kind: "value", value }};
+    for (const pkg of Object.keys(core)) {{
+        for (const name of Object.keys(core[pkg] || {{}})) {{
+            const path = pkg + "." + name;
+            const original = core[pkg][name];
+            if (typeof original !== "function" || {skipped}.includes(path)) continue;
+            core[pkg][name] = (...args) => {{
+                const entry = {{ seq: seq++, function: path, args }};
+                let result;
+                try {{
+                    result = original(...args);
+                }} catch (e) {{
+                    log({{ ...entry, outcome: {{ kind: "error", message: errorMessage(e) }} }});
+                    throw e;
+                }}
+                if (result && typeof result.then === "function") {{
+                    return result.then(
+                        (value) => {{
+                            log({{ ...entry, async: true, outcome: outcome(value) }});
+                            return value;
+                        }},
+                        (e) => {{
+                            log({{ ...entry, async: true, outcome: {{ kind: "error", message: errorMessage(e) }} }});
+                            throw e;
+                        }},
+                    );
+                }}
+                log({{ ...entry, outcome: outcome(result) }});
+                return result;
+            }};
+        }}
+    }}
+}})();
+{code}"#
+    )
+}
+
+/// Splits console output from a recorded run into the calls, in call order, and the
+/// plain output.
+pub(crate) fn parse_recording(output: &str) -> (Vec<RecordedCall>, String) {
+    let mut calls = Vec::new();
+    let mut plain = String::new();
+
+    for line in output.split_inclusive('\n') {
+        let call = line
+            .trim_end_matches(['\r', '\n'])
+            .strip_prefix(RECORD_MARKER)
+            .and_then(|json| serde_json::from_str::<RecordedCall>(json).ok());
+        match call {
+            Some(call) => calls.push(call),
+            None => plain.push_str(line),
+        }
+    }
+
+    // Async calls are logged when they settle, which may differ from call order.
+    calls.sort_by_key(|call| call.seq);
+    (calls, plain)
+}
+
+/// Prepends a prelude replacing every recorded plugin function with a stub that
+/// returns the next recorded outcome.
+///
+/// The stubs check that calls arrive in the recorded order and throw a
+/// `replay diverged` error otherwise. Every plugin function is stubbed, so a call
+/// the recording does not contain fails instead of reaching the outside world.
+pub(crate) fn instrument_replay(calls: &[RecordedCall], code: &str) -> String {
+    let calls = serde_json::to_string(calls).unwrap_or_else(|_| "[]".to_string());
+    let skipped = serde_json::to_string(UNRECORDED_FUNCTIONS).unwrap_or_default();
+    format!(
+        r#"
+(() => {{
+    const core = globalThis.app?.sapphillon?.core;
+    if (!core) return;
+    const calls = {calls};
+    let next = 0;
+    for (const pkg of Object.keys(core)) {{
+        for (const name of Object.keys(core[pkg] || {{}})) {{
+            const path = pkg + "." + name;
+            if (typeof core[pkg][name] !== "function" || {skipped}.includes(path)) continue;
+            core[pkg][name] = () => {{
+                const call = calls[next++];
+                if (!call || call.function !== path) {{
+                    const expected = call ? call.function : "end of recording";
+                    throw new Error("replay diverged: expected " + expected + ", got " + path);
+                }}
+                const settle = () => {{
+                    if (call.outcome.kind === "error") throw new Error(call.outcome.message);
+                    return call.outcome.kind === "value" ? call.outcome.value : undefined;
+                }};
+                if (call.async) {{
+                    return new Promise((resolve) => resolve(settle()));
+                }}
+                return settle();
+            }};
+        }}
+    }}
+}})();
+{code}"#
+    )
+}
+
+/// Describes recorded calls one per line, e.g. `#0 fetch.get("https://a.test") -> "body"`,
+/// shortening long values.
+pub(crate) fn format_calls(calls: &[RecordedCall]) -> String {
+    let value = |value: &serde_json::Value| {
+        let text = value.to_string();
+        match text.char_indices().nth(MAX_TRACE_VALUE_CHARS) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        }
+    };
+    calls
+        .iter()
+        .map(|call| {
+            let args: Vec<String> = call.args.iter().map(value).collect();
+            let outcome = match &call.outcome {
+                RecordedOutcome::Value { value: result } => format!("-> {}", value(result)),
+                RecordedOutcome::Undefined => "-> undefined".to_string(),
+                RecordedOutcome::Error { message } => format!("threw {message}"),
+            };
+            format!(
+                "#{} {}({}) {outcome}\n",
+                call.seq,
+                call.function,
+                args.join(", ")
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_recording_orders_calls_and_strips_markers() {
+        let output = format!(
+            "start\n{RECORD_MARKER}{}\n{RECORD_MARKER}{}\ndone\n",
+            r#"{"seq":1,"function":"filesystem.read","args":["/tmp/a"],"outcome":{"kind":"error","message":"missing"}}"#,
+            r#"{"seq":0,"function":"fetch.get","args":["https://example.com"],"async":true,"outcome":{"kind":"value","value":"body"}}"#
+        );
+        let (calls, plain) = parse_recording(&output);
+        assert_eq!(
+            calls,
+            vec![
+                RecordedCall {
+                    seq: 0,
+                    function: "fetch.get".to_string(),
+                    args: vec![json!("https://example.com")],
+                    is_async: true,
+                    outcome: RecordedOutcome::Value {
+                        value: json!("body")
+                    },
+                },
+                RecordedCall {
+                    seq: 1,
+                    function: "filesystem.read".to_string(),
+                    args: vec![json!("/tmp/a")],
+                    is_async: false,
+                    outcome: RecordedOutcome::Error {
+                        message: "missing".to_string()
+                    },
+                },
+            ]
+        );
+        assert_eq!(plain, "start\ndone\n");
+    }
+
+    #[test]
+    fn instrument_replay_embeds_recorded_calls() {
+        let calls = vec![RecordedCall {
+            seq: 0,
+            function: "sysinfo.hostname".to_string(),
+            args: vec![],
+            is_async: false,
+            outcome: RecordedOutcome::Undefined,
+        }];
+        let code = instrument_replay(&calls, "workflow();");
+        assert!(code.contains(r#""function":"sysinfo.hostname""#));
+        assert!(code.contains(r#""kind":"undefined""#));
+        assert!(code.ends_with("workflow();"));
+    }
+
+    #[test]
+    fn format_calls_shortens_long_values() {
+        let calls = vec![
+            RecordedCall {
+                seq: 0,
+                function: "fetch.get".to_string(),
+                args: vec![json!("https://example.com")],
+                is_async: true,
+                outcome: RecordedOutcome::Value {
+                    value: json!("x".repeat(500)),
+                },
+            },
+            RecordedCall {
+                seq: 1,
+                function: "filesystem.read".to_string(),
+                args: vec![json!("/tmp/a")],
+                is_async: false,
+                outcome: RecordedOutcome::Error {
+                    message: "missing".to_string(),
+                },
+            },
+        ];
+        let trace = format_calls(&calls);
+        let lines: Vec<&str> = trace.lines().collect();
+        assert!(lines[0].starts_with(r#"#0 fetch.get("https://example.com") -> "xxx"#));
+        assert!(lines[0].ends_with('…'));
+        assert_eq!(lines[0].chars().count(), 39 + MAX_TRACE_VALUE_CHARS + 1);
+        assert_eq!(lines[1], r#"#1 filesystem.read("/tmp/a") threw missing"#);
+    }
+}
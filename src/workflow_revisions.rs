@@ -0,0 +1,146 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Line diffs recorded between workflow code revisions
+
+use sapphillon_core::proto::google::protobuf::Timestamp;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+/// Unchanged lines kept around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// A revision of a workflow's code and how it differs from the revision it was made from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodeRevision {
+    pub workflow_code_id: String,
+    pub code_revision: i32,
+    pub created_at: Option<Timestamp>,
+    /// The revision this one was made from; `None` for the first revision and for
+    /// revisions stored without a recorded change.
+    pub previous_code_id: Option<String>,
+    pub summary: Option<String>,
+    pub diff: Vec<DiffHunk>,
+}
+
+/// Nearby line changes with their context, like a hunk of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiffHunk {
+    /// First line of the hunk in the old code, counting from 1.
+    pub old_start: usize,
+    pub old_lines: usize,
+    /// First line of the hunk in the new code, counting from 1.
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// The lines of the hunk without line endings, prefixed with ` ` when unchanged,
+    /// `-` when removed and `+` when added.
+    pub lines: Vec<String>,
+}
+
+/// Compares two revisions of a workflow code line by line.
+///
+/// # Arguments
+///
+/// * `old` - Code of the earlier revision.
+/// * `new` - Code of the later revision.
+///
+/// # Returns
+///
+/// Returns the hunks of changed lines; an empty list means the code is unchanged.
+pub(crate) fn diff_code(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    diff.grouped_ops(CONTEXT_LINES)
+        .iter()
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| {
+                    let sign = match change.tag() {
+                        ChangeTag::Equal => ' ',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Insert => '+',
+                    };
+                    format!("{sign}{}", change.value().trim_end_matches(['\r', '\n']))
+                })
+                .collect();
+            Some(DiffHunk {
+                old_start: old_range.start + 1,
+                old_lines: old_range.len(),
+                new_start: new_range.start + 1,
+                new_lines: new_range.len(),
+                lines,
+            })
+        })
+        .collect()
+}
+
+/// Renders hunks in the unified diff format, without file headers.
+pub(crate) fn render_diff(hunks: &[DiffHunk]) -> String {
+    let mut rendered = String::new();
+    for hunk in hunks {
+        rendered.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+        ));
+        for line in &hunk.lines {
+            rendered.push_str(line);
+            rendered.push('\n');
+        }
+    }
+    rendered
+}
+
+/// Describes the size of a change, used when no summary can be written.
+pub(crate) fn describe_diff(hunks: &[DiffHunk]) -> String {
+    let lines = hunks.iter().flat_map(|hunk| &hunk.lines);
+    let (mut added, mut removed) = (0, 0);
+    for line in lines {
+        match line.chars().next() {
+            Some('+') => added += 1,
+            Some('-') => removed += 1,
+            _ => {}
+        }
+    }
+    format!("{added} line(s) added, {removed} line(s) removed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_code_groups_changes_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let hunks = diff_code(old, new);
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(
+            hunks[0],
+            DiffHunk {
+                old_start: 1,
+                old_lines: 5,
+                new_start: 1,
+                new_lines: 5,
+                lines: [" a", "-b", "+B", " c", " d", " e"]
+                    .map(str::to_string)
+                    .to_vec(),
+            }
+        );
+        assert_eq!((hunks[1].old_start, hunks[1].new_lines), (10, 4));
+        assert_eq!(hunks[1].lines.last().map(String::as_str), Some("+m"));
+        assert_eq!(describe_diff(&hunks), "2 line(s) added, 1 line(s) removed");
+        assert!(render_diff(&hunks).starts_with("@@ -1,5 +1,5 @@\n a\n-b\n+B\n"));
+    }
+
+    #[test]
+    fn diff_code_of_identical_code_is_empty() {
+        assert!(diff_code("a\nb\n", "a\nb\n").is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Named workflow steps: a JS `step(name, fn)` helper and per-step results parsed from console output
+
+use serde::Deserialize;
+
+/// Prefix of the console lines the step helper writes; they are removed from the stored output.
+const STEP_MARKER: &str = "::sapphillon-step::";
+
+/// JS prelude defining `step(name, fn)`. It logs a start and an end marker around `fn`
+/// (also when `fn` returns a promise) and rethrows failures so the workflow still fails.
+const STEP_PRELUDE: &str = r#"
+globalThis.step = function step(name, fn) {
+    const marker = (event) => console.log("::sapphillon-step::" + JSON.stringify(event));
+    const startedAt = Date.now();
+    const finish = (error) => marker({
+        event: "end",
+        name: String(name),
+        ok: error === undefined,
+        durationMs: Date.now() - startedAt,
+        error: error === undefined ? undefined : String(error),
+    });
+    marker({ event: "start", name: String(name) });
+    let result;
+    try {
+        result = fn();
+    } catch (e) {
+        finish(e);
+        throw e;
+    }
+    if (result && typeof result.then === "function") {
+        return result.then(
+            (value) => { finish(); return value; },
+            (e) => { finish(e); throw e; },
+        );
+    }
+    finish();
+    return result;
+};
+"#;
+
+/// Outcome of a single named step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StepStatus {
+    Succeeded,
+    Failed {
+        error: String,
+    },
+    /// The step started but never reported an end, e.g. the script was aborted.
+    Incomplete,
+}
+
+impl std::fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepStatus::Succeeded => write!(f, "succeeded"),
+            StepStatus::Failed { error } => write!(f, "failed: {error}"),
+            StepStatus::Incomplete => write!(f, "incomplete"),
+        }
+    }
+}
+
+/// A named step recorded during a workflow run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WorkflowStep {
+    pub name: String,
+    pub status: StepStatus,
+    pub duration_ms: u64,
+    /// Console output written while the step was the innermost running step.
+    pub output: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum StepMarker {
+    Start {
+        name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    End {
+        name: String,
+        ok: bool,
+        duration_ms: u64,
+        error: Option<String>,
+    },
+}
+
+/// Prepends the `step` helper to workflow code.
+pub(crate) fn instrument_steps(code: &str) -> String {
+    format!("{STEP_PRELUDE}\n{code}")
+}
+
+/// Splits console output from an instrumented run into recorded steps and the plain output
+/// with all step markers removed.
+pub(crate) fn parse_steps(output: &str) -> (Vec<WorkflowStep>, String) {
+    let mut tracker = StepTracker::default();
+    let mut plain = String::new();
+    for line in output.split_inclusive('\n') {
+        if matches!(tracker.push(line), TrackedLine::Output) {
+            plain.push_str(line);
+        }
+    }
+    (tracker.into_steps(), plain)
+}
+
+/// What a console line fed to a [`StepTracker`] turned out to be.
+pub(crate) enum TrackedLine<'a> {
+    /// Plain output of the workflow.
+    Output,
+    /// A step marker that did not finish a step.
+    Marker,
+    /// An end marker, with the step it finished.
+    Finished(&'a WorkflowStep),
+}
+
+/// Incremental form of [`parse_steps`], fed one console line at a time while a run is
+/// still in progress.
+#[derive(Default)]
+pub(crate) struct StepTracker {
+    steps: Vec<WorkflowStep>,
+    open: Vec<usize>,
+}
+
+impl StepTracker {
+    /// Records `line`, which keeps its line break if it had one.
+    pub(crate) fn push(&mut self, line: &str) -> TrackedLine<'_> {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let marker = content
+            .strip_prefix(STEP_MARKER)
+            .and_then(|json| serde_json::from_str::<StepMarker>(json).ok());
+
+        match marker {
+            Some(StepMarker::Start { name }) => {
+                self.open.push(self.steps.len());
+                self.steps.push(WorkflowStep {
+                    name,
+                    status: StepStatus::Incomplete,
+                    duration_ms: 0,
+                    output: String::new(),
+                });
+                TrackedLine::Marker
+            }
+            Some(StepMarker::End {
+                name,
+                ok,
+                duration_ms,
+                error,
+            }) => {
+                let Some(position) = self.open.iter().rposition(|&i| self.steps[i].name == name)
+                else {
+                    return TrackedLine::Marker;
+                };
+                let index = self.open.remove(position);
+                let step = &mut self.steps[index];
+                step.duration_ms = duration_ms;
+                step.status = if ok {
+                    StepStatus::Succeeded
+                } else {
+                    StepStatus::Failed {
+                        error: error.unwrap_or_default(),
+                    }
+                };
+                TrackedLine::Finished(step)
+            }
+            None => {
+                if let Some(&index) = self.open.last() {
+                    self.steps[index].output.push_str(line);
+                }
+                TrackedLine::Output
+            }
+        }
+    }
+
+    /// Returns the recorded steps in the order they started; steps still open are
+    /// left [`StepStatus::Incomplete`].
+    pub(crate) fn into_steps(self) -> Vec<WorkflowStep> {
+        self.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(json: &str) -> String {
+        format!("{STEP_MARKER}{json}\n")
+    }
+
+    #[test]
+    fn parse_steps_records_status_duration_and_output() {
+        let output = [
+            "before\n".to_string(),
+            marker(r#"{"event":"start","name":"fetch"}"#),
+            "got 3 rows\n".to_string(),
+            marker(r#"{"event":"end","name":"fetch","ok":true,"durationMs":12}"#),
+            marker(r#"{"event":"start","name":"output"}"#),
+            marker(r#"{"event":"end","name":"output","ok":false,"durationMs":1,"error":"boom"}"#),
+        ]
+        .concat();
+
+        let (steps, plain) = parse_steps(&output);
+        assert_eq!(plain, "before\ngot 3 rows\n");
+        assert_eq!(
+            steps,
+            vec![
+                WorkflowStep {
+                    name: "fetch".to_string(),
+                    status: StepStatus::Succeeded,
+                    duration_ms: 12,
+                    output: "got 3 rows\n".to_string(),
+                },
+                WorkflowStep {
+                    name: "output".to_string(),
+                    status: StepStatus::Failed {
+                        error: "boom".to_string(),
+                    },
+                    duration_ms: 1,
+                    output: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn step_tracker_reports_each_step_as_it_finishes() {
+        let mut tracker = StepTracker::default();
+        assert!(matches!(
+            tracker.push(&marker(r#"{"event":"start","name":"fetch"}"#)),
+            TrackedLine::Marker
+        ));
+        assert!(matches!(tracker.push("row\n"), TrackedLine::Output));
+        match tracker.push(&marker(
+            r#"{"event":"end","name":"fetch","ok":true,"durationMs":4}"#,
+        )) {
+            TrackedLine::Finished(step) => {
+                assert_eq!(step.name, "fetch");
+                assert_eq!(step.status, StepStatus::Succeeded);
+                assert_eq!(step.output, "row\n");
+            }
+            _ => panic!("the end marker should finish the step"),
+        }
+    }
+
+    #[test]
+    fn parse_steps_marks_unfinished_steps_incomplete() {
+        let output = marker(r#"{"event":"start","name":"transform"}"#) + "partial";
+        let (steps, plain) = parse_steps(&output);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].status, StepStatus::Incomplete);
+        assert_eq!(steps[0].output, "partial");
+        assert_eq!(plain, "partial");
+    }
+
+    #[test]
+    fn parse_steps_without_markers_keeps_output() {
+        let (steps, plain) = parse_steps("hello\nworld\n");
+        assert!(steps.is_empty());
+        assert_eq!(plain, "hello\nworld\n");
+    }
+
+    #[test]
+    fn instrument_steps_prepends_helper() {
+        let code = instrument_steps("workflow();");
+        assert!(code.contains("globalThis.step = function step"));
+        assert!(code.ends_with("workflow();"));
+    }
+}
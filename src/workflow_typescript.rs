@@ -0,0 +1,74 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// TypeScript workflow support: strips types so the code can run on the JS runtime
+
+use deno_ast::{
+    EmitOptions, MediaType, ModuleSpecifier, ParseParams, SourceMapOption, TranspileModuleOptions,
+    TranspileOptions,
+};
+
+/// `WorkflowLanguage` value for TypeScript workflow code.
+pub(crate) const WORKFLOW_LANGUAGE_TS: i32 = 1;
+
+/// Transpiles TypeScript workflow code to JavaScript.
+///
+/// Workflows are evaluated as scripts, so the code is parsed as a script rather than
+/// a module; type errors are not checked, only syntax.
+///
+/// # Arguments
+///
+/// * `code` - TypeScript source of the workflow.
+///
+/// # Returns
+///
+/// Returns the JavaScript source, or a message describing the first syntax or
+/// transpilation error.
+pub(crate) fn transpile_typescript(code: &str) -> Result<String, String> {
+    let specifier = ModuleSpecifier::parse("file:///workflow.ts").map_err(|e| e.to_string())?;
+    let parsed = deno_ast::parse_script(ParseParams {
+        specifier,
+        text: code.into(),
+        media_type: MediaType::TypeScript,
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let transpiled = parsed
+        .transpile(
+            &TranspileOptions::default(),
+            &TranspileModuleOptions::default(),
+            &EmitOptions {
+                source_map: SourceMapOption::None,
+                ..Default::default()
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(transpiled.into_source().text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transpile_strips_types() {
+        let js = transpile_typescript(
+            "interface Item { name: string }\nconst items: Item[] = [{ name: \"a\" }];\nconsole.log(items.map((i: Item) => i.name).join(\",\"));",
+        )
+        .unwrap();
+        assert!(!js.contains("interface"));
+        assert!(!js.contains(": Item"));
+        assert!(js.contains("console.log"));
+    }
+
+    #[test]
+    fn transpile_reports_syntax_errors() {
+        let err = transpile_typescript("const x: = 1;").unwrap_err();
+        assert!(!err.is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+// Sapphillon
+// SPDX-FileCopyrightText: 2025 Yuta Takahashi
+// SPDX-License-Identifier: MPL-2.0 OR GPL-3.0-or-later
+
+// Checks generated workflow code before it is stored
+
+use std::collections::HashSet;
+
+use deno_core::{JsRuntime, RuntimeOptions};
+use regex::Regex;
+use sapphillon_core::proto::sapphillon::v1::PluginPackage;
+
+/// Finds `app.sapphillon.core.<package>[.<function>]` paths that are not part of a
+/// longer path, e.g. `my.app.sapphillon.core.fetch`.
+const CORE_REFERENCE_PATTERN: &str =
+    r"(?:^|[^\w$.])(app\.sapphillon\.core\.[A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)?)";
+
+/// Checks that `code` compiles and only refers to plugin functions of `plugins`.
+///
+/// # Arguments
+///
+/// * `code` - JavaScript source of the workflow.
+/// * `plugins` - Plugin packages the workflow may call.
+///
+/// # Returns
+///
+/// Returns one message per problem found; an empty list means the code is valid.
+pub(crate) fn validate_workflow_code(code: &str, plugins: &[PluginPackage]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Err(err) = check_syntax(code) {
+        problems.push(err);
+    }
+    problems.extend(
+        unknown_plugin_references(code, plugins)
+            .into_iter()
+            .map(|path| format!("unknown plugin function: {path}")),
+    );
+    problems
+}
+
+/// Compiles `code` with V8 without running it. Wrapping the source in `new Function`
+/// parses the whole body but never calls it.
+fn check_syntax(code: &str) -> Result<(), String> {
+    let source = serde_json::to_string(code).map_err(|err| err.to_string())?;
+    let mut runtime = JsRuntime::new(RuntimeOptions::default());
+    runtime
+        .execute_script("<workflow-check>", format!("new Function({source});"))
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Returns the core plugin paths `code` refers to that are neither a package nor a
+/// function of `plugins`, in order of first use.
+fn unknown_plugin_references(code: &str, plugins: &[PluginPackage]) -> Vec<String> {
+    let known: HashSet<&str> = plugins
+        .iter()
+        .flat_map(|package| {
+            std::iter::once(package.package_id.as_str())
+                .chain(package.functions.iter().map(|f| f.function_id.as_str()))
+        })
+        .collect();
+    let pattern = Regex::new(CORE_REFERENCE_PATTERN).expect("valid core reference pattern");
+
+    let mut unknown: Vec<String> = Vec::new();
+    for captures in pattern.captures_iter(code) {
+        let path = &captures[1];
+        if !known.contains(path) && !unknown.iter().any(|seen| seen == path) {
+            unknown.push(path.to_string());
+        }
+    }
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugins() -> Vec<PluginPackage> {
+        vec![
+            fetch::fetch_plugin_package(),
+            filesystem::filesystem_plugin_package(),
+        ]
+    }
+
+    #[test]
+    fn valid_code_has_no_problems() {
+        let code = r#"
+            function workflow() {
+                const fs = app.sapphillon.core.filesystem;
+                const body = app.sapphillon.core.fetch.fetch("https://example.com");
+                fs.write("/tmp/out.txt", body);
+                return;
+            }
+        "#;
+        assert!(validate_workflow_code(code, &plugins()).is_empty());
+    }
+
+    #[test]
+    fn syntax_errors_are_reported_without_running_the_code() {
+        let problems = validate_workflow_code(
+            "throw new Error('ran');\nfunction workflow() { if (x { }",
+            &plugins(),
+        );
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("SyntaxError"), "{problems:?}");
+    }
+
+    #[test]
+    fn unknown_plugin_functions_are_reported_once() {
+        let code = r#"
+            app.sapphillon.core.fetch.download("https://example.com");
+            app.sapphillon.core.fetch.download("https://example.org");
+            app.sapphillon.core.weather.today();
+            my.app.sapphillon.core.other.call();
+        "#;
+        assert_eq!(
+            validate_workflow_code(code, &plugins()),
+            vec![
+                "unknown plugin function: app.sapphillon.core.fetch.download",
+                "unknown plugin function: app.sapphillon.core.weather.today",
+            ]
+        );
+    }
+}